@@ -0,0 +1,153 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Ghost types, which describe mathematical collections in specifications and
+//! in pure functions, for example the content of a data structure:
+//!
+//! ```ignore
+//! #[pure]
+//! fn model(&self) -> Seq<u32> { ... }
+//!
+//! #[ensures="self.model() == old(self.model()).concat(Seq::single(value))"]
+//! fn push(&mut self, value: u32) { ... }
+//! ```
+//!
+//! Prusti encodes the ghost types with Viper domains. Their elements (and the
+//! keys and values of a map) have to be integers, characters or booleans.
+//! The operations of the ghost types cannot be executed, and panic when
+//! called at runtime.
+
+#![allow(unused_variables)]
+
+use std::marker::PhantomData;
+
+/// A finite sequence of elements, indexed from `0` to `len() - 1`.
+pub struct Seq<T> {
+    _elements: PhantomData<T>,
+}
+
+impl<T> Seq<T> {
+    /// The empty sequence.
+    pub fn empty() -> Self {
+        unimplemented!()
+    }
+
+    /// The sequence that contains only `elem`.
+    pub fn single(elem: T) -> Self {
+        unimplemented!()
+    }
+
+    /// The number of elements of the sequence.
+    pub fn len(self) -> usize {
+        unimplemented!()
+    }
+
+    /// The element at the given index, which is unspecified if the index is
+    /// not smaller than `len()`.
+    pub fn lookup(self, index: usize) -> T {
+        unimplemented!()
+    }
+
+    /// Whether the sequence contains `elem`.
+    pub fn contains(self, elem: T) -> bool {
+        unimplemented!()
+    }
+
+    /// The sequence with the element at the given index replaced by `elem`.
+    /// The sequence is unchanged if the index is not smaller than `len()`.
+    pub fn update(self, index: usize, elem: T) -> Self {
+        unimplemented!()
+    }
+
+    /// The sequence with the elements of `self` followed by the elements of
+    /// `other`.
+    pub fn concat(self, other: Self) -> Self {
+        unimplemented!()
+    }
+}
+
+/// A finite set of elements.
+pub struct Set<T> {
+    _elements: PhantomData<T>,
+}
+
+impl<T> Set<T> {
+    /// The empty set.
+    pub fn empty() -> Self {
+        unimplemented!()
+    }
+
+    /// The set that contains only `elem`.
+    pub fn single(elem: T) -> Self {
+        unimplemented!()
+    }
+
+    /// Whether the set contains `elem`.
+    pub fn contains(self, elem: T) -> bool {
+        unimplemented!()
+    }
+
+    /// The set that contains `elem` and the elements of `self`.
+    pub fn insert(self, elem: T) -> Self {
+        unimplemented!()
+    }
+
+    /// The set that contains the elements of `self` and of `other`.
+    pub fn union(self, other: Self) -> Self {
+        unimplemented!()
+    }
+}
+
+/// A finite map from keys to values.
+pub struct Map<K, V> {
+    _entries: PhantomData<(K, V)>,
+}
+
+impl<K, V> Map<K, V> {
+    /// The empty map.
+    pub fn empty() -> Self {
+        unimplemented!()
+    }
+
+    /// Whether the map contains a value for `key`.
+    pub fn contains_key(self, key: K) -> bool {
+        unimplemented!()
+    }
+
+    /// The value of `key`, which is unspecified if the map does not contain
+    /// `key`.
+    pub fn lookup(self, key: K) -> V {
+        unimplemented!()
+    }
+
+    /// The map with `key` mapped to `value`, and the other keys mapped as in
+    /// `self`.
+    pub fn update(self, key: K, value: V) -> Self {
+        unimplemented!()
+    }
+}
+
+macro_rules! impl_ghost_traits {
+    ($name:ident < $($param:ident),* >) => {
+        impl<$($param),*> Clone for $name<$($param),*> {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<$($param),*> Copy for $name<$($param),*> {}
+
+        impl<$($param),*> PartialEq for $name<$($param),*> {
+            fn eq(&self, _other: &Self) -> bool {
+                unimplemented!()
+            }
+        }
+    };
+}
+
+impl_ghost_traits!(Seq<T>);
+impl_ghost_traits!(Set<T>);
+impl_ghost_traits!(Map<K, V>);
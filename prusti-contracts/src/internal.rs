@@ -9,6 +9,11 @@
 
 /// This function is used to evaluate an expression in the “old”
 /// context, that is at the beginning of the method call.
+///
+/// It can also be used in the body of a function, for example in an
+/// `assert!(x == old(x) + 1)`, on integer or boolean fields of the
+/// arguments. Note that, when the code is executed, the function returns
+/// the current value of its argument.
 pub fn old<T>(arg: T) -> T {
     arg
 }
@@ -18,3 +23,28 @@ pub fn old<T>(arg: T) -> T {
 pub fn before_expiry<T>(arg: T) -> T {
     arg
 }
+
+/// This function is used to check a contract at runtime, when Prusti is
+/// configured with `CONTRACT_CHECKS=runtime` (or `both`).
+pub fn check_contract(holds: bool, message: &'static str) {
+    if !holds {
+        panic!("{}", message);
+    }
+}
+
+/// This function marks the position of a `prusti_assert!` statement, which
+/// Prusti encodes as a Viper `assert`.
+pub fn prusti_assert(_condition: bool) {}
+
+/// This function marks the position of a `prusti_assume!` statement, which
+/// Prusti encodes as a Viper `inhale`.
+pub fn prusti_assume(_condition: bool) {}
+
+/// This function marks the position of a `fold!` statement, which Prusti
+/// encodes as a Viper `fold` of the predicate of the borrowed place.
+pub fn prusti_fold<T>(_place: &T) {}
+
+/// This function marks the position of an `unfold!` statement, which
+/// Prusti encodes as a Viper `unfold` of the predicate of the borrowed
+/// place.
+pub fn prusti_unfold<T>(_place: &T) {}
@@ -9,4 +9,145 @@
 
 #![warn(missing_docs)]
 
+pub mod ghost;
 pub mod internal;
+
+pub use ghost::{Map, Seq, Set};
+pub use internal::old;
+
+/// Defines a boolean function that can be used in the specifications of
+/// other functions, for example:
+///
+/// ```ignore
+/// predicate! {
+///     fn sorted(s: &List) -> bool {
+///         ...
+///     }
+/// }
+/// ```
+///
+/// When verifying with Prusti (i.e. when the `prusti` feature is enabled),
+/// the function is a `#[pure]` function with the given body. Otherwise, the
+/// body is replaced with `unimplemented!()`, because the function is meant
+/// to be used only in specifications.
+#[macro_export]
+macro_rules! predicate {
+    ($(#[$attr:meta])* fn $name:ident ($($arg:ident : $ty:ty),*) -> bool $body:block) => {
+        #[cfg(feature = "prusti")]
+        $(#[$attr])*
+        #[pure]
+        #[allow(dead_code)]
+        fn $name($($arg: $ty),*) -> bool $body
+
+        #[cfg(not(feature = "prusti"))]
+        $(#[$attr])*
+        #[allow(dead_code, unused_variables)]
+        fn $name($($arg: $ty),*) -> bool {
+            unimplemented!()
+        }
+    };
+    ($(#[$attr:meta])* pub fn $name:ident ($($arg:ident : $ty:ty),*) -> bool $body:block) => {
+        #[cfg(feature = "prusti")]
+        $(#[$attr])*
+        #[pure]
+        #[allow(dead_code)]
+        pub fn $name($($arg: $ty),*) -> bool $body
+
+        #[cfg(not(feature = "prusti"))]
+        $(#[$attr])*
+        #[allow(dead_code, unused_variables)]
+        pub fn $name($($arg: $ty),*) -> bool {
+            unimplemented!()
+        }
+    };
+}
+
+/// Asks Prusti to check that a boolean expression holds at this point of
+/// the body of a function, for example to document an intermediate step of
+/// a proof:
+///
+/// ```ignore
+/// prusti_assert!(x + y <= 2 * max);
+/// ```
+///
+/// The expression is evaluated only when verifying with Prusti (i.e. when
+/// the `prusti` feature is enabled), and it should be free of side effects.
+#[macro_export]
+macro_rules! prusti_assert {
+    ($condition:expr) => {
+        #[cfg(feature = "prusti")]
+        $crate::internal::prusti_assert($condition);
+    };
+}
+
+/// Asks Prusti to assume, without checking it, that a boolean expression
+/// holds at this point of the body of a function, for example to cut a
+/// proof short:
+///
+/// ```ignore
+/// prusti_assume!(v.len() < usize::MAX);
+/// ```
+///
+/// An assumption that does not hold makes the verification unsound, so it
+/// should be used with care. As for `prusti_assert!`, the expression is
+/// evaluated only when verifying with Prusti.
+#[macro_export]
+macro_rules! prusti_assume {
+    ($condition:expr) => {
+        #[cfg(feature = "prusti")]
+        $crate::internal::prusti_assume($condition);
+    };
+}
+
+/// Asks Prusti to fold the predicate that describes the ownership of a
+/// place, for example of a node of a list:
+///
+/// ```ignore
+/// fold!(list.next);
+/// ```
+///
+/// Prusti folds and unfolds the predicates automatically, so this is only
+/// an escape hatch for the rare cases in which the automatic algorithm
+/// fails on complex nested structures. As for `prusti_assert!`, the
+/// statement is encoded only when verifying with Prusti.
+#[macro_export]
+macro_rules! fold {
+    ($place:expr) => {
+        #[cfg(feature = "prusti")]
+        $crate::internal::prusti_fold(&$place);
+    };
+}
+
+/// Asks Prusti to unfold the predicate that describes the ownership of a
+/// place, giving access to the predicates of its fields. Like `fold!`, this
+/// is only an escape hatch for the automatic algorithm:
+///
+/// ```ignore
+/// unfold!(list.next);
+/// ```
+#[macro_export]
+macro_rules! unfold {
+    ($place:expr) => {
+        #[cfg(feature = "prusti")]
+        $crate::internal::prusti_unfold(&$place);
+    };
+}
+
+/// Declares a loop invariant anywhere in the body of the loop, as an
+/// alternative to the `#[invariant=".."]` attribute on the loop:
+///
+/// ```ignore
+/// while i < n {
+///     body_invariant!(i <= n && sum == 2 * i);
+///     sum += 2;
+///     i += 1;
+/// }
+/// ```
+///
+/// The invariants of a loop are checked at the loop head, wherever they are
+/// written in the body. Prusti replaces the macro before the expansion, so
+/// it expands to nothing when compiling without Prusti.
+#[macro_export]
+macro_rules! body_invariant {
+    ($($tokens:tt)*) => {};
+}
@@ -58,14 +58,35 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
 
             Node::NodeTraitItem(hir::TraitItem {
                 node: hir::TraitItemKind::Method(ref method_sig, ..),
+                id,
+                ref generics,
                 span,
                 ..
             })
             | Node::NodeImplItem(hir::ImplItem {
                 node: hir::ImplItemKind::Method(ref method_sig, ..),
+                id,
+                ref generics,
                 span,
                 ..
             }) => {
+                self.check_where_clause(&generics.where_clause, *span);
+                // The bounds declared on the trait or impl block constrain the method as well
+                let parent_id = self.tcx().hir.get_parent(*id);
+                match self.tcx().hir.get(parent_id) {
+                    Node::NodeItem(hir::Item {
+                        node: hir::Item_::ItemImpl(_, _, _, ref parent_generics, ..),
+                        ..
+                    })
+                    | Node::NodeItem(hir::Item {
+                        node: hir::Item_::ItemTrait(_, _, ref parent_generics, ..),
+                        ..
+                    }) => {
+                        self.check_where_clause(&parent_generics.where_clause, *span);
+                    }
+
+                    _ => {}
+                }
                 self.check_fn_header(method_sig.header, *span);
             }
 
@@ -85,9 +106,7 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
                         }
                     }
                 }
-                if !generics.where_clause.predicates.is_empty() {
-                    unsupported!(self, *span, "has lifetimes constraints");
-                }
+                self.check_where_clause(&generics.where_clause, *span);
                 self.check_fn_header(*header, *span);
             }
 
@@ -95,6 +114,24 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
         }
     }
 
+    fn check_where_clause(&mut self, where_clause: &hir::WhereClause, span: Span) {
+        for predicate in where_clause.predicates.iter() {
+            match predicate {
+                hir::WherePredicate::BoundPredicate(..) => {
+                    interesting!(self, "uses trait bounds in where clauses")
+                }
+
+                hir::WherePredicate::RegionPredicate(..) => {
+                    unsupported!(self, span, "has lifetimes constraints")
+                }
+
+                hir::WherePredicate::EqPredicate(..) => {
+                    unsupported!(self, span, "has equality constraints")
+                }
+            }
+        }
+    }
+
     fn check_fn_header(&mut self, fh: hir::FnHeader, span: Span) {
         match fh.unsafety {
             hir::Unsafety::Unsafe => {
@@ -170,7 +207,7 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
                 unsupported!(self, span, "uses function pointer types")
             }
 
-            ty::TypeVariants::TyDynamic(..) => unsupported!(self, span, "uses dynamic trait types"),
+            ty::TypeVariants::TyDynamic(..) => {} // OK, we use the contracts of the trait
 
             ty::TypeVariants::TyClosure(..) => unsupported!(self, span, "uses closures"),
 
@@ -186,9 +223,14 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
                 }
             }
 
-            ty::TypeVariants::TyProjection(..) => unsupported!(self, span, "uses associated types"),
+            ty::TypeVariants::TyProjection(projection_ty) => {
+                // OK, we normalize it or encode it as an unknown type
+                for inner_ty in projection_ty.substs.types() {
+                    self.check_inner_ty(inner_ty, span);
+                }
+            }
 
-            ty::TypeVariants::TyAnon(..) => unsupported!(self, span, "uses anonymized types"),
+            ty::TypeVariants::TyAnon(..) => {} // OK, we use the contracts of the trait
 
             ty::TypeVariants::TyParam(..) => {} // OK
 
@@ -211,6 +253,10 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
         if adt_def.is_box() {
             let boxed_ty = substs.type_at(0);
             self.check_inner_ty(boxed_ty, span);
+        } else if is_rc_path(&self.tcx().item_path_str(adt_def.did)) {
+            // The content of an `Rc` or `Arc` is shared, and its reference count is not modelled
+            let content_ty = substs.type_at(0);
+            self.check_inner_ty(content_ty, span);
         } else {
             for field_def in adt_def.all_fields() {
                 let field_ty = field_def.ty(self.tcx(), substs);
@@ -338,7 +384,10 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
         {
             let proc_name: &str = &self.tcx().absolute_item_path_str(def_id);
             match proc_name {
-                "std::rt::begin_panic" | "std::panicking::begin_panic" => {
+                "std::rt::begin_panic"
+                | "std::panicking::begin_panic"
+                | "std::rt::begin_panic_fmt"
+                | "std::panicking::begin_panic_fmt" => {
                     interesting!(self, "uses panics");
                 }
 
@@ -351,6 +400,19 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
                     }
                 }
 
+                "<std::rc::Rc<T>>::get_mut"
+                | "<std::rc::Rc<T>>::make_mut"
+                | "<std::sync::Arc<T>>::get_mut"
+                | "<std::sync::Arc<T>>::make_mut" => {
+                    unsupported!(self, span, "mutates the content of reference-counted pointers");
+                }
+
+                _ if self.tcx().is_foreign_item(def_id)
+                    && !self.tcx().has_attr(def_id, "trusted_ffi") =>
+                {
+                    unsupported!(self, span, "calls foreign functions without `#[trusted_ffi]`");
+                }
+
                 _ => {
                     for arg in args {
                         self.check_operand(mir, arg, span);
@@ -541,130 +603,21 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
         let src_ty = self.get_operand_ty(mir, op);
 
         match (&src_ty.sty, &dst_ty.sty) {
-            (ty::TypeVariants::TyInt(ast::IntTy::I8), ty::TypeVariants::TyInt(ast::IntTy::I8))
-            | (ty::TypeVariants::TyInt(ast::IntTy::I8), ty::TypeVariants::TyInt(ast::IntTy::I16))
-            | (ty::TypeVariants::TyInt(ast::IntTy::I8), ty::TypeVariants::TyInt(ast::IntTy::I32))
-            | (ty::TypeVariants::TyInt(ast::IntTy::I8), ty::TypeVariants::TyInt(ast::IntTy::I64))
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I8),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::Isize),
-                ty::TypeVariants::TyInt(ast::IntTy::Isize),
-            )
+            // Casts between integers may truncate the value
+            (ty::TypeVariants::TyInt(_), ty::TypeVariants::TyInt(_))
+            | (ty::TypeVariants::TyInt(_), ty::TypeVariants::TyUint(_))
+            | (ty::TypeVariants::TyUint(_), ty::TypeVariants::TyInt(_))
+            | (ty::TypeVariants::TyUint(_), ty::TypeVariants::TyUint(_))
             | (ty::TypeVariants::TyChar, ty::TypeVariants::TyChar)
             | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U8))
             | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U16))
             | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U32))
             | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U64))
             | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U128))
-            | (ty::TypeVariants::TyUint(ast::UintTy::U8), ty::TypeVariants::TyChar)
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::Usize),
-                ty::TypeVariants::TyUint(ast::UintTy::Usize),
-            ) => {} // OK
-
+            | (ty::TypeVariants::TyUint(ast::UintTy::U8), ty::TypeVariants::TyChar) => {} // OK
+            // Boxes can be coerced to boxed trait objects, which are opaque
+            (ty::TypeVariants::TyAdt(..), ty::TypeVariants::TyAdt(..))
+                if src_ty.is_box() && dst_ty.is_box() && dst_ty.boxed_ty().is_trait() => {} // OK
             _ => unsupported!(self, span, "uses unsupported casts"),
         };
     }
@@ -778,3 +731,11 @@ pub trait CommonValidator<'a, 'tcx: 'a> {
         }
     }
 }
+
+/// Returns true if the path is the one of `Rc` or `Arc`.
+fn is_rc_path(path: &str) -> bool {
+    match path {
+        "std::rc::Rc" | "alloc::rc::Rc" | "std::sync::Arc" | "alloc::sync::Arc" => true,
+        _ => false,
+    }
+}
@@ -176,6 +176,10 @@ impl<'a, 'tcx: 'a> ProcedureValidator<'a, 'tcx> {
             if !procedure.is_reachable_block(bbi) || procedure.is_spec_block(bbi) {
                 continue;
             }
+            if procedure.is_panic_message_block(bbi) {
+                // The computation of the message of a panic is not encoded
+                continue;
+            }
             if !procedure.is_panic_block(bbi) {
                 for stmt in &basic_block_data.statements {
                     self.check_mir_stmt(mir, stmt);
@@ -7,6 +7,7 @@
 use prusti_interface::environment::{Procedure, ProcedureLoops};
 use rustc::hir;
 use rustc::hir::def_id::DefId;
+use rustc::mir;
 use rustc::ty;
 use std::collections::HashSet;
 use syntax::codemap::Span;
@@ -132,15 +133,36 @@ impl<'a, 'tcx: 'a> PureFunctionValidator<'a, 'tcx> {
             if !procedure.is_reachable_block(bbi) || procedure.is_spec_block(bbi) {
                 continue;
             }
+            if procedure.is_panic_message_block(bbi) {
+                // The computation of the message of a panic is not encoded
+                continue;
+            }
             if !procedure.is_panic_block(bbi) {
                 for stmt in &basic_block_data.statements {
                     self.check_mir_stmt(mir, stmt);
+                    self.check_no_mutation(stmt);
                 }
             }
             self.check_mir_terminator(mir, basic_block_data.terminator.as_ref().unwrap());
         }
     }
 
+    /// The arguments of pure functions, including `&mut` references, are encoded as read-only
+    /// snapshots. Thus, the body is not allowed to modify memory behind a reference.
+    fn check_no_mutation(&mut self, stmt: &mir::Statement<'tcx>) {
+        let span = stmt.source_info.span;
+        match stmt.kind {
+            mir::StatementKind::Assign(ref place, _)
+            | mir::StatementKind::SetDiscriminant { ref place, .. } => {
+                if is_behind_deref(place) {
+                    unsupported!(self, span, "assigns to memory behind a reference");
+                }
+            }
+
+            _ => {} // OK
+        }
+    }
+
     fn check_mir_signature(&mut self, procedure: &Procedure<'a, 'tcx>) {
         let mir = procedure.get_mir();
         let span = procedure.get_span();
@@ -160,3 +182,13 @@ impl<'a, 'tcx: 'a> PureFunctionValidator<'a, 'tcx> {
         }
     }
 }
+
+fn is_behind_deref(place: &mir::Place) -> bool {
+    match *place {
+        mir::Place::Projection(box mir::Projection {
+            ref base,
+            ref elem,
+        }) => *elem == mir::ProjectionElem::Deref || is_behind_deref(base),
+        _ => false,
+    }
+}
@@ -110,6 +110,17 @@ impl<'a> MinimalAstBuilder<'a> {
         )
     }
 
+    pub fn attribute_cfg(&self, span: Span, value: &str) -> ast::Attribute {
+        self.attribute(
+            span,
+            self.meta_list(
+                span,
+                self.name_of("cfg"),
+                vec![self.meta_list_item_word(span, self.name_of(value))],
+            ),
+        )
+    }
+
     pub fn attribute_word(&self, span: Span, word: &str) -> ast::Attribute {
         self.attribute(span, self.meta_word(span, self.name_of(word)))
     }
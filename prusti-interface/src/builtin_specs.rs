@@ -0,0 +1,181 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Built-in specifications of the standard library.
+//!
+//! The specifications are written as an `#[extern_spec]` module, which the parser adds to every
+//! crate that uses `prusti_contracts` (unless disabled with the `BUILTIN_SPECS` option).
+//!
+//! A `std::vec::Vec` and a slice are modelled by their length, which is given by the pure
+//! function `len`. An iterator over a slice (or, through `deref`, over a vector) is modelled by
+//! the number of elements that it has not visited yet, which is given by the pure function `len`
+//! of `ExactSizeIterator`.
+//! The length of a `str` and of a `String` is given by the model of strings of the encoder.
+//! Unwrapping an `Option` or a `Result` requires it to contain a value, so that the verifier can
+//! check that the unwrapping does not panic.
+
+/// The name of the module declared by `BUILTIN_SPECS`
+pub const BUILTIN_SPECS_MODULE: &str = "__prusti_builtin_specs";
+
+pub const BUILTIN_SPECS: &str = r##"
+#[extern_spec]
+#[allow(non_snake_case, dead_code, unused_variables)]
+mod __prusti_builtin_specs {
+    mod str {
+        #[trusted]
+        #[pure]
+        fn len(s: &str) -> usize {
+            s.len()
+        }
+
+        #[trusted]
+        #[pure]
+        fn is_empty(s: &str) -> bool {
+            s.is_empty()
+        }
+    }
+
+    mod slice {
+        #[trusted]
+        #[pure]
+        fn len<T>(slice: &[T]) -> usize {
+            slice.len()
+        }
+
+        #[trusted]
+        #[ensures="result.len() == slice.len()"]
+        fn iter<T>(slice: &[T]) -> ::std::slice::Iter<T> {
+            slice.iter()
+        }
+    }
+
+    mod std {
+        mod iter {
+            mod ExactSizeIterator {
+                #[trusted]
+                #[pure]
+                fn len<I: ::std::iter::ExactSizeIterator>(iter: &I) -> usize {
+                    iter.len()
+                }
+            }
+        }
+
+        mod slice {
+            mod Iter {
+                #[trusted]
+                #[ensures="old(iter.len()) == 0 ==> (iter.len() == 0 && result.is_none())"]
+                #[ensures="old(iter.len()) > 0 ==> (
+                    iter.len() == old(iter.len()) - 1 && result.is_some()
+                )"]
+                fn next<'a, T>(iter: &mut ::std::slice::Iter<'a, T>) -> Option<&'a T> {
+                    iter.next()
+                }
+            }
+        }
+
+        mod string {
+            mod String {
+                #[trusted]
+                #[pure]
+                fn len(string: &::std::string::String) -> usize {
+                    string.len()
+                }
+
+                #[trusted]
+                #[pure]
+                fn is_empty(string: &::std::string::String) -> bool {
+                    string.is_empty()
+                }
+            }
+        }
+
+        mod vec {
+            mod Vec {
+                #[trusted]
+                #[ensures="result.len() == 0"]
+                fn new<T>() -> ::std::vec::Vec<T> {
+                    ::std::vec::Vec::new()
+                }
+
+                #[trusted]
+                #[pure]
+                fn len<T>(vec: &::std::vec::Vec<T>) -> usize {
+                    vec.len()
+                }
+
+                #[trusted]
+                #[ensures="vec.len() == old(vec.len()) + 1"]
+                fn push<T>(vec: &mut ::std::vec::Vec<T>, value: T) {
+                    vec.push(value)
+                }
+
+                #[trusted]
+                #[ensures="old(vec.len()) == 0 ==> (vec.len() == 0 && result.is_none())"]
+                #[ensures="old(vec.len()) > 0 ==> (
+                    vec.len() == old(vec.len()) - 1 && result.is_some()
+                )"]
+                fn pop<T>(vec: &mut ::std::vec::Vec<T>) -> Option<T> {
+                    vec.pop()
+                }
+
+                #[trusted]
+                #[ensures="result.len() == vec.len()"]
+                fn deref<T>(vec: &::std::vec::Vec<T>) -> &[T] {
+                    vec
+                }
+
+                #[trusted]
+                #[requires="index < vec.len()"]
+                fn index<T>(vec: &::std::vec::Vec<T>, index: usize) -> &T {
+                    &vec[index]
+                }
+
+                #[trusted]
+                #[requires="index < vec.len()"]
+                #[ensures="after_expiry(vec.len() == old(vec.len()))"]
+                fn index_mut<T>(vec: &mut ::std::vec::Vec<T>, index: usize) -> &mut T {
+                    &mut vec[index]
+                }
+            }
+        }
+
+        mod option {
+            mod Option {
+                #[trusted]
+                #[requires="opt.is_some()"]
+                fn unwrap<T>(opt: ::std::option::Option<T>) -> T {
+                    opt.unwrap()
+                }
+
+                #[trusted]
+                #[requires="opt.is_some()"]
+                fn expect<T>(opt: ::std::option::Option<T>, msg: &str) -> T {
+                    opt.expect(msg)
+                }
+            }
+        }
+
+        mod result {
+            mod Result {
+                #[trusted]
+                #[requires="res.is_ok()"]
+                fn unwrap<T, E: ::std::fmt::Debug>(res: ::std::result::Result<T, E>) -> T {
+                    res.unwrap()
+                }
+
+                #[trusted]
+                #[requires="res.is_ok()"]
+                fn expect<T, E: ::std::fmt::Debug>(
+                    res: ::std::result::Result<T, E>,
+                    msg: &str,
+                ) -> T {
+                    res.expect(msg)
+                }
+            }
+        }
+    }
+}
+"##;
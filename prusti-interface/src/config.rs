@@ -4,49 +4,95 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use config_crate::{Config, Environment, File};
+use config_crate::{self, Environment, File, Source, Value};
+use rustc::session::config::ErrorOutputType;
+use rustc::session::early_error;
+use serde::de::{self, Deserialize, Deserializer};
 use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::RwLock;
 
 lazy_static! {
     // Is this RwLock<..> necessary?
-    static ref SETTINGS: RwLock<Config> = RwLock::new({
-        let mut settings = Config::default();
+    static ref SETTINGS: RwLock<config_crate::Config> = RwLock::new({
+        let mut settings = config_crate::Config::default();
 
         // 1. Default values
         settings.set_default("VIPER_BACKEND", "Silicon").unwrap();
         settings.set_default("CHECK_FOLDUNFOLD_STATE", false).unwrap();
         settings.set_default("CHECK_BINARY_OPERATIONS", false).unwrap();
+        settings.set_default("WRAPPING_ARITHMETIC", false).unwrap();
+        settings.set_default("CHECK_INTEGER_CASTS", false).unwrap();
         settings.set_default("CHECK_PANICS", true).unwrap();
+        settings.set_default("SUGGEST_UNDERFLOW_GUARDS", false).unwrap();
+        settings.set_default("CHECK_PURE_FUNCTION_TERMINATION", false).unwrap();
+        settings.set_default("CHECK_SPEC_SANITY", false).unwrap();
         settings.set_default("ENCODE_UNSIGNED_NUM_CONSTRAINT", false).unwrap();
         settings.set_default("SIMPLIFY_EXPRESSIONS", true).unwrap();
+        settings.set_default("SIMPLIFY_ENCODED_EXPRESSIONS", true).unwrap();
         settings.set_default("SIMPLIFY_FUNCTIONS", true).unwrap();
+        settings.set_default("INLINE_PURE_FUNCTIONS", false).unwrap();
+        settings.set_default("INLINE_PURE_FUNCTIONS_MAX_SIZE", 20).unwrap();
+        settings.set_default::<Vec<String>>("OPTIMIZATIONS", vec![
+            "fold_constants".to_string(),
+            "remove_trivial_assertions".to_string(),
+            "remove_unused_vars".to_string(),
+        ]).unwrap();
+        settings.set_default::<Vec<String>>("VIR_PLUGINS", vec![]).unwrap();
         settings.set_default("ENABLE_WHITELIST", false).unwrap();
         settings.set_default::<Vec<String>>("WHITELIST", vec![]).unwrap();
+        settings.set_default::<Vec<String>>("CHECK_ONLY", vec![]).unwrap();
+        settings.set_default::<Vec<String>>("SKIP", vec![]).unwrap();
+        settings.set_default("ENTRY_POINT", "").unwrap();
         settings.set_default("LOG_DIR", "./log/").unwrap();
         settings.set_default("DUMP_DEBUG_INFO", false).unwrap();
         settings.set_default("DUMP_BRANCH_CTXT_IN_DEBUG_INFO", false).unwrap();
         settings.set_default("DUMP_REBORROWING_DAG_IN_DEBUG_INFO", false).unwrap();
         settings.set_default("DUMP_BORROWCK_INFO", false).unwrap();
         settings.set_default("DUMP_VIPER_PROGRAM", false).unwrap();
+        settings.set_default("DUMP_VIR_PROGRAM", false).unwrap();
         settings.set_default("CONTRACTS_LIB", "").unwrap();
         settings.set_default::<Vec<String>>("EXTRA_JVM_ARGS", vec![]).unwrap();
         settings.set_default::<Vec<String>>("EXTRA_VERIFIER_ARGS", vec![]).unwrap();
         settings.set_default("QUIET", false).unwrap();
         settings.set_default("ASSERT_TIMEOUT", 10_000).unwrap();
+        settings.set_default("VERIFICATION_TIMEOUT_MS", 0).unwrap();
+        settings.set_default("REPORT_VERIFIER_STATISTICS", false).unwrap();
         settings.set_default("USE_MORE_COMPLETE_EXHALE", true).unwrap();
         settings.set_default("USE_ASSUME_FALSE_BACK_EDGES", true).unwrap();
+        settings.set_default("INFER_LOOP_INVARIANTS", true).unwrap();
         settings.set_default("REPORT_SUPPORT_STATUS", true).unwrap();
+        settings.set_default("INCREMENTAL", false).unwrap();
+        settings.set_default("CACHE_DIR", "./prusti_cache/").unwrap();
+        settings.set_default("REPORT_JSON", "").unwrap();
+        settings.set_default("CHECK_SPECS_ONLY", false).unwrap();
+        settings.set_default("STREAM_JSON", false).unwrap();
+        settings.set_default("VERIFIER_DAEMON", "").unwrap();
+        settings.set_default("CONTRACT_CHECKS", "verify").unwrap();
+        settings.set_default("BUILTIN_SPECS", true).unwrap();
+        settings.set_default("CONTRACT_METADATA", true).unwrap();
+        settings.set_default("STRICT_TRIGGERS", false).unwrap();
+        settings.set_default("EXHALE_DEAD_PERMISSIONS", false).unwrap();
+        settings.set_default("PROFILE_ENCODER", false).unwrap();
+        settings.set_default("DEBUG_ASSERTIONS", "verify").unwrap();
+        settings.set_default("EXPLAIN_ASSERTIONS", "off").unwrap();
+        settings.set_default("MAX_UNFOLD_DEPTH", 100).unwrap();
+        settings.set_default("VERIFY_PROVIDED_METHODS_ONCE", false).unwrap();
 
-        // 2. Override with the optional TOML file "Prusti.toml" (if there is any)
-        settings.merge(
-            File::with_name("Prusti.toml").required(false)
-        ).unwrap();
+        // 2. Override with the optional TOML files "Prusti.toml" of the workspace and of the
+        //    crate (if there are any), the latter taking precedence
+        for path in config_file_paths() {
+            let file = File::from(path.as_path()).required(false);
+            check_config_file(&settings, &file, &path.display().to_string());
+            settings.merge(file).unwrap();
+        }
 
         // 3. Override with an optional TOML file specified by the `PRUSTI_CONFIG` env variable
-        settings.merge(
-            File::with_name(&env::var("PRUSTI_CONFIG").unwrap_or("".to_string())).required(false)
-        ).unwrap();
+        let config_path = env::var("PRUSTI_CONFIG").unwrap_or("".to_string());
+        let file = File::with_name(&config_path).required(false);
+        check_config_file(&settings, &file, &config_path);
+        settings.merge(file).unwrap();
 
         // 4. Override with env variables (`PRUSTI_VIPER_BACKEND`, ...)
         settings.merge(
@@ -55,211 +101,673 @@ lazy_static! {
 
         settings
     });
+
+    static ref CONFIG: Config = SETTINGS
+        .read()
+        .unwrap()
+        .clone()
+        .try_into()
+        .unwrap_or_else(|err| {
+            early_error(ErrorOutputType::default(), &format!("invalid configuration: {}", err))
+        });
+}
+
+/// The paths of the `Prusti.toml` files of the workspace and of the crate, in order of increasing
+/// precedence. Cargo runs the compiler in the root of the workspace and sets `CARGO_MANIFEST_DIR`
+/// to the root of the crate; outside of Cargo both are the current directory.
+fn config_file_paths() -> Vec<PathBuf> {
+    let workspace_root = env::current_dir().unwrap_or_default();
+    let crate_root = env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| workspace_root.clone());
+    let mut paths = vec![workspace_root.join("Prusti.toml")];
+    if crate_root != workspace_root {
+        paths.push(crate_root.join("Prusti.toml"));
+    }
+    paths
+}
+
+/// Reject the flags of a configuration file that have no default value, which are likely typos.
+/// A missing file has no flags.
+fn check_config_file<S: Source>(settings: &config_crate::Config, file: &S, name: &str) {
+    let file_settings = file.collect().unwrap_or_else(|err| {
+        early_error(
+            ErrorOutputType::default(),
+            &format!("invalid configuration file '{}': {}", name, err),
+        )
+    });
+    for key in file_settings.keys() {
+        if settings.get::<Value>(key).is_err() {
+            early_error(
+                ErrorOutputType::default(),
+                &format!("unknown configuration flag '{}' in '{}'", key, name),
+            );
+        }
+    }
+}
+
+/// How the contracts are checked (see `CONTRACT_CHECKS`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractChecks {
+    /// By the verifier
+    Verify,
+    /// By assertions compiled into debug builds of the program
+    Runtime,
+    /// Both by the verifier and at runtime
+    Both,
+}
+
+impl FromStr for ContractChecks {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().trim() {
+            "verify" => Ok(ContractChecks::Verify),
+            "runtime" => Ok(ContractChecks::Runtime),
+            "both" => Ok(ContractChecks::Both),
+            _ => Err(format!(
+                "invalid value '{}' of CONTRACT_CHECKS, expected 'verify', 'runtime' or 'both'",
+                value
+            )),
+        }
+    }
+}
+
+/// How the debug assertions are handled (see `DEBUG_ASSERTIONS`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugAssertions {
+    /// Like the other assertions
+    Verify,
+    /// Not at all
+    Ignore,
+}
+
+impl FromStr for DebugAssertions {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().trim() {
+            "verify" => Ok(DebugAssertions::Verify),
+            "ignore" => Ok(DebugAssertions::Ignore),
+            _ => Err(format!(
+                "invalid value '{}' of DEBUG_ASSERTIONS, expected 'verify' or 'ignore'",
+                value
+            )),
+        }
+    }
+}
+
+/// How the failing Viper assertions are explained (see `EXPLAIN_ASSERTIONS`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplainAssertions {
+    /// Not at all
+    Off,
+    /// On stderr
+    Print,
+    /// In `log/assertion_explanations/`
+    Log,
+}
+
+impl FromStr for ExplainAssertions {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().trim() {
+            "off" => Ok(ExplainAssertions::Off),
+            "print" => Ok(ExplainAssertions::Print),
+            "log" => Ok(ExplainAssertions::Log),
+            _ => Err(format!(
+                "invalid value '{}' of EXPLAIN_ASSERTIONS, expected 'off', 'print' or 'log'",
+                value
+            )),
+        }
+    }
+}
+
+/// Deserialize a flag whose string value is parsed with `FromStr`.
+fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err = String>,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map_err(de::Error::custom)
+}
+
+/// The typed configuration, obtained from the layered settings: the default values, the
+/// `Prusti.toml` files, the file of `PRUSTI_CONFIG` and the `PRUSTI_*` environment variables.
+/// Each field corresponds to the flag with the same name in upper case.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    /// The Viper backend that should be used for the verification
+    pub viper_backend: String,
+
+    /// Generate additional, *slow*, checks for the foldunfold algorithm
+    pub check_foldunfold_state: bool,
+
+    /// Check binary operations for overflows
+    pub check_binary_operations: bool,
+
+    /// Give wrapping semantics to integer overflows, like Rust without overflow checks.
+    pub wrapping_arithmetic: bool,
+
+    /// Check that integer casts (`as`) do not truncate the value, unless wrapping arithmetic is
+    /// enabled.
+    pub check_integer_casts: bool,
+
+    /// Should we check absence of panics?
+    pub check_panics: bool,
+
+    /// Suggest the guard that prevents an unsigned subtraction from underflowing, when reporting
+    /// that it might underflow.
+    pub suggest_underflow_guards: bool,
+
+    /// Should we check that the recursive pure functions terminate?
+    pub check_pure_function_termination: bool,
+
+    /// Warn about the preconditions and type invariants that are unsatisfiable
+    pub check_spec_sanity: bool,
+
+    /// Encode (and check) that unsigned integers are non-negative.
+    pub encode_unsigned_num_constraint: bool,
+
+    /// Should we simplify expressions?
+    pub simplify_expressions: bool,
+
+    /// Should the encoder simplify the trivial boolean operations that it generates, like `true && e`
+    /// or `e ==> true`, while it constructs them?
+    pub simplify_encoded_expressions: bool,
+
+    /// Should we simplify functions?
+    pub simplify_functions: bool,
+
+    /// Should we inline the calls of small pure functions?
+    pub inline_pure_functions: bool,
+
+    /// The maximum number of nodes of the body of a pure function that is inlined
+    pub inline_pure_functions_max_size: usize,
+
+    /// The optimisations to run on the Viper program, in order
+    pub optimizations: Vec<String>,
+
+    /// The plugin libraries that provide additional VIR transformations.
+    pub vir_plugins: Vec<String>,
+
+    /// Whether to use the verifiation whitelist
+    pub enable_whitelist: bool,
+
+    /// Get the whitelist of procedures that should be verified
+    pub whitelist: Vec<String>,
+
+    /// Get the paths of the procedures and modules that should be verified. If empty, all
+    /// procedures are verified.
+    pub check_only: Vec<String>,
+
+    /// Get the paths of the procedures and modules whose verification should be skipped
+    pub skip: Vec<String>,
+
+    /// Get the path of the procedure from which the verification starts, e.g. `main`. If not empty,
+    /// only this procedure and the procedures of the crate that it (transitively) calls are
+    /// verified, callees before callers.
+    pub entry_point: String,
+
+    /// In which folder should we sore log/dumps?
+    pub log_dir: String,
+
+    /// Should we dump debug files?
+    pub dump_debug_info: bool,
+
+    /// Should we dump the branch context state in debug files?
+    pub dump_branch_ctxt_in_debug_info: bool,
+
+    /// Should we dump the reborrowing DAGs in debug files?
+    pub dump_reborrowing_dag_in_debug_info: bool,
+
+    /// Should we dump borrowck info?
+    pub dump_borrowck_info: bool,
+
+    /// Should we dump the Viper program, and each encoded method annotated with the Rust source?
+    pub dump_viper_program: bool,
+
+    /// Should we dump the VIR program in the textual form that can be read back?
+    pub dump_vir_program: bool,
+
+    /// Location of 'libprusti_contracts*.rlib'
+    pub contracts_lib: String,
+
+    /// Get extra JVM arguments
+    pub extra_jvm_args: Vec<String>,
+
+    /// Get extra arguments for the verifier
+    pub extra_verifier_args: Vec<String>,
+
+    /// Should we hide user messages?
+    pub quiet: bool,
+
+    /// The assert timeout (in miliseconds) passed to Silicon.
+    pub assert_timeout: u64,
+
+    /// The timeout (in miliseconds) for the verification of each encoded method, or `0` for no
+    /// timeout. It is rounded up to whole seconds, because that is the unit used by Silicon.
+    pub verification_timeout_ms: u64,
+
+    /// Collect the statistics of Silicon (e.g. the number of quantifier instantiations) for each
+    /// encoded method, and report them in the log and in the JSON report.
+    pub report_verifier_statistics: bool,
+
+    /// Use the Silicon configuration option `--enableMoreCompleteExhale`.
+    pub use_more_complete_exhale: bool,
+
+    /// Replace all back-edges with `assume false`.
+    pub use_assume_false_back_edges: bool,
+
+    /// Infer the bounds of the counter of `while i < n { ...; i += 1 }` loops without invariant
+    pub infer_loop_invariants: bool,
+
+    /// Report the support status of functions using the compiler's error messages
+    pub report_support_status: bool,
+
+    /// Re-verify only the methods whose encoding changed since the last successful run
+    pub incremental: bool,
+
+    /// Directory in which the hashes of the verified methods are stored by the incremental mode
+    pub cache_dir: String,
+
+    /// Path of the JSON file in which the per-procedure verification results are written (if not empty)
+    pub report_json: String,
+
+    /// Only parse and type-check the specifications, without running the verifier
+    pub check_specs_only: bool,
+
+    /// Print the progress and the results of the verification of each procedure on stdout, as
+    /// JSON events (one per line)
+    pub stream_json: bool,
+
+    /// Address (`host:port`) of a running `prusti-server`, to which the verification of the encoded
+    /// programs is delegated (if not empty)
+    pub verifier_daemon: String,
+
+    /// How the contracts are checked: `verify` (by the verifier), `runtime` (by assertions compiled
+    /// into debug builds of the program) or `both`
+    #[serde(deserialize_with = "deserialize_from_str")]
+    pub contract_checks: ContractChecks,
+
+    /// Use the built-in specifications of the standard library (e.g. of `Vec`)
+    pub builtin_specs: bool,
+
+    /// Export the contracts of verified libraries, and import the contracts of the dependencies
+    pub contract_metadata: bool,
+
+    /// Report quantifiers without valid triggers and nested quantifiers as errors instead of warnings
+    pub strict_triggers: bool,
+
+    /// Exhale the permissions of local variables when their storage dies, so that the verifier does
+    /// not accumulate them in long procedures
+    pub exhale_dead_permissions: bool,
+
+    /// Report the time spent in each phase of the encoding and of the verification
+    pub profile_encoder: bool,
+
+    /// How the `debug_assert!`, `debug_assert_eq!` and `debug_assert_ne!` statements are handled:
+    /// `verify` (like the other assertions) or `ignore`
+    #[serde(deserialize_with = "deserialize_from_str")]
+    pub debug_assertions: DebugAssertions,
+
+    /// How the Viper assertions that fail are explained: `off`, `print` (on stderr) or `log` (in
+    /// `log/assertion_explanations/`)
+    #[serde(deserialize_with = "deserialize_from_str")]
+    pub explain_assertions: ExplainAssertions,
+
+    /// The maximum number of nested predicates that the encoding can unfold to access a place, or 0
+    /// to not bound the unfolding
+    pub max_unfold_depth: u32,
+
+    /// Should the body of a pure method provided by a trait be verified only once, against the
+    /// contract of the trait, instead of once for each implementation that does not override it?
+    pub verify_provided_methods_once: bool,
+}
+
+/// The configuration of Prusti
+pub fn get() -> &'static Config {
+    &CONFIG
 }
 
 /// Generate a dump of the settings
 pub fn dump() -> String {
-    format!("{:?}", SETTINGS.read().unwrap())
+    format!("{:?}", *CONFIG)
 }
 
 /// Generate additional, *slow*, checks for the foldunfold algorithm
 pub fn check_foldunfold_state() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("CHECK_FOLDUNFOLD_STATE")
-        .unwrap()
+    CONFIG.check_foldunfold_state
 }
 
 /// The Viper backend that should be used for the verification
 pub fn viper_backend() -> String {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<String>("VIPER_BACKEND")
-        .unwrap()
-        .to_lowercase()
-        .trim()
-        .to_string()
+    CONFIG.viper_backend.to_lowercase().trim().to_string()
 }
 
 /// Should we check absence of panics?
 pub fn check_panics() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("CHECK_PANICS")
-        .unwrap()
+    CONFIG.check_panics
+}
+
+/// Should we check that the recursive pure functions terminate?
+pub fn check_pure_function_termination() -> bool {
+    CONFIG.check_pure_function_termination
 }
 
 /// Should we simplify expressions?
 pub fn simplify_expressions() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("SIMPLIFY_EXPRESSIONS")
-        .unwrap()
+    CONFIG.simplify_expressions
+}
+
+/// Should the encoder simplify the trivial boolean operations that it generates, like `true && e`
+/// or `e ==> true`, while it constructs them?
+pub fn simplify_encoded_expressions() -> bool {
+    CONFIG.simplify_encoded_expressions
 }
 
 /// Whether to use the verifiation whitelist
 pub fn enable_whitelist() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("ENABLE_WHITELIST")
-        .unwrap()
+    CONFIG.enable_whitelist
 }
 
 /// Get the whitelist of procedures that should be verified
 pub fn verification_whitelist() -> Vec<String> {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<Vec<String>>("WHITELIST")
-        .unwrap()
+    CONFIG.whitelist.clone()
+}
+
+/// Get the paths of the procedures and modules that should be verified. If empty, all
+/// procedures are verified.
+pub fn check_only() -> Vec<String> {
+    CONFIG.check_only.clone()
+}
+
+/// Get the paths of the procedures and modules whose verification should be skipped
+pub fn skip() -> Vec<String> {
+    CONFIG.skip.clone()
+}
+
+/// Get the path of the procedure from which the verification starts, e.g. `main`. If not empty,
+/// only this procedure and the procedures of the crate that it (transitively) calls are
+/// verified, callees before callers.
+pub fn entry_point() -> String {
+    CONFIG.entry_point.clone()
 }
 
 /// Should we dump debug files?
 pub fn dump_debug_info() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("DUMP_DEBUG_INFO")
-        .unwrap()
+    CONFIG.dump_debug_info
 }
 
 /// Should we dump the branch context state in debug files?
 pub fn dump_branch_ctxt_in_debug_info() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("DUMP_BRANCH_CTXT_IN_DEBUG_INFO")
-        .unwrap()
+    CONFIG.dump_branch_ctxt_in_debug_info
 }
 
 /// Should we dump the reborrowing DAGs in debug files?
 pub fn dump_reborrowing_dag_in_debug_info() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("DUMP_REBORROWING_DAG_IN_DEBUG_INFO")
-        .unwrap()
+    CONFIG.dump_reborrowing_dag_in_debug_info
 }
 
 /// Should we dump borrowck info?
 pub fn dump_borrowck_info() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("DUMP_BORROWCK_INFO")
-        .unwrap()
+    CONFIG.dump_borrowck_info
 }
 
-/// Should we dump the Viper program?
+/// Should we dump the Viper program, and each encoded method annotated with the Rust source?
 pub fn dump_viper_program() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("DUMP_VIPER_PROGRAM")
-        .unwrap()
+    CONFIG.dump_viper_program
+}
+
+/// Should we dump the VIR program in the textual form that can be read back?
+pub fn dump_vir_program() -> bool {
+    CONFIG.dump_vir_program
 }
 
 /// In which folder should we sore log/dumps?
 pub fn log_dir() -> String {
-    SETTINGS.read().unwrap().get::<String>("LOG_DIR").unwrap()
+    CONFIG.log_dir.clone()
 }
 
 /// Check binary operations for overflows
 pub fn check_binary_operations() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("CHECK_BINARY_OPERATIONS")
-        .unwrap()
+    CONFIG.check_binary_operations
+}
+
+/// Suggest the guard that prevents an unsigned subtraction from underflowing, when reporting
+/// that it might underflow.
+pub fn suggest_underflow_guards() -> bool {
+    CONFIG.suggest_underflow_guards
+}
+
+/// Warn about the preconditions and type invariants that are unsatisfiable
+pub fn check_spec_sanity() -> bool {
+    CONFIG.check_spec_sanity
+}
+
+/// Give wrapping semantics to integer overflows, like Rust without overflow checks.
+pub fn wrapping_arithmetic() -> bool {
+    CONFIG.wrapping_arithmetic
+}
+
+/// Check that integer casts (`as`) do not truncate the value, unless wrapping arithmetic is
+/// enabled.
+pub fn check_integer_casts() -> bool {
+    CONFIG.check_integer_casts
 }
 
 /// Encode (and check) that unsigned integers are non-negative.
 pub fn encode_unsigned_num_constraint() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("ENCODE_UNSIGNED_NUM_CONSTRAINT")
-        .unwrap()
+    CONFIG.encode_unsigned_num_constraint
 }
 
 /// Location of 'libprusti_contracts*.rlib'
 pub fn contracts_lib() -> String {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<String>("CONTRACTS_LIB")
-        .unwrap()
+    CONFIG.contracts_lib.clone()
 }
 
 /// Get extra JVM arguments
 pub fn extra_jvm_args() -> Vec<String> {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<Vec<String>>("EXTRA_JVM_ARGS")
-        .unwrap()
+    CONFIG.extra_jvm_args.clone()
 }
 
 /// Get extra arguments for the verifier
 pub fn extra_verifier_args() -> Vec<String> {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<Vec<String>>("EXTRA_VERIFIER_ARGS")
-        .unwrap()
+    CONFIG.extra_verifier_args.clone()
 }
 
 /// Should we simplify functions?
 pub fn simplify_functions() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("SIMPLIFY_FUNCTIONS")
-        .unwrap()
+    CONFIG.simplify_functions
+}
+
+/// Should we inline the calls of small pure functions?
+pub fn inline_pure_functions() -> bool {
+    CONFIG.inline_pure_functions
+}
+
+/// The maximum number of nodes of the body of a pure function that is inlined
+pub fn inline_pure_functions_max_size() -> usize {
+    CONFIG.inline_pure_functions_max_size
+}
+
+/// The optimisations to run on the Viper program, in order
+pub fn optimizations() -> Vec<String> {
+    CONFIG.optimizations.clone()
+}
+
+/// The plugin libraries that provide additional VIR transformations.
+pub fn vir_plugins() -> Vec<String> {
+    CONFIG.vir_plugins.clone()
 }
 
 /// Should we hide user messages?
 pub fn quiet() -> bool {
-    SETTINGS.read().unwrap().get::<bool>("QUIET").unwrap()
+    CONFIG.quiet
 }
 
 /// The assert timeout (in miliseconds) passed to Silicon.
 pub fn assert_timeout() -> u64 {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<u64>("ASSERT_TIMEOUT")
-        .unwrap()
+    CONFIG.assert_timeout
+}
+
+/// The timeout (in miliseconds) for the verification of each encoded method, or `0` for no
+/// timeout. It is rounded up to whole seconds, because that is the unit used by Silicon.
+pub fn verification_timeout_ms() -> u64 {
+    CONFIG.verification_timeout_ms
+}
+
+/// Collect the statistics of Silicon (e.g. the number of quantifier instantiations) for each
+/// encoded method, and report them in the log and in the JSON report.
+pub fn report_verifier_statistics() -> bool {
+    CONFIG.report_verifier_statistics
 }
 
 /// Use the Silicon configuration option `--enableMoreCompleteExhale`.
 pub fn use_more_complete_exhale() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("USE_MORE_COMPLETE_EXHALE")
-        .unwrap()
+    CONFIG.use_more_complete_exhale
 }
 
 /// Replace all back-edges with `assume false`.
 pub fn use_assume_false_back_edges() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("USE_ASSUME_FALSE_BACK_EDGES")
-        .unwrap()
+    CONFIG.use_assume_false_back_edges
+}
+
+/// Infer the bounds of the counter of `while i < n { ...; i += 1 }` loops without invariant
+pub fn infer_loop_invariants() -> bool {
+    CONFIG.infer_loop_invariants
 }
 
 /// Report the support status of functions using the compiler's error messages
 pub fn report_support_status() -> bool {
-    SETTINGS
-        .read()
-        .unwrap()
-        .get::<bool>("REPORT_SUPPORT_STATUS")
-        .unwrap()
+    CONFIG.report_support_status
+}
+
+/// Re-verify only the methods whose encoding changed since the last successful run
+pub fn incremental() -> bool {
+    CONFIG.incremental
+}
+
+/// Directory in which the hashes of the verified methods are stored by the incremental mode
+pub fn cache_dir() -> String {
+    CONFIG.cache_dir.clone()
+}
+
+/// Path of the JSON file in which the per-procedure verification results are written (if not empty)
+pub fn report_json() -> String {
+    CONFIG.report_json.clone()
+}
+
+/// Only parse and type-check the specifications, without running the verifier
+pub fn check_specs_only() -> bool {
+    CONFIG.check_specs_only
+}
+
+/// Print the progress and the results of the verification of each procedure on stdout, as
+/// JSON events (one per line)
+pub fn stream_json() -> bool {
+    CONFIG.stream_json
+}
+
+/// Address (`host:port`) of a running `prusti-server`, to which the verification of the encoded
+/// programs is delegated (if not empty)
+pub fn verifier_daemon() -> String {
+    CONFIG.verifier_daemon.clone()
+}
+
+/// How the contracts are checked: `verify` (by the verifier), `runtime` (by assertions compiled
+/// into debug builds of the program) or `both`
+pub fn contract_checks() -> ContractChecks {
+    CONFIG.contract_checks
+}
+
+/// Should the contracts be checked by the verifier?
+pub fn verify_contracts() -> bool {
+    contract_checks() != ContractChecks::Runtime
+}
+
+/// Should the contracts be compiled into runtime assertions?
+pub fn runtime_contract_checks() -> bool {
+    contract_checks() != ContractChecks::Verify
+}
+
+/// Use the built-in specifications of the standard library (e.g. of `Vec`)
+pub fn builtin_specs() -> bool {
+    CONFIG.builtin_specs
+}
+
+/// Report quantifiers without valid triggers and nested quantifiers as errors instead of warnings
+pub fn strict_triggers() -> bool {
+    CONFIG.strict_triggers
+}
+
+/// Exhale the permissions of local variables when their storage dies, so that the verifier does
+/// not accumulate them in long procedures
+pub fn exhale_dead_permissions() -> bool {
+    CONFIG.exhale_dead_permissions
+}
+
+/// Export the contracts of verified libraries, and import the contracts of the dependencies
+pub fn contract_metadata() -> bool {
+    CONFIG.contract_metadata
+}
+
+/// Report the time spent in each phase of the encoding and of the verification
+pub fn profile_encoder() -> bool {
+    CONFIG.profile_encoder
+}
+
+/// How the `debug_assert!`, `debug_assert_eq!` and `debug_assert_ne!` statements are handled:
+/// `verify` (like the other assertions) or `ignore`
+pub fn debug_assertions() -> DebugAssertions {
+    CONFIG.debug_assertions
+}
+
+/// How the Viper assertions that fail are explained: `off`, `print` (on stderr) or `log` (in
+/// `log/assertion_explanations/`)
+pub fn explain_assertions() -> ExplainAssertions {
+    CONFIG.explain_assertions
+}
+
+/// The maximum number of nested predicates that the encoding can unfold to access a place, or 0
+/// to not bound the unfolding
+pub fn max_unfold_depth() -> u32 {
+    CONFIG.max_unfold_depth
+}
+
+/// Should the body of a pure method provided by a trait be verified only once, against the
+/// contract of the trait, instead of once for each implementation that does not override it?
+pub fn verify_provided_methods_once() -> bool {
+    CONFIG.verify_provided_methods_once
+}
+
+/// Should the debug assertions be verified?
+pub fn verify_debug_assertions() -> bool {
+    debug_assertions() == DebugAssertions::Verify
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flag_values() {
+        assert_eq!(" Both".parse(), Ok(ContractChecks::Both));
+        assert_eq!("IGNORE".parse(), Ok(DebugAssertions::Ignore));
+        assert_eq!("log".parse(), Ok(ExplainAssertions::Log));
+    }
+
+    #[test]
+    fn test_reject_invalid_flag_values() {
+        assert_eq!(
+            "always".parse::<ContractChecks>(),
+            Err("invalid value 'always' of CONTRACT_CHECKS, expected 'verify', 'runtime' or \
+                 'both'"
+                .to_string())
+        );
+        assert!("skip".parse::<DebugAssertions>().is_err());
+        assert!("stderr".parse::<ExplainAssertions>().is_err());
+    }
 }
@@ -8,3 +8,10 @@
 
 /// The name of the attribute that contains the id of a specification
 pub const PRUSTI_SPEC_ATTR: &str = "__PRUSTI_SPEC";
+
+/// The name of the attribute that marks a procedure whose verification should be skipped
+pub const PRUSTI_SKIP_ATTR: &str = "__PRUSTI_SKIP";
+
+/// The name of the attribute that enables (`"on"`) or disables (`"off"`) the overflow checks of
+/// the procedures of an item
+pub const PRUSTI_OVERFLOW_CHECKS_ATTR: &str = "__PRUSTI_OVERFLOW_CHECKS";
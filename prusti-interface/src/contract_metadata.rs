@@ -0,0 +1,245 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Metadata with the contracts of a verified library, which is emitted alongside the compiled
+//! library so that the crates that depend on it can be verified against its contracts, without
+//! parsing its sources again.
+//!
+//! The metadata of the crate `a` is stored in the file `a.prusti-contracts` of the output
+//! directory. Its format is an `#[extern_spec]` module (like the built-in specifications of the
+//! standard library) that declares a trusted wrapper with the contract of each exported function
+//! and inherent method of the crate:
+//!
+//! ```ignore
+//! #[extern_spec]
+//! mod __prusti_contracts_of_a {
+//!     mod a {
+//!         mod Pair {
+//!             #[trusted]
+//!             #[pure]
+//!             fn first(_self: &::a::Pair) -> i32 {
+//!                 ::a::Pair::first(_self)
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! When a crate with an `extern crate a;` item is verified, the parser looks for the metadata of
+//! `a` in the library search paths and adds the module to the crate.
+//!
+//! Generic functions and functions that return references are not exported.
+
+use regex::Regex;
+use rustc::hir;
+use rustc::hir::def_id::{DefId, LOCAL_CRATE};
+use rustc::session::search_paths::PathKind;
+use rustc::session::Session;
+use rustc::ty::{self, TyCtxt};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use syntax::print::pprust;
+
+/// The attributes of a procedure that are part of its contract.
+const CONTRACT_ATTRIBUTES: &[&str] = &["requires", "ensures", "ensures_on_panic", "pure"];
+
+/// The name of the file that contains the contracts of a crate.
+fn metadata_file_name(crate_name: &str) -> String {
+    format!("{}.prusti-contracts", crate_name)
+}
+
+/// Write the contracts of the exported procedures of the local crate in the output directory.
+pub fn export_contracts<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, out_dir: &Path) {
+    let crate_name = tcx.crate_name(LOCAL_CRATE).to_string();
+    let mut root = ModuleTree::default();
+    for &body_id in tcx.hir.krate().body_ids.iter() {
+        let def_id = tcx.hir.body_owner_def_id(body_id);
+        if let Some((path, function)) = encode_exported_contract(tcx, def_id) {
+            root.insert(&path, function);
+        }
+    }
+    let path = out_dir.join(metadata_file_name(&crate_name));
+    debug!("Exporting contracts of crate '{}' to {:?}", crate_name, path);
+    let source = format!(
+        "#[extern_spec]\n#[allow(non_snake_case, dead_code, unused_variables)]\n{}",
+        root.to_source(&format!("__prusti_contracts_of_{}", crate_name), 0)
+    );
+    if let Err(error) = fs::write(&path, source) {
+        warn!("Failed to export contracts to {:?}: {}", path, error);
+    }
+}
+
+/// Read the contracts of an external crate from the library search paths, if they have been
+/// exported.
+pub fn import_contracts(session: &Session, crate_name: &str) -> Option<String> {
+    let file_name = metadata_file_name(crate_name);
+    session
+        .opts
+        .search_paths
+        .iter(PathKind::All)
+        .map(|(dir, _)| dir.join(&file_name))
+        .filter(|path| path.exists())
+        .filter_map(|path| {
+            debug!("Importing contracts of crate '{}' from {:?}", crate_name, path);
+            fs::read_to_string(path).ok()
+        })
+        .next()
+}
+
+/// Encode the trusted wrapper that carries the contract of an exported procedure, together with
+/// the path of the module of the `#[extern_spec]` module in which it has to be declared.
+fn encode_exported_contract<'a, 'tcx>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    def_id: DefId,
+) -> Option<(Vec<String>, String)> {
+    let node_id = tcx.hir.as_local_node_id(def_id)?;
+    match tcx.hir.get(node_id) {
+        hir::map::NodeItem(&hir::Item {
+            node: hir::Item_::ItemFn(..),
+            ..
+        })
+        | hir::map::NodeImplItem(&hir::ImplItem {
+            node: hir::ImplItemKind::Method(..),
+            ..
+        }) => {}
+        _ => return None,
+    }
+    if !tcx.privacy_access_levels(LOCAL_CRATE).is_exported(node_id) {
+        return None;
+    }
+    let attrs = tcx.get_attrs(def_id);
+    let contract_attrs: Vec<String> = attrs
+        .iter()
+        .filter(|attr| CONTRACT_ATTRIBUTES.iter().any(|name| attr.check_name(name)))
+        .map(|attr| rename_self(&pprust::attribute_to_string(attr)))
+        .collect();
+    if contract_attrs.is_empty() || tcx.generics_of(def_id).requires_monomorphization(tcx) {
+        return None;
+    }
+
+    // The path of the function, as computed by `Environment::get_extern_item_path`
+    let (path, callee) = match tcx.impl_of_method(def_id) {
+        Some(impl_def_id) => {
+            if tcx.trait_id_of_impl(impl_def_id).is_some() {
+                return None;
+            }
+            match tcx.type_of(impl_def_id).sty {
+                ty::TypeVariants::TyAdt(adt_def, _) => {
+                    let adt_path = absolute_item_path(tcx, adt_def.did);
+                    let method_name = tcx.item_name(def_id).to_string();
+                    (adt_path.clone(), format!("::{}::{}", adt_path.join("::"), method_name))
+                }
+                _ => return None,
+            }
+        }
+        None => {
+            let mut fn_path = absolute_item_path(tcx, def_id);
+            let callee = format!("::{}", fn_path.join("::"));
+            fn_path.pop();
+            (fn_path, callee)
+        }
+    };
+
+    let sig = tcx.erase_late_bound_regions(&tcx.fn_sig(def_id));
+    if sig.output().walk().any(|ty| match ty.sty {
+        ty::TypeVariants::TyRef(..) => true,
+        _ => false,
+    }) {
+        return None;
+    }
+    let arg_names: Vec<String> = tcx
+        .fn_arg_names(def_id)
+        .iter()
+        .enumerate()
+        .map(|(index, name)| match &*name.as_str() {
+            "self" => "_self".to_string(),
+            "" | "_" => format!("_arg{}", index),
+            name => name.to_string(),
+        })
+        .collect();
+    let args: Vec<String> = arg_names
+        .iter()
+        .zip(sig.inputs().iter())
+        .map(|(name, ty)| format!("{}: {}", name, absolute_type(*ty)))
+        .collect();
+
+    let mut function = String::new();
+    function.push_str("#[trusted]\n");
+    for attr in contract_attrs {
+        function.push_str(&attr);
+        function.push('\n');
+    }
+    function.push_str(&format!(
+        "fn {}({}) -> {} {{\n    {}({})\n}}\n",
+        tcx.item_name(def_id),
+        args.join(", "),
+        absolute_type(sig.output()),
+        callee,
+        arg_names.join(", ")
+    ));
+    Some((path, function))
+}
+
+/// The segments of the path of an item, starting with the name of its crate.
+fn absolute_item_path<'a, 'tcx>(tcx: TyCtxt<'a, 'tcx, 'tcx>, def_id: DefId) -> Vec<String> {
+    ty::item_path::with_forced_absolute_paths(|| tcx.item_path_str(def_id))
+        .split("::")
+        .map(|segment| segment.to_string())
+        .collect()
+}
+
+/// Print a type with absolute paths that can be used in any module, e.g. `::std::vec::Vec<i32>`.
+fn absolute_type(ty: ty::Ty) -> String {
+    lazy_static! {
+        static ref PATH_START: Regex = Regex::new(r"(^|[^:\w])([A-Za-z_]\w*)::").unwrap();
+    }
+    let ty_str = ty::item_path::with_forced_absolute_paths(|| ty.to_string());
+    PATH_START.replace_all(&ty_str, "$1::$2::").to_string()
+}
+
+/// The receiver `self` of a method is an ordinary argument `_self` of its wrapper.
+fn rename_self(attribute: &str) -> String {
+    lazy_static! {
+        static ref SELF: Regex = Regex::new(r"\bself\b").unwrap();
+    }
+    SELF.replace_all(attribute, "_self").to_string()
+}
+
+/// The modules of an `#[extern_spec]` module, with the functions declared in each of them.
+#[derive(Default)]
+struct ModuleTree {
+    functions: Vec<String>,
+    modules: BTreeMap<String, ModuleTree>,
+}
+
+impl ModuleTree {
+    fn insert(&mut self, path: &[String], function: String) {
+        match path.split_first() {
+            Some((first, rest)) => self
+                .modules
+                .entry(first.clone())
+                .or_insert_with(ModuleTree::default)
+                .insert(rest, function),
+            None => self.functions.push(function),
+        }
+    }
+
+    fn to_source(&self, name: &str, depth: usize) -> String {
+        let indent = "    ".repeat(depth);
+        let mut source = format!("{}mod {} {{\n", indent, name);
+        for function in &self.functions {
+            for line in function.lines() {
+                source.push_str(&format!("{}    {}\n", indent, line));
+            }
+        }
+        for (module_name, module) in &self.modules {
+            source.push_str(&module.to_source(module_name, depth + 1));
+        }
+        source.push_str(&format!("{}}}\n", indent));
+        source
+    }
+}
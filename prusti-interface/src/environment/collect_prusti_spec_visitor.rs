@@ -47,7 +47,26 @@ impl<'r, 'a, 'tcx> ItemLikeVisitor<'tcx> for CollectPrustiSpecVisitor<'r, 'a, 't
         if let hir::Item_::ItemFn(..) = item.node {
             let def_id = self.tcx.hir.local_def_id(item.id);
             let item_def_path = self.env.get_item_def_path(def_id);
-            if !self.use_whitelist || self.whitelist.contains(&item_def_path) {
+            if self.env.is_skipped(def_id) {
+                debug!("Skip verification of item '{}': skipped by the user", item_def_path)
+            } else if self.env.is_test_harness() && attr::contains_name(&item.attrs, "main") {
+                debug!(
+                    "Skip verification of item '{}': entry point generated by the test harness",
+                    item_def_path
+                )
+            } else if attr::contains_name(&item.attrs, "should_panic")
+                || attr::contains_name(&item.attrs, "bench")
+            {
+                debug!(
+                    "Skip verification of item '{}': test that is expected to panic or benchmark",
+                    item_def_path
+                )
+            } else if self.env.get_extern_spec_path(def_id).is_some() {
+                debug!(
+                    "Skip verification of item '{}': specification of an external function",
+                    item_def_path
+                )
+            } else if !self.use_whitelist || self.whitelist.contains(&item_def_path) {
                 trace!("Add {} to result", item_def_path);
                 self.result.push(def_id);
             } else {
@@ -77,9 +96,15 @@ impl<'r, 'a, 'tcx> ItemLikeVisitor<'tcx> for CollectPrustiSpecVisitor<'r, 'a, 't
         if let hir::TraitItemKind::Method(_, hir::TraitMethod::Required(_)) = trait_item.node {
             return;
         }
+
+        // The body of a provided method is verified only here, against the contract of the
+        // trait. Implementations that do not override the method have no impl item for it, so
+        // the body is not verified again for each of them.
         let def_id = self.tcx.hir.local_def_id(trait_item.id);
         let item_def_path = self.env.get_item_def_path(def_id);
-        if !self.use_whitelist || self.whitelist.contains(&item_def_path) {
+        if self.env.is_skipped(def_id) {
+            debug!("Skip verification of trait item '{}': skipped by the user", item_def_path)
+        } else if !self.use_whitelist || self.whitelist.contains(&item_def_path) {
             trace!("Add {} to result", item_def_path);
             self.result.push(def_id);
         } else {
@@ -106,7 +131,14 @@ impl<'r, 'a, 'tcx> ItemLikeVisitor<'tcx> for CollectPrustiSpecVisitor<'r, 'a, 't
 
         let def_id = self.tcx.hir.local_def_id(impl_item.id);
         let item_def_path = self.env.get_item_def_path(def_id);
-        if !self.use_whitelist || self.whitelist.contains(&item_def_path) {
+        if self.env.is_skipped(def_id) {
+            debug!("Skip verification of impl item '{}': skipped by the user", item_def_path)
+        } else if self.env.get_extern_spec_path(def_id).is_some() {
+            debug!(
+                "Skip verification of impl item '{}': specification of an external method",
+                item_def_path
+            )
+        } else if !self.use_whitelist || self.whitelist.contains(&item_def_path) {
             trace!("Add {} to result", item_def_path);
             self.result.push(def_id);
         } else {
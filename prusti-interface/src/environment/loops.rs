@@ -143,7 +143,8 @@ impl<'b, 'tcx> Visitor<'tcx> for AccessCollector<'b, 'tcx> {
     ) {
         // TODO: using `location`, skip the places that are used for typechecking
         // because that part of the generated code contains closures.
-        if self.body.contains(&location.block) {
+        // `static` items are not modified by the loop; the encoder reads them as constants.
+        if self.body.contains(&location.block) && !utils::is_static_place(place) {
             trace!(
                 "visit_place(place={:?}, context={:?}, location={:?})",
                 place,
@@ -8,9 +8,12 @@
 
 use rustc::hir;
 use rustc::hir::def_id::DefId;
+use rustc::mir;
 use rustc::ty;
 use rustc::ty::TyCtxt;
 use rustc_driver::driver;
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::path::PathBuf;
 use syntax::attr;
 use syntax::errors::DiagnosticId;
@@ -30,8 +33,10 @@ mod procedure;
 use self::collect_prusti_spec_visitor::CollectPrustiSpecVisitor;
 pub use self::loops::{PlaceAccess, PlaceAccessKind, ProcedureLoops};
 pub use self::loops_utils::*;
-pub use self::procedure::{BasicBlockIndex, Procedure};
+pub use self::procedure::{get_panic_message_blocks, BasicBlockIndex, Procedure};
+use builtin_specs::BUILTIN_SPECS_MODULE;
 use config;
+use constants::{PRUSTI_OVERFLOW_CHECKS_ATTR, PRUSTI_SKIP_ATTR};
 use data::ProcedureDefId;
 use syntax::codemap::CodeMap;
 use syntax::codemap::Span;
@@ -136,11 +141,49 @@ impl<'r, 'a, 'tcx> Environment<'r, 'a, 'tcx> {
         diagnostic.emit();
     }
 
+    /// Emits an error message, with an optional note that points to the failing assertion and
+    /// an optional note that explains the error.
+    pub fn span_err_with_notes<S: Into<MultiSpan>>(
+        &self,
+        sp: S,
+        msg: &str,
+        reason_sp: Option<S>,
+        note: Option<&str>,
+        help: Option<&str>,
+    ) {
+        let mut diagnostic = self.state.session.struct_err(msg);
+        diagnostic.set_span(sp);
+        if let Some(reason_sp) = reason_sp {
+            diagnostic.span_note(reason_sp, "the failing assertion is this one");
+        }
+        if let Some(note) = note {
+            diagnostic.note(note);
+        }
+        if let Some(help) = help {
+            diagnostic.help(help);
+        }
+        diagnostic.emit();
+    }
+
+    /// Emits an error message, with a help message that suggests how to fix the error.
+    pub fn span_err_with_help<S: Into<MultiSpan>>(&self, sp: S, msg: &str, help: &str) {
+        let mut diagnostic = self.state.session.struct_err(msg);
+        diagnostic.set_span(sp);
+        diagnostic.help(help);
+        diagnostic.emit();
+    }
+
     /// Returns true if an error has been emitted
     pub fn has_errors(&self) -> bool {
         self.state.session.has_errors()
     }
 
+    /// Returns true if the crate is compiled with the test harness (i.e. with `--test`), so that
+    /// its `#[cfg(test)]` items and its `#[test]` functions are included
+    pub fn is_test_harness(&self) -> bool {
+        self.state.session.opts.test
+    }
+
     /// Aborts in case of error.
     pub fn abort_if_errors(&self) {
         self.state.session.abort_if_errors();
@@ -184,6 +227,270 @@ impl<'r, 'a, 'tcx> Environment<'r, 'a, 'tcx> {
         }
     }
 
+    /// Find whether the verification of the procedure has been disabled, either with the
+    /// `#[prusti::skip]` attribute or with the `CHECK_ONLY` and `SKIP` configuration options.
+    /// A path in the configuration selects a procedure or all the procedures of a module.
+    pub fn is_skipped(&self, def_id: ProcedureDefId) -> bool {
+        if self.has_attribute_name(def_id, PRUSTI_SKIP_ATTR) {
+            return true;
+        }
+        let item_name = self.get_item_name(def_id);
+        let matches = |path: &String| {
+            item_name == *path || item_name.starts_with(&format!("{}::", path))
+        };
+        let check_only = config::check_only();
+        (!check_only.is_empty() && !check_only.iter().any(&matches))
+            || config::skip().iter().any(&matches)
+    }
+
+    /// Find whether the overflow checks of the procedure have been enabled or disabled with the
+    /// `#[prusti::overflow_checks(on)]` or `#[prusti::overflow_checks(off)]` attribute, either on
+    /// the procedure itself or on the innermost enclosing item (e.g. a module) with the attribute.
+    /// Returns `None` if the global configuration applies.
+    pub fn get_overflow_checks(&self, def_id: ProcedureDefId) -> Option<bool> {
+        let tcx = self.tcx();
+        let mut opt_item = Some(def_id);
+        while let Some(item) = opt_item {
+            if let Some(value) =
+                attr::first_attr_value_str_by_name(&tcx.get_attrs(item), PRUSTI_OVERFLOW_CHECKS_ATTR)
+            {
+                return Some(value == "on");
+            }
+            opt_item = tcx.parent_def_id(item);
+        }
+        None
+    }
+
+    /// Get the path of the external function specified by a procedure declared in a module
+    /// with the `#[extern_spec]` attribute. The path is made of the names of the modules
+    /// nested in the one with the attribute, e.g. `std::cmp::max` for the procedure `max`
+    /// declared in `#[extern_spec] mod specs { mod std { mod cmp { ... } } }`. A method declared
+    /// in an `impl` block of such a module is named after the type of the block instead, e.g.
+    /// `std::vec::Vec::push` for `push` in `impl<T> Vec<T> { ... }`.
+    pub fn get_extern_spec_path(&self, def_id: ProcedureDefId) -> Option<String> {
+        let tcx = self.tcx();
+        if tcx.impl_of_method(def_id).is_some() {
+            let mut opt_parent = tcx.parent_def_id(def_id);
+            while let Some(parent) = opt_parent {
+                if attr::contains_name(&tcx.get_attrs(parent), "extern_spec") {
+                    return Some(self.get_extern_item_path(def_id));
+                }
+                opt_parent = tcx.parent_def_id(parent);
+            }
+            return None;
+        }
+        let mut segments = vec![tcx.item_name(def_id).to_string()];
+        let mut opt_parent = tcx.parent_def_id(def_id);
+        while let Some(parent) = opt_parent {
+            opt_parent = tcx.parent_def_id(parent);
+            if opt_parent.is_none() {
+                // The crate root has no name
+                break;
+            }
+            if attr::contains_name(&tcx.get_attrs(parent), "extern_spec") {
+                segments.reverse();
+                return Some(segments.join("::"));
+            }
+            segments.push(tcx.item_name(parent).to_string());
+        }
+        None
+    }
+
+    /// Get the procedures declared with `#[extern_spec]`, indexed by the path of the external
+    /// function that they specify. The specifications written in the crate take precedence over
+    /// the built-in ones.
+    pub fn get_extern_specs(&self) -> HashMap<String, ProcedureDefId> {
+        let tcx = self.tcx();
+        let krate = tcx.hir.krate();
+        let fn_ids = krate.items.iter().filter_map(|(node_id, item)| match item.node {
+            hir::Item_::ItemFn(..) => Some(*node_id),
+            _ => None,
+        });
+        let method_ids = krate.impl_items.values().filter_map(|impl_item| match impl_item.node {
+            hir::ImplItemKind::Method(..) => Some(impl_item.id),
+            _ => None,
+        });
+        let mut extern_specs = HashMap::new();
+        for node_id in fn_ids.chain(method_ids) {
+            let def_id = tcx.hir.local_def_id(node_id);
+            if let Some(path) = self.get_extern_spec_path(def_id) {
+                debug!("Procedure {:?} specifies external function '{}'", def_id, path);
+                let is_builtin = self.get_item_def_path(def_id).contains(BUILTIN_SPECS_MODULE);
+                if !is_builtin || !extern_specs.contains_key(&path) {
+                    extern_specs.insert(path, def_id);
+                }
+            }
+        }
+        extern_specs
+    }
+
+    /// Find whether `ty` implements the trait that declares the method `trait_method_def_id`
+    /// with an implementation generated by `#[derive]`.
+    pub fn has_derived_impl(&self, trait_method_def_id: DefId, ty: ty::Ty<'tcx>) -> bool {
+        let tcx = self.tcx();
+        let trait_def_id = match tcx.trait_of_item(trait_method_def_id) {
+            Some(trait_def_id) => trait_def_id,
+            None => return false,
+        };
+        let mut is_derived = false;
+        tcx.for_each_relevant_impl(trait_def_id, ty, |impl_def_id| {
+            if tcx.has_attr(impl_def_id, "automatically_derived") {
+                is_derived = true;
+            }
+        });
+        is_derived
+    }
+
+    /// Find the method declared in a trait that is implemented by the method `impl_method_def_id`
+    /// of a trait implementation.
+    pub fn get_implemented_trait_method(&self, impl_method_def_id: DefId) -> Option<DefId> {
+        let tcx = self.tcx();
+        let impl_def_id = tcx.impl_of_method(impl_method_def_id)?;
+        let trait_def_id = tcx.trait_id_of_impl(impl_def_id)?;
+        let method_name = tcx.item_name(impl_method_def_id).to_string();
+        tcx.associated_items(trait_def_id)
+            .find(|assoc_item| assoc_item.name == method_name)
+            .map(|assoc_item| assoc_item.def_id)
+    }
+
+    /// Find the method `method_name` of the implementation of a trait for the type `ty`, among
+    /// the implementations of the current crate. The trait is identified by its possible paths,
+    /// e.g. `std::cmp::Ord` and `core::cmp::Ord`.
+    pub fn find_local_trait_impl_method(
+        &self,
+        trait_paths: &[&str],
+        method_name: &str,
+        ty: ty::Ty<'tcx>,
+    ) -> Option<DefId> {
+        let tcx = self.tcx();
+        let trait_def_id = tcx
+            .hir
+            .krate()
+            .trait_impls
+            .keys()
+            .cloned()
+            .find(|&trait_def_id| trait_paths.contains(&tcx.item_path_str(trait_def_id).as_str()))?;
+        let mut method_def_id = None;
+        tcx.for_each_relevant_impl(trait_def_id, ty, |impl_def_id| {
+            if impl_def_id.is_local() && method_def_id.is_none() {
+                method_def_id = tcx
+                    .associated_items(impl_def_id)
+                    .find(|assoc_item| assoc_item.name == method_name)
+                    .map(|assoc_item| assoc_item.def_id);
+            }
+        });
+        method_def_id
+    }
+
+    /// Get the path that an `#[extern_spec]` module uses for an external function. The methods
+    /// of implementations (inherent or of a trait) are named after the type that they are
+    /// implemented for, e.g. `std::vec::Vec::push`, `std::vec::Vec::index`, `str::len` and
+    /// `slice::iter`.
+    pub fn get_extern_item_path(&self, def_id: DefId) -> String {
+        let tcx = self.tcx();
+        if let Some(impl_def_id) = tcx.impl_of_method(def_id) {
+            match tcx.type_of(impl_def_id).sty {
+                ty::TypeVariants::TyAdt(adt_def, _) => {
+                    return format!(
+                        "{}::{}",
+                        tcx.item_path_str(adt_def.did),
+                        tcx.item_name(def_id)
+                    );
+                }
+                ty::TypeVariants::TyStr => return format!("str::{}", tcx.item_name(def_id)),
+                ty::TypeVariants::TySlice(_) => {
+                    return format!("slice::{}", tcx.item_name(def_id));
+                }
+                _ => {}
+            }
+        }
+        tcx.item_path_str(def_id)
+    }
+
+    /// Find the implementation of a trait method that is called with the given substitutions,
+    /// if it can be determined statically.
+    pub fn resolve_method_call(
+        &self,
+        def_id: DefId,
+        substs: &'tcx ty::subst::Substs<'tcx>,
+    ) -> Option<(DefId, &'tcx ty::subst::Substs<'tcx>)> {
+        let tcx = self.tcx();
+        tcx.trait_of_item(def_id)?;
+        ty::Instance::resolve(tcx, ty::ParamEnv::reveal_all(), def_id, substs)
+            .map(|instance| (instance.def_id(), instance.substs))
+    }
+
+    /// Get the procedures of the crate that are reachable from `entries` through calls, including
+    /// the entries themselves. Only the calls in the bodies of `procedures` are followed, because
+    /// the other procedures (e.g. trusted ones) are not verified. The callees come before their
+    /// callers, except in cycles of recursive calls.
+    pub fn get_reachable_procedures(
+        &self,
+        entries: &[ProcedureDefId],
+        procedures: &[ProcedureDefId],
+    ) -> Vec<ProcedureDefId> {
+        let mut visited = HashSet::new();
+        let mut reachable = vec![];
+        for &entry in entries {
+            self.collect_reachable_procedures(entry, procedures, &mut visited, &mut reachable);
+        }
+        reachable
+    }
+
+    fn collect_reachable_procedures(
+        &self,
+        def_id: ProcedureDefId,
+        procedures: &[ProcedureDefId],
+        visited: &mut HashSet<ProcedureDefId>,
+        reachable: &mut Vec<ProcedureDefId>,
+    ) {
+        if !def_id.is_local() || !visited.insert(def_id) {
+            return;
+        }
+        if procedures.contains(&def_id) {
+            for callee in self.get_called_procedures(def_id) {
+                self.collect_reachable_procedures(callee, procedures, visited, reachable);
+            }
+        }
+        reachable.push(def_id);
+    }
+
+    /// Get the procedures that are called in the body of a procedure. The calls of trait methods
+    /// are resolved to the implementation, if it can be determined statically.
+    pub fn get_called_procedures(&self, def_id: ProcedureDefId) -> Vec<ProcedureDefId> {
+        let tcx = self.tcx();
+        let mir = tcx.mir_validated(def_id).borrow();
+        mir.basic_blocks()
+            .iter()
+            .filter_map(|bb_data| match bb_data.terminator().kind {
+                mir::TerminatorKind::Call {
+                    func:
+                        mir::Operand::Constant(box mir::Constant {
+                            literal:
+                                mir::Literal::Value {
+                                    value:
+                                        ty::Const {
+                                            ty:
+                                                &ty::TyS {
+                                                    sty: ty::TyFnDef(callee_def_id, substs),
+                                                    ..
+                                                },
+                                            ..
+                                        },
+                                },
+                            ..
+                        }),
+                    ..
+                } => Some(
+                    self.resolve_method_call(callee_def_id, substs)
+                        .map(|(resolved_def_id, _)| resolved_def_id)
+                        .unwrap_or(callee_def_id),
+                ),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Dump various information from the borrow checker.
     ///
     /// Mostly used for experiments and debugging.
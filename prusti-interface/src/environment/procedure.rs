@@ -26,6 +26,7 @@ pub struct Procedure<'a, 'tcx: 'a> {
     mir: Ref<'a, Mir<'tcx>>,
     reachable_basic_blocks: HashSet<BasicBlock>,
     nonspec_basic_blocks: HashSet<BasicBlock>,
+    panic_message_blocks: HashSet<BasicBlock>,
     predecessors: HashMap<BasicBlockIndex, HashSet<BasicBlockIndex>>,
     ordered_basic_blocks: Vec<BasicBlockIndex>,
 }
@@ -37,6 +38,7 @@ impl<'a, 'tcx> Procedure<'a, 'tcx> {
         let mir = tcx.mir_validated(proc_def_id).borrow();
         let reachable_basic_blocks = build_reachable_basic_blocks(&mir);
         let nonspec_basic_blocks = build_nonspec_basic_blocks(&mir);
+        let panic_message_blocks = get_panic_message_blocks(tcx, &mir);
 
         let mut predecessors = HashMap::new();
         for bbi in mir.basic_blocks().indices() {
@@ -58,6 +60,7 @@ impl<'a, 'tcx> Procedure<'a, 'tcx> {
             mir,
             reachable_basic_blocks,
             nonspec_basic_blocks,
+            panic_message_blocks,
             predecessors,
             ordered_basic_blocks,
         }
@@ -217,35 +220,15 @@ impl<'a, 'tcx> Procedure<'a, 'tcx> {
         self.reachable_basic_blocks.contains(&bbi)
     }
 
+    /// Check whether the block calls `begin_panic` or `begin_panic_fmt`
     pub fn is_panic_block(&self, bbi: BasicBlockIndex) -> bool {
-        if let TerminatorKind::Call {
-            args: ref _args,
-            destination: ref _destination,
-            func:
-                mir::Operand::Constant(box mir::Constant {
-                    literal:
-                        mir::Literal::Value {
-                            value:
-                                ty::Const {
-                                    ty:
-                                        &ty::TyS {
-                                            sty: ty::TyFnDef(def_id, ..),
-                                            ..
-                                        },
-                                    ..
-                                },
-                        },
-                    ..
-                }),
-            ..
-        } = self.mir[bbi].terminator.as_ref().unwrap().kind
-        {
-            let func_proc_name = self.tcx.absolute_item_path_str(def_id);
-            &func_proc_name == "std::panicking::begin_panic"
-                || &func_proc_name == "std::rt::begin_panic"
-        } else {
-            false
-        }
+        is_panic_block(self.tcx, &self.mir, bbi)
+    }
+
+    /// Check whether the block only computes the message of a panic (e.g. the formatted message
+    /// of an `assert_eq!`)
+    pub fn is_panic_message_block(&self, bbi: BasicBlockIndex) -> bool {
+        self.panic_message_blocks.contains(&bbi)
     }
 
     pub fn successors(&self, bbi: BasicBlockIndex) -> Vec<BasicBlockIndex> {
@@ -253,6 +236,96 @@ impl<'a, 'tcx> Procedure<'a, 'tcx> {
     }
 }
 
+/// Returns the name of the function called by the terminator, if any
+fn get_called_function_name<'a, 'tcx>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    terminator: &Terminator<'tcx>,
+) -> Option<String> {
+    if let TerminatorKind::Call {
+        func:
+            mir::Operand::Constant(box mir::Constant {
+                literal:
+                    mir::Literal::Value {
+                        value:
+                            ty::Const {
+                                ty:
+                                    &ty::TyS {
+                                        sty: ty::TyFnDef(def_id, ..),
+                                        ..
+                                    },
+                                ..
+                            },
+                    },
+                ..
+            }),
+        ..
+    } = terminator.kind
+    {
+        Some(tcx.absolute_item_path_str(def_id))
+    } else {
+        None
+    }
+}
+
+fn is_panic_block<'a, 'tcx>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    mir: &Mir<'tcx>,
+    bbi: BasicBlockIndex,
+) -> bool {
+    match get_called_function_name(tcx, mir[bbi].terminator()) {
+        Some(ref func_proc_name) => match func_proc_name.as_str() {
+            "std::rt::begin_panic"
+            | "std::panicking::begin_panic"
+            | "std::rt::begin_panic_fmt"
+            | "std::panicking::begin_panic_fmt" => true,
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+/// Returns the set of basic blocks that only compute the message of a panic, i.e. the blocks
+/// that unconditionally lead to a panic block and that only call formatting functions.
+pub fn get_panic_message_blocks<'a, 'tcx>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    mir: &Mir<'tcx>,
+) -> HashSet<BasicBlock> {
+    let is_formatting_call = |terminator: &Terminator<'tcx>| {
+        get_called_function_name(tcx, terminator).map_or(false, |name| {
+            name.starts_with("std::fmt::")
+                || name.starts_with("<std::fmt::")
+                || name.starts_with("core::fmt::")
+                || name.starts_with("<core::fmt::")
+        })
+    };
+
+    let mut panic_message_blocks: HashSet<BasicBlock> = HashSet::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for bbi in mir.basic_blocks().indices() {
+            if panic_message_blocks.contains(&bbi) {
+                continue;
+            }
+            let terminator = mir[bbi].terminator();
+            let target = match terminator.kind {
+                TerminatorKind::Goto { target } => target,
+                TerminatorKind::Call {
+                    destination: Some((_, target)),
+                    ..
+                } if is_formatting_call(terminator) => target,
+                _ => continue,
+            };
+            if panic_message_blocks.contains(&target) || is_panic_block(tcx, mir, target) {
+                panic_message_blocks.insert(bbi);
+                changed = true;
+            }
+        }
+    }
+
+    panic_message_blocks
+}
+
 fn get_normal_targets(terminator: &Terminator) -> Vec<BasicBlock> {
     match terminator.kind {
         TerminatorKind::Goto { ref target } | TerminatorKind::Assert { ref target, .. } => {
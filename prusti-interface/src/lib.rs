@@ -43,8 +43,10 @@ extern crate syntax_pos;
 extern crate lazy_static;
 
 pub mod ast_builder;
+mod builtin_specs;
 pub mod config;
 pub mod constants;
+pub mod contract_metadata;
 pub mod data;
 pub mod environment;
 pub mod parser;
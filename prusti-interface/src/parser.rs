@@ -29,6 +29,13 @@
 //! }
 //! ```
 //!
+//! A precondition or postcondition can be given a reason, which is
+//! reported when the specification does not hold:
+//!
+//! ```rust,ignore
+//! #[requires(reason = "index must be within bounds", "i < self.len()")]
+//! ```
+//!
 //! The current version of the tool support the following assertion
 //! syntax:
 //!
@@ -36,10 +43,41 @@
 //! assertion := assertion && assertion
 //!            | expression ==> assertion
 //!            | (forall variable_name :: {expression} expression ==> expression)
+//!            | (exists variable_name :: {expression} expression)
 //! ```
 //!
 //! Here `expression` is a Rust expression that contains only elements
 //! that are considered expressions in Viper, plus `match` expressions.
+//!
+//! A specification can also be written as Rust tokens instead of a
+//! string, using `forall(|vars| ..)` and `exists(|vars| ..)` for
+//! quantifiers and `implies(a, b)` for implications (the `!` of `forall!`,
+//! `exists!` and `implies!` is optional):
+//!
+//! ```rust,ignore
+//! #[requires(n > 0 && forall(|i: usize| i < n ==> a[i] > 0))]
+//! ```
+//!
+//! The tokens are converted to the equivalent specification string, which
+//! is then parsed and type-checked like any other specification.
+//!
+//! A specification can be prefixed with a typestate condition
+//! `Type == Type ~~> assertion`, typically comparing a generic parameter
+//! with a concrete type (e.g. `#[invariant="S == Even ~~> self.i % 2 == 0"]`).
+//! The assertion is then required only for the instantiations of the
+//! generic parameters that satisfy the condition.
+//!
+//! A struct can also have history invariants, which may use `old(..)`
+//! (e.g. `#[history_invariant="self.len() >= old(self.len())"]`).
+//! They are added to the postcondition of every method of the struct
+//! that takes `&mut self`, in whichever module the method is implemented.
+//!
+//! A loop invariant can also be written anywhere in the body of the loop
+//! as `body_invariant!(..)`, with the syntax of the token specifications.
+//! All the invariants of a loop are anchored at the loop head, where they
+//! are checked like the `#[invariant]` attributes, wherever they are
+//! written in the body (except in nested loops and closures).
+//!
 //! The parsed specification is stored in the structure
 //! `specifications::UntypedSpecification` and type-checked
 //! specification is stored in the structure
@@ -58,8 +96,9 @@
 //!
 //! 2.  When the `after_parse` callback is invoked:
 //!
-//!     1.  Register attributes `requires`, `ensures`, `invariant`,
-//!         `__PRUSTI_SPEC_ONLY`, and `__PRUSTI_SPEC` to avoid the
+//!     1.  Register attributes `requires`, `ensures`, `ensures_on_panic`,
+//!         `after_expiry`, `assert_on_expiry`, `invariant`, `__PRUSTI_SPEC_ONLY`, and
+//!         `__PRUSTI_SPEC` to avoid the
 //!         annoying warning about unknown attributes.
 //!     2.  Collect all specification attributes.
 //!     3.  Construct `UntypedSpecification` objects by parsing the
@@ -127,9 +166,23 @@
 //!
 //! Note: AST/HIR nodes are linked to assertions by specification
 //! identifier that is stored as a ``__PRUSTI_SPEC`` attribute.
+//!
+//! # Runtime checks
+//!
+//! With `CONTRACT_CHECKS=runtime` (or `both`), the preconditions and
+//! postconditions are also compiled into the body of the function, as
+//! calls to `prusti_contracts::internal::check_contract` that are enabled
+//! only in debug builds (like `debug_assert!`). For example, the body of
+//! `fib` would start with
+//! `#[cfg(debug_assertions)] ::prusti_contracts::internal::check_contract((0 < n) && (n < 10), "..");`.
+//! Assertions that cannot be evaluated at runtime (quantifiers, pledges,
+//! typestate conditions and `old(..)` expressions) are not checked.
 
 use ast_builder::MinimalAstBuilder;
-use constants::PRUSTI_SPEC_ATTR;
+use builtin_specs::BUILTIN_SPECS;
+use config;
+use constants::{PRUSTI_OVERFLOW_CHECKS_ATTR, PRUSTI_SKIP_ATTR, PRUSTI_SPEC_ATTR};
+use contract_metadata;
 use regex::{self, Regex};
 use report::log;
 use rustc::session::Session;
@@ -139,7 +192,7 @@ use specifications::{
     SpecificationSet, Trigger, TriggerSet, UntypedAssertion, UntypedExpression,
     UntypedSpecification, UntypedSpecificationMap, UntypedSpecificationSet, UntypedTriggerSet,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::io::Write;
 use std::mem;
@@ -148,14 +201,21 @@ use syntax::codemap::Span;
 use syntax::ext::build::AstBuilder;
 use syntax::feature_gate::AttributeType;
 use syntax::fold::{self, Folder};
+use syntax::symbol::{keywords, Symbol};
 use syntax::util::small_vector::SmallVector;
+use syntax::visit::{self, Visitor};
 use syntax::{self, ast, parse, ptr};
 use syntax_pos::DUMMY_SP;
 use syntax_pos::{BytePos, FileName, SyntaxContext};
 
 /// Rewrite specifications in the expanded AST to get them type-checked
 /// by rustc. For more information see the module documentation.
-pub fn rewrite_crate(state: &mut driver::CompileState) -> UntypedSpecificationMap {
+///
+/// Also returns the warnings about specifications that should be reported if the crate
+/// type-checks.
+pub fn rewrite_crate(
+    state: &mut driver::CompileState,
+) -> (UntypedSpecificationMap, Vec<SpecificationWarning>) {
     trace!("[rewrite_crate] enter");
     let krate = state.krate.take().unwrap();
     let source_path = match driver::source_name(state.input) {
@@ -168,7 +228,21 @@ pub fn rewrite_crate(state: &mut driver::CompileState) -> UntypedSpecificationMa
     log_crate(&krate, &source_filename);
     state.krate = Some(krate);
     trace!("[rewrite_crate] exit");
-    parser.untyped_specifications
+    (parser.untyped_specifications, parser.warnings)
+}
+
+/// A warning about a specification that is useless, for example because it is trivially true.
+#[derive(Debug)]
+pub struct SpecificationWarning {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Report the warnings about specifications through the compiler diagnostics.
+pub fn report_specification_warnings(session: &Session, warnings: Vec<SpecificationWarning>) {
+    for warning in warnings {
+        session.span_warn(warning.span, &warning.message);
+    }
 }
 
 /// Register attributes in whitelist
@@ -176,11 +250,30 @@ pub fn register_attributes(state: &mut driver::CompileState) {
     trace!("[register_attributes] enter");
     let registry = state.registry.as_mut().unwrap();
     registry.register_attribute(String::from("trusted"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("trusted_ffi"), AttributeType::Whitelisted);
     registry.register_attribute(String::from("pure"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("duplicable_reads"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("decreases"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("model"), AttributeType::Whitelisted);
     registry.register_attribute(String::from("invariant"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("open_invariant"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("history_invariant"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("extern_spec"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("viper_domain"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("viper_axiom"), AttributeType::Whitelisted);
     registry.register_attribute(String::from("requires"), AttributeType::Whitelisted);
     registry.register_attribute(String::from("ensures"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("ensures_on_panic"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("refine_requires"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("refine_ensures"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("after_expiry"), AttributeType::Whitelisted);
+    registry.register_attribute(String::from("assert_on_expiry"), AttributeType::Whitelisted);
     registry.register_attribute(PRUSTI_SPEC_ATTR.to_string(), AttributeType::Whitelisted);
+    registry.register_attribute(PRUSTI_SKIP_ATTR.to_string(), AttributeType::Whitelisted);
+    registry.register_attribute(
+        PRUSTI_OVERFLOW_CHECKS_ATTR.to_string(),
+        AttributeType::Whitelisted,
+    );
     registry.register_attribute(
         String::from("__PRUSTI_SPEC_ONLY"),
         AttributeType::Whitelisted,
@@ -221,7 +314,12 @@ pub struct SpecParser<'tcx> {
     last_specification_id: SpecID,
     last_expression_id: ExpressionId,
     untyped_specifications: UntypedSpecificationMap,
+    warnings: Vec<SpecificationWarning>,
     rust_program_before_typechecking_writer: Box<Write>,
+    /// Are the items being rewritten in an `#[extern_spec]` module?
+    in_extern_spec_module: bool,
+    /// The number of `impl` blocks of `#[extern_spec]` modules rewritten so far
+    extern_spec_impl_count: u32,
 }
 
 impl<'tcx> SpecParser<'tcx> {
@@ -233,12 +331,15 @@ impl<'tcx> SpecParser<'tcx> {
             last_specification_id: SpecID::new(),
             last_expression_id: ExpressionId::new(),
             untyped_specifications: HashMap::new(),
+            warnings: vec![],
             rust_program_before_typechecking_writer: log::build_writer(
                 "rust_program_before_typechecking",
                 source_filename,
             )
             .ok()
             .unwrap(),
+            in_extern_spec_module: false,
+            extern_spec_impl_count: 0,
         }
     }
 
@@ -275,6 +376,98 @@ impl<'tcx> SpecParser<'tcx> {
         err.emit();
     }
 
+    fn report_warning(&mut self, span: Span, message: &str) {
+        self.warnings.push(SpecificationWarning {
+            span,
+            message: message.to_string(),
+        });
+    }
+
+    /// Warn about specifications that are trivially true, and thus have no effect.
+    fn check_trivial_specs(&mut self, specs: &[UntypedSpecification]) {
+        for spec in specs {
+            if spec.typ == SpecType::PanicPostcondition {
+                // `#[ensures_on_panic="true"]` declares that the procedure may panic
+                continue;
+            }
+            if spec.assertion.is_trivially_true() {
+                let spec_name = match spec.typ {
+                    SpecType::Precondition => "precondition",
+                    SpecType::Postcondition => "postcondition",
+                    SpecType::PanicPostcondition => unreachable!(),
+                    SpecType::Invariant => "invariant",
+                    SpecType::HistoryInvariant => "history invariant",
+                };
+                let span = get_exprs_span(&spec.assertion.get_exprs());
+                self.report_warning(
+                    span,
+                    &format!("this {} is trivially true and has no effect", spec_name),
+                );
+            }
+        }
+    }
+
+    /// Report panic postconditions that mention the result, which does not exist when the
+    /// procedure panics.
+    fn check_panic_postconditions(&mut self, specs: &[UntypedSpecification]) {
+        let result_name = Symbol::intern("result");
+        for spec in specs {
+            if spec.typ != SpecType::PanicPostcondition {
+                continue;
+            }
+            let exprs = spec.assertion.get_exprs();
+            let mut mentioned_collector = MentionedVariablesCollector::default();
+            for expr in &exprs {
+                mentioned_collector.visit_expr(expr);
+            }
+            if mentioned_collector.names.contains(&result_name) {
+                self.report_error(
+                    get_exprs_span(&exprs),
+                    "a panic postcondition cannot refer to `result`",
+                );
+            }
+        }
+    }
+
+    /// Warn about loop invariants that do not mention any variable modified by the loop.
+    fn check_loop_invariants(&mut self, loop_expr: &ast::Expr, invariants: &[UntypedSpecification]) {
+        let mut modified_collector = ModifiedVariablesCollector::default();
+        match loop_expr.node {
+            ast::ExprKind::WhileLet(ref pats, _, ref block, _) => {
+                for pat in pats {
+                    modified_collector.visit_pat(pat);
+                }
+                modified_collector.visit_block(block);
+            }
+            ast::ExprKind::ForLoop(ref pat, _, ref block, _) => {
+                modified_collector.visit_pat(pat);
+                modified_collector.visit_block(block);
+            }
+            ast::ExprKind::While(_, ref block, _) | ast::ExprKind::Loop(ref block, _) => {
+                modified_collector.visit_block(block);
+            }
+            _ => unreachable!(),
+        }
+        for invariant in invariants {
+            if invariant.assertion.is_trivially_true() {
+                // Already reported
+                continue;
+            }
+            let mut mentioned_collector = MentionedVariablesCollector::default();
+            let exprs = invariant.assertion.get_exprs();
+            for expr in &exprs {
+                mentioned_collector.visit_expr(expr);
+            }
+            if mentioned_collector.names.is_disjoint(&modified_collector.names) {
+                let span = get_exprs_span(&exprs);
+                self.report_warning(
+                    span,
+                    "this loop invariant does not mention any variable modified by the loop",
+                );
+            }
+        }
+    }
+
     /// Construct a lambda function with an attribute that identifies the spec id of the loop
     fn build_loop_mark(&self, spec_id: SpecID) -> ast::Stmt {
         let builder = &self.ast_builder;
@@ -392,7 +585,8 @@ impl<'tcx> SpecParser<'tcx> {
                 self.populate_statements(assertion, statements);
             }
             // encode generics typecond as forallvargs (hack!)
-            AssertionKind::ForAll(ref vars, ref trigger_set, ref body) => {
+            AssertionKind::ForAll(ref vars, ref trigger_set, ref body)
+            | AssertionKind::Exists(ref vars, ref trigger_set, ref body) => {
                 let mut stmts = self.convert_trigger_set_to_statements(trigger_set);
                 self.populate_statements(body, &mut stmts);
                 let builder = &self.ast_builder;
@@ -460,6 +654,206 @@ impl<'tcx> SpecParser<'tcx> {
         builder.stmt_item(span, ptr::P(item))
     }
 
+    /// Build a boolean Rust expression that evaluates the assertion at runtime. Returns `None` if
+    /// the assertion cannot be evaluated at runtime.
+    fn build_runtime_assertion(&self, assertion: &UntypedAssertion) -> Option<ptr::P<ast::Expr>> {
+        let builder = &self.ast_builder;
+        match *assertion.kind {
+            AssertionKind::Expr(ref expression) => self.build_runtime_expression(expression),
+            AssertionKind::And(ref assertions) => {
+                let mut conjunction: Option<ptr::P<ast::Expr>> = None;
+                for assertion in assertions {
+                    let conjunct = self.build_runtime_assertion(assertion)?;
+                    conjunction = Some(match conjunction {
+                        Some(lhs) => {
+                            let span = lhs.span.to(conjunct.span);
+                            builder.expr_binary(span, ast::BinOpKind::And, lhs, conjunct)
+                        }
+                        None => conjunct,
+                    });
+                }
+                Some(conjunction.unwrap_or_else(|| builder.expr_bool(DUMMY_SP, true)))
+            }
+            AssertionKind::Implies(ref lhs, ref rhs) => {
+                let lhs = self.build_runtime_expression(lhs)?;
+                let rhs = self.build_runtime_assertion(rhs)?;
+                let span = lhs.span.to(rhs.span);
+                let not_lhs = builder.expr_unary(lhs.span, ast::UnOp::Not, lhs);
+                Some(builder.expr_binary(span, ast::BinOpKind::Or, not_lhs, rhs))
+            }
+            AssertionKind::TypeCond(..)
+            | AssertionKind::ForAll(..)
+            | AssertionKind::Exists(..)
+            | AssertionKind::Pledge(..) => None,
+        }
+    }
+
+    fn build_runtime_expression(
+        &self,
+        expression: &UntypedExpression,
+    ) -> Option<ptr::P<ast::Expr>> {
+        let mut old_collector = OldExpressionsCollector::default();
+        old_collector.visit_expr(&expression.expr);
+        if old_collector.found {
+            return None;
+        }
+        let expr = expression.expr.clone();
+        Some(self.ast_builder.expr(expr.span, ast::ExprKind::Paren(expr)))
+    }
+
+    /// Build the statements that check the specifications at runtime, in debug builds.
+    /// The specifications that mention one of the `unavailable` variables are not checked.
+    fn build_runtime_checks(
+        &self,
+        specifications: &[UntypedSpecification],
+        unavailable: &HashSet<ast::Name>,
+    ) -> Vec<ast::Stmt> {
+        let builder = &self.ast_builder;
+        let mut statements = vec![];
+        for specification in specifications {
+            if specification.typ == SpecType::PanicPostcondition {
+                // The function returns normally, so it does not panic.
+                continue;
+            }
+            let exprs = specification.assertion.get_exprs();
+            if exprs.is_empty() {
+                continue;
+            }
+            let mut mentioned_collector = MentionedVariablesCollector::default();
+            for expr in &exprs {
+                mentioned_collector.visit_expr(expr);
+            }
+            if !mentioned_collector.names.is_disjoint(unavailable) {
+                debug!("Cannot check at runtime {:?}", specification.assertion);
+                continue;
+            }
+            let check = match self.build_runtime_assertion(&specification.assertion) {
+                Some(check) => check,
+                None => {
+                    debug!("Cannot check at runtime {:?}", specification.assertion);
+                    continue;
+                }
+            };
+            let spec_name = match specification.typ {
+                SpecType::Precondition => "precondition",
+                SpecType::Postcondition => "postcondition",
+                _ => unreachable!(),
+            };
+            let message = format!(
+                "[Prusti: runtime check] the {} does not hold: {}",
+                spec_name,
+                syntax::print::pprust::expr_to_string(&check)
+            );
+            let span = get_exprs_span(&exprs);
+            let path = ["prusti_contracts", "internal", "check_contract"]
+                .iter()
+                .map(|name| builder.ident_of(name))
+                .collect();
+            let mut call = builder
+                .expr_call_global(
+                    span,
+                    path,
+                    vec![check, builder.expr_str(span, Symbol::intern(&message))],
+                )
+                .into_inner();
+            call.attrs = vec![builder.attribute_cfg(span, "debug_assertions")].into();
+            statements.push(builder.stmt_semi(ptr::P(call)));
+        }
+        statements
+    }
+
+    /// Add to the body of a function the runtime checks of its contract. The preconditions are
+    /// checked at the beginning of the body, the postconditions before each `return` and at the
+    /// end of the body.
+    ///
+    /// A postcondition refers to the values of the arguments at the beginning of the call, so it
+    /// is not checked if it mentions an argument that the body might move, modify or shadow.
+    fn add_runtime_checks(
+        &self,
+        decl: &ast::FnDecl,
+        body: ptr::P<ast::Block>,
+        preconditions: &[UntypedSpecification],
+        postconditions: &[UntypedSpecification],
+    ) -> ptr::P<ast::Block> {
+        let builder = &self.ast_builder;
+        let mut bound_collector = BoundVariablesCollector::default();
+        for arg in &decl.inputs {
+            let is_available = match arg.pat.node {
+                ast::PatKind::Ident(
+                    ast::BindingMode::ByValue(ast::Mutability::Immutable),
+                    _,
+                    None,
+                ) => is_copy_or_reference(&arg.ty),
+                _ => false,
+            };
+            if !is_available {
+                bound_collector.visit_pat(&arg.pat);
+            }
+        }
+        bound_collector.visit_block(&body);
+
+        let precondition_checks = self.build_runtime_checks(preconditions, &HashSet::new());
+        let unit_ty = builder.ty(body.span, ast::TyKind::Tup(vec![]));
+        let (result_ty, returns_unit, returns_never) = match decl.output {
+            ast::FunctionRetTy::Default(_) => (Some(unit_ty), true, false),
+            ast::FunctionRetTy::Ty(ref ty) => match ty.node {
+                ast::TyKind::Tup(ref tys) if tys.is_empty() => (Some(ty.clone()), true, false),
+                ast::TyKind::Never => (None, false, true),
+                ast::TyKind::ImplTrait(..) => (None, false, false),
+                _ => (Some(ty.clone()), false, false),
+            },
+        };
+        let postcondition_checks = if returns_never {
+            vec![]
+        } else {
+            self.build_runtime_checks(postconditions, &bound_collector.names)
+        };
+
+        let mut body = body;
+        if !postcondition_checks.is_empty() {
+            let mut rewriter = ReturnRewriter {
+                ast_builder: builder,
+                checks: postcondition_checks,
+                result_ty,
+            };
+            body = rewriter.fold_block(body).map(|mut block| {
+                let has_tail = match block.stmts.last() {
+                    Some(stmt) => match stmt.node {
+                        ast::StmtKind::Expr(_) => true,
+                        ast::StmtKind::Mac(ref mac) => mac.1 == ast::MacStmtStyle::NoBraces,
+                        _ => false,
+                    },
+                    None => false,
+                };
+                if has_tail {
+                    let stmt = block.stmts.pop().unwrap();
+                    let tail = match stmt.node {
+                        ast::StmtKind::Expr(expr) => expr,
+                        ast::StmtKind::Mac(mac) => {
+                            let (mac, _, attrs) = mac.into_inner();
+                            let mut expr =
+                                builder.expr(stmt.span, ast::ExprKind::Mac(mac)).into_inner();
+                            expr.attrs = attrs;
+                            ptr::P(expr)
+                        }
+                        _ => unreachable!(),
+                    };
+                    block.stmts.push(builder.stmt_expr(rewriter.check_result(tail)));
+                } else if returns_unit {
+                    let unit = builder.expr_tuple(block.span, vec![]);
+                    block.stmts.push(builder.stmt_semi(rewriter.check_result(unit)));
+                }
+                block
+            });
+        }
+        body.map(|mut block| {
+            let mut stmts = precondition_checks;
+            stmts.extend(block.stmts);
+            block.stmts = stmts;
+            block
+        })
+    }
+
     /// Generate a function that contains only the precondition and postcondition
     /// for type-checking.
     fn generate_spec_item(
@@ -469,77 +863,96 @@ impl<'tcx> SpecParser<'tcx> {
         preconditions: &[UntypedSpecification],
         postconditions: &[UntypedSpecification],
     ) -> ast::Item {
-        let mut name = item.ident.to_string();
         match item.node {
-            ast::ItemKind::Fn(ref decl, ref _header, ref generics, ref _body) => {
-                let mut statements = vec![];
-
-                // Add preconditions.
-                statements.extend(self.convert_to_statements(preconditions));
-
-                // Add postconditions.
-                statements.extend(self.convert_to_statements(postconditions));
-
-                // Import contracts, if needed
-                if !statements.is_empty() {
-                    statements.insert(0, self.build_prusti_contract_import(item.span));
-                }
-
-                // Add result to arguments
-                let unit_type = self.ast_builder.ty(item.span, ast::TyKind::Tup(Vec::new()));
-                let return_type = match decl.output.clone() {
-                    ast::FunctionRetTy::Ty(ret_ty) => match ret_ty.node {
-                        ast::TyKind::Never => unit_type.clone(),
-                        _ => ret_ty,
-                    },
-                    ast::FunctionRetTy::Default(_) => unit_type.clone(),
-                };
-                let mut inputs_with_result: Vec<ast::Arg> = decl.inputs.clone();
-                inputs_with_result.push(self.ast_builder.arg(
+            ast::ItemKind::Fn(ref decl, ref _header, ref generics, ref _body) => self
+                .generate_spec_fn(
+                    item.ident,
                     item.span,
-                    ast::Ident::from_str("result"),
-                    return_type.clone(),
-                ));
-
-                // Glue everything.
-                name.push_str("__spec");
-                let mut spec_item = self
-                    .ast_builder
-                    .item_fn_poly(
-                        item.span,
-                        ast::Ident::from_str(&name),
-                        inputs_with_result,
-                        unit_type,
-                        generics.clone(),
-                        self.ast_builder.block(item.span, statements),
-                    )
-                    .into_inner();
-                mem::replace(
-                    &mut spec_item.attrs,
-                    vec![
-                        self.ast_builder.attribute_name_value(
-                            item.span,
-                            "__PRUSTI_SPEC_ONLY",
-                            &spec_id.to_string(),
-                        ),
-                        self.ast_builder.attribute_allow(item.span, "unused_mut"),
-                        self.ast_builder.attribute_allow(item.span, "dead_code"),
-                        self.ast_builder
-                            .attribute_allow(item.span, "non_snake_case"),
-                        self.ast_builder
-                            .attribute_allow(item.span, "unused_imports"),
-                        self.ast_builder
-                            .attribute_allow(item.span, "unused_variables"),
-                    ],
-                );
-                spec_item
-            }
+                    decl,
+                    generics,
+                    spec_id,
+                    preconditions,
+                    postconditions,
+                ),
             _ => {
                 unreachable!();
             }
         }
     }
 
+    /// Generate the function that type-checks the precondition and postcondition of a function
+    /// with the given signature.
+    fn generate_spec_fn(
+        &mut self,
+        ident: ast::Ident,
+        span: Span,
+        decl: &ast::FnDecl,
+        generics: &ast::Generics,
+        spec_id: SpecID,
+        preconditions: &[UntypedSpecification],
+        postconditions: &[UntypedSpecification],
+    ) -> ast::Item {
+        let mut name = ident.to_string();
+        let mut statements = vec![];
+
+        // Add preconditions.
+        statements.extend(self.convert_to_statements(preconditions));
+
+        // Add postconditions.
+        statements.extend(self.convert_to_statements(postconditions));
+
+        // Import contracts, if needed
+        if !statements.is_empty() {
+            statements.insert(0, self.build_prusti_contract_import(span));
+        }
+
+        // Add result to arguments
+        let unit_type = self.ast_builder.ty(span, ast::TyKind::Tup(Vec::new()));
+        let return_type = match decl.output.clone() {
+            ast::FunctionRetTy::Ty(ret_ty) => match ret_ty.node {
+                ast::TyKind::Never => unit_type.clone(),
+                _ => ret_ty,
+            },
+            ast::FunctionRetTy::Default(_) => unit_type.clone(),
+        };
+        let mut inputs_with_result: Vec<ast::Arg> = decl.inputs.clone();
+        inputs_with_result.push(self.ast_builder.arg(
+            span,
+            ast::Ident::from_str("result"),
+            return_type.clone(),
+        ));
+
+        // Glue everything.
+        name.push_str("__spec");
+        let mut spec_item = self
+            .ast_builder
+            .item_fn_poly(
+                span,
+                ast::Ident::from_str(&name),
+                inputs_with_result,
+                unit_type,
+                generics.clone(),
+                self.ast_builder.block(span, statements),
+            )
+            .into_inner();
+        mem::replace(
+            &mut spec_item.attrs,
+            vec![
+                self.ast_builder.attribute_name_value(
+                    span,
+                    "__PRUSTI_SPEC_ONLY",
+                    &spec_id.to_string(),
+                ),
+                self.ast_builder.attribute_allow(span, "unused_mut"),
+                self.ast_builder.attribute_allow(span, "dead_code"),
+                self.ast_builder.attribute_allow(span, "non_snake_case"),
+                self.ast_builder.attribute_allow(span, "unused_imports"),
+                self.ast_builder.attribute_allow(span, "unused_variables"),
+            ],
+        );
+        spec_item
+    }
+
     /// Generate a function that contains only an invariant for type-checking.
     fn generate_spec_item_inv(
         &mut self,
@@ -809,20 +1222,14 @@ impl<'tcx> SpecParser<'tcx> {
         }
     }
 
-    fn rewrite_loop_block(
-        &mut self,
-        block: ptr::P<ast::Block>,
+    /// Build the `if false { .. }` statement that type-checks the given invariants of a loop and
+    /// marks the enclosing loop with the specification ID.
+    fn build_loop_spec_block(
+        &self,
+        span: Span,
         spec_id: SpecID,
         invariants: &[UntypedSpecification],
-    ) -> ptr::P<ast::Block> {
-        trace!("[rewrite_loop_block] enter");
-        // Important: fold the content of the loop with `self.fold_block`
-        let mut block = self.fold_block(block).into_inner();
-        // Early return
-        if invariants.is_empty() {
-            return ptr::P(block);
-        }
-        let span = block.span;
+    ) -> ast::Stmt {
         let mut statements = self.convert_to_statements(invariants);
         if !statements.is_empty() {
             statements.insert(0, self.build_prusti_contract_import(span));
@@ -842,7 +1249,59 @@ impl<'tcx> SpecParser<'tcx> {
             &spec_id.to_string(),
         )]
         .into();
-        block.stmts.insert(0, builder.stmt_expr(ptr::P(expr)));
+        builder.stmt_expr(ptr::P(expr))
+    }
+
+    /// Parse the assertion of a `body_invariant!(..)` macro.
+    fn parse_body_invariant(&mut self, mac: &ast::Mac) -> Option<UntypedSpecification> {
+        use syntax::tokenstream::TokenTree;
+
+        let trees: Vec<TokenTree> = mac.node.stream().trees().collect();
+        let spec_string = match spec_tokens_to_string(&trees) {
+            Some(spec_string) => spec_string,
+            None => {
+                self.report_error(
+                    mac.span,
+                    "malformed specification (expected `forall(|vars| ..)`, \
+                     `exists(|vars| ..)` or `implies(.., ..)`)",
+                );
+                return None;
+            }
+        };
+        let assertion = self.parse_assertion_wrap(mac.span, &spec_string)?;
+        Some(UntypedSpecification {
+            typ: SpecType::Invariant,
+            assertion,
+            reason: None,
+        })
+    }
+
+    /// Fold the body of a loop, replacing its `body_invariant!(..)` statements by specification
+    /// blocks. The invariants given by attributes are type-checked at the start of the body, and
+    /// the ones given in the body are appended to `invariants`.
+    fn rewrite_loop_block(
+        &mut self,
+        block: ptr::P<ast::Block>,
+        spec_id: SpecID,
+        invariants: &mut Vec<UntypedSpecification>,
+    ) -> ptr::P<ast::Block> {
+        trace!("[rewrite_loop_block] enter");
+        let (block, body_invariants) = {
+            let mut rewriter = BodyInvariantRewriter {
+                parser: self,
+                spec_id,
+                invariants: vec![],
+            };
+            let block = rewriter.fold_block(block);
+            (block, rewriter.invariants)
+        };
+        // Important: fold the content of the loop with `self.fold_block`
+        let mut block = self.fold_block(block).into_inner();
+        if !invariants.is_empty() {
+            let statement = self.build_loop_spec_block(block.span, spec_id, invariants);
+            block.stmts.insert(0, statement);
+        }
+        invariants.extend(body_invariants);
         trace!("[rewrite_loop_block] exit");
         ptr::P(block)
     }
@@ -852,7 +1311,7 @@ impl<'tcx> SpecParser<'tcx> {
         let mut expr = expr.into_inner();
         let attrs = expr.attrs.to_vec();
         expr.attrs = vec![].into();
-        let invariants = self.parse_specs(attrs);
+        let mut invariants = self.parse_specs(attrs);
         if !invariants
             .iter()
             .all(|spec| spec.typ == SpecType::Invariant)
@@ -860,27 +1319,29 @@ impl<'tcx> SpecParser<'tcx> {
             self.report_error(expr.span, "loops can have only invariants");
             return ptr::P(expr);
         }
-        let spec_set = SpecificationSet::Loop(invariants.clone());
-        let id = self.register_specification(spec_set);
+        let id = self.get_new_specification_id();
         expr.node = match expr.node {
             ast::ExprKind::While(condition, block, ident) => {
-                let block = self.rewrite_loop_block(block, id, &invariants);
+                let block = self.rewrite_loop_block(block, id, &mut invariants);
                 ast::ExprKind::While(condition, block, ident)
             }
             ast::ExprKind::WhileLet(pattern, expr, block, label) => {
-                let block = self.rewrite_loop_block(block, id, &invariants);
+                let block = self.rewrite_loop_block(block, id, &mut invariants);
                 ast::ExprKind::WhileLet(pattern, expr, block, label)
             }
             ast::ExprKind::ForLoop(pattern, expr, block, label) => {
-                let block = self.rewrite_loop_block(block, id, &invariants);
+                let block = self.rewrite_loop_block(block, id, &mut invariants);
                 ast::ExprKind::ForLoop(pattern, expr, block, label)
             }
             ast::ExprKind::Loop(block, label) => {
-                let block = self.rewrite_loop_block(block, id, &invariants);
+                let block = self.rewrite_loop_block(block, id, &mut invariants);
                 ast::ExprKind::Loop(block, label)
             }
             _ => unreachable!(),
         };
+        self.check_loop_invariants(&expr, &invariants);
+        self.untyped_specifications
+            .insert(id, SpecificationSet::Loop(invariants));
         let mut new_attrs: Vec<_> = expr
             .attrs
             .iter()
@@ -888,9 +1349,18 @@ impl<'tcx> SpecParser<'tcx> {
             .filter(|attr| {
                 !attr.check_name("trusted")
                     && !attr.check_name("pure")
+                    && !attr.check_name("duplicable_reads")
+                    && !attr.check_name("model")
                     && !attr.check_name("invariant")
+                    && !attr.check_name("open_invariant")
+                    && !attr.check_name("history_invariant")
                     && !attr.check_name("requires")
                     && !attr.check_name("ensures")
+                    && !attr.check_name("ensures_on_panic")
+                    && !attr.check_name("refine_requires")
+                    && !attr.check_name("refine_ensures")
+                    && !attr.check_name("after_expiry")
+                    && !attr.check_name("assert_on_expiry")
             })
             .collect();
         new_attrs.push(self.ast_builder.attribute_name_value(
@@ -904,13 +1374,86 @@ impl<'tcx> SpecParser<'tcx> {
         ptr::P(expr)
     }
 
+    /// A `#[model]` method is a ghost field of its type, defined by the body of the method. Model
+    /// fields are pure functions that take `&self` as their only argument.
+    fn rewrite_model_attribute(&self, impl_item: &mut ast::ImplItem) {
+        if !impl_item.attrs.iter().any(|attr| attr.check_name("model")) {
+            return;
+        }
+        let takes_only_shared_self = match impl_item.node {
+            ast::ImplItemKind::Method(ref sig, _) => {
+                sig.decl.inputs.len() == 1 && match sig.decl.get_self() {
+                    Some(ref explicit_self) => match explicit_self.node {
+                        ast::SelfKind::Region(_, ast::Mutability::Immutable) => true,
+                        _ => false,
+                    },
+                    None => false,
+                }
+            }
+            _ => false,
+        };
+        if !takes_only_shared_self {
+            self.report_error(
+                impl_item.span,
+                "a model field must be a method that takes `&self` as its only argument",
+            );
+        }
+        if !impl_item.attrs.iter().any(|attr| attr.check_name("pure")) {
+            impl_item
+                .attrs
+                .push(self.ast_builder.attribute_word(impl_item.span, "pure"));
+        }
+    }
+
+    /// Replace the `#[prusti::skip]` and `#[prusti::overflow_checks(on/off)]` attributes, which
+    /// the compiler would reject as unknown tool attributes, with the internal attributes
+    /// `PRUSTI_SKIP_ATTR` and `PRUSTI_OVERFLOW_CHECKS_ATTR`.
+    fn rewrite_tool_attributes(&self, attrs: &mut Vec<ast::Attribute>) {
+        for attr in attrs.iter_mut() {
+            let is_prusti_attr = attr.path.segments.len() == 2
+                && attr.path.segments[0].ident.name == "prusti";
+            if !is_prusti_attr {
+                continue;
+            }
+            let name = attr.path.segments[1].ident.name;
+            if name == "skip" {
+                *attr = self.ast_builder.attribute_word(attr.span, PRUSTI_SKIP_ATTR);
+            } else if name == "overflow_checks" {
+                let argument = attr.tokens.to_string();
+                let value = match argument.trim_matches(|c| c == '(' || c == ')' || c == ' ') {
+                    "on" => "on",
+                    "off" => "off",
+                    _ => {
+                        self.report_error(
+                            attr.span,
+                            "the argument of `prusti::overflow_checks` must be `on` or `off`",
+                        );
+                        *attr = self
+                            .ast_builder
+                            .attribute_word(attr.span, PRUSTI_OVERFLOW_CHECKS_ATTR);
+                        continue;
+                    }
+                };
+                *attr = self.ast_builder.attribute_name_value(
+                    attr.span,
+                    PRUSTI_OVERFLOW_CHECKS_ATTR,
+                    value,
+                );
+            }
+        }
+    }
+
     fn rewrite_fn_item(&mut self, item: ptr::P<ast::Item>) -> SmallVector<ptr::P<ast::Item>> {
         trace!("[rewrite_fn_item] enter");
         let mut item = item.into_inner();
+        self.rewrite_tool_attributes(&mut item.attrs);
+        if item.attrs.iter().any(|attr| attr.check_name("model")) {
+            self.report_error(item.span, "model fields must be methods of the type they abstract");
+        }
 
         // Parse specification
         let specs = self.parse_specs(item.attrs.clone());
-        if specs.iter().any(|spec| spec.typ == SpecType::Invariant) {
+        if specs.iter().any(|spec| spec.is_invariant()) {
             self.report_error(item.span, "invariant not allowed for procedure");
             return SmallVector::one(ptr::P(item));
         }
@@ -921,7 +1464,9 @@ impl<'tcx> SpecParser<'tcx> {
             .collect();
         let postconditions: Vec<_> = specs
             .into_iter()
-            .filter(|spec| spec.typ == SpecType::Postcondition)
+            .filter(|spec| {
+                spec.typ == SpecType::Postcondition || spec.typ == SpecType::PanicPostcondition
+            })
             .collect();
         let spec_set = SpecificationSet::Procedure(preconditions.clone(), postconditions.clone());
 
@@ -945,6 +1490,24 @@ impl<'tcx> SpecParser<'tcx> {
             }
         }
 
+        // Check the contract at runtime
+        if config::runtime_contract_checks()
+            && !item.attrs.iter().any(|attr| attr.check_name("pure"))
+        {
+            if let ast::ItemKind::Fn(ref decl, fn_header, _, ref mut body) = item.node {
+                if fn_header.constness.node == ast::Constness::NotConst {
+                    *body = self.add_runtime_checks(
+                        decl,
+                        body.clone(),
+                        &preconditions,
+                        &postconditions,
+                    );
+                }
+            }
+            // The checks at the end of the body might be unreachable
+            item.attrs.push(self.ast_builder.attribute_allow(item.span, "unreachable_code"));
+        }
+
         // Dump modified item
         let new_item_str = syntax::print::pprust::item_to_string(&item);
         debug!("new_item:\n{}", new_item_str);
@@ -957,9 +1520,18 @@ impl<'tcx> SpecParser<'tcx> {
             .extend(item.attrs.iter().cloned().filter(|attr| {
                 !attr.check_name("trusted")
                     && !attr.check_name("pure")
+                    && !attr.check_name("duplicable_reads")
+                    && !attr.check_name("model")
                     && !attr.check_name("invariant")
+                    && !attr.check_name("open_invariant")
+                    && !attr.check_name("history_invariant")
                     && !attr.check_name("requires")
                     && !attr.check_name("ensures")
+                    && !attr.check_name("ensures_on_panic")
+                    && !attr.check_name("refine_requires")
+                    && !attr.check_name("refine_ensures")
+                    && !attr.check_name("after_expiry")
+                    && !attr.check_name("assert_on_expiry")
                     && !attr.check_name(PRUSTI_SPEC_ATTR)
             }));
 
@@ -976,15 +1548,109 @@ impl<'tcx> SpecParser<'tcx> {
         result
     }
 
+    /// Rewrites an inherent `impl` block of an `#[extern_spec]` module, which cannot be declared
+    /// for the external type that it specifies. The methods (and their spec items) are declared
+    /// in a trait of the module instead, and the block implements the trait for the type, so that
+    /// the methods are still named after the type (e.g. `std::vec::Vec::push`).
+    fn rewrite_extern_spec_impl(
+        &mut self,
+        item: ast::Item,
+        generics: ast::Generics,
+        ty: ptr::P<ast::Ty>,
+        impl_items: Vec<ast::ImplItem>,
+    ) -> SmallVector<ptr::P<ast::Item>> {
+        let span = item.span;
+        self.extern_spec_impl_count += 1;
+        let trait_ident = self
+            .ast_builder
+            .ident_of(&format!("PrustiExternSpecImpl{}", self.extern_spec_impl_count));
+
+        let mut trait_items = vec![];
+        let mut new_impl_items = vec![];
+        for impl_item in impl_items.into_iter() {
+            if let ast::ImplItemKind::Method(ref sig, _) = impl_item.node {
+                trait_items.push(ast::TraitItem {
+                    id: ast::DUMMY_NODE_ID,
+                    ident: impl_item.ident,
+                    attrs: vec![],
+                    generics: impl_item.generics.clone(),
+                    node: ast::TraitItemKind::Method(sig.clone(), None),
+                    span: impl_item.span,
+                    tokens: None,
+                });
+            } else {
+                self.session.span_err(
+                    impl_item.span,
+                    "[Prusti: invalid specification] an `impl` block of an `#[extern_spec]` \
+                     module can only contain methods",
+                );
+                continue;
+            }
+            // The items of a trait implementation have the visibility of the trait
+            new_impl_items.push(ast::ImplItem {
+                vis: self.ast_builder.visinh(),
+                ..impl_item
+            });
+        }
+
+        let trait_args = generics
+            .params
+            .iter()
+            .map(|param| match param.kind {
+                ast::GenericParamKind::Lifetime => {
+                    ast::GenericArg::Lifetime(self.ast_builder.lifetime(span, param.ident))
+                }
+                ast::GenericParamKind::Type { .. } => {
+                    ast::GenericArg::Type(self.ast_builder.ty_ident(span, param.ident))
+                }
+            })
+            .collect();
+        let trait_path =
+            self.ast_builder
+                .path_all(span, false, vec![trait_ident], trait_args, vec![]);
+
+        let trait_item = self.ast_builder.item(
+            span,
+            trait_ident,
+            vec![],
+            ast::ItemKind::Trait(
+                ast::IsAuto::No,
+                ast::Unsafety::Normal,
+                generics.clone(),
+                vec![],
+                trait_items,
+            ),
+        );
+        let impl_item = ptr::P(ast::Item {
+            node: ast::ItemKind::Impl(
+                ast::Unsafety::Normal,
+                ast::ImplPolarity::Positive,
+                ast::Defaultness::Final,
+                generics,
+                Some(self.ast_builder.trait_ref(trait_path)),
+                ty,
+                new_impl_items,
+            ),
+            ..item
+        });
+
+        let mut result = SmallVector::new();
+        result.push(trait_item);
+        result.push(impl_item);
+        result
+    }
+
     fn rewrite_impl_item_method(
         &mut self,
         mut impl_item: ast::ImplItem,
     ) -> (SmallVector<ast::ImplItem>, SmallVector<ast::ImplItem>) {
         trace!("[rewrite_impl_item_method] enter");
+        self.rewrite_tool_attributes(&mut impl_item.attrs);
+        self.rewrite_model_attribute(&mut impl_item);
 
         // Parse specification
         let specs = self.parse_specs(impl_item.attrs.clone());
-        if specs.iter().any(|spec| spec.typ == SpecType::Invariant) {
+        if specs.iter().any(|spec| spec.is_invariant()) {
             self.report_error(impl_item.span, "invariant not allowed for procedure");
             return (SmallVector::one(impl_item), SmallVector::new());
         }
@@ -995,7 +1661,9 @@ impl<'tcx> SpecParser<'tcx> {
             .collect();
         let postconditions: Vec<_> = specs
             .into_iter()
-            .filter(|spec| spec.typ == SpecType::Postcondition)
+            .filter(|spec| {
+                spec.typ == SpecType::Postcondition || spec.typ == SpecType::PanicPostcondition
+            })
             .collect();
         let spec_set = SpecificationSet::Procedure(preconditions.clone(), postconditions.clone());
 
@@ -1019,6 +1687,26 @@ impl<'tcx> SpecParser<'tcx> {
             }
         }
 
+        // Check the contract at runtime
+        if config::runtime_contract_checks()
+            && !impl_item.attrs.iter().any(|attr| attr.check_name("pure"))
+        {
+            if let ast::ImplItemKind::Method(ref sig, ref mut body) = impl_item.node {
+                if sig.header.constness.node == ast::Constness::NotConst {
+                    *body = self.add_runtime_checks(
+                        &sig.decl,
+                        body.clone(),
+                        &preconditions,
+                        &postconditions,
+                    );
+                }
+            }
+            // The checks at the end of the body might be unreachable
+            impl_item
+                .attrs
+                .push(self.ast_builder.attribute_allow(impl_item.span, "unreachable_code"));
+        }
+
         // Dump modified item
         let new_item_str = syntax::print::pprust::impl_item_to_string(&impl_item);
         debug!("new_item:\n{}", new_item_str);
@@ -1032,9 +1720,18 @@ impl<'tcx> SpecParser<'tcx> {
             .extend(impl_item.attrs.iter().cloned().filter(|attr| {
                 !attr.check_name("trusted")
                     && !attr.check_name("pure")
+                    && !attr.check_name("duplicable_reads")
+                    && !attr.check_name("model")
                     && !attr.check_name("invariant")
+                    && !attr.check_name("open_invariant")
+                    && !attr.check_name("history_invariant")
                     && !attr.check_name("requires")
                     && !attr.check_name("ensures")
+                    && !attr.check_name("ensures_on_panic")
+                    && !attr.check_name("refine_requires")
+                    && !attr.check_name("refine_ensures")
+                    && !attr.check_name("after_expiry")
+                    && !attr.check_name("assert_on_expiry")
                     && !attr.check_name(PRUSTI_SPEC_ATTR)
             }));
 
@@ -1054,15 +1751,11 @@ impl<'tcx> SpecParser<'tcx> {
 
         // Parse specification
         let specs = self.parse_specs(item.attrs.clone());
-        if specs.iter().any(|spec| spec.typ != SpecType::Invariant) {
+        if specs.iter().any(|spec| !spec.is_invariant()) {
             self.report_error(item.span, "only invariant allowed for struct");
             return SmallVector::one(ptr::P(item));
         }
-        let invariants: Vec<_> = specs
-            .clone()
-            .into_iter()
-            .filter(|spec| spec.typ == SpecType::Invariant)
-            .collect();
+        let invariants: Vec<_> = specs.clone();
         let spec_set = SpecificationSet::Struct(invariants.clone());
 
         // Register specification
@@ -1092,9 +1785,18 @@ impl<'tcx> SpecParser<'tcx> {
             .extend(item.attrs.iter().cloned().filter(|attr| {
                 !attr.check_name("trusted")
                     && !attr.check_name("pure")
+                    && !attr.check_name("duplicable_reads")
+                    && !attr.check_name("model")
                     && !attr.check_name("invariant")
+                    && !attr.check_name("open_invariant")
+                    && !attr.check_name("history_invariant")
                     && !attr.check_name("requires")
                     && !attr.check_name("ensures")
+                    && !attr.check_name("ensures_on_panic")
+                    && !attr.check_name("refine_requires")
+                    && !attr.check_name("refine_ensures")
+                    && !attr.check_name("after_expiry")
+                    && !attr.check_name("assert_on_expiry")
                     && !attr.check_name(PRUSTI_SPEC_ATTR)
             }));
 
@@ -1111,15 +1813,110 @@ impl<'tcx> SpecParser<'tcx> {
         result
     }
 
+    /// Register the specifications of the foreign functions declared with `#[trusted_ffi]`,
+    /// whose calls are encoded with their contract. The items that type-check the specifications
+    /// are added next to the `extern` block.
+    fn rewrite_foreign_mod(&mut self, item: ptr::P<ast::Item>) -> SmallVector<ptr::P<ast::Item>> {
+        trace!("[rewrite_foreign_mod] enter");
+        let mut item = item.into_inner();
+        let mut spec_items = vec![];
+        if let ast::ItemKind::ForeignMod(ref mut foreign_mod) = item.node {
+            for foreign_item in &mut foreign_mod.items {
+                spec_items.extend(self.rewrite_foreign_fn_item(foreign_item));
+            }
+        }
+
+        trace!("[rewrite_foreign_mod] exit");
+        let mut result = SmallVector::new();
+        result.push(ptr::P(item));
+        result.extend(spec_items.into_iter().map(ptr::P));
+        result
+    }
+
+    fn rewrite_foreign_fn_item(&mut self, foreign_item: &mut ast::ForeignItem) -> Option<ast::Item> {
+        let (decl, generics) = match foreign_item.node {
+            ast::ForeignItemKind::Fn(ref decl, ref generics) => (decl.clone(), generics.clone()),
+            _ => return None,
+        };
+
+        // Parse specification
+        let specs = self.parse_specs(foreign_item.attrs.clone());
+        if !foreign_item.attrs.iter().any(|attr| attr.check_name("trusted_ffi")) {
+            if !specs.is_empty() {
+                self.report_error(
+                    foreign_item.span,
+                    "the specification of a foreign function requires `#[trusted_ffi]`",
+                );
+            }
+            return None;
+        }
+        if specs.iter().any(|spec| spec.is_invariant()) {
+            self.report_error(foreign_item.span, "invariant not allowed for procedure");
+            return None;
+        }
+        if decl.variadic {
+            self.report_error(
+                foreign_item.span,
+                "variadic foreign functions cannot be declared with `#[trusted_ffi]`",
+            );
+            return None;
+        }
+        let preconditions: Vec<_> = specs
+            .clone()
+            .into_iter()
+            .filter(|spec| spec.typ == SpecType::Precondition)
+            .collect();
+        let postconditions: Vec<_> = specs
+            .into_iter()
+            .filter(|spec| {
+                spec.typ == SpecType::Postcondition || spec.typ == SpecType::PanicPostcondition
+            })
+            .collect();
+        let spec_set = SpecificationSet::Procedure(preconditions.clone(), postconditions.clone());
+
+        // Register specification
+        let id = self.register_specification(spec_set.clone());
+        foreign_item.attrs.push(self.ast_builder.attribute_name_value(
+            foreign_item.span,
+            PRUSTI_SPEC_ATTR,
+            &id.to_string(),
+        ));
+        if spec_set.is_empty() {
+            return None;
+        }
+
+        // Create spec item
+        let spec_item = self.generate_spec_fn(
+            foreign_item.ident,
+            foreign_item.span,
+            &decl,
+            &generics,
+            id,
+            &preconditions,
+            &postconditions,
+        );
+        let spec_item_str = syntax::print::pprust::item_to_string(&spec_item);
+        debug!("spec_item:\n{}", spec_item_str);
+        self.log_modified_program(spec_item_str);
+        Some(spec_item)
+    }
+
     fn rewrite_trait_item_method(
         &mut self,
         mut trait_item: ast::TraitItem,
     ) -> SmallVector<ast::TraitItem> {
         trace!("[rewrite_trait_item_method] enter");
+        self.rewrite_tool_attributes(&mut trait_item.attrs);
+        if trait_item.attrs.iter().any(|attr| attr.check_name("model")) {
+            self.report_error(
+                trait_item.span,
+                "model fields must be methods of the type they abstract",
+            );
+        }
 
         // Parse specification
         let specs = self.parse_specs(trait_item.attrs.clone());
-        if specs.iter().any(|spec| spec.typ == SpecType::Invariant) {
+        if specs.iter().any(|spec| spec.is_invariant()) {
             self.report_error(trait_item.span, "invariant not allowed for procedure");
             return SmallVector::one(trait_item);
         }
@@ -1130,7 +1927,9 @@ impl<'tcx> SpecParser<'tcx> {
             .collect();
         let postconditions: Vec<_> = specs
             .into_iter()
-            .filter(|spec| spec.typ == SpecType::Postcondition)
+            .filter(|spec| {
+                spec.typ == SpecType::Postcondition || spec.typ == SpecType::PanicPostcondition
+            })
             .collect();
         let spec_set = SpecificationSet::Procedure(preconditions.clone(), postconditions.clone());
 
@@ -1180,9 +1979,18 @@ impl<'tcx> SpecParser<'tcx> {
             .extend(trait_item.attrs.iter().cloned().filter(|attr| {
                 !attr.check_name("trusted")
                     && !attr.check_name("pure")
+                    && !attr.check_name("duplicable_reads")
+                    && !attr.check_name("model")
                     && !attr.check_name("invariant")
+                    && !attr.check_name("open_invariant")
+                    && !attr.check_name("history_invariant")
                     && !attr.check_name("requires")
                     && !attr.check_name("ensures")
+                    && !attr.check_name("ensures_on_panic")
+                    && !attr.check_name("refine_requires")
+                    && !attr.check_name("refine_ensures")
+                    && !attr.check_name("after_expiry")
+                    && !attr.check_name("assert_on_expiry")
                     && !attr.check_name(PRUSTI_SPEC_ATTR)
             }));
 
@@ -1205,6 +2013,9 @@ impl<'tcx> SpecParser<'tcx> {
         use syntax::parse::token;
         use syntax::tokenstream::TokenTree;
 
+        if is_token_spec(attribute) {
+            return self.extract_spec_tokens(attribute);
+        }
         let trees: Vec<TokenTree> = attribute.tokens.trees().collect();
         if trees.len() != 2 {
             self.report_error(
@@ -1256,25 +2067,113 @@ impl<'tcx> SpecParser<'tcx> {
         spec_string_with_span
     }
 
+    /// Converts a specification written as Rust tokens, e.g. `#[requires(x > 0)]`, to a
+    /// specification string. The span of the string is the span of the attribute.
+    fn extract_spec_tokens(&self, attribute: &ast::Attribute) -> Option<(String, Span)> {
+        use syntax::tokenstream::TokenTree;
+
+        let spec_string = match attribute.tokens.trees().next() {
+            Some(TokenTree::Delimited(_, ref delimited)) => {
+                let trees: Vec<TokenTree> = delimited.stream().trees().collect();
+                spec_tokens_to_string(&trees)
+            }
+            _ => None,
+        };
+        match spec_string {
+            Some(spec_string) => Some((spec_string, attribute.span)),
+            None => {
+                self.report_error(
+                    attribute.span,
+                    "malformed specification (expected `forall(|vars| ..)`, \
+                     `exists(|vars| ..)` or `implies(.., ..)`)",
+                );
+                None
+            }
+        }
+    }
+
+    /// Extract the specification string of an attribute, which is either of the form
+    /// `#[requires="..."]` or `#[requires(reason = "...", "...")]`. The second form, which
+    /// attaches a reason to the specification, is only accepted for preconditions and
+    /// postconditions.
+    fn extract_spec_string_with_reason(
+        &self,
+        attribute: &ast::Attribute,
+        spec_type: SpecType,
+    ) -> Option<(String, Span, Option<String>)> {
+        if is_token_spec(attribute) {
+            return self
+                .extract_spec_tokens(attribute)
+                .map(|(spec, span)| (spec, span, None));
+        }
+        let items = match attribute.meta() {
+            Some(ast::MetaItem { node: ast::MetaItemKind::List(items), .. }) => items,
+            _ => {
+                return self
+                    .extract_spec_string(attribute)
+                    .map(|(spec, span)| (spec, span, None));
+            }
+        };
+        if spec_type != SpecType::Precondition && spec_type != SpecType::Postcondition {
+            self.report_error(
+                attribute.span,
+                "only preconditions and postconditions can have a reason",
+            );
+            return None;
+        }
+        let (reason, spec_literal) = match items.as_slice() {
+            [reason_item, spec_item] if reason_item.check_name("reason") => {
+                (reason_item.value_str(), spec_item.literal())
+            }
+            _ => (None, None),
+        };
+        match (reason, spec_literal) {
+            (Some(reason), Some(ast::Lit { node: ast::LitKind::Str(spec, style), span })) => {
+                let span = match style {
+                    ast::StrStyle::Cooked => *span,
+                    ast::StrStyle::Raw(delimiter_size) => {
+                        shift_span(*span, (delimiter_size + 1) as u32)
+                    }
+                };
+                Some((spec.as_str().to_string(), span, Some(reason.as_str().to_string())))
+            }
+            _ => {
+                self.report_error(
+                    attribute.span,
+                    "malformed specification (expected `reason = \"...\"` and a \
+                     specification string)",
+                );
+                None
+            }
+        }
+    }
+
+    /// Parses the typestate condition `T1 == T2 ~~> ...` that may prefix a specification. On
+    /// success, the condition is removed from `spec_string` and `span` is updated accordingly.
     fn parse_typaram_condition(
         &mut self,
         span: &mut Span,
         spec_string: &mut &str,
-    ) -> Option<ForAllVars<ast::Arg>> {
-        // FIXME... the uglier the better
+    ) -> Result<Option<ForAllVars<ast::Arg>>, AssertionParsingError> {
         if let Some(pos) = spec_string.find("~~>") {
-            let foo = &spec_string[..pos];
-            let (ty1, ty2) = self.parse_type(*span, foo.to_string()).unwrap(); // FIXME span
-            let arg1 = self.ast_builder.arg(
-                *span,
-                self.ast_builder.ident_of("t1"),
-                ty1, // FIXME
-            );
-            let arg2 = self.ast_builder.arg(
-                *span,
-                self.ast_builder.ident_of("t2"),
-                ty2, // FIXME
-            );
+            let condition_span = shift_resize_span(*span, 0, pos as u32);
+            let condition = &spec_string[..pos];
+            let (ty1, ty2) = match self.parse_type(condition_span, condition.to_string()) {
+                Ok(types) => types,
+                Err(err) => {
+                    self.report_error(
+                        condition_span,
+                        "malformed typestate condition (expected `Type == Type ~~> assertion`)",
+                    );
+                    return Err(err);
+                }
+            };
+            let arg1 = self
+                .ast_builder
+                .arg(condition_span, self.ast_builder.ident_of("t1"), ty1);
+            let arg2 = self
+                .ast_builder
+                .arg(condition_span, self.ast_builder.ident_of("t2"), ty2);
             let forallvars = ForAllVars {
                 // is actually a "FORALL_ID"
                 id: self.get_new_expression_id(),
@@ -1282,9 +2181,9 @@ impl<'tcx> SpecParser<'tcx> {
             };
             *spec_string = &spec_string[pos + 3..];
             *span = shift_span(*span, (pos + 3) as u32);
-            Some(forallvars)
+            Ok(Some(forallvars))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -1301,22 +2200,65 @@ impl<'tcx> SpecParser<'tcx> {
         }
     }
 
+    /// Report contracts that mix refined preconditions (resp. postconditions) with non-refined
+    /// ones, because it would not be clear which one should refine the trait contract.
+    fn check_refined_specs(&mut self, attributes: &[ast::Attribute]) {
+        for &(name, refined_name) in &[
+            ("requires", "refine_requires"),
+            ("ensures", "refine_ensures"),
+        ] {
+            if !attributes.iter().any(|attr| attr.check_name(name)) {
+                continue;
+            }
+            if let Some(attr) = attributes.iter().find(|attr| attr.check_name(refined_name)) {
+                self.report_error(
+                    attr.span,
+                    &format!("`{}` cannot be combined with `{}`", refined_name, name),
+                );
+            }
+        }
+    }
+
     /// Parses attribute into specification.
     /// TODO: Rewrite to use the [syn](https://crates.io/crates/syn) crate.
     fn parse_specs(&mut self, attributes: Vec<ast::Attribute>) -> Vec<UntypedSpecification> {
         trace!("[parse_specs] enter attributes.len={}", attributes.len());
+        self.check_refined_specs(&attributes);
 
         let specifications: Vec<_> = attributes
             .into_iter()
             .map(|attribute| {
-                if let Ok(spec_type) = SpecType::try_from(&attribute.path.to_string() as &str) {
-                    if let Some((spec_string, mut span)) = self.extract_spec_string(&attribute) {
+                let attribute_name = match attribute.path.to_string().as_str() {
+                    // The refined contract of the implementation of a trait method is parsed like
+                    // any other contract. The encoder checks that it refines the trait contract.
+                    "refine_requires" => "requires".to_string(),
+                    "refine_ensures" => "ensures".to_string(),
+                    attribute_name => attribute_name.to_string(),
+                };
+                if attribute_name == "after_expiry" || attribute_name == "assert_on_expiry" {
+                    // `#[after_expiry="..."]` is a shorthand for `#[ensures="after_expiry(...)"]`
+                    let (spec_string, span) = self.extract_spec_string(&attribute)?;
+                    debug!("spec={:?} pledge={:?}", spec_string, attribute_name);
+                    let assertion = self
+                        .parse_pledge(span, &attribute_name, None, &spec_string)
+                        .ok()?;
+                    return Some(UntypedSpecification {
+                        typ: SpecType::Postcondition,
+                        assertion: assertion,
+                        reason: None,
+                    });
+                }
+                if let Ok(spec_type) = SpecType::try_from(&attribute_name as &str) {
+                    if let Some((spec_string, mut span, reason)) =
+                        self.extract_spec_string_with_reason(&attribute, spec_type)
+                    {
                         debug!("spec={:?} spec_type={:?}", spec_string, spec_type);
-                        // FIXME ugly code
                         let mut spec_string: &str = &spec_string;
-                        let foo = self.parse_typaram_condition(&mut span, &mut spec_string);
+                        let typaram_condition = self
+                            .parse_typaram_condition(&mut span, &mut spec_string)
+                            .ok()?;
                         if let Some(assertion) = self.parse_assertion_wrap(span, &spec_string) {
-                            let assertion = match foo {
+                            let assertion = match typaram_condition {
                                 Some(x) => Assertion {
                                     kind: box AssertionKind::TypeCond(x, assertion),
                                 },
@@ -1326,6 +2268,7 @@ impl<'tcx> SpecParser<'tcx> {
                             Some(UntypedSpecification {
                                 typ: spec_type,
                                 assertion: assertion,
+                                reason: reason,
                             })
                         } else {
                             None
@@ -1342,11 +2285,53 @@ impl<'tcx> SpecParser<'tcx> {
             .filter(|spec| spec.is_some())
             .map(|spec| spec.unwrap())
             .collect();
+        self.check_trivial_specs(&specifications);
+        self.check_panic_postconditions(&specifications);
 
         trace!("[parse_specs] exit");
         specifications
     }
 
+    /// Parses the built-in specifications of the standard library, which are added to the crate
+    /// as an `#[extern_spec]` module.
+    fn parse_builtin_specs(&self) -> Vec<ptr::P<ast::Item>> {
+        self.parse_extern_spec_module(BUILTIN_SPECS.to_string())
+    }
+
+    /// Parses the contracts exported by the crates imported with `extern crate`, which are added
+    /// to the crate as `#[extern_spec]` modules.
+    fn parse_imported_contracts(&self, krate: &ast::Crate) -> Vec<ptr::P<ast::Item>> {
+        krate
+            .module
+            .items
+            .iter()
+            .filter_map(|item| match item.node {
+                ast::ItemKind::ExternCrate(orig_name) => {
+                    Some(orig_name.unwrap_or(item.ident.name).to_string())
+                }
+                _ => None,
+            })
+            .filter(|crate_name| crate_name != "prusti_contracts")
+            .filter_map(|crate_name| contract_metadata::import_contracts(self.session, &crate_name))
+            .flat_map(|source| self.parse_extern_spec_module(source))
+            .collect()
+    }
+
+    fn parse_extern_spec_module(&self, source: String) -> Vec<ptr::P<ast::Item>> {
+        let mut parser = parse::new_parser_from_source_str(
+            &self.session.parse_sess,
+            FileName::QuoteExpansion,
+            source,
+        );
+        match parser.parse_item() {
+            Ok(opt_item) => opt_item.into_iter().collect(),
+            Err(mut err) => {
+                err.emit();
+                vec![]
+            }
+        }
+    }
+
     /// Parse Rust expression.
     fn parse_expression(
         &mut self,
@@ -1368,7 +2353,7 @@ impl<'tcx> SpecParser<'tcx> {
         let expr = parse::new_parser_from_source_str(
             &self.session.parse_sess,
             FileName::QuoteExpansion,
-            spec_string,
+            split_nested_tuple_indices(&spec_string),
         )
         .parse_expr();
         debug!("Parsed expr: {:?}", expr);
@@ -1387,7 +2372,7 @@ impl<'tcx> SpecParser<'tcx> {
         result
     }
 
-    /// Parse Rust type. (copied from parse_expression)
+    /// Parse the two Rust types of a typestate condition `T1 == T2`.
     fn parse_type(
         &mut self,
         base_span: Span,
@@ -1410,8 +2395,13 @@ impl<'tcx> SpecParser<'tcx> {
             FileName::QuoteExpansion,
             spec_string,
         );
-        let expr = parser.parse_ty();
-        //parser.expected_tokens.clear();
+        let ty1 = match parser.parse_ty() {
+            Ok(ty) => ty,
+            Err(mut err) => {
+                err.emit();
+                return Err(AssertionParsingError::ParsingRustExpressionFailed);
+            }
+        };
         match parser.expect(&parse::token::Token::EqEq) {
             Ok(_) => {}
             Err(mut err) => {
@@ -1419,23 +2409,19 @@ impl<'tcx> SpecParser<'tcx> {
                 return Err(AssertionParsingError::ParsingRustExpressionFailed);
             }
         }
-        let expr2 = parser.parse_ty();
-        // TODO expect "eof" after parsing!
-        debug!("Parsed ty: {:?}", expr);
-        return Ok((expr.unwrap(), expr2.unwrap()));
-        //let result = match expr {
-        //    Ok(expr) => {
-        //        let mut rewriter = SpanRewriter::new(whitespace_count, expr.span, base_span);
-        //        let expr = rewriter.fold_ty(expr);
-        //        Ok(expr)
-        //    }
-        //    Err(mut err) => {
-        //        err.emit();
-        //        Err(AssertionParsingError::ParsingRustExpressionFailed)
-        //    }
-        //};
-        //trace!("[parse_ty] exit");
-        //result
+        let ty2 = match parser.parse_ty() {
+            Ok(ty) => ty,
+            Err(mut err) => {
+                err.emit();
+                return Err(AssertionParsingError::ParsingRustExpressionFailed);
+            }
+        };
+        if parser.token != parse::token::Token::Eof {
+            return Err(AssertionParsingError::ParsingRustExpressionFailed);
+        }
+        debug!("Parsed types: {:?} == {:?}", ty1, ty2);
+        trace!("[parse_ty] exit");
+        Ok((ty1, ty2))
     }
 
     fn parse_vars(
@@ -1566,44 +2552,56 @@ impl<'tcx> SpecParser<'tcx> {
                 None
             };
             let body_str = caps.name("body").unwrap().as_str();
-            let (lhs_str, rhs_str) = match caps.name("construct").unwrap().as_str() {
-                "after_expiry" => {
-                    // after_expiry(rhs) // lhs == true
-                    ("true", body_str)
-                }
-                "assert_on_expiry" => {
-                    // assert_on_expiry(lhs[, rhs]) // default: rhs == true
-                    let mut iter = body_str.rsplitn(2, ',');
-                    let one = iter.next();
-                    let two = iter.next();
-                    if two.is_none() {
-                        (one.unwrap(), "true")
-                    } else {
-                        (two.unwrap(), one.unwrap())
-                    }
-                }
-                _ => unreachable!(),
-            };
             let new_span = {
                 let whitespace1_len = caps.name("whitespace1").unwrap().as_str().len();
                 shift_resize_span(span, whitespace1_len as u32, body_str.len() as u32)
             };
-            let lhs = self.parse_assertion(new_span, lhs_str)?;
-            let rhs = self.parse_assertion(new_span, rhs_str)?;
-            debug!(
-                "after_expiry: reference={:?} lhs={:?} rhs={:?}",
-                reference, lhs, rhs
-            );
-            let assertion = UntypedAssertion {
-                kind: box AssertionKind::Pledge(reference, lhs, rhs),
-            };
-            Ok(assertion)
+            let construct = caps.name("construct").unwrap().as_str();
+            self.parse_pledge(new_span, construct, reference, body_str)
         } else {
             self.report_error(span, "failed to parse after_expiry expression");
             Err(AssertionParsingError::FailedAfterExpiryMatch)
         }
     }
 
+    /// Parses the body of an `after_expiry` or `assert_on_expiry` pledge.
+    fn parse_pledge(
+        &mut self,
+        span: Span,
+        construct: &str,
+        reference: Option<UntypedExpression>,
+        body_str: &str,
+    ) -> Result<UntypedAssertion, AssertionParsingError> {
+        let (lhs_str, rhs_str) = match construct {
+            "after_expiry" => {
+                // after_expiry(rhs) // lhs == true
+                ("true", body_str)
+            }
+            "assert_on_expiry" => {
+                // assert_on_expiry(lhs[, rhs]) // default: rhs == true
+                let mut iter = body_str.rsplitn(2, ',');
+                let one = iter.next();
+                let two = iter.next();
+                if two.is_none() {
+                    (one.unwrap(), "true")
+                } else {
+                    (two.unwrap(), one.unwrap())
+                }
+            }
+            _ => unreachable!(),
+        };
+        let lhs = self.parse_assertion(span, lhs_str)?;
+        let rhs = self.parse_assertion(span, rhs_str)?;
+        debug!(
+            "after_expiry: reference={:?} lhs={:?} rhs={:?}",
+            reference, lhs, rhs
+        );
+        let assertion = UntypedAssertion {
+            kind: box AssertionKind::Pledge(reference, lhs, rhs),
+        };
+        Ok(assertion)
+    }
+
     fn parse_forall(
         &mut self,
         span: Span,
@@ -1682,8 +2680,57 @@ impl<'tcx> SpecParser<'tcx> {
         }
     }
 
+    fn parse_exists(
+        &mut self,
+        span: Span,
+        spec_string: &str,
+    ) -> Result<UntypedAssertion, AssertionParsingError> {
+        trace!("[enter] parse_exists spec_string={}", spec_string);
+        lazy_static! {
+            static ref RE: Regex = Regex::new(
+                r"(?sx)
+                ^\s*exists\s*
+                (?P<vars>.*)\s*::\s*(\{(?P<triggers>.*)\})?\s*
+                (?P<body>.*)\s*$
+            ",
+            )
+            .unwrap();
+        }
+        if let Some(caps) = RE.captures(spec_string) {
+            let vars = self.parse_vars(span, caps.name("vars").unwrap())?;
+            let triggers = match caps.name("triggers") {
+                Some(triggers) => self.parse_triggers(span, triggers)?,
+                None => TriggerSet::new(vec![]),
+            };
+            let body = self.parse_forall_expr(span, caps.name("body").unwrap())?;
+            debug!(
+                "exists: vars={:?} triggers={:?} body={:?}",
+                vars, triggers, body
+            );
+            let assertion = UntypedAssertion {
+                kind: box AssertionKind::Exists(
+                    ForAllVars {
+                        id: self.get_new_expression_id(),
+                        vars: vars,
+                    },
+                    triggers,
+                    UntypedAssertion {
+                        kind: box AssertionKind::Expr(Expression {
+                            id: self.get_new_expression_id(),
+                            expr: body,
+                        }),
+                    },
+                ),
+            };
+            Ok(assertion)
+        } else {
+            self.report_error(span, "failed to parse exists expression");
+            Err(AssertionParsingError::FailedExistsMatch)
+        }
+    }
+
     /// Parse an assertion string into an assertion object.
-    /// The assertion string can only contain an implication, forall, or a
+    /// The assertion string can only contain an implication, forall, exists, or a
     /// Rust expression.
     fn parse_assertion_simple(
         &mut self,
@@ -1695,14 +2742,17 @@ impl<'tcx> SpecParser<'tcx> {
             spec_string
         );
 
-        // Drop surrounding parenthesis.
+        // Drop surrounding parenthesis, and split the conjunctions that they contain.
         {
             lazy_static! {
                 static ref RE: Regex = Regex::new(r"^(\s*\()(.*)\)\s*$").unwrap();
             }
             if let Some(caps) = RE.captures(&spec_string) {
-                let new_span = shift_span(span, caps[1].len() as u32);
-                return self.parse_assertion_simple(new_span, String::from(&caps[2]));
+                // Skip strings like `(a) == (b)`, whose first and last parenthesis don't match.
+                if is_balanced(&caps[2]) {
+                    let new_span = shift_span(span, caps[1].len() as u32);
+                    return self.parse_assertion(new_span, &caps[2]);
+                }
             }
         }
 
@@ -1711,6 +2761,16 @@ impl<'tcx> SpecParser<'tcx> {
             return self.parse_after_expiry(span, &spec_string);
         }
 
+        // Parse exists.
+        {
+            lazy_static! {
+                static ref RE: Regex = Regex::new(r"^\s*exists\b").unwrap();
+            }
+            if RE.is_match(&spec_string) {
+                return self.parse_exists(span, &spec_string);
+            }
+        }
+
         // Parse forall.
         if spec_string.contains("forall")
             && (!spec_string.contains("==>")
@@ -1726,12 +2786,12 @@ impl<'tcx> SpecParser<'tcx> {
             let mut last2 = None;
             let mut last1 = None;
             for (position, char) in iter {
-                if char == '(' {
+                if is_open_delimiter(char) {
                     parenthesis_depth += 1;
                     last1 = None;
                     continue;
                 }
-                if char == ')' {
+                if is_close_delimiter(char) {
                     parenthesis_depth -= 1;
                     if parenthesis_depth < 0 {
                         return Err(AssertionParsingError::NotMatchingParenthesis);
@@ -1792,11 +2852,11 @@ impl<'tcx> SpecParser<'tcx> {
         let mut assertions: Vec<UntypedAssertion> = Vec::new();
         let mut parenthesis_depth = 0;
         while let Some((position, char)) = iter.next() {
-            if char == '(' {
+            if is_open_delimiter(char) {
                 parenthesis_depth += 1;
                 continue;
             }
-            if char == ')' {
+            if is_close_delimiter(char) {
                 parenthesis_depth -= 1;
                 if parenthesis_depth < 0 {
                     return Err(AssertionParsingError::NotMatchingParenthesis);
@@ -1835,7 +2895,14 @@ impl<'tcx> SpecParser<'tcx> {
 }
 
 impl<'tcx> Folder for SpecParser<'tcx> {
-    fn fold_crate(&mut self, c: ast::Crate) -> ast::Crate {
+    fn fold_crate(&mut self, mut c: ast::Crate) -> ast::Crate {
+        if config::builtin_specs() && uses_prusti_contracts(&c) {
+            c.module.items.extend(self.parse_builtin_specs());
+        }
+        if config::contract_metadata() && uses_prusti_contracts(&c) {
+            let imported_contracts = self.parse_imported_contracts(&c);
+            c.module.items.extend(imported_contracts);
+        }
         let mut krate = fold::noop_fold_crate(c, self);
         // Avoid compiler error "unstable feature"
         krate.attrs.push(
@@ -1861,6 +2928,12 @@ impl<'tcx> Folder for SpecParser<'tcx> {
 
     fn fold_item(&mut self, item: ptr::P<ast::Item>) -> SmallVector<ptr::P<ast::Item>> {
         trace!("[fold_item] enter");
+        let was_in_extern_spec_module = self.in_extern_spec_module;
+        if let ast::ItemKind::Mod(..) = item.node {
+            if item.attrs.iter().any(|attr| attr.check_name("extern_spec")) {
+                self.in_extern_spec_module = true;
+            }
+        }
         let result = fold::noop_fold_item(item, self)
             .into_iter()
             .flat_map(|item| match item.node.clone() {
@@ -1870,6 +2943,9 @@ impl<'tcx> Folder for SpecParser<'tcx> {
                 // Structs
                 ast::ItemKind::Struct(..) => self.rewrite_struct_item(item),
 
+                // Foreign functions
+                ast::ItemKind::ForeignMod(..) => self.rewrite_foreign_mod(item),
+
                 // Impl methods
                 ast::ItemKind::Impl(
                     unsafety,
@@ -1895,6 +2971,29 @@ impl<'tcx> Folder for SpecParser<'tcx> {
                         }
                     }
 
+                    if self.in_extern_spec_module && ifce.is_none() {
+                        new_code_items.extend(new_spec_items);
+                        return self.rewrite_extern_spec_impl(
+                            item.into_inner(),
+                            generics,
+                            ty,
+                            new_code_items,
+                        );
+                    }
+
+                    // The spec items are declared in an inherent impl, where the associated
+                    // items of the implemented trait have to be named with a qualified path.
+                    if let Some(ref trait_ref) = ifce {
+                        let mut qualifier = SelfPathQualifier {
+                            self_ty: ty.clone(),
+                            trait_path: trait_ref.path.clone(),
+                        };
+                        new_spec_items = new_spec_items
+                            .into_iter()
+                            .flat_map(|spec_item| qualifier.fold_impl_item(spec_item))
+                            .collect();
+                    }
+
                     let mut new_items = SmallVector::new();
                     if !new_spec_items.is_empty() {
                         new_items.push(ptr::P(ast::Item {
@@ -1950,10 +3049,18 @@ impl<'tcx> Folder for SpecParser<'tcx> {
                     }))
                 }
 
+                // Modules, whose attributes apply to the procedures that they contain
+                ast::ItemKind::Mod(..) => {
+                    let mut item = item.into_inner();
+                    self.rewrite_tool_attributes(&mut item.attrs);
+                    SmallVector::one(ptr::P(item))
+                }
+
                 // Any other item
                 _ => SmallVector::one(item),
             })
             .collect();
+        self.in_extern_spec_module = was_in_extern_spec_module;
         trace!("[fold_item] exit");
         result
     }
@@ -1968,6 +3075,19 @@ impl<'tcx> Folder for SpecParser<'tcx> {
         }
     }
 
+    fn fold_stmt(&mut self, stmt: ast::Stmt) -> SmallVector<ast::Stmt> {
+        // The invariants in the body of a loop have already been replaced by `rewrite_loop`
+        if let ast::StmtKind::Mac(ref mac) = stmt.node {
+            if is_body_invariant(&mac.0) {
+                self.report_error(
+                    stmt.span,
+                    "`body_invariant!` can only be used in the body of a loop",
+                );
+            }
+        }
+        fold::noop_fold_stmt(stmt, self)
+    }
+
     fn fold_mac(&mut self, mac: ast::Mac) -> ast::Mac {
         mac
     }
@@ -1982,10 +3102,203 @@ pub enum AssertionParsingError {
     ParsingRustExpressionFailed,
     /// Reported when matching forall expression fails.
     FailedForallMatch,
+    /// Reported when matching exists expression fails.
+    FailedExistsMatch,
     /// Reported when matching after_expiry expression fails.
     FailedAfterExpiryMatch,
 }
 
+/// Returns true if the specification of the attribute is written as Rust tokens between
+/// parentheses, e.g. `#[requires(x > 0)]`, and not as a string with a reason, e.g.
+/// `#[requires(reason = "...", "x > 0")]`.
+fn is_token_spec(attribute: &ast::Attribute) -> bool {
+    use syntax::parse::token;
+    use syntax::tokenstream::TokenTree;
+
+    let trees: Vec<TokenTree> = attribute.tokens.trees().collect();
+    match trees.as_slice() {
+        [TokenTree::Delimited(_, delimited)] if delimited.delim == token::DelimToken::Paren => {
+            let inner: Vec<TokenTree> = delimited.stream().trees().take(2).collect();
+            match inner.as_slice() {
+                [
+                    TokenTree::Token(_, token::Token::Ident(ident, _)),
+                    TokenTree::Token(_, token::Token::Eq),
+                ] if ident.name == "reason" => false,
+                _ => true,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Converts the Rust tokens of a specification to a specification string. The quantifiers
+/// `forall(|vars| filter ==> body)` and `exists(|vars| body)` become
+/// `(forall vars :: filter ==> body)` and `(exists vars :: body)`, and the
+/// implications `implies(a, b)` become `((a) ==> (b))`. Returns `None` if the arguments of a
+/// quantifier or of an implication are malformed.
+fn spec_tokens_to_string(trees: &[syntax::tokenstream::TokenTree]) -> Option<String> {
+    use syntax::parse::token;
+    use syntax::print::pprust;
+    use syntax::tokenstream::TokenTree;
+
+    let is_token = |tree: &TokenTree, expected: &token::Token| match tree {
+        TokenTree::Token(_, token) => token == expected,
+        _ => false,
+    };
+    let mut parts = vec![];
+    let mut plain_start = 0;
+    let mut index = 0;
+    while index < trees.len() {
+        let keyword = match trees[index] {
+            TokenTree::Token(_, token::Token::Ident(ident, false)) => ident.name.as_str(),
+            TokenTree::Delimited(_, ref delimited) => {
+                // Convert the content of nested parentheses, brackets and braces
+                let (open, close) = match delimited.delim {
+                    token::DelimToken::Paren => ("(", ")"),
+                    token::DelimToken::Bracket => ("[", "]"),
+                    token::DelimToken::Brace => ("{", "}"),
+                    token::DelimToken::NoDelim => ("", ""),
+                };
+                let inner: Vec<TokenTree> = delimited.stream().trees().collect();
+                parts.push(plain_spec_tokens_to_string(&trees[plain_start..index]));
+                parts.push(format!("{}{}{}", open, spec_tokens_to_string(&inner)?, close));
+                index += 1;
+                plain_start = index;
+                continue;
+            }
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+        if &*keyword != "forall" && &*keyword != "exists" && &*keyword != "implies" {
+            index += 1;
+            continue;
+        }
+        // The arguments follow the keyword, or the `!` of a macro invocation
+        let mut args_index = index + 1;
+        if args_index < trees.len() && is_token(&trees[args_index], &token::Token::Not) {
+            args_index += 1;
+        }
+        let args: Vec<TokenTree> = match trees.get(args_index) {
+            Some(TokenTree::Delimited(_, delimited))
+                if delimited.delim == token::DelimToken::Paren =>
+            {
+                delimited.stream().trees().collect()
+            }
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+        let converted = if &*keyword == "forall" || &*keyword == "exists" {
+            let bar = token::Token::BinOp(token::BinOpToken::Or);
+            if args.is_empty() || !is_token(&args[0], &bar) {
+                return None;
+            }
+            let vars_end = 1 + args[1..].iter().position(|tree| is_token(tree, &bar))?;
+            format!(
+                "({} {} :: {})",
+                keyword,
+                pprust::tts_to_string(&args[1..vars_end]),
+                spec_tokens_to_string(&args[vars_end + 1..])?
+            )
+        } else {
+            let comma = args.iter().position(|tree| is_token(tree, &token::Token::Comma))?;
+            format!(
+                "(({}) ==> ({}))",
+                spec_tokens_to_string(&args[..comma])?,
+                spec_tokens_to_string(&args[comma + 1..])?
+            )
+        };
+        parts.push(plain_spec_tokens_to_string(&trees[plain_start..index]));
+        parts.push(converted);
+        index = args_index + 1;
+        plain_start = index;
+    }
+    parts.push(plain_spec_tokens_to_string(&trees[plain_start..]));
+    Some(parts.join(" "))
+}
+
+/// Prints Rust tokens of a specification. The lexer splits an implication `==>` into the
+/// tokens `==` and `>`, which are joined again.
+fn plain_spec_tokens_to_string(trees: &[syntax::tokenstream::TokenTree]) -> String {
+    use syntax::parse::token;
+    use syntax::print::pprust;
+    use syntax::tokenstream::TokenTree;
+
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut index = 0;
+    while index + 1 < trees.len() {
+        match (&trees[index], &trees[index + 1]) {
+            (TokenTree::Token(_, token::Token::EqEq), TokenTree::Token(_, token::Token::Gt)) => {
+                parts.push(pprust::tts_to_string(&trees[start..index]));
+                index += 2;
+                start = index;
+            }
+            _ => index += 1,
+        }
+    }
+    parts.push(pprust::tts_to_string(&trees[start..]));
+    parts.join(" ==> ")
+}
+
+/// Separates the nested tuple indices of an expression (e.g. `x.0.1` becomes `x.0 .1`), which
+/// would otherwise be lexed as a floating-point literal (e.g. `0.1`).
+fn split_nested_tuple_indices(string: &str) -> String {
+    let chars: Vec<char> = string.chars().collect();
+    let mut result = String::with_capacity(string.len());
+    let mut index = 0;
+    while index < chars.len() {
+        result.push(chars[index]);
+        let follows_expr = index > 0 && {
+            let prev = chars[index - 1];
+            prev.is_alphanumeric() || prev == '_' || prev == ')' || prev == ']'
+        };
+        if chars[index] == '.' && follows_expr {
+            let mut end = index + 1;
+            while end < chars.len() && chars[end].is_ascii_digit() {
+                end += 1;
+            }
+            result.extend(&chars[index + 1..end]);
+            if end > index + 1 && end < chars.len() && chars[end] == '.' {
+                result.push(' ');
+            }
+            index = end;
+        } else {
+            index += 1;
+        }
+    }
+    result
+}
+
+/// Opening delimiters that start a nested Rust expression, such as the arms of a `match`.
+/// Implications and conjunctions inside a nested expression are not split.
+fn is_open_delimiter(char: char) -> bool {
+    char == '(' || char == '{' || char == '['
+}
+
+fn is_close_delimiter(char: char) -> bool {
+    char == ')' || char == '}' || char == ']'
+}
+
+/// Returns true if the delimiters of `string` are balanced.
+fn is_balanced(string: &str) -> bool {
+    let mut depth = 0;
+    for char in string.chars() {
+        if is_open_delimiter(char) {
+            depth += 1;
+        } else if is_close_delimiter(char) {
+            depth -= 1;
+            if depth < 0 {
+                return false;
+            }
+        }
+    }
+    depth == 0
+}
+
 fn substring(string: &str, start: usize, end: usize) -> String {
     string
         .chars()
@@ -2019,6 +3332,62 @@ impl Folder for SpanRewriter {
     }
 }
 
+/// Rewrites the paths `Self::Name` to `<self_ty as trait_path>::Name`, so that the associated
+/// items of a trait resolve in the same way outside of the trait implementation.
+struct SelfPathQualifier {
+    self_ty: ptr::P<ast::Ty>,
+    trait_path: ast::Path,
+}
+
+impl SelfPathQualifier {
+    fn qualify(&self, path: &ast::Path) -> Option<(ast::QSelf, ast::Path)> {
+        if path.segments.len() < 2 || path.segments[0].ident.name != keywords::SelfType.name() {
+            return None;
+        }
+        let mut qualified_path = self.trait_path.clone();
+        qualified_path.span = path.span;
+        qualified_path
+            .segments
+            .extend(path.segments[1..].iter().cloned());
+        let qself = ast::QSelf {
+            ty: self.self_ty.clone(),
+            path_span: path.span,
+            position: self.trait_path.segments.len(),
+        };
+        Some((qself, qualified_path))
+    }
+}
+
+impl Folder for SelfPathQualifier {
+    fn fold_ty(&mut self, ty: ptr::P<ast::Ty>) -> ptr::P<ast::Ty> {
+        let ty = ty.map(|mut ty| {
+            if let ast::TyKind::Path(None, ref path) = ty.node.clone() {
+                if let Some((qself, path)) = self.qualify(path) {
+                    ty.node = ast::TyKind::Path(Some(qself), path);
+                }
+            }
+            ty
+        });
+        fold::noop_fold_ty(ty, self)
+    }
+
+    fn fold_expr(&mut self, expr: ptr::P<ast::Expr>) -> ptr::P<ast::Expr> {
+        let expr = expr.map(|mut expr| {
+            if let ast::ExprKind::Path(None, ref path) = expr.node.clone() {
+                if let Some((qself, path)) = self.qualify(path) {
+                    expr.node = ast::ExprKind::Path(Some(qself), path);
+                }
+            }
+            expr
+        });
+        expr.map(|expr| fold::noop_fold_expr(expr, self))
+    }
+
+    fn fold_mac(&mut self, mac: ast::Mac) -> ast::Mac {
+        fold::noop_fold_mac(mac, self)
+    }
+}
+
 fn shift_span(span: Span, offset: u32) -> Span {
     let offset = syntax::codemap::BytePos(offset);
     Span::new(span.lo() + offset, span.hi() + offset, span.ctxt())
@@ -2030,9 +3399,265 @@ fn shift_resize_span(span: Span, offset: u32, length: u32) -> Span {
     Span::new(lo, hi, span.ctxt())
 }
 
+/// Find whether the crate declares `extern crate prusti_contracts`, which the generated
+/// specification items rely on.
+fn uses_prusti_contracts(krate: &ast::Crate) -> bool {
+    krate.module.items.iter().any(|item| match item.node {
+        ast::ItemKind::ExternCrate(_) => item.ident.name == "prusti_contracts",
+        _ => false,
+    })
+}
+
 fn is_unsafe(fn_header: ast::FnHeader) -> bool {
     match fn_header.unsafety {
         ast::Unsafety::Unsafe => true,
         ast::Unsafety::Normal => false,
     }
 }
+
+/// Get the span that covers all the given expressions of an assertion.
+fn get_exprs_span(exprs: &[&ast::Expr]) -> Span {
+    exprs
+        .iter()
+        .map(|expr| expr.span)
+        .fold(exprs[0].span, |span, expr_span| span.to(expr_span))
+}
+
+/// Collects the names of the local variables that might be modified by an expression: assigned
+/// variables, mutably borrowed variables, receivers of method calls and new bindings.
+#[derive(Default)]
+struct ModifiedVariablesCollector {
+    names: HashSet<ast::Name>,
+}
+
+impl ModifiedVariablesCollector {
+    fn add_root(&mut self, expr: &ast::Expr) {
+        match expr.node {
+            ast::ExprKind::Path(None, ref path) if path.segments.len() == 1 => {
+                self.names.insert(path.segments[0].ident.name);
+            }
+            ast::ExprKind::Field(ref base, _)
+            | ast::ExprKind::Index(ref base, _)
+            | ast::ExprKind::Unary(ast::UnOp::Deref, ref base)
+            | ast::ExprKind::Paren(ref base) => self.add_root(base),
+            _ => {}
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for ModifiedVariablesCollector {
+    fn visit_expr(&mut self, expr: &'a ast::Expr) {
+        if expr.attrs.iter().any(|attr| attr.check_name("__PRUSTI_SPEC_ONLY")) {
+            // The type-checking blocks of the invariants written in the body of the loop
+            return;
+        }
+        match expr.node {
+            ast::ExprKind::Assign(ref lhs, _) | ast::ExprKind::AssignOp(_, ref lhs, _) => {
+                self.add_root(lhs)
+            }
+            ast::ExprKind::AddrOf(ast::Mutability::Mutable, ref place) => self.add_root(place),
+            ast::ExprKind::MethodCall(_, ref args) => self.add_root(&args[0]),
+            _ => {}
+        }
+        visit::walk_expr(self, expr);
+    }
+
+    fn visit_pat(&mut self, pat: &'a ast::Pat) {
+        if let ast::PatKind::Ident(_, ident, _) = pat.node {
+            self.names.insert(ident.name);
+        }
+        visit::walk_pat(self, pat);
+    }
+
+    fn visit_mac(&mut self, _mac: &'a ast::Mac) {
+        // Macros are not expanded yet
+    }
+}
+
+/// Collects the names of the variables mentioned by an expression.
+#[derive(Default)]
+struct MentionedVariablesCollector {
+    names: HashSet<ast::Name>,
+}
+
+impl<'a> Visitor<'a> for MentionedVariablesCollector {
+    fn visit_expr(&mut self, expr: &'a ast::Expr) {
+        if let ast::ExprKind::Path(None, ref path) = expr.node {
+            if path.segments.len() == 1 {
+                self.names.insert(path.segments[0].ident.name);
+            }
+        }
+        visit::walk_expr(self, expr);
+    }
+
+    fn visit_mac(&mut self, _mac: &'a ast::Mac) {
+        // Macros are not expanded yet
+    }
+}
+
+/// Collects the names of the variables bound by patterns (e.g. in `let` statements).
+#[derive(Default)]
+struct BoundVariablesCollector {
+    names: HashSet<ast::Name>,
+}
+
+impl<'a> Visitor<'a> for BoundVariablesCollector {
+    fn visit_pat(&mut self, pat: &'a ast::Pat) {
+        if let ast::PatKind::Ident(_, ident, _) = pat.node {
+            self.names.insert(ident.name);
+        }
+        visit::walk_pat(self, pat);
+    }
+
+    fn visit_mac(&mut self, _mac: &'a ast::Mac) {
+        // Macros are not expanded yet
+    }
+}
+
+/// Checks whether an expression contains an `old(..)` expression.
+#[derive(Default)]
+struct OldExpressionsCollector {
+    found: bool,
+}
+
+impl<'a> Visitor<'a> for OldExpressionsCollector {
+    fn visit_expr(&mut self, expr: &'a ast::Expr) {
+        if let ast::ExprKind::Call(ref func, _) = expr.node {
+            if let ast::ExprKind::Path(None, ref path) = func.node {
+                if path.segments.len() == 1 && path.segments[0].ident.name == "old" {
+                    self.found = true;
+                }
+            }
+        }
+        visit::walk_expr(self, expr);
+    }
+
+    fn visit_mac(&mut self, _mac: &'a ast::Mac) {
+        // Macros are not expanded yet
+    }
+}
+
+/// Checks whether the values of a type are references or of a primitive type, so that an
+/// argument of that type cannot be moved by the body of a function.
+fn is_copy_or_reference(ty: &ast::Ty) -> bool {
+    match ty.node {
+        ast::TyKind::Rptr(..) => true,
+        ast::TyKind::Paren(ref ty) => is_copy_or_reference(ty),
+        ast::TyKind::Path(None, ref path) if path.segments.len() == 1 => {
+            match &*path.segments[0].ident.name.as_str() {
+                "bool" | "char" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8"
+                | "u16" | "u32" | "u64" | "u128" | "usize" | "f32" | "f64" => true,
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Adds the runtime checks of the postconditions of a function before each `return` of its body,
+/// except for the ones in nested closures and items.
+struct ReturnRewriter<'a, 'tcx: 'a> {
+    ast_builder: &'a MinimalAstBuilder<'tcx>,
+    checks: Vec<ast::Stmt>,
+    /// The return type of the function, if it can be written in a `let` statement.
+    result_ty: Option<ptr::P<ast::Ty>>,
+}
+
+impl<'a, 'tcx> ReturnRewriter<'a, 'tcx> {
+    /// Build `{ let result: T = expr; <checks>; result }`.
+    fn check_result(&self, expr: ptr::P<ast::Expr>) -> ptr::P<ast::Expr> {
+        let builder = self.ast_builder;
+        let span = expr.span;
+        let result = builder.ident_of("result");
+        let mut statements = vec![match self.result_ty {
+            Some(ref ty) => builder.stmt_let_typed(span, false, result, ty.clone(), expr),
+            None => builder.stmt_let(span, false, result, expr),
+        }];
+        statements.extend(self.checks.iter().cloned());
+        statements.push(builder.stmt_expr(builder.expr_ident(span, result)));
+        builder.expr_block(builder.block(span, statements))
+    }
+}
+
+impl<'a, 'tcx> Folder for ReturnRewriter<'a, 'tcx> {
+    fn fold_expr(&mut self, expr: ptr::P<ast::Expr>) -> ptr::P<ast::Expr> {
+        match expr.node {
+            ast::ExprKind::Closure(..) => expr,
+            _ => expr.map(|expr| {
+                let mut expr = fold::noop_fold_expr(expr, self);
+                let span = expr.span;
+                if let ast::ExprKind::Ret(ref mut value) = expr.node {
+                    let returned = value
+                        .take()
+                        .unwrap_or_else(|| self.ast_builder.expr_tuple(span, vec![]));
+                    *value = Some(self.check_result(returned));
+                }
+                expr
+            }),
+        }
+    }
+
+    fn fold_item(&mut self, item: ptr::P<ast::Item>) -> SmallVector<ptr::P<ast::Item>> {
+        SmallVector::one(item)
+    }
+
+    fn fold_mac(&mut self, mac: ast::Mac) -> ast::Mac {
+        mac
+    }
+}
+
+/// Returns true if the macro is a `body_invariant!(..)`.
+fn is_body_invariant(mac: &ast::Mac) -> bool {
+    let segments = &mac.node.path.segments;
+    segments.len() == 1 && segments[0].ident.name == "body_invariant"
+}
+
+/// Replaces the `body_invariant!(..)` statements in the body of a loop by blocks that type-check
+/// the invariants, and collects the parsed invariants. Nested loops and closures are skipped.
+struct BodyInvariantRewriter<'p, 'tcx: 'p> {
+    parser: &'p mut SpecParser<'tcx>,
+    spec_id: SpecID,
+    invariants: Vec<UntypedSpecification>,
+}
+
+impl<'p, 'tcx> Folder for BodyInvariantRewriter<'p, 'tcx> {
+    fn fold_stmt(&mut self, stmt: ast::Stmt) -> SmallVector<ast::Stmt> {
+        let invariant_mac = match stmt.node {
+            ast::StmtKind::Mac(ref mac) if is_body_invariant(&mac.0) => Some(mac.0.clone()),
+            _ => None,
+        };
+        match invariant_mac {
+            Some(mac) => match self.parser.parse_body_invariant(&mac) {
+                Some(invariant) => {
+                    let invariants = vec![invariant];
+                    let statement =
+                        self.parser
+                            .build_loop_spec_block(stmt.span, self.spec_id, &invariants);
+                    self.invariants.extend(invariants);
+                    SmallVector::one(statement)
+                }
+                None => SmallVector::new(),
+            },
+            None => fold::noop_fold_stmt(stmt, self),
+        }
+    }
+
+    fn fold_expr(&mut self, expr: ptr::P<ast::Expr>) -> ptr::P<ast::Expr> {
+        match expr.node {
+            ast::ExprKind::While(..)
+            | ast::ExprKind::WhileLet(..)
+            | ast::ExprKind::ForLoop(..)
+            | ast::ExprKind::Loop(..)
+            | ast::ExprKind::Closure(..) => expr,
+            _ => expr.map(|expr| fold::noop_fold_expr(expr, self)),
+        }
+    }
+
+    fn fold_item(&mut self, item: ptr::P<ast::Item>) -> SmallVector<ptr::P<ast::Item>> {
+        SmallVector::one(item)
+    }
+
+    fn fold_mac(&mut self, mac: ast::Mac) -> ast::Mac {
+        mac
+    }
+}
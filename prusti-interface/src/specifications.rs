@@ -23,15 +23,21 @@ pub enum SpecType {
     Precondition,
     /// Postcondition of a procedure.
     Postcondition,
+    /// Postcondition of a procedure that may panic, which holds when the procedure panics.
+    PanicPostcondition,
     /// Loop invariant or struct invariant
     Invariant,
+    /// Two-state invariant of a struct, that relates the state of `self` before and after
+    /// each call of a method that takes `&mut self`.
+    HistoryInvariant,
 }
 
 #[derive(Debug)]
 /// A conversion from string into specification type error.
 pub enum TryFromStringError {
     /// Reported when the string being converted is not one of the
-    /// following: `requires`, `ensures`, `invariant`.
+    /// following: `requires`, `ensures`, `ensures_on_panic`, `invariant`,
+    /// `history_invariant`.
     UnknownSpecificationType,
 }
 
@@ -42,7 +48,9 @@ impl<'a> TryFrom<&'a str> for SpecType {
         match typ {
             "requires" => Ok(SpecType::Precondition),
             "ensures" => Ok(SpecType::Postcondition),
+            "ensures_on_panic" => Ok(SpecType::PanicPostcondition),
             "invariant" => Ok(SpecType::Invariant),
+            "history_invariant" => Ok(SpecType::HistoryInvariant),
             _ => Err(TryFromStringError::UnknownSpecificationType),
         }
     }
@@ -201,6 +209,8 @@ pub enum AssertionKind<ET, AT> {
     TypeCond(ForAllVars<AT>, Assertion<ET, AT>),
     /// Quantifier (forall vars :: {triggers} filter ==> body)
     ForAll(ForAllVars<AT>, TriggerSet<ET>, Assertion<ET, AT>),
+    /// Quantifier (exists vars :: {triggers} body)
+    Exists(ForAllVars<AT>, TriggerSet<ET>, Assertion<ET, AT>),
     /// Pledge after_expiry<reference>(rhs)
     ///     or after_expiry_if<reference>(lhs,rhs)
     Pledge(
@@ -220,12 +230,21 @@ pub struct Specification<ET, AT> {
     pub typ: SpecType,
     /// Actual specification.
     pub assertion: Assertion<ET, AT>,
+    /// The user-provided explanation of the specification, reported when it does not hold.
+    pub reason: Option<String>,
+}
+
+impl<ET, AT> Specification<ET, AT> {
+    /// Returns true for both single-state and history invariants.
+    pub fn is_invariant(&self) -> bool {
+        self.typ == SpecType::Invariant || self.typ == SpecType::HistoryInvariant
+    }
 }
 
 #[derive(Debug, Clone)]
 /// Specification of a single element such as procedure or loop.
 pub enum SpecificationSet<ET, AT> {
-    /// (Precondition, Postcondition)
+    /// (Precondition, Postcondition). The postcondition also contains the panic postconditions.
     Procedure(Vec<Specification<ET, AT>>, Vec<Specification<ET, AT>>),
     /// Loop invariant.
     Loop(Vec<Specification<ET, AT>>),
@@ -293,7 +312,8 @@ impl TypedAssertion {
                 spans.extend(rhs.get_spans());
                 spans
             }
-            AssertionKind::ForAll(ref _vars, ref _trigger_set, ref body) => {
+            AssertionKind::ForAll(ref _vars, ref _trigger_set, ref body)
+            | AssertionKind::Exists(ref _vars, ref _trigger_set, ref body) => {
                 // FIXME: include the variables
                 body.get_spans()
             }
@@ -309,4 +329,102 @@ impl TypedAssertion {
             }
         }
     }
+
+    /// The Rust expressions that occur in the assertion, including the ones of quantifier
+    /// triggers and of pledges.
+    pub fn get_exprs(&self) -> Vec<&rustc::hir::Expr> {
+        match *self.kind {
+            AssertionKind::Expr(ref assertion_expr) => vec![&assertion_expr.expr],
+            AssertionKind::And(ref assertions) => assertions
+                .iter()
+                .flat_map(|assertion| assertion.get_exprs())
+                .collect(),
+            AssertionKind::Implies(ref lhs, ref rhs) => {
+                let mut exprs = vec![&lhs.expr];
+                exprs.extend(rhs.get_exprs());
+                exprs
+            }
+            AssertionKind::ForAll(_, ref trigger_set, ref body)
+            | AssertionKind::Exists(_, ref trigger_set, ref body) => {
+                let mut exprs: Vec<_> = trigger_set
+                    .triggers()
+                    .iter()
+                    .flat_map(|trigger| trigger.terms().iter().map(|term| &term.expr))
+                    .collect();
+                exprs.extend(body.get_exprs());
+                exprs
+            }
+            AssertionKind::Pledge(ref reference, ref lhs, ref rhs) => {
+                let mut exprs: Vec<_> = reference.iter().map(|reference| &reference.expr).collect();
+                exprs.extend(lhs.get_exprs());
+                exprs.extend(rhs.get_exprs());
+                exprs
+            }
+            AssertionKind::TypeCond(_, ref body) => body.get_exprs(),
+        }
+    }
+}
+
+impl UntypedAssertion {
+    /// Check whether the assertion holds in every state, because its conclusions are just the
+    /// literal `true`.
+    pub fn is_trivially_true(&self) -> bool {
+        match *self.kind {
+            AssertionKind::Expr(ref assertion_expr) => is_true_literal(&assertion_expr.expr),
+            AssertionKind::And(ref assertions) => {
+                assertions.iter().all(|assertion| assertion.is_trivially_true())
+            }
+            AssertionKind::Implies(_, ref rhs) => rhs.is_trivially_true(),
+            AssertionKind::ForAll(_, _, ref body) => body.is_trivially_true(),
+            AssertionKind::Exists(_, _, ref body) => body.is_trivially_true(),
+            AssertionKind::TypeCond(_, ref body) => body.is_trivially_true(),
+            AssertionKind::Pledge(..) => false,
+        }
+    }
+
+    /// Get all the Rust expressions that occur in the assertion.
+    pub fn get_exprs(&self) -> Vec<&ast::Expr> {
+        match *self.kind {
+            AssertionKind::Expr(ref assertion_expr) => vec![&*assertion_expr.expr],
+            AssertionKind::And(ref assertions) => assertions
+                .iter()
+                .flat_map(|assertion| assertion.get_exprs())
+                .collect(),
+            AssertionKind::Implies(ref lhs, ref rhs) => {
+                let mut exprs = vec![&*lhs.expr];
+                exprs.extend(rhs.get_exprs());
+                exprs
+            }
+            AssertionKind::ForAll(_, ref trigger_set, ref body)
+            | AssertionKind::Exists(_, ref trigger_set, ref body) => {
+                let mut exprs: Vec<&ast::Expr> = trigger_set
+                    .triggers()
+                    .iter()
+                    .flat_map(|trigger| trigger.terms().iter().map(|term| &*term.expr))
+                    .collect();
+                exprs.extend(body.get_exprs());
+                exprs
+            }
+            AssertionKind::Pledge(ref reference, ref lhs, ref rhs) => {
+                let mut exprs: Vec<&ast::Expr> =
+                    reference.iter().map(|reference| &*reference.expr).collect();
+                exprs.extend(lhs.get_exprs());
+                exprs.extend(rhs.get_exprs());
+                exprs
+            }
+            AssertionKind::TypeCond(_, ref body) => body.get_exprs(),
+        }
+    }
+}
+
+/// Check whether the expression is the literal `true`, possibly in parentheses.
+fn is_true_literal(expr: &ast::Expr) -> bool {
+    match expr.node {
+        ast::ExprKind::Lit(ref lit) => match lit.node {
+            ast::LitKind::Bool(value) => value,
+            _ => false,
+        },
+        ast::ExprKind::Paren(ref inner) => is_true_literal(inner),
+        _ => false,
+    }
 }
@@ -30,6 +30,15 @@ pub fn is_prefix(place: &mir::Place, potential_prefix: &mir::Place) -> bool {
     }
 }
 
+/// Check if the place is rooted in a `static` item, e.g. `X` or `X.f.g`.
+pub fn is_static_place(place: &mir::Place) -> bool {
+    match place {
+        mir::Place::Local(_) => false,
+        mir::Place::Static(_) => true,
+        mir::Place::Projection(box mir::Projection { base, .. }) => is_static_place(base),
+    }
+}
+
 /// Expands a place `x.f.g` of type struct into a vector of places for
 /// each of the struct's fields `{x.f.g.f, x.f.g.g, x.f.g.h}`. If
 /// `without_field` is not `None`, then omits that field from the final
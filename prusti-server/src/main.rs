@@ -0,0 +1,30 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate env_logger;
+extern crate prusti_interface;
+extern crate prusti_viper;
+extern crate viper;
+
+use prusti_interface::config;
+use std::env;
+use std::net::TcpListener;
+use viper::{VerificationBackend, Viper};
+
+fn main() {
+    env_logger::init();
+
+    let address = env::args().nth(1).unwrap_or_else(|| "127.0.0.1:0".to_string());
+    let listener = TcpListener::bind(&address).unwrap_or_else(|error| {
+        panic!("Failed to listen on '{}': {}", address, error);
+    });
+
+    let backend = VerificationBackend::from_str(&config::viper_backend());
+    let viper = Viper::new_with_args(config::extra_jvm_args(), backend);
+
+    println!("Verifier daemon listening on {}", listener.local_addr().unwrap());
+    prusti_viper::daemon::serve(&viper, backend, listener);
+}
@@ -1,3 +1,4 @@
+use std::env;
 use std::process::Command;
 
 fn main(){
@@ -17,10 +18,25 @@ where
         prusti_rustc_path.set_extension("exe");
     }
 
+    // Re-verify only the methods that changed since the last successful run, unless the user
+    // explicitly configured otherwise.
+    let incremental = env::var("PRUSTI_INCREMENTAL").unwrap_or_else(|_| "true".to_string());
+    let cache_dir = env::var("PRUSTI_CACHE_DIR").unwrap_or_else(|_| {
+        let target_dir = env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
+        env::current_dir()
+            .expect("current directory invalid")
+            .join(target_dir)
+            .join("prusti-cache")
+            .to_string_lossy()
+            .to_string()
+    });
+
     let exit_status = Command::new("cargo".to_string())
         .arg("check")
         .args(args)
         .env("PRUSTI_FULL_COMPILATION", "true")
+        .env("PRUSTI_INCREMENTAL", incremental)
+        .env("PRUSTI_CACHE_DIR", cache_dir)
         .env("RUSTC_WRAPPER", prusti_rustc_path)
         .spawn()
         .expect("could not run cargo")
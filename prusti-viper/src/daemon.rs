@@ -0,0 +1,165 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A verifier daemon, which keeps the JVM and the Viper backend running across the verification
+//! of different crates, and the client that the verifier uses to talk to it.
+//!
+//! Each TCP connection carries a single request: the client writes a JSON-encoded
+//! `VerificationRequest` and shuts down its side of the connection, then the daemon writes a
+//! JSON-encoded `VerificationResponse` and closes the connection.
+
+use encoder::vir::{self, ToViper};
+use serde_json;
+use std::collections::HashMap;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use viper::{self, VerificationBackend, Viper};
+
+#[derive(Serialize, Deserialize)]
+pub struct VerificationRequest {
+    pub program: vir::Program,
+    /// The backend expected by the client. It has to match the one of the daemon.
+    pub backend: String,
+    pub verifier_args: Vec<String>,
+}
+
+/// The result of the verification, or a description of why the daemon could not verify the
+/// program.
+pub type VerificationResponse = Result<viper::VerificationResult, String>;
+
+/// Sends the encoded programs to the verifier daemon listening at `address`.
+pub struct DaemonClient {
+    address: String,
+}
+
+impl DaemonClient {
+    pub fn new(address: String) -> Self {
+        DaemonClient { address }
+    }
+
+    pub fn verify(&self, request: &VerificationRequest) -> VerificationResponse {
+        let mut stream = TcpStream::connect(&self.address).map_err(|error| {
+            format!("failed to connect to the verifier daemon at '{}': {}", self.address, error)
+        })?;
+        serde_json::to_writer(&mut stream, request)
+            .map_err(|error| format!("failed to send the program: {}", error))?;
+        stream
+            .shutdown(Shutdown::Write)
+            .map_err(|error| format!("failed to send the program: {}", error))?;
+        serde_json::from_reader::<_, VerificationResponse>(stream)
+            .map_err(|error| format!("failed to receive the verification result: {}", error))?
+    }
+}
+
+/// Verify the programs received on `listener`, until the process is killed.
+///
+/// A started verifier is kept for each distinct list of verifier arguments, so that only the
+/// first request of a client pays for the startup of the backend.
+pub fn serve(viper: &Viper, backend: VerificationBackend, listener: TcpListener) {
+    let verification_ctx = viper.new_verification_context();
+    let ast_factory = verification_ctx.new_ast_factory();
+    let mut verifiers = HashMap::new();
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| {
+            let request: VerificationRequest = serde_json::from_reader(&stream)?;
+            let response: VerificationResponse =
+                if request.backend.to_lowercase() != backend.to_string().to_lowercase() {
+                    Err(format!(
+                        "the verifier daemon uses {}, but {} was requested",
+                        backend, request.backend
+                    ))
+                } else {
+                    let verifier = verifiers
+                        .entry(request.verifier_args.clone())
+                        .or_insert_with(|| {
+                            info!("Starting {} with arguments {:?}", backend, request.verifier_args);
+                            verification_ctx.new_verifier_with_args(
+                                backend,
+                                request.verifier_args.clone(),
+                                None,
+                            )
+                        });
+                    Ok(verifier.verify(request.program.to_viper(&ast_factory)))
+                };
+            serde_json::to_writer(&stream, &response)?;
+            Ok(())
+        });
+        if let Err(error) = result {
+            warn!("Failed to serve a verification request: {}", error);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoder::vir::ast::*;
+    use encoder::vir::cfg::{CfgMethod, Successor};
+    use std::thread;
+
+    lazy_static! {
+        static ref VIPER: Viper = Viper::new();
+    }
+
+    /// A program with a method that assumes `x > 0` and asserts `x > bound`.
+    fn program(bound: i64) -> vir::Program {
+        let x = LocalVar::new("x", Type::Int);
+        let mut method = CfgMethod::new("m".to_string(), vec![x.clone()], vec![], vec![], vec![]);
+        let stmts = vec![
+            Stmt::Inhale(Expr::gt_cmp(Expr::local(x.clone()), 0.into()), FoldingBehaviour::Stmt),
+            Stmt::Assert(
+                Expr::gt_cmp(Expr::local(x), bound.into()),
+                FoldingBehaviour::Stmt,
+                Position::new(1, 1, "1".to_string()),
+            ),
+        ];
+        let block = method.add_block("start", vec![], stmts);
+        method.set_successor(block, Successor::Return);
+        vir::Program {
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![method],
+            functions: vec![],
+            predicates: vec![],
+        }
+    }
+
+    fn request(program: vir::Program) -> VerificationRequest {
+        VerificationRequest {
+            program,
+            backend: VerificationBackend::Silicon.to_string(),
+            verifier_args: vec![],
+        }
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let request = request(program(0));
+        let json = serde_json::to_string(&request).unwrap();
+        let deserialized: VerificationRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.program.to_text(), request.program.to_text());
+        assert_eq!(deserialized.backend, request.backend);
+        assert_eq!(deserialized.verifier_args, request.verifier_args);
+    }
+
+    #[test]
+    fn test_daemon_and_local_results_agree() {
+        let backend = VerificationBackend::Silicon;
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = DaemonClient::new(listener.local_addr().unwrap().to_string());
+        thread::spawn(move || serve(&VIPER, backend, listener));
+
+        let verification_ctx = VIPER.new_verification_context();
+        let ast_factory = verification_ctx.new_ast_factory();
+        let verifier = verification_ctx.new_verifier(backend, None);
+        for &bound in &[0, 1] {
+            let local_result = verifier.verify(program(bound).to_viper(&ast_factory));
+            let daemon_result = client.verify(&request(program(bound)));
+            assert_eq!(daemon_result, Ok(local_result));
+        }
+    }
+}
@@ -7,8 +7,8 @@
 use encoder::places;
 use prusti_interface::data::ProcedureDefId;
 use prusti_interface::specifications::{
-    AssertionKind, SpecificationSet, TypedAssertion, TypedExpression, TypedSpecification,
-    TypedSpecificationSet,
+    AssertionKind, SpecType, SpecificationSet, TypedAssertion, TypedExpression,
+    TypedSpecification, TypedSpecificationSet,
 };
 use rustc::hir::{self, Mutability};
 use rustc::mir;
@@ -70,6 +70,8 @@ where
     L: fmt::Debug,
     P: fmt::Debug,
 {
+    /// The procedure to which the contract belongs.
+    pub def_id: ProcedureDefId,
     /// Formal arguments for which we should have permissions in the
     /// precondition. This includes both borrows and moved in values.
     /// For example, if `_2` is in the vector, this means that we have
@@ -87,6 +89,11 @@ where
     /// Magic wands passed out of the procedure.
     /// TODO: Implement support for `blocked_lifetimes` via nested magic wands.
     pub borrow_infos: Vec<BorrowInfo<P>>,
+    /// The place of a borrowed argument that is reborrowed by the returned
+    /// reference, if the procedure is a simple getter. For example, if the
+    /// procedure returns `&mut (*_1).0`, this is `(*_1).0`. It is used to
+    /// infer the pledge of the procedure when the user does not write one.
+    pub reborrowed_place: Option<P>,
     /// The functional specification: precondition and postcondition
     specification: TypedSpecificationSet,
 }
@@ -100,9 +107,23 @@ impl<L: fmt::Debug, P: fmt::Debug> ProcedureContractGeneric<L, P> {
         }
     }
 
-    pub fn functional_postcondition(&self) -> &[TypedSpecification] {
+    pub fn functional_postcondition(&self) -> Vec<&TypedSpecification> {
+        self.postcondition_items(SpecType::Postcondition)
+    }
+
+    /// The postcondition that holds when the procedure panics.
+    pub fn functional_panic_postcondition(&self) -> Vec<&TypedSpecification> {
+        self.postcondition_items(SpecType::PanicPostcondition)
+    }
+
+    /// Returns true if the procedure declares that it may panic.
+    pub fn may_panic(&self) -> bool {
+        !self.functional_panic_postcondition().is_empty()
+    }
+
+    fn postcondition_items(&self, typ: SpecType) -> Vec<&TypedSpecification> {
         if let SpecificationSet::Procedure(_, ref post) = self.specification {
-            post
+            post.iter().filter(|item| item.typ == typ).collect()
         } else {
             unreachable!("Unexpected: {:?}", self.specification)
         }
@@ -118,7 +139,8 @@ impl<L: fmt::Debug, P: fmt::Debug> ProcedureContractGeneric<L, P> {
                 AssertionKind::Expr(_)
                 | AssertionKind::Implies(_, _)
                 | AssertionKind::TypeCond(_, _)
-                | AssertionKind::ForAll(_, _, _) => {}
+                | AssertionKind::ForAll(_, _, _)
+                | AssertionKind::Exists(_, _, _) => {}
                 AssertionKind::And(ref assertions) => {
                     for assertion in assertions {
                         check_assertion(assertion, pledges);
@@ -190,6 +212,7 @@ impl<'tcx> ProcedureContractMirDef<'tcx> {
             })
             .collect();
         ProcedureContract {
+            def_id: self.def_id,
             args: self.args.iter().map(|&a| a.into()).collect(),
             returned_refs: self
                 .returned_refs
@@ -198,6 +221,7 @@ impl<'tcx> ProcedureContractMirDef<'tcx> {
                 .collect(),
             returned_value: self.returned_value.into(),
             borrow_infos,
+            reborrowed_place: self.reborrowed_place.as_ref().map(|p| p.into()),
             specification: self.specification.clone(),
         }
     }
@@ -232,11 +256,17 @@ impl<'tcx> ProcedureContractMirDef<'tcx> {
             })
             .collect();
         let returned_refs = self.returned_refs.iter().map(&substitute).collect();
+        let reborrowed_place = self
+            .reborrowed_place
+            .as_ref()
+            .map(|place| substitute(&(place.clone(), Mutability::MutMutable)).0);
         let result = ProcedureContract {
+            def_id: self.def_id,
             args: args.clone(),
             returned_refs: returned_refs,
             returned_value: target,
             borrow_infos,
+            reborrowed_place,
             specification: self.specification.clone(),
         };
         result
@@ -430,14 +460,140 @@ where
         .into_iter()
         .filter(|(place, _)| is_not_blocked(place))
         .collect();
-    let contract = ProcedureContractGeneric {
+    let mut contract = ProcedureContractGeneric {
+        def_id: proc_def_id,
         args: fake_mir_args,
         returned_refs,
         returned_value: mir::RETURN_PLACE,
         borrow_infos,
+        reborrowed_place: None,
         specification,
     };
+    if contract.pledges().is_empty() && is_simple_getter_signature(&contract, return_ty) {
+        contract.reborrowed_place = find_reborrowed_place(tcx, proc_def_id, &contract);
+    }
 
     trace!("[compute_borrow_infos] exit result={}", contract);
     contract
 }
+
+/// Checks whether the procedure returns a mutable reference to a primitive value
+/// that blocks a single mutable reference passed as argument.
+fn is_simple_getter_signature<'tcx>(
+    contract: &ProcedureContractMirDef<'tcx>,
+    return_ty: Ty<'tcx>,
+) -> bool {
+    let is_primitive_ref = match return_ty.sty {
+        ty::TypeVariants::TyRef(_, ty, Mutability::MutMutable) => match ty.sty {
+            ty::TypeVariants::TyBool
+            | ty::TypeVariants::TyInt(..)
+            | ty::TypeVariants::TyUint(..)
+            | ty::TypeVariants::TyChar => true,
+            _ => false,
+        },
+        _ => false,
+    };
+    is_primitive_ref
+        && contract.borrow_infos.len() == 1
+        && contract.borrow_infos[0].blocked_paths.len() == 1
+        && contract.borrow_infos[0].blocked_paths[0].1 == Mutability::MutMutable
+}
+
+/// Looks in the body of the procedure for the place reborrowed by the returned
+/// reference. The place has to be reachable from the blocked argument only via
+/// field accesses, as in `&mut self.f.g`.
+fn find_reborrowed_place<'a, 'tcx>(
+    tcx: TyCtxt<'a, 'tcx, 'tcx>,
+    proc_def_id: ProcedureDefId,
+    contract: &ProcedureContractMirDef<'tcx>,
+) -> Option<mir::Place<'tcx>> {
+    if !proc_def_id.is_local() {
+        return None;
+    }
+    let mir = tcx.mir_validated(proc_def_id).borrow();
+
+    // The right-hand side of the assignments to locals that are assigned
+    // exactly once, or `None` for the other assigned locals.
+    let mut assignments: HashMap<mir::Local, Option<&mir::Rvalue<'tcx>>> = HashMap::new();
+    for bb_data in mir.basic_blocks().iter() {
+        for stmt in &bb_data.statements {
+            if let mir::StatementKind::Assign(mir::Place::Local(local), ref rvalue) = stmt.kind {
+                let is_first = !assignments.contains_key(&local);
+                assignments.insert(local, if is_first { Some(rvalue) } else { None });
+            }
+        }
+        if let Some(mir::TerminatorKind::Call {
+            destination: Some((mir::Place::Local(local), _)),
+            ..
+        }) = bb_data.terminator.as_ref().map(|terminator| &terminator.kind)
+        {
+            assignments.insert(*local, None);
+        }
+    }
+
+    let reborrowed_place = match assignments.get(&mir::RETURN_PLACE) {
+        Some(&Some(&mir::Rvalue::Ref(_, mir::BorrowKind::Mut { .. }, ref place))) => {
+            resolve_place(&mir, &assignments, place)?
+        }
+        _ => return None,
+    };
+
+    // Only field accesses are allowed below the blocked reference.
+    let blocked_place = &contract.borrow_infos[0].blocked_paths[0].0;
+    let mut current_place = &reborrowed_place;
+    loop {
+        if current_place == blocked_place {
+            return Some(reborrowed_place.clone());
+        }
+        match current_place {
+            &mir::Place::Projection(box mir::Projection {
+                ref base,
+                elem: mir::ProjectionElem::Field(..),
+            }) => {
+                current_place = base;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Expresses `place` in terms of the arguments of the procedure, by following
+/// the assignments of temporary variables.
+fn resolve_place<'tcx>(
+    mir: &mir::Mir<'tcx>,
+    assignments: &HashMap<mir::Local, Option<&mir::Rvalue<'tcx>>>,
+    place: &mir::Place<'tcx>,
+) -> Option<mir::Place<'tcx>> {
+    match place {
+        &mir::Place::Local(local) => {
+            if local.index() > 0 && local.index() <= mir.arg_count {
+                return Some(place.clone());
+            }
+            match assignments.get(&local) {
+                Some(&Some(&mir::Rvalue::Use(mir::Operand::Move(ref operand_place))))
+                | Some(&Some(&mir::Rvalue::Use(mir::Operand::Copy(ref operand_place)))) => {
+                    resolve_place(mir, assignments, operand_place)
+                }
+                _ => None,
+            }
+        }
+        &mir::Place::Projection(box mir::Projection { ref base, ref elem }) => match elem {
+            &mir::ProjectionElem::Field(field, ty) => {
+                Some(resolve_place(mir, assignments, base)?.field(field, ty))
+            }
+            &mir::ProjectionElem::Deref => {
+                if let &mir::Place::Local(local) = base {
+                    // Dereferencing a temporary reference gives the borrowed place.
+                    if let Some(&Some(&mir::Rvalue::Ref(_, _, ref borrowed_place))) =
+                        assignments.get(&local)
+                    {
+                        return resolve_place(mir, assignments, borrowed_place);
+                    }
+                }
+                Some(resolve_place(mir, assignments, base)?.deref())
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
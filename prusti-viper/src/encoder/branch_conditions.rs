@@ -0,0 +1,289 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Human-readable descriptions of the branch decisions (e.g. "`x < 0` is true") that are taken
+//! on every path that reaches a basic block, used to explain in which case a verification error
+//! happens.
+
+use encoder::Encoder;
+use prusti_interface::environment::BasicBlockIndex;
+use prusti_interface::environment::Procedure;
+use rustc::mir;
+use rustc::ty;
+use std::collections::HashMap;
+use syntax::codemap::Span;
+use syntax_pos::hygiene::SyntaxContext;
+
+pub struct BranchConditions {
+    /// The decisions that are taken on every path from the entry to the block.
+    block_conditions: HashMap<BasicBlockIndex, Vec<String>>,
+    /// The decision taken by an edge of the CFG, if the edge is a branch.
+    edge_conditions: HashMap<(BasicBlockIndex, BasicBlockIndex), String>,
+}
+
+impl BranchConditions {
+    pub fn new<'v, 'r, 'a, 'tcx>(
+        encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+        procedure: &Procedure<'a, 'tcx>,
+    ) -> Self {
+        let describer = DecisionDescriber {
+            encoder,
+            mir: procedure.get_mir(),
+        };
+        let mut branch_conditions = BranchConditions {
+            block_conditions: HashMap::new(),
+            edge_conditions: HashMap::new(),
+        };
+
+        for bbi in procedure.get_reachable_cfg_blocks() {
+            for (target, decision) in describer.describe_decisions(bbi) {
+                branch_conditions.edge_conditions.insert((bbi, target), decision);
+            }
+        }
+
+        // The blocks are visited in topological order, so the predecessors that have not been
+        // visited yet are the sources of back edges, which do not constrain the paths.
+        for bbi in procedure.get_reachable_cfg_blocks() {
+            let mut conditions: Option<Vec<String>> = None;
+            for pred in procedure.predecessors(bbi) {
+                if !branch_conditions.block_conditions.contains_key(&pred) {
+                    continue;
+                }
+                let pred_conditions = branch_conditions.get_edge_conditions(pred, bbi);
+                conditions = Some(match conditions {
+                    None => pred_conditions,
+                    Some(conditions) => conditions
+                        .into_iter()
+                        .filter(|condition| pred_conditions.contains(condition))
+                        .collect(),
+                });
+            }
+            branch_conditions
+                .block_conditions
+                .insert(bbi, conditions.unwrap_or_default());
+        }
+
+        branch_conditions
+    }
+
+    /// The decisions that are taken on every path that reaches the block.
+    pub fn get_block_conditions(&self, bbi: BasicBlockIndex) -> Vec<String> {
+        self.block_conditions
+            .get(&bbi)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The decisions that are taken on every path that goes through the edge `from --> to`.
+    pub fn get_edge_conditions(&self, from: BasicBlockIndex, to: BasicBlockIndex) -> Vec<String> {
+        let mut conditions = self.get_block_conditions(from);
+        if let Some(decision) = self.edge_conditions.get(&(from, to)) {
+            if !conditions.contains(decision) {
+                conditions.push(decision.clone());
+            }
+        }
+        conditions
+    }
+}
+
+/// The value of the discriminant of a `SwitchInt` terminator.
+enum Discriminant<'tcx> {
+    /// A boolean expression, e.g. `x < 0`
+    Bool(String),
+    /// An integer or a character, e.g. `x`
+    Int(String, ty::Ty<'tcx>),
+    /// The variant of an enumeration, e.g. `opt`
+    Variant(String, &'tcx ty::AdtDef),
+}
+
+struct DecisionDescriber<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> {
+    encoder: &'p Encoder<'v, 'r, 'a, 'tcx>,
+    mir: &'p mir::Mir<'tcx>,
+}
+
+impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> DecisionDescriber<'p, 'v, 'r, 'a, 'tcx> {
+    /// Describe the decision that leads to each target of the terminator of the block, if the
+    /// terminator is a branch.
+    fn describe_decisions(&self, bbi: BasicBlockIndex) -> Vec<(BasicBlockIndex, String)> {
+        let terminator = self.mir[bbi].terminator();
+        let (discr, switch_ty, values, targets) = match terminator.kind {
+            mir::TerminatorKind::SwitchInt {
+                ref discr,
+                switch_ty,
+                ref values,
+                ref targets,
+            } => (discr, switch_ty, values, targets),
+            _ => return vec![],
+        };
+        let discriminant = match self.describe_discriminant(bbi, discr, switch_ty) {
+            Some(discriminant) => discriminant,
+            None => return vec![],
+        };
+
+        let default_target = targets[values.len()];
+        let mut decisions = vec![];
+        for &target in targets {
+            if decisions.iter().any(|(other, _)| *other == target) {
+                continue;
+            }
+            // The target is reached iff the value is one of `listed`. If the target is the
+            // default one, it is reached iff the value is not one of `excluded`.
+            let (listed, excluded): (Vec<_>, Vec<_>) = values
+                .iter()
+                .zip(targets.iter())
+                .partition(|(_, other)| **other == target);
+            let listed: Vec<u128> = listed.into_iter().map(|(value, _)| *value).collect();
+            let excluded: Vec<u128> = excluded.into_iter().map(|(value, _)| *value).collect();
+            let is_default = target == default_target;
+            if is_default && excluded.is_empty() {
+                // The branch does not depend on the discriminant.
+                continue;
+            }
+            if let Some(decision) =
+                self.describe_decision(&discriminant, &listed, &excluded, is_default)
+            {
+                decisions.push((target, decision));
+            }
+        }
+        decisions
+    }
+
+    fn describe_decision(
+        &self,
+        discriminant: &Discriminant<'tcx>,
+        listed: &[u128],
+        excluded: &[u128],
+        is_default: bool,
+    ) -> Option<String> {
+        match discriminant {
+            Discriminant::Bool(expr) => {
+                let is_true = if is_default {
+                    excluded.contains(&0)
+                } else {
+                    listed.contains(&1)
+                };
+                Some(format!("`{}` is {}", expr, is_true))
+            }
+
+            Discriminant::Int(expr, ty) => {
+                let describe_values = |values: &[u128], separator: &str| {
+                    values
+                        .iter()
+                        .map(|&value| self.encoder.encode_int_cast(value, *ty).to_string())
+                        .collect::<Vec<_>>()
+                        .join(separator)
+                };
+                if is_default {
+                    Some(format!("`{}` is not {}", expr, describe_values(excluded, " nor ")))
+                } else {
+                    Some(format!("`{}` is {}", expr, describe_values(listed, " or ")))
+                }
+            }
+
+            Discriminant::Variant(expr, adt_def) => {
+                let tcx = self.encoder.env().tcx();
+                let variants: Vec<String> = adt_def
+                    .variants
+                    .iter()
+                    .enumerate()
+                    .filter(|(variant_index, _)| {
+                        let value = adt_def.discriminant_for_variant(tcx, *variant_index).val;
+                        if is_default {
+                            !excluded.contains(&value)
+                        } else {
+                            listed.contains(&value)
+                        }
+                    })
+                    .map(|(_, variant_def)| format!("`{}`", variant_def.name))
+                    .collect();
+                if variants.is_empty() {
+                    None
+                } else {
+                    Some(format!("`{}` is {}", expr, variants.join(" or ")))
+                }
+            }
+        }
+    }
+
+    /// Describe the discriminant of a `SwitchInt` terminator with the source code of the
+    /// expression that computes it, if it is written by the user.
+    fn describe_discriminant(
+        &self,
+        bbi: BasicBlockIndex,
+        discr: &mir::Operand<'tcx>,
+        switch_ty: ty::Ty<'tcx>,
+    ) -> Option<Discriminant<'tcx>> {
+        let local = match discr {
+            mir::Operand::Copy(mir::Place::Local(local))
+            | mir::Operand::Move(mir::Place::Local(local)) => *local,
+            _ => return None,
+        };
+
+        if let Some(name) = self.mir.local_decls[local].name {
+            // The discriminant is a variable of the user
+            return match switch_ty.sty {
+                ty::TypeVariants::TyBool => Some(Discriminant::Bool(name.to_string())),
+                _ => Some(Discriminant::Int(name.to_string(), switch_ty)),
+            };
+        }
+
+        let (span, rvalue) = self.find_assignment(bbi, local)?;
+        if let mir::Rvalue::Discriminant(ref place) = *rvalue {
+            let tcx = self.encoder.env().tcx();
+            return match place.ty(self.mir, tcx).to_ty(tcx).sty {
+                ty::TypeVariants::TyAdt(adt_def, _) if adt_def.is_enum() => {
+                    let expr = self.describe_place(place)?;
+                    Some(Discriminant::Variant(expr, adt_def))
+                }
+                _ => None,
+            };
+        }
+        let expr = self.snippet(span)?;
+        match switch_ty.sty {
+            ty::TypeVariants::TyBool => Some(Discriminant::Bool(expr)),
+            _ => Some(Discriminant::Int(expr, switch_ty)),
+        }
+    }
+
+    /// Find the assignment of a temporary variable in a block.
+    fn find_assignment(
+        &self,
+        bbi: BasicBlockIndex,
+        local: mir::Local,
+    ) -> Option<(Span, &'p mir::Rvalue<'tcx>)> {
+        let mir = self.mir;
+        mir[bbi].statements.iter().rev().filter_map(|stmt| {
+            match stmt.kind {
+                mir::StatementKind::Assign(mir::Place::Local(lhs), ref rvalue) if lhs == local => {
+                    Some((stmt.source_info.span, rvalue))
+                }
+                _ => None,
+            }
+        }).next()
+    }
+
+    /// Describe a place with the names of the variables of the user.
+    fn describe_place(&self, place: &mir::Place<'tcx>) -> Option<String> {
+        match *place {
+            mir::Place::Local(local) => self.mir.local_decls[local]
+                .name
+                .map(|name| name.to_string()),
+            mir::Place::Projection(box mir::Projection {
+                ref base,
+                elem: mir::ProjectionElem::Deref,
+            }) => self.describe_place(base).map(|base| format!("*{}", base)),
+            _ => None,
+        }
+    }
+
+    /// The source code of a span, unless it has been generated by a macro.
+    fn snippet(&self, span: Span) -> Option<String> {
+        if span.ctxt() != SyntaxContext::empty() {
+            return None;
+        }
+        self.encoder.env().codemap().span_to_snippet(span).ok()
+    }
+}
@@ -19,6 +19,9 @@ pub enum BuiltinFunctionKind {
     Unreachable(vir::Type),
     /// type
     Undefined(vir::Type),
+    /// A function whose precondition is its argument, used to check that the measure of a
+    /// recursive pure function decreases at a call
+    TerminationCheck,
 }
 
 pub struct BuiltinEncoder {
@@ -60,6 +63,13 @@ impl BuiltinEncoder {
             BuiltinFunctionKind::Undefined(vir::Type::Int) => format!("builtin$undef_int"),
             BuiltinFunctionKind::Undefined(vir::Type::Bool) => format!("builtin$undef_bool"),
             BuiltinFunctionKind::Undefined(vir::Type::TypedRef(_)) => format!("builtin$undef_ref"),
+            BuiltinFunctionKind::Unreachable(vir::Type::Domain(name)) => {
+                format!("builtin$unreach_{}", name)
+            }
+            BuiltinFunctionKind::Undefined(vir::Type::Domain(name)) => {
+                format!("builtin$undef_{}", name)
+            }
+            BuiltinFunctionKind::TerminationCheck => format!("builtin$termination_check"),
         }
     }
 
@@ -83,6 +93,17 @@ impl BuiltinEncoder {
                 posts: vec![],
                 body: None,
             },
+            BuiltinFunctionKind::TerminationCheck => {
+                let decreases = vir::LocalVar::new("decreases", vir::Type::Bool);
+                vir::Function {
+                    name: fn_name,
+                    formal_args: vec![decreases.clone()],
+                    return_type: vir::Type::Bool,
+                    pres: vec![vir::Expr::local(decreases)],
+                    posts: vec![],
+                    body: None,
+                }
+            }
         }
     }
 }
@@ -9,13 +9,22 @@ use encoder::builtin_encoder::BuiltinEncoder;
 use encoder::builtin_encoder::BuiltinFunctionKind;
 use encoder::builtin_encoder::BuiltinMethodKind;
 use encoder::error_manager::{ErrorCtxt, ErrorManager};
+use encoder::errors::{EncodingResult, SpannedEncodingError, UnsupportedFeature};
 use encoder::foldunfold;
+use encoder::ghost_encoder::{get_ghost_kind, GhostEncoder, GhostKind};
+use encoder::model_fields;
 use encoder::places;
 use encoder::procedure_encoder::ProcedureEncoder;
 use encoder::pure_function_encoder::PureFunctionEncoder;
+use encoder::rc_encoder::is_rc_ty;
+use encoder::snapshot_encoder::{Snapshot, SnapshotEncoder};
 use encoder::spec_encoder::SpecEncoder;
+use encoder::spec_sanity::{self, SpecSanityCheck, SpecSanityCheckKind};
+use encoder::spec_visibility;
+use encoder::string_encoder::StringEncoder;
 use encoder::type_encoder::{
-    compute_discriminant_values, compute_discriminant_bounds, TypeEncoder};
+    compute_discriminant_values, compute_discriminant_bounds, has_value_type, TypeEncoder};
+use encoder::viper_domain_encoder;
 use encoder::vir;
 use encoder::vir::WithIdentifier;
 use prusti_interface::config;
@@ -24,44 +33,85 @@ use prusti_interface::data::ProcedureDefId;
 use prusti_interface::environment::Environment;
 use prusti_interface::report::log;
 use prusti_interface::specifications::{
-    SpecID, SpecificationSet, TypedAssertion,
+    SpecID, SpecificationSet, TypedAssertion, TypedSpecification,
     TypedSpecificationMap, TypedSpecificationSet,
 };
 use rustc::hir;
 use rustc::hir::def_id::DefId;
 use rustc::middle::const_val::ConstVal;
 use rustc::mir;
-use rustc::mir::interpret::GlobalId;
+use rustc::mir::interpret::{ConstValue, GlobalId};
 use rustc::ty;
-use std::cell::{RefCell, RefMut};
+use rustc::ty::subst::Subst;
+use rustc::ty::TypeFoldable;
+use std::cell::{Cell, RefCell, RefMut};
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::io::Write;
 use std::iter::FromIterator;
 use std::mem;
+use std::time::Duration;
 use syntax::ast;
+use syntax_pos::Span;
 use viper;
 
 pub struct Encoder<'v, 'r: 'v, 'a: 'r, 'tcx: 'a> {
     env: &'v Environment<'r, 'a, 'tcx>,
     spec: &'v TypedSpecificationMap,
     error_manager: RefCell<ErrorManager<'tcx>>,
+    /// The errors that prevented the encoding of some procedures
+    encoding_errors: RefCell<Vec<SpannedEncodingError>>,
+    /// The errors found by the parts of the encoding that cannot fail (e.g. the encoding of a
+    /// type), which continue with a placeholder. They are reported for the procedure or pure
+    /// function that is being encoded.
+    pending_errors: RefCell<Vec<SpannedEncodingError>>,
     procedure_contracts: RefCell<HashMap<ProcedureDefId, ProcedureContractMirDef<'tcx>>>,
     builtin_methods: RefCell<HashMap<BuiltinMethodKind, vir::BodylessMethod>>,
     builtin_functions: RefCell<HashMap<BuiltinFunctionKind, vir::Function>>,
     procedures: RefCell<HashMap<ProcedureDefId, vir::CfgMethod>>,
+    /// The sanity checks of the specifications, encoded if `CHECK_SPEC_SANITY` is enabled.
+    spec_sanity_checks: RefCell<Vec<SpecSanityCheck>>,
     pure_function_bodies: RefCell<HashMap<(ProcedureDefId, String), vir::Expr>>,
     pure_functions: RefCell<HashMap<(ProcedureDefId, String), vir::Function>>,
+    /// The instantiations of pure functions that have already been queued for encoding, indexed
+    /// by the substitutions of the type parameters of the function.
+    pure_function_instantiations: RefCell<HashSet<(ProcedureDefId, String)>>,
     type_predicate_names: RefCell<HashMap<ty::TypeVariants<'tcx>, String>>,
     type_invariant_names: RefCell<HashMap<ty::TypeVariants<'tcx>, String>>,
     type_tag_names: RefCell<HashMap<ty::TypeVariants<'tcx>, String>>,
     predicate_types: RefCell<HashMap<String, ty::Ty<'tcx>>>,
     type_predicates: RefCell<HashMap<String, vir::Predicate>>,
+    /// The predicates that still have to be encoded, with the type that they encode.
+    predicate_queue: RefCell<Vec<(String, ty::Ty<'tcx>)>>,
+    /// The names of the predicates that have been queued for encoding.
+    queued_predicates: RefCell<HashSet<String>>,
+    processing_predicate_queue: Cell<bool>,
     type_invariants: RefCell<HashMap<String, vir::Function>>,
     type_tags: RefCell<HashMap<String, vir::Function>>,
+    /// The functions that encode the value of associated constants whose implementation
+    /// cannot be determined, because it depends on type parameters.
+    associated_constants: RefCell<HashMap<String, vir::Function>>,
+    /// The functions that encode the value of (primitive fields of) immutable `static` items.
+    static_items: RefCell<HashMap<String, vir::Function>>,
     type_discriminant_funcs: RefCell<HashMap<String, vir::Function>>,
     memory_eq_funcs: RefCell<HashMap<String, Option<vir::Function>>>,
+    /// The snapshots of the types, indexed by the name of the snapshot function. `None` marks
+    /// the types that have no snapshot or whose snapshot is being encoded.
+    snapshots: RefCell<HashMap<String, Option<Snapshot>>>,
+    /// Whether the model of strings is used, and thus its domain has to be declared.
+    uses_strings: Cell<bool>,
+    /// The identifiers of the string literals, indexed by their content.
+    string_literals: RefCell<HashMap<String, usize>>,
+    /// The domains of the instantiations of the ghost types that are used, indexed by name.
+    ghost_domains: RefCell<HashMap<String, vir::Domain>>,
+    /// The functions of the user-supplied Viper domains, indexed by the name of the Viper
+    /// function of the corresponding pure function.
+    viper_domain_functions: RefCell<HashMap<String, vir::DomainFunc>>,
+    /// The axioms of the user-supplied Viper domains, indexed by the name of the domain.
+    viper_domain_axioms: RefCell<HashMap<String, Vec<vir::DomainAxiom>>>,
     fields: RefCell<HashMap<String, vir::Field>>,
+    /// The time spent in the fold/unfold algorithm, reported by `PROFILE_ENCODER`.
+    fold_unfold_duration: Cell<Duration>,
     /// For each instantiation of each closure: DefId, basic block index, statement index, operands
     closure_instantiations: HashMap<
         DefId,
@@ -77,6 +127,8 @@ pub struct Encoder<'v, 'r: 'v, 'a: 'r, 'tcx: 'a> {
     vir_program_before_viper_writer: RefCell<Box<Write>>,
     use_whitelist: bool,
     whitelist: HashSet<String>,
+    /// The procedures declared with `#[extern_spec]`, indexed by the path of the external function
+    extern_specs: HashMap<String, ProcedureDefId>,
     pub typaram_repl: RefCell<Vec<HashMap<ty::Ty<'tcx>, ty::Ty<'tcx>>>>,
 }
 
@@ -104,29 +156,49 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         Encoder {
             env,
             spec,
-            error_manager: RefCell::new(ErrorManager::new(env.codemap())),
+            error_manager: RefCell::new(ErrorManager::new(
+                env.codemap(),
+                viper::VerificationBackend::from_str(&config::viper_backend()),
+            )),
+            encoding_errors: RefCell::new(vec![]),
+            pending_errors: RefCell::new(vec![]),
             procedure_contracts: RefCell::new(HashMap::new()),
             builtin_methods: RefCell::new(HashMap::new()),
             builtin_functions: RefCell::new(HashMap::new()),
             procedures: RefCell::new(HashMap::new()),
+            spec_sanity_checks: RefCell::new(vec![]),
             pure_function_bodies: RefCell::new(HashMap::new()),
             pure_functions: RefCell::new(HashMap::new()),
+            pure_function_instantiations: RefCell::new(HashSet::new()),
             type_predicate_names: RefCell::new(HashMap::new()),
             type_invariant_names: RefCell::new(HashMap::new()),
             type_tag_names: RefCell::new(HashMap::new()),
             predicate_types: RefCell::new(HashMap::new()),
             type_predicates: RefCell::new(HashMap::new()),
+            predicate_queue: RefCell::new(Vec::new()),
+            queued_predicates: RefCell::new(HashSet::new()),
+            processing_predicate_queue: Cell::new(false),
             type_invariants: RefCell::new(HashMap::new()),
             type_tags: RefCell::new(HashMap::new()),
+            associated_constants: RefCell::new(HashMap::new()),
+            static_items: RefCell::new(HashMap::new()),
             type_discriminant_funcs: RefCell::new(HashMap::new()),
             memory_eq_funcs: RefCell::new(HashMap::new()),
+            snapshots: RefCell::new(HashMap::new()),
+            uses_strings: Cell::new(false),
+            string_literals: RefCell::new(HashMap::new()),
+            ghost_domains: RefCell::new(HashMap::new()),
+            viper_domain_functions: RefCell::new(HashMap::new()),
+            viper_domain_axioms: RefCell::new(HashMap::new()),
             fields: RefCell::new(HashMap::new()),
+            fold_unfold_duration: Cell::new(Duration::from_secs(0)),
             closure_instantiations: HashMap::new(),
             encoding_queue: RefCell::new(vec![]),
             vir_program_before_foldunfold_writer,
             vir_program_before_viper_writer,
             use_whitelist: config::enable_whitelist(),
             whitelist: HashSet::from_iter(config::verification_whitelist()),
+            extern_specs: env.get_extern_specs(),
             typaram_repl: RefCell::new(Vec::new()),
         }
     }
@@ -173,8 +245,67 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         self.error_manager.borrow_mut()
     }
 
-    pub fn get_used_viper_domains(&self) -> Vec<viper::Domain<'v>> {
-        vec![]
+    pub fn get_encoding_errors(&self) -> Vec<SpannedEncodingError> {
+        self.encoding_errors.borrow().clone()
+    }
+
+    /// Register an error found by a part of the encoding that cannot fail (see `pending_errors`).
+    pub fn register_encoding_error(&self, error: SpannedEncodingError) {
+        debug!("Registered encoding error: {:?}", error);
+        self.pending_errors.borrow_mut().push(error);
+    }
+
+    /// Take the first registered error, forgetting the others.
+    fn take_pending_error(&self) -> Option<SpannedEncodingError> {
+        self.pending_errors.borrow_mut().drain(..).next()
+    }
+
+    pub fn add_fold_unfold_duration(&self, duration: Duration) {
+        self.fold_unfold_duration.set(self.fold_unfold_duration.get() + duration);
+    }
+
+    /// The total time spent in the fold/unfold algorithm
+    pub fn get_fold_unfold_duration(&self) -> Duration {
+        self.fold_unfold_duration.get()
+    }
+
+    pub fn get_used_viper_domains(&self) -> Vec<vir::Domain> {
+        let mut domains: Vec<_> = self
+            .snapshots
+            .borrow()
+            .values()
+            .filter_map(|snapshot| snapshot.as_ref().map(|s| s.domain.clone()))
+            .collect();
+        if self.uses_strings.get() {
+            domains.push(StringEncoder::new().encode_domain());
+        }
+        domains.extend(self.ghost_domains.borrow().values().cloned());
+        let domain_functions = self.viper_domain_functions.borrow();
+        for (domain_name, axioms) in self.viper_domain_axioms.borrow().iter() {
+            let mut functions: Vec<_> = domain_functions
+                .values()
+                .filter(|function| &function.domain_name == domain_name)
+                .cloned()
+                .collect();
+            functions.sort_by_key(|function| function.name.clone());
+            let axioms = axioms
+                .iter()
+                .map(|axiom| vir::DomainAxiom {
+                    expr: viper_domain_encoder::replace_function_calls(
+                        axiom.expr.clone(),
+                        &domain_functions,
+                    ),
+                    ..axiom.clone()
+                })
+                .collect();
+            domains.push(vir::Domain {
+                name: domain_name.clone(),
+                functions,
+                axioms,
+            });
+        }
+        domains.sort_by_key(|d| d.get_identifier());
+        domains
     }
 
     pub fn get_used_viper_fields(&self) -> Vec<vir::Field> {
@@ -197,12 +328,23 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         for function in self.type_tags.borrow().values() {
             functions.push(function.clone());
         }
+        for function in self.associated_constants.borrow().values() {
+            functions.push(function.clone());
+        }
+        for function in self.static_items.borrow().values() {
+            functions.push(function.clone());
+        }
         for function in self.type_discriminant_funcs.borrow().values() {
             functions.push(function.clone());
         }
         for function in self.memory_eq_funcs.borrow().values() {
             functions.push(function.as_ref().unwrap().clone());
         }
+        for snapshot in self.snapshots.borrow().values() {
+            if let Some(snapshot) = snapshot {
+                functions.push(snapshot.function.clone());
+            }
+        }
         functions.sort_by_key(|f| f.get_identifier());
         functions
     }
@@ -225,6 +367,10 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         self.procedures.borrow().values().cloned().collect()
     }
 
+    pub fn get_spec_sanity_checks(&self) -> Vec<SpecSanityCheck> {
+        self.spec_sanity_checks.borrow().clone()
+    }
+
     fn collect_closure_instantiations(&mut self) {
         debug!("Collecting closure instantiations...");
         let tcx = self.env().tcx();
@@ -302,20 +448,199 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
     }
 
     pub fn get_spec_by_def_id(&self, def_id: DefId) -> Option<&TypedSpecificationSet> {
-        // Currently, we don't support specifications for external functions.
+        // Specifications of external functions are only available if declared with
+        // `#[extern_spec]` in the current crate.
         // Since we have a collision of PRUSTI_SPEC_ATTR between different crates, we manually check
         // that the def_id does not point to an external crate.
         if !def_id.is_local() {
-            return None;
+            return self
+                .get_extern_spec_procedure(def_id)
+                .and_then(|spec_def_id| self.get_spec_by_def_id(spec_def_id));
         }
         self.get_opt_spec_id(def_id)
             .and_then(|spec_id| self.spec().get(&spec_id))
     }
 
+    /// The invariants (and history invariants) declared on a type, looked up through the
+    /// `DefId` of its definition. They apply to the methods of every implementation of the type,
+    /// regardless of the module in which the implementation is written. The invariants of types
+    /// defined in other crates are not available, because their specifications are not exported.
+    pub fn get_type_invariants(&self, adt_did: DefId) -> &[TypedSpecification] {
+        if !adt_did.is_local() {
+            return &[];
+        }
+        let opt_spec = self
+            .get_opt_spec_id(adt_did)
+            .and_then(|spec_id| self.spec().get(&spec_id));
+        match opt_spec {
+            Some(SpecificationSet::Struct(items)) => items,
+            _ => &[],
+        }
+    }
+
+    /// The procedure declared with `#[extern_spec]` that specifies an external function.
+    pub fn get_extern_spec_procedure(&self, def_id: DefId) -> Option<ProcedureDefId> {
+        if def_id.is_local() {
+            return None;
+        }
+        self.extern_specs
+            .get(&self.env().get_extern_item_path(def_id))
+            .or_else(|| self.extern_specs.get(&self.env().get_item_name(def_id)))
+            .cloned()
+    }
+
+    /// The procedure to encode for a call of `def_id` with the given substitutions. A call of an
+    /// external function (or of a trait method implemented by an external function) that is
+    /// specified with `#[extern_spec]` is encoded as a call of the procedure that specifies it,
+    /// provided that the two procedures take the same arguments. A call of a trait method that is
+    /// statically resolved to an implementation that refines the trait contract is encoded as a
+    /// call of the implementation, and so is the use of an operator (e.g. `a + b` or `a[i]`)
+    /// that is implemented in the crate.
+    pub fn get_called_procedure(
+        &self,
+        def_id: DefId,
+        substs: &'tcx ty::subst::Substs<'tcx>,
+    ) -> (DefId, &'tcx ty::subst::Substs<'tcx>) {
+        let (callee_def_id, callee_substs) = self
+            .env()
+            .resolve_method_call(def_id, substs)
+            .unwrap_or((def_id, substs));
+        if callee_def_id != def_id
+            && (self.refines_trait_spec(callee_def_id) || self.implements_operator(callee_def_id))
+        {
+            return (callee_def_id, callee_substs);
+        }
+        let spec_def_id = match self.get_extern_spec_procedure(callee_def_id) {
+            Some(spec_def_id) => spec_def_id,
+            None => return (def_id, substs),
+        };
+        let tcx = self.env().tcx();
+        let num_spec_params = tcx.generics_of(spec_def_id).count();
+        let spec_substs = tcx.mk_substs(callee_substs.iter().take(num_spec_params).cloned());
+        let get_inputs = |def_id: DefId, substs: &'tcx ty::subst::Substs<'tcx>| {
+            let fn_sig = tcx.fn_sig(def_id).subst(tcx, substs);
+            let fn_sig = tcx.erase_late_bound_regions(&fn_sig);
+            fn_sig
+                .inputs()
+                .iter()
+                .map(|input_ty| tcx.erase_regions(input_ty))
+                .collect::<Vec<_>>()
+        };
+        if spec_substs.len() == num_spec_params
+            && get_inputs(callee_def_id, callee_substs) == get_inputs(spec_def_id, spec_substs)
+        {
+            debug!("Call of {:?} is encoded as a call of {:?}", def_id, spec_def_id);
+            (spec_def_id, spec_substs)
+        } else {
+            (def_id, substs)
+        }
+    }
+
+    /// Find whether a procedure is pure, either because it is marked with `#[pure]` or because
+    /// it is an external function specified by a pure `#[extern_spec]` procedure.
+    pub fn is_pure(&self, def_id: DefId) -> bool {
+        let proc_def_id = self.get_extern_spec_procedure(def_id).unwrap_or(def_id);
+        self.env.has_attribute_name(proc_def_id, "pure")
+    }
+
+    /// Find whether a pure function is marked with `#[duplicable_reads]`, i.e. whether it requires
+    /// only wildcard read permissions so that it can be applied to aliased shared references.
+    pub fn has_duplicable_reads(&self, def_id: DefId) -> bool {
+        let proc_def_id = self.get_extern_spec_procedure(def_id).unwrap_or(def_id);
+        self.env.has_attribute_name(proc_def_id, "duplicable_reads")
+    }
+
+    /// Find whether the implementation of a trait method refines the contract declared in the
+    /// trait with `#[refine_requires]` or `#[refine_ensures]`.
+    pub fn refines_trait_spec(&self, proc_def_id: ProcedureDefId) -> bool {
+        self.env.has_attribute_name(proc_def_id, "refine_requires")
+            || self.env.has_attribute_name(proc_def_id, "refine_ensures")
+    }
+
+    /// Find whether a procedure of the crate implements an operator, i.e. a method of a trait of
+    /// `std::ops` (e.g. `Add::add` or `Index::index`). These traits have no contract, so the
+    /// implementations can declare their own, which are used wherever the operator is statically
+    /// resolved to the implementation (in the code and in the specifications).
+    pub fn implements_operator(&self, proc_def_id: ProcedureDefId) -> bool {
+        if !proc_def_id.is_local() {
+            return false;
+        }
+        match self.env.get_implemented_trait_method(proc_def_id) {
+            Some(trait_method_def_id) => {
+                let trait_method_path = self.env.tcx().absolute_item_path_str(trait_method_def_id);
+                trait_method_path.starts_with("std::ops::")
+                    || trait_method_path.starts_with("core::ops::")
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the implementation of a trait method only adds postconditions to a trait method
+    /// without specification, e.g. `#[ensures]` on an implementation of `From::from`. This only
+    /// strengthens the trivial contract of the trait method, so it needs no explicit refinement.
+    pub fn strengthens_unspecified_trait_method(&self, proc_def_id: ProcedureDefId) -> bool {
+        let trait_spec_is_empty = self
+            .env()
+            .get_implemented_trait_method(proc_def_id)
+            .and_then(|trait_method_def_id| self.get_spec_by_def_id(trait_method_def_id))
+            .map_or(true, |spec| spec.is_empty());
+        let has_own_pres = match self.get_spec_by_def_id(proc_def_id) {
+            Some(&SpecificationSet::Procedure(ref pres, _)) => !pres.is_empty(),
+            _ => false,
+        };
+        trait_spec_is_empty && !has_own_pres
+    }
+
+    /// The specification of a procedure. The implementation of a trait method that does not
+    /// declare its own specification has the one of the method declared in the trait. If the
+    /// implementation refines only the precondition (resp. postcondition) of the trait method,
+    /// it keeps the postcondition (resp. precondition) declared in the trait.
+    pub fn get_procedure_spec(
+        &self,
+        proc_def_id: ProcedureDefId,
+    ) -> Option<TypedSpecificationSet> {
+        let own_spec = self.get_spec_by_def_id(proc_def_id);
+        let trait_spec = self
+            .env()
+            .get_implemented_trait_method(proc_def_id)
+            .and_then(|trait_method_def_id| self.get_spec_by_def_id(trait_method_def_id));
+        if self.refines_trait_spec(proc_def_id) {
+            if let (
+                Some(SpecificationSet::Procedure(own_pres, own_posts)),
+                Some(SpecificationSet::Procedure(trait_pres, trait_posts)),
+            ) = (own_spec, trait_spec)
+            {
+                let pres = if self.env.has_attribute_name(proc_def_id, "refine_requires") {
+                    own_pres
+                } else {
+                    trait_pres
+                };
+                let posts = if self.env.has_attribute_name(proc_def_id, "refine_ensures") {
+                    own_posts
+                } else {
+                    trait_posts
+                };
+                return Some(SpecificationSet::Procedure(pres.clone(), posts.clone()));
+            }
+        }
+        own_spec.or(trait_spec).cloned()
+    }
+
+    /// The contract declared in the trait for the implementation of a trait method.
+    pub fn get_trait_procedure_contract_for_def(
+        &self,
+        proc_def_id: ProcedureDefId,
+    ) -> Option<ProcedureContract<'tcx>> {
+        let trait_method_def_id = self.env().get_implemented_trait_method(proc_def_id)?;
+        let trait_spec = self.get_spec_by_def_id(trait_method_def_id)?.clone();
+        let contract = compute_procedure_contract(proc_def_id, self.env().tcx(), trait_spec, None);
+        Some(contract.to_def_site_contract())
+    }
+
     fn get_procedure_contract(&self, proc_def_id: ProcedureDefId) -> ProcedureContractMirDef<'tcx> {
-        let opt_fun_spec = self.get_spec_by_def_id(proc_def_id);
+        let opt_fun_spec = self.get_procedure_spec(proc_def_id);
         let fun_spec = match opt_fun_spec {
-            Some(fun_spec) => fun_spec.clone(),
+            Some(fun_spec) => fun_spec,
             None => {
                 debug!("Procedure {:?} has no specification", proc_def_id);
                 SpecificationSet::Procedure(vec![], vec![])
@@ -341,9 +666,9 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         args: &Vec<places::Local>,
         target: places::Local,
     ) -> ProcedureContract<'tcx> {
-        let opt_fun_spec = self.get_spec_by_def_id(proc_def_id);
+        let opt_fun_spec = self.get_procedure_spec(proc_def_id);
         let fun_spec = match opt_fun_spec {
-            Some(fun_spec) => fun_spec.clone(),
+            Some(fun_spec) => fun_spec,
             None => {
                 debug!("Procedure {:?} has no specification", proc_def_id);
                 SpecificationSet::Procedure(vec![], vec![])
@@ -410,6 +735,183 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         field
     }
 
+    /// The field that stores the content of a `str` or of a `String` (see `string_encoder`).
+    pub fn encode_string_field(&self) -> vir::Field {
+        self.uses_strings.set(true);
+        let field = StringEncoder::new().encode_value_field();
+        self.fields
+            .borrow_mut()
+            .entry(field.name.clone())
+            .or_insert_with(|| field.clone());
+        field
+    }
+
+    /// The model of a ghost type (see `ghost_encoder`), whose domain is then declared in the
+    /// program. The span is the one of the code that uses the type.
+    pub fn encode_ghost_type(&self, ty: ty::Ty<'tcx>, span: Span) -> EncodingResult<GhostEncoder> {
+        let kind = get_ghost_kind(self.env().tcx(), ty).unwrap();
+        let elem_types = match ty.sty {
+            ty::TypeVariants::TyAdt(_, substs) => substs
+                .types()
+                .map(|elem_ty| {
+                    let elem_ty = self.resolve_typaram(elem_ty);
+                    match elem_ty.sty {
+                        ty::TypeVariants::TyBool
+                        | ty::TypeVariants::TyInt(_)
+                        | ty::TypeVariants::TyUint(_)
+                        | ty::TypeVariants::TyChar => self.encode_value_type(elem_ty),
+                        // Rejected by `GhostEncoder::new`
+                        _ => self.encode_type(elem_ty),
+                    }
+                })
+                .collect(),
+            ref x => unreachable!("{:?}", x),
+        };
+        let ghost_encoder = GhostEncoder::new(kind, elem_types, span)?;
+        let domain_name = ghost_encoder.domain_name();
+        if !self.ghost_domains.borrow().contains_key(&domain_name) {
+            let domain = ghost_encoder.encode_domain();
+            self.ghost_domains.borrow_mut().insert(domain_name, domain);
+        }
+        Ok(ghost_encoder)
+    }
+
+    /// Like `encode_ghost_type`, for the parts of the encoding that cannot fail. An unsupported
+    /// ghost type is registered as an error and modelled as a ghost type of integers.
+    pub fn encode_ghost_type_or_register(&self, ty: ty::Ty<'tcx>, span: Span) -> GhostEncoder {
+        self.encode_ghost_type(ty, span).unwrap_or_else(|error| {
+            self.register_encoding_error(error);
+            let kind = get_ghost_kind(self.env().tcx(), ty).unwrap();
+            let elem_count = if kind == GhostKind::Map { 2 } else { 1 };
+            let elem_types = vec![vir::Type::Int; elem_count];
+            GhostEncoder::new(kind, elem_types, span).unwrap()
+        })
+    }
+
+    /// Check that the ghost types used by the local variables of a MIR body are supported, to
+    /// report the unsupported ones at the declaration of the variable.
+    pub fn check_ghost_types(&self, mir: &mir::Mir<'tcx>) -> EncodingResult<()> {
+        for local_decl in mir.local_decls.iter() {
+            for ty in local_decl.ty.walk() {
+                if get_ghost_kind(self.env().tcx(), ty).is_some() {
+                    self.encode_ghost_type(ty, local_decl.source_info.span)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode a string literal with the given content, which is printed as a Rust string (with
+    /// quotes and escaped characters), and length in bytes.
+    pub fn encode_string_literal(&self, content: &str, len: u128) -> vir::Expr {
+        self.uses_strings.set(true);
+        let mut string_literals = self.string_literals.borrow_mut();
+        let next_id = string_literals.len();
+        let id = *string_literals
+            .entry(content.to_string())
+            .or_insert(next_id);
+        StringEncoder::new().encode_literal(id, len)
+    }
+
+    /// Declare a trusted pure function marked with `#[viper_domain]` in its user-supplied Viper
+    /// domain (see `viper_domain_encoder`), and return the corresponding domain function. The
+    /// first use of a domain encodes its axioms.
+    pub fn encode_viper_domain_function(
+        &self,
+        proc_def_id: ProcedureDefId,
+        function_name: &str,
+        formal_args: Vec<vir::LocalVar>,
+        return_type: vir::Type,
+    ) -> Option<vir::DomainFunc> {
+        let domain_name = self.env.get_attr(proc_def_id, "viper_domain")?;
+        let function = viper_domain_encoder::encode_domain_function(
+            &domain_name,
+            function_name,
+            formal_args,
+            return_type,
+        );
+        self.viper_domain_functions
+            .borrow_mut()
+            .insert(function_name.to_string(), function.clone());
+        if !self.viper_domain_axioms.borrow().contains_key(&domain_name) {
+            // Mark the domain as encoded before encoding the axioms, which may use the domain
+            self.viper_domain_axioms
+                .borrow_mut()
+                .insert(domain_name.clone(), vec![]);
+            let axioms = self.encode_viper_domain_axioms(&domain_name);
+            self.viper_domain_axioms
+                .borrow_mut()
+                .insert(domain_name, axioms);
+        }
+        Some(function)
+    }
+
+    /// Encode the axioms of a user-supplied Viper domain, i.e. the postconditions of the
+    /// functions marked with `#[viper_axiom]`.
+    fn encode_viper_domain_axioms(&self, domain_name: &str) -> Vec<vir::DomainAxiom> {
+        let tcx = self.env.tcx();
+        let mut axioms = vec![];
+        for &body_id in tcx.hir.krate().body_ids.iter() {
+            let def_id = tcx.hir.body_owner_def_id(body_id);
+            if self.env.get_attr(def_id, "viper_axiom").as_ref().map(|name| name.as_str())
+                != Some(domain_name)
+            {
+                continue;
+            }
+            let posts = match self.get_spec_by_def_id(def_id) {
+                Some(&SpecificationSet::Procedure(_, ref posts)) => posts,
+                _ => continue,
+            };
+            let procedure = self.env.get_procedure(def_id);
+            for (index, post) in posts.iter().enumerate() {
+                let expr = self.encode_assertion(
+                    &post.assertion,
+                    procedure.get_mir(),
+                    "",
+                    &[],
+                    None,
+                    true,
+                    None,
+                );
+                axioms.push(vir::DomainAxiom {
+                    name: format!("{}${}${}", domain_name, tcx.item_name(def_id), index),
+                    expr,
+                    domain_name: domain_name.to_string(),
+                });
+            }
+        }
+        axioms
+    }
+
+    /// Encode a constant of type `&str`. Only string literals are supported.
+    pub fn encode_string_constant(
+        &self,
+        constant: &mir::Constant<'tcx>,
+    ) -> EncodingResult<vir::Expr> {
+        let len = match constant.literal {
+            mir::Literal::Value {
+                value:
+                    ty::Const {
+                        val: ConstVal::Value(ConstValue::ScalarPair(_, len)),
+                        ..
+                    },
+            } => len
+                .to_bits(ty::layout::Size::from_bits(mem::size_of::<usize>() as u64 * 8))
+                .ok()
+                .unwrap(),
+            ref x => {
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::StringConstant,
+                    constant.span,
+                    format!("{:?}", x),
+                ))
+            }
+        };
+        // The MIR prints a string constant as `const "..."`
+        let printed_constant = format!("{:?}", constant);
+        Ok(self.encode_string_literal(printed_constant.trim_left_matches("const "), len))
+    }
+
     pub fn encode_discriminant_func_app(
         &self,
         place: vir::Expr,
@@ -543,6 +1045,7 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
                 let second_field = second.clone().field(field);
                 Some(vir::Expr::eq_cmp(first_field, second_field))
             }
+            _ if is_rc_ty(self.env().tcx(), self_ty) => None,
             ty::TypeVariants::TyAdt(adt_def, subst) if !adt_def.is_box() => {
                 // TODO: If adt_def contains fields of unsupported type,
                 // we should return None.
@@ -551,7 +1054,9 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
             ty::TypeVariants::TyTuple(elems) => {
                 Some(self.encode_memory_eq_tuple(first.clone(), second.clone(), elems))
             }
-            ty::TypeVariants::TyParam(_) => {
+            ty::TypeVariants::TyParam(_)
+            | ty::TypeVariants::TyAnon(..)
+            | ty::TypeVariants::TyProjection(..) => {
                 None
             },
 
@@ -707,6 +1212,35 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         )
     }
 
+    /// Encode the snapshot of the memory location `place` of type `ty`.
+    /// Returns `None` if the type has no snapshot.
+    pub fn encode_snapshot_func_app(
+        &self,
+        place: vir::Expr,
+        ty: ty::Ty<'tcx>,
+        position: vir::Position,
+    ) -> Option<vir::Expr> {
+        let snapshot_encoder = SnapshotEncoder::new(self, ty);
+        let name = snapshot_encoder.encode_function_name();
+        if !self.snapshots.borrow().contains_key(&name) {
+            // Mark that we started encoding this snapshot to avoid infinite recursion.
+            self.snapshots.borrow_mut().insert(name.clone(), None);
+            let snapshot = snapshot_encoder.encode();
+            self.snapshots.borrow_mut().insert(name.clone(), snapshot);
+        }
+        let function = match self.snapshots.borrow()[&name] {
+            Some(ref snapshot) => snapshot.function.clone(),
+            None => return None,
+        };
+        Some(vir::Expr::func_app(
+            function.name,
+            vec![place],
+            function.formal_args,
+            function.return_type,
+            position,
+        ))
+    }
+
     pub fn encode_havoc_methods(&self) -> HashMap<vir::TypeId, String> {
         lazy_static! {
             static ref TYPES: Vec<(vir::TypeId, BuiltinMethodKind)> = vec![
@@ -768,7 +1302,7 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         builtin_encoder.encode_builtin_function_name(&function_kind)
     }
 
-    pub fn encode_procedure(&self, proc_def_id: ProcedureDefId) -> vir::CfgMethod {
+    pub fn encode_procedure(&self, proc_def_id: ProcedureDefId) -> EncodingResult<vir::CfgMethod> {
         debug!("encode_procedure({:?})", proc_def_id);
         assert!(
             !self.env.has_attribute_name(proc_def_id, "pure"),
@@ -783,11 +1317,48 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         if !self.procedures.borrow().contains_key(&proc_def_id) {
             let procedure = self.env.get_procedure(proc_def_id);
             let procedure_encoder = ProcedureEncoder::new(self, &procedure);
-            let method = procedure_encoder.encode();
+            let method = procedure_encoder.encode()?;
+            if let Some(error) = self.take_pending_error() {
+                return Err(error);
+            }
             self.log_vir_program_before_viper(method.to_string());
             self.procedures.borrow_mut().insert(proc_def_id, method);
+            if config::check_spec_sanity() {
+                self.encode_spec_sanity_checks(proc_def_id);
+            }
+        }
+        Ok(self.procedures.borrow()[&proc_def_id].clone())
+    }
+
+    /// Encode the sanity checks of the precondition of a procedure and of the invariants of the
+    /// types of its arguments. The invariant of a type is checked only once.
+    fn encode_spec_sanity_checks(&self, proc_def_id: ProcedureDefId) {
+        let procedure = self.env.get_procedure(proc_def_id);
+        let mir = procedure.get_mir();
+        for arg in mir.args_iter() {
+            let invariant_ty = match spec_sanity::get_invariant_ty(self, mir.local_decls[arg].ty) {
+                Some(invariant_ty) => invariant_ty,
+                None => continue,
+            };
+            let adt_did = invariant_ty.ty_adt_def().unwrap().did;
+            let is_checked = self.spec_sanity_checks.borrow().iter().any(|check| {
+                match check.kind {
+                    SpecSanityCheckKind::TypeInvariant(did) => did == adt_did,
+                    _ => false,
+                }
+            });
+            if !is_checked {
+                let check = spec_sanity::encode_invariant_sanity_check(self, invariant_ty);
+                self.spec_sanity_checks.borrow_mut().push(check);
+            }
+        }
+        match ProcedureEncoder::new(self, &procedure).encode_precondition_sanity_check() {
+            Ok(check) => self.spec_sanity_checks.borrow_mut().push(check),
+            Err(error) => debug!(
+                "The precondition of {:?} will not be checked: {:?}",
+                proc_def_id, error
+            ),
         }
-        self.procedures.borrow()[&proc_def_id].clone()
     }
 
     pub fn encode_value_type(&self, ty: ty::Ty<'tcx>) -> vir::Type {
@@ -800,6 +1371,20 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         type_encoder.encode_type()
     }
 
+    /// Whether the arithmetic operations of a procedure have to be checked for overflows, which
+    /// can be overridden for each procedure with `#[prusti::overflow_checks(on/off)]`.
+    pub fn check_overflows(&self, def_id: DefId) -> bool {
+        self.env
+            .get_overflow_checks(def_id)
+            .unwrap_or_else(config::check_binary_operations)
+    }
+
+    /// Returns the minimum and maximum value of an integer type.
+    pub fn get_integer_bounds(&self, ty: ty::Ty<'tcx>) -> Option<(vir::Expr, vir::Expr)> {
+        let type_encoder = TypeEncoder::new(self, ty);
+        type_encoder.get_integer_bounds()
+    }
+
     pub fn encode_type_bounds(&self, var: &vir::Expr, ty: ty::Ty<'tcx>) -> Vec<vir::Expr> {
         let type_encoder = TypeEncoder::new(self, ty);
         type_encoder.encode_bounds(var)
@@ -837,32 +1422,63 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
             self.type_predicate_names
                 .borrow_mut()
                 .insert(ty.sty.clone(), result);
-            // Trigger encoding of definition
-            self.encode_type_predicate_def(ty);
         }
         let predicate_name = self.type_predicate_names.borrow()[&ty.sty].clone();
         self.predicate_types
             .borrow_mut()
             .insert(predicate_name.clone(), ty);
+        // Trigger encoding of definition
+        if self.queued_predicates.borrow_mut().insert(predicate_name.clone()) {
+            self.predicate_queue
+                .borrow_mut()
+                .push((predicate_name.clone(), ty));
+            self.process_predicate_queue();
+        }
         predicate_name
     }
 
     pub fn encode_type_predicate_def(&self, ty: ty::Ty<'tcx>) -> vir::Predicate {
         let predicate_name = self.encode_type_predicate_use(ty);
         if !self.type_predicates.borrow().contains_key(&predicate_name) {
-            let type_encoder = TypeEncoder::new(self, ty);
-            let predicates = type_encoder.encode_predicate_def();
-            for predicate in predicates {
-                self.log_vir_program_before_viper(predicate.to_string());
-                let predicate_name = predicate.name();
-                self.type_predicates
-                    .borrow_mut()
-                    .insert(predicate_name.to_string(), predicate);
-            }
+            // The predicate is still in the queue, which is being processed by a caller.
+            self.encode_type_predicates(ty);
         }
         self.type_predicates.borrow()[&predicate_name].clone()
     }
 
+    /// Encode the predicates of the queued types. The encoding of a predicate queues the
+    /// predicates of the types of its fields, which are then encoded by the same loop instead of
+    /// recursively. Since a predicate is queued only once, the predicates of (mutually)
+    /// recursive types are encoded exactly once.
+    fn process_predicate_queue(&self) {
+        if self.processing_predicate_queue.get() {
+            return;
+        }
+        self.processing_predicate_queue.set(true);
+        loop {
+            let (predicate_name, ty) = match self.predicate_queue.borrow_mut().pop() {
+                Some(item) => item,
+                None => break,
+            };
+            if !self.type_predicates.borrow().contains_key(&predicate_name) {
+                self.encode_type_predicates(ty);
+            }
+        }
+        self.processing_predicate_queue.set(false);
+    }
+
+    fn encode_type_predicates(&self, ty: ty::Ty<'tcx>) {
+        let type_encoder = TypeEncoder::new(self, ty);
+        let predicates = type_encoder.encode_predicate_def();
+        for predicate in predicates {
+            self.log_vir_program_before_viper(predicate.to_string());
+            let predicate_name = predicate.name();
+            self.type_predicates
+                .borrow_mut()
+                .insert(predicate_name.to_string(), predicate);
+        }
+    }
+
     pub fn encode_type_invariant_use(&self, ty: ty::Ty<'tcx>) -> String {
         // TODO we could use type_predicate_names instead (see TypeEncoder::encode_invariant_use)
         if !self.type_invariant_names.borrow().contains_key(&ty.sty) {
@@ -914,6 +1530,182 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         self.type_tags.borrow()[&tag_name].clone()
     }
 
+    /// Returns the value of the next encoded type tag. Tags of distinct types have distinct
+    /// values, so that typestate conditions such as `S == Even` can be decided by the verifier.
+    pub fn get_next_type_tag_value(&self) -> i64 {
+        self.type_tags.borrow().len() as i64
+    }
+
+    /// Evaluate a constant item, or a `const fn` without arguments, using the compiler.
+    fn eval_const(
+        &self,
+        def_id: DefId,
+        substs: &'tcx ty::subst::Substs<'tcx>,
+        span: Span,
+    ) -> EncodingResult<&'tcx ty::Const<'tcx>> {
+        let tcx = self.env().tcx();
+        let param_env = tcx.param_env(def_id);
+        let cid = GlobalId {
+            instance: ty::Instance::new(def_id, substs),
+            promoted: None,
+        };
+        tcx.const_eval(param_env.and(cid)).map_err(|_| {
+            SpannedEncodingError::unsupported(
+                UnsupportedFeature::ConstEvaluation,
+                span,
+                format!("constant evaluation of {:?} failed", def_id),
+            )
+        })
+    }
+
+    /// Resolve a constant item, which may be an associated constant of a trait, to the item that
+    /// defines its value. Returns `None` if the item cannot be determined, because it depends on
+    /// type parameters that are not instantiated in the current encoding context.
+    fn resolve_const(
+        &self,
+        def_id: DefId,
+        substs: &'tcx ty::subst::Substs<'tcx>,
+    ) -> Option<(DefId, &'tcx ty::subst::Substs<'tcx>)> {
+        let tcx = self.env().tcx();
+        let tymap = self.current_tymap();
+        let substs = tcx.mk_substs(substs.iter().map(|kind| match kind.unpack() {
+            ty::subst::UnpackedKind::Type(ty) => tymap.get(ty).cloned().unwrap_or(ty).into(),
+            _ => kind.clone(),
+        }));
+        if substs.needs_subst() {
+            return None;
+        }
+        if tcx.trait_of_item(def_id).is_some() {
+            self.env().resolve_method_call(def_id, substs)
+        } else {
+            Some((def_id, substs))
+        }
+    }
+
+    /// Encode an associated constant whose value is unknown as a call of a bodyless function.
+    /// Different uses of the same constant with the same type arguments are encoded as calls of
+    /// the same function, so that the verifier knows that they have the same value.
+    fn encode_symbolic_const(
+        &self,
+        def_id: DefId,
+        substs: &'tcx ty::subst::Substs<'tcx>,
+        ty: ty::Ty<'tcx>,
+    ) -> vir::Expr {
+        trace!("encode_symbolic_const {:?} {:?}", def_id, substs);
+        let mut function_name = format!("constant${}", self.encode_item_name(def_id));
+        for subst_ty in substs.types() {
+            function_name.push_str("$");
+            function_name.push_str(&self.encode_type_predicate_use(self.resolve_typaram(subst_ty)));
+        }
+        let return_type = self.encode_value_type(ty);
+        if !self.associated_constants.borrow().contains_key(&function_name) {
+            let result = vir::LocalVar::new("__result", return_type.clone());
+            let function = vir::Function {
+                name: function_name.clone(),
+                formal_args: vec![],
+                return_type: return_type.clone(),
+                pres: vec![],
+                // The value of a constant is always in the range of its type.
+                posts: self.encode_type_bounds(&result.into(), ty),
+                body: None,
+            };
+            self.associated_constants
+                .borrow_mut()
+                .insert(function_name.clone(), function);
+        }
+        vir::Expr::func_app(
+            function_name,
+            vec![],
+            vec![],
+            return_type,
+            vir::Position::default(),
+        )
+    }
+
+    /// Encode the value of a primitive field of an immutable `static` item, identified by the
+    /// path of field indices `fields`, as a call of a function without arguments. The body of the
+    /// function is the constant that initializes the field, if it can be found in the MIR of the
+    /// item. Otherwise, the function is bodyless and only specifies the range of the value.
+    pub fn encode_static_field_value(
+        &self,
+        def_id: DefId,
+        fields: &[usize],
+        ty: ty::Ty<'tcx>,
+    ) -> vir::Expr {
+        trace!("encode_static_field_value {:?} {:?}", def_id, fields);
+        let mut function_name = format!("static${}", self.encode_item_name(def_id));
+        for field in fields {
+            function_name.push_str(&format!("$f{}", field));
+        }
+        let return_type = self.encode_value_type(ty);
+        if !self.static_items.borrow().contains_key(&function_name) {
+            let body = self
+                .find_static_initializer(def_id, fields)
+                .map(|value| self.encode_const_expr(value));
+            let posts = if body.is_some() {
+                vec![]
+            } else {
+                // The value of a static is always in the range of its type.
+                let result = vir::LocalVar::new("__result", return_type.clone());
+                self.encode_type_bounds(&result.into(), ty)
+            };
+            let function = vir::Function {
+                name: function_name.clone(),
+                formal_args: vec![],
+                return_type: return_type.clone(),
+                pres: vec![],
+                posts,
+                body,
+            };
+            self.static_items
+                .borrow_mut()
+                .insert(function_name.clone(), function);
+        }
+        vir::Expr::func_app(
+            function_name,
+            vec![],
+            vec![],
+            return_type,
+            vir::Position::default(),
+        )
+    }
+
+    /// Find the constant that initializes a field of a local `static` item, by following the
+    /// assignments of the MIR that computes the value of the item.
+    fn find_static_initializer(
+        &self,
+        def_id: DefId,
+        fields: &[usize],
+    ) -> Option<&'tcx ty::Const<'tcx>> {
+        if !def_id.is_local() {
+            return None;
+        }
+        let mir = self.env().tcx().mir_validated(def_id).borrow();
+        find_local_initializer(&mir, mir::RETURN_PLACE, fields)
+    }
+
+    /// Encode the result of the call of a `const fn` without arguments.
+    ///
+    /// If the evaluation fails, the error is registered and the result is encoded as a symbolic
+    /// constant.
+    pub fn encode_const_fn_call(
+        &self,
+        def_id: DefId,
+        substs: &'tcx ty::subst::Substs<'tcx>,
+        span: Span,
+    ) -> vir::Expr {
+        trace!("encode_const_fn_call {:?}", def_id);
+        match self.eval_const(def_id, substs, span) {
+            Ok(const_value) => self.encode_const_expr(const_value),
+            Err(error) => {
+                self.register_encoding_error(error);
+                let tcx = self.env().tcx();
+                let return_ty = tcx.fn_sig(def_id).output().skip_binder().subst(tcx, substs);
+                self.encode_symbolic_const(def_id, substs, return_ty)
+            }
+        }
+    }
+
     pub fn encode_const_expr(&self, value: &ty::Const<'tcx>) -> vir::Expr {
         trace!("encode_const_expr {:?}", value);
         let scalar_value = match value.val {
@@ -921,22 +1713,24 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
                 .to_scalar()
                 .expect(&format!("Unsupported const: {:?}", value)),
             ConstVal::Unevaluated(def_id, substs) => {
-                let tcx = self.env().tcx();
-                let param_env = tcx.param_env(def_id);
-                let cid = GlobalId {
-                    instance: ty::Instance::new(def_id, substs),
-                    promoted: None,
+                let (def_id, substs) = match self.resolve_const(def_id, substs) {
+                    Some(resolved) => resolved,
+                    None => return self.encode_symbolic_const(def_id, substs, value.ty),
                 };
-                if let Ok(const_value) = tcx.const_eval(param_env.and(cid)) {
-                    if let ConstVal::Value(ref value) = const_value.val {
-                        value
-                            .to_scalar()
-                            .expect(&format!("Unsupported const: {:?}", value))
-                    } else {
-                        unreachable!()
+                let span = self.env().tcx().def_span(def_id);
+                let const_value = match self.eval_const(def_id, substs, span) {
+                    Ok(const_value) => const_value,
+                    Err(error) => {
+                        self.register_encoding_error(error);
+                        return self.encode_symbolic_const(def_id, substs, value.ty);
                     }
+                };
+                if let ConstVal::Value(ref value) = const_value.val {
+                    value
+                        .to_scalar()
+                        .expect(&format!("Unsupported const: {:?}", value))
                 } else {
-                    panic!("Constant evaluation of {:?} failed", value.val)
+                    unreachable!()
                 }
             }
         };
@@ -951,6 +1745,8 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
             ((casted_val << shift) >> shift).into()
         }
 
+        // Values of the types that do not fit in an `i64` (`u64`, `u128`, `i128`, `usize` and
+        // `isize`) are converted to `vir::Const::BigInt`, so that they are never truncated.
         let expr = match value.ty.sty {
             ty::TypeVariants::TyBool => scalar_value.to_bool().ok().unwrap().into(),
             ty::TypeVariants::TyInt(ast::IntTy::I8) => (with_sign(
@@ -1139,7 +1935,7 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         &self,
         proc_def_id: ProcedureDefId,
         substs: Vec<(ty::Ty<'tcx>, ty::Ty<'tcx>)>,
-    ) {
+    ) -> EncodingResult<()> {
         trace!("[enter] encode_pure_function_def({:?})", proc_def_id);
         assert!(
             self.env.has_attribute_name(proc_def_id, "pure"),
@@ -1147,6 +1943,8 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
             proc_def_id
         );
 
+        let is_provided_method_instance = self.is_provided_method_instance(proc_def_id, &substs);
+
         {
             // FIXME; hideous monstrosity...
             let mut tymap_stack = self.typaram_repl.borrow_mut();
@@ -1162,16 +1960,35 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         let substs_key = self.type_substitution_key();
         let key = (proc_def_id, substs_key);
 
+        let mut result = Ok(());
         if !self.pure_functions.borrow().contains_key(&key) {
             trace!("not encoded: {:?}", key);
             let procedure = self.env.get_procedure(proc_def_id);
             let pure_function_encoder =
                 PureFunctionEncoder::new(self, proc_def_id, procedure.get_mir(), false);
-            let function = if self.is_trusted(proc_def_id) {
-                pure_function_encoder.encode_bodyless_function()
-            } else {
-                pure_function_encoder.encode_function()
-            };
+            result = self
+                .check_ghost_types(procedure.get_mir())
+                .and_then(|()| self.check_pure_function_signature(procedure.get_mir()));
+            let mut function = None;
+            if is_provided_method_instance {
+                debug!(
+                    "The body of {:?} is verified once for all the implementations",
+                    proc_def_id
+                );
+            } else if result.is_ok() && !self.is_trusted(proc_def_id) {
+                let encoded_function = pure_function_encoder.encode_function();
+                match self.take_pending_error() {
+                    Some(error) => result = Err(error),
+                    None => function = Some(encoded_function),
+                }
+            }
+            // A function whose body uses an unsupported feature is declared without its body,
+            // so that the callers can still be encoded
+            let mut function =
+                function.unwrap_or_else(|| pure_function_encoder.encode_bodyless_function());
+            if self.has_duplicable_reads(proc_def_id) {
+                function = function.make_reads_duplicable();
+            }
             self.log_vir_program_before_viper(function.to_string());
             self.pure_functions.borrow_mut().insert(key, function);
         }
@@ -1182,10 +1999,44 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
             tymap_stack.pop();
         }
         trace!("[exit] encode_pure_function_def({:?})", proc_def_id);
+        result
+    }
+
+    /// Returns true if the substitutions instantiate the `Self` type of a method provided by a
+    /// trait, i.e. for an implementation that does not override the method, and the body of such
+    /// methods is verified only once (`VERIFY_PROVIDED_METHODS_ONCE`). The instance is then
+    /// declared with the contract of the trait and without the body, which is verified by the
+    /// generic instance.
+    fn is_provided_method_instance(
+        &self,
+        proc_def_id: ProcedureDefId,
+        substs: &[(ty::Ty<'tcx>, ty::Ty<'tcx>)],
+    ) -> bool {
+        config::verify_provided_methods_once()
+            && self.env().tcx().trait_of_item(proc_def_id).is_some()
+            && substs.iter().any(|(typ, subst)| typ.is_self() && typ != subst)
+    }
+
+    /// Check that the arguments and the result of a pure function have Viper values, to report
+    /// the unsupported ones at their declaration.
+    fn check_pure_function_signature(&self, mir: &mir::Mir<'tcx>) -> EncodingResult<()> {
+        for local in mir.local_decls.indices().take(mir.arg_count + 1) {
+            let local_decl = &mir.local_decls[local];
+            let ty = self.resolve_typaram(local_decl.ty);
+            if !has_value_type(self.env().tcx(), ty) {
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::ValueType,
+                    local_decl.source_info.span,
+                    format!("pure function with a value of type {:?}", ty),
+                ));
+            }
+        }
+        Ok(())
     }
 
     pub fn encode_pure_function_use(&self, proc_def_id: ProcedureDefId) -> String {
         trace!("encode_pure_function_use({:?})", proc_def_id);
+        let proc_def_id = self.get_extern_spec_procedure(proc_def_id).unwrap_or(proc_def_id);
         assert!(
             self.env.has_attribute_name(proc_def_id, "pure"),
             "procedure is not marked as pure: {:?}",
@@ -1200,6 +2051,7 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
 
     pub fn encode_pure_function_return_type(&self, proc_def_id: ProcedureDefId) -> vir::Type {
         trace!("encode_pure_function_return_type({:?})", proc_def_id);
+        let proc_def_id = self.get_extern_spec_procedure(proc_def_id).unwrap_or(proc_def_id);
         assert!(
             self.env.has_attribute_name(proc_def_id, "pure"),
             "procedure is not marked as pure: {:?}",
@@ -1212,15 +2064,41 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         pure_function_encoder.encode_function_return_type()
     }
 
+    /// Check that the public contract of a procedure does not reveal the private fields of the
+    /// types that have model fields. Returns `false` if an error has been reported.
+    pub fn check_model_fields(&self, proc_def_id: ProcedureDefId) -> bool {
+        model_fields::check_contract_hides_fields(self, proc_def_id)
+    }
+
+    /// Check that the contract of a procedure exported from a library does not mention fields
+    /// that are not visible to its callers. Returns `false` if an error has been reported.
+    pub fn check_contract_visibility(&self, proc_def_id: ProcedureDefId) -> bool {
+        spec_visibility::check_contract_visibility(self, proc_def_id)
+    }
+
     pub fn queue_procedure_encoding(&self, proc_def_id: ProcedureDefId) {
         self.encoding_queue
             .borrow_mut()
             .push((proc_def_id, Vec::new()));
     }
 
+    /// Queue the encoding of the instantiation of a pure function for the current type
+    /// substitutions. Only the substitutions of the type parameters of the function are kept, so
+    /// that all the uses of the same instantiation share a single Viper function.
     pub fn queue_pure_function_encoding(&self, proc_def_id: ProcedureDefId) {
-        let substs = self.current_tymap().into_iter().collect();
-        self.encoding_queue.borrow_mut().push((proc_def_id, substs));
+        let own_substs = ty::subst::Substs::identity_for_item(self.env.tcx(), proc_def_id);
+        let type_params: HashSet<_> = own_substs.types().collect();
+        let substs: Vec<_> = self
+            .current_tymap()
+            .into_iter()
+            .filter(|(typ, _)| type_params.contains(typ))
+            .collect();
+        let key = (proc_def_id, self.substitution_key(&substs));
+        if self.pure_function_instantiations.borrow_mut().insert(key) {
+            self.encoding_queue.borrow_mut().push((proc_def_id, substs));
+        } else {
+            trace!("already queued: {:?} {:?}", proc_def_id, substs);
+        }
     }
 
     pub fn process_encoding_queue(&mut self) {
@@ -1234,9 +2112,18 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
                 "Encoding: {} from {:?} ({})",
                 proc_name, proc_span, proc_def_path
             );
+            // The errors registered after the encoding of the previous item (e.g. by its sanity
+            // checks) are reported on their own
+            self.encoding_errors.borrow_mut().extend(self.take_pending_error());
             let is_pure_function = self.env.has_attribute_name(proc_def_id, "pure");
             if is_pure_function {
-                self.encode_pure_function_def(proc_def_id, substs);
+                if let Err(error) = self.encode_pure_function_def(proc_def_id, substs) {
+                    debug!(
+                        "Pure function {:?} uses an unsupported feature: {:?}",
+                        proc_def_id, error
+                    );
+                    self.encoding_errors.borrow_mut().push(error);
+                }
             } else {
                 assert!(substs.is_empty());
                 if self.is_trusted(proc_def_id) {
@@ -1244,16 +2131,24 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
                         "Trusted procedure will not be encoded or verified: {:?}",
                         proc_def_id
                     );
-                } else {
-                    self.encode_procedure(proc_def_id);
+                } else if let Err(error) = self.encode_procedure(proc_def_id) {
+                    debug!(
+                        "Procedure {:?} uses an unsupported feature: {:?}",
+                        proc_def_id, error
+                    );
+                    self.encoding_errors.borrow_mut().push(error);
                 }
             }
         }
+        // The errors registered after the encoding of the last item
+        self.encoding_errors.borrow_mut().extend(self.take_pending_error());
     }
 
     pub fn is_trusted(&self, def_id: ProcedureDefId) -> bool {
         trace!("is_trusted {:?}", def_id);
         let result = self.env().has_attribute_name(def_id, "trusted")
+            || self.env().has_attribute_name(def_id, "trusted_ffi")
+            || self.env().is_skipped(def_id)
             || (self.use_whitelist
                 && !self
                     .whitelist
@@ -1273,6 +2168,18 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         ty
     }
 
+    /// Normalize the associated type projections of a type that does not depend on type
+    /// parameters, e.g. `<std::vec::Vec<u32> as IntoIterator>::Item` into `u32`. The projections
+    /// that depend on type parameters, e.g. `<T as Iterator>::Item`, are left as they are.
+    pub fn normalize_projections(&self, ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+        if ty.has_projections() && !ty.needs_subst() {
+            let tcx = self.env().tcx();
+            tcx.normalize_erasing_regions(ty::ParamEnv::reveal_all(), ty)
+        } else {
+            ty
+        }
+    }
+
     /// Merges the stack of type maps into a single map.
     pub fn current_tymap(&self) -> HashMap<ty::Ty<'tcx>, ty::Ty<'tcx>> {
         let mut map = HashMap::new();
@@ -1292,10 +2199,19 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         map
     }
 
-    /// TODO: This is a hack, it generates strings that can be used to instantiate generic pure
-    /// functions.
-    pub fn type_substitution_strings(&self) -> HashMap<String, String> {
-        self.current_tymap()
+    /// The substitution of the type parameters of the VIR types that corresponds to the current
+    /// type map. It is used to instantiate generic pure functions and the specifications of
+    /// generic callees.
+    pub fn type_substitution(&self) -> vir::typaram::Substs {
+        let substs: Vec<_> = self.current_tymap().into_iter().collect();
+        vir::typaram::Substs::new(self.encode_substitution_strings(&substs))
+    }
+
+    fn encode_substitution_strings(
+        &self,
+        substs: &[(ty::Ty<'tcx>, ty::Ty<'tcx>)],
+    ) -> HashMap<String, String> {
+        substs
             .iter()
             .map(|(typ, subst)| {
                 let encoded_typ = match self.encode_type(typ) {
@@ -1314,8 +2230,14 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
     /// TODO: This is a hack, it generates a String that can be used for uniquely identifying this
     /// type substitution.
     pub fn type_substitution_key(&self) -> String {
+        let substs: Vec<_> = self.current_tymap().into_iter().collect();
+        self.substitution_key(&substs)
+    }
+
+    /// Generates a String that uniquely identifies the given type substitutions.
+    fn substitution_key(&self, substs: &[(ty::Ty<'tcx>, ty::Ty<'tcx>)]) -> String {
         let mut substs: Vec<_> = self
-            .type_substitution_strings()
+            .encode_substitution_strings(substs)
             .into_iter()
             .filter(|(typ, subst)| typ != subst)
             .map(|(typ, subst)| format!("({},{})", typ, subst))
@@ -1324,3 +2246,58 @@ impl<'v, 'r, 'a, 'tcx> Encoder<'v, 'r, 'a, 'tcx> {
         substs.join(";")
     }
 }
+
+/// Find the constant assigned to a field of `local`, if `local` is assigned exactly once.
+fn find_local_initializer<'tcx>(
+    mir: &mir::Mir<'tcx>,
+    local: mir::Local,
+    fields: &[usize],
+) -> Option<&'tcx ty::Const<'tcx>> {
+    let mut rvalues = mir
+        .basic_blocks()
+        .iter()
+        .flat_map(|block| block.statements.iter())
+        .filter_map(|stmt| match stmt.kind {
+            mir::StatementKind::Assign(mir::Place::Local(target), ref rvalue)
+                if target == local =>
+            {
+                Some(rvalue)
+            }
+            _ => None,
+        });
+    let rvalue = rvalues.next()?;
+    if rvalues.next().is_some() {
+        return None;
+    }
+    match (rvalue, fields.split_first()) {
+        (&mir::Rvalue::Use(ref operand), _) => find_operand_initializer(mir, operand, fields),
+        (
+            &mir::Rvalue::Aggregate(box mir::AggregateKind::Tuple, ref operands),
+            Some((&field, rest)),
+        ) => find_operand_initializer(mir, &operands[field], rest),
+        (
+            &mir::Rvalue::Aggregate(box mir::AggregateKind::Adt(adt_def, ..), ref operands),
+            Some((&field, rest)),
+        ) if adt_def.is_struct() => find_operand_initializer(mir, &operands[field], rest),
+        _ => None,
+    }
+}
+
+/// Find the constant that a field of `operand` evaluates to.
+fn find_operand_initializer<'tcx>(
+    mir: &mir::Mir<'tcx>,
+    operand: &mir::Operand<'tcx>,
+    fields: &[usize],
+) -> Option<&'tcx ty::Const<'tcx>> {
+    match operand {
+        &mir::Operand::Constant(box mir::Constant {
+            literal: mir::Literal::Value { value },
+            ..
+        }) if fields.is_empty() => Some(value),
+        &mir::Operand::Copy(mir::Place::Local(local))
+        | &mir::Operand::Move(mir::Place::Local(local)) => {
+            find_local_initializer(mir, local, fields)
+        }
+        _ => None,
+    }
+}
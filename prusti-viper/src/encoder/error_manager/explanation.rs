@@ -0,0 +1,206 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Explanation of verification errors in terms of the encoding: the Viper statement that fails,
+//! the statements around it and the Rust code from which their positions have been generated.
+
+use super::ErrorManager;
+use encoder::vir::{default_walk_expr, CfgMethod, Expr, ExprWalker, Position, Stmt, StmtWalker};
+use std::collections::HashSet;
+use std::fmt::Write;
+use viper::VerificationError;
+
+/// The number of statements shown before the failing one.
+const STATEMENTS_BEFORE: usize = 6;
+
+/// The number of statements shown after the failing one.
+const STATEMENTS_AFTER: usize = 2;
+
+/// The positions of a statement and of its expressions, in the order in which they appear.
+fn collect_positions(stmt: &Stmt) -> Vec<Position> {
+    struct PositionCollector {
+        positions: Vec<Position>,
+    }
+    impl ExprWalker for PositionCollector {
+        fn walk(&mut self, expr: &Expr) {
+            self.positions.push(expr.pos().clone());
+            default_walk_expr(self, expr);
+        }
+    }
+    impl StmtWalker for PositionCollector {
+        fn walk_expr(&mut self, expr: &Expr) {
+            ExprWalker::walk(self, expr);
+        }
+    }
+    let mut collector = PositionCollector { positions: vec![] };
+    if let Some(pos) = stmt.pos() {
+        collector.positions.push(pos.clone());
+    }
+    StmtWalker::walk(&mut collector, stmt);
+    collector
+        .positions
+        .into_iter()
+        .filter(|pos| !pos.is_default())
+        .collect()
+}
+
+/// The method, the index of the block and the index of the statement in the block of the
+/// statement that has the position `pos_id`, or that contains an expression with it.
+fn find_stmt<'m>(methods: &'m [CfgMethod], pos_id: &str) -> Option<(&'m CfgMethod, usize, usize)> {
+    for method in methods {
+        for (block_index, block) in method.basic_blocks.iter().enumerate() {
+            for (stmt_index, stmt) in block.stmts.iter().enumerate() {
+                if collect_positions(stmt).iter().any(|pos| pos.id() == pos_id) {
+                    return Some((method, block_index, stmt_index));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The expression of `stmt` that has the position `pos_id`, if any.
+fn find_expr(stmt: &Stmt, pos_id: &str) -> Option<Expr> {
+    struct ExprFinder<'a> {
+        pos_id: &'a str,
+        found: Option<Expr>,
+    }
+    impl<'a> ExprWalker for ExprFinder<'a> {
+        fn walk(&mut self, expr: &Expr) {
+            if self.found.is_none() {
+                if expr.pos().id() == self.pos_id {
+                    self.found = Some(expr.clone());
+                } else {
+                    default_walk_expr(self, expr);
+                }
+            }
+        }
+    }
+    impl<'a> StmtWalker for ExprFinder<'a> {
+        fn walk_expr(&mut self, expr: &Expr) {
+            ExprWalker::walk(self, expr);
+        }
+    }
+    let mut finder = ExprFinder {
+        pos_id,
+        found: None,
+    };
+    StmtWalker::walk(&mut finder, stmt);
+    finder.found
+}
+
+impl<'tcx> ErrorManager<'tcx> {
+    /// Describe the Rust code from which a position has been generated, e.g.
+    /// `src/main.rs:12:5: 12:20 (AssertMethodPostcondition)`.
+    pub fn describe_position(&self, pos_id: &str) -> Option<String> {
+        let (span, error_ctxt) = self.error_contexts.get(pos_id)?;
+        let location = match span.primary_span() {
+            Some(primary_span) => self.codemap.span_to_string(primary_span.source_callsite()),
+            None => "<no span>".to_string(),
+        };
+        Some(format!("{} ({:?})", location, error_ctxt))
+    }
+
+    /// Explain a verification error with the Viper statement that fails (found among the
+    /// statements of `methods`), the statements around it and a table that maps the positions
+    /// of these statements to the Rust code.
+    pub fn explain(&self, ver_error: &VerificationError, methods: &[CfgMethod]) -> String {
+        let mut res = String::new();
+        writeln!(res, "Verification error {}: {}", ver_error.full_id, ver_error.message).unwrap();
+        let pos_id = match ver_error.pos_id {
+            Some(ref pos_id) => pos_id,
+            None => {
+                writeln!(res, "The error has no position.").unwrap();
+                return res;
+            }
+        };
+
+        let (method, block_index, stmt_index) = match find_stmt(methods, pos_id) {
+            Some(location) => location,
+            None => {
+                writeln!(res, "The failing statement is not in an encoded method.").unwrap();
+                if let Some(description) = self.describe_position(pos_id) {
+                    writeln!(res, "Position {}: {}", pos_id, description).unwrap();
+                }
+                return res;
+            }
+        };
+
+        let stmts = &method.basic_blocks[block_index].stmts;
+        writeln!(
+            res,
+            "In method {}, block {}:",
+            method.name(),
+            method.get_block_label(block_index)
+        ).unwrap();
+        let first = stmt_index.saturating_sub(STATEMENTS_BEFORE);
+        let last = (stmt_index + STATEMENTS_AFTER).min(stmts.len() - 1);
+        let mut positions = vec![];
+        for (index, stmt) in stmts.iter().enumerate().take(last + 1).skip(first) {
+            let marker = if index == stmt_index { ">>" } else { "  " };
+            for (line_index, line) in stmt.to_string().lines().enumerate() {
+                let marker = if line_index == 0 { marker } else { "  " };
+                writeln!(res, "  {} {}", marker, line).unwrap();
+            }
+            positions.extend(collect_positions(stmt));
+        }
+
+        let reason = ver_error
+            .reason_pos_id
+            .as_ref()
+            .and_then(|reason_pos_id| find_expr(&stmts[stmt_index], reason_pos_id));
+        if let Some(reason) = reason {
+            writeln!(res, "The failing part of the statement is: {}", reason).unwrap();
+        }
+
+        writeln!(res, "Positions:").unwrap();
+        let mut described = HashSet::new();
+        for pos in positions {
+            if !described.insert(pos.id()) {
+                continue;
+            }
+            if let Some(description) = self.describe_position(&pos.id()) {
+                writeln!(res, "  {}: {}", pos.id(), description).unwrap();
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoder::vir::{FoldingBehaviour, Successor};
+
+    #[test]
+    fn test_find_stmt() {
+        let mut method = CfgMethod::new("m".to_string(), vec![], vec![], vec![], vec![]);
+        let block = method.add_block("start", vec![], vec![Stmt::comment("start")]);
+        let failing_expr = Expr::from(false).set_pos(Position::new(3, 5, "expr".to_string()));
+        method.add_stmt(
+            block,
+            Stmt::Assert(
+                failing_expr,
+                FoldingBehaviour::Expr,
+                Position::new(3, 5, "stmt".to_string()),
+            ),
+        );
+        method.set_successor(block, Successor::Return);
+        let methods = vec![method];
+
+        for pos_id in &["stmt", "expr"] {
+            let (found_method, block_index, stmt_index) = find_stmt(&methods, pos_id).unwrap();
+            assert_eq!(found_method.name(), "m");
+            assert_eq!(found_method.get_block_label(block_index), "start");
+            assert_eq!(stmt_index, 1);
+        }
+        assert!(find_stmt(&methods, "other").is_none());
+        assert_eq!(
+            find_expr(&methods[0].basic_blocks[0].stmts[1], "expr"),
+            Some(false.into())
+        );
+    }
+}
@@ -4,15 +4,18 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use encoder::vir::Position;
+use encoder::vir::{default_walk_expr, Expr, ExprWalker, Position, Stmt, StmtWalker};
+use prusti_interface::config;
 use std::collections::HashMap;
 use syntax::codemap::CodeMap;
 use syntax_pos::MultiSpan;
 use uuid::Uuid;
-use viper::VerificationError;
+use viper::{VerificationBackend, VerificationError};
+
+mod explanation;
 
 /// The cause of a panic!()
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PanicCause {
     /// Unknown cause
     Unknown,
@@ -20,10 +23,34 @@ pub enum PanicCause {
     Panic,
     /// Caused by an assert!()
     Assert,
+    /// Caused by an assert_eq!()
+    AssertEq,
+    /// Caused by an assert_ne!()
+    AssertNe,
+    /// Caused by a debug_assert!()
+    DebugAssert,
+    /// Caused by a debug_assert_eq!()
+    DebugAssertEq,
+    /// Caused by a debug_assert_ne!()
+    DebugAssertNe,
     /// Caused by an unreachable!()
     Unreachable,
     /// Caused by an unimplemented!()
     Unimplemented,
+    /// Caused by the call of a procedure that may panic
+    Call,
+}
+
+impl PanicCause {
+    /// Is the panic caused by a `debug_assert!`, `debug_assert_eq!` or `debug_assert_ne!`?
+    pub fn is_debug_assertion(&self) -> bool {
+        match *self {
+            PanicCause::DebugAssert | PanicCause::DebugAssertEq | PanicCause::DebugAssertNe => {
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
 /// In case of verification error, this enum will contain additional information
@@ -38,6 +65,15 @@ pub enum ErrorCtxt {
     AssertMethodPostcondition,
     /// A Viper `assert expr` that encodes the call of a Rust procedure with precondition `expr`
     AssertMethodPostconditionTypeInvariants,
+    /// A Viper `assert expr` that encodes a panic in a Rust procedure with panic postcondition
+    /// `expr`
+    AssertMethodPanicPostcondition,
+    /// A Viper `assert expr` that checks that the precondition `expr` of the implementation of a
+    /// trait method is implied by the precondition of the trait
+    AssertRefinedPrecondition,
+    /// A Viper `assert expr` that checks that the postcondition of the implementation of a
+    /// trait method implies the postcondition `expr` of the trait
+    AssertRefinedPostcondition,
     /// A Viper `exhale expr` that encodes the end of a Rust procedure with postcondition `expr`
     ExhaleMethodPostcondition,
     /// A Viper `exhale expr` that exhales the permissions of a loop invariant `expr`
@@ -49,6 +85,19 @@ pub enum ErrorCtxt {
     /// A Viper `assert false` that encodes the failure (panic) of an `assert` Rust terminator
     /// Arguments: the message of the Rust assertion
     AssertTerminator(String),
+    /// A Viper `assert false` that encodes the failure (panic) of the overflow check of a
+    /// subtraction of unsigned integers.
+    /// Arguments: the source code of the operands of the subtraction
+    UnsignedSubtractionUnderflow(String, String),
+    /// A Viper `assert expr` that encodes a `prusti_assert!(expr)` ghost statement
+    AssertGhostStatement,
+    /// A Viper `fold` that encodes a `fold!(..)` ghost statement
+    FoldGhostStatement,
+    /// A Viper `assert expr` that checks that an integer cast does not truncate the value
+    IntegerCast,
+    /// A Viper `assert expr` that checks the type invariant of a value that is dropped, because
+    /// its type implements `Drop`
+    DropTypeInvariant,
     /// A Viper `assert false` that encodes an `abort` Rust terminator
     AbortTerminator,
     /// A Viper `assert false` that encodes an `unreachable` Rust terminator
@@ -79,6 +128,9 @@ pub enum ErrorCtxt {
     DivergingCallInPureFunction,
     /// A Viper pure function call with `false` precondition that encodes a Rust panic in a pure function
     PanicInPureFunction(PanicCause),
+    /// A Viper pure function call whose precondition checks that the measure of a recursive
+    /// pure function decreases at a recursive call
+    PureFunctionTermination,
 }
 
 /// The Rust error that will be reported from the compiler
@@ -87,6 +139,11 @@ pub struct CompilerError {
     pub message: String,
     pub span: MultiSpan,
     pub reason_span: Option<MultiSpan>,
+    /// The branch decisions that lead to the error, e.g. "this error happens when `x < 0` is
+    /// true"
+    pub note: Option<String>,
+    /// A suggestion of how to fix the error
+    pub help: Option<String>,
 }
 
 impl CompilerError {
@@ -95,6 +152,8 @@ impl CompilerError {
             message: message.to_string(),
             span,
             reason_span,
+            note: None,
+            help: None,
         }
     }
 }
@@ -103,14 +162,27 @@ impl CompilerError {
 #[derive(Clone)]
 pub struct ErrorManager<'tcx> {
     codemap: &'tcx CodeMap,
+    backend: VerificationBackend,
     error_contexts: HashMap<String, (MultiSpan, ErrorCtxt)>,
+    /// The reasons of the specifications, indexed by the positions of their expressions.
+    reasons: HashMap<String, String>,
+    /// The branch decisions that are taken on every path that reaches a statement or an
+    /// expression, indexed by its position.
+    branch_conditions: HashMap<String, Vec<String>>,
+    /// The spans of the specifications that are checked at a position, but that are written
+    /// elsewhere, indexed by the position (e.g. the preconditions of a function checked at a call).
+    spec_spans: HashMap<String, MultiSpan>,
 }
 
 impl<'tcx> ErrorManager<'tcx> {
-    pub fn new(codemap: &'tcx CodeMap) -> Self {
+    pub fn new(codemap: &'tcx CodeMap, backend: VerificationBackend) -> Self {
         ErrorManager {
             codemap,
+            backend,
             error_contexts: HashMap::new(),
+            reasons: HashMap::new(),
+            branch_conditions: HashMap::new(),
+            spec_spans: HashMap::new(),
         }
     }
 
@@ -133,12 +205,110 @@ impl<'tcx> ErrorManager<'tcx> {
         pos
     }
 
+    /// Register a new position with the span of an existing position, to report a different
+    /// error at the same place.
+    pub fn register_same_span(&mut self, pos: &Position, error_ctxt: ErrorCtxt) -> Position {
+        let span = self.error_contexts[&pos.id()].0.clone();
+        self.register(span, error_ctxt)
+    }
+
+    /// The span of the Rust code from which a position has been generated, if any.
+    pub fn get_span(&self, pos: &Position) -> Option<MultiSpan> {
+        self.error_contexts.get(&pos.id()).map(|(span, _)| span.clone())
+    }
+
     pub fn redefine(&mut self, pos: &Position, span: MultiSpan, error_ctxt: ErrorCtxt) {
         debug!("Register position: {:?}", pos);
         self.error_contexts.insert(pos.id(), (span, error_ctxt));
     }
 
+    /// Record the spans of the specification that is checked at a position, so that they can be
+    /// reported together with the position when the verifier does not tell which part of the
+    /// specification fails.
+    pub fn register_spec_span(&mut self, pos: &Position, spec_span: MultiSpan) {
+        self.spec_spans.insert(pos.id(), spec_span);
+    }
+
+    /// Record the reason of a specification encoded as `expr`, so that it can be reported when
+    /// a part of the specification does not hold.
+    pub fn register_reason(&mut self, expr: &Expr, reason: &str) {
+        struct PositionCollector {
+            positions: Vec<Position>,
+        }
+        impl ExprWalker for PositionCollector {
+            fn walk(&mut self, expr: &Expr) {
+                self.positions.push(expr.pos().clone());
+                default_walk_expr(self, expr);
+            }
+        }
+        let mut collector = PositionCollector { positions: vec![] };
+        collector.walk(expr);
+        for pos in collector.positions {
+            if !pos.is_default() {
+                self.reasons.insert(pos.id(), reason.to_string());
+            }
+        }
+    }
+
+    /// Record the branch decisions that lead to some statements, so that they can be reported
+    /// when the statements (or their expressions) fail. The statements of a position that is
+    /// reached in different ways are reported with the decisions that they have in common.
+    pub fn register_branch_conditions(&mut self, stmts: &[Stmt], conditions: &[String]) {
+        struct PositionCollector {
+            positions: Vec<Position>,
+        }
+        impl ExprWalker for PositionCollector {
+            fn walk(&mut self, expr: &Expr) {
+                self.positions.push(expr.pos().clone());
+                default_walk_expr(self, expr);
+            }
+        }
+        impl StmtWalker for PositionCollector {
+            fn walk_expr(&mut self, expr: &Expr) {
+                ExprWalker::walk(self, expr);
+            }
+        }
+        let mut collector = PositionCollector { positions: vec![] };
+        for stmt in stmts {
+            if let Some(pos) = stmt.pos() {
+                collector.positions.push(pos.clone());
+            }
+            StmtWalker::walk(&mut collector, stmt);
+        }
+        for pos in collector.positions {
+            if pos.is_default() {
+                continue;
+            }
+            let common_conditions = match self.branch_conditions.get(&pos.id()) {
+                Some(existing_conditions) => conditions
+                    .iter()
+                    .filter(|condition| existing_conditions.contains(condition))
+                    .cloned()
+                    .collect(),
+                None => conditions.to_vec(),
+            };
+            self.branch_conditions.insert(pos.id(), common_conditions);
+        }
+    }
+
     pub fn translate(&self, ver_error: &VerificationError) -> CompilerError {
+        let mut compiler_error = self.translate_error(ver_error);
+        let conditions = ver_error
+            .pos_id
+            .as_ref()
+            .and_then(|pos_id| self.branch_conditions.get(pos_id));
+        if let Some(conditions) = conditions {
+            if !conditions.is_empty() {
+                compiler_error.note = Some(format!(
+                    "this error happens when {}",
+                    conditions.join(" and ")
+                ));
+            }
+        }
+        compiler_error
+    }
+
+    fn translate_error(&self, ver_error: &VerificationError) -> CompilerError {
         debug!("Verification error: {:?}", ver_error);
         let pos_id = &ver_error.pos_id;
         let opt_error_ctxt = pos_id
@@ -183,7 +353,13 @@ impl<'tcx> ErrorManager<'tcx> {
                 }
             };
 
-        match (ver_error.full_id.as_str(), error_ctxt) {
+        let full_id = normalize_error_id(self.backend, &ver_error.full_id, error_ctxt);
+        let reason = ver_error
+            .reason_pos_id
+            .as_ref()
+            .and_then(|pos_id| self.reasons.get(pos_id));
+
+        match (full_id, error_ctxt) {
             ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Unknown)) => {
                 CompilerError::new("statement might panic", error_span, reason_span)
             }
@@ -200,6 +376,46 @@ impl<'tcx> ErrorManager<'tcx> {
                 )
             }
 
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::AssertEq)) => {
+                CompilerError::new(
+                    "assert_eq!(..) statement might not hold",
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::AssertNe)) => {
+                CompilerError::new(
+                    "assert_ne!(..) statement might not hold",
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::DebugAssert)) => {
+                CompilerError::new(
+                    "debug_assert!(..) statement might not hold",
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::DebugAssertEq)) => {
+                CompilerError::new(
+                    "debug_assert_eq!(..) statement might not hold",
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::DebugAssertNe)) => {
+                CompilerError::new(
+                    "debug_assert_ne!(..) statement might not hold",
+                    error_span,
+                    reason_span,
+                )
+            }
+
             ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Unreachable)) => {
                 CompilerError::new(
                     "unreachable!(..) statement might be reachable",
@@ -216,6 +432,14 @@ impl<'tcx> ErrorManager<'tcx> {
                 )
             }
 
+            ("assert.failed:assertion.false", ErrorCtxt::Panic(PanicCause::Call)) => {
+                CompilerError::new(
+                    "the called function might panic",
+                    error_span,
+                    reason_span,
+                )
+            }
+
             ("assert.failed:assertion.false", ErrorCtxt::AssertTerminator(ref message)) => {
                 CompilerError::new(
                     format!("assertion might fail with \"{}\"", message),
@@ -224,6 +448,52 @@ impl<'tcx> ErrorManager<'tcx> {
                 )
             }
 
+            (
+                "assert.failed:assertion.false",
+                ErrorCtxt::UnsignedSubtractionUnderflow(ref left, ref right),
+            ) => {
+                let mut error = CompilerError::new(
+                    format!(
+                        "the unsigned subtraction `{} - {}` might underflow below zero",
+                        left, right
+                    ),
+                    error_span,
+                    reason_span,
+                );
+                if config::suggest_underflow_guards() {
+                    error.help = Some(format!(
+                        "consider checking that `{} >= {}` before the subtraction (e.g. in the \
+                         condition of the loop or in a precondition)",
+                        left, right
+                    ));
+                }
+                error
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertGhostStatement) => {
+                CompilerError::new(
+                    "prusti_assert!(..) statement might not hold",
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::IntegerCast) => {
+                CompilerError::new(
+                    "the value might not fit into the target type of the cast",
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::DropTypeInvariant) => {
+                CompilerError::new(
+                    "type invariant might not hold when the value is dropped",
+                    error_span,
+                    reason_span,
+                )
+            }
+
             ("assert.failed:assertion.false", ErrorCtxt::AbortTerminator) => {
                 CompilerError::new(format!("statement might abort"), error_span, reason_span)
             }
@@ -239,10 +509,30 @@ impl<'tcx> ErrorManager<'tcx> {
             }
 
             ("assert.failed:assertion.false", ErrorCtxt::ExhaleMethodPrecondition) => {
+                // Label the failing precondition of the callee in the error at the call site
+                let mut error_span = error_span;
+                let spec_span = pos_id
+                    .as_ref()
+                    .and_then(|pos_id| self.spec_spans.get(pos_id));
+                if let Some(reason_span) = reason_span {
+                    for &span in reason_span.primary_spans() {
+                        error_span.push_span_label(
+                            span,
+                            "this precondition might not hold".to_string(),
+                        );
+                    }
+                } else if let Some(spec_span) = spec_span {
+                    for &span in spec_span.primary_spans() {
+                        error_span.push_span_label(
+                            span,
+                            "the precondition of the called function".to_string(),
+                        );
+                    }
+                }
                 CompilerError::new(
-                    format!("precondition might not hold."),
+                    with_reason("precondition might not hold", reason),
                     error_span,
-                    reason_span,
+                    None,
                 )
             }
 
@@ -306,7 +596,7 @@ impl<'tcx> ErrorManager<'tcx> {
 
             ("application.precondition:assertion.false", ErrorCtxt::PureFunctionCall) => {
                 CompilerError::new(
-                    format!("precondition of pure function call might not hold."),
+                    with_reason("precondition of pure function call might not hold", reason),
                     error_span,
                     reason_span,
                 )
@@ -356,6 +646,51 @@ impl<'tcx> ErrorManager<'tcx> {
                 reason_span,
             ),
 
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PanicInPureFunction(PanicCause::AssertEq),
+            ) => CompilerError::new(
+                "assert_eq!(..) statement in pure function might not hold",
+                error_span,
+                reason_span,
+            ),
+
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PanicInPureFunction(PanicCause::AssertNe),
+            ) => CompilerError::new(
+                "assert_ne!(..) statement in pure function might not hold",
+                error_span,
+                reason_span,
+            ),
+
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PanicInPureFunction(PanicCause::DebugAssert),
+            ) => CompilerError::new(
+                "debug_assert!(..) statement in pure function might not hold",
+                error_span,
+                reason_span,
+            ),
+
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PanicInPureFunction(PanicCause::DebugAssertEq),
+            ) => CompilerError::new(
+                "debug_assert_eq!(..) statement in pure function might not hold",
+                error_span,
+                reason_span,
+            ),
+
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PanicInPureFunction(PanicCause::DebugAssertNe),
+            ) => CompilerError::new(
+                "debug_assert_ne!(..) statement in pure function might not hold",
+                error_span,
+                reason_span,
+            ),
+
             (
                 "application.precondition:assertion.false",
                 ErrorCtxt::PanicInPureFunction(PanicCause::Unreachable),
@@ -384,6 +719,15 @@ impl<'tcx> ErrorManager<'tcx> {
                 )
             }
 
+            (
+                "application.precondition:assertion.false",
+                ErrorCtxt::PureFunctionTermination,
+            ) => CompilerError::new(
+                "termination of the recursive call in pure function might not hold",
+                error_span,
+                reason_span,
+            ),
+
             (
                 "application.precondition:assertion.false",
                 ErrorCtxt::PureFunctionAssertTerminator(ref message),
@@ -403,7 +747,31 @@ impl<'tcx> ErrorManager<'tcx> {
 
             ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPostcondition) => {
                 CompilerError::new(
-                    format!("postcondition might not hold."),
+                    with_reason("postcondition might not hold", reason),
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertRefinedPrecondition) => {
+                CompilerError::new(
+                    "the precondition of the trait might not imply the refined precondition.",
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertRefinedPostcondition) => {
+                CompilerError::new(
+                    "the refined postcondition might not imply the postcondition of the trait.",
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("assert.failed:assertion.false", ErrorCtxt::AssertMethodPanicPostcondition) => {
+                CompilerError::new(
+                    "panic postcondition might not hold.",
                     error_span,
                     reason_span,
                 )
@@ -427,6 +795,23 @@ impl<'tcx> ErrorManager<'tcx> {
                 )
             }
 
+            ("fold.failed:assertion.false", ErrorCtxt::FoldGhostStatement) => {
+                CompilerError::new(
+                    "the predicate of the fold!(..) statement might not hold",
+                    error_span,
+                    reason_span,
+                )
+            }
+
+            ("fold.failed:insufficient.permission", ErrorCtxt::FoldGhostStatement) => {
+                CompilerError::new(
+                    "there might be insufficient permission to fold the predicate of the \
+                     fold!(..) statement",
+                    error_span,
+                    reason_span,
+                )
+            }
+
             (full_err_id, ErrorCtxt::Unexpected) => CompilerError::new(
                 format!(
                     "internal encoding error - unexpected verification error: [{}] {}",
@@ -453,3 +838,44 @@ impl<'tcx> ErrorManager<'tcx> {
         }
     }
 }
+
+/// Append the reason of the specification that does not hold, if any, to an error message.
+fn with_reason(message: &str, reason: Option<&String>) -> String {
+    match reason {
+        Some(reason) => format!("{}: {}.", message, reason),
+        None => format!("{}.", message),
+    }
+}
+
+/// Carbon and Silicon do not always report the same failure with the same error identifier.
+/// Map the identifiers reported by Carbon to the ones reported by Silicon, so that the
+/// translation of verification errors does not depend on the backend.
+fn normalize_error_id<'a>(
+    backend: VerificationBackend,
+    full_id: &'a str,
+    error_ctxt: &ErrorCtxt,
+) -> &'a str {
+    if backend != VerificationBackend::Carbon {
+        return full_id;
+    }
+    match (full_id, error_ctxt) {
+        // Carbon reports the failure of the pure part of an `exhale` as an exhale failure,
+        // while Silicon reports it as the failure of the corresponding `assert`.
+        ("exhale.failed:assertion.false", ErrorCtxt::ExhaleMethodPrecondition)
+        | ("exhale.failed:assertion.false", ErrorCtxt::ExhaleMethodPostcondition)
+        | ("exhale.failed:assertion.false", ErrorCtxt::ExhaleLoopInvariantOnEntry)
+        | ("exhale.failed:assertion.false", ErrorCtxt::ExhaleLoopInvariantAfterIteration) => {
+            "assert.failed:assertion.false"
+        }
+
+        // Carbon reports the failure of the body of a `fold` that the encoder generates in the
+        // package of a magic wand as a permission failure. In the other contexts, including the
+        // user-written `fold!(..)` statements, a permission failure is a genuine one and must be
+        // reported as such.
+        ("fold.failed:insufficient.permission", ErrorCtxt::PackageMagicWandForPostcondition) => {
+            "fold.failed:assertion.false"
+        }
+
+        _ => full_id,
+    }
+}
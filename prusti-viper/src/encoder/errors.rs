@@ -0,0 +1,266 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use rustc::mir;
+use std::fmt;
+use syntax_pos::MultiSpan;
+
+/// The Rust features that the encoder does not support.
+///
+/// Each feature has a stable name, so that the reports of different crates (or of different
+/// versions of Prusti) can be aggregated and compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UnsupportedFeature {
+    /// The length of an array or slice, computed with `Rvalue::Len`
+    ArrayLength,
+    /// An array expression of the form `[x; N]`
+    ArrayRepeat,
+    /// A cast that is not a numeric cast (e.g. an unsizing or a function pointer cast)
+    PointerCast,
+    /// The `size_of` intrinsic
+    SizeOf,
+    /// A unique borrow, which is created when a closure captures a mutable reference
+    UniqueBorrow,
+    /// The `asm!` macro
+    InlineAssembly,
+    /// A MIR statement that is not generated from plain Rust code (e.g. `SetDiscriminant`)
+    Statement,
+    /// A MIR right-hand side without a more specific feature (e.g. an array aggregate or a
+    /// `box` expression)
+    Rvalue,
+    /// The call of a function pointer or of a closure
+    IndirectCall,
+    /// The `yield` of a generator
+    Generator,
+    /// The unwinding of the stack after a panic
+    Unwinding,
+    /// A specification on the implementation of a trait method
+    TraitSpecRefinement,
+    /// An `old(..)` expression in the body of a procedure that is not a place of an argument
+    OldExpression,
+    /// The `?` operator applied to an `Option`
+    OptionTry,
+    /// The access of a `static mut` item
+    MutableStatic,
+    /// A use of an immutable `static` item other than reading a primitive field (e.g. a borrow)
+    StaticPlace,
+    /// A `fold!(..)` or `unfold!(..)` ghost statement whose argument is not a place
+    FoldUnfoldArgument,
+    /// A mutable access to the content of an `Rc` or `Arc`
+    RcMutation,
+    /// The call of a foreign function that is not declared with `#[trusted_ffi]`
+    ForeignCall,
+    /// The unfolding of more nested predicates than allowed by `MAX_UNFOLD_DEPTH`, which happens
+    /// when deeply nested fields of a recursive data structure are accessed
+    DeepUnfolding,
+    /// A ghost type (`Seq<T>`, `Set<T>` or `Map<K, V>`) whose elements are not integers,
+    /// characters or booleans
+    GhostElementType,
+    /// A constant of type `&str` that is not a string literal (e.g. a `const` item)
+    StringConstant,
+    /// A value of a type that has no value in the encoding (e.g. a floating-point number, or a
+    /// struct passed by value to a pure function)
+    ValueType,
+    /// An expression in a trigger that is not a variable, a field or the call of a pure function
+    TriggerExpression,
+    /// A constant or a call of a `const fn` whose value the compiler fails to evaluate
+    ConstEvaluation,
+}
+
+impl UnsupportedFeature {
+    /// The stable identifier of the feature.
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnsupportedFeature::ArrayLength => "array-length",
+            UnsupportedFeature::ArrayRepeat => "array-repeat",
+            UnsupportedFeature::PointerCast => "pointer-cast",
+            UnsupportedFeature::SizeOf => "size-of",
+            UnsupportedFeature::UniqueBorrow => "unique-borrow",
+            UnsupportedFeature::InlineAssembly => "inline-assembly",
+            UnsupportedFeature::Statement => "statement",
+            UnsupportedFeature::Rvalue => "rvalue",
+            UnsupportedFeature::IndirectCall => "indirect-call",
+            UnsupportedFeature::Generator => "generator",
+            UnsupportedFeature::Unwinding => "unwinding",
+            UnsupportedFeature::TraitSpecRefinement => "trait-spec-refinement",
+            UnsupportedFeature::OldExpression => "old-expression",
+            UnsupportedFeature::OptionTry => "option-try",
+            UnsupportedFeature::MutableStatic => "mutable-static",
+            UnsupportedFeature::StaticPlace => "static-place",
+            UnsupportedFeature::FoldUnfoldArgument => "fold-unfold-argument",
+            UnsupportedFeature::RcMutation => "rc-mutation",
+            UnsupportedFeature::ForeignCall => "foreign-call",
+            UnsupportedFeature::DeepUnfolding => "deep-unfolding",
+            UnsupportedFeature::GhostElementType => "ghost-element-type",
+            UnsupportedFeature::StringConstant => "string-constant",
+            UnsupportedFeature::ValueType => "value-type",
+            UnsupportedFeature::TriggerExpression => "trigger-expression",
+            UnsupportedFeature::ConstEvaluation => "const-evaluation",
+        }
+    }
+
+    /// A short description of the feature, to be displayed to the user.
+    pub fn description(&self) -> &'static str {
+        match self {
+            UnsupportedFeature::ArrayLength => "the length of arrays and slices",
+            UnsupportedFeature::ArrayRepeat => "array repeat expressions",
+            UnsupportedFeature::PointerCast => "casts between non-numeric types",
+            UnsupportedFeature::SizeOf => "`size_of`",
+            UnsupportedFeature::UniqueBorrow => "unique borrows",
+            UnsupportedFeature::InlineAssembly => "inline assembly",
+            UnsupportedFeature::Statement => "this kind of MIR statement",
+            UnsupportedFeature::Rvalue => "this kind of MIR expression",
+            UnsupportedFeature::IndirectCall => "calling function pointers or closures",
+            UnsupportedFeature::Generator => "generators",
+            UnsupportedFeature::Unwinding => "unwinding",
+            UnsupportedFeature::TraitSpecRefinement => "refinement of trait specifications",
+            UnsupportedFeature::OldExpression => "`old(..)` of this expression in the body",
+            UnsupportedFeature::OptionTry => "the `?` operator on `Option`",
+            UnsupportedFeature::MutableStatic => "`static mut` items",
+            UnsupportedFeature::StaticPlace => "uses of `static` items other than reading primitive values",
+            UnsupportedFeature::FoldUnfoldArgument => "`fold!(..)` and `unfold!(..)` of this expression",
+            UnsupportedFeature::RcMutation => "mutation of the content of an `Rc` or `Arc`",
+            UnsupportedFeature::ForeignCall => "calling foreign functions without `#[trusted_ffi]`",
+            UnsupportedFeature::DeepUnfolding => {
+                "accessing fields nested deeper than the unfolding bound (`MAX_UNFOLD_DEPTH`)"
+            }
+            UnsupportedFeature::GhostElementType => {
+                "ghost types with elements that are not integers, characters or booleans"
+            }
+            UnsupportedFeature::StringConstant => "string constants other than literals",
+            UnsupportedFeature::ValueType => {
+                "values of this type (e.g. floating-point numbers, or structs passed by value to \
+                 pure functions)"
+            }
+            UnsupportedFeature::TriggerExpression => "this kind of expression in a trigger",
+            UnsupportedFeature::ConstEvaluation => "constants that cannot be evaluated at compile time",
+        }
+    }
+
+    /// A suggestion on how to work around the missing support.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            UnsupportedFeature::ArrayLength
+            | UnsupportedFeature::ArrayRepeat => {
+                "move the code that uses arrays into a `#[trusted]` function"
+            }
+            UnsupportedFeature::PointerCast
+            | UnsupportedFeature::SizeOf
+            | UnsupportedFeature::InlineAssembly => {
+                "move the low-level code into a `#[trusted]` function with a specification"
+            }
+            UnsupportedFeature::UniqueBorrow | UnsupportedFeature::IndirectCall => {
+                "replace the closure with a named function"
+            }
+            UnsupportedFeature::Statement
+            | UnsupportedFeature::Rvalue
+            | UnsupportedFeature::Generator
+            | UnsupportedFeature::Unwinding => {
+                "mark the function as `#[trusted]` to skip its verification"
+            }
+            UnsupportedFeature::TraitSpecRefinement => {
+                "move the specification to the method declared in the trait, or refine it with \
+                 `#[refine_requires]` and `#[refine_ensures]`"
+            }
+            UnsupportedFeature::OldExpression => {
+                "use `old(..)` only on integer or boolean fields of the arguments, e.g. `old(x.len)`"
+            }
+            UnsupportedFeature::OptionTry => "match on the `Option` and return `None` explicitly",
+            UnsupportedFeature::MutableStatic => {
+                "move the access of the `static mut` into a `#[trusted]` function with a specification"
+            }
+            UnsupportedFeature::StaticPlace => {
+                "read the primitive fields of the `static` item into local variables, or declare it \
+                 as a `const`"
+            }
+            UnsupportedFeature::FoldUnfoldArgument => {
+                "apply `fold!(..)` and `unfold!(..)` to a local variable or to a field of it, e.g. \
+                 `unfold!(list.next)`"
+            }
+            UnsupportedFeature::RcMutation => {
+                "clone the content and mutate the copy, or move the mutation into a `#[trusted]` \
+                 function with a specification"
+            }
+            UnsupportedFeature::ForeignCall => {
+                "declare the foreign function with `#[trusted_ffi]` and a specification of its \
+                 behaviour"
+            }
+            UnsupportedFeature::DeepUnfolding => {
+                "specify the recursive data structure with `#[pure]` functions (e.g. `len` and \
+                 `lookup`) instead of accessing its nested fields, or increase `MAX_UNFOLD_DEPTH`"
+            }
+            UnsupportedFeature::GhostElementType => {
+                "store an integer that identifies each element (e.g. its index or its key) in the \
+                 ghost type instead of the element"
+            }
+            UnsupportedFeature::StringConstant => {
+                "write the string literal instead of the name of the constant"
+            }
+            UnsupportedFeature::ValueType => {
+                "pass structs to pure functions by reference, and move the code that uses other \
+                 types into a `#[trusted]` function"
+            }
+            UnsupportedFeature::TriggerExpression => {
+                "use only variables, fields and calls of `#[pure]` functions in triggers"
+            }
+            UnsupportedFeature::ConstEvaluation => {
+                "write the value of the constant instead, or specify it with a `#[pure]` function"
+            }
+        }
+    }
+
+    /// The feature used by a MIR right-hand side that the encoder does not support.
+    pub fn of_rvalue(rvalue: &mir::Rvalue) -> Self {
+        match rvalue {
+            mir::Rvalue::Len(_) => UnsupportedFeature::ArrayLength,
+            mir::Rvalue::Repeat(..) => UnsupportedFeature::ArrayRepeat,
+            mir::Rvalue::Cast(..) => UnsupportedFeature::PointerCast,
+            mir::Rvalue::NullaryOp(mir::NullOp::SizeOf, _) => UnsupportedFeature::SizeOf,
+            _ => UnsupportedFeature::Rvalue,
+        }
+    }
+}
+
+impl fmt::Display for UnsupportedFeature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// An error that prevents the encoding of a procedure, together with the span of the Rust code
+/// that caused it.
+#[derive(Clone, Debug)]
+pub struct SpannedEncodingError {
+    pub feature: UnsupportedFeature,
+    pub span: MultiSpan,
+    /// Additional details for debugging, e.g. the MIR that could not be encoded.
+    pub details: String,
+}
+
+impl SpannedEncodingError {
+    pub fn unsupported<S: Into<MultiSpan>, T: ToString>(
+        feature: UnsupportedFeature,
+        span: S,
+        details: T,
+    ) -> Self {
+        SpannedEncodingError {
+            feature,
+            span: span.into(),
+            details: details.to_string(),
+        }
+    }
+
+    /// The message to be reported to the user.
+    pub fn message(&self) -> String {
+        format!(
+            "[Prusti: unsupported feature] {} (`{}`)",
+            self.feature.description(),
+            self.feature.name()
+        )
+    }
+}
+
+pub type EncodingResult<T> = Result<T, SpannedEncodingError>;
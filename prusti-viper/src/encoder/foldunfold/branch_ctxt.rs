@@ -10,9 +10,11 @@ use encoder::foldunfold::places_utils::*;
 use encoder::foldunfold::state::*;
 use encoder::vir;
 use encoder::vir::PermAmount;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::iter::FromIterator;
+use std::rc::Rc;
 use utils::to_string::ToString;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -20,6 +22,12 @@ pub struct BranchCtxt<'a> {
     state: State,
     /// The definition of the predicates
     predicates: &'a HashMap<String, vir::Predicate>,
+    /// The maximum number of nested predicates that can be unfolded to obtain a place, or 0 if
+    /// the unfolding is not bounded.
+    max_unfold_depth: u32,
+    /// The first predicate whose unfolding exceeded `max_unfold_depth`, with its depth. It is
+    /// shared by all the branch contexts cloned from the initial one.
+    exceeded_unfold_depth: Rc<RefCell<Option<(vir::Expr, u32)>>>,
 }
 
 impl<'a> BranchCtxt<'a> {
@@ -38,9 +46,22 @@ impl<'a> BranchCtxt<'a> {
                 HashSet::new(),
             ),
             predicates,
+            max_unfold_depth: 0,
+            exceeded_unfold_depth: Rc::new(RefCell::new(None)),
         }
     }
 
+    /// Bound the number of nested predicates that can be unfolded to obtain a place.
+    pub fn set_max_unfold_depth(&mut self, max_unfold_depth: u32) {
+        self.max_unfold_depth = max_unfold_depth;
+    }
+
+    /// The first predicate whose unfolding exceeded the bound, with the number of nested
+    /// predicates that had to be unfolded to reach it.
+    pub fn exceeded_unfold_depth(&self) -> Option<(vir::Expr, u32)> {
+        self.exceeded_unfold_depth.borrow().clone()
+    }
+
     pub fn state(&self) -> &State {
         &self.state
     }
@@ -73,6 +94,25 @@ impl<'a> BranchCtxt<'a> {
             "Invalid permission amount."
         );
 
+        // Each field of the place is the body of a predicate that has been unfolded
+        let unfold_depth = pred_place
+            .explode_place()
+            .1
+            .into_iter()
+            .filter(|component| match component {
+                vir::PlaceComponent::Field(..) => true,
+                vir::PlaceComponent::Variant(..) => false,
+            })
+            .count() as u32
+            + 1;
+        if self.max_unfold_depth > 0 && unfold_depth > self.max_unfold_depth {
+            debug!("Unfolding {} exceeds the bound with depth {}", pred_place, unfold_depth);
+            let mut exceeded_unfold_depth = self.exceeded_unfold_depth.borrow_mut();
+            if exceeded_unfold_depth.is_none() {
+                *exceeded_unfold_depth = Some((pred_place.clone(), unfold_depth));
+            }
+        }
+
         let predicate_name = pred_place.typed_ref_name().unwrap();
         let predicate = self.predicates.get(&predicate_name).unwrap();
 
@@ -82,7 +122,7 @@ impl<'a> BranchCtxt<'a> {
             .into_iter()
             .map(|perm| {
                 perm.map_place(|p| p.replace_place(&pred_self_place, pred_place))
-                    .update_perm_amount(perm_amount)
+                    .scale_perm_amount(perm_amount)
             })
             .collect();
 
@@ -479,6 +519,13 @@ impl<'a> BranchCtxt<'a> {
 
         let mut actions: Vec<Action> = vec![];
 
+        // 0. Stop unfolding as soon as the bound has been exceeded, since the encoding of the
+        // procedure will be discarded
+        if self.exceeded_unfold_depth.borrow().is_some() {
+            trace!("[exit] obtain: The unfolding bound has been exceeded");
+            return ObtainResult::Success(actions);
+        }
+
         trace!("Acc state before: {{\n{}\n}}", self.state.display_acc());
         trace!("Pred state before: {{\n{}\n}}", self.state.display_pred());
 
@@ -597,7 +644,7 @@ impl<'a> BranchCtxt<'a> {
 
                 let scaled_places_in_pred: Vec<_> = places_in_pred
                     .into_iter()
-                    .map(|perm| perm.update_perm_amount(perm_amount))
+                    .map(|perm| perm.scale_perm_amount(perm_amount))
                     .collect();
 
                 let pos = req.get_place().pos().clone();
@@ -5,19 +5,23 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use self::branch_ctxt::*;
+use encoder::errors::{EncodingResult, SpannedEncodingError, UnsupportedFeature};
 use encoder::foldunfold::action::Action;
 use encoder::foldunfold::log::EventLog;
 use encoder::foldunfold::perm::*;
 use encoder::foldunfold::permissions::RequiredPermissionsGetter;
 use encoder::vir;
 use encoder::vir::ExprFolder;
+use encoder::vir::ExprIterator;
 use encoder::vir::{CfgBlockIndex, CfgReplacer, CheckNoOpAction};
 use encoder::Encoder;
 use prusti_interface::config;
 use prusti_interface::report;
 use std;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::mem;
+use syntax_pos::MultiSpan;
 use utils::to_string::ToString;
 
 mod action;
@@ -31,9 +35,20 @@ mod semantics;
 mod state;
 
 pub fn add_folding_unfolding_to_expr(expr: vir::Expr, bctxt: &BranchCtxt) -> vir::Expr {
-    let bctxt_at_label = HashMap::new();
-    let expr = ExprReplacer::new(bctxt.clone(), &bctxt_at_label, true).fold(expr);
-    ExprReplacer::new(bctxt.clone(), &bctxt_at_label, false).fold(expr)
+    let curr_bctxt_at_label = HashMap::new();
+    let obtained_at_label = RefCell::new(HashMap::new());
+    let expr = ExprReplacer::new(
+        bctxt.clone(),
+        &curr_bctxt_at_label,
+        &obtained_at_label,
+        true,
+    ).fold(expr);
+    ExprReplacer::new(
+        bctxt.clone(),
+        &curr_bctxt_at_label,
+        &obtained_at_label,
+        false,
+    ).fold(expr)
 }
 
 pub fn add_folding_unfolding_to_function(
@@ -71,11 +86,32 @@ pub fn add_fold_unfold<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a>(
     cfg: vir::CfgMethod,
     borrow_positions: HashMap<vir::borrows::Borrow, vir::CfgBlockIndex>,
     method_pos: vir::Position,
-) -> vir::CfgMethod {
+) -> EncodingResult<vir::CfgMethod> {
     let cfg_vars = cfg.get_all_vars();
     let predicates = encoder.get_used_viper_predicates_map();
-    let initial_bctxt = BranchCtxt::new(cfg_vars, &predicates);
-    FoldUnfold::new(encoder, initial_bctxt, &cfg, borrow_positions, method_pos).replace_cfg(&cfg)
+    let mut initial_bctxt = BranchCtxt::new(cfg_vars, &predicates);
+    let max_unfold_depth = config::max_unfold_depth();
+    initial_bctxt.set_max_unfold_depth(max_unfold_depth);
+    let mut foldunfold =
+        FoldUnfold::new(encoder, initial_bctxt, &cfg, borrow_positions, method_pos.clone());
+    let new_cfg = foldunfold.replace_cfg(&cfg);
+    if let Some((place, depth)) = foldunfold.initial_bctxt.exceeded_unfold_depth() {
+        let error_manager = encoder.error_manager();
+        // Without a registered position, the error is reported without a location
+        let span = error_manager
+            .get_span(place.pos())
+            .or_else(|| error_manager.get_span(&method_pos))
+            .unwrap_or_else(MultiSpan::new);
+        return Err(SpannedEncodingError::unsupported(
+            UnsupportedFeature::DeepUnfolding,
+            span,
+            format!(
+                "obtaining {} requires to unfold {} nested predicates, but the bound is {}",
+                place, depth, max_unfold_depth
+            ),
+        ));
+    }
+    Ok(new_cfg)
 }
 
 #[derive(Clone)]
@@ -83,6 +119,13 @@ struct FoldUnfold<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> {
     encoder: &'p Encoder<'v, 'r, 'a, 'tcx>,
     initial_bctxt: BranchCtxt<'p>,
     bctxt_at_label: HashMap<String, BranchCtxt<'p>>,
+    /// The state in which the `old[label](..)` expressions are evaluated, i.e. the state at the
+    /// label with the places of the label made current again. It is computed once, when the
+    /// label is reached, instead of once for each old expression.
+    curr_bctxt_at_label: HashMap<String, BranchCtxt<'p>>,
+    /// The actions that obtain some permissions in the state of `old[label](..)` expressions,
+    /// indexed by label and by the obtained permissions.
+    obtained_at_label: RefCell<ObtainedPermissions>,
     dump_debug_info: bool,
     check_foldunfold_state: bool,
     cfg: &'p vir::CfgMethod,
@@ -103,6 +146,8 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> FoldUnfold<'p, 'v, 'r, 'a, 'tcx> {
             encoder,
             initial_bctxt,
             bctxt_at_label: HashMap::new(),
+            curr_bctxt_at_label: HashMap::new(),
+            obtained_at_label: RefCell::new(HashMap::new()),
             dump_debug_info: config::dump_debug_info(),
             check_foldunfold_state: config::check_foldunfold_state(),
             cfg,
@@ -113,11 +158,46 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> FoldUnfold<'p, 'v, 'r, 'a, 'tcx> {
     }
 
     fn replace_expr(&self, expr: &vir::Expr, curr_bctxt: &BranchCtxt<'p>) -> vir::Expr {
-        ExprReplacer::new(curr_bctxt.clone(), &self.bctxt_at_label, false).fold(expr.clone())
+        ExprReplacer::new(
+            curr_bctxt.clone(),
+            &self.curr_bctxt_at_label,
+            &self.obtained_at_label,
+            false,
+        ).fold(expr.clone())
     }
 
     fn replace_old_expr(&self, expr: &vir::Expr, curr_bctxt: &BranchCtxt<'p>) -> vir::Expr {
-        ExprReplacer::new(curr_bctxt.clone(), &self.bctxt_at_label, true).fold(expr.clone())
+        ExprReplacer::new(
+            curr_bctxt.clone(),
+            &self.curr_bctxt_at_label,
+            &self.obtained_at_label,
+            true,
+        ).fold(expr.clone())
+    }
+
+    /// Store the state at a label, updating the state of the `old[label](..)` expressions and
+    /// forgetting the permissions that have been obtained in the previous state of the label.
+    fn store_bctxt_at_label(&mut self, label: &str, labelled_bctxt: BranchCtxt<'p>) {
+        // Replace old[label] with curr
+        let mut curr_bctxt = labelled_bctxt.clone();
+        curr_bctxt.mut_state().replace_places(|place| {
+            place.map_labels(|opt_label| {
+                if opt_label == label {
+                    None
+                } else {
+                    Some(opt_label)
+                }
+            })
+        });
+        self.obtained_at_label.borrow_mut().remove(label);
+        self.curr_bctxt_at_label.insert(label.to_string(), curr_bctxt);
+        self.bctxt_at_label.insert(label.to_string(), labelled_bctxt);
+    }
+
+    fn remove_bctxt_at_label(&mut self, label: &str) {
+        self.obtained_at_label.borrow_mut().remove(label);
+        self.curr_bctxt_at_label.remove(label);
+        self.bctxt_at_label.remove(label);
     }
 
     /// Insert "unfolding in" in old expressions
@@ -466,6 +546,56 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> FoldUnfold<'p, 'v, 'r, 'a, 'tcx> {
         stmts
     }
 
+    /// Exhale the permissions of a local variable whose storage is dead, so that the verifier
+    /// does not accumulate them. The variable itself stays in the state, because its storage
+    /// may become live again.
+    fn exhale_dead_permissions(
+        &self,
+        local_var: &vir::LocalVar,
+        bctxt: &mut BranchCtxt<'p>,
+    ) -> Vec<vir::Stmt> {
+        let local: vir::Expr = local_var.clone().into();
+        let mut accesses: Vec<_> = bctxt
+            .state()
+            .acc()
+            .iter()
+            .filter(|(place, _)| place.has_proper_prefix(&local))
+            .map(|(place, &perm_amount)| vir::Expr::acc_permission(place.clone(), perm_amount))
+            .collect();
+        accesses.extend(
+            bctxt
+                .state()
+                .pred()
+                .iter()
+                .filter(|(place, _)| place.has_prefix(&local))
+                .filter_map(|(place, &perm_amount)| {
+                    vir::Expr::pred_permission(place.clone(), perm_amount)
+                }),
+        );
+        if accesses.is_empty() {
+            return vec![];
+        }
+        // Sort the permissions, to generate a deterministic encoding.
+        accesses.sort_by_key(|access| access.to_string());
+        debug!(
+            "exhale dead permissions of {}: {}",
+            local_var,
+            accesses
+                .iter()
+                .map(|access| access.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let state = bctxt.mut_state();
+        state.remove_acc_matching(|place| place.has_proper_prefix(&local));
+        state.remove_pred_matching(|place| place.has_prefix(&local));
+        state.remove_moved_matching(|place| place.has_proper_prefix(&local));
+        vec![vir::Stmt::Exhale(
+            accesses.into_iter().conjoin(),
+            self.method_pos.clone(),
+        )]
+    }
+
     /// Restore `Write` permissions that were converted to `Read` due to borrowing.
     fn restore_write_permissions(
         &self,
@@ -612,6 +742,11 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> vir::CfgReplacer<BranchCtxt<'p>, Vec<
         //debug_assert_eq!(left_state.framing_stack(), right_state.framing_stack(), "back edge (framing)");
     }
 
+    /// Stop as soon as an unfolding exceeded the bound, instead of unfolding even more
+    fn is_aborted(&self) -> bool {
+        self.initial_bctxt.exceeded_unfold_depth().is_some()
+    }
+
     /// Give the initial branch context
     fn initial_context(&mut self) -> BranchCtxt<'p> {
         self.initial_bctxt.clone()
@@ -630,10 +765,19 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> vir::CfgReplacer<BranchCtxt<'p>, Vec<
     ) -> Vec<vir::Stmt> {
         debug!("[enter] replace_stmt: ##### {} #####", stmt);
 
+        if self.is_aborted() {
+            debug!("[exit] replace_stmt: The unfolding bound has been exceeded");
+            return vec![];
+        }
+
         if let vir::Stmt::ExpireBorrows(ref dag) = stmt {
             return self.process_expire_borrows(dag, bctxt, curr_block_index, new_cfg, label);
         }
 
+        if let vir::Stmt::ExhaleDeadPermissions(ref local_var) = stmt {
+            return self.exhale_dead_permissions(local_var, bctxt);
+        }
+
         let mut stmt = stmt.clone();
 
         // Store state for old expressions
@@ -642,8 +786,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> vir::CfgReplacer<BranchCtxt<'p>, Vec<
                 let mut labelled_bctxt = bctxt.clone();
                 let labelled_state = labelled_bctxt.mut_state();
                 labelled_state.replace_places(|place| place.old(label));
-                self.bctxt_at_label
-                    .insert(label.to_string(), labelled_bctxt);
+                self.store_bctxt_at_label(label, labelled_bctxt);
             }
 
             vir::Stmt::PackageMagicWand(vir::Expr::MagicWand(box ref lhs, _, _, _), ..)
@@ -663,8 +806,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> vir::CfgReplacer<BranchCtxt<'p>, Vec<
                     labelled_state.insert_acc(arg.clone(), *perm_amount);
                 }
                 labelled_state.replace_places(|place| place.old(&label));
-                self.bctxt_at_label
-                    .insert(label.to_string(), labelled_bctxt);
+                self.store_bctxt_at_label(&label, labelled_bctxt);
             }
 
             _ => {} // Nothing
@@ -731,6 +873,12 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> vir::CfgReplacer<BranchCtxt<'p>, Vec<
                 if !perms.is_empty() {
                     stmts.extend(bctxt.obtain_permissions(perms).iter().map(|a| a.to_stmt()));
 
+                    // The permissions of the statement might not have been obtained
+                    if self.is_aborted() {
+                        debug!("[exit] replace_stmt: The unfolding bound has been exceeded");
+                        return stmts;
+                    }
+
                     if self.check_foldunfold_state && !is_last_before_return {
                         stmts.push(vir::Stmt::comment("Assert content of fold/unfold state"));
                         stmts.push(
@@ -973,7 +1121,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> vir::CfgReplacer<BranchCtxt<'p>, Vec<
         }
 
         // Delete lhs state
-        self.bctxt_at_label.remove("lhs");
+        self.remove_bctxt_at_label("lhs");
 
         debug!(
             "[exit] replace_stmt = [\n{}\n]",
@@ -1120,9 +1268,16 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> vir::CfgReplacer<BranchCtxt<'p>, Vec<
     }
 }
 
+/// The actions that obtain some permissions, indexed by the label of the state in which they
+/// have been obtained and by the permissions.
+type ObtainedPermissions = HashMap<String, HashMap<Vec<Perm>, Vec<Action>>>;
+
 struct ExprReplacer<'b, 'a: 'b> {
     curr_bctxt: BranchCtxt<'a>,
-    bctxt_at_label: &'b HashMap<String, BranchCtxt<'a>>,
+    curr_bctxt_at_label: &'b HashMap<String, BranchCtxt<'a>>,
+    obtained_at_label: &'b RefCell<ObtainedPermissions>,
+    /// The label of `curr_bctxt`, if it is the state of the `old[label](..)` expressions.
+    curr_label: Option<String>,
     lhs_bctxt: Option<BranchCtxt<'a>>,
     wait_old_expr: bool,
 }
@@ -1130,16 +1285,43 @@ struct ExprReplacer<'b, 'a: 'b> {
 impl<'b, 'a: 'b> ExprReplacer<'b, 'a> {
     pub fn new(
         curr_bctxt: BranchCtxt<'a>,
-        bctxt_at_label: &'b HashMap<String, BranchCtxt<'a>>,
+        curr_bctxt_at_label: &'b HashMap<String, BranchCtxt<'a>>,
+        obtained_at_label: &'b RefCell<ObtainedPermissions>,
         wait_old_expr: bool,
     ) -> Self {
         ExprReplacer {
             curr_bctxt,
-            bctxt_at_label,
+            curr_bctxt_at_label,
+            obtained_at_label,
+            curr_label: None,
             lhs_bctxt: None,
             wait_old_expr,
         }
     }
+
+    /// Obtain the permissions in the current state, without changing it. The actions obtained
+    /// in the state of a label are memoized, because the state does not change until the label
+    /// is reached again.
+    fn obtain_permissions(&self, perms: Vec<Perm>) -> Vec<Action> {
+        let label = match self.curr_label {
+            Some(ref label) => label,
+            None => return self.curr_bctxt.clone().obtain_permissions(perms),
+        };
+        if let Some(actions) = self.obtained_at_label
+            .borrow()
+            .get(label)
+            .and_then(|obtained| obtained.get(&perms))
+        {
+            return actions.clone();
+        }
+        let actions = self.curr_bctxt.clone().obtain_permissions(perms.clone());
+        self.obtained_at_label
+            .borrow_mut()
+            .entry(label.clone())
+            .or_insert_with(HashMap::new)
+            .insert(perms, actions.clone());
+        actions
+    }
 }
 
 impl<'b, 'a: 'b> ExprFolder for ExprReplacer<'b, 'a> {
@@ -1199,11 +1381,13 @@ impl<'b, 'a: 'b> ExprFolder for ExprReplacer<'b, 'a> {
             // Store states
             let mut tmp_curr_bctxt = inner_bctxt;
             std::mem::swap(&mut self.curr_bctxt, &mut tmp_curr_bctxt);
+            let old_curr_label = self.curr_label.take();
 
             let inner_expr = self.fold_boxed(expr);
 
             // Restore states
             std::mem::swap(&mut self.curr_bctxt, &mut tmp_curr_bctxt);
+            self.curr_label = old_curr_label;
 
             vir::Expr::Unfolding(name, args, inner_expr, perm, variant, pos)
         };
@@ -1239,6 +1423,7 @@ impl<'b, 'a: 'b> ExprFolder for ExprReplacer<'b, 'a> {
 
         // Store states
         std::mem::swap(&mut self.curr_bctxt, &mut lhs_bctxt);
+        let old_curr_label = self.curr_label.take();
 
         // Rewrite lhs
         let new_lhs = self.fold_boxed(lhs);
@@ -1285,6 +1470,7 @@ impl<'b, 'a: 'b> ExprFolder for ExprReplacer<'b, 'a> {
         // Restore states
         self.lhs_bctxt = None;
         std::mem::swap(&mut self.curr_bctxt, &mut rhs_bctxt);
+        self.curr_label = old_curr_label;
 
         // Rewrite lhs and build magic wand
         let res = vir::Expr::MagicWand(new_lhs, new_rhs, borrow, pos);
@@ -1301,25 +1487,30 @@ impl<'b, 'a: 'b> ExprFolder for ExprReplacer<'b, 'a> {
     ) -> vir::Expr {
         debug!("[enter] fold_labelled_old {}: {}", label, expr);
 
-        let mut tmp_curr_bctxt = if label == "lhs" && self.lhs_bctxt.is_some() {
-            self.lhs_bctxt.as_ref().unwrap().clone()
-        } else {
-            self.bctxt_at_label.get(&label).unwrap().clone()
-        };
-
-        // Replace old[label] with curr
-        tmp_curr_bctxt.mut_state().replace_places(|place| {
-            place.map_labels(|opt_label| {
-                if opt_label == label.clone() {
-                    None
-                } else {
-                    Some(opt_label)
-                }
-            })
-        });
+        let (mut tmp_curr_bctxt, mut tmp_curr_label) =
+            if label == "lhs" && self.lhs_bctxt.is_some() {
+                // Replace old[label] with curr
+                let mut lhs_bctxt = self.lhs_bctxt.as_ref().unwrap().clone();
+                lhs_bctxt.mut_state().replace_places(|place| {
+                    place.map_labels(|opt_label| {
+                        if opt_label == label {
+                            None
+                        } else {
+                            Some(opt_label)
+                        }
+                    })
+                });
+                (lhs_bctxt, None)
+            } else {
+                (
+                    self.curr_bctxt_at_label.get(&label).unwrap().clone(),
+                    Some(label.clone()),
+                )
+            };
 
         // Store states
         std::mem::swap(&mut self.curr_bctxt, &mut tmp_curr_bctxt);
+        std::mem::swap(&mut self.curr_label, &mut tmp_curr_label);
         let old_wait_old_expr = self.wait_old_expr;
         self.wait_old_expr = false;
 
@@ -1328,6 +1519,7 @@ impl<'b, 'a: 'b> ExprFolder for ExprReplacer<'b, 'a> {
 
         // Restore states
         std::mem::swap(&mut self.curr_bctxt, &mut tmp_curr_bctxt);
+        std::mem::swap(&mut self.curr_label, &mut tmp_curr_label);
         self.wait_old_expr = old_wait_old_expr;
 
         // Rebuild expression
@@ -1364,8 +1556,6 @@ impl<'b, 'a: 'b> ExprFolder for ExprReplacer<'b, 'a> {
             // Add appropriate unfolding around this old expression
             // Note: unfoldings must have no effect on siblings
             let result = self
-                .curr_bctxt
-                .clone()
                 .obtain_permissions(perms)
                 .into_iter()
                 .rev()
@@ -1406,8 +1596,6 @@ impl<'b, 'a: 'b> ExprFolder for ExprReplacer<'b, 'a> {
                 .collect();
 
             let result = self
-                .curr_bctxt
-                .clone()
                 .obtain_permissions(perms)
                 .into_iter()
                 .rev()
@@ -94,12 +94,16 @@ impl Perm {
         }
     }
 
-    pub fn update_perm_amount(self, new_perm: PermAmount) -> Self {
-        assert!(self.get_perm_amount().is_valid_for_specs()); // Just a sanity check.
-        assert!(new_perm.is_valid_for_specs());
+    /// Scale the permission amount of a permission in the body of a predicate by the amount with
+    /// which the predicate is folded or unfolded. The body of a predicate contains write
+    /// permissions, except for the read permission to the target of a shared reference.
+    pub fn scale_perm_amount(self, pred_perm: PermAmount) -> Self {
+        assert!(pred_perm.is_valid_for_specs());
         match self {
-            Perm::Acc(expr, _) => Perm::Acc(expr, new_perm),
-            Perm::Pred(expr, _) => Perm::Pred(expr, new_perm),
+            Perm::Acc(_expr, PermAmount::Remaining) => unreachable!(),
+            Perm::Pred(_expr, PermAmount::Remaining) => unreachable!(),
+            Perm::Acc(expr, perm) => Perm::Acc(expr, perm.scale(pred_perm)),
+            Perm::Pred(expr, perm) => Perm::Pred(expr, perm.scale(pred_perm)),
         }
     }
 
@@ -107,7 +107,7 @@ impl RequiredPermissionsGetter for vir::Stmt {
                     .into_iter()
                     .map(|perm| {
                         perm.map_place(|p| p.replace_place(&pred_self_place, &place))
-                            .init_perm_amount(perm_amount)
+                            .scale_perm_amount(perm_amount)
                     })
                     .collect();
 
@@ -188,7 +188,7 @@ impl RequiredPermissionsGetter for vir::Expr {
                     .into_iter()
                     .map(|aop| {
                         aop.map_place(|p| p.replace_place(&pred_self_place, place))
-                            .update_perm_amount(*perm_amount)
+                            .scale_perm_amount(*perm_amount)
                     })
                     .collect();
 
@@ -251,7 +251,8 @@ impl RequiredPermissionsGetter for vir::Expr {
                 unreachable!("Let expressions should be introduced after fold/unfold.");
             }
 
-            vir::Expr::ForAll(vars, _triggers, box body, _) => {
+            vir::Expr::ForAll(vars, _triggers, box body, _)
+            | vir::Expr::Exists(vars, _triggers, box body, _) => {
                 assert!(vars.iter().all(|var| !var.typ.is_ref()));
 
                 let vars_places: HashSet<_> = vars
@@ -310,6 +311,8 @@ impl RequiredPermissionsGetter for vir::Expr {
                     .collect::<Vec<_>>()
                     .get_required_permissions(predicates)
             }
+
+            vir::Expr::DomainFuncApp(_, ref args, _) => args.get_required_permissions(predicates),
         };
         trace!(
             "[exit] get_required_permissions(expr={}): {:#?}",
@@ -332,7 +335,8 @@ impl vir::Expr {
             | vir::Expr::AddrOf(_, _, _)
             | vir::Expr::LabelledOld(_, _, _)
             | vir::Expr::Const(_, _)
-            | vir::Expr::FuncApp(..) => HashSet::new(),
+            | vir::Expr::FuncApp(..)
+            | vir::Expr::DomainFuncApp(..) => HashSet::new(),
 
             vir::Expr::Unfolding(_, args, expr, perm_amount, variant, _) => {
                 assert_eq!(args.len(), 1);
@@ -349,7 +353,7 @@ impl vir::Expr {
                     .into_iter()
                     .map(|aop| {
                         aop.map_place(|p| p.replace_place(&pred_self_place, place))
-                            .init_perm_amount(*perm_amount)
+                            .scale_perm_amount(*perm_amount)
                     })
                     .collect();
 
@@ -373,7 +377,8 @@ impl vir::Expr {
                 &right.get_permissions(predicates),
             ),
 
-            vir::Expr::ForAll(vars, _triggers, box body, _) => {
+            vir::Expr::ForAll(vars, _triggers, box body, _)
+            | vir::Expr::Exists(vars, _triggers, box body, _) => {
                 assert!(vars.iter().all(|var| !var.typ.is_ref()));
                 let vars_places: HashSet<Perm> = vars
                     .iter()
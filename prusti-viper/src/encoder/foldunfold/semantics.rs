@@ -169,7 +169,7 @@ impl vir::Stmt {
                     .into_iter()
                     .map(|perm| {
                         perm.map_place(|p| p.replace_place(&pred_self_place, place))
-                            .init_perm_amount(perm_amount)
+                            .scale_perm_amount(perm_amount)
                     })
                     .collect();
 
@@ -0,0 +1,424 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The model of the ghost types `Seq<T>`, `Set<T>` and `Map<K, V>` of `prusti_contracts`. A value
+//! of a ghost type is a value of a Viper domain, stored in a value field of its predicate like
+//! the values of the primitive types. There is one domain for each instantiation of the encoded
+//! types of the elements, e.g. `Seq$Int` for the sequences of integers and `Map$Int$Bool` for the
+//! maps from integers to booleans. The elements, keys and values have to be integers, characters
+//! or booleans.
+//!
+//! The methods of the ghost types are encoded as applications of the domain functions, which are
+//! axiomatized to give:
+//! * a sequence its length, the element at each index, and its concatenation with another
+//!   sequence; updating the element at an index outside of the sequence leaves it unchanged;
+//! * a set whether it contains each element, and its union with another set;
+//! * a map whether it contains each key, and the value of each key.
+//!
+//! Looking up an index outside of a sequence, or a key that is not in a map, gives an
+//! unspecified value. Two ghost values that are equal in Rust are equal in Viper, but the
+//! converse does not hold because the domains have no extensionality axioms.
+
+use encoder::errors::{EncodingResult, SpannedEncodingError, UnsupportedFeature};
+use encoder::vir;
+use encoder::vir::ExprIterator;
+use rustc::ty;
+use syntax_pos::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GhostKind {
+    Seq,
+    Set,
+    Map,
+}
+
+/// Returns the kind of the ghost type, or `None` if the type is not a ghost type.
+pub fn get_ghost_kind(tcx: ty::TyCtxt, ty: ty::Ty) -> Option<GhostKind> {
+    match ty.sty {
+        ty::TypeVariants::TyAdt(adt_def, _) => {
+            match tcx.item_path_str(adt_def.did).as_str() {
+                "prusti_contracts::Seq" | "prusti_contracts::ghost::Seq" => Some(GhostKind::Seq),
+                "prusti_contracts::Set" | "prusti_contracts::ghost::Set" => Some(GhostKind::Set),
+                "prusti_contracts::Map" | "prusti_contracts::ghost::Map" => Some(GhostKind::Map),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns true if the type is one of the ghost types `Seq<T>`, `Set<T>` and `Map<K, V>`.
+pub fn is_ghost_ty(tcx: ty::TyCtxt, ty: ty::Ty) -> bool {
+    get_ghost_kind(tcx, ty).is_some()
+}
+
+pub struct GhostEncoder {
+    kind: GhostKind,
+    /// The encoded types of the elements, or of the keys and of the values of a map.
+    elem_types: Vec<vir::Type>,
+}
+
+impl GhostEncoder {
+    /// The span is the one of the code that uses the ghost type, to report unsupported element
+    /// types.
+    pub fn new(kind: GhostKind, elem_types: Vec<vir::Type>, span: Span) -> EncodingResult<Self> {
+        for elem_type in &elem_types {
+            match elem_type {
+                vir::Type::Int | vir::Type::Bool => {}
+                _ => {
+                    return Err(SpannedEncodingError::unsupported(
+                        UnsupportedFeature::GhostElementType,
+                        span,
+                        format!("ghost type {:?} with elements of type {}", kind, elem_type),
+                    ))
+                }
+            }
+        }
+        Ok(GhostEncoder { kind, elem_types })
+    }
+
+    pub fn domain_name(&self) -> String {
+        let kind = match self.kind {
+            GhostKind::Seq => "Seq",
+            GhostKind::Set => "Set",
+            GhostKind::Map => "Map",
+        };
+        let elem_types: Vec<_> = self
+            .elem_types
+            .iter()
+            .map(|elem_type| match elem_type {
+                vir::Type::Int => "Int",
+                _ => "Bool",
+            })
+            .collect();
+        format!("{}${}", kind, elem_types.join("$"))
+    }
+
+    pub fn encode_domain_type(&self) -> vir::Type {
+        vir::Type::Domain(self.domain_name())
+    }
+
+    pub fn encode_value_field(&self) -> vir::Field {
+        vir::Field::new(format!("val_{}", self.domain_name()), self.encode_domain_type())
+    }
+
+    /// Encode a call of the method with the given name and encoded arguments (including the
+    /// receiver).
+    pub fn encode_method_call(&self, method_name: &str, mut args: Vec<vir::Expr>) -> vir::Expr {
+        match (self.kind, method_name) {
+            (GhostKind::Set, "insert") => {
+                // `s.insert(e)` is the union of `s` with the singleton set of `e`
+                let single = self.apply("single", vec![args.pop().unwrap()]);
+                args.push(single);
+                self.apply("union", args)
+            }
+            (_, name) => self.apply(name, args),
+        }
+    }
+
+    pub fn encode_domain(&self) -> vir::Domain {
+        let axioms = match self.kind {
+            GhostKind::Seq => self.encode_seq_axioms(),
+            GhostKind::Set => self.encode_set_axioms(),
+            GhostKind::Map => self.encode_map_axioms(),
+        };
+        let functions = self
+            .function_signatures()
+            .into_iter()
+            .map(|(name, _, _)| self.function(name))
+            .collect();
+        vir::Domain {
+            name: self.domain_name(),
+            functions,
+            axioms: axioms
+                .into_iter()
+                .map(|(name, expr)| vir::DomainAxiom {
+                    name: format!("{}${}", self.domain_name(), name),
+                    expr,
+                    domain_name: self.domain_name(),
+                })
+                .collect(),
+        }
+    }
+
+    fn encode_seq_axioms(&self) -> Vec<(&'static str, vir::Expr)> {
+        let seq_var = |name: &str| vir::LocalVar::new(name, self.encode_domain_type());
+        let int_var = |name: &str| vir::LocalVar::new(name, vir::Type::Int);
+        let elem_var = |name: &str| vir::LocalVar::new(name, self.elem_types[0].clone());
+        let s = seq_var("s");
+        let left = seq_var("left");
+        let right = seq_var("right");
+        let i = int_var("i");
+        let j = int_var("j");
+        let e = elem_var("e");
+
+        let len = |seq: vir::Expr| self.apply("len", vec![seq]);
+        let lookup = |seq: vir::Expr, index: vir::Expr| self.apply("lookup", vec![seq, index]);
+        let single = self.apply("single", vec![e.clone().into()]);
+        let concat = self.apply("concat", vec![left.clone().into(), right.clone().into()]);
+        let update = self.apply("update", vec![s.clone().into(), i.clone().into(), e.clone().into()]);
+        let contains = self.apply("contains", vec![s.clone().into(), e.clone().into()]);
+        let in_bounds = |seq: vir::Expr, index: vir::Expr| {
+            vir::Expr::and(
+                vir::Expr::le_cmp(0.into(), index.clone()),
+                vir::Expr::lt_cmp(index, len(seq)),
+            )
+        };
+
+        let mut axioms = vec![];
+        axioms.push((
+            "len$non_negative",
+            vir::Expr::forall(
+                vec![s.clone()],
+                vec![vir::Trigger::new(vec![len(s.clone().into())])],
+                vir::Expr::le_cmp(0.into(), len(s.clone().into())),
+            ),
+        ));
+        axioms.push((
+            "empty$len",
+            vir::Expr::eq_cmp(len(self.apply("empty", vec![])), 0.into()),
+        ));
+        axioms.push((
+            "single$lookup",
+            vir::Expr::forall(
+                vec![e.clone()],
+                vec![vir::Trigger::new(vec![single.clone()])],
+                vir::Expr::and(
+                    vir::Expr::eq_cmp(len(single.clone()), 1.into()),
+                    vir::Expr::eq_cmp(lookup(single, 0.into()), e.clone().into()),
+                ),
+            ),
+        ));
+        axioms.push((
+            "concat$len",
+            vir::Expr::forall(
+                vec![left.clone(), right.clone()],
+                vec![vir::Trigger::new(vec![concat.clone()])],
+                vir::Expr::eq_cmp(
+                    len(concat.clone()),
+                    vir::Expr::add(len(left.clone().into()), len(right.clone().into())),
+                ),
+            ),
+        ));
+        axioms.push((
+            "concat$lookup",
+            vir::Expr::forall(
+                vec![left.clone(), right.clone(), j.clone()],
+                vec![vir::Trigger::new(vec![lookup(concat.clone(), j.clone().into())])],
+                vir::Expr::eq_cmp(
+                    lookup(concat, j.clone().into()),
+                    vir::Expr::ite(
+                        vir::Expr::lt_cmp(j.clone().into(), len(left.clone().into())),
+                        lookup(left.clone().into(), j.clone().into()),
+                        lookup(
+                            right.into(),
+                            vir::Expr::sub(j.clone().into(), len(left.into())),
+                        ),
+                    ),
+                ),
+            ),
+        ));
+        axioms.push((
+            "update$len",
+            vir::Expr::forall(
+                vec![s.clone(), i.clone(), e.clone()],
+                vec![vir::Trigger::new(vec![update.clone()])],
+                vir::Expr::eq_cmp(len(update.clone()), len(s.clone().into())),
+            ),
+        ));
+        axioms.push((
+            "update$lookup",
+            vir::Expr::forall(
+                vec![s.clone(), i.clone(), e.clone(), j.clone()],
+                vec![vir::Trigger::new(vec![lookup(update.clone(), j.clone().into())])],
+                vir::Expr::eq_cmp(
+                    lookup(update, j.clone().into()),
+                    vir::Expr::ite(
+                        vir::Expr::and(
+                            vir::Expr::eq_cmp(j.clone().into(), i.clone().into()),
+                            in_bounds(s.clone().into(), i.into()),
+                        ),
+                        e.clone().into(),
+                        lookup(s.clone().into(), j.into()),
+                    ),
+                ),
+            ),
+        ));
+        let k = int_var("k");
+        axioms.push((
+            "contains$def",
+            vir::Expr::forall(
+                vec![s.clone(), e.clone()],
+                vec![vir::Trigger::new(vec![contains.clone()])],
+                vir::Expr::eq_cmp(
+                    contains,
+                    vir::Expr::exists(
+                        vec![k.clone()],
+                        vec![vir::Trigger::new(vec![lookup(s.clone().into(), k.clone().into())])],
+                        vec![
+                            in_bounds(s.clone().into(), k.clone().into()),
+                            vir::Expr::eq_cmp(lookup(s.into(), k.into()), e.into()),
+                        ]
+                        .into_iter()
+                        .conjoin(),
+                    ),
+                ),
+            ),
+        ));
+        axioms
+    }
+
+    fn encode_set_axioms(&self) -> Vec<(&'static str, vir::Expr)> {
+        let set_var = |name: &str| vir::LocalVar::new(name, self.encode_domain_type());
+        let elem_var = |name: &str| vir::LocalVar::new(name, self.elem_types[0].clone());
+        let left = set_var("left");
+        let right = set_var("right");
+        let e = elem_var("e");
+        let f = elem_var("f");
+
+        let contains = |set: vir::Expr| self.apply("contains", vec![set, e.clone().into()]);
+        let empty = contains(self.apply("empty", vec![]));
+        let single = contains(self.apply("single", vec![f.clone().into()]));
+        let union = contains(self.apply(
+            "union",
+            vec![left.clone().into(), right.clone().into()],
+        ));
+
+        let mut axioms = vec![];
+        axioms.push((
+            "empty$contains",
+            vir::Expr::forall(
+                vec![e.clone()],
+                vec![vir::Trigger::new(vec![empty.clone()])],
+                vir::Expr::not(empty),
+            ),
+        ));
+        axioms.push((
+            "single$contains",
+            vir::Expr::forall(
+                vec![f.clone(), e.clone()],
+                vec![vir::Trigger::new(vec![single.clone()])],
+                vir::Expr::eq_cmp(single, vir::Expr::eq_cmp(e.clone().into(), f.into())),
+            ),
+        ));
+        axioms.push((
+            "union$contains",
+            vir::Expr::forall(
+                vec![left.clone(), right.clone(), e.clone()],
+                vec![vir::Trigger::new(vec![union.clone()])],
+                vir::Expr::eq_cmp(
+                    union,
+                    vir::Expr::or(contains(left.into()), contains(right.into())),
+                ),
+            ),
+        ));
+        axioms
+    }
+
+    fn encode_map_axioms(&self) -> Vec<(&'static str, vir::Expr)> {
+        let map_var = |name: &str| vir::LocalVar::new(name, self.encode_domain_type());
+        let key_var = |name: &str| vir::LocalVar::new(name, self.elem_types[0].clone());
+        let value_var = |name: &str| vir::LocalVar::new(name, self.elem_types[1].clone());
+        let m = map_var("m");
+        let k = key_var("k");
+        let j = key_var("j");
+        let v = value_var("v");
+
+        let update = self.apply("update", vec![m.clone().into(), k.clone().into(), v.clone().into()]);
+        let contains_key = |map: vir::Expr| self.apply("contains_key", vec![map, j.clone().into()]);
+        let lookup = |map: vir::Expr| self.apply("lookup", vec![map, j.clone().into()]);
+        let empty = contains_key(self.apply("empty", vec![]));
+        let is_updated_key = vir::Expr::eq_cmp(j.clone().into(), k.clone().into());
+
+        let mut axioms = vec![];
+        axioms.push((
+            "empty$contains_key",
+            vir::Expr::forall(
+                vec![j.clone()],
+                vec![vir::Trigger::new(vec![empty.clone()])],
+                vir::Expr::not(empty),
+            ),
+        ));
+        axioms.push((
+            "update$contains_key",
+            vir::Expr::forall(
+                vec![m.clone(), k.clone(), v.clone(), j.clone()],
+                vec![vir::Trigger::new(vec![contains_key(update.clone())])],
+                vir::Expr::eq_cmp(
+                    contains_key(update.clone()),
+                    vir::Expr::or(is_updated_key.clone(), contains_key(m.clone().into())),
+                ),
+            ),
+        ));
+        axioms.push((
+            "update$lookup",
+            vir::Expr::forall(
+                vec![m.clone(), k, v.clone(), j.clone()],
+                vec![vir::Trigger::new(vec![lookup(update.clone())])],
+                vir::Expr::eq_cmp(
+                    lookup(update),
+                    vir::Expr::ite(is_updated_key, v.into(), lookup(m.into())),
+                ),
+            ),
+        ));
+        axioms
+    }
+
+    /// The names, argument types and return types of the domain functions.
+    fn function_signatures(&self) -> Vec<(&'static str, Vec<vir::Type>, vir::Type)> {
+        let this = self.encode_domain_type();
+        let elem = self.elem_types[0].clone();
+        match self.kind {
+            GhostKind::Seq => vec![
+                ("empty", vec![], this.clone()),
+                ("single", vec![elem.clone()], this.clone()),
+                ("len", vec![this.clone()], vir::Type::Int),
+                ("lookup", vec![this.clone(), vir::Type::Int], elem.clone()),
+                ("contains", vec![this.clone(), elem.clone()], vir::Type::Bool),
+                ("update", vec![this.clone(), vir::Type::Int, elem], this.clone()),
+                ("concat", vec![this.clone(), this.clone()], this),
+            ],
+            GhostKind::Set => vec![
+                ("empty", vec![], this.clone()),
+                ("single", vec![elem.clone()], this.clone()),
+                ("contains", vec![this.clone(), elem], vir::Type::Bool),
+                ("union", vec![this.clone(), this.clone()], this),
+            ],
+            GhostKind::Map => {
+                let value = self.elem_types[1].clone();
+                vec![
+                    ("empty", vec![], this.clone()),
+                    ("contains_key", vec![this.clone(), elem.clone()], vir::Type::Bool),
+                    ("lookup", vec![this.clone(), elem.clone()], value.clone()),
+                    ("update", vec![this.clone(), elem, value], this),
+                ]
+            }
+        }
+    }
+
+    fn function(&self, name: &str) -> vir::DomainFunc {
+        let (_, arg_types, return_type) = self
+            .function_signatures()
+            .into_iter()
+            .find(|(function_name, _, _)| *function_name == name)
+            .unwrap_or_else(|| unreachable!("unknown method of a ghost type: {}", name));
+        vir::DomainFunc {
+            name: format!("{}${}", self.domain_name(), name),
+            formal_args: arg_types
+                .into_iter()
+                .enumerate()
+                .map(|(index, typ)| vir::LocalVar::new(format!("x{}", index), typ))
+                .collect(),
+            return_type,
+            unique: false,
+            domain_name: self.domain_name(),
+        }
+    }
+
+    fn apply(&self, name: &str, args: Vec<vir::Expr>) -> vir::Expr {
+        self.function(name).apply(args)
+    }
+}
@@ -13,8 +13,20 @@ use prusti_interface::environment::{
 };
 use prusti_interface::utils;
 use rustc::hir::def_id::DefId;
+use rustc::middle::const_val::ConstVal;
 use rustc::mir;
 use rustc::ty;
+use std::mem;
+
+/// A loop of the form `while i < n { ...; i += 1; ... }`, in which the counter `i` is
+/// incremented by one at most once per iteration and the bound `n` is not modified.
+#[derive(Debug, Clone, Copy)]
+pub struct RangeLoop {
+    /// The local variable that counts the iterations (`i`).
+    pub counter: mir::Local,
+    /// The local variable that bounds the counter (`n`).
+    pub bound: mir::Local,
+}
 
 pub struct LoopEncoder<'a, 'tcx: 'a> {
     mir: &'a mir::Mir<'tcx>,
@@ -109,6 +121,241 @@ impl<'a, 'tcx: 'a> LoopEncoder<'a, 'tcx> {
         forest
     }
 
+    /// Is ``bbi`` inside the loop of ``loop_head``, possibly in a nested loop?
+    fn is_in_loop(&self, loop_head: BasicBlockIndex, bbi: BasicBlockIndex) -> bool {
+        self.get_enclosing_loop_heads(bbi).contains(&loop_head)
+    }
+
+    /// Recognise loops of the form `while i < n { ...; i += 1; ... }`.
+    pub fn get_range_loop(&self, loop_head: BasicBlockIndex) -> Option<RangeLoop> {
+        assert!(self.is_loop_head(loop_head));
+        let loop_blocks: Vec<BasicBlockIndex> = self
+            .mir
+            .basic_blocks()
+            .indices()
+            .filter(|&bbi| self.is_in_loop(loop_head, bbi))
+            .collect();
+
+        let (counter, bound) = loop_blocks
+            .iter()
+            .filter(|&&bbi| self.get_loop_head(bbi) == Some(loop_head))
+            .filter_map(|&bbi| self.get_loop_guard(bbi))
+            .next()?;
+
+        let mut increments = 0;
+        for &bbi in &loop_blocks {
+            let bb_data = &self.mir[bbi];
+            for stmt in &bb_data.statements {
+                match stmt.kind {
+                    mir::StatementKind::Assign(ref place, ref rvalue) => {
+                        let root = place_root_local(place);
+                        if root == bound {
+                            return None;
+                        }
+                        if root == counter {
+                            if *place != mir::Place::Local(counter)
+                                || !self.is_increment_by_one(&loop_blocks, counter, rvalue)
+                                || self.get_loop_head(bbi) != Some(loop_head)
+                            {
+                                return None;
+                            }
+                            increments += 1;
+                        }
+                        if let mir::Rvalue::Ref(_, mir::BorrowKind::Mut { .. }, ref borrowed) = *rvalue {
+                            let borrowed_root = place_root_local(borrowed);
+                            if borrowed_root == counter || borrowed_root == bound {
+                                return None;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if let mir::TerminatorKind::Call {
+                destination: Some((ref place, _)),
+                ..
+            } = bb_data.terminator().kind
+            {
+                let root = place_root_local(place);
+                if root == counter || root == bound {
+                    return None;
+                }
+            }
+        }
+
+        if increments == 1 {
+            Some(RangeLoop { counter, bound })
+        } else {
+            None
+        }
+    }
+
+    /// If ``bbi`` exits the loop when `i < n` is false, returns `(i, n)`.
+    fn get_loop_guard(&self, bbi: BasicBlockIndex) -> Option<(mir::Local, mir::Local)> {
+        let bb_data = &self.mir[bbi];
+        let (discr, values, targets) = match bb_data.terminator().kind {
+            mir::TerminatorKind::SwitchInt {
+                ref discr,
+                ref values,
+                ref targets,
+                ..
+            } => (discr, values, targets),
+            _ => return None,
+        };
+        // `switchInt(guard) -> [false: exit, otherwise: body]`
+        if values.len() != 1 || values[0] != 0 || !self.loops.is_out_edge(bbi, targets[0]) {
+            return None;
+        }
+        let guard = operand_local(discr)?;
+        let (lhs, rhs) = bb_data.statements.iter().filter_map(|stmt| match stmt.kind {
+            mir::StatementKind::Assign(
+                mir::Place::Local(local),
+                mir::Rvalue::BinaryOp(mir::BinOp::Lt, ref lhs, ref rhs),
+            ) if local == guard => Some((lhs, rhs)),
+            _ => None,
+        }).next()?;
+        let counter = self.resolve_copies(bb_data, operand_local(lhs)?);
+        let bound = self.resolve_copies(bb_data, operand_local(rhs)?);
+        let is_user_var = |local: mir::Local| match self.mir.local_kind(local) {
+            mir::LocalKind::Var | mir::LocalKind::Arg => {
+                self.mir.local_decls[local].ty.is_integral()
+            }
+            _ => false,
+        };
+        if counter != bound && is_user_var(counter) && is_user_var(bound) {
+            Some((counter, bound))
+        } else {
+            None
+        }
+    }
+
+    /// Follow the temporaries that copy a local variable in the block, like `_5 = _1`.
+    fn resolve_copies(&self, bb_data: &mir::BasicBlockData<'tcx>, local: mir::Local) -> mir::Local {
+        let mut curr = local;
+        for stmt in bb_data.statements.iter().rev() {
+            if let mir::StatementKind::Assign(
+                mir::Place::Local(target),
+                mir::Rvalue::Use(ref operand),
+            ) = stmt.kind
+            {
+                if target == curr {
+                    if let Some(source) = operand_local(operand) {
+                        curr = source;
+                    }
+                }
+            }
+        }
+        curr
+    }
+
+    /// Is `rvalue` equal to `counter + 1`, possibly computed by a checked addition?
+    fn is_increment_by_one(
+        &self,
+        loop_blocks: &[BasicBlockIndex],
+        counter: mir::Local,
+        rvalue: &mir::Rvalue<'tcx>,
+    ) -> bool {
+        let is_counter_plus_one = |lhs: &mir::Operand<'tcx>, rhs: &mir::Operand<'tcx>| {
+            operand_local(lhs) == Some(counter) && is_constant_one(rhs)
+        };
+        match *rvalue {
+            mir::Rvalue::BinaryOp(mir::BinOp::Add, ref lhs, ref rhs) => {
+                is_counter_plus_one(lhs, rhs)
+            }
+            // `_t = CheckedAdd(counter, 1); assert(!_t.1); counter = move (_t.0)`
+            mir::Rvalue::Use(mir::Operand::Move(mir::Place::Projection(box mir::Projection {
+                base: mir::Place::Local(tmp),
+                elem: mir::ProjectionElem::Field(field, _),
+            }))) if field.index() == 0 => loop_blocks.iter().any(|&bbi| {
+                self.mir[bbi].statements.iter().any(|stmt| match stmt.kind {
+                    mir::StatementKind::Assign(
+                        mir::Place::Local(local),
+                        mir::Rvalue::CheckedBinaryOp(mir::BinOp::Add, ref lhs, ref rhs),
+                    ) if local == tmp => is_counter_plus_one(lhs, rhs),
+                    _ => false,
+                })
+            }),
+            _ => false,
+        }
+    }
+
+    /// Recognise loops of the form `for i in a..b { ... }` over an integer range, returning the
+    /// local variable that stores the iterator of the loop.
+    pub fn get_range_iterator(&self, loop_head: BasicBlockIndex) -> Option<mir::Local> {
+        assert!(self.is_loop_head(loop_head));
+        self.mir
+            .basic_blocks()
+            .indices()
+            .filter(|&bbi| self.get_loop_head(bbi) == Some(loop_head))
+            .filter_map(|bbi| self.get_range_step(bbi))
+            .next()
+    }
+
+    /// If ``bbi`` calls `Iterator::next(&mut iter)` on an integer range `iter`, returns `iter`.
+    fn get_range_step(&self, bbi: BasicBlockIndex) -> Option<mir::Local> {
+        let bb_data = &self.mir[bbi];
+        let (def_id, args) = match bb_data.terminator().kind {
+            mir::TerminatorKind::Call {
+                func:
+                    mir::Operand::Constant(box mir::Constant {
+                        literal:
+                            mir::Literal::Value {
+                                value:
+                                    ty::Const {
+                                        ty:
+                                            &ty::TyS {
+                                                sty: ty::TyFnDef(def_id, _),
+                                                ..
+                                            },
+                                        ..
+                                    },
+                            },
+                        ..
+                    }),
+                ref args,
+                ..
+            } => (def_id, args),
+            _ => return None,
+        };
+        let func_proc_name = self.tcx.absolute_item_path_str(def_id);
+        if func_proc_name != "std::iter::Iterator::next"
+            && func_proc_name != "core::iter::Iterator::next"
+        {
+            return None;
+        }
+        let borrow = match &args[..] {
+            &[ref arg] => operand_local(arg)?,
+            _ => return None,
+        };
+        // `_t = &mut iter; _r = Iterator::next(move _t)`, possibly with reborrows of `_t`
+        let mut iterator = borrow;
+        loop {
+            let borrowed = bb_data.statements.iter().rev().filter_map(|stmt| match stmt.kind {
+                mir::StatementKind::Assign(
+                    mir::Place::Local(local),
+                    mir::Rvalue::Ref(_, mir::BorrowKind::Mut { .. }, ref borrowed),
+                ) if local == iterator => Some(borrowed),
+                _ => None,
+            }).next()?;
+            match *borrowed {
+                mir::Place::Local(local) => {
+                    iterator = local;
+                    break;
+                }
+                mir::Place::Projection(box mir::Projection {
+                    base: mir::Place::Local(local),
+                    elem: mir::ProjectionElem::Deref,
+                }) => iterator = local,
+                _ => return None,
+            }
+        }
+        if is_integer_range_ty(self.tcx, self.mir.local_decls[iterator].ty) {
+            Some(iterator)
+        } else {
+            None
+        }
+    }
+
     /// Is the ``place`` definitely initialised at the beginning of ``bbi``?
     pub fn is_definitely_initialised(&self, place: &mir::Place, bbi: BasicBlockIndex) -> bool {
         self.initialization
@@ -117,3 +364,73 @@ impl<'a, 'tcx: 'a> LoopEncoder<'a, 'tcx> {
             .any(|def_init_place| utils::is_prefix(place, def_init_place))
     }
 }
+
+fn operand_local(operand: &mir::Operand) -> Option<mir::Local> {
+    match *operand {
+        mir::Operand::Copy(mir::Place::Local(local))
+        | mir::Operand::Move(mir::Place::Local(local)) => Some(local),
+        _ => None,
+    }
+}
+
+/// The local variable that is the root of the place.
+pub fn place_root_local(place: &mir::Place) -> mir::Local {
+    match *place {
+        mir::Place::Local(local) => local,
+        mir::Place::Projection(box mir::Projection { ref base, .. }) => place_root_local(base),
+        // Statics and promoted values are not local variables
+        _ => mir::RETURN_PLACE,
+    }
+}
+
+/// Is the type a range of integers, e.g. `Range<usize>`?
+pub fn is_integer_range_ty<'a, 'tcx>(tcx: ty::TyCtxt<'a, 'tcx, 'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.sty {
+        ty::TypeVariants::TyAdt(adt_def, substs) => {
+            let range_path = tcx.item_path_str(adt_def.did);
+            (range_path == "std::ops::Range" || range_path == "core::ops::Range")
+                && substs.type_at(0).is_integral()
+        }
+        _ => false,
+    }
+}
+
+/// Is the type an iterator over a slice, i.e. `std::slice::Iter`?
+pub fn is_slice_iter_ty<'a, 'tcx>(tcx: ty::TyCtxt<'a, 'tcx, 'tcx>, ty: ty::Ty<'tcx>) -> bool {
+    match ty.sty {
+        ty::TypeVariants::TyAdt(adt_def, _) => {
+            let iter_path = tcx.item_path_str(adt_def.did);
+            iter_path == "std::slice::Iter" || iter_path == "core::slice::Iter"
+        }
+        _ => false,
+    }
+}
+
+fn is_constant_one(operand: &mir::Operand) -> bool {
+    match *operand {
+        mir::Operand::Constant(box mir::Constant {
+            ty,
+            literal: mir::Literal::Value { value },
+            ..
+        }) => {
+            let bit_width = match ty.sty {
+                ty::TypeVariants::TyInt(int_ty) => int_ty.bit_width(),
+                ty::TypeVariants::TyUint(uint_ty) => uint_ty.bit_width(),
+                _ => return false,
+            }
+            .unwrap_or(mem::size_of::<usize>() * 8);
+            match value.val {
+                ConstVal::Value(ref value) => value
+                    .to_scalar()
+                    .and_then(|scalar| {
+                        scalar
+                            .to_bits(ty::layout::Size::from_bits(bit_width as u64))
+                            .ok()
+                    })
+                    == Some(1),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
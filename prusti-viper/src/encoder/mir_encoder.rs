@@ -6,16 +6,23 @@
 
 use encoder::builtin_encoder::BuiltinFunctionKind;
 use encoder::error_manager::ErrorCtxt;
+use encoder::error_manager::PanicCause;
+use encoder::errors::{EncodingResult, SpannedEncodingError, UnsupportedFeature};
 use encoder::vir;
+use encoder::vir::ExprIterator;
 use encoder::Encoder;
 use prusti_interface::config;
+use prusti_interface::utils;
+use rustc::hir;
 use rustc::hir::def_id::DefId;
 use rustc::mir;
 use rustc::ty;
+use rustc::ty::layout;
+use rustc::ty::layout::IntegerExt;
 use rustc_data_structures::indexed_vec::Idx;
-use std;
-use syntax::ast;
+use syntax::attr::{SignedInt, UnsignedInt};
 use syntax::codemap::Span;
+use syntax_pos::hygiene::SyntaxContext;
 
 pub static PRECONDITION_LABEL: &'static str = "pre";
 pub static POSTCONDITION_LABEL: &'static str = "post";
@@ -267,6 +274,49 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> MirEncoder<'p, 'v, 'r, 'a, 'tcx> {
         encoded_place.field(value_field)
     }
 
+    /// Returns the `static` item in which `place` is rooted, with the indices of the fields that
+    /// are projected from it. Returns `None` if `place` is not a (field of a) `static` item.
+    pub fn get_static_place(&self, place: &mir::Place<'tcx>) -> Option<(DefId, Vec<usize>)> {
+        let mut fields = vec![];
+        let mut current_place = place;
+        loop {
+            match current_place {
+                &mir::Place::Static(box mir::Static { def_id, .. }) => {
+                    fields.reverse();
+                    return Some((def_id, fields));
+                }
+                &mir::Place::Projection(box mir::Projection {
+                    ref base,
+                    elem: mir::ProjectionElem::Field(field, _),
+                }) => {
+                    fields.push(field.index());
+                    current_place = base;
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    /// Returns the value of a primitive field of an immutable `static` item. Returns `None` if
+    /// `place` is not such a field.
+    pub fn encode_static_place_value(&self, place: &mir::Place<'tcx>) -> Option<vir::Expr> {
+        let (def_id, fields) = self.get_static_place(place)?;
+        let tcx = self.encoder.env().tcx();
+        if tcx.is_static(def_id) != Some(hir::Mutability::MutImmutable) {
+            return None;
+        }
+        let ty = place.ty(self.mir, tcx).to_ty(tcx);
+        match ty.sty {
+            ty::TypeVariants::TyBool
+            | ty::TypeVariants::TyInt(_)
+            | ty::TypeVariants::TyUint(_)
+            | ty::TypeVariants::TyChar => {
+                Some(self.encoder.encode_static_field_value(def_id, &fields, ty))
+            }
+            _ => None,
+        }
+    }
+
     /// Returns an `vir::Expr` that corresponds to the value of the operand
     pub fn encode_operand_expr(&self, operand: &mir::Operand<'tcx>) -> vir::Expr {
         trace!("Encode operand expr {:?}", operand);
@@ -276,6 +326,9 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> MirEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 ..
             }) => self.encoder.encode_const_expr(value),
             &mir::Operand::Copy(ref place) | &mir::Operand::Move(ref place) => {
+                if let Some(static_value) = self.encode_static_place_value(place) {
+                    return static_value;
+                }
                 let val_place = self.eval_place(&place);
                 val_place.into()
             }
@@ -306,6 +359,12 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> MirEncoder<'p, 'v, 'r, 'a, 'tcx> {
     pub fn get_operand_ty(&self, operand: &mir::Operand<'tcx>) -> ty::Ty<'tcx> {
         debug!("Get operand ty {:?}", operand);
         match operand {
+            &mir::Operand::Move(ref place) | &mir::Operand::Copy(ref place)
+                if utils::is_static_place(place) =>
+            {
+                let tcx = self.encoder.env().tcx();
+                place.ty(self.mir, tcx).to_ty(tcx)
+            }
             &mir::Operand::Move(ref place) | &mir::Operand::Copy(ref place) => {
                 let (_, ty, _) = self.encode_place(place);
                 ty
@@ -322,6 +381,13 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> MirEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 let ty = self.encoder.resolve_typaram(ty);
                 self.encoder.encode_value_type(ty)
             }
+            &mir::Operand::Copy(ref place) | &mir::Operand::Move(ref place)
+                if utils::is_static_place(place) =>
+            {
+                let tcx = self.encoder.env().tcx();
+                self.encoder
+                    .encode_value_type(place.ty(self.mir, tcx).to_ty(tcx))
+            }
             &mir::Operand::Copy(ref place) | &mir::Operand::Move(ref place) => {
                 let (encoded_place, place_ty, _) = self.encode_place(place);
                 let place_ty = self.encoder.resolve_typaram(place_ty);
@@ -340,7 +406,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> MirEncoder<'p, 'v, 'r, 'a, 'tcx> {
         ty: ty::Ty<'tcx>,
     ) -> vir::Expr {
         let is_bool = ty.sty == ty::TypeVariants::TyBool;
-        match op {
+        let result = match op {
             mir::BinOp::Eq => vir::Expr::eq_cmp(left, right),
             mir::BinOp::Ne => vir::Expr::ne_cmp(left, right),
             mir::BinOp::Gt => vir::Expr::gt_cmp(left, right),
@@ -356,6 +422,14 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> MirEncoder<'p, 'v, 'r, 'a, 'tcx> {
             mir::BinOp::BitOr if is_bool => vir::Expr::or(left, right),
             mir::BinOp::BitXor if is_bool => vir::Expr::xor(left, right),
             x => unimplemented!("{:?}", x),
+        };
+        match op {
+            mir::BinOp::Add | mir::BinOp::Sub | mir::BinOp::Mul
+                if config::wrapping_arithmetic() && self.get_int_repr(ty).is_some() =>
+            {
+                self.encode_wrapped_int(result, ty)
+            }
+            _ => result,
         }
     }
 
@@ -374,76 +448,59 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> MirEncoder<'p, 'v, 'r, 'a, 'tcx> {
         right: vir::Expr,
         ty: ty::Ty<'tcx>,
     ) -> vir::Expr {
-        if !op.is_checkable() || !config::check_binary_operations() {
+        if !op.is_checkable()
+            || !self.encoder.check_overflows(self.def_id)
+            || config::wrapping_arithmetic()
+        {
             false.into()
         } else {
-            let result = self.encode_bin_op_expr(op, left.clone(), right.clone(), ty);
-
             match op {
-                mir::BinOp::Add | mir::BinOp::Mul | mir::BinOp::Sub => match ty.sty {
-                    // Unsigned
-                    ty::TypeVariants::TyUint(ast::UintTy::U8) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u8::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u8::MAX.into()),
-                    ),
-                    ty::TypeVariants::TyUint(ast::UintTy::U16) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u16::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u16::MAX.into()),
-                    ),
-                    ty::TypeVariants::TyUint(ast::UintTy::U32) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u32::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u32::MAX.into()),
-                    ),
-                    ty::TypeVariants::TyUint(ast::UintTy::U64) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u64::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u64::MAX.into()),
-                    ),
-                    ty::TypeVariants::TyUint(ast::UintTy::U128) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::u128::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::u128::MAX.into()),
-                    ),
-                    ty::TypeVariants::TyUint(ast::UintTy::Usize) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::usize::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::usize::MAX.into()),
-                    ),
-                    // Signed
-                    ty::TypeVariants::TyInt(ast::IntTy::I8) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i8::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i8::MAX.into()),
-                    ),
-                    ty::TypeVariants::TyInt(ast::IntTy::I16) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i16::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i16::MIN.into()),
-                    ),
-                    ty::TypeVariants::TyInt(ast::IntTy::I32) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i32::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i32::MAX.into()),
-                    ),
-                    ty::TypeVariants::TyInt(ast::IntTy::I64) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i64::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i64::MAX.into()),
-                    ),
-                    ty::TypeVariants::TyInt(ast::IntTy::I128) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::i128::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::i128::MAX.into()),
-                    ),
-                    ty::TypeVariants::TyInt(ast::IntTy::Isize) => vir::Expr::or(
-                        vir::Expr::lt_cmp(result.clone(), std::isize::MIN.into()),
-                        vir::Expr::gt_cmp(result, std::isize::MAX.into()),
-                    ),
-
-                    _ => {
+                mir::BinOp::Add | mir::BinOp::Mul | mir::BinOp::Sub => {
+                    if let Some((lower, upper)) = self.encoder.get_integer_bounds(ty) {
+                        let result = self.encode_bin_op_expr(op, left.clone(), right.clone(), ty);
+                        let overflow = vir::Expr::or(
+                            vir::Expr::lt_cmp(result.clone(), lower.clone()),
+                            vir::Expr::gt_cmp(result, upper.clone()),
+                        );
+                        if config::check_binary_operations() {
+                            overflow
+                        } else {
+                            // The overflow checks are enabled only for this procedure, so the
+                            // type predicates do not bound the operands. Their values are within
+                            // the bounds of the type anyway.
+                            let in_bounds = |value: vir::Expr| {
+                                vir::Expr::and(
+                                    vir::Expr::le_cmp(lower.clone(), value.clone()),
+                                    vir::Expr::le_cmp(value, upper.clone()),
+                                )
+                            };
+                            vec![in_bounds(left), in_bounds(right), overflow]
+                                .into_iter()
+                                .conjoin()
+                        }
+                    } else {
                         debug!(
                             "Encoding of bin op check '{:?}' is incomplete for type {:?}",
                             op, ty
                         );
                         false.into()
                     }
-                },
+                }
 
                 mir::BinOp::Shl | mir::BinOp::Shr => {
-                    debug!("Encoding of bin op check '{:?}' is incomplete", op);
-                    false.into()
+                    // The shift amount has to be smaller than the number of bits of the type
+                    if let Some((_, bits)) = self.get_int_repr(ty) {
+                        vir::Expr::or(
+                            vir::Expr::lt_cmp(right.clone(), 0.into()),
+                            vir::Expr::ge_cmp(right, (bits as i64).into()),
+                        )
+                    } else {
+                        debug!(
+                            "Encoding of bin op check '{:?}' is incomplete for type {:?}",
+                            op, ty
+                        );
+                        false.into()
+                    }
                 }
 
                 _ => unreachable!("{:?}", op),
@@ -451,151 +508,126 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> MirEncoder<'p, 'v, 'r, 'a, 'tcx> {
         }
     }
 
+    /// Returns whether an integer type is signed, and its number of bits.
+    fn get_int_repr(&self, ty: ty::Ty<'tcx>) -> Option<(bool, u64)> {
+        let tcx = self.encoder.env().tcx();
+        match ty.sty {
+            ty::TypeVariants::TyInt(int_ty) => Some((
+                true,
+                layout::Integer::from_attr(tcx, SignedInt(int_ty))
+                    .size()
+                    .bits(),
+            )),
+            ty::TypeVariants::TyUint(uint_ty) => Some((
+                false,
+                layout::Integer::from_attr(tcx, UnsignedInt(uint_ty))
+                    .size()
+                    .bits(),
+            )),
+            // char is always four bytes in size
+            ty::TypeVariants::TyChar => Some((false, 32)),
+            _ => None,
+        }
+    }
+
+    /// Encode the value of type `ty` that `expr` wraps around to, in two's complement.
+    pub fn encode_wrapped_int(&self, expr: vir::Expr, ty: ty::Ty<'tcx>) -> vir::Expr {
+        let (signed, bits) = self.get_int_repr(ty).unwrap();
+        let modulus = vir::Expr::Const(
+            vir::Const::BigInt(pow2_to_string(bits)),
+            vir::Position::default(),
+        );
+        if signed {
+            let min = vir::Expr::Const(
+                vir::Const::BigInt(format!("-{}", pow2_to_string(bits - 1))),
+                vir::Position::default(),
+            );
+            vir::Expr::add(
+                vir::Expr::modulo(vir::Expr::sub(expr, min.clone()), modulus),
+                min,
+            )
+        } else {
+            vir::Expr::modulo(expr, modulus)
+        }
+    }
+
+    /// Encode the cast of `operand` to `dst_ty`, located at `span`. Only the casts between
+    /// integers and characters are supported.
     pub fn encode_cast_expr(
         &self,
         operand: &mir::Operand<'tcx>,
         dst_ty: ty::Ty<'tcx>,
-    ) -> vir::Expr {
+        span: Span,
+    ) -> EncodingResult<vir::Expr> {
         let src_ty = self.get_operand_ty(operand);
+        let is_lossless = self.is_lossless_cast(src_ty, dst_ty, span)?;
+        let encoded_operand = self.encode_operand_expr(operand);
 
-        let encoded_val = match (&src_ty.sty, &dst_ty.sty) {
-            (ty::TypeVariants::TyInt(ast::IntTy::I8), ty::TypeVariants::TyInt(ast::IntTy::I8))
-            | (ty::TypeVariants::TyInt(ast::IntTy::I8), ty::TypeVariants::TyInt(ast::IntTy::I16))
-            | (ty::TypeVariants::TyInt(ast::IntTy::I8), ty::TypeVariants::TyInt(ast::IntTy::I32))
-            | (ty::TypeVariants::TyInt(ast::IntTy::I8), ty::TypeVariants::TyInt(ast::IntTy::I64))
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I8),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I16),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I32),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I64),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-                ty::TypeVariants::TyInt(ast::IntTy::I128),
-            )
-            | (
-                ty::TypeVariants::TyInt(ast::IntTy::Isize),
-                ty::TypeVariants::TyInt(ast::IntTy::Isize),
-            )
-            | (ty::TypeVariants::TyChar, ty::TypeVariants::TyChar)
-            | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U8))
-            | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U16))
-            | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U32))
-            | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U64))
-            | (ty::TypeVariants::TyChar, ty::TypeVariants::TyUint(ast::UintTy::U128))
-            | (ty::TypeVariants::TyUint(ast::UintTy::U8), ty::TypeVariants::TyChar)
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U8),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U16),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U32),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U64),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-                ty::TypeVariants::TyUint(ast::UintTy::U128),
-            )
-            | (
-                ty::TypeVariants::TyUint(ast::UintTy::Usize),
-                ty::TypeVariants::TyUint(ast::UintTy::Usize),
-            ) => self.encode_operand_expr(operand),
-
-            _ => unimplemented!(
-                "unimplemented cast from type '{:?}' to type '{:?}'",
-                src_ty,
-                dst_ty
-            ),
-        };
+        if is_lossless {
+            Ok(encoded_operand)
+        } else {
+            // Casts between integers truncate the value
+            Ok(self.encode_wrapped_int(encoded_operand, dst_ty))
+        }
+    }
 
-        encoded_val
+    /// Encode the condition under which the cast of `operand` to `dst_ty` does not truncate
+    /// the value. Returns `None` if the cast can never truncate or if casts are not checked.
+    pub fn encode_cast_check(
+        &self,
+        operand: &mir::Operand<'tcx>,
+        dst_ty: ty::Ty<'tcx>,
+        span: Span,
+    ) -> EncodingResult<Option<vir::Expr>> {
+        let src_ty = self.get_operand_ty(operand);
+        if !config::check_integer_casts()
+            || config::wrapping_arithmetic()
+            || self.is_lossless_cast(src_ty, dst_ty, span)?
+        {
+            return Ok(None);
+        }
+        let (lower, upper) = self.encoder.get_integer_bounds(dst_ty).unwrap();
+        let encoded_operand = self.encode_operand_expr(operand);
+        Ok(Some(vir::Expr::and(
+            vir::Expr::le_cmp(lower, encoded_operand.clone()),
+            vir::Expr::le_cmp(encoded_operand, upper),
+        )))
+    }
+
+    /// Returns whether every value of type `src_ty` can be represented in type `dst_ty`.
+    fn is_lossless_cast(
+        &self,
+        src_ty: ty::Ty<'tcx>,
+        dst_ty: ty::Ty<'tcx>,
+        span: Span,
+    ) -> EncodingResult<bool> {
+        match (self.get_int_repr(src_ty), self.get_int_repr(dst_ty)) {
+            (Some((src_signed, src_bits)), Some((dst_signed, dst_bits))) => {
+                if src_signed == dst_signed {
+                    Ok(src_bits <= dst_bits)
+                } else {
+                    Ok(!src_signed && src_bits < dst_bits)
+                }
+            }
+
+            _ => Err(SpannedEncodingError::unsupported(
+                UnsupportedFeature::PointerCast,
+                span,
+                format!("cast from type '{:?}' to type '{:?}'", src_ty, dst_ty),
+            )),
+        }
     }
 
     pub fn encode_operand_place(&self, operand: &mir::Operand<'tcx>) -> Option<vir::Expr> {
         debug!("Encode operand place {:?}", operand);
         match operand {
+            // The value of a `static` item is encoded as a function call, not as a place
+            &mir::Operand::Move(ref place) | &mir::Operand::Copy(ref place)
+                if utils::is_static_place(place) =>
+            {
+                None
+            }
+
             &mir::Operand::Move(ref place) | &mir::Operand::Copy(ref place) => {
                 let (src, _, _) = self.encode_place(place);
                 Some(src)
@@ -634,4 +666,148 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> MirEncoder<'p, 'v, 'r, 'a, 'tcx> {
             .error_manager()
             .register(span, ErrorCtxt::GenericExpression)
     }
+
+    /// Returns the cause of a panic, given the span of a call to `begin_panic` or
+    /// `begin_panic_fmt`, together with the span of the macro call written by the user.
+    pub fn get_panic_cause(&self, span: Span) -> (PanicCause, Span) {
+        // Pattern match on the macro that generated the panic
+        // TODO: use a better approach to match macros
+        let macro_backtrace = span.macro_backtrace();
+        debug!("macro_backtrace: {:?}", macro_backtrace);
+
+        // HACK to match the filename of the span
+        let macro_at = |index: usize| {
+            macro_backtrace.get(index).map(|info| {
+                (
+                    info.macro_decl_name.clone(),
+                    format!("{:?}", info.def_site_span),
+                )
+            })
+        };
+
+        let is_panic_macro = match macro_at(0) {
+            Some((ref name, ref def_site)) => {
+                name == "panic!" && def_site.contains("<panic macros>")
+            }
+            None => false,
+        };
+        if !is_panic_macro {
+            // Something else called panic!()
+            return (PanicCause::Unknown, span);
+        }
+
+        let cause = match macro_at(1) {
+            Some((ref name, ref def_site)) => match name.as_str() {
+                "panic!" if def_site.contains("<panic macros>") => PanicCause::Panic,
+                "assert!" if def_site == "None" => PanicCause::Assert,
+                "assert_eq!" if def_site.contains("<assert_eq macros>") => PanicCause::AssertEq,
+                "assert_ne!" if def_site.contains("<assert_ne macros>") => PanicCause::AssertNe,
+                "unreachable!" if def_site.contains("<unreachable macros>") => {
+                    PanicCause::Unreachable
+                }
+                "unimplemented!" if def_site.contains("<unimplemented macros>") => {
+                    PanicCause::Unimplemented
+                }
+                _ => return (PanicCause::Panic, macro_backtrace[0].call_site),
+            },
+            None => return (PanicCause::Panic, macro_backtrace[0].call_site),
+        };
+
+        // The debug assertions expand to the corresponding assertions
+        let debug_cause = match (cause, macro_at(2)) {
+            (PanicCause::Assert, Some((ref name, ref def_site)))
+                if name == "debug_assert!" && def_site.contains("<debug_assert macros>") =>
+            {
+                Some(PanicCause::DebugAssert)
+            }
+            (PanicCause::AssertEq, Some((ref name, ref def_site)))
+                if name == "debug_assert_eq!" && def_site.contains("<debug_assert_eq macros>") =>
+            {
+                Some(PanicCause::DebugAssertEq)
+            }
+            (PanicCause::AssertNe, Some((ref name, ref def_site)))
+                if name == "debug_assert_ne!" && def_site.contains("<debug_assert_ne macros>") =>
+            {
+                Some(PanicCause::DebugAssertNe)
+            }
+            _ => None,
+        };
+
+        match debug_cause {
+            Some(debug_cause) => (debug_cause, macro_backtrace[2].call_site),
+            None => (cause, macro_backtrace[1].call_site),
+        }
+    }
+
+    /// Describe an operand with the source code of the user (e.g. `self.count` or `1`), if
+    /// possible.
+    pub fn describe_operand(&self, operand: &mir::Operand<'tcx>) -> Option<String> {
+        match *operand {
+            mir::Operand::Constant(ref constant) => self.snippet(constant.span),
+            mir::Operand::Copy(ref place) | mir::Operand::Move(ref place) => {
+                self.describe_place(place)
+            }
+        }
+    }
+
+    fn describe_place(&self, place: &mir::Place<'tcx>) -> Option<String> {
+        match *place {
+            mir::Place::Local(local) => {
+                let local_decl = &self.mir.local_decls[local];
+                match local_decl.name {
+                    Some(name) => Some(name.to_string()),
+                    // A temporary variable, which stores the value of an expression
+                    None => self.snippet(local_decl.source_info.span),
+                }
+            }
+            mir::Place::Projection(box mir::Projection {
+                ref base,
+                elem: mir::ProjectionElem::Deref,
+            }) => self.describe_place(base).map(|base| format!("*{}", base)),
+            mir::Place::Projection(box mir::Projection {
+                ref base,
+                elem: mir::ProjectionElem::Field(field, _),
+            }) => {
+                let tcx = self.encoder.env().tcx();
+                let field_name = match base.ty(self.mir, tcx).to_ty(tcx).sty {
+                    ty::TypeVariants::TyAdt(adt_def, _) if adt_def.is_struct() => {
+                        adt_def.non_enum_variant().fields[field.index()]
+                            .ident
+                            .to_string()
+                    }
+                    ty::TypeVariants::TyTuple(_) => field.index().to_string(),
+                    _ => return None,
+                };
+                // The dereference of a reference is implicit in a field access
+                let base = match *base {
+                    mir::Place::Projection(box mir::Projection {
+                        base: ref deref_base,
+                        elem: mir::ProjectionElem::Deref,
+                    }) => deref_base,
+                    ref base => base,
+                };
+                self.describe_place(base)
+                    .map(|base| format!("{}.{}", base, field_name))
+            }
+            _ => None,
+        }
+    }
+
+    /// The source code of a span, unless it has been generated by a macro.
+    fn snippet(&self, span: Span) -> Option<String> {
+        if span.ctxt() != SyntaxContext::empty() {
+            return None;
+        }
+        self.encoder.env().codemap().span_to_snippet(span).ok()
+    }
+}
+
+/// Returns the decimal representation of `2^exponent`, for `exponent <= 128`.
+fn pow2_to_string(exponent: u64) -> String {
+    if exponent < 128 {
+        (1u128 << exponent).to_string()
+    } else {
+        assert_eq!(exponent, 128);
+        "340282366920938463463374607431768211456".to_string()
+    }
 }
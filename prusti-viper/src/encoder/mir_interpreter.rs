@@ -302,6 +302,91 @@ impl MultiExprBackwardInterpreterState {
         }
     }
 
+    /// Substitute the applications of the function `function_name` to `args` with
+    /// `replacement`.
+    pub fn substitute_func_app(
+        &mut self,
+        function_name: &str,
+        args: &[vir::Expr],
+        replacement: &vir::Expr,
+    ) {
+        trace!(
+            "substitute_func_app {}({:?}) --> {:?}",
+            function_name,
+            args,
+            replacement
+        );
+        for expr in &mut self.exprs {
+            *expr = expr.clone().fold_expr(|expr| match expr {
+                vir::Expr::FuncApp(ref name, ref expr_args, ..)
+                    if name == function_name && expr_args.as_slice() == args =>
+                {
+                    replacement.clone()
+                }
+                expr => expr,
+            });
+        }
+    }
+
+    /// Substitute `sub_target` with the place `replacement` evaluated in the state `label`.
+    /// A place of a non-primitive type does not denote a Viper value, so the old expression is
+    /// put around each place that extends `sub_target` and around each function application
+    /// (e.g. a snapshot or a pure function) that takes such places. A function reads the heap
+    /// of the state in which it is evaluated, so the whole application is put in the old
+    /// expression, as long as its other arguments (e.g. a bound variable) do not depend on
+    /// the heap.
+    pub fn substitute_old_place(
+        &mut self,
+        sub_target: &vir::Expr,
+        replacement: &vir::Expr,
+        label: &str,
+    ) {
+        trace!(
+            "substitute_old_place {:?} --> old[{}]({:?})",
+            sub_target,
+            label,
+            replacement
+        );
+        struct OldPlaceReplacer<'a> {
+            target: &'a vir::Expr,
+            replacement: &'a vir::Expr,
+            label: &'a str,
+        }
+        impl<'a> OldPlaceReplacer<'a> {
+            fn extends_target(&self, expr: &vir::Expr) -> bool {
+                expr.is_place() && !expr.is_old() && expr.has_prefix(self.target)
+            }
+        }
+        impl<'a> vir::ExprFolder for OldPlaceReplacer<'a> {
+            fn fold(&mut self, expr: vir::Expr) -> vir::Expr {
+                let is_old_expr = match expr {
+                    vir::Expr::FuncApp(_, ref args, ..) => {
+                        args.iter().any(|arg| self.extends_target(arg)) && args
+                            .iter()
+                            .all(|arg| self.extends_target(arg) || arg.is_heap_independent())
+                    }
+                    ref place => self.extends_target(place),
+                };
+                if is_old_expr {
+                    vir::Expr::labelled_old(
+                        self.label,
+                        expr.replace_place(self.target, self.replacement),
+                    )
+                } else {
+                    vir::default_fold_expr(self, expr)
+                }
+            }
+        }
+        let mut replacer = OldPlaceReplacer {
+            target: sub_target,
+            replacement,
+            label,
+        };
+        for expr in &mut self.exprs {
+            *expr = vir::ExprFolder::fold(&mut replacer, expr.clone());
+        }
+    }
+
     pub fn use_place(&self, sub_target: &vir::Expr) -> bool {
         trace!("use_place {:?}", sub_target);
         self.exprs.iter().any(|expr| expr.find(sub_target))
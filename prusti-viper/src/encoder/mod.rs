@@ -5,22 +5,34 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 pub use self::encoder::Encoder;
+pub use self::spec_sanity::{SpecSanityCheck, SpecSanityCheckKind};
 
 mod borrows;
+mod branch_conditions;
 mod builtin_encoder;
 mod encoder;
 mod error_manager;
 mod foldunfold;
+mod ghost_encoder;
 mod initialisation;
 mod loop_encoder;
 mod mir_encoder;
 mod mir_interpreter;
+mod model_fields;
 mod optimiser;
 mod places;
 mod procedure_encoder;
 mod pure_function_encoder;
+mod rc_encoder;
+mod snapshot_encoder;
+mod spec_sanity;
+mod spec_visibility;
 mod spec_encoder;
+mod string_encoder;
+mod termination;
 mod type_encoder;
 mod utils;
+mod viper_domain_encoder;
 
+pub mod errors;
 pub mod vir;
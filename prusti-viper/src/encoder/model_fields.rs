@@ -0,0 +1,109 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Model fields, which abstract the private representation of a type.
+//!
+//! A model field is a `#[model]` method of a type, that is, a pure method that takes `&self` as
+//! its only argument. The public contracts of a type with model fields have to be expressed in
+//! terms of the model, so that clients of the type cannot depend on its private fields.
+
+use encoder::Encoder;
+use prusti_interface::data::ProcedureDefId;
+use prusti_interface::specifications::SpecificationSet;
+use rustc::hir;
+use rustc::hir::def_id::DefId;
+use rustc::hir::intravisit::{self, NestedVisitorMap, Visitor};
+use rustc::ty;
+
+/// Check that the public contract of a procedure does not mention the private fields of a type
+/// with model fields. Returns `false` if an error has been reported.
+pub fn check_contract_hides_fields<'v, 'r, 'a, 'tcx>(
+    encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+    proc_def_id: ProcedureDefId,
+) -> bool {
+    let tcx = encoder.env().tcx();
+    if tcx.visibility(proc_def_id) != ty::Visibility::Public {
+        return true;
+    }
+    let assertions = match encoder.get_spec_by_def_id(proc_def_id) {
+        Some(SpecificationSet::Procedure(ref pres, ref posts)) => pres
+            .iter()
+            .chain(posts.iter())
+            .map(|spec| &spec.assertion)
+            .collect::<Vec<_>>(),
+        _ => return true,
+    };
+
+    let mut collector = FieldAccessCollector { accesses: vec![] };
+    for assertion in assertions {
+        for expr in assertion.get_exprs() {
+            collector.visit_expr(expr);
+        }
+    }
+
+    let mut hides_fields = true;
+    for field_access in collector.accesses {
+        let base_expr = match field_access.node {
+            hir::Expr_::ExprField(ref base_expr, _) => base_expr,
+            _ => unreachable!(),
+        };
+        let typeck_tables = tcx.typeck_tables_of(field_access.hir_id.owner_def_id());
+        let mut base_ty = typeck_tables.expr_ty_adjusted(base_expr);
+        while let ty::TypeVariants::TyRef(_, target_ty, _) = base_ty.sty {
+            base_ty = target_ty;
+        }
+        let adt_def = match base_ty.ty_adt_def() {
+            Some(adt_def) if adt_def.is_struct() => adt_def,
+            _ => continue,
+        };
+        let field_index = tcx.field_index(field_access.id, typeck_tables);
+        let field = &adt_def.non_enum_variant().fields[field_index];
+        if field.vis != ty::Visibility::Public && has_model_fields(encoder, adt_def.did) {
+            encoder.env().span_err(
+                field_access.span,
+                &format!(
+                    "[Prusti: invalid specification] the public contract of `{}` mentions a \
+                     private field of `{}`, which should only be specified through its model",
+                    encoder.env().get_item_name(proc_def_id),
+                    encoder.env().get_item_name(adt_def.did),
+                ),
+            );
+            hides_fields = false;
+        }
+    }
+    hides_fields
+}
+
+/// Find whether an inherent implementation of the type declares a `#[model]` method.
+pub fn has_model_fields<'v, 'r, 'a, 'tcx>(
+    encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+    adt_did: DefId,
+) -> bool {
+    let tcx = encoder.env().tcx();
+    tcx.inherent_impls(adt_did).iter().any(|&impl_def_id| {
+        tcx.associated_item_def_ids(impl_def_id)
+            .iter()
+            .any(|&item_def_id| encoder.env().has_attribute_name(item_def_id, "model"))
+    })
+}
+
+/// Collects the field accesses of an expression, without entering closures.
+pub struct FieldAccessCollector<'v> {
+    pub accesses: Vec<&'v hir::Expr>,
+}
+
+impl<'v> Visitor<'v> for FieldAccessCollector<'v> {
+    fn nested_visit_map<'this>(&'this mut self) -> NestedVisitorMap<'this, 'v> {
+        NestedVisitorMap::None
+    }
+
+    fn visit_expr(&mut self, expr: &'v hir::Expr) {
+        if let hir::Expr_::ExprField(..) = expr.node {
+            self.accesses.push(expr);
+        }
+        intravisit::walk_expr(self, expr);
+    }
+}
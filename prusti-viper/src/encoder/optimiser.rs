@@ -98,6 +98,22 @@ impl vir::ExprFolder for Optimiser {
 
         forall
     }
+
+    fn fold_exists(
+        &mut self,
+        variables: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        body: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        let mut replacer = OldPlaceReplacer::new();
+        let replaced_body = replacer.fold_boxed(body);
+        let mut exists = vir::Expr::Exists(variables, triggers, replaced_body, pos.clone());
+        for (expr, variable) in replacer.map {
+            exists = vir::Expr::LetExpr(variable, box expr, box exists, pos.clone());
+        }
+        exists
+    }
 }
 
 struct OldPlaceReplacer {
@@ -178,6 +194,29 @@ impl vir::ExprFolder for UnfoldingExtractor {
 
         forall
     }
+    fn fold_exists(
+        &mut self,
+        variables: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        body: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        assert!(self.unfoldings.is_empty(), "Nested quantifiers are not supported.");
+
+        self.in_quantifier = true;
+        let replaced_body = self.fold_boxed(body);
+        self.in_quantifier = false;
+
+        let mut exists = vir::Expr::Exists(variables, triggers, replaced_body, pos.clone());
+
+        let unfoldings = mem::replace(&mut self.unfoldings, HashMap::new());
+
+        for ((name, args), (perm_amount, variant, _)) in unfoldings {
+            exists = vir::Expr::Unfolding(name, args, box exists, perm_amount, variant, pos.clone());
+        }
+
+        exists
+    }
     fn fold_unfolding(
         &mut self,
         name: String,
@@ -5,18 +5,22 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use encoder::borrows::ProcedureContract;
+use encoder::branch_conditions::BranchConditions;
 use encoder::builtin_encoder::BuiltinMethodKind;
 use encoder::error_manager::ErrorCtxt;
 use encoder::error_manager::PanicCause;
+use encoder::errors::{EncodingResult, SpannedEncodingError, UnsupportedFeature};
 use encoder::foldunfold;
 use encoder::initialisation::InitInfo;
-use encoder::loop_encoder::LoopEncoder;
+use encoder::loop_encoder::{is_integer_range_ty, is_slice_iter_ty, place_root_local, LoopEncoder};
 use encoder::mir_encoder::MirEncoder;
-use encoder::mir_encoder::{POSTCONDITION_LABEL, PRECONDITION_LABEL};
+use encoder::mir_encoder::{POSTCONDITION_LABEL, PRECONDITION_LABEL, WAND_LHS_LABEL};
 use encoder::optimiser;
 use encoder::places::{Local, LocalVariableManager, Place};
+use encoder::rc_encoder::{get_rc_content_ty, is_rc_ty};
+use encoder::spec_sanity::{self, SpecSanityCheck, SpecSanityCheckKind};
+use encoder::string_encoder::{is_string_ref_ty, is_string_ty, StringEncoder};
 use encoder::vir::fixes::{fix_ghost_vars, havoc_assigned_locals};
-use encoder::vir::optimisations::methods::{remove_trivial_assertions, remove_unused_vars};
 use encoder::vir::ExprIterator;
 use encoder::vir::{self, CfgBlockIndex, Successor};
 use encoder::Encoder;
@@ -33,8 +37,12 @@ use prusti_interface::environment::PermissionKind;
 use prusti_interface::environment::Procedure;
 use prusti_interface::report::log;
 use prusti_interface::specifications::*;
+use prusti_interface::utils;
 use rustc::hir::Mutability;
+use rustc::hir::def_id::DefId;
 use rustc::mir;
+use rustc::mir::interpret::EvalErrorKind;
+use rustc::mir::visit::{PlaceContext, Visitor};
 use rustc::mir::TerminatorKind;
 use rustc::ty;
 use rustc::ty::layout;
@@ -42,6 +50,7 @@ use rustc::ty::layout::IntegerExt;
 use rustc_data_structures::indexed_vec::Idx;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::time::Instant;
 use syntax::attr::SignedInt;
 use syntax::codemap::{MultiSpan, Span};
 use utils::to_string::ToString;
@@ -131,44 +140,52 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         }
     }
 
-    pub fn encode(mut self) -> vir::CfgMethod {
+    pub fn encode(mut self) -> EncodingResult<vir::CfgMethod> {
         trace!("Encode procedure {}", self.cfg_method.name());
 
+        self.encoder.check_ghost_types(self.mir)?;
+
         let mut procedure_contract = self
             .encoder
             .get_procedure_contract_for_def(self.proc_def_id);
 
         debug!("procedure_contract: {:?}", &procedure_contract);
-        //trace!("def_id of proc: {:?}", &self.proc_def_id);
-        let impl_def_id = self.encoder.env().tcx().impl_of_method(self.proc_def_id);
-        //trace!("def_id of impl: {:?}", &impl_def_id);
-        if let Some(id) = impl_def_id {
-            let def_id_trait = self.encoder.env().tcx().trait_id_of_impl(id);
-            //trace!("def_id of trait: {:?}", &def_id_trait);
-            if let Some(id) = def_id_trait {
-                let proc_name = self
+        // The implementation of a trait method is verified against the specification declared
+        // in the trait, which `get_procedure_contract_for_def` already takes into account,
+        // unless the implementation explicitly refines it. Operators have their own contracts.
+        let mut trait_contract = None;
+        if self
+            .encoder
+            .env()
+            .get_implemented_trait_method(self.proc_def_id)
+            .is_some()
+        {
+            let has_own_spec = self
+                .encoder
+                .get_spec_by_def_id(self.proc_def_id)
+                .map_or(false, |spec| !spec.is_empty());
+            if self.encoder.refines_trait_spec(self.proc_def_id) {
+                trait_contract = self
                     .encoder
-                    .env()
-                    .tcx()
-                    .item_name(self.proc_def_id)
-                    .to_string();
-                //trace!("proc_name: {:?}", proc_name);
-                let assoc_items: Vec<_> = self.encoder.env().tcx().associated_items(id).collect();
-                //trace!("assoc items: {:?}", &assoc_items);
-                if procedure_contract.functional_precondition().len() != 0
-                    || procedure_contract.functional_postcondition().len() != 0
-                {
-                    unimplemented!("Refinement of trait specifications is not supported.");
-                }
-                for assoc_item in assoc_items {
-                    if assoc_item.name == proc_name {
-                        // TODO use the impl's specs if there are any (separately replace pre/post!)
-                        procedure_contract = self
-                            .encoder
-                            .get_procedure_contract_for_def(assoc_item.def_id);
-                    }
-                }
+                    .get_trait_procedure_contract_for_def(self.proc_def_id);
+            } else if has_own_spec
+                && !self
+                    .encoder
+                    .strengthens_unspecified_trait_method(self.proc_def_id)
+                && !self.encoder.implements_operator(self.proc_def_id)
+            {
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::TraitSpecRefinement,
+                    self.mir.span,
+                    self.cfg_method.name(),
+                ));
             }
+        } else if self.encoder.refines_trait_spec(self.proc_def_id) {
+            self.encoder.env().span_err(
+                self.mir.span,
+                "[Prusti: invalid specification] `refine_requires` and `refine_ensures` can only \
+                 be used on implementations of trait methods",
+            );
         }
 
         // Formal return
@@ -185,7 +202,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             HashMap::new();
 
         // Initialize CFG blocks
-        let start_cfg_block = self.cfg_method.add_block(
+        let mut start_cfg_block = self.cfg_method.add_block(
             "start",
             vec![],
             vec![
@@ -196,6 +213,12 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             ],
         );
 
+        // Check that the refined contract is a behavioural subtype of the trait contract
+        if let Some(ref trait_contract) = trait_contract {
+            start_cfg_block =
+                self.encode_refinement_check(start_cfg_block, &procedure_contract, trait_contract);
+        }
+
         for bbi in self.procedure.get_reachable_cfg_blocks() {
             let cfg_block = self.cfg_method.add_block(
                 &format!("{:?}", bbi),
@@ -282,9 +305,11 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
 
         // Encode statements
         for bbi in self.procedure.get_reachable_cfg_blocks() {
-            self.encode_block(bbi, &cfg_edges, &mut procedure_contract, return_cfg_block);
+            self.encode_block(bbi, &cfg_edges, &mut procedure_contract, return_cfg_block)?;
         }
 
+        self.register_branch_conditions(&cfg_edges, return_cfg_block);
+
         let local_vars: Vec<_> = self
             .locals
             .iter()
@@ -388,8 +413,10 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             self.mir.span,
             ErrorCtxt::Unexpected,
         );
+        let fold_unfold_start = Instant::now();
         let method_with_fold_unfold = foldunfold::add_fold_unfold(
-            self.encoder, self.cfg_method, loan_positions, method_pos);
+            self.encoder, self.cfg_method, loan_positions, method_pos)?;
+        self.encoder.add_fold_unfold_duration(fold_unfold_start.elapsed());
 
         // Fix variable declarations.
         let mut fixed_method = fix_ghost_vars(method_with_fold_unfold);
@@ -399,11 +426,9 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             havoc_assigned_locals(&mut fixed_method, &havoc_methods);
         }
 
-        // Optimise encoding a bit
-        let method_without_unused_vars = remove_unused_vars(fixed_method);
-        let method_without_trivial_assertions =
-            remove_trivial_assertions(method_without_unused_vars);
-        let final_method = optimiser::rewrite(method_without_trivial_assertions);
+        // Optimise encoding a bit. The configurable optimisations are run later on the whole
+        // program.
+        let final_method = optimiser::rewrite(fixed_method);
 
         // Dump final CFG
         if config::dump_debug_info() {
@@ -414,7 +439,69 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             );
         }
 
-        final_method
+        Ok(final_method)
+    }
+
+    /// Encode a method that assumes the precondition of the procedure, together with the type
+    /// invariants of its arguments, and asserts `false`. The method verifies if and only if the
+    /// precondition is unsatisfiable.
+    pub fn encode_precondition_sanity_check(mut self) -> EncodingResult<SpecSanityCheck> {
+        trace!("Encode sanity check of the precondition of {}", self.cfg_method.name());
+
+        let procedure_contract = self
+            .encoder
+            .get_procedure_contract_for_def(self.proc_def_id);
+        self.cfg_method = vir::CfgMethod::new(
+            format!("sanity_check${}", self.cfg_method.name()),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+        );
+
+        let mut invariant_adts = vec![];
+        for &arg in &procedure_contract.args {
+            let arg_ty = self.locals.get_type(arg);
+            if let ty::TypeVariants::TyClosure(..) = arg_ty.sty {
+                // Do not encode closures
+                continue;
+            }
+            let type_name = self.encoder.encode_type_predicate_use(arg_ty);
+            let var_name = self.locals.get_name(arg);
+            self.cfg_method
+                .add_local_var(&var_name, vir::Type::TypedRef(type_name));
+            if let Some(invariant_ty) = spec_sanity::get_invariant_ty(self.encoder, arg_ty) {
+                invariant_adts.push(invariant_ty.ty_adt_def().unwrap().did);
+            }
+        }
+
+        let start_cfg_block = self.cfg_method.add_block(
+            "start",
+            vec![],
+            vec![
+                vir::Stmt::comment("========== start =========="),
+                vir::Stmt::comment("Check the satisfiability of the precondition"),
+            ],
+        );
+        self.encode_preconditions(start_cfg_block, &procedure_contract);
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(self.mir.span, ErrorCtxt::Unexpected);
+        self.cfg_method.add_stmt(
+            start_cfg_block,
+            vir::Stmt::Assert(false.into(), vir::FoldingBehaviour::Stmt, pos.clone()),
+        );
+        self.cfg_method
+            .set_successor(start_cfg_block, Successor::Return);
+
+        let method =
+            foldunfold::add_fold_unfold(self.encoder, self.cfg_method, HashMap::new(), pos)?;
+        Ok(SpecSanityCheck {
+            kind: SpecSanityCheckKind::Precondition(self.proc_def_id, invariant_adts),
+            span: self.mir.span,
+            method,
+        })
     }
 
     fn encode_block(
@@ -423,7 +510,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         cfg_edges: &HashMap<BasicBlockIndex, HashMap<BasicBlockIndex, CfgBlockIndex>>,
         procedure_contract: &mut ProcedureContract<'tcx>,
         return_cfg_block: vir::CfgBlockIndex,
-    ) {
+    ) -> EncodingResult<()> {
         let cfg_block = *self.mir_to_vir_blocks.get(&bbi).unwrap();
         let bb_pos = self
             .mir_encoder
@@ -433,9 +520,46 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         if self.loop_encoder.is_loop_head(bbi) {
             self.encode_loop_invariant_inhale(bbi, cfg_edges);
         }
-        self.encode_statements(bbi, cfg_block, cfg_edges);
-        self.encode_terminators(bbi, cfg_edges, procedure_contract, return_cfg_block);
+        self.encode_statements(bbi, cfg_block, cfg_edges)?;
+        self.encode_terminators(bbi, cfg_edges, procedure_contract, return_cfg_block)?;
         self.encode_loop_invariant_exhale(bbi, cfg_edges);
+        Ok(())
+    }
+
+    /// Record the branch decisions that lead to the encoded statements of each block and CFG
+    /// edge, so that a verification error can explain in which case it happens.
+    fn register_branch_conditions(
+        &self,
+        cfg_edges: &HashMap<BasicBlockIndex, HashMap<BasicBlockIndex, CfgBlockIndex>>,
+        return_cfg_block: CfgBlockIndex,
+    ) {
+        let branch_conditions = BranchConditions::new(self.encoder, self.procedure);
+        // The blocks that encode other MIR blocks or CFG edges
+        let mut stop_blocks: HashSet<CfgBlockIndex> =
+            self.mir_to_vir_blocks.values().cloned().collect();
+        stop_blocks.insert(return_cfg_block);
+        for cfg_successors in cfg_edges.values() {
+            stop_blocks.extend(cfg_successors.values().cloned());
+        }
+
+        let mut error_manager = self.encoder.error_manager();
+        let mut register = |cfg_block: CfgBlockIndex, conditions: &[String]| {
+            for reachable_block in self.cfg_method.get_reachable_blocks(cfg_block, &stop_blocks) {
+                error_manager.register_branch_conditions(
+                    self.cfg_method.get_block_stmts(reachable_block),
+                    conditions,
+                );
+            }
+        };
+        for bbi in self.procedure.get_reachable_cfg_blocks() {
+            register(
+                self.mir_to_vir_blocks[&bbi],
+                &branch_conditions.get_block_conditions(bbi),
+            );
+            for (&successor, &cfg_edge) in &cfg_edges[&bbi] {
+                register(cfg_edge, &branch_conditions.get_edge_conditions(bbi, successor));
+            }
+        }
     }
 
     /// Store a flag that becomes true the first time the block is executed
@@ -502,14 +626,14 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         bbi: BasicBlockIndex,
         cfg_block: vir::CfgBlockIndex,
         cfg_edges: &HashMap<BasicBlockIndex, HashMap<BasicBlockIndex, CfgBlockIndex>>,
-    ) {
+    ) -> EncodingResult<()> {
         if !self.procedure.is_spec_block(bbi) {
             if self.loop_encoder.is_loop_head(bbi) {
                 for cfg_successor in cfg_edges[&bbi].values() {
-                    self.encode_block_statements(bbi, *cfg_successor);
+                    self.encode_block_statements(bbi, *cfg_successor)?;
                 }
             } else {
-                self.encode_block_statements(bbi, cfg_block);
+                self.encode_block_statements(bbi, cfg_block)?;
             }
         } else {
             // Any spec block must be unreachable
@@ -526,6 +650,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 ),
             );
         }
+        Ok(())
     }
 
     /// Encode terminators and set CFG edges
@@ -535,7 +660,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         cfg_edges: &HashMap<BasicBlockIndex, HashMap<BasicBlockIndex, CfgBlockIndex>>,
         procedure_contract: &mut ProcedureContract<'tcx>,
         return_cfg_block: vir::CfgBlockIndex,
-    ) {
+    ) -> EncodingResult<()> {
         let bb_data = &self.mir.basic_blocks()[bbi];
         if let Some(ref term) = bb_data.terminator {
             trace!("Encode terminator of {:?}", bbi);
@@ -556,7 +681,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                     .expect(&format!("CFG block {:?} has no entry in 'cfg_edges'", bbi)),
                 return_cfg_block,
                 procedure_contract,
-            );
+            )?;
             if self.loop_encoder.is_loop_head(bbi) {
                 for cfg_successor in cfg_edges[&bbi].values() {
                     for stmt in stmts.iter() {
@@ -605,7 +730,9 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 for stmt in stmts.into_iter() {
                     self.cfg_method.add_stmt(cfg_block, stmt);
                 }
-                if successor != vir::Successor::Return {
+                if successor != vir::Successor::Return
+                    && !self.procedure.is_panic_message_block(bbi)
+                {
                     for successor in self.procedure.successors(bbi) {
                         if self.procedure.is_reachable_block(successor) {
                             let succ_location = mir::Location {
@@ -619,18 +746,42 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                             {
                                 self.cfg_method.add_stmt(cfg_edge_block, stmt);
                             }
+                            // A `break`, a `return` (or the exit of a `while let` loop) leaves
+                            // the loop from a block that is not the loop head. Restore the
+                            // permissions that have been framed when entering the loop. This is
+                            // done after the expiration of the borrows created in the loop,
+                            // whose permissions are in the frame of the loop.
+                            for _ in 0..self.count_exited_loops(bbi, successor) {
+                                self.cfg_method.add_stmt(cfg_edge_block, vir::Stmt::EndFrame);
+                            }
                         }
                     }
                 }
                 self.cfg_method.set_successor(cfg_block, successor);
             }
         }
+        Ok(())
+    }
+
+    /// The number of loops that are exited by the CFG edge `from --> to`.
+    fn count_exited_loops(&self, from: BasicBlockIndex, to: BasicBlockIndex) -> usize {
+        let to_loop_heads = self.loop_encoder.get_enclosing_loop_heads(to);
+        self.loop_encoder
+            .get_enclosing_loop_heads(from)
+            .iter()
+            .filter(|loop_head| !to_loop_heads.contains(loop_head))
+            .count()
     }
 
-    fn encode_block_statements(&mut self, bbi: BasicBlockIndex, cfg_block: CfgBlockIndex) {
+    fn encode_block_statements(
+        &mut self,
+        bbi: BasicBlockIndex,
+        cfg_block: CfgBlockIndex,
+    ) -> EncodingResult<()> {
         let bb_data = &self.mir.basic_blocks()[bbi];
         let statements: &Vec<mir::Statement<'tcx>> = &bb_data.statements;
-        let is_panic_block = self.procedure.is_panic_block(bbi);
+        let is_panic_message_block = self.procedure.is_panic_message_block(bbi);
+        let is_panic_block = self.procedure.is_panic_block(bbi) || is_panic_message_block;
         for (stmt_index, stmt) in statements.iter().enumerate() {
             trace!("Encode statement {:?}:{}", bbi, stmt_index);
             self.cfg_method
@@ -640,26 +791,73 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 statement_index: stmt_index,
             };
             if !is_panic_block {
-                for stmt in self.encode_statement(stmt, location).drain(..) {
+                for stmt in self.encode_statement(stmt, location)?.drain(..) {
+                    self.cfg_method.add_stmt(cfg_block, stmt);
+                }
+            }
+            // The borrows that expire while computing the message of a panic have not been
+            // encoded, because the statements that create them are skipped.
+            if !is_panic_message_block {
+                for stmt in self.encode_expiring_borrows_at(location).drain(..) {
                     self.cfg_method.add_stmt(cfg_block, stmt);
                 }
             }
-            for stmt in self.encode_expiring_borrows_at(location).drain(..) {
-                self.cfg_method.add_stmt(cfg_block, stmt);
+            if let mir::StatementKind::StorageDead(local) = stmt.kind {
+                // This has to be done after the expiration of the borrows of the local
+                if !is_panic_block && config::exhale_dead_permissions() {
+                    let ty = self.mir_encoder.get_local_ty(local);
+                    if !self.contains_references(ty, &mut HashSet::new()) {
+                        let encoded_local = self.mir_encoder.encode_local(local);
+                        self.cfg_method.add_stmt(
+                            cfg_block,
+                            vir::Stmt::ExhaleDeadPermissions(encoded_local),
+                        );
+                    }
+                }
             }
         }
+        Ok(())
+    }
+
+    /// Returns true if a value of the given type may contain references, whose permissions may
+    /// still be needed to expire a borrow after the value is dead.
+    fn contains_references(
+        &self,
+        ty: ty::Ty<'tcx>,
+        visited: &mut HashSet<ty::Ty<'tcx>>,
+    ) -> bool {
+        if !visited.insert(ty) {
+            return false;
+        }
+        let tcx = self.encoder.env().tcx();
+        ty.walk().any(|ty| match ty.sty {
+            ty::TypeVariants::TyAdt(adt_def, _) if adt_def.is_box() => false,
+            ty::TypeVariants::TyAdt(adt_def, substs) => adt_def
+                .all_fields()
+                .any(|field| self.contains_references(field.ty(tcx, substs), visited)),
+            ty::TypeVariants::TyRef(..)
+            | ty::TypeVariants::TyRawPtr(..)
+            | ty::TypeVariants::TyParam(..)
+            | ty::TypeVariants::TyProjection(..)
+            | ty::TypeVariants::TyAnon(..)
+            | ty::TypeVariants::TyDynamic(..)
+            | ty::TypeVariants::TyClosure(..) => true,
+            _ => false,
+        })
     }
 
     fn encode_statement(
         &mut self,
         stmt: &mir::Statement<'tcx>,
         location: mir::Location,
-    ) -> Vec<vir::Stmt> {
+    ) -> EncodingResult<Vec<vir::Stmt>> {
         debug!(
             "Encode statement '{:?}', span: {:?}",
             stmt.kind, stmt.source_info.span
         );
 
+        self.check_static_uses(stmt, location)?;
+
         let stmts = match stmt.kind {
             mir::StatementKind::StorageDead(_)
             | mir::StatementKind::StorageLive(_)
@@ -693,26 +891,56 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                         self.encode_assign_unary_op(op, operand, encoded_lhs, ty, location)
                     }
                     &mir::Rvalue::NullaryOp(op, ref op_ty) => {
-                        self.encode_assign_nullary_op(op, op_ty, encoded_lhs, ty, location)
+                        self.encode_assign_nullary_op(op, op_ty, encoded_lhs, ty, location, stmt)?
                     }
                     &mir::Rvalue::Discriminant(ref src) => {
                         self.encode_assign_discriminant(src, location, encoded_lhs, ty)
                     }
                     &mir::Rvalue::Ref(ref _region, mir_borrow_kind, ref place) => {
-                        self.encode_assign_ref(mir_borrow_kind, place, location, encoded_lhs, ty)
+                        self.encode_assign_ref(
+                            mir_borrow_kind,
+                            place,
+                            location,
+                            encoded_lhs,
+                            ty,
+                            stmt,
+                        )?
                     }
                     &mir::Rvalue::Cast(mir::CastKind::Misc, ref operand, dst_ty) => {
-                        self.encode_cast(operand, dst_ty, encoded_lhs, ty, location)
+                        self.encode_cast(operand, dst_ty, encoded_lhs, ty, location)?
+                    }
+                    &mir::Rvalue::Cast(mir::CastKind::Unsize, ref operand, dst_ty)
+                        if dst_ty.is_box() && dst_ty.boxed_ty().is_trait() =>
+                    {
+                        self.encode_assign_boxed_trait_object(operand, encoded_lhs, location)
                     }
                     ref rhs => {
-                        unimplemented!("encoding of '{:?}'", rhs);
+                        return Err(SpannedEncodingError::unsupported(
+                            UnsupportedFeature::of_rvalue(rhs),
+                            stmt.source_info.span,
+                            format!("{:?}", stmt),
+                        ));
                     }
                 }
             }
 
-            ref x => unimplemented!("{:?}", x),
+            mir::StatementKind::InlineAsm { .. } => {
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::InlineAssembly,
+                    stmt.source_info.span,
+                    format!("{:?}", stmt),
+                ));
+            }
+
+            _ => {
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::Statement,
+                    stmt.source_info.span,
+                    format!("{:?}", stmt),
+                ));
+            }
         };
-        stmts
+        Ok(stmts
             .into_iter()
             .map(|s| {
                 let expr_pos = self
@@ -725,7 +953,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                     .register(stmt.source_info.span, ErrorCtxt::GenericStatement);
                 s.set_default_expr_pos(expr_pos).set_default_pos(stmt_pos)
             })
-            .collect()
+            .collect())
     }
 
     /// Translate a borrowed place to a place that is currently usable
@@ -1190,14 +1418,14 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         cfg_blocks: &HashMap<BasicBlockIndex, CfgBlockIndex>,
         return_cfg_block: CfgBlockIndex,
         contract: &ProcedureContract<'tcx>,
-    ) -> (Vec<vir::Stmt>, Successor) {
+    ) -> EncodingResult<(Vec<vir::Stmt>, Successor)> {
         debug!(
             "Encode terminator '{:?}', span: {:?}",
             term.kind, term.source_info.span
         );
         let mut stmts: Vec<vir::Stmt> = vec![];
 
-        match term.kind {
+        Ok(match term.kind {
             TerminatorKind::Return => {
                 // Package magic wands, if there is any
                 stmts.extend(self.encode_package_end_of_method(
@@ -1277,10 +1505,23 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
 
                         ref x => unreachable!("{:?}", x),
                     };
-                    let target_cfg_block = cfg_blocks.get(&target).unwrap();
-                    cfg_targets.push((viper_guard, *target_cfg_block))
+                    let target_cfg_block = *cfg_blocks.get(&target).unwrap();
+                    // The values of an or-pattern (e.g. `A | B`) jump to the same block, so the
+                    // guard of that block is the disjunction of their tests.
+                    let existing_target = cfg_targets
+                        .iter_mut()
+                        .find(|(_, cfg_target)| *cfg_target == target_cfg_block);
+                    if let Some((guard, _)) = existing_target {
+                        *guard = vir::Expr::or(guard.clone(), viper_guard);
+                        continue;
+                    }
+                    cfg_targets.push((viper_guard, target_cfg_block))
                 }
                 let default_target = targets[values.len()];
+                // A value that jumps to the default block does not need its own guard.
+                if let Some(default_cfg_block) = cfg_blocks.get(&default_target) {
+                    cfg_targets.retain(|(_, cfg_target)| cfg_target != default_cfg_block);
+                }
                 let cfg_default_target = if let Some(cfg_target) = cfg_blocks.get(&default_target) {
                     *cfg_target
                 } else {
@@ -1349,7 +1590,12 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 (stmts, Successor::Return)
             }
 
-            TerminatorKind::Drop { ref target, .. } => {
+            TerminatorKind::Drop {
+                ref target,
+                location: ref place,
+                ..
+            } => {
+                stmts.extend(self.encode_drop(place, location, term.source_info.span));
                 let target_cfg_block = cfg_blocks.get(&target).unwrap();
                 (stmts, Successor::Goto(*target_cfg_block))
             }
@@ -1402,6 +1648,23 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 ..
             } => {
                 let func_proc_name: &str = &self.encoder.env().tcx().absolute_item_path_str(def_id);
+                // Used to recognise the identity conversion `impl<T> From<T> for T`
+                let is_identity_conversion = substs.types().count() == 2 && {
+                    let tcx = self.encoder.env().tcx();
+                    tcx.erase_regions(&substs.type_at(0)) == tcx.erase_regions(&substs.type_at(1))
+                };
+                let (def_id, substs) = self.encoder.get_called_procedure(def_id, substs);
+
+                // The behaviour of a foreign function is only known from its specification
+                if self.encoder.env().tcx().is_foreign_item(def_id)
+                    && !self.encoder.env().has_attribute_name(def_id, "trusted_ffi")
+                {
+                    return Err(SpannedEncodingError::unsupported(
+                        UnsupportedFeature::ForeignCall,
+                        term.source_info.span,
+                        format!("{:?}", term.kind),
+                    ));
+                }
 
                 let own_substs =
                     ty::subst::Substs::identity_for_item(self.encoder.env().tcx(), def_id);
@@ -1424,94 +1687,36 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 }
 
                 match func_proc_name {
-                    "std::rt::begin_panic" | "std::panicking::begin_panic" => {
+                    _ if self.procedure.is_panic_message_block(location.block) => {
+                        // The computation of the message of a panic is not encoded
+                        debug!("Skip the computation of a panic message: {:?}", term);
+                    }
+
+                    "std::rt::begin_panic"
+                    | "std::panicking::begin_panic"
+                    | "std::rt::begin_panic_fmt"
+                    | "std::panicking::begin_panic_fmt" => {
                         // This is called when a Rust assertion fails
-                        // args[0]: message
+                        // args[0]: message (or formatted message)
                         // args[1]: position of failing assertions
 
                         // Example of args[0]: 'const "internal error: entered unreachable code"'
                         let panic_message = format!("{:?}", args[0]);
 
-                        // Pattern match on the macro that generated the panic
-                        // TODO: use a better approach to match macros
-                        let macro_backtrace = term.source_info.span.macro_backtrace();
-                        debug!("macro_backtrace: {:?}", macro_backtrace);
-
-                        let panic_cause = if !macro_backtrace.is_empty() {
-                            let macro_name = term.source_info.span.macro_backtrace()[0]
-                                .macro_decl_name
-                                .clone();
-                            // HACK to match the filename of the span
-                            let def_site_span = format!(
-                                "{:?}",
-                                term.source_info.span.macro_backtrace()[0].def_site_span
-                            );
-
-                            match macro_name.as_str() {
-                                "panic!" if def_site_span.contains("<panic macros>") => {
-                                    if macro_backtrace.len() > 1 {
-                                        let second_macro_name =
-                                            term.source_info.span.macro_backtrace()[1]
-                                                .macro_decl_name
-                                                .clone();
-                                        // HACK to match the filename of the span
-                                        let second_def_site_span = format!(
-                                            "{:?}",
-                                            term.source_info.span.macro_backtrace()[1]
-                                                .def_site_span
-                                        );
-
-                                        match second_macro_name.as_str() {
-                                            "panic!"
-                                                if second_def_site_span
-                                                    .contains("<panic macros>") =>
-                                            {
-                                                PanicCause::Panic
-                                            }
-                                            "assert!" if second_def_site_span == "None" => {
-                                                PanicCause::Assert
-                                            }
-                                            "unreachable!"
-                                                if second_def_site_span
-                                                    .contains("<unreachable macros>") =>
-                                            {
-                                                PanicCause::Unreachable
-                                            }
-                                            "unimplemented!"
-                                                if second_def_site_span
-                                                    .contains("<unimplemented macros>") =>
-                                            {
-                                                PanicCause::Unimplemented
-                                            }
-                                            _ => PanicCause::Panic,
-                                        }
-                                    } else {
-                                        PanicCause::Panic
-                                    }
-                                }
-                                _ => PanicCause::Unknown,
-                            }
-                        } else {
-                            // Something else called panic!()
-                            PanicCause::Unknown
-                        };
-                        let pos = self
-                            .encoder
-                            .error_manager()
-                            .register(term.source_info.span, ErrorCtxt::Panic(panic_cause));
-
-                        if self.check_panics {
+                        let (panic_cause, panic_span) =
+                            self.mir_encoder.get_panic_cause(term.source_info.span);
+                        let ignore_debug_assertion =
+                            panic_cause.is_debug_assertion() && !config::verify_debug_assertions();
+                        if self.check_panics && !ignore_debug_assertion {
                             stmts.push(vir::Stmt::comment(format!(
                                 "Rust panic - {}",
                                 panic_message
                             )));
-                            stmts.push(
-                                vir::Stmt::Assert(
-                                    false.into(),
-                                    vir::FoldingBehaviour::Stmt,
-                                    pos
-                                )
-                            );
+                            stmts.extend(self.encode_panic_check(
+                                contract,
+                                panic_span,
+                                ErrorCtxt::Panic(panic_cause),
+                            ));
                         } else {
                             debug!("Absence of panic will not be checked")
                         }
@@ -1543,9 +1748,534 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                         stmts.extend(self.encode_assign_operand(&box_content, &args[0], location));
                     }
 
+                    "<std::rc::Rc<T>>::new"
+                    | "<alloc::rc::Rc<T>>::new"
+                    | "<std::sync::Arc<T>>::new"
+                    | "<alloc::sync::Arc<T>>::new" => {
+                        // This is the initialization of a reference-counted pointer, which is
+                        // encoded like the initialization of a box
+                        // args[0]: value to be shared
+                        assert_eq!(args.len(), 1);
+
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, dest_ty, _) = self.mir_encoder.encode_place(target_place);
+                        let content_ty = get_rc_content_ty(dest_ty);
+                        let ref_field = self.encoder.encode_dereference_field(content_ty);
+
+                        let rc_content = dst.clone().field(ref_field.clone());
+
+                        stmts.extend(self.prepare_assign_target(
+                            dst,
+                            ref_field,
+                            location,
+                            vir::AssignKind::Move,
+                        ));
+
+                        // Allocate `rc_content`
+                        stmts.extend(self.encode_havoc_and_allocation(&rc_content));
+
+                        // Initialize `rc_content`
+                        stmts.extend(self.encode_assign_operand(&rc_content, &args[0], location));
+                    }
+
+                    "std::clone::Clone::clone" | "core::clone::Clone::clone"
+                        if self.is_rc_argument(args) =>
+                    {
+                        // This is the creation of another pointer to the content of an `Rc`,
+                        // which gets its own read permission to the content
+                        // args[0]: shared reference to the `Rc`
+                        let (rc, rc_ty) = self.encode_deref_operand(&args[0]);
+                        let content_ty = get_rc_content_ty(rc_ty);
+                        let ref_field = self.encoder.encode_dereference_field(content_ty);
+
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, _, _) = self.mir_encoder.encode_place(target_place);
+                        stmts.extend(self.encode_havoc_and_allocation(&dst));
+                        stmts.push(vir::Stmt::Inhale(
+                            vir::Expr::eq_cmp(dst.field(ref_field.clone()), rc.field(ref_field)),
+                            vir::FoldingBehaviour::Expr,
+                        ));
+                    }
+
+                    "std::ops::Deref::deref" | "core::ops::Deref::deref"
+                        if self.is_rc_argument(args) =>
+                    {
+                        // This is the dereferentiation of an `Rc`, which gives a shared reference
+                        // to its content
+                        // args[0]: shared reference to the `Rc`
+                        let (rc, rc_ty) = self.encode_deref_operand(&args[0]);
+                        let content_ty = get_rc_content_ty(rc_ty);
+                        let rc_field = self.encoder.encode_dereference_field(content_ty);
+
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, dest_ty, _) = self.mir_encoder.encode_place(target_place);
+                        let ref_field = self.encoder.encode_value_field(dest_ty);
+                        stmts.extend(self.encode_havoc_and_allocation(&dst));
+                        stmts.push(vir::Stmt::Inhale(
+                            vir::Expr::eq_cmp(dst.field(ref_field), rc.field(rc_field)),
+                            vir::FoldingBehaviour::Expr,
+                        ));
+                    }
+
+                    "<std::rc::Rc<T>>::get_mut"
+                    | "<std::rc::Rc<T>>::make_mut"
+                    | "<alloc::rc::Rc<T>>::get_mut"
+                    | "<alloc::rc::Rc<T>>::make_mut"
+                    | "<std::sync::Arc<T>>::get_mut"
+                    | "<std::sync::Arc<T>>::make_mut"
+                    | "<alloc::sync::Arc<T>>::get_mut"
+                    | "<alloc::sync::Arc<T>>::make_mut" => {
+                        // The reference count is not modelled, so we cannot know whether the
+                        // pointer is unique
+                        return Err(SpannedEncodingError::unsupported(
+                            UnsupportedFeature::RcMutation,
+                            term.source_info.span,
+                            format!("{:?}", term.kind),
+                        ));
+                    }
+
+                    "std::ops::Try::into_result" | "core::ops::Try::into_result" => {
+                        // This is the conversion of the operand of the `?` operator
+                        // args[0]: the `Result` or `Option` to be converted into a `Result`
+                        assert_eq!(args.len(), 1);
+
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, dest_ty, _) = self.mir_encoder.encode_place(target_place);
+                        let arg_ty = self.mir_encoder.get_operand_ty(&args[0]);
+                        if arg_ty.ty_adt_def() != dest_ty.ty_adt_def() {
+                            // An `Option` becomes a `Result<T, NoneError>`
+                            return Err(SpannedEncodingError::unsupported(
+                                UnsupportedFeature::OptionTry,
+                                term.source_info.span,
+                                format!("{:?}", term.kind),
+                            ));
+                        }
+
+                        // A `Result` is converted into itself, so it is moved to the destination
+                        stmts.extend(self.encode_assign_operand(&dst, &args[0], location));
+                    }
+
+                    "std::ops::Try::from_error" | "core::ops::Try::from_error" => {
+                        // This is the construction of the value that the `?` operator returns early
+                        // args[0]: the error (a `NoneError` in the case of an `Option`)
+                        assert_eq!(args.len(), 1);
+
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, dest_ty, _) = self.mir_encoder.encode_place(target_place);
+                        let (adt_def, adt_substs) = match dest_ty.sty {
+                            ty::TypeVariants::TyAdt(adt_def, adt_substs) => (adt_def, adt_substs),
+                            ref x => unreachable!("{:?}", x),
+                        };
+
+                        // `Err(args[0])` for a `Result`, `None` for an `Option`
+                        let is_result = adt_def
+                            .variants
+                            .iter()
+                            .any(|variant| variant.name.as_str() == "Err");
+                        let (variant_name, operands) = if is_result {
+                            ("Err", vec![args[0].clone()])
+                        } else {
+                            ("None", vec![])
+                        };
+                        let variant_index = adt_def
+                            .variants
+                            .iter()
+                            .position(|variant| variant.name.as_str() == variant_name)
+                            .unwrap();
+                        let aggregate =
+                            mir::AggregateKind::Adt(adt_def, variant_index, adt_substs, None);
+                        stmts.extend(self.encode_assign_aggregate(
+                            &dst,
+                            dest_ty,
+                            &aggregate,
+                            &operands,
+                            location,
+                        ));
+                    }
+
+                    "std::convert::From::from" | "core::convert::From::from"
+                        if is_identity_conversion =>
+                    {
+                        // This is the conversion of a value into its own type, e.g. of the error
+                        // propagated by the `?` operator
+                        // args[0]: the value to be converted
+                        assert_eq!(args.len(), 1);
+
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, _, _) = self.mir_encoder.encode_place(target_place);
+                        stmts.extend(self.encode_assign_operand(&dst, &args[0], location));
+                    }
+
+                    "std::string::String::new" | "alloc::string::String::new" => {
+                        // This is the creation of an empty string
+                        assert_eq!(args.len(), 0);
+
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, _, _) = self.mir_encoder.encode_place(target_place);
+                        stmts.extend(self.encode_havoc_and_allocation(&dst));
+                        let empty_string = self.encoder.encode_string_literal("\"\"", 0);
+                        stmts.push(vir::Stmt::Inhale(
+                            vir::Expr::eq_cmp(
+                                dst.field(self.encoder.encode_string_field()),
+                                empty_string,
+                            ),
+                            vir::FoldingBehaviour::Expr,
+                        ));
+                    }
+
+                    "std::string::String::push_str" | "alloc::string::String::push_str" => {
+                        // This is the concatenation of a string at the end of a `String`
+                        // args[0]: mutable reference to the `String`
+                        // args[1]: the `&str` to be appended
+                        assert_eq!(args.len(), 2);
+
+                        let (string, _) = self.encode_deref_operand(&args[0]);
+                        let string_value = string.field(self.encoder.encode_string_field());
+                        let suffix = self.encode_string_operand(&args[1])?;
+                        stmts.push(vir::Stmt::Assign(
+                            string_value.clone(),
+                            StringEncoder::new().encode_concat(string_value, suffix),
+                            vir::AssignKind::Copy,
+                        ));
+
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, _, _) = self.mir_encoder.encode_place(target_place);
+                        stmts.extend(self.encode_havoc_and_allocation(&dst));
+
+                        // Store a label for this state
+                        let label = self.cfg_method.get_fresh_label_name();
+                        debug!("Current loc {:?} has label {}", location, label);
+                        self.label_after_location.insert(location, label.clone());
+                        stmts.push(vir::Stmt::Label(label));
+                    }
+
+                    "std::convert::From::from"
+                    | "core::convert::From::from"
+                    | "std::string::ToString::to_string"
+                    | "alloc::string::ToString::to_string"
+                    | "std::borrow::ToOwned::to_owned"
+                    | "alloc::borrow::ToOwned::to_owned"
+                        if self.is_string_conversion(args, destination) =>
+                    {
+                        // This is the conversion of a `&str` into a `String` with the same content
+                        // args[0]: the `&str` to be converted
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, _, _) = self.mir_encoder.encode_place(target_place);
+                        let content = self.encode_string_operand(&args[0])?;
+                        stmts.extend(self.encode_havoc_and_allocation(&dst));
+                        stmts.push(vir::Stmt::Inhale(
+                            vir::Expr::eq_cmp(
+                                dst.field(self.encoder.encode_string_field()),
+                                content,
+                            ),
+                            vir::FoldingBehaviour::Expr,
+                        ));
+                    }
+
+                    "std::ops::Index::index" | "core::ops::Index::index"
+                        if self.is_substring(args) =>
+                    {
+                        // This is the slicing `&s[start..end]` of a string
+                        // args[0]: the `&str` to be sliced
+                        // args[1]: the `Range<usize>` of the bytes of the substring
+                        assert_eq!(args.len(), 2);
+
+                        let string = self.encode_string_operand(&args[0])?;
+                        let usize_ty = self.encoder.env().tcx().types.usize;
+                        let usize_value_field = self.encoder.encode_value_field(usize_ty);
+                        let range = self.mir_encoder.encode_operand_place(&args[1]).unwrap();
+                        let start = range
+                            .clone()
+                            .field(self.encoder.encode_struct_field("start", usize_ty))
+                            .field(usize_value_field.clone());
+                        let end = range
+                            .field(self.encoder.encode_struct_field("end", usize_ty))
+                            .field(usize_value_field);
+
+                        // The range has to be within the string
+                        if self.check_panics {
+                            let pos = self.encoder.error_manager().register(
+                                term.source_info.span,
+                                ErrorCtxt::AssertTerminator(
+                                    "byte index out of range of the string".to_string(),
+                                ),
+                            );
+                            stmts.push(vir::Stmt::Assert(
+                                vir::Expr::and(
+                                    vir::Expr::le_cmp(start.clone(), end.clone()),
+                                    vir::Expr::le_cmp(
+                                        end.clone(),
+                                        StringEncoder::new().encode_len(string.clone()),
+                                    ),
+                                ),
+                                vir::FoldingBehaviour::Expr,
+                                pos,
+                            ));
+                        }
+
+                        // The result references a new string with the content of the substring
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, dest_ty, _) = self.mir_encoder.encode_place(target_place);
+                        let ref_field = self.encoder.encode_value_field(dest_ty);
+                        stmts.extend(self.prepare_assign_target(
+                            dst.clone(),
+                            ref_field.clone(),
+                            location,
+                            vir::AssignKind::Move,
+                        ));
+                        let substring = dst.field(ref_field);
+                        stmts.extend(self.encode_havoc_and_allocation(&substring));
+                        stmts.push(vir::Stmt::Inhale(
+                            vir::Expr::eq_cmp(
+                                substring.field(self.encoder.encode_string_field()),
+                                StringEncoder::new().encode_substring(string, start, end),
+                            ),
+                            vir::FoldingBehaviour::Expr,
+                        ));
+                    }
+
+                    "std::iter::IntoIterator::into_iter" | "core::iter::IntoIterator::into_iter"
+                        if self.is_range_argument(args, false)
+                            || self.is_slice_iter_argument(args) =>
+                    {
+                        // This is the conversion of the range or slice iterator of a `for` loop
+                        // into its iterator, which is the range or iterator itself
+                        // args[0]: the range or slice iterator
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, _, _) = self.mir_encoder.encode_place(target_place);
+                        stmts.extend(self.encode_assign_operand(&dst, &args[0], location));
+                    }
+
+                    "std::iter::Iterator::next" | "core::iter::Iterator::next"
+                        if self.is_range_argument(args, true) =>
+                    {
+                        // This is a step of the iteration over a range of integers
+                        // args[0]: mutable reference to the range
+                        let (range, range_ty) = self.encode_deref_operand(&args[0]);
+                        let elem_ty = match range_ty.sty {
+                            ty::TypeVariants::TyAdt(_, range_substs) => range_substs.type_at(0),
+                            ref x => unreachable!("{:?}", x),
+                        };
+                        let value_field = self.encoder.encode_value_field(elem_ty);
+                        let start = range
+                            .clone()
+                            .field(self.encoder.encode_struct_field("start", elem_ty))
+                            .field(value_field.clone());
+                        let end = range
+                            .field(self.encoder.encode_struct_field("end", elem_ty))
+                            .field(value_field.clone());
+                        let has_next = vir::Expr::lt_cmp(start.clone(), end);
+
+                        // The result is `Some(start)` if the range is not empty, `None` otherwise
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, dest_ty, _) = self.mir_encoder.encode_place(target_place);
+                        let adt_def = match dest_ty.sty {
+                            ty::TypeVariants::TyAdt(adt_def, _) => adt_def,
+                            ref x => unreachable!("{:?}", x),
+                        };
+                        let tcx = self.encoder.env().tcx();
+                        let variant_discr = |name: &str| -> vir::Expr {
+                            let variant_index = adt_def
+                                .variants
+                                .iter()
+                                .position(|variant| variant.name.as_str() == name)
+                                .unwrap();
+                            adt_def.discriminant_for_variant(tcx, variant_index).val.into()
+                        };
+                        let some_discr = variant_discr("Some");
+                        let none_discr = variant_discr("None");
+                        stmts.extend(self.encode_havoc_and_allocation(&dst));
+                        stmts.push(vir::Stmt::Inhale(
+                            vir::Expr::eq_cmp(
+                                self.encoder.encode_discriminant_func_app(dst.clone(), adt_def),
+                                vir::Expr::ite(has_next.clone(), some_discr, none_discr),
+                            ),
+                            vir::FoldingBehaviour::Stmt,
+                        ));
+                        let next_value = dst
+                            .variant("Some")
+                            .field(self.encoder.encode_struct_field("0", elem_ty))
+                            .field(value_field);
+                        stmts.push(vir::Stmt::Inhale(
+                            vir::Expr::implies(
+                                has_next.clone(),
+                                vir::Expr::eq_cmp(next_value, start.clone()),
+                            ),
+                            vir::FoldingBehaviour::Expr,
+                        ));
+
+                        // Advance the range
+                        stmts.push(vir::Stmt::Assign(
+                            start.clone(),
+                            vir::Expr::ite(
+                                has_next,
+                                vir::Expr::add(start.clone(), 1.into()),
+                                start,
+                            ),
+                            vir::AssignKind::Copy,
+                        ));
+
+                        // Store a label for this state
+                        let label = self.cfg_method.get_fresh_label_name();
+                        debug!("Current loc {:?} has label {}", location, label);
+                        self.label_after_location.insert(location, label.clone());
+                        stmts.push(vir::Stmt::Label(label));
+                    }
+
+                    "std::mem::swap" | "core::mem::swap" => {
+                        // This is an exchange of the values of two places
+                        // args[0]: mutable reference to the first place
+                        // args[1]: mutable reference to the second place
+                        assert_eq!(args.len(), 2);
+
+                        let (lhs, ty) = self.encode_deref_operand(&args[0]);
+                        let (rhs, _) = self.encode_deref_operand(&args[1]);
+
+                        // Move the permissions through a fresh temporary, so that nothing is lost
+                        let tmp_local = self.locals.get_fresh(ty);
+                        let tmp = vir::Expr::local(self.encode_prusti_local(tmp_local));
+                        stmts.push(vir::Stmt::Assign(tmp.clone(), lhs.clone(), vir::AssignKind::Move));
+                        stmts.push(vir::Stmt::Assign(lhs, rhs.clone(), vir::AssignKind::Move));
+                        stmts.push(vir::Stmt::Assign(rhs, tmp, vir::AssignKind::Move));
+
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, _, _) = self.mir_encoder.encode_place(target_place);
+                        stmts.extend(self.encode_havoc_and_allocation(&dst));
+
+                        // Store a label for this state
+                        let label = self.cfg_method.get_fresh_label_name();
+                        debug!("Current loc {:?} has label {}", location, label);
+                        self.label_after_location.insert(location, label.clone());
+                        stmts.push(vir::Stmt::Label(label));
+                    }
+
+                    "prusti_contracts::internal::check_contract" => {
+                        // This is a runtime check of a contract (see `CONTRACT_CHECKS`), which is
+                        // already checked by the verifier
+                        debug!("Skipping the runtime check of a contract");
+                    }
+
+                    "prusti_contracts::internal::prusti_assert" => {
+                        // This is a `prusti_assert!` ghost statement
+                        // args[0]: the asserted condition
+                        assert_eq!(args.len(), 1);
+                        let condition = self.mir_encoder.encode_operand_expr(&args[0]);
+                        // Report the error at the macro invocation
+                        let span = term.source_info.span.source_callsite();
+                        let pos = self
+                            .encoder
+                            .error_manager()
+                            .register(span, ErrorCtxt::AssertGhostStatement);
+                        stmts.push(vir::Stmt::Assert(
+                            condition,
+                            vir::FoldingBehaviour::Expr,
+                            pos,
+                        ));
+                    }
+
+                    "prusti_contracts::internal::prusti_assume" => {
+                        // This is a `prusti_assume!` ghost statement
+                        // args[0]: the assumed condition
+                        assert_eq!(args.len(), 1);
+                        let condition = self.mir_encoder.encode_operand_expr(&args[0]);
+                        stmts.push(vir::Stmt::Inhale(condition, vir::FoldingBehaviour::Expr));
+                    }
+
+                    "prusti_contracts::internal::prusti_fold"
+                    | "prusti_contracts::internal::prusti_unfold" => {
+                        // This is a `fold!(..)` or `unfold!(..)` ghost statement
+                        // args[0]: shared reference to the place of the predicate
+                        assert_eq!(args.len(), 1);
+                        let place = self.get_ghost_statement_place(&args[0], location, term)?;
+                        let (encoded_place, place_ty, _) = self.mir_encoder.encode_place(&place);
+                        let predicate_name = self.encoder.encode_type_predicate_use(place_ty);
+                        let perm_amount = if self.is_behind_shared_reference(&place) {
+                            vir::PermAmount::Read
+                        } else {
+                            vir::PermAmount::Write
+                        };
+                        if func_proc_name == "prusti_contracts::internal::prusti_fold" {
+                            // Report the error at the macro invocation
+                            let span = term.source_info.span.source_callsite();
+                            let pos = self
+                                .encoder
+                                .error_manager()
+                                .register(span, ErrorCtxt::FoldGhostStatement);
+                            stmts.push(vir::Stmt::Fold(
+                                predicate_name,
+                                vec![encoded_place],
+                                perm_amount,
+                                None,
+                                pos,
+                            ));
+                        } else {
+                            stmts.push(vir::Stmt::Unfold(
+                                predicate_name,
+                                vec![encoded_place],
+                                perm_amount,
+                                None,
+                            ));
+                        }
+                    }
+
+                    "prusti_contracts::internal::old" => {
+                        // This is an `old(..)` expression in the body, e.g. in an `assert!`
+                        // args[0]: the expression to be evaluated in the pre-state
+                        assert_eq!(args.len(), 1);
+                        let old_value = self.encode_old_argument(&args[0], location, term)?;
+
+                        // Havoc the content of the lhs
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, _, _) = self.mir_encoder.encode_place(target_place);
+                        stmts.extend(self.encode_havoc(&dst));
+                        let type_predicate = self
+                            .mir_encoder
+                            .encode_place_predicate_permission(dst, vir::PermAmount::Write)
+                            .unwrap();
+                        stmts.push(vir::Stmt::Inhale(type_predicate, vir::FoldingBehaviour::Stmt));
+
+                        // Initialize the lhs
+                        let target_value = self.mir_encoder.eval_place(target_place);
+                        stmts.push(vir::Stmt::Inhale(
+                            vir::Expr::eq_cmp(target_value, old_value),
+                            vir::FoldingBehaviour::Stmt,
+                        ));
+                    }
+
+                    "std::mem::replace" | "core::mem::replace" => {
+                        // This is a replacement of the value of a place
+                        // args[0]: mutable reference to the place
+                        // args[1]: new value of the place
+                        assert_eq!(args.len(), 2);
+
+                        let (place, ty) = self.encode_deref_operand(&args[0]);
+
+                        // The old value is moved to the destination, through a fresh
+                        // temporary so that the destination is initialised with its permissions
+                        let tmp_local = self.locals.get_fresh(ty);
+                        let tmp = vir::Expr::local(self.encode_prusti_local(tmp_local));
+                        stmts.push(vir::Stmt::Assign(
+                            tmp.clone(),
+                            place.clone(),
+                            vir::AssignKind::Move,
+                        ));
+                        let &(ref target_place, _) = destination.as_ref().unwrap();
+                        let (dst, _, _) = self.mir_encoder.encode_place(target_place);
+                        stmts.extend(self.encode_havoc(&dst));
+                        stmts.push(vir::Stmt::Assign(dst, tmp, vir::AssignKind::Move));
+
+                        // Initialize the place with the new value
+                        stmts.extend(self.encode_assign_operand(&place, &args[1], location));
+
+                        // Store a label for this state
+                        let label = self.cfg_method.get_fresh_label_name();
+                        debug!("Current loc {:?} has label {}", location, label);
+                        self.label_after_location.insert(location, label.clone());
+                        stmts.push(vir::Stmt::Label(label));
+                    }
+
                     _ => {
-                        let is_pure_function =
-                            self.encoder.env().has_attribute_name(def_id, "pure");
+                        let is_pure_function = self.encoder.is_pure(def_id);
                         if is_pure_function {
                             let function_name = self.encoder.encode_pure_function_use(def_id);
                             debug!("Encoding pure function call '{}'", function_name);
@@ -1829,6 +2559,15 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                                 term.source_info.span,
                                 ErrorCtxt::ExhaleMethodPrecondition,
                             );
+                            let precondition_spans: Vec<Span> = procedure_contract
+                                .functional_precondition()
+                                .iter()
+                                .flat_map(|item| item.assertion.get_spans())
+                                .collect();
+                            self.encoder.error_manager().register_spec_span(
+                                &pos,
+                                MultiSpan::from_spans(precondition_spans),
+                            );
                             stmts.push(
                                 vir::Stmt::Assert(
                                     replace_fake_exprs(pre_func_spec),
@@ -1843,6 +2582,24 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                                     pos.clone(),
                                 )
                             );
+
+                            // The callee may panic, so the caller has to declare that it may
+                            // panic as well. The panic postcondition of the caller is not checked
+                            // when the callee panics, because unwinding is not encoded.
+                            if self.check_panics
+                                && procedure_contract.may_panic()
+                                && !contract.may_panic()
+                            {
+                                stmts.push(vir::Stmt::Assert(
+                                    false.into(),
+                                    vir::FoldingBehaviour::Stmt,
+                                    self.encoder.error_manager().register(
+                                        term.source_info.span,
+                                        ErrorCtxt::Panic(PanicCause::Call),
+                                    ),
+                                ));
+                            }
+
                             let pre_perm_spec = replace_fake_exprs(pre_type_spec.clone());
                             assert!(!pos.is_default());
                             stmts.push(vir::Stmt::Exhale(
@@ -1983,8 +2740,11 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             }
 
             TerminatorKind::Call { .. } => {
-                // Other kind of calls?
-                unimplemented!();
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::IndirectCall,
+                    term.source_info.span,
+                    format!("{:?}", term.kind),
+                ));
             }
 
             TerminatorKind::Assert {
@@ -2013,29 +2773,34 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
 
                 // Prepare a block that encodes the branch of the failure
                 let failure_label = self.cfg_method.get_fresh_label_name();
-                let failure_block = self.cfg_method.add_block(
-                    &failure_label,
-                    vec![],
-                    vec![
-                        vir::Stmt::comment(format!("========== {} ==========", &failure_label)),
-                        vir::Stmt::comment(format!(
-                            "A Rust assertion failed: {}",
-                            msg.description()
-                        )),
-                        if self.check_panics {
-                            vir::Stmt::Assert(
-                                false.into(),
-                                vir::FoldingBehaviour::Stmt,
-                                self.encoder.error_manager().register(
-                                    term.source_info.span,
-                                    ErrorCtxt::AssertTerminator(msg.description().to_string()),
-                                ),
-                            )
-                        } else {
-                            vir::Stmt::comment("This assertion will not be checked")
-                        },
-                    ],
-                );
+                let mut failure_stmts = vec![
+                    vir::Stmt::comment(format!("========== {} ==========", &failure_label)),
+                    vir::Stmt::comment(format!("A Rust assertion failed: {}", msg.description())),
+                ];
+                if self.check_panics {
+                    let subtraction = self.get_unsigned_subtraction(location.block, cond, msg);
+                    let error_ctxt = match subtraction {
+                        Some((left, right)) => {
+                            let describe = |operand| {
+                                self.mir_encoder
+                                    .describe_operand(operand)
+                                    .unwrap_or_else(|| "..".to_string())
+                            };
+                            ErrorCtxt::UnsignedSubtractionUnderflow(describe(left), describe(right))
+                        }
+                        None => ErrorCtxt::AssertTerminator(msg.description().to_string()),
+                    };
+                    failure_stmts.extend(self.encode_panic_check(
+                        contract,
+                        term.source_info.span,
+                        error_ctxt,
+                    ));
+                } else {
+                    failure_stmts.push(vir::Stmt::comment("This assertion will not be checked"));
+                }
+                let failure_block = self
+                    .cfg_method
+                    .add_block(&failure_label, vec![], failure_stmts);
                 self.cfg_method
                     .set_successor(failure_block, Successor::Return);
 
@@ -2045,10 +2810,22 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 )
             }
 
-            TerminatorKind::Resume
-            | TerminatorKind::Yield { .. }
-            | TerminatorKind::GeneratorDrop => unimplemented!("{:?}", term.kind),
-        }
+            TerminatorKind::Resume => {
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::Unwinding,
+                    term.source_info.span,
+                    format!("{:?}", term.kind),
+                ));
+            }
+
+            TerminatorKind::Yield { .. } | TerminatorKind::GeneratorDrop => {
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::Generator,
+                    term.source_info.span,
+                    format!("{:?}", term.kind),
+                ));
+            }
+        })
     }
 
     /// Encode permissions that are implicitly carried by the given local variable.
@@ -2082,6 +2859,14 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         }
     }
 
+    /// Procedures marked with `#[open_invariant]` may temporarily violate the type invariants,
+    /// so the invariants are neither assumed nor checked at their boundaries.
+    fn has_open_invariant(&self, contract: &ProcedureContract<'tcx>) -> bool {
+        self.encoder
+            .env()
+            .has_attribute_name(contract.def_id, "open_invariant")
+    }
+
     /// Encode the precondition with three expressions:
     /// - one for the type encoding
     /// - one for the type invariants
@@ -2139,11 +2924,14 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
 
         let mut invs_spec: Vec<vir::Expr> = vec![];
 
-        for arg in contract.args.iter() {
-            invs_spec.push(self.encoder.encode_invariant_func_app(
-                self.locals.get_type(*arg),
-                self.encode_prusti_local(*arg).into(),
-            ));
+        // Procedures marked with `#[open_invariant]` do not require the type invariants.
+        if !self.has_open_invariant(contract) {
+            for arg in contract.args.iter() {
+                invs_spec.push(self.encoder.encode_invariant_func_app(
+                    self.locals.get_type(*arg),
+                    self.encode_prusti_local(*arg).into(),
+                ));
+            }
         }
 
         let mut func_spec: Vec<vir::Expr> = vec![];
@@ -2167,6 +2955,9 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 None,
             );
             //warn!("after:  {:?}", &value);
+            if let Some(ref reason) = item.reason {
+                self.encoder.error_manager().register_reason(&value, reason);
+            }
             func_spec.push(value);
         }
 
@@ -2216,6 +3007,101 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         );
     }
 
+    /// Encode, in a non-deterministic branch of `start_cfg_block`, the check that the contract of
+    /// the implementation of a trait method refines the contract declared in the trait. That is,
+    /// the precondition of the trait has to imply the one of the implementation, and the
+    /// postcondition of the implementation has to imply the one of the trait.
+    /// Returns the block in which the encoding of the procedure continues.
+    fn encode_refinement_check(
+        &mut self,
+        start_cfg_block: CfgBlockIndex,
+        contract: &ProcedureContract<'tcx>,
+        trait_contract: &ProcedureContract<'tcx>,
+    ) -> CfgBlockIndex {
+        let check_block_label = self.cfg_method.get_fresh_label_name();
+        let check_label = self.cfg_method.get_fresh_label_name();
+        let mut stmts = vec![
+            vir::Stmt::comment(format!("========== {} ==========", check_block_label)),
+            vir::Stmt::comment("Check the refinement of the trait contract"),
+        ];
+
+        // Assume the precondition of the trait
+        let (type_spec, mandatory_type_spec, invs_spec, trait_func_pre) =
+            self.encode_precondition_expr(trait_contract);
+        let mandatory_type_spec = mandatory_type_spec.into_iter().conjoin();
+        stmts.push(vir::Stmt::Inhale(type_spec.clone(), vir::FoldingBehaviour::Stmt));
+        stmts.push(vir::Stmt::Inhale(mandatory_type_spec.clone(), vir::FoldingBehaviour::Stmt));
+        stmts.push(vir::Stmt::Inhale(invs_spec, vir::FoldingBehaviour::Stmt));
+        stmts.push(vir::Stmt::Inhale(trait_func_pre, vir::FoldingBehaviour::Expr));
+        stmts.push(vir::Stmt::Label(check_label.clone()));
+
+        // Check the precondition of the implementation
+        let (_, _, _, func_pre) = self.encode_precondition_expr(contract);
+        let pre_pos = self
+            .encoder
+            .error_manager()
+            .register(self.mir.span, ErrorCtxt::AssertRefinedPrecondition);
+        stmts.push(vir::Stmt::Assert(func_pre, vir::FoldingBehaviour::Expr, pre_pos));
+
+        // Havoc the state reachable from the arguments, as done by the procedure
+        let perm_pos = self
+            .encoder
+            .error_manager()
+            .register(self.mir.span, ErrorCtxt::Unexpected);
+        stmts.push(vir::Stmt::Exhale(type_spec.clone(), perm_pos.clone()));
+        stmts.push(vir::Stmt::Exhale(mandatory_type_spec.clone(), perm_pos));
+        stmts.push(vir::Stmt::Inhale(type_spec, vir::FoldingBehaviour::Stmt));
+        stmts.push(vir::Stmt::Inhale(mandatory_type_spec, vir::FoldingBehaviour::Stmt));
+        stmts.push(vir::Stmt::Inhale(
+            self.encode_local_variable_permission(contract.returned_value),
+            vir::FoldingBehaviour::Stmt,
+        ));
+
+        // Assume the postcondition of the implementation and check the one of the trait
+        let (_, _, _, func_post, _, _) = self.encode_postcondition_expr(
+            contract,
+            &check_label,
+            &check_label,
+            None,
+            false,
+            None,
+            false,
+        );
+        stmts.push(vir::Stmt::Inhale(func_post, vir::FoldingBehaviour::Expr));
+        let (_, _, _, trait_func_post, _, _) = self.encode_postcondition_expr(
+            trait_contract,
+            &check_label,
+            &check_label,
+            None,
+            false,
+            None,
+            false,
+        );
+        let post_pos = self
+            .encoder
+            .error_manager()
+            .register(self.mir.span, ErrorCtxt::AssertRefinedPostcondition);
+        stmts.push(vir::Stmt::Assert(trait_func_post, vir::FoldingBehaviour::Expr, post_pos));
+
+        let check_block = self.cfg_method.add_block(&check_block_label, vec![], stmts);
+        self.cfg_method.set_successor(check_block, Successor::Return);
+
+        // Continue with the encoding of the procedure in a new block
+        let continue_block = self.cfg_method.add_block(
+            "start_after_refinement_check",
+            vec![],
+            vec![vir::Stmt::comment(
+                "========== start_after_refinement_check ==========",
+            )],
+        );
+        let check_var = self.cfg_method.add_fresh_local_var(vir::Type::Bool);
+        self.cfg_method.set_successor(
+            start_cfg_block,
+            Successor::GotoSwitch(vec![(check_var.into(), check_block)], continue_block),
+        );
+        continue_block
+    }
+
     /// Encode the magic wand used in the postcondition with its
     /// functional specification. Returns (lhs, rhs).
     fn encode_postcondition_magic_wand(
@@ -2312,6 +3198,27 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 assertion_rhs = assertion_rhs.remove_redundant_old();
                 lhs.push(assertion_lhs);
                 rhs.push(assertion_rhs);
+            } else if let Some(ref reborrowed_place) = contract.reborrowed_place {
+                // Infer the pledge `after_expiry(<place> == before_expiry(*result))`
+                let (place_expr, place_ty, _) = self.encode_generic_place(reborrowed_place);
+                let value_field = self.encoder.encode_value_field(place_ty);
+                let place_value = self.wrap_arguments_into_old(
+                    place_expr.field(value_field.clone()),
+                    pre_label,
+                    contract,
+                    &encoded_args,
+                );
+                let ty = self.locals.get_type(contract.returned_value);
+                let (encoded_deref, ..) = self.mir_encoder.encode_deref(encoded_return.clone(), ty);
+                let result_value = vir::Expr::labelled_old(
+                    WAND_LHS_LABEL,
+                    vir::Expr::labelled_old(post_label, encoded_deref).field(value_field),
+                );
+                debug!(
+                    "inferred pledge place_value={} result_value={}",
+                    place_value, result_value
+                );
+                rhs.push(vir::Expr::eq_cmp(place_value, result_value).remove_redundant_old());
             }
             let lhs = lhs.into_iter().conjoin();
             let rhs = rhs.into_iter().conjoin();
@@ -2420,10 +3327,12 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 }
                 Mutability::MutMutable => {
                     add_type_spec(vir::PermAmount::Write);
-                    let inv = self
-                        .encoder
-                        .encode_invariant_func_app(place_ty, old_place_expr);
-                    invs_spec.push(inv);
+                    if !self.has_open_invariant(contract) {
+                        let inv = self
+                            .encoder
+                            .encode_invariant_func_app(place_ty, old_place_expr);
+                        invs_spec.push(inv);
+                    }
                 }
             };
         }
@@ -2463,10 +3372,12 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
 
         // Encode invariant for return value
         // TODO put this in the above if?
-        invs_spec.push(self.encoder.encode_invariant_func_app(
-            self.locals.get_type(contract.returned_value),
-            encoded_return.clone(),
-        ));
+        if !self.has_open_invariant(contract) {
+            invs_spec.push(self.encoder.encode_invariant_func_app(
+                self.locals.get_type(contract.returned_value),
+                encoded_return.clone(),
+            ));
+        }
 
         // Encode functional specification
         let mut func_spec = vec![];
@@ -2481,17 +3392,119 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 None,
             );
             assertion = self.wrap_arguments_into_old(assertion, pre_label, contract, &encoded_args);
+            if let Some(ref reason) = item.reason {
+                self.encoder.error_manager().register_reason(&assertion, reason);
+            }
             func_spec.push(assertion);
         }
+        func_spec.extend(self.encode_history_invariants(contract, pre_label, &encoded_args));
+
+        (
+            type_spec.into_iter().conjoin(),
+            return_perm,
+            invs_spec.into_iter().conjoin(),
+            func_spec.into_iter().conjoin(),
+            magic_wands,
+            read_transfer,
+        )
+    }
+
+    /// Encode the check of a panic at `span`. A procedure that does not declare a panic
+    /// postcondition must not panic, so the panic has to be unreachable. Otherwise, the panic
+    /// postcondition has to hold when the procedure panics.
+    fn encode_panic_check(
+        &mut self,
+        contract: &ProcedureContract<'tcx>,
+        span: Span,
+        panic_ctxt: ErrorCtxt,
+    ) -> Vec<vir::Stmt> {
+        if !contract.may_panic() {
+            let pos = self.encoder.error_manager().register(span, panic_ctxt);
+            return vec![vir::Stmt::Assert(
+                false.into(),
+                vir::FoldingBehaviour::Stmt,
+                pos,
+            )];
+        }
+        let encoded_args: Vec<vir::Expr> = contract
+            .args
+            .iter()
+            .map(|local| self.encode_prusti_local(*local).into())
+            .collect();
+        let mut func_spec = vec![];
+        for item in contract.functional_panic_postcondition() {
+            let assertion = self.encoder.encode_assertion(
+                &item.assertion,
+                &self.mir,
+                PRECONDITION_LABEL,
+                &encoded_args,
+                None,
+                false,
+                None,
+            );
+            func_spec.push(self.wrap_arguments_into_old(
+                assertion,
+                PRECONDITION_LABEL,
+                contract,
+                &encoded_args,
+            ));
+        }
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(span, ErrorCtxt::AssertMethodPanicPostcondition);
+        let patched_func_spec =
+            self.replace_old_places_with_ghost_vars(None, func_spec.into_iter().conjoin());
+        vec![vir::Stmt::Assert(
+            patched_func_spec,
+            vir::FoldingBehaviour::Expr,
+            pos,
+        )]
+    }
 
-        (
-            type_spec.into_iter().conjoin(),
-            return_perm,
-            invs_spec.into_iter().conjoin(),
-            func_spec.into_iter().conjoin(),
-            magic_wands,
-            read_transfer,
-        )
+    /// Encode the history invariants of the type of `self`, if the procedure is a method that
+    /// takes `&mut self`.
+    fn encode_history_invariants(
+        &self,
+        contract: &ProcedureContract<'tcx>,
+        pre_label: &str,
+        encoded_args: &[vir::Expr],
+    ) -> Vec<vir::Expr> {
+        let has_self_arg = self
+            .encoder
+            .env()
+            .tcx()
+            .opt_associated_item(contract.def_id)
+            .map_or(false, |item| item.method_has_self_argument);
+        if !has_self_arg || encoded_args.is_empty() {
+            return vec![];
+        }
+        let self_ty = self.locals.get_type(contract.args[0]);
+        let adt_def = match self_ty.sty {
+            ty::TypeVariants::TyRef(_, ty, Mutability::MutMutable) => match ty.sty {
+                ty::TypeVariants::TyAdt(adt_def, _) => adt_def,
+                _ => return vec![],
+            },
+            _ => return vec![],
+        };
+        let (encoded_self, ..) = self.mir_encoder.encode_deref(encoded_args[0].clone(), self_ty);
+        self.encoder
+            .get_type_invariants(adt_def.did)
+            .iter()
+            .filter(|item| item.typ == SpecType::HistoryInvariant)
+            .map(|item| {
+                let assertion = self.encoder.encode_assertion(
+                    &item.assertion,
+                    &self.mir,
+                    pre_label,
+                    &[encoded_self.clone()],
+                    None,
+                    false,
+                    None,
+                );
+                self.wrap_arguments_into_old(assertion, pre_label, contract, encoded_args)
+            })
+            .collect()
     }
 
     /// Modelling move as simple assignment on Viper level has a consequence
@@ -2521,6 +3534,14 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 pos: vir::Position,
             ) -> vir::Expr {
                 let base = self.fold_boxed(base);
+                // Only places can be stored in ghost variables.
+                let is_operation = match *base {
+                    vir::Expr::UnaryOp(..) | vir::Expr::BinOp(..) | vir::Expr::Cond(..) => true,
+                    _ => false,
+                };
+                if is_operation && self.label == Some(&label) {
+                    return self.fold(base.push_old_to_places(&label));
+                }
                 let expr = vir::Expr::LabelledOld(label.clone(), base, pos.clone());
                 debug!(
                     "replace_old_places_with_ghost_vars({:?}, {})",
@@ -2791,11 +3812,17 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         } else {
             encoded_return
         };
+        // A shared reference returned by the procedure (e.g. `&self.value`) only gives read
+        // access to its target, which is enough to evaluate `*result` in the postcondition.
+        let return_perm_amount = match ty.sty {
+            ty::TypeVariants::TyRef(_, _, Mutability::MutImmutable) => vir::PermAmount::Read,
+            _ => vir::PermAmount::Write,
+        };
         let return_pred = self
             .mir_encoder
             .encode_place_predicate_permission(
                 encoded_return_expr.clone(),
-                vir::PermAmount::Write,
+                return_perm_amount,
             )
             .unwrap();
         let obtain_return_stmt = vir::Stmt::Obtain(return_pred, type_inv_pos.clone());
@@ -3070,6 +4097,49 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         (permissions, equalities)
     }
 
+    /// If the `Assert` terminator of a block checks the overflow of a subtraction of unsigned
+    /// integers, returns the operands of the subtraction.
+    fn get_unsigned_subtraction(
+        &self,
+        bbi: BasicBlockIndex,
+        cond: &mir::Operand<'tcx>,
+        msg: &mir::AssertMessage<'tcx>,
+    ) -> Option<(&'p mir::Operand<'tcx>, &'p mir::Operand<'tcx>)> {
+        match msg {
+            EvalErrorKind::Overflow(mir::BinOp::Sub) => {}
+            _ => return None,
+        }
+        // The condition is the overflow flag `tmp.1` of `tmp = CheckedSub(left, right)`
+        let checked_local = match *cond {
+            mir::Operand::Copy(mir::Place::Projection(box mir::Projection {
+                base: mir::Place::Local(local),
+                elem: mir::ProjectionElem::Field(..),
+            }))
+            | mir::Operand::Move(mir::Place::Projection(box mir::Projection {
+                base: mir::Place::Local(local),
+                elem: mir::ProjectionElem::Field(..),
+            })) => local,
+            _ => return None,
+        };
+        let mir = self.mir;
+        let (left, right) = mir[bbi]
+            .statements
+            .iter()
+            .rev()
+            .filter_map(|stmt| match stmt.kind {
+                mir::StatementKind::Assign(
+                    mir::Place::Local(lhs),
+                    mir::Rvalue::CheckedBinaryOp(mir::BinOp::Sub, ref left, ref right),
+                ) if lhs == checked_local => Some((left, right)),
+                _ => None,
+            })
+            .next()?;
+        match left.ty(mir, self.encoder.env().tcx()).sty {
+            ty::TypeVariants::TyUint(_) => Some((left, right)),
+            _ => None,
+        }
+    }
+
     /// Get the basic blocks that encode the specification of a loop invariant
     fn get_loop_spec_blocks(&self, loop_head: BasicBlockIndex) -> Vec<BasicBlockIndex> {
         let mut res = vec![];
@@ -3113,8 +4183,12 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                         .env()
                         .get_attr(cl_def_id, "__PRUSTI_LOOP_SPEC_ID")
                     {
-                        let spec_id: u64 = attr.parse().unwrap();
-                        spec_ids.push(SpecID::from(spec_id));
+                        // All the spec blocks of a loop, including the ones of the
+                        // `body_invariant!` in its body, share the spec id of the loop
+                        let spec_id = SpecID::from(attr.parse::<u64>().unwrap());
+                        if !spec_ids.contains(&spec_id) {
+                            spec_ids.push(spec_id);
+                        }
                     }
                 }
             }
@@ -3156,6 +4230,86 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         encoded_specs
     }
 
+    /// Infer the bounds invariant of a loop `while i < n { ...; i += 1; ... }` that has no
+    /// user-provided invariant. Returns the ghost variable that stores the value of the
+    /// counter on loop entry, the value of the counter, and the inferred invariant.
+    fn encode_inferred_loop_invariant(
+        &mut self,
+        loop_head: BasicBlockIndex,
+    ) -> Option<(vir::LocalVar, vir::Expr, vir::Expr)> {
+        if !config::infer_loop_invariants() || !self.get_loop_spec_blocks(loop_head).is_empty() {
+            return None;
+        }
+        let range_loop = self.loop_encoder.get_range_loop(loop_head)?;
+        debug!("Inferring the invariant of range loop {:?}: {:?}", loop_head, range_loop);
+        let counter = self
+            .mir_encoder
+            .eval_place(&mir::Place::Local(range_loop.counter));
+        let bound = self
+            .mir_encoder
+            .eval_place(&mir::Place::Local(range_loop.bound));
+        let entry_var = self.get_auxiliar_local_var(
+            &format!("loop_entry_{}", loop_head.index()),
+            vir::Type::Int,
+        );
+        let entry_value: vir::Expr = entry_var.clone().into();
+        // `entry <= i && (entry <= n ==> i <= n)`
+        let invariant = vir::Expr::and(
+            vir::Expr::le_cmp(entry_value.clone(), counter.clone()),
+            vir::Expr::implies(
+                vir::Expr::le_cmp(entry_value, bound.clone()),
+                vir::Expr::le_cmp(counter.clone(), bound),
+            ),
+        );
+        Some((entry_var, counter, invariant))
+    }
+
+    /// The bounds invariant of a loop `for i in a..b { ... }` over an integer range, which holds
+    /// independently of the user-provided invariant. Returns the ghost variables that store the
+    /// bounds of the range on loop entry with the values of the bounds, and the invariant.
+    fn encode_range_iterator_invariant(
+        &mut self,
+        loop_head: BasicBlockIndex,
+    ) -> Option<(Vec<(vir::LocalVar, vir::Expr)>, vir::Expr)> {
+        let iterator = self.loop_encoder.get_range_iterator(loop_head)?;
+        debug!("Encoding the invariant of range iterator {:?}: {:?}", loop_head, iterator);
+        let elem_ty = match self.mir.local_decls[iterator].ty.sty {
+            ty::TypeVariants::TyAdt(_, substs) => substs.type_at(0),
+            ref x => unreachable!("{:?}", x),
+        };
+        let start = self.mir_encoder.eval_place(
+            &mir::Place::Local(iterator).field(mir::Field::new(0), elem_ty),
+        );
+        let end = self.mir_encoder.eval_place(
+            &mir::Place::Local(iterator).field(mir::Field::new(1), elem_ty),
+        );
+        let entry_start_var = self.get_auxiliar_local_var(
+            &format!("range_start_{}", loop_head.index()),
+            vir::Type::Int,
+        );
+        let entry_end_var = self.get_auxiliar_local_var(
+            &format!("range_end_{}", loop_head.index()),
+            vir::Type::Int,
+        );
+        let entry_start: vir::Expr = entry_start_var.clone().into();
+        let entry_end: vir::Expr = entry_end_var.clone().into();
+        // `entry_start <= start && end == entry_end && (entry_start <= entry_end ==> start <= end)`
+        let invariant = vir::Expr::and(
+            vir::Expr::and(
+                vir::Expr::le_cmp(entry_start.clone(), start.clone()),
+                vir::Expr::eq_cmp(end.clone(), entry_end.clone()),
+            ),
+            vir::Expr::implies(
+                vir::Expr::le_cmp(entry_start, entry_end),
+                vir::Expr::le_cmp(start.clone(), end.clone()),
+            ),
+        );
+        Some((
+            vec![(entry_start_var, start), (entry_end_var, end)],
+            invariant,
+        ))
+    }
+
     fn encode_loop_invariant_exhale_stmts(
         &mut self,
         loop_head: BasicBlockIndex,
@@ -3173,7 +4327,9 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         }
         let (permissions, equalities) = self.encode_loop_invariant_permissions(
             loop_head, !after_loop_iteration);
-        let func_spec = self.encode_loop_invariant_specs(loop_head);
+        let mut func_spec = self.encode_loop_invariant_specs(loop_head);
+        let inferred_invariant = self.encode_inferred_loop_invariant(loop_head);
+        let range_invariant = self.encode_range_iterator_invariant(loop_head);
 
         // TODO: use different positions, and generate different error messages, for the exhale
         // before the loop and after the loop body
@@ -3211,6 +4367,28 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 ));
             }
         }
+        if let Some((entry_var, counter, invariant)) = inferred_invariant {
+            if !after_loop_iteration {
+                stmts.push(vir::Stmt::Assign(
+                    entry_var.into(),
+                    counter,
+                    vir::AssignKind::Ghost,
+                ));
+            }
+            func_spec.push(invariant);
+        }
+        if let Some((entry_values, invariant)) = range_invariant {
+            if !after_loop_iteration {
+                for (entry_var, value) in entry_values {
+                    stmts.push(vir::Stmt::Assign(
+                        entry_var.into(),
+                        value,
+                        vir::AssignKind::Ghost,
+                    ));
+                }
+            }
+            func_spec.push(invariant);
+        }
         assert!(!assert_pos.is_default());
         let obtain_predicates = permissions
             .iter()
@@ -3247,7 +4425,13 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         );
         let (permissions, equalities) = self.encode_loop_invariant_permissions(
             loop_head, after_loop);
-        let func_spec = self.encode_loop_invariant_specs(loop_head);
+        let mut func_spec = self.encode_loop_invariant_specs(loop_head);
+        if let Some((_, _, invariant)) = self.encode_inferred_loop_invariant(loop_head) {
+            func_spec.push(invariant);
+        }
+        if let Some((_, invariant)) = self.encode_range_iterator_invariant(loop_head) {
+            func_spec.push(invariant);
+        }
 
         let permission_expr = permissions.into_iter().conjoin();
         let equality_expr = equalities.into_iter().conjoin();
@@ -3279,6 +4463,101 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         vir::LocalVar::new(var_name, vir::Type::TypedRef(type_name))
     }
 
+    /// Encode the content of the string that an operand references, possibly through several
+    /// references, e.g. for an operand of type `&&str`.
+    fn encode_string_operand(&self, operand: &mir::Operand<'tcx>) -> EncodingResult<vir::Expr> {
+        if let &mir::Operand::Constant(box ref constant) = operand {
+            return self.encoder.encode_string_constant(constant);
+        }
+        let mut place = self.mir_encoder.encode_operand_place(operand).unwrap();
+        let mut ty = self.mir_encoder.get_operand_ty(operand);
+        while let ty::TypeVariants::TyRef(_, inner_ty, _) = ty.sty {
+            place = place.field(self.encoder.encode_dereference_field(inner_ty));
+            ty = inner_ty;
+        }
+        Ok(place.field(self.encoder.encode_string_field()))
+    }
+
+    /// Returns true if a call converts a `&str` into a `String`.
+    fn is_string_conversion(
+        &self,
+        args: &[mir::Operand<'tcx>],
+        destination: &Option<(mir::Place<'tcx>, mir::BasicBlock)>,
+    ) -> bool {
+        let tcx = self.encoder.env().tcx();
+        match (args, destination) {
+            (&[ref arg], &Some((ref target_place, _))) => {
+                let arg_ty = self.mir_encoder.get_operand_ty(arg);
+                let dest_ty = self.mir_encoder.encode_place(target_place).1;
+                is_string_ref_ty(tcx, arg_ty) && is_string_ty(tcx, dest_ty)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if a call of `Index::index` slices a `&str` with a `Range<usize>`.
+    fn is_substring(&self, args: &[mir::Operand<'tcx>]) -> bool {
+        let tcx = self.encoder.env().tcx();
+        match args {
+            &[ref string, ref range] => {
+                let range_path = match self.mir_encoder.get_operand_ty(range).sty {
+                    ty::TypeVariants::TyAdt(adt_def, _) => tcx.item_path_str(adt_def.did),
+                    _ => return false,
+                };
+                is_string_ref_ty(tcx, self.mir_encoder.get_operand_ty(string))
+                    && (range_path == "std::ops::Range" || range_path == "core::ops::Range")
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if the only argument of a call is an integer range, or a mutable reference
+    /// to an integer range if `by_mut_ref` is set.
+    fn is_range_argument(&self, args: &[mir::Operand<'tcx>], by_mut_ref: bool) -> bool {
+        let tcx = self.encoder.env().tcx();
+        match args {
+            &[ref arg] => match self.mir_encoder.get_operand_ty(arg).sty {
+                ty::TypeVariants::TyRef(_, ty, Mutability::MutMutable) if by_mut_ref => {
+                    is_integer_range_ty(tcx, ty)
+                }
+                _ if by_mut_ref => false,
+                _ => is_integer_range_ty(tcx, self.mir_encoder.get_operand_ty(arg)),
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns true if the only argument of a call is an iterator over a slice.
+    fn is_slice_iter_argument(&self, args: &[mir::Operand<'tcx>]) -> bool {
+        match args {
+            &[ref arg] => is_slice_iter_ty(
+                self.encoder.env().tcx(),
+                self.mir_encoder.get_operand_ty(arg),
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns true if the only argument of a call is a shared reference to an `Rc` or an `Arc`.
+    fn is_rc_argument(&self, args: &[mir::Operand<'tcx>]) -> bool {
+        let tcx = self.encoder.env().tcx();
+        match args {
+            &[ref arg] => match self.mir_encoder.get_operand_ty(arg).sty {
+                ty::TypeVariants::TyRef(_, ty, Mutability::MutImmutable) => is_rc_ty(tcx, ty),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns the place pointed to by a reference operand, and the type of that place.
+    fn encode_deref_operand(&self, operand: &mir::Operand<'tcx>) -> (vir::Expr, ty::Ty<'tcx>) {
+        let ref_place = self.mir_encoder.encode_operand_place(operand).unwrap();
+        let ref_ty = self.mir_encoder.get_operand_ty(operand);
+        let (place, ty, _) = self.mir_encoder.encode_deref(ref_place, ref_ty);
+        (place, ty)
+    }
+
     /// Returns
     /// - `vir::Expr`: the place of the projection;
     /// - `ty::Ty<'tcx>`: the type of the place;
@@ -3405,6 +4684,26 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             lhs, operand, location
         );
         let stmts = match operand {
+            &mir::Operand::Move(ref place) | &mir::Operand::Copy(ref place)
+                if utils::is_static_place(place) =>
+            {
+                // The value of an immutable `static` item is a constant
+                let ty = self.mir_encoder.get_operand_ty(operand);
+                let field = self.encoder.encode_value_field(ty);
+                let mut stmts = self.prepare_assign_target(
+                    lhs.clone(),
+                    field.clone(),
+                    location,
+                    vir::AssignKind::Copy,
+                );
+                stmts.push(vir::Stmt::Assign(
+                    lhs.clone().field(field),
+                    self.mir_encoder.encode_operand_expr(operand),
+                    vir::AssignKind::Copy,
+                ));
+                stmts
+            }
+
             &mir::Operand::Move(ref place) => {
                 let (src, ty, _) = self.mir_encoder.encode_place(place);
                 let mut stmts = match ty.sty {
@@ -3472,6 +4771,29 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 stmts
             }
 
+            &mir::Operand::Constant(box ref constant)
+                if is_string_ref_ty(self.encoder.env().tcx(), constant.ty) =>
+            {
+                // A string literal is a reference to a new string with the content of the literal
+                let ref_field = self.encoder.encode_value_field(constant.ty);
+                let mut stmts = self.prepare_assign_target(
+                    lhs.clone(),
+                    ref_field.clone(),
+                    location,
+                    vir::AssignKind::Move,
+                );
+                let string = lhs.clone().field(ref_field);
+                stmts.extend(self.encode_havoc_and_allocation(&string));
+                match self.encoder.encode_string_constant(constant) {
+                    Ok(content) => stmts.push(vir::Stmt::Inhale(
+                        vir::Expr::eq_cmp(string.field(self.encoder.encode_string_field()), content),
+                        vir::FoldingBehaviour::Expr,
+                    )),
+                    Err(error) => self.encoder.register_encoding_error(error),
+                }
+                stmts
+            }
+
             &mir::Operand::Constant(box mir::Constant {
                 ty, ref literal, ..
             }) => {
@@ -3639,11 +4961,12 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             Vec::with_capacity(2)
         };
         // Initialize lhs.field
+        let encoded_lhs_value = encoded_lhs
+            .clone()
+            .field(value_field)
+            .field(value_field_value);
         stmts.push(vir::Stmt::Assign(
-            encoded_lhs
-                .clone()
-                .field(value_field)
-                .field(value_field_value),
+            encoded_lhs_value.clone(),
             encoded_value,
             vir::AssignKind::Copy,
         ));
@@ -3652,6 +4975,24 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             encoded_check,
             vir::AssignKind::Copy,
         ));
+        // The type predicates bound the values of integers, so if the overflow checks are
+        // disabled only for this procedure the absence of overflows is assumed
+        let assume_no_overflow = config::check_binary_operations()
+            && !self.encoder.check_overflows(self.proc_def_id);
+        match op {
+            mir::BinOp::Add | mir::BinOp::Sub | mir::BinOp::Mul if assume_no_overflow => {
+                let bounds = self
+                    .encoder
+                    .encode_type_bounds(&encoded_lhs_value, operand_ty);
+                if !bounds.is_empty() {
+                    stmts.push(vir::Stmt::Inhale(
+                        bounds.into_iter().conjoin(),
+                        vir::FoldingBehaviour::Expr,
+                    ));
+                }
+            }
+            _ => {}
+        }
         stmts
     }
 
@@ -3681,7 +5022,8 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         encoded_lhs: vir::Expr,
         ty: ty::Ty<'tcx>,
         location: mir::Location,
-    ) -> Vec<vir::Stmt> {
+        stmt: &mir::Statement<'tcx>,
+    ) -> EncodingResult<Vec<vir::Stmt>> {
         trace!(
             "[enter] encode_assign_nullary_op(op={:?}, op_ty={:?})",
             op,
@@ -3704,9 +5046,13 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
                 stmts.extend(self.encode_havoc_and_allocation(&box_content));
 
                 // Leave `box_content` uninitialized
-                stmts
+                Ok(stmts)
             }
-            mir::NullOp::SizeOf => unimplemented!(),
+            mir::NullOp::SizeOf => Err(SpannedEncodingError::unsupported(
+                UnsupportedFeature::SizeOf,
+                stmt.source_info.span,
+                format!("{:?}", stmt),
+            )),
         }
     }
 
@@ -3761,7 +5107,8 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         location: mir::Location,
         encoded_lhs: vir::Expr,
         ty: ty::Ty<'tcx>,
-    ) -> Vec<vir::Stmt> {
+        stmt: &mir::Statement<'tcx>,
+    ) -> EncodingResult<Vec<vir::Stmt>> {
         trace!(
             "[enter] encode_assign_ref(mir_borrow_kind={:?}, place={:?}, location={:?})",
             mir_borrow_kind,
@@ -3772,7 +5119,13 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         let loan = self.polonius_info.get_loan_at_location(location);
         let vir_assign_kind = match mir_borrow_kind {
             mir::BorrowKind::Shared => vir::AssignKind::SharedBorrow(loan),
-            mir::BorrowKind::Unique => unimplemented!(),
+            mir::BorrowKind::Unique => {
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::UniqueBorrow,
+                    stmt.source_info.span,
+                    format!("{:?}", stmt),
+                ));
+            }
             mir::BorrowKind::Mut { .. } => vir::AssignKind::MutableBorrow(loan),
         };
         // Initialize ref_var.ref_field
@@ -3793,7 +5146,154 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         debug!("Current loc {:?} has label {}", location, label);
         self.label_after_location.insert(location, label.clone());
         stmts.push(vir::Stmt::Label(label.clone()));
-        stmts
+        Ok(stmts)
+    }
+
+    /// Encode the value in the pre-state of the argument of an `old(..)` call in the body.
+    ///
+    /// The compiler copies the argument into temporary variables before the call, which are
+    /// traced back to the place that is copied. That place has to be rooted in a formal argument,
+    /// because the other local variables do not exist in the pre-state.
+    fn encode_old_argument(
+        &self,
+        operand: &mir::Operand<'tcx>,
+        location: mir::Location,
+        term: &mir::Terminator<'tcx>,
+    ) -> EncodingResult<vir::Expr> {
+        let mut place = match operand {
+            &mir::Operand::Copy(ref place) | &mir::Operand::Move(ref place) => place.clone(),
+            &mir::Operand::Constant(..) => {
+                return Ok(self.mir_encoder.encode_operand_expr(operand));
+            }
+        };
+        for stmt in self.mir[location.block].statements.iter().rev() {
+            if let mir::StatementKind::Assign(ref lhs, ref rhs) = stmt.kind {
+                if *lhs != place {
+                    continue;
+                }
+                match rhs {
+                    &mir::Rvalue::Use(mir::Operand::Copy(ref rhs_place))
+                    | &mir::Rvalue::Use(mir::Operand::Move(ref rhs_place)) => {
+                        place = rhs_place.clone();
+                    }
+                    _ => break,
+                }
+            }
+        }
+        let (_, place_ty, _) = self.mir_encoder.encode_place(&place);
+        let is_argument = self
+            .mir
+            .args_iter()
+            .any(|arg| arg == place_root_local(&place));
+        if !is_argument || !place_ty.is_primitive() {
+            return Err(SpannedEncodingError::unsupported(
+                UnsupportedFeature::OldExpression,
+                term.source_info.span,
+                format!("{:?}", place),
+            ));
+        }
+        Ok(vir::Expr::labelled_old(
+            PRECONDITION_LABEL,
+            self.mir_encoder.eval_place(&place),
+        ))
+    }
+
+    /// Returns the place of a `fold!(..)` or `unfold!(..)` ghost statement.
+    ///
+    /// The compiler stores a shared reference to the place in a temporary variable before the
+    /// call, which is traced back to the borrowed place. A shared borrow leaves the permissions
+    /// with the borrowed place, so the predicate is folded or unfolded there.
+    fn get_ghost_statement_place(
+        &self,
+        operand: &mir::Operand<'tcx>,
+        location: mir::Location,
+        term: &mir::Terminator<'tcx>,
+    ) -> EncodingResult<mir::Place<'tcx>> {
+        let mut place = match operand {
+            &mir::Operand::Copy(ref place) | &mir::Operand::Move(ref place) => place.clone(),
+            &mir::Operand::Constant(..) => {
+                return Err(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::FoldUnfoldArgument,
+                    term.source_info.span.source_callsite(),
+                    format!("{:?}", operand),
+                ));
+            }
+        };
+        for stmt in self.mir[location.block].statements.iter().rev() {
+            if let mir::StatementKind::Assign(ref lhs, ref rhs) = stmt.kind {
+                if *lhs != place {
+                    continue;
+                }
+                match rhs {
+                    &mir::Rvalue::Use(mir::Operand::Copy(ref rhs_place))
+                    | &mir::Rvalue::Use(mir::Operand::Move(ref rhs_place)) => {
+                        place = rhs_place.clone();
+                    }
+                    &mir::Rvalue::Ref(_, mir::BorrowKind::Shared, ref borrowed_place) => {
+                        return Ok(borrowed_place.clone());
+                    }
+                    _ => break,
+                }
+            }
+        }
+        Err(SpannedEncodingError::unsupported(
+            UnsupportedFeature::FoldUnfoldArgument,
+            term.source_info.span.source_callsite(),
+            format!("{:?}", place),
+        ))
+    }
+
+    /// Is the place reached by dereferencing a shared reference, so that only read permission
+    /// is available?
+    fn is_behind_shared_reference(&self, place: &mir::Place<'tcx>) -> bool {
+        match place {
+            &mir::Place::Projection(box mir::Projection { ref base, ref elem }) => {
+                let is_shared_deref = match elem {
+                    &mir::ProjectionElem::Deref => match self.mir_encoder.encode_place(base).1.sty {
+                        ty::TypeVariants::TyRef(_, _, Mutability::MutImmutable) => true,
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                is_shared_deref || self.is_behind_shared_reference(base)
+            }
+            _ => false,
+        }
+    }
+
+    /// Encode the drop of a value at the exit of its scope.
+    ///
+    /// The `drop` method of a type that implements `Drop` assumes the invariant of the type, so
+    /// the invariant has to hold whenever such a value is dropped. The values that have been
+    /// moved out are not dropped, and the compiler rejects partial moves out of these types.
+    /// The drops on unwinding paths need no encoding, because panics are either checked to be
+    /// unreachable (see `CHECK_PANICS`) or not verified at all.
+    fn encode_drop(
+        &self,
+        place: &mir::Place<'tcx>,
+        location: mir::Location,
+        span: Span,
+    ) -> Vec<vir::Stmt> {
+        let (encoded_place, ty, _) = self.mir_encoder.encode_place(place);
+        let has_destructor = match ty.ty_adt_def() {
+            Some(adt_def) => adt_def.has_dtor(self.encoder.env().tcx()),
+            None => false,
+        };
+        if !has_destructor || !self.init_info.is_vir_place_accessible(&encoded_place, location) {
+            return vec![];
+        }
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(span, ErrorCtxt::DropTypeInvariant);
+        vec![
+            vir::Stmt::comment(format!("Drop of {:?}", place)),
+            vir::Stmt::Assert(
+                self.encoder.encode_invariant_func_app(ty, encoded_place),
+                vir::FoldingBehaviour::Expr,
+                pos,
+            ),
+        ]
     }
 
     fn encode_cast(
@@ -3803,14 +5303,55 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         encoded_lhs: vir::Expr,
         ty: ty::Ty<'tcx>,
         location: mir::Location,
-    ) -> Vec<vir::Stmt> {
+    ) -> EncodingResult<Vec<vir::Stmt>> {
         trace!(
             "[enter] encode_cast(operand={:?}, dst_ty={:?})",
             operand,
             dst_ty
         );
-        let encoded_val = self.mir_encoder.encode_cast_expr(operand, dst_ty);
-        self.encode_copy_value_assign(encoded_lhs, encoded_val, ty, location)
+        let span = self.mir.source_info(location).span;
+        let mut stmts = vec![];
+        let encoded_val = match self.mir_encoder.encode_cast_check(operand, dst_ty, span)? {
+            Some(check) => {
+                let pos = self.encoder.error_manager().register(span, ErrorCtxt::IntegerCast);
+                stmts.push(vir::Stmt::Assert(check, vir::FoldingBehaviour::Expr, pos));
+                // The value is in range, so the cast does not change it
+                self.mir_encoder.encode_operand_expr(operand)
+            }
+            None => self.mir_encoder.encode_cast_expr(operand, dst_ty, span)?,
+        };
+        stmts.extend(self.encode_copy_value_assign(encoded_lhs, encoded_val, ty, location));
+        Ok(stmts)
+    }
+
+    /// Encode the coercion of a box to a boxed trait object (e.g. of a `Box<Circle>` to a
+    /// `Box<dyn Shape>`). The predicate of a trait object is abstract, so the boxed value is
+    /// moved into an opaque object that can only be used through the contracts of the trait.
+    fn encode_assign_boxed_trait_object(
+        &mut self,
+        operand: &mir::Operand<'tcx>,
+        encoded_lhs: vir::Expr,
+        location: mir::Location,
+    ) -> Vec<vir::Stmt> {
+        trace!(
+            "[enter] encode_assign_boxed_trait_object(operand={:?}, encoded_lhs={:?})",
+            operand,
+            encoded_lhs
+        );
+        let mut stmts = self.encode_havoc_and_allocation(&encoded_lhs);
+        if let &mir::Operand::Move(ref place) = operand {
+            let (encoded_place, _, _) = self.mir_encoder.encode_place(place);
+            let pos = self.encoder.error_manager().register(
+                self.mir.source_info(location).span,
+                ErrorCtxt::Unexpected,
+            );
+            let permission = self
+                .mir_encoder
+                .encode_place_predicate_permission(encoded_place, vir::PermAmount::Write)
+                .unwrap();
+            stmts.push(vir::Stmt::Exhale(permission, pos));
+        }
+        stmts
     }
 
     pub fn get_auxiliar_local_var(&mut self, suffix: &str, vir_type: vir::Type) -> vir::LocalVar {
@@ -3994,7 +5535,9 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             ty::TypeVariants::TyTuple(elems) => {
                 self.encode_deep_copy_tuple(src, dst, elems)
             }
-            ty::TypeVariants::TyParam(_) => {
+            ty::TypeVariants::TyParam(_)
+            | ty::TypeVariants::TyAnon(..)
+            | ty::TypeVariants::TyProjection(..) => {
                 let mut stmts = self.encode_havoc_and_allocation(&dst.clone());
                 let eq = self.encoder.encode_memory_eq_func_app(
                     src, dst, self_ty, vir::Position::default());
@@ -4019,6 +5562,23 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
             "[enter] encode_assign_aggregate({:?}, {:?})",
             aggregate, operands
         );
+        // With the struct update syntax (e.g. `s = S { x: 1, ..s }`) the operands may read the
+        // remaining fields from the place that is being assigned. In this case we build the
+        // value in a fresh local first, so that the fields are not havocked before being read.
+        let reads_dst = operands.iter().any(|operand| match operand {
+            &mir::Operand::Copy(ref place) | &mir::Operand::Move(ref place) => {
+                let (src, _, _) = self.mir_encoder.encode_place(place);
+                src.has_prefix(dst)
+            }
+            &mir::Operand::Constant(..) => false,
+        });
+        if reads_dst {
+            let tmp_local = self.locals.get_fresh(ty);
+            let tmp = vir::Expr::local(self.encode_prusti_local(tmp_local));
+            let mut stmts = self.encode_assign_aggregate(&tmp, ty, aggregate, operands, location);
+            stmts.push(vir::Stmt::Assign(dst.clone(), tmp, vir::AssignKind::Move));
+            return stmts;
+        }
         let mut stmts = self.encode_havoc_and_allocation(dst);
         // Initialize values
         match aggregate {
@@ -4103,6 +5663,39 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         }
     }
 
+    /// Check that the statement uses `static` items only to read primitive fields of immutable
+    /// items, which are encoded as constants.
+    fn check_static_uses(
+        &self,
+        stmt: &mir::Statement<'tcx>,
+        location: mir::Location,
+    ) -> EncodingResult<()> {
+        let mut collector = StaticPlaceCollector { places: vec![] };
+        collector.visit_statement(location.block, stmt, location);
+        for (place, is_read) in collector.places {
+            if is_read && self.mir_encoder.encode_static_place_value(&place).is_some() {
+                continue;
+            }
+            let is_mutable = match place_static_root(&place) {
+                Some(def_id) => {
+                    self.encoder.env().tcx().is_static(def_id) == Some(Mutability::MutMutable)
+                }
+                None => false,
+            };
+            let feature = if is_mutable {
+                UnsupportedFeature::MutableStatic
+            } else {
+                UnsupportedFeature::StaticPlace
+            };
+            return Err(SpannedEncodingError::unsupported(
+                feature,
+                stmt.source_info.span,
+                format!("{:?}", stmt.kind),
+            ));
+        }
+        Ok(())
+    }
+
     fn check_vir(&self) {
         let mut encoded_mir_locals = HashSet::new();
         for local in self.mir.local_decls.indices() {
@@ -4110,3 +5703,37 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ProcedureEncoder<'p, 'v, 'r, 'a, 'tcx
         }
     }
 }
+
+/// Returns the `static` item in which the place is rooted, if any.
+fn place_static_root(place: &mir::Place) -> Option<DefId> {
+    match place {
+        mir::Place::Local(_) => None,
+        mir::Place::Static(box mir::Static { def_id, .. }) => Some(*def_id),
+        mir::Place::Projection(box mir::Projection { base, .. }) => place_static_root(base),
+    }
+}
+
+/// Collects the places rooted in `static` items that are used by a statement, together with
+/// whether they are just read (i.e. copied or moved).
+struct StaticPlaceCollector<'tcx> {
+    places: Vec<(mir::Place<'tcx>, bool)>,
+}
+
+impl<'tcx> Visitor<'tcx> for StaticPlaceCollector<'tcx> {
+    fn visit_place(
+        &mut self,
+        place: &mir::Place<'tcx>,
+        context: PlaceContext<'tcx>,
+        location: mir::Location,
+    ) {
+        if utils::is_static_place(place) {
+            let is_read = match context {
+                PlaceContext::Copy | PlaceContext::Move => true,
+                _ => false,
+            };
+            self.places.push((place.clone(), is_read));
+        } else {
+            self.super_place(place, context, location);
+        }
+    }
+}
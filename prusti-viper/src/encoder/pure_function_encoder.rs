@@ -8,22 +8,32 @@ use encoder::borrows::{compute_procedure_contract, ProcedureContract};
 use encoder::builtin_encoder::BuiltinFunctionKind;
 use encoder::error_manager::ErrorCtxt;
 use encoder::error_manager::PanicCause;
+use encoder::errors::{SpannedEncodingError, UnsupportedFeature};
 use encoder::foldunfold;
+use encoder::ghost_encoder::is_ghost_ty;
 use encoder::mir_encoder::MirEncoder;
 use encoder::mir_encoder::{PRECONDITION_LABEL, WAND_LHS_LABEL};
+use encoder::rc_encoder::is_rc_ty;
+use encoder::snapshot_encoder::SnapshotEncoder;
 use encoder::mir_interpreter::{
     run_backward_interpretation, BackwardMirInterpreter, MultiExprBackwardInterpreterState,
 };
+use encoder::string_encoder::{is_string_ref_ty, is_string_ty, StringEncoder};
+use encoder::termination::add_termination_checks;
+use encoder::type_encoder::{compute_discriminant_values, is_ordering_ty};
 use encoder::vir;
 use encoder::vir::ExprIterator;
 use encoder::Encoder;
 use prusti_interface::config;
+use prusti_interface::environment::get_panic_message_blocks;
 use prusti_interface::specifications::SpecificationSet;
-use rustc::hir;
 use rustc::hir::def_id::DefId;
 use rustc::mir;
 use rustc::ty;
+use rustc::ty::subst::Subst;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use syntax::codemap::Span;
 
 pub struct PureFunctionEncoder<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> {
     encoder: &'p Encoder<'v, 'r, 'a, 'tcx>,
@@ -62,13 +72,22 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionEncoder<'p, 'v, 'r, 'a, '
 
         let state = run_backward_interpretation(self.mir, &self.interpreter)
             .expect(&format!("Procedure {:?} contains a loop", self.proc_def_id));
-        let body_expr = state.into_expressions().remove(0);
+        let mut body_expr = state.into_expressions().remove(0);
+        if config::check_pure_function_termination() {
+            let formal_args: Vec<_> = self
+                .mir
+                .args_iter()
+                .map(|arg| self.encode_local(arg))
+                .collect();
+            body_expr =
+                add_termination_checks(self.encoder, self.proc_def_id, &formal_args, body_expr);
+        }
         debug!(
             "Pure function {} has been encoded with expr: {}",
             function_name, body_expr
         );
-        let subst_strings = self.encoder.type_substitution_strings();
-        let patched_body_expr = body_expr.patch_types(&subst_strings);
+        let type_substs = self.encoder.type_substitution();
+        let patched_body_expr = body_expr.patch_types(&type_substs);
         patched_body_expr
     }
 
@@ -90,7 +109,16 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionEncoder<'p, 'v, 'r, 'a, '
             state.substitute_place(&target_place, new_place);
         }
 
-        let body_expr = state.into_expressions().remove(0);
+        let mut body_expr = state.into_expressions().remove(0);
+        if config::check_pure_function_termination() {
+            let formal_args: Vec<_> = self
+                .mir
+                .args_iter()
+                .map(|arg| self.encode_local(arg))
+                .collect();
+            body_expr =
+                add_termination_checks(self.encoder, self.proc_def_id, &formal_args, body_expr);
+        }
         debug!(
             "Pure function {} has been encoded with expr: {}",
             function_name, body_expr
@@ -124,9 +152,9 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionEncoder<'p, 'v, 'r, 'a, '
         // TODO: Clean up code duplication:
         //let contract = self.encoder.get_procedure_contract_for_def(self.proc_def_id);
         let contract = {
-            let opt_fun_spec = self.encoder.get_spec_by_def_id(self.proc_def_id);
+            let opt_fun_spec = self.encoder.get_procedure_spec(self.proc_def_id);
             let fun_spec = match opt_fun_spec {
-                Some(fun_spec) => fun_spec.clone(),
+                Some(fun_spec) => fun_spec,
                 None => {
                     debug!("Procedure {:?} has no specification", self.proc_def_id);
                     SpecificationSet::Procedure(vec![], vec![])
@@ -141,12 +169,15 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionEncoder<'p, 'v, 'r, 'a, '
             );
             contract.to_def_site_contract()
         };
-        let subst_strings = self.encoder.type_substitution_strings();
+        let type_substs = self.encoder.type_substitution();
 
         let (type_precondition, func_precondition) = self.encode_precondition_expr(&contract);
-        let patched_type_precondition = type_precondition.patch_types(&subst_strings);
+        let patched_type_precondition = type_precondition.patch_types(&type_substs);
         let mut precondition = vec![patched_type_precondition, func_precondition];
         let mut postcondition = vec![self.encode_postcondition_expr(&contract)];
+        if is_bodyless {
+            postcondition.extend(self.encode_string_method_postcondition());
+        }
 
         let formal_args: Vec<_> = self
             .mir
@@ -157,11 +188,14 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionEncoder<'p, 'v, 'r, 'a, '
                 let var_type = self
                     .encoder
                     .encode_value_type(self.encoder.resolve_typaram(mir_type));
-                let var_type = var_type.patch(&subst_strings);
+                let var_type = var_type.patch(&type_substs);
                 vir::LocalVar::new(var_name, var_type)
             })
             .collect();
         let return_type = self.encode_function_return_type();
+        if is_bodyless {
+            postcondition.extend(self.encode_viper_domain_postcondition(&formal_args));
+        }
 
         let res_value_range_pos = self.encoder.error_manager().register(
             self.mir.span,
@@ -236,9 +270,9 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionEncoder<'p, 'v, 'r, 'a, '
     ) -> (vir::Expr, vir::Expr) {
         let type_spec = contract.args.iter().flat_map(|&local| {
             let local_ty = self.interpreter.mir_encoder().get_local_ty(local.into());
-            let fraction = if let ty::TypeVariants::TyRef(_, _, hir::Mutability::MutImmutable) =
-                local_ty.sty
-            {
+            // Pure functions cannot modify their arguments, so `&mut` references are
+            // treated as read-only snapshots.
+            let fraction = if let ty::TypeVariants::TyRef(..) = local_ty.sty {
                 vir::PermAmount::Read
             } else {
                 vir::PermAmount::Write
@@ -257,7 +291,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionEncoder<'p, 'v, 'r, 'a, '
             .collect();
         for item in contract.functional_precondition() {
             debug!("Encode spec item: {:?}", item);
-            func_spec.push(self.encoder.encode_assertion(
+            let assertion = self.encoder.encode_assertion(
                 &item.assertion,
                 &self.mir,
                 &"",
@@ -265,7 +299,11 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionEncoder<'p, 'v, 'r, 'a, '
                 None,
                 true,
                 None,
-            ));
+            );
+            if let Some(ref reason) = item.reason {
+                self.encoder.error_manager().register_reason(&assertion, reason);
+            }
+            func_spec.push(assertion);
         }
 
         (
@@ -316,6 +354,56 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionEncoder<'p, 'v, 'r, 'a, '
             .set_default_pos(postcondition_pos)
     }
 
+    /// The postcondition of a trusted pure function that specifies a method of strings in the
+    /// built-in specifications, which relates its result to the model of strings.
+    fn encode_string_method_postcondition(&self) -> Option<vir::Expr> {
+        let path = self.encoder.env().get_extern_spec_path(self.proc_def_id)?;
+        let string_len = || {
+            let string_arg = self.encode_local(self.mir.args_iter().next().unwrap());
+            let string = vir::Expr::local(string_arg).field(self.encoder.encode_string_field());
+            StringEncoder::new().encode_len(string)
+        };
+        let result = match path.as_str() {
+            "str::len" | "std::string::String::len" => string_len(),
+            "str::is_empty" | "std::string::String::is_empty" => {
+                vir::Expr::eq_cmp(string_len(), 0.into())
+            }
+            _ => return None,
+        };
+        let pure_fn_return_variable =
+            vir::LocalVar::new("__result", self.encode_function_return_type());
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(self.mir.span, ErrorCtxt::GenericExpression);
+        Some(vir::Expr::eq_cmp(pure_fn_return_variable.into(), result).set_default_pos(pos))
+    }
+
+    /// The postcondition of a trusted pure function declared in a user-supplied Viper domain
+    /// with `#[viper_domain]`, which makes it equal to its domain function.
+    fn encode_viper_domain_postcondition(
+        &self,
+        formal_args: &[vir::LocalVar],
+    ) -> Option<vir::Expr> {
+        let domain_function = self.encoder.encode_viper_domain_function(
+            self.proc_def_id,
+            &self.encode_function_name(),
+            formal_args.to_vec(),
+            self.encode_function_return_type(),
+        )?;
+        let args = formal_args.iter().cloned().map(vir::Expr::local).collect();
+        let pure_fn_return_variable =
+            vir::LocalVar::new("__result", self.encode_function_return_type());
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(self.mir.span, ErrorCtxt::GenericExpression);
+        Some(
+            vir::Expr::eq_cmp(pure_fn_return_variable.into(), domain_function.apply(args))
+                .set_default_pos(pos),
+        )
+    }
+
     fn encode_local(&self, local: mir::Local) -> vir::LocalVar {
         let var_name = self.interpreter.mir_encoder().encode_local_var_name(local);
         let var_type = self
@@ -344,6 +432,8 @@ pub(super) struct PureFunctionBackwardInterpreter<'p, 'v: 'p, 'r: 'v, 'a: 'r, 't
     /// when to a undefined function calls. This distinction allows overflow checks to be checked
     /// on the caller side and assumed on the definition side.
     is_encoding_assertion: bool,
+    /// The blocks that only compute the message of a panic, which are not encoded.
+    panic_message_blocks: HashSet<mir::BasicBlock>,
 }
 
 /// XXX: This encoding works backward, but there is the risk of generating expressions whose length
@@ -363,6 +453,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionBackwardInterpreter<'p, '
             mir_encoder: MirEncoder::new_with_namespace(encoder, mir, def_id, namespace.clone()),
             namespace,
             is_encoding_assertion,
+            panic_message_blocks: get_panic_message_blocks(encoder.env().tcx(), mir),
         }
     }
 
@@ -370,6 +461,198 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> PureFunctionBackwardInterpreter<'p, '
         &self.mir_encoder
     }
 
+    /// Returns the place of an argument of a built-in method, together with its type.
+    /// The argument can be passed either by value or by reference.
+    fn encode_referenced_operand(&self, operand: &mir::Operand<'tcx>) -> (vir::Expr, ty::Ty<'tcx>) {
+        let place = self.mir_encoder.encode_operand_place(operand).unwrap();
+        let ty = self.mir_encoder.get_operand_ty(operand);
+        match ty.sty {
+            ty::TypeVariants::TyRef(_, inner_ty, _) => {
+                let deref_field = self.encoder.encode_dereference_field(inner_ty);
+                (place.field(deref_field), inner_ty)
+            }
+            _ => (place, ty),
+        }
+    }
+
+    /// Encode the content of the string that an operand references, possibly through several
+    /// references, e.g. for an operand of type `&&str`.
+    fn encode_string_operand(&self, operand: &mir::Operand<'tcx>) -> vir::Expr {
+        let mut place = self.mir_encoder.encode_operand_place(operand).unwrap();
+        let mut ty = self.mir_encoder.get_operand_ty(operand);
+        while let ty::TypeVariants::TyRef(_, inner_ty, _) = ty.sty {
+            place = place.field(self.encoder.encode_dereference_field(inner_ty));
+            ty = inner_ty;
+        }
+        place.field(self.encoder.encode_string_field())
+    }
+
+    /// Returns true if the only argument of a call is a shared reference to an `Rc` or an `Arc`.
+    fn is_rc_argument(&self, args: &[mir::Operand<'tcx>]) -> bool {
+        let tcx = self.encoder.env().tcx();
+        match args {
+            &[ref arg] => match self.mir_encoder.get_operand_ty(arg).sty {
+                ty::TypeVariants::TyRef(_, ty, _) => is_rc_ty(tcx, ty),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Returns true if a call of `PartialEq::eq` (or `ne`) compares two strings, possibly
+    /// behind references, e.g. a `&str` with a `String`.
+    fn is_string_eq(&self, substs: &ty::subst::Substs<'tcx>) -> bool {
+        let tcx = self.encoder.env().tcx();
+        let is_string = |mut ty: ty::Ty<'tcx>| {
+            while let ty::TypeVariants::TyRef(_, inner_ty, _) = ty.sty {
+                ty = inner_ty;
+            }
+            is_string_ty(tcx, ty)
+        };
+        is_string(substs.type_at(0)) && is_string(substs.type_at(1))
+    }
+
+    /// Returns true if a call of `PartialEq::eq` (or `ne`) with the given type substitutions
+    /// can be encoded as the equality of the snapshots of the two operands. This is the case
+    /// when both operands have the same type and `PartialEq` is derived for the type and,
+    /// recursively, for the types of all its fields.
+    fn is_structural_eq(&self, def_id: DefId, substs: &ty::subst::Substs<'tcx>) -> bool {
+        let self_ty = substs.type_at(0);
+        self_ty == substs.type_at(1) && self.has_structural_eq(def_id, self_ty)
+    }
+
+    /// Returns the pure `Ord::cmp` method that can encode a call of a comparison method of
+    /// `PartialOrd` (e.g. `lt`) with the given type substitutions. This is the case when both
+    /// operands have the same type, whose `PartialOrd` implementation does not override the
+    /// comparison method and whose `Ord` implementation marks `cmp` as `#[pure]`.
+    fn get_pure_cmp(
+        &self,
+        def_id: DefId,
+        substs: &'tcx ty::subst::Substs<'tcx>,
+    ) -> Option<DefId> {
+        let self_ty = substs.type_at(0);
+        if self_ty != substs.type_at(1) {
+            return None;
+        }
+        let env = self.encoder.env();
+        let is_overridden = env
+            .resolve_method_call(def_id, substs)
+            .map_or(false, |(callee_def_id, _)| callee_def_id != def_id);
+        if is_overridden {
+            return None;
+        }
+        let cmp_def_id =
+            env.find_local_trait_impl_method(&["std::cmp::Ord", "core::cmp::Ord"], "cmp", self_ty)?;
+        if env.has_attribute_name(cmp_def_id, "pure") {
+            Some(cmp_def_id)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the ghost type (e.g. `Seq<u32>`) of which the called function is an inherent
+    /// method, if any.
+    fn get_ghost_method_self_ty(
+        &self,
+        def_id: DefId,
+        substs: &'tcx ty::subst::Substs<'tcx>,
+    ) -> Option<ty::Ty<'tcx>> {
+        let tcx = self.encoder.env().tcx();
+        let impl_def_id = tcx.impl_of_method(def_id)?;
+        if tcx.trait_id_of_impl(impl_def_id).is_some() {
+            return None;
+        }
+        let self_ty = self
+            .encoder
+            .resolve_typaram(tcx.type_of(impl_def_id).subst(tcx, substs));
+        if is_ghost_ty(tcx, self_ty) {
+            Some(self_ty)
+        } else {
+            None
+        }
+    }
+
+    fn has_structural_eq(&self, def_id: DefId, ty: ty::Ty<'tcx>) -> bool {
+        match ty.sty {
+            _ if is_string_ty(self.encoder.env().tcx(), ty) => true,
+
+            ty::TypeVariants::TyBool
+            | ty::TypeVariants::TyInt(_)
+            | ty::TypeVariants::TyUint(_)
+            | ty::TypeVariants::TyChar => true,
+
+            ty::TypeVariants::TyAdt(adt_def, substs)
+                if !adt_def.is_box() && !adt_def.variants.is_empty() =>
+            {
+                let tcx = self.encoder.env().tcx();
+                self.encoder.env().has_derived_impl(def_id, ty)
+                    && adt_def.all_fields().all(|field| {
+                        self.has_structural_eq(def_id, field.ty(tcx, substs))
+                    })
+            }
+
+            ty::TypeVariants::TyTuple(elems) => {
+                elems.iter().all(|elem_ty| self.has_structural_eq(def_id, elem_ty))
+            }
+
+            _ => false,
+        }
+    }
+
+    /// Register an unsupported feature used by the MIR (see `register_encoding_error`). The
+    /// function is then declared without its body, so the encoding can continue with any value.
+    fn register_unsupported<T: ToString>(
+        &self,
+        feature: UnsupportedFeature,
+        span: Span,
+        details: T,
+    ) {
+        self.encoder
+            .register_encoding_error(SpannedEncodingError::unsupported(feature, span, details));
+    }
+}
+
+/// Returns true if values of the type are encoded with a value field.
+fn is_primitive_ty(ty: ty::Ty) -> bool {
+    match ty.sty {
+        ty::TypeVariants::TyBool
+        | ty::TypeVariants::TyInt(..)
+        | ty::TypeVariants::TyUint(..)
+        | ty::TypeVariants::TyChar
+        | ty::TypeVariants::TyRawPtr(..)
+        | ty::TypeVariants::TyRef(..) => true,
+        _ => false,
+    }
+}
+
+/// The methods of `Option` and `Result` that are encoded directly over the enum encoding.
+/// Returns the name of the variant that the method checks, and whether the method unwraps the
+/// content of the variant (instead of just checking the discriminant).
+fn get_builtin_enum_method(func_proc_name: &str) -> Option<(&'static str, bool)> {
+    match func_proc_name {
+        "<std::option::Option<T>>::is_some" | "<core::option::Option<T>>::is_some" => {
+            Some(("Some", false))
+        }
+        "<std::option::Option<T>>::is_none" | "<core::option::Option<T>>::is_none" => {
+            Some(("None", false))
+        }
+        "<std::option::Option<T>>::unwrap" | "<core::option::Option<T>>::unwrap" => {
+            Some(("Some", true))
+        }
+        "<std::result::Result<T, E>>::is_ok" | "<core::result::Result<T, E>>::is_ok" => {
+            Some(("Ok", false))
+        }
+        "<std::result::Result<T, E>>::is_err" | "<core::result::Result<T, E>>::is_err" => {
+            Some(("Err", false))
+        }
+        "<std::result::Result<T, E>>::unwrap" | "<core::result::Result<T, E>>::unwrap" => {
+            Some(("Ok", true))
+        }
+        "<std::result::Result<T, E>>::unwrap_err" | "<core::result::Result<T, E>>::unwrap_err" => {
+            Some(("Err", true))
+        }
+        _ => None,
+    }
 }
 
 impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
@@ -379,7 +662,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
 
     fn apply_terminator(
         &self,
-        _bb: mir::BasicBlock,
+        bb: mir::BasicBlock,
         term: &mir::Terminator<'tcx>,
         states: HashMap<mir::BasicBlock, &Self::State>,
     ) -> Self::State {
@@ -488,12 +771,12 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                             }
                         }
 
-                        ty::TypeVariants::TyInt(_) | ty::TypeVariants::TyUint(_) => {
-                            vir::Expr::eq_cmp(
-                                discr_val.clone().into(),
-                                self.encoder.encode_int_cast(value, switch_ty),
-                            )
-                        }
+                        ty::TypeVariants::TyInt(_)
+                        | ty::TypeVariants::TyUint(_)
+                        | ty::TypeVariants::TyChar => vir::Expr::eq_cmp(
+                            discr_val.clone().into(),
+                            self.encoder.encode_int_cast(value, switch_ty),
+                        ),
 
                         ref x => unreachable!("{:?}", x),
                     };
@@ -544,9 +827,18 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                 )
             }
 
-            TerminatorKind::DropAndReplace { ..  } => {
-                unimplemented!()
-            },
+            TerminatorKind::DropAndReplace { .. } => {
+                self.register_unsupported(
+                    UnsupportedFeature::Statement,
+                    term.source_info.span,
+                    format!("{:?}", term.kind),
+                );
+                let pos = self
+                    .encoder
+                    .error_manager()
+                    .register(term.source_info.span, ErrorCtxt::Unexpected);
+                MultiExprBackwardInterpreterState::new_single(undef_expr(pos))
+            }
 
             TerminatorKind::Call {
                 ref args,
@@ -570,6 +862,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                 ..
             } => {
                 let func_proc_name: &str = &self.encoder.env().tcx().absolute_item_path_str(def_id);
+                let (def_id, substs) = self.encoder.get_called_procedure(def_id, substs);
 
                 let own_substs =
                     ty::subst::Substs::identity_for_item(self.encoder.env().tcx(), def_id);
@@ -591,26 +884,53 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                     tymap_stack.push(tymap);
                 }
 
-                let state = if destination.is_some() {
+                let state = if self.panic_message_blocks.contains(&bb) {
+                    // The computation of the message of a panic is not encoded
+                    let target_block = destination.as_ref().unwrap().1;
+                    states[&target_block].clone()
+                } else if destination.is_some() {
                     let (ref lhs_place, target_block) = destination.as_ref().unwrap();
                     let (encoded_lhs, ty, _) = self.mir_encoder.encode_place(lhs_place);
-                    let lhs_value = encoded_lhs
-                        .clone()
-                        .field(self.encoder.encode_value_field(ty));
-                    let encoded_args: Vec<vir::Expr> = args
-                        .iter()
-                        .map(|arg| self.mir_encoder.encode_operand_expr(arg))
-                        .collect();
+                    // The value field and the values of the arguments are only defined for
+                    // primitive types, so they are encoded on demand.
+                    let lhs_value = || {
+                        encoded_lhs
+                            .clone()
+                            .field(self.encoder.encode_value_field(ty))
+                    };
+                    let encoded_args = || -> Vec<vir::Expr> {
+                        args.iter()
+                            .map(|arg| self.mir_encoder.encode_operand_expr(arg))
+                            .collect()
+                    };
 
                     match func_proc_name {
+                        "prusti_contracts::internal::old"
+                        | "prusti_contracts::internal::before_expiry"
+                            if !is_primitive_ty(ty) =>
+                        {
+                            trace!("Encoding old place {:?}", args[0]);
+                            assert_eq!(args.len(), 1);
+                            let label = if func_proc_name.ends_with("::old") {
+                                PRECONDITION_LABEL
+                            } else {
+                                WAND_LHS_LABEL
+                            };
+                            let arg_place =
+                                self.mir_encoder.encode_operand_place(&args[0]).unwrap();
+                            let mut state = states[&target_block].clone();
+                            state.substitute_old_place(&encoded_lhs, &arg_place, label);
+                            state
+                        }
+
                         "prusti_contracts::internal::old" => {
                             trace!("Encoding old expression {:?}", args[0]);
                             assert_eq!(args.len(), 1);
                             let encoded_rhs = self
                                 .mir_encoder
-                                .encode_old_expr(encoded_args[0].clone(), PRECONDITION_LABEL);
+                                .encode_old_expr(encoded_args().remove(0), PRECONDITION_LABEL);
                             let mut state = states[&target_block].clone();
-                            state.substitute_value(&lhs_value, encoded_rhs);
+                            state.substitute_value(&lhs_value(), encoded_rhs);
                             state
                         }
 
@@ -619,9 +939,229 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                             assert_eq!(args.len(), 1);
                             let encoded_rhs = self
                                 .mir_encoder
-                                .encode_old_expr(encoded_args[0].clone(), WAND_LHS_LABEL);
+                                .encode_old_expr(encoded_args().remove(0), WAND_LHS_LABEL);
                             let mut state = states[&target_block].clone();
-                            state.substitute_value(&lhs_value, encoded_rhs);
+                            state.substitute_value(&lhs_value(), encoded_rhs);
+                            state
+                        }
+
+                        _ if get_builtin_enum_method(func_proc_name).is_some() => {
+                            let (variant_name, is_unwrap) =
+                                get_builtin_enum_method(func_proc_name).unwrap();
+                            trace!("Encoding built-in enum method '{}'", func_proc_name);
+                            assert_eq!(args.len(), 1);
+                            let (enum_place, enum_ty) = self.encode_referenced_operand(&args[0]);
+                            let (adt_def, enum_substs) = match enum_ty.sty {
+                                ty::TypeVariants::TyAdt(adt_def, enum_substs) => {
+                                    (adt_def, enum_substs)
+                                }
+                                ref x => unreachable!("{:?}", x),
+                            };
+                            let variant_index = adt_def
+                                .variants
+                                .iter()
+                                .position(|variant| variant.name.as_str() == variant_name)
+                                .unwrap();
+                            let discr_field = self.encoder.encode_discriminant_field();
+                            let guard = vir::Expr::eq_cmp(
+                                enum_place.clone().field(discr_field),
+                                variant_index.into(),
+                            );
+
+                            let mut state = states[&target_block].clone();
+                            if is_unwrap {
+                                let field = &adt_def.variants[variant_index].fields[0];
+                                let field_ty = field.ty(self.encoder.env().tcx(), enum_substs);
+                                let encoded_field = self
+                                    .encoder
+                                    .encode_struct_field(&field.ident.as_str(), field_ty);
+                                let content = enum_place.variant(variant_name).field(encoded_field);
+                                state.substitute_place(&encoded_lhs, content);
+
+                                // Unwrapping the other variant panics
+                                let pos = self.encoder.error_manager().register(
+                                    term.source_info.span,
+                                    ErrorCtxt::PanicInPureFunction(PanicCause::Panic),
+                                );
+                                state = MultiExprBackwardInterpreterState::new(
+                                    state
+                                        .into_expressions()
+                                        .into_iter()
+                                        .map(|expr| {
+                                            vir::Expr::ite(
+                                                guard.clone(),
+                                                expr,
+                                                unreachable_expr(pos.clone()),
+                                            )
+                                        })
+                                        .collect(),
+                                );
+                            } else {
+                                state.substitute_value(&lhs_value(), guard);
+                            }
+                            state
+                        }
+
+                        _ if self.get_ghost_method_self_ty(def_id, substs).is_some() => {
+                            trace!("Encoding ghost method '{}'", func_proc_name);
+                            let self_ty = self.get_ghost_method_self_ty(def_id, substs).unwrap();
+                            let method_name =
+                                self.encoder.env().tcx().item_name(def_id).to_string();
+                            let encoded_rhs = self
+                                .encoder
+                                .encode_ghost_type_or_register(self_ty, term.source_info.span)
+                                .encode_method_call(&method_name, encoded_args());
+                            let mut state = states[&target_block].clone();
+                            state.substitute_value(&lhs_value(), encoded_rhs);
+                            state
+                        }
+
+                        "std::ops::Deref::deref" | "core::ops::Deref::deref"
+                            if self.is_rc_argument(args) =>
+                        {
+                            // The result references the content of the `Rc`
+                            trace!("Encoding dereferentiation of an Rc '{}'", func_proc_name);
+                            assert_eq!(args.len(), 1);
+                            let (rc_place, _) = self.encode_referenced_operand(&args[0]);
+                            let mut state = states[&target_block].clone();
+                            state.substitute_place(&encoded_lhs, rc_place);
+                            state
+                        }
+
+                        "std::cmp::PartialEq::eq"
+                        | "core::cmp::PartialEq::eq"
+                        | "std::cmp::PartialEq::ne"
+                        | "core::cmp::PartialEq::ne"
+                            if self.is_string_eq(substs) =>
+                        {
+                            trace!("Encoding string equality '{}'", func_proc_name);
+                            assert_eq!(args.len(), 2);
+                            let first = self.encode_string_operand(&args[0]);
+                            let second = self.encode_string_operand(&args[1]);
+                            let encoded_rhs = if func_proc_name.ends_with("::ne") {
+                                vir::Expr::ne_cmp(first, second)
+                            } else {
+                                vir::Expr::eq_cmp(first, second)
+                            };
+                            let mut state = states[&target_block].clone();
+                            state.substitute_value(&lhs_value(), encoded_rhs);
+                            state
+                        }
+
+                        "std::cmp::PartialEq::eq"
+                        | "core::cmp::PartialEq::eq"
+                        | "std::cmp::PartialEq::ne"
+                        | "core::cmp::PartialEq::ne"
+                            if is_ordering_ty(self.encoder.env().tcx(), substs.type_at(0))
+                                || is_ghost_ty(self.encoder.env().tcx(), substs.type_at(0)) =>
+                        {
+                            // The values of orderings and of ghost types are compared directly
+                            trace!("Encoding value equality '{}'", func_proc_name);
+                            assert_eq!(args.len(), 2);
+                            let (first, value_ty) = self.encode_referenced_operand(&args[0]);
+                            let (second, _) = self.encode_referenced_operand(&args[1]);
+                            let value_field = self.encoder.encode_value_field(value_ty);
+                            let first = first.field(value_field.clone());
+                            let second = second.field(value_field);
+                            let encoded_rhs = if func_proc_name.ends_with("::ne") {
+                                vir::Expr::ne_cmp(first, second)
+                            } else {
+                                vir::Expr::eq_cmp(first, second)
+                            };
+                            let mut state = states[&target_block].clone();
+                            state.substitute_value(&lhs_value(), encoded_rhs);
+                            state
+                        }
+
+                        "std::cmp::PartialOrd::lt"
+                        | "core::cmp::PartialOrd::lt"
+                        | "std::cmp::PartialOrd::le"
+                        | "core::cmp::PartialOrd::le"
+                        | "std::cmp::PartialOrd::gt"
+                        | "core::cmp::PartialOrd::gt"
+                        | "std::cmp::PartialOrd::ge"
+                        | "core::cmp::PartialOrd::ge"
+                            if self.get_pure_cmp(def_id, substs).is_some() =>
+                        {
+                            trace!("Encoding comparison '{}' with `Ord::cmp`", func_proc_name);
+                            assert_eq!(args.len(), 2);
+                            let cmp_def_id = self.get_pure_cmp(def_id, substs).unwrap();
+                            let function_name = self.encoder.encode_pure_function_use(cmp_def_id);
+                            let formal_args: Vec<vir::LocalVar> = args
+                                .iter()
+                                .enumerate()
+                                .map(|(i, arg)| {
+                                    vir::LocalVar::new(
+                                        format!("x{}", i),
+                                        self.mir_encoder.encode_operand_expr_type(arg),
+                                    )
+                                })
+                                .collect();
+                            let pos = self
+                                .encoder
+                                .error_manager()
+                                .register(term.source_info.span, ErrorCtxt::PureFunctionCall);
+                            let ordering = vir::Expr::func_app(
+                                function_name,
+                                encoded_args(),
+                                formal_args,
+                                vir::Type::Int,
+                                pos,
+                            );
+                            // `Less`, `Equal` and `Greater` are encoded as -1, 0 and 1
+                            let encoded_rhs = match &func_proc_name[func_proc_name.len() - 2..] {
+                                "lt" => vir::Expr::lt_cmp(ordering, 0.into()),
+                                "le" => vir::Expr::le_cmp(ordering, 0.into()),
+                                "gt" => vir::Expr::gt_cmp(ordering, 0.into()),
+                                _ => vir::Expr::ge_cmp(ordering, 0.into()),
+                            };
+                            let mut state = states[&target_block].clone();
+                            state.substitute_value(&lhs_value(), encoded_rhs);
+                            state
+                        }
+
+                        "std::cmp::PartialEq::eq"
+                        | "core::cmp::PartialEq::eq"
+                        | "std::cmp::PartialEq::ne"
+                        | "core::cmp::PartialEq::ne"
+                            if self.is_structural_eq(def_id, substs) =>
+                        {
+                            trace!("Encoding snapshot equality '{}'", func_proc_name);
+                            assert_eq!(args.len(), 2);
+                            let (first, eq_ty) = self.encode_referenced_operand(&args[0]);
+                            let (second, _) = self.encode_referenced_operand(&args[1]);
+                            let pos = self
+                                .encoder
+                                .error_manager()
+                                .register(term.source_info.span, ErrorCtxt::PureFunctionCall);
+                            let first_snapshot = self
+                                .encoder
+                                .encode_snapshot_func_app(first, eq_ty, pos.clone())
+                                .unwrap();
+                            let second_snapshot = self
+                                .encoder
+                                .encode_snapshot_func_app(second, eq_ty, pos)
+                                .unwrap();
+                            let encoded_rhs = if func_proc_name.ends_with("::ne") {
+                                vir::Expr::ne_cmp(first_snapshot, second_snapshot)
+                            } else {
+                                vir::Expr::eq_cmp(first_snapshot, second_snapshot)
+                            };
+                            let mut state = states[&target_block].clone();
+                            state.substitute_value(&lhs_value(), encoded_rhs);
+                            state
+                        }
+
+                        // A `const fn` without arguments is evaluated by the compiler
+                        _ if args.is_empty() && self.encoder.env().tcx().is_const_fn(def_id) => {
+                            trace!("Encoding constant function call '{}'", func_proc_name);
+                            let encoded_rhs = self.encoder.encode_const_fn_call(
+                                def_id,
+                                substs,
+                                term.source_info.span,
+                            );
+                            let mut state = states[&target_block].clone();
+                            state.substitute_value(&lhs_value(), encoded_rhs);
                             state
                         }
 
@@ -648,98 +1188,48 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                                 .register(term.source_info.span, ErrorCtxt::PureFunctionCall);
                             let encoded_rhs = vir::Expr::func_app(
                                 function_name,
-                                encoded_args,
+                                encoded_args(),
                                 formal_args,
                                 return_type,
                                 pos,
                             );
 
                             let mut state = states[&target_block].clone();
-                            state.substitute_value(&lhs_value, encoded_rhs);
+                            state.substitute_value(&lhs_value(), encoded_rhs);
                             state
                         }
                     }
                 } else {
                     // Encoding of a non-terminating function call
-                    let error_ctxt = match func_proc_name {
-                        "std::rt::begin_panic" | "std::panicking::begin_panic" => {
+                    let (span, error_ctxt) = match func_proc_name {
+                        "std::rt::begin_panic"
+                        | "std::panicking::begin_panic"
+                        | "std::rt::begin_panic_fmt"
+                        | "std::panicking::begin_panic_fmt" => {
                             // This is called when a Rust assertion fails
-                            // args[0]: message
+                            // args[0]: message (or formatted message)
                             // args[1]: position of failing assertions
-
-                            // Pattern match on the macro that generated the panic
-                            // TODO: use a better approach to match macros
-                            let macro_backtrace = term.source_info.span.macro_backtrace();
-                            debug!("macro_backtrace: {:?}", macro_backtrace);
-
-                            let panic_cause = if !macro_backtrace.is_empty() {
-                                let macro_name = term.source_info.span.macro_backtrace()[0]
-                                    .macro_decl_name
-                                    .clone();
-                                // HACK to match the filename of the span
-                                let def_site_span = format!(
-                                    "{:?}",
-                                    term.source_info.span.macro_backtrace()[0].def_site_span
-                                );
-
-                                match macro_name.as_str() {
-                                    "panic!" if def_site_span.contains("<panic macros>") => {
-                                        if macro_backtrace.len() > 1 {
-                                            let second_macro_name =
-                                                term.source_info.span.macro_backtrace()[1]
-                                                    .macro_decl_name
-                                                    .clone();
-                                            // HACK to match the filename of the span
-                                            let second_def_site_span = format!(
-                                                "{:?}",
-                                                term.source_info.span.macro_backtrace()[1]
-                                                    .def_site_span
-                                            );
-
-                                            match second_macro_name.as_str() {
-                                                "panic!"
-                                                    if second_def_site_span
-                                                        .contains("<panic macros>") =>
-                                                {
-                                                    PanicCause::Panic
-                                                }
-                                                "assert!" if second_def_site_span == "None" => {
-                                                    PanicCause::Assert
-                                                }
-                                                "unreachable!"
-                                                    if second_def_site_span
-                                                        .contains("<unreachable macros>") =>
-                                                {
-                                                    PanicCause::Unreachable
-                                                }
-                                                "unimplemented!"
-                                                    if second_def_site_span
-                                                        .contains("<unimplemented macros>") =>
-                                                {
-                                                    PanicCause::Unimplemented
-                                                }
-                                                _ => PanicCause::Panic,
-                                            }
-                                        } else {
-                                            PanicCause::Panic
-                                        }
-                                    }
-                                    _ => PanicCause::Unknown,
-                                }
-                            } else {
-                                // Something else called panic!()
-                                PanicCause::Unknown
-                            };
-                            ErrorCtxt::PanicInPureFunction(panic_cause)
+                            let (panic_cause, panic_span) =
+                                self.mir_encoder.get_panic_cause(term.source_info.span);
+                            (panic_span, ErrorCtxt::PanicInPureFunction(panic_cause))
                         }
 
-                        _ => ErrorCtxt::DivergingCallInPureFunction,
+                        _ => (
+                            term.source_info.span,
+                            ErrorCtxt::DivergingCallInPureFunction,
+                        ),
                     };
-                    let pos = self
-                        .encoder
-                        .error_manager()
-                        .register(term.source_info.span, error_ctxt);
-                    MultiExprBackwardInterpreterState::new_single(unreachable_expr(pos))
+                    let pos = self.encoder.error_manager().register(span, error_ctxt.clone());
+                    match error_ctxt {
+                        ErrorCtxt::PanicInPureFunction(panic_cause)
+                            if panic_cause.is_debug_assertion()
+                                && !config::verify_debug_assertions() =>
+                        {
+                            // The debug assertion is not checked
+                            MultiExprBackwardInterpreterState::new_single(undef_expr(pos))
+                        }
+                        _ => MultiExprBackwardInterpreterState::new_single(unreachable_expr(pos)),
+                    }
                 };
 
                 // FIXME; hideous monstrosity...
@@ -751,8 +1241,17 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
             }
 
             TerminatorKind::Call { .. } => {
-                // Other kind of calls?
-                unimplemented!();
+                // The call of a function pointer or of a closure
+                self.register_unsupported(
+                    UnsupportedFeature::IndirectCall,
+                    term.source_info.span,
+                    format!("{:?}", term.kind),
+                );
+                let pos = self
+                    .encoder
+                    .error_manager()
+                    .register(term.source_info.span, ErrorCtxt::Unexpected);
+                MultiExprBackwardInterpreterState::new_single(undef_expr(pos))
             }
 
             TerminatorKind::Assert {
@@ -795,20 +1294,34 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
             }
 
             TerminatorKind::Yield { .. } | TerminatorKind::GeneratorDrop => {
-                unimplemented!("{:?}", term.kind)
+                self.register_unsupported(
+                    UnsupportedFeature::Generator,
+                    term.source_info.span,
+                    format!("{:?}", term.kind),
+                );
+                let pos = self
+                    .encoder
+                    .error_manager()
+                    .register(term.source_info.span, ErrorCtxt::Unexpected);
+                MultiExprBackwardInterpreterState::new_single(undef_expr(pos))
             }
         }
     }
 
     fn apply_statement(
         &self,
-        _bb: mir::BasicBlock,
+        bb: mir::BasicBlock,
         _stmt_index: usize,
         stmt: &mir::Statement<'tcx>,
         state: &mut Self::State,
     ) {
         trace!("apply_statement {:?}, state: {}", stmt, state);
 
+        if self.panic_message_blocks.contains(&bb) {
+            // The computation of the message of a panic is not encoded
+            return;
+        }
+
         match stmt.kind {
             mir::StatementKind::StorageLive(..)
             | mir::StatementKind::StorageDead(..)
@@ -830,6 +1343,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                     ty::TypeVariants::TyBool
                     | ty::TypeVariants::TyInt(..)
                     | ty::TypeVariants::TyUint(..)
+                    | ty::TypeVariants::TyChar
                     | ty::TypeVariants::TyRawPtr(..)
                     | ty::TypeVariants::TyRef(..) => Some(
                         encoded_lhs
@@ -848,11 +1362,30 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                                 // Substitute a place
                                 state.substitute_place(&encoded_lhs, encoded_rhs);
                             }
-                            None => {
-                                // Substitute a place of a value with an expression
-                                let rhs_expr = self.mir_encoder.encode_operand_expr(operand);
-                                state.substitute_value(&opt_lhs_value_place.unwrap(), rhs_expr);
-                            }
+                            None => match operand {
+                                &mir::Operand::Constant(box ref constant)
+                                    if is_string_ref_ty(self.encoder.env().tcx(), ty) =>
+                                {
+                                    // Substitute the content of a string literal
+                                    let string_place = opt_lhs_value_place
+                                        .unwrap()
+                                        .field(self.encoder.encode_string_field());
+                                    match self.encoder.encode_string_constant(constant) {
+                                        Ok(literal) => {
+                                            state.substitute_value(&string_place, literal)
+                                        }
+                                        Err(error) => self.encoder.register_encoding_error(error),
+                                    }
+                                }
+                                _ => {
+                                    // Substitute a place of a value with an expression
+                                    let rhs_expr = self.mir_encoder.encode_operand_expr(operand);
+                                    state.substitute_value(
+                                        &opt_lhs_value_place.unwrap(),
+                                        rhs_expr,
+                                    );
+                                }
+                            },
                         }
                     }
 
@@ -865,6 +1398,19 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                                 } else {
                                     unreachable!()
                                 };
+                                // The snapshot of the whole tuple (e.g. used by an equality)
+                                // is expressed with the snapshots of its elements, which are
+                                // substituted below.
+                                let snapshot_encoder = SnapshotEncoder::new(self.encoder, ty);
+                                if let Some(constructor_app) = snapshot_encoder
+                                    .encode_tuple_constructor_app(encoded_lhs.clone())
+                                {
+                                    state.substitute_func_app(
+                                        &snapshot_encoder.encode_function_name(),
+                                        &[encoded_lhs.clone()],
+                                        &constructor_app,
+                                    );
+                                }
                                 for (field_num, operand) in operands.iter().enumerate() {
                                     let field_name = format!("tuple_{}", field_num);
                                     let field_ty = field_types[field_num];
@@ -897,10 +1443,13 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                                 let variant_def = &adt_def.variants[variant_index];
                                 let mut encoded_lhs_variant = encoded_lhs.clone();
                                 if num_variants != 1 {
+                                    let tcx = self.encoder.env().tcx();
                                     let discr_field = self.encoder.encode_discriminant_field();
+                                    let discr_value =
+                                        compute_discriminant_values(adt_def, tcx)[variant_index];
                                     state.substitute_value(
                                         &encoded_lhs.clone().field(discr_field),
-                                        variant_index.into(),
+                                        discr_value.into(),
                                     );
                                     encoded_lhs_variant =
                                         encoded_lhs_variant.variant(&variant_def.name.as_str());
@@ -935,7 +1484,11 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                                 }
                             }
 
-                            ref x => unimplemented!("{:?}", x),
+                            ref x => self.register_unsupported(
+                                UnsupportedFeature::Rvalue,
+                                stmt.source_info.span,
+                                format!("{:?}", x),
+                            ),
                         }
                     }
 
@@ -1012,7 +1565,11 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                         state.substitute_value(&opt_lhs_value_place.unwrap(), encoded_value);
                     }
 
-                    &mir::Rvalue::NullaryOp(_op, ref _op_ty) => unimplemented!(),
+                    &mir::Rvalue::NullaryOp(..) => self.register_unsupported(
+                        UnsupportedFeature::of_rvalue(rhs),
+                        stmt.source_info.span,
+                        format!("{:?}", rhs),
+                    ),
 
                     &mir::Rvalue::Discriminant(ref src) => {
                         let (encoded_src, src_ty, _) = self.mir_encoder.encode_place(src);
@@ -1074,19 +1631,29 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> BackwardMirInterpreter<'tcx>
                     }
 
                     &mir::Rvalue::Cast(mir::CastKind::Misc, ref operand, dst_ty) => {
-                        let encoded_val = self.mir_encoder.encode_cast_expr(operand, dst_ty);
-
-                        // Substitute a place of a value with an expression
-                        state.substitute_value(&opt_lhs_value_place.unwrap(), encoded_val);
+                        let span = stmt.source_info.span;
+                        match self.mir_encoder.encode_cast_expr(operand, dst_ty, span) {
+                            Ok(encoded_val) => {
+                                // Substitute a place of a value with an expression
+                                state.substitute_value(&opt_lhs_value_place.unwrap(), encoded_val);
+                            }
+                            Err(error) => self.encoder.register_encoding_error(error),
+                        }
                     }
 
-                    ref rhs => {
-                        unimplemented!("encoding of '{:?}'", rhs);
-                    }
+                    ref rhs => self.register_unsupported(
+                        UnsupportedFeature::of_rvalue(rhs),
+                        stmt.source_info.span,
+                        format!("{:?}", rhs),
+                    ),
                 }
             }
 
-            ref stmt => unimplemented!("encoding of '{:?}'", stmt),
+            ref x => self.register_unsupported(
+                UnsupportedFeature::Statement,
+                stmt.source_info.span,
+                format!("{:?}", x),
+            ),
         }
     }
 }
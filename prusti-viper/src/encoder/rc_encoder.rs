@@ -0,0 +1,38 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The model of the reference-counted pointers `Rc<T>` and `Arc<T>`, which share the immutable
+//! ownership of their content. An `Rc<T>` is encoded like a shared reference `&T`: its predicate
+//! gives read permission to the content through the `val_ref` field. Hence:
+//! * `Rc::new(x)` moves `x` into a newly allocated content;
+//! * `rc.clone()` creates a pointer to the same content, with its own read permission;
+//! * `*rc` (i.e. `Deref::deref`) creates a shared reference to the content.
+//!
+//! The reference count is not modelled, so the methods that mutate the content when the pointer
+//! is unique (e.g. `Rc::get_mut` and `Rc::make_mut`) are not supported.
+
+use rustc::ty;
+
+/// The paths of the reference-counted pointer types.
+const RC_PATHS: &[&str] = &["std::rc::Rc", "alloc::rc::Rc", "std::sync::Arc", "alloc::sync::Arc"];
+
+/// Returns true if the type is `Rc<T>` or `Arc<T>`.
+pub fn is_rc_ty(tcx: ty::TyCtxt, ty: ty::Ty) -> bool {
+    match ty.sty {
+        ty::TypeVariants::TyAdt(adt_def, _) => {
+            RC_PATHS.contains(&tcx.item_path_str(adt_def.did).as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Returns the type `T` of the content of an `Rc<T>` or `Arc<T>`.
+pub fn get_rc_content_ty<'tcx>(ty: ty::Ty<'tcx>) -> ty::Ty<'tcx> {
+    match ty.sty {
+        ty::TypeVariants::TyAdt(_, substs) => substs.type_at(0),
+        ref x => unreachable!("{:?}", x),
+    }
+}
@@ -0,0 +1,311 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use encoder::ghost_encoder::is_ghost_ty;
+use encoder::rc_encoder::is_rc_ty;
+use encoder::string_encoder::is_string_ty;
+use encoder::type_encoder::compute_discriminant_values;
+use encoder::vir;
+use encoder::vir::ExprIterator;
+use encoder::Encoder;
+use rustc::ty;
+
+/// The snapshot of a type: a Viper domain with one constructor per variant of the type, and a
+/// function that maps a memory location of the type to the value of the domain that abstracts
+/// its content. Two locations are structurally equal if and only if their snapshots are equal.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub domain: vir::Domain,
+    pub function: vir::Function,
+}
+
+pub struct SnapshotEncoder<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> {
+    encoder: &'p Encoder<'v, 'r, 'a, 'tcx>,
+    ty: ty::Ty<'tcx>,
+    predicate_name: String,
+}
+
+impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> SnapshotEncoder<'p, 'v, 'r, 'a, 'tcx> {
+    pub fn new(encoder: &'p Encoder<'v, 'r, 'a, 'tcx>, ty: ty::Ty<'tcx>) -> Self {
+        let predicate_name = encoder.encode_type_predicate_use(ty);
+        SnapshotEncoder {
+            encoder,
+            ty,
+            predicate_name,
+        }
+    }
+
+    pub fn encode_domain_name(&self) -> String {
+        format!("Snap${}", self.predicate_name)
+    }
+
+    pub fn encode_domain_type(&self) -> vir::Type {
+        vir::Type::Domain(self.encode_domain_name())
+    }
+
+    pub fn encode_function_name(&self) -> String {
+        format!("snap${}", self.predicate_name)
+    }
+
+    /// Returns `None` if the type, or the type of one of its fields, has no snapshot.
+    pub fn encode(&self) -> Option<Snapshot> {
+        debug!("Encode snapshot of type '{:?}'", self.ty);
+        let this = vir::LocalVar::new("self", vir::Type::TypedRef(self.predicate_name.clone()));
+        let location: vir::Expr = this.clone().into();
+        let tcx = self.encoder.env().tcx();
+        let (constructors, body) = match self.ty.sty {
+            // The content of an `Rc` is shared, so it has no snapshot.
+            _ if is_rc_ty(tcx, self.ty) => return None,
+            ty::TypeVariants::TyAdt(adt_def, subst) if !adt_def.is_box() => {
+                match adt_def.variants.len() {
+                    0 => return None,
+                    1 => {
+                        // A struct.
+                        let fields = adt_def.variants[0]
+                            .fields
+                            .iter()
+                            .map(|field| {
+                                let field_ty = field.ty(tcx, subst);
+                                let encoded_field = self
+                                    .encoder
+                                    .encode_struct_field(&field.ident.as_str(), field_ty);
+                                (encoded_field, field_ty)
+                            })
+                            .collect();
+                        let (constructor, body) =
+                            self.encode_constructor(0, location.clone(), fields)?;
+                        (vec![constructor], body)
+                    }
+                    _ => {
+                        // An enum.
+                        let discr_field = self.encoder.encode_discriminant_field();
+                        let discriminant = location.clone().field(discr_field);
+                        let discriminant_values = compute_discriminant_values(adt_def, tcx);
+                        let mut constructors = vec![];
+                        let mut variant_bodies = vec![];
+                        for (index, variant_def) in adt_def.variants.iter().enumerate() {
+                            let variant_location =
+                                location.clone().variant(&variant_def.name.as_str());
+                            let fields = variant_def
+                                .fields
+                                .iter()
+                                .map(|field| {
+                                    let field_ty = field.ty(tcx, subst);
+                                    let encoded_field = self
+                                        .encoder
+                                        .encode_struct_field(&field.ident.as_str(), field_ty);
+                                    (encoded_field, field_ty)
+                                })
+                                .collect::<Vec<_>>();
+                            let has_fields = !fields.is_empty();
+                            let (constructor, variant_body) =
+                                self.encode_constructor(index, variant_location.clone(), fields)?;
+                            let variant_body = if has_fields {
+                                vir::Expr::wrap_in_unfolding(variant_location, variant_body)
+                            } else {
+                                variant_body
+                            };
+                            constructors.push(constructor);
+                            variant_bodies.push(variant_body);
+                        }
+                        let last_body = variant_bodies.pop().unwrap();
+                        let body = variant_bodies
+                            .into_iter()
+                            .zip(discriminant_values)
+                            .rev()
+                            .fold(last_body, |else_body, (then_body, value)| {
+                                let guard = vir::Expr::eq_cmp(discriminant.clone(), value.into());
+                                vir::Expr::ite(guard, then_body, else_body)
+                            });
+                        (constructors, body)
+                    }
+                }
+            }
+            ty::TypeVariants::TyTuple(elems) => {
+                let fields = self.encode_tuple_fields(elems);
+                let (constructor, body) = self.encode_constructor(0, location.clone(), fields)?;
+                (vec![constructor], body)
+            }
+            _ => return None,
+        };
+
+        let domain = self.encode_domain(constructors);
+        let function = vir::Function {
+            name: self.encode_function_name(),
+            formal_args: vec![this],
+            return_type: self.encode_domain_type(),
+            pres: vec![vir::Expr::predicate_access_predicate(
+                self.predicate_name.clone(),
+                location.clone(),
+                vir::PermAmount::Read,
+            )],
+            posts: vec![],
+            body: Some(vir::Expr::wrap_in_unfolding(location, body)),
+        };
+        Some(Snapshot { domain, function })
+    }
+
+    /// Encode the snapshot of the tuple at `place` as the application of the constructor to the
+    /// snapshots of its elements, so that the elements of a tuple that is built in a pure
+    /// expression (e.g. `result == (b, a)`) can be substituted. Returns `None` if the type is not
+    /// a tuple or has no snapshot.
+    pub fn encode_tuple_constructor_app(&self, place: vir::Expr) -> Option<vir::Expr> {
+        match self.ty.sty {
+            ty::TypeVariants::TyTuple(elems) => {
+                let fields = self.encode_tuple_fields(elems);
+                let (_, application) = self.encode_constructor(0, place, fields)?;
+                Some(application)
+            }
+            _ => None,
+        }
+    }
+
+    fn encode_tuple_fields(&self, elems: &[ty::Ty<'tcx>]) -> Vec<(vir::Field, ty::Ty<'tcx>)> {
+        elems
+            .iter()
+            .enumerate()
+            .map(|(field_num, field_ty)| {
+                let field_name = format!("tuple_{}", field_num);
+                (self.encoder.encode_raw_ref_field(field_name, field_ty), *field_ty)
+            })
+            .collect()
+    }
+
+    /// Encode the constructor of the `index`-th variant, together with its application to the
+    /// snapshots of the given fields of `location`.
+    fn encode_constructor(
+        &self,
+        index: usize,
+        location: vir::Expr,
+        fields: Vec<(vir::Field, ty::Ty<'tcx>)>,
+    ) -> Option<(vir::DomainFunc, vir::Expr)> {
+        let mut formal_args = vec![];
+        let mut args = vec![];
+        for (field_num, (field, field_ty)) in fields.into_iter().enumerate() {
+            let place = location.clone().field(field);
+            let (arg_type, arg) = self.encode_field_snapshot(place, field_ty)?;
+            formal_args.push(vir::LocalVar::new(format!("arg{}", field_num), arg_type));
+            args.push(arg);
+        }
+        let constructor = vir::DomainFunc {
+            name: format!("cons${}${}", index, self.predicate_name),
+            formal_args,
+            return_type: self.encode_domain_type(),
+            unique: false,
+            domain_name: self.encode_domain_name(),
+        };
+        let application = constructor.apply(args);
+        Some((constructor, application))
+    }
+
+    /// Primitive, string and ghost fields are represented by their value, the others by their
+    /// snapshot.
+    fn encode_field_snapshot(
+        &self,
+        place: vir::Expr,
+        ty: ty::Ty<'tcx>,
+    ) -> Option<(vir::Type, vir::Expr)> {
+        match ty.sty {
+            _ if is_string_ty(self.encoder.env().tcx(), ty) => {
+                let string_field = self.encoder.encode_string_field();
+                Some((string_field.typ.clone(), place.field(string_field)))
+            }
+            _ if is_ghost_ty(self.encoder.env().tcx(), ty) => {
+                let value_field = self.encoder.encode_value_field(ty);
+                Some((value_field.typ.clone(), place.field(value_field)))
+            }
+            ty::TypeVariants::TyBool
+            | ty::TypeVariants::TyInt(_)
+            | ty::TypeVariants::TyUint(_)
+            | ty::TypeVariants::TyChar => {
+                let value_field = self.encoder.encode_value_field(ty);
+                Some((value_field.typ.clone(), place.field(value_field)))
+            }
+            ty::TypeVariants::TyAdt(..) | ty::TypeVariants::TyTuple(..) => {
+                let snapshot = self
+                    .encoder
+                    .encode_snapshot_func_app(place, ty, vir::Position::default())?;
+                Some((SnapshotEncoder::new(self.encoder, ty).encode_domain_type(), snapshot))
+            }
+            _ => None,
+        }
+    }
+
+    /// The constructors are injective and, for enums, build values of distinct variants.
+    fn encode_domain(&self, constructors: Vec<vir::DomainFunc>) -> vir::Domain {
+        let domain_name = self.encode_domain_name();
+        let mut functions = vec![];
+        let mut axioms = vec![];
+        let variant_function = vir::DomainFunc {
+            name: format!("variant${}", self.predicate_name),
+            formal_args: vec![vir::LocalVar::new("self", self.encode_domain_type())],
+            return_type: vir::Type::Int,
+            unique: false,
+            domain_name: domain_name.clone(),
+        };
+        let is_enum = constructors.len() > 1;
+        for (index, constructor) in constructors.iter().enumerate() {
+            let left_vars: Vec<_> = constructor
+                .formal_args
+                .iter()
+                .map(|arg| vir::LocalVar::new(format!("{}_left", arg.name), arg.typ.clone()))
+                .collect();
+            let right_vars: Vec<_> = constructor
+                .formal_args
+                .iter()
+                .map(|arg| vir::LocalVar::new(format!("{}_right", arg.name), arg.typ.clone()))
+                .collect();
+            let left = constructor.apply(left_vars.iter().cloned().map(|v| v.into()).collect());
+            let right = constructor.apply(right_vars.iter().cloned().map(|v| v.into()).collect());
+            if !left_vars.is_empty() {
+                let equalities: Vec<_> = left_vars
+                    .iter()
+                    .zip(right_vars.iter())
+                    .map(|(l, r)| vir::Expr::eq_cmp(l.clone().into(), r.clone().into()))
+                    .collect();
+                let mut vars = left_vars.clone();
+                vars.extend(right_vars);
+                axioms.push(vir::DomainAxiom {
+                    name: format!("{}$injectivity", constructor.name),
+                    expr: vir::Expr::forall(
+                        vars,
+                        vec![vir::Trigger::new(vec![left.clone(), right.clone()])],
+                        vir::Expr::implies(
+                            vir::Expr::eq_cmp(left.clone(), right),
+                            equalities.into_iter().conjoin(),
+                        ),
+                    ),
+                    domain_name: domain_name.clone(),
+                });
+            }
+            if is_enum {
+                let variant = vir::Expr::eq_cmp(
+                    variant_function.apply(vec![left.clone()]),
+                    index.into(),
+                );
+                let expr = if left_vars.is_empty() {
+                    variant
+                } else {
+                    vir::Expr::forall(left_vars, vec![vir::Trigger::new(vec![left])], variant)
+                };
+                axioms.push(vir::DomainAxiom {
+                    name: format!("{}$variant", constructor.name),
+                    expr,
+                    domain_name: domain_name.clone(),
+                });
+            }
+        }
+        functions.extend(constructors);
+        if is_enum {
+            functions.push(variant_function);
+        }
+        vir::Domain {
+            name: domain_name,
+            functions,
+            axioms,
+        }
+    }
+}
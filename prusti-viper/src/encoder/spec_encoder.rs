@@ -5,6 +5,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use encoder::error_manager::ErrorCtxt;
+use encoder::errors::{SpannedEncodingError, UnsupportedFeature};
 use encoder::mir_encoder::MirEncoder;
 use encoder::mir_encoder::PRECONDITION_LABEL;
 use encoder::mir_interpreter::{
@@ -15,6 +16,7 @@ use encoder::pure_function_encoder::PureFunctionBackwardInterpreter;
 use encoder::vir::ExprIterator;
 use encoder::vir;
 use encoder::Encoder;
+use prusti_interface::config;
 use prusti_interface::specifications::*;
 use rustc::hir;
 use rustc::hir::def_id::DefId;
@@ -99,28 +101,28 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> SpecEncoder<'p, 'v, 'r, 'a, 'tcx> {
         let base_expr_ty = typeck_tables.expr_ty(base_expr);
 
         let field_ty = typeck_tables.expr_ty(field_expr);
-        let encoded_type = self.encoder.encode_type(field_ty);
         match base_expr_ty.ty_adt_def() {
-            Some(adt) => match tcx.hir.describe_def(base_expr.id) {
-                Some(def) => {
-                    let num_variants = adt.variants.len();
-                    let place = if num_variants != 1 {
-                        let variant_def = tcx.expect_variant_def(def);
-                        base_place.variant(&variant_def.name.as_str())
-                    } else {
-                        base_place
-                    };
-                    let field = vir::Field::new(field_id.name.as_str().to_string(), encoded_type);
-                    place.field(field)
-                }
-                None => {
-                    let field = vir::Field::new(field_id.name.as_str().to_string(), encoded_type);
-                    base_place.field(field)
+            Some(adt) => {
+                let field = self
+                    .encoder
+                    .encode_struct_field(&field_id.name.as_str(), field_ty);
+                match tcx.hir.describe_def(base_expr.id) {
+                    Some(def) => {
+                        let num_variants = adt.variants.len();
+                        let place = if num_variants != 1 {
+                            let variant_def = tcx.expect_variant_def(def);
+                            base_place.variant(&variant_def.name.as_str())
+                        } else {
+                            base_place
+                        };
+                        place.field(field)
+                    }
+                    None => base_place.field(field),
                 }
-            },
+            }
             None => {
                 let field_name = format!("tuple_{}", field_index);
-                let field = vir::Field::new(field_name, encoded_type);
+                let field = self.encoder.encode_raw_ref_field(field_name, field_ty);
                 base_place.field(field)
             }
         }
@@ -227,14 +229,13 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> SpecEncoder<'p, 'v, 'r, 'a, 'tcx> {
             }
 
             hir::Expr_::ExprUnary(hir::UnOp::UnDeref, ref expr) => {
+                // References and boxes are both encoded as a `val_ref` field that points to
+                // the dereferenced value.
                 let place = self.encode_hir_path(expr);
                 assert!(place.get_type().is_ref());
                 match place {
                     vir::Expr::AddrOf(box base, _typ, _) => base,
-                    _ => {
-                        let type_name: String = self.encoder.encode_type_predicate_use(base_ty);
-                        place.field(vir::Field::new("val_ref", vir::Type::TypedRef(type_name)))
-                    }
+                    _ => place.field(self.encoder.encode_dereference_field(base_ty)),
                 }
             }
 
@@ -265,12 +266,18 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> SpecEncoder<'p, 'v, 'r, 'a, 'tcx> {
                     .field(vir::Field::new("val_bool", vir::Type::Bool))
                     .into(),
 
-                ty::TypeVariants::TyInt(..) | ty::TypeVariants::TyUint(..) => place
+                ty::TypeVariants::TyInt(..)
+                | ty::TypeVariants::TyUint(..)
+                | ty::TypeVariants::TyChar => place
                     .field(vir::Field::new("val_int", vir::Type::Int))
                     .into(),
 
                 ty::TypeVariants::TyTuple(..) | ty::TypeVariants::TyAdt(..) => place.into(),
 
+                ty::TypeVariants::TyRef(_, target_ty, _) => place
+                    .field(self.encoder.encode_dereference_field(target_ty))
+                    .into(),
+
                 ref x => unimplemented!("{:?}", x),
             }
         } else {
@@ -285,6 +292,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> SpecEncoder<'p, 'v, 'r, 'a, 'tcx> {
             ast::LitKind::Int(int_val, ast::LitIntType::Unsigned(_))
             | ast::LitKind::Int(int_val, ast::LitIntType::Unsuffixed) => int_val.into(),
             ast::LitKind::Bool(bool_val) => bool_val.into(),
+            ast::LitKind::Char(char_val) => (char_val as u32).into(),
             ref x => unimplemented!("{:?}", x),
         }
     }
@@ -304,40 +312,166 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> SpecEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 encoded_expr
             }
 
-            hir::Expr_::ExprCall(ref callee, ref _arguments) => {
+            hir::Expr_::ExprCall(ref callee, ref arguments) => {
                 match callee.node {
                     hir::Expr_::ExprPath(hir::QPath::Resolved(_, ref fn_path)) => {
-                        let fn_name = self.path_to_string(fn_path);
-                        if fn_name == "old" {
-                            panic!("Old expressions can not be used in triggers");
-                        /*assert_eq!(arguments.len(), 1);
-                        vir::Expr::labelled_old(
-                            PRECONDITION_LABEL,
-                            self.encode_hir_expr(&arguments[0]),
-                        )*/
-                        } else {
-                            unimplemented!("TODO: function call {:?}", fn_name)
+                        // Old expressions can not be used in triggers
+                        match fn_path.def {
+                            hir::def::Def::Fn(def_id)
+                                if self.encoder.is_pure(def_id)
+                                    && self.path_to_string(fn_path) != "old" =>
+                            {
+                                self.encode_hir_pure_function_call(def_id, arguments, base_expr)
+                            }
+                            _ => self.encode_unsupported_trigger_expr(base_expr),
                         }
                     }
 
-                    ref x => unimplemented!("{:?}", x),
+                    _ => self.encode_unsupported_trigger_expr(base_expr),
                 }
             }
 
-            ref x => unimplemented!("{:?}", x),
+            _ => self.encode_unsupported_trigger_expr(base_expr),
         }
     }
 
-    fn encode_trigger(&self, trigger: &TypedTrigger) -> vir::Trigger {
+    /// Register an expression of a trigger that is not supported (see
+    /// `register_encoding_error`), which is then encoded as `true`.
+    fn encode_unsupported_trigger_expr(&self, expr: &hir::Expr) -> vir::Expr {
+        self.encoder.register_encoding_error(SpannedEncodingError::unsupported(
+            UnsupportedFeature::TriggerExpression,
+            expr.span,
+            format!("{:?}", expr.node),
+        ));
+        true.into()
+    }
+
+    /// Encode the call of a pure function that appears in a trigger.
+    fn encode_hir_pure_function_call(
+        &self,
+        def_id: DefId,
+        arguments: &[hir::Expr],
+        call_expr: &hir::Expr,
+    ) -> vir::Expr {
+        trace!("encode_hir_pure_function_call: {:?}", def_id);
+        let function_name = self.encoder.encode_pure_function_use(def_id);
+        let return_type = self.encoder.encode_pure_function_return_type(def_id);
+        let mut encoded_args = vec![];
+        let mut formal_args = vec![];
+        for (i, arg) in arguments.iter().enumerate() {
+            let arg_ty = self.encoder.env().hir_id_to_type(arg.hir_id);
+            let encoded_arg = match arg.node {
+                // A reference is encoded as the place that it points to
+                hir::Expr_::ExprAddrOf(_, ref expr) => self.encode_hir_path(expr),
+                _ => self.encode_hir_expr(arg),
+            };
+            encoded_args.push(encoded_arg);
+            formal_args.push(vir::LocalVar::new(
+                format!("x{}", i),
+                self.encoder.encode_value_type(arg_ty),
+            ));
+        }
+        let pos = self
+            .encoder
+            .error_manager()
+            .register(call_expr.span, ErrorCtxt::PureFunctionCall);
+        vir::Expr::func_app(function_name, encoded_args, formal_args, return_type, pos)
+    }
+
+    /// Check that a trigger term contains only places, literals and calls of pure functions.
+    /// Arithmetic and other interpreted operations are not allowed in triggers.
+    fn check_trigger_term(&self, expr: &hir::Expr) -> bool {
+        match expr.node {
+            hir::Expr_::ExprLit(..) | hir::Expr_::ExprPath(hir::QPath::Resolved(..)) => true,
+
+            hir::Expr_::ExprUnary(hir::UnOp::UnDeref, ref base)
+            | hir::Expr_::ExprField(ref base, _)
+            | hir::Expr_::ExprAddrOf(_, ref base) => self.check_trigger_term(base),
+
+            hir::Expr_::ExprCall(ref callee, ref arguments) => {
+                let is_pure_call = match callee.node {
+                    hir::Expr_::ExprPath(hir::QPath::Resolved(_, ref fn_path)) => match fn_path.def {
+                        hir::def::Def::Fn(def_id) => {
+                            self.encoder.is_pure(def_id)
+                        }
+                        _ => false,
+                    },
+                    _ => false,
+                };
+                if !is_pure_call {
+                    self.encoder.env().span_err(
+                        expr.span,
+                        "[Prusti] only calls of pure functions are allowed in quantifier triggers",
+                    );
+                    return false;
+                }
+                arguments.iter().all(|arg| self.check_trigger_term(arg))
+            }
+
+            hir::Expr_::ExprBinary(..) | hir::Expr_::ExprUnary(..) => {
+                self.encoder.env().span_err(
+                    expr.span,
+                    "[Prusti] arithmetic operations are not allowed in quantifier triggers",
+                );
+                false
+            }
+
+            _ => {
+                self.encoder.env().span_err(
+                    expr.span,
+                    "[Prusti] unsupported expression in quantifier trigger",
+                );
+                false
+            }
+        }
+    }
+
+    /// Report the quantifiers that the verifier is likely to instantiate poorly: the ones for
+    /// which no trigger can be inferred, and the ones that contain other quantifiers, which may
+    /// cause matching loops.
+    fn check_quantifier(
+        &self,
+        assertion: &TypedAssertion,
+        vars: &[vir::LocalVar],
+        body: &vir::Expr,
+        has_user_triggers: bool,
+    ) {
+        if !has_user_triggers && vir::Trigger::infer(vars, body).is_none() {
+            self.report_quantifier_issue(
+                assertion,
+                "[Prusti] no valid trigger can be inferred for the quantifier; consider \
+                 providing one as in `forall i: usize :: {f(i)} ...`",
+            );
+        }
+        if body.contains_quantifier() {
+            self.report_quantifier_issue(
+                assertion,
+                "[Prusti] nested quantifiers are likely to cause matching loops",
+            );
+        }
+    }
+
+    fn report_quantifier_issue(&self, assertion: &TypedAssertion, message: &str) {
+        if config::strict_triggers() {
+            self.encoder.env().span_err(assertion.get_spans(), message);
+        } else {
+            self.encoder.env().span_warn(assertion.get_spans(), message);
+        }
+    }
+
+    fn encode_trigger(&self, trigger: &TypedTrigger) -> Option<vir::Trigger> {
         trace!("encode_trigger {:?}", trigger);
+        let terms = trigger.terms();
+        if !terms.iter().all(|expr| self.check_trigger_term(&expr.expr)) {
+            return None;
+        }
         // TODO: `encode_hir_expr` generated also the final `.val_int` field access, that we may not want...
-        vir::Trigger::new(
-            trigger
-                .terms()
+        Some(vir::Trigger::new(
+            terms
                 .iter()
                 .map(|expr| self.encode_hir_expr(&expr.expr))
                 .collect(),
-        )
+        ))
     }
 
     /// Encode a specification item as a single expression.
@@ -367,15 +501,32 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> SpecEncoder<'p, 'v, 'r, 'a, 'tcx> {
                     vir::Expr::eq_cmp(enc(vars.vars[0].hir_id), enc(vars.vars[1].hir_id));
                 vir::Expr::implies(typecond, self.encode_assertion(assertion))
             }
-            box AssertionKind::ForAll(ref vars, ref trigger_set, ref body) => vir::Expr::forall(
-                vars.vars.iter().map(|x| self.encode_hir_arg(x)).collect(),
-                trigger_set
+            box AssertionKind::ForAll(ref vars, ref trigger_set, ref body) => {
+                let encoded_vars: Vec<_> =
+                    vars.vars.iter().map(|x| self.encode_hir_arg(x)).collect();
+                let triggers = trigger_set
                     .triggers()
                     .iter()
-                    .map(|x| self.encode_trigger(x))
-                    .collect(),
-                self.encode_assertion(body),
-            ),
+                    .filter_map(|x| self.encode_trigger(x))
+                    .collect();
+                let encoded_body = self.encode_assertion(body);
+                let has_user_triggers = !trigger_set.triggers().is_empty();
+                self.check_quantifier(assertion, &encoded_vars, &encoded_body, has_user_triggers);
+                vir::Expr::forall(encoded_vars, triggers, encoded_body)
+            }
+            box AssertionKind::Exists(ref vars, ref trigger_set, ref body) => {
+                let encoded_vars: Vec<_> =
+                    vars.vars.iter().map(|x| self.encode_hir_arg(x)).collect();
+                let triggers = trigger_set
+                    .triggers()
+                    .iter()
+                    .filter_map(|x| self.encode_trigger(x))
+                    .collect();
+                let encoded_body = self.encode_assertion(body);
+                let has_user_triggers = !trigger_set.triggers().is_empty();
+                self.check_quantifier(assertion, &encoded_vars, &encoded_body, has_user_triggers);
+                vir::Expr::exists(encoded_vars, triggers, encoded_body)
+            }
             box AssertionKind::Pledge(ref _reference, ref _lhs, ref _rhs) => {
                 // Pledges are moved inside magic wands, so here we have only true.
                 true.into()
@@ -646,7 +797,7 @@ impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> SpecEncoder<'p, 'v, 'r, 'a, 'tcx> {
             } else {
                 label.clone()
             }
-        });
+        }).remove_redundant_old();
 
         debug!("MIR expr {:?} --> {}", assertion_expr.id, encoded_expr);
         encoded_expr.set_default_pos(
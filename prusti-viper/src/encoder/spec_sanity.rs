@@ -0,0 +1,106 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Sanity checks of specifications.
+//!
+//! A contradictory precondition (e.g. `x > 0 && x < 0`) makes every call of the procedure fail
+//! and its body verify vacuously, and a contradictory type invariant does the same to every
+//! procedure that takes the type. Such mistakes are silent, so each specification can be checked
+//! with a Viper method that assumes it and then asserts `false`: the method verifies if and only
+//! if the specification is unsatisfiable.
+
+use encoder::error_manager::ErrorCtxt;
+use encoder::Encoder;
+use encoder::vir::{self, Successor};
+use prusti_interface::data::ProcedureDefId;
+use rustc::hir::def_id::DefId;
+use rustc::ty;
+use syntax::codemap::Span;
+
+/// The specification assumed by a sanity check.
+#[derive(Clone, Debug)]
+pub enum SpecSanityCheckKind {
+    /// The precondition of a procedure, which also assumes the invariants of the given types.
+    Precondition(ProcedureDefId, Vec<DefId>),
+    /// The invariant of a type.
+    TypeInvariant(DefId),
+}
+
+/// A method that verifies if and only if the checked specification is unsatisfiable.
+#[derive(Clone, Debug)]
+pub struct SpecSanityCheck {
+    pub kind: SpecSanityCheckKind,
+    /// The span of the item that declares the specification.
+    pub span: Span,
+    pub method: vir::CfgMethod,
+}
+
+/// The type with an invariant that is assumed for an argument of type `ty`, if any. The
+/// invariant of a referenced value is assumed as well.
+pub fn get_invariant_ty<'v, 'r, 'a, 'tcx>(
+    encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+    ty: ty::Ty<'tcx>,
+) -> Option<ty::Ty<'tcx>> {
+    match ty.sty {
+        ty::TypeVariants::TyRef(_, inner_ty, _) => get_invariant_ty(encoder, inner_ty),
+        ty::TypeVariants::TyAdt(adt_def, _)
+            if !encoder.get_type_invariants(adt_def.did).is_empty() =>
+        {
+            Some(ty)
+        }
+        _ => None,
+    }
+}
+
+/// Encode the sanity check of the invariant of the type `ty`, which has to be an ADT with an
+/// invariant.
+pub fn encode_invariant_sanity_check<'v, 'r, 'a, 'tcx>(
+    encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+    ty: ty::Ty<'tcx>,
+) -> SpecSanityCheck {
+    let adt_did = ty.ty_adt_def().unwrap().did;
+    let span = encoder.env().get_item_span(adt_did);
+    let predicate_name = encoder.encode_type_predicate_use(ty);
+    let mut method = vir::CfgMethod::new(
+        format!("sanity_check${}", encoder.encode_type_invariant_use(ty)),
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+    );
+    method.add_local_var("self", vir::Type::TypedRef(predicate_name.clone()));
+    let self_var: vir::Expr =
+        vir::LocalVar::new("self", vir::Type::TypedRef(predicate_name.clone())).into();
+    let pos = encoder
+        .error_manager()
+        .register(span, ErrorCtxt::Unexpected);
+    let block = method.add_block(
+        "start",
+        vec![],
+        vec![
+            vir::Stmt::comment("Check the satisfiability of the type invariant"),
+            vir::Stmt::Inhale(
+                vir::Expr::predicate_access_predicate(
+                    predicate_name,
+                    self_var.clone(),
+                    vir::PermAmount::Write,
+                ),
+                vir::FoldingBehaviour::Stmt,
+            ),
+            vir::Stmt::Inhale(
+                encoder.encode_invariant_func_app(ty, self_var),
+                vir::FoldingBehaviour::Stmt,
+            ),
+            vir::Stmt::Assert(false.into(), vir::FoldingBehaviour::Stmt, pos),
+        ],
+    );
+    method.set_successor(block, Successor::Return);
+    SpecSanityCheck {
+        kind: SpecSanityCheckKind::TypeInvariant(adt_did),
+        span,
+        method,
+    }
+}
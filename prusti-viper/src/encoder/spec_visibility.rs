@@ -0,0 +1,98 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Visibility checks of specifications.
+//!
+//! The contract of a procedure that a library exports should only mention what the callers can
+//! see. Otherwise, callers would depend on private fields that they can neither read nor reason
+//! about. The private fields of the types with model fields are checked by `model_fields`.
+
+use encoder::model_fields::{has_model_fields, FieldAccessCollector};
+use encoder::Encoder;
+use prusti_interface::data::ProcedureDefId;
+use prusti_interface::specifications::SpecificationSet;
+use rustc::hir;
+use rustc::hir::def_id::LOCAL_CRATE;
+use rustc::hir::intravisit::Visitor;
+use rustc::session::config::CrateType;
+use rustc::ty;
+
+/// Check that the contract of a procedure exported from a library does not mention fields that
+/// are not visible to its callers. Returns `false` if an error has been reported.
+pub fn check_contract_visibility<'v, 'r, 'a, 'tcx>(
+    encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+    proc_def_id: ProcedureDefId,
+) -> bool {
+    let tcx = encoder.env().tcx();
+    // The procedures of a binary have no callers outside of the crate
+    let is_library = tcx
+        .sess
+        .crate_types
+        .borrow()
+        .iter()
+        .any(|crate_type| *crate_type != CrateType::CrateTypeExecutable);
+    let is_exported = match tcx.hir.as_local_node_id(proc_def_id) {
+        Some(node_id) => tcx.privacy_access_levels(LOCAL_CRATE).is_exported(node_id),
+        None => false,
+    };
+    if !is_library || !is_exported {
+        return true;
+    }
+    let assertions = match encoder.get_spec_by_def_id(proc_def_id) {
+        Some(SpecificationSet::Procedure(ref pres, ref posts)) => pres
+            .iter()
+            .chain(posts.iter())
+            .map(|spec| &spec.assertion)
+            .collect::<Vec<_>>(),
+        _ => return true,
+    };
+
+    let mut collector = FieldAccessCollector { accesses: vec![] };
+    for assertion in assertions {
+        for expr in assertion.get_exprs() {
+            collector.visit_expr(expr);
+        }
+    }
+
+    let mut is_visible = true;
+    for field_access in collector.accesses {
+        let (base_expr, field_name) = match field_access.node {
+            hir::Expr_::ExprField(ref base_expr, ident) => (base_expr, ident),
+            _ => unreachable!(),
+        };
+        let typeck_tables = tcx.typeck_tables_of(field_access.hir_id.owner_def_id());
+        let mut base_ty = typeck_tables.expr_ty_adjusted(base_expr);
+        while let ty::TypeVariants::TyRef(_, target_ty, _) = base_ty.sty {
+            base_ty = target_ty;
+        }
+        let adt_def = match base_ty.ty_adt_def() {
+            Some(adt_def) if adt_def.is_struct() => adt_def,
+            _ => continue,
+        };
+        let field_index = tcx.field_index(field_access.id, typeck_tables);
+        let field = &adt_def.non_enum_variant().fields[field_index];
+        if field.vis == ty::Visibility::Public || has_model_fields(encoder, adt_def.did) {
+            continue;
+        }
+        let adt_name = encoder.env().get_item_name(adt_def.did);
+        encoder.env().span_err_with_help(
+            field_access.span,
+            &format!(
+                "[Prusti: invalid specification] the public contract of `{}` mentions the \
+                 field `{}` of `{}`, which is not visible to its callers",
+                encoder.env().get_item_name(proc_def_id),
+                field_name,
+                adt_name,
+            ),
+            &format!(
+                "consider specifying the field through a public `#[pure]` getter of `{}`",
+                adt_name,
+            ),
+        );
+        is_visible = false;
+    }
+    is_visible
+}
@@ -0,0 +1,284 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The model of strings. The content of a `str` and of a `String` is a value of the Viper
+//! domain `Str`, stored in the field `val_str` of their predicate. The domain provides the length
+//! (in bytes) of a string, the concatenation of two strings, the substring between two indices,
+//! and the string literals. Two literals are equal if and only if they have the same content.
+
+use encoder::vir;
+use encoder::vir::ExprIterator;
+use rustc::ty;
+
+const DOMAIN_NAME: &str = "Str";
+
+/// Returns true if the values of the type are modelled as strings, i.e. for `str` and `String`.
+pub fn is_string_ty(tcx: ty::TyCtxt, ty: ty::Ty) -> bool {
+    match ty.sty {
+        ty::TypeVariants::TyStr => true,
+        ty::TypeVariants::TyAdt(adt_def, _) => {
+            let path = tcx.item_path_str(adt_def.did);
+            path == "std::string::String" || path == "alloc::string::String"
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if the type is a reference to a string, e.g. the type `&str` of a literal.
+pub fn is_string_ref_ty(tcx: ty::TyCtxt, ty: ty::Ty) -> bool {
+    match ty.sty {
+        ty::TypeVariants::TyRef(_, inner_ty, _) => is_string_ty(tcx, inner_ty),
+        _ => false,
+    }
+}
+
+pub struct StringEncoder {
+}
+
+impl StringEncoder {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn encode_domain_type(&self) -> vir::Type {
+        vir::Type::Domain(DOMAIN_NAME.to_string())
+    }
+
+    pub fn encode_value_field(&self) -> vir::Field {
+        vir::Field::new("val_str", self.encode_domain_type())
+    }
+
+    pub fn encode_len(&self, string: vir::Expr) -> vir::Expr {
+        self.len_function().apply(vec![string])
+    }
+
+    pub fn encode_concat(&self, left: vir::Expr, right: vir::Expr) -> vir::Expr {
+        self.concat_function().apply(vec![left, right])
+    }
+
+    pub fn encode_substring(
+        &self,
+        string: vir::Expr,
+        start: vir::Expr,
+        end: vir::Expr,
+    ) -> vir::Expr {
+        self.substring_function().apply(vec![string, start, end])
+    }
+
+    /// Encode the literal with the given identifier and length. Literals with the same content
+    /// have to be given the same identifier.
+    pub fn encode_literal(&self, id: usize, len: u128) -> vir::Expr {
+        self.literal_function().apply(vec![id.into(), len.into()])
+    }
+
+    pub fn encode_domain(&self) -> vir::Domain {
+        let string_var = |name: &str| vir::LocalVar::new(name, self.encode_domain_type());
+        let int_var = |name: &str| vir::LocalVar::new(name, vir::Type::Int);
+        let s = string_var("s");
+        let left = string_var("left");
+        let right = string_var("right");
+        let start = int_var("start");
+        let end = int_var("end");
+
+        let len = |var: &vir::LocalVar| self.encode_len(var.clone().into());
+        let concat = self.encode_concat(left.clone().into(), right.clone().into());
+        let substring =
+            self.encode_substring(s.clone().into(), start.clone().into(), end.clone().into());
+        let whole_substring = self.encode_substring(s.clone().into(), 0.into(), len(&s));
+
+        let mut axioms = vec![];
+        axioms.push((
+            "len$non_negative",
+            vir::Expr::forall(
+                vec![s.clone()],
+                vec![vir::Trigger::new(vec![len(&s)])],
+                vir::Expr::le_cmp(0.into(), len(&s)),
+            ),
+        ));
+        axioms.push((
+            "concat$len",
+            vir::Expr::forall(
+                vec![left.clone(), right.clone()],
+                vec![vir::Trigger::new(vec![concat.clone()])],
+                vir::Expr::eq_cmp(
+                    self.encode_len(concat.clone()),
+                    vir::Expr::add(len(&left), len(&right)),
+                ),
+            ),
+        ));
+        axioms.push((
+            "concat$empty",
+            vir::Expr::forall(
+                vec![left.clone(), right.clone()],
+                vec![vir::Trigger::new(vec![concat.clone()])],
+                vir::Expr::and(
+                    vir::Expr::implies(
+                        vir::Expr::eq_cmp(len(&right), 0.into()),
+                        vir::Expr::eq_cmp(concat.clone(), left.clone().into()),
+                    ),
+                    vir::Expr::implies(
+                        vir::Expr::eq_cmp(len(&left), 0.into()),
+                        vir::Expr::eq_cmp(concat.clone(), right.clone().into()),
+                    ),
+                ),
+            ),
+        ));
+        axioms.push((
+            "concat$substring",
+            vir::Expr::forall(
+                vec![left.clone(), right.clone()],
+                vec![vir::Trigger::new(vec![concat.clone()])],
+                vir::Expr::and(
+                    vir::Expr::eq_cmp(
+                        self.encode_substring(concat.clone(), 0.into(), len(&left)),
+                        left.clone().into(),
+                    ),
+                    vir::Expr::eq_cmp(
+                        self.encode_substring(
+                            concat.clone(),
+                            len(&left),
+                            self.encode_len(concat.clone()),
+                        ),
+                        right.clone().into(),
+                    ),
+                ),
+            ),
+        ));
+        axioms.push((
+            "substring$len",
+            vir::Expr::forall(
+                vec![s.clone(), start.clone(), end.clone()],
+                vec![vir::Trigger::new(vec![substring.clone()])],
+                vir::Expr::implies(
+                    vec![
+                        vir::Expr::le_cmp(0.into(), start.clone().into()),
+                        vir::Expr::le_cmp(start.clone().into(), end.clone().into()),
+                        vir::Expr::le_cmp(end.clone().into(), len(&s)),
+                    ]
+                    .into_iter()
+                    .conjoin(),
+                    vir::Expr::eq_cmp(
+                        self.encode_len(substring.clone()),
+                        vir::Expr::sub(end.clone().into(), start.clone().into()),
+                    ),
+                ),
+            ),
+        ));
+        axioms.push((
+            "substring$whole",
+            vir::Expr::forall(
+                vec![s.clone()],
+                vec![vir::Trigger::new(vec![whole_substring.clone()])],
+                vir::Expr::eq_cmp(whole_substring, s.clone().into()),
+            ),
+        ));
+
+        let id = int_var("id");
+        let length = int_var("length");
+        let other_id = int_var("other_id");
+        let other_length = int_var("other_length");
+        let literal = self
+            .literal_function()
+            .apply(vec![id.clone().into(), length.clone().into()]);
+        let other_literal = self
+            .literal_function()
+            .apply(vec![other_id.clone().into(), other_length.clone().into()]);
+        axioms.push((
+            "literal$len",
+            vir::Expr::forall(
+                vec![id.clone(), length.clone()],
+                vec![vir::Trigger::new(vec![literal.clone()])],
+                vir::Expr::eq_cmp(self.encode_len(literal.clone()), length.clone().into()),
+            ),
+        ));
+        axioms.push((
+            "literal$injectivity",
+            vir::Expr::forall(
+                vec![id.clone(), length, other_id.clone(), other_length],
+                vec![vir::Trigger::new(vec![literal.clone(), other_literal.clone()])],
+                vir::Expr::implies(
+                    vir::Expr::eq_cmp(literal, other_literal),
+                    vir::Expr::eq_cmp(id.into(), other_id.into()),
+                ),
+            ),
+        ));
+
+        vir::Domain {
+            name: DOMAIN_NAME.to_string(),
+            functions: vec![
+                self.len_function(),
+                self.concat_function(),
+                self.substring_function(),
+                self.literal_function(),
+            ],
+            axioms: axioms
+                .into_iter()
+                .map(|(name, expr)| vir::DomainAxiom {
+                    name: format!("str${}", name),
+                    expr,
+                    domain_name: DOMAIN_NAME.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn function(
+        &self,
+        name: &str,
+        formal_args: Vec<vir::LocalVar>,
+        return_type: vir::Type,
+    ) -> vir::DomainFunc {
+        vir::DomainFunc {
+            name: format!("str${}", name),
+            formal_args,
+            return_type,
+            unique: false,
+            domain_name: DOMAIN_NAME.to_string(),
+        }
+    }
+
+    fn len_function(&self) -> vir::DomainFunc {
+        self.function(
+            "len",
+            vec![vir::LocalVar::new("s", self.encode_domain_type())],
+            vir::Type::Int,
+        )
+    }
+
+    fn concat_function(&self) -> vir::DomainFunc {
+        self.function(
+            "concat",
+            vec![
+                vir::LocalVar::new("left", self.encode_domain_type()),
+                vir::LocalVar::new("right", self.encode_domain_type()),
+            ],
+            self.encode_domain_type(),
+        )
+    }
+
+    fn substring_function(&self) -> vir::DomainFunc {
+        self.function(
+            "substring",
+            vec![
+                vir::LocalVar::new("s", self.encode_domain_type()),
+                vir::LocalVar::new("start", vir::Type::Int),
+                vir::LocalVar::new("end", vir::Type::Int),
+            ],
+            self.encode_domain_type(),
+        )
+    }
+
+    fn literal_function(&self) -> vir::DomainFunc {
+        self.function(
+            "literal",
+            vec![
+                vir::LocalVar::new("id", vir::Type::Int),
+                vir::LocalVar::new("length", vir::Type::Int),
+            ],
+            self.encode_domain_type(),
+        )
+    }
+}
@@ -0,0 +1,303 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The termination check of the recursive pure functions. A pure function is encoded as a Viper
+//! function, which is only sound if the Rust function terminates. Hence, each call of a pure
+//! function that is mutually recursive with the caller has to decrease a well-founded measure:
+//! * with `#[decreases="a, b"]`, the measure is the lexicographic tuple of the given integer
+//!   arguments, which have to stay non-negative;
+//! * otherwise, the function has to descend structurally on one of its arguments, i.e. to pass a
+//!   strict part of it (e.g. `&self.next`) at the same position in each recursive call. In a
+//!   mutual recursion of several functions without measures this has to be the first argument.
+//!
+//! A recursive call `f(args)` is encoded as `check(decreases) ? f(args) : f(args)`, where `check`
+//! is a builtin function that requires its argument. Hence Viper checks that the measure
+//! decreases when it checks the well-definedness of the body, in the context of the call.
+
+use encoder::builtin_encoder::BuiltinFunctionKind;
+use encoder::error_manager::ErrorCtxt;
+use encoder::vir;
+use encoder::vir::{ExprFolder, ExprWalker};
+use encoder::Encoder;
+use prusti_interface::data::ProcedureDefId;
+use rustc::mir;
+use rustc::ty;
+use std::collections::{HashMap, HashSet};
+
+/// Wrap the recursive calls in the body of a pure function with the checks that they decrease
+/// the measure of the function. `formal_args` are the arguments of the encoded function.
+pub fn add_termination_checks<'v, 'r, 'a, 'tcx>(
+    encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+    proc_def_id: ProcedureDefId,
+    formal_args: &[vir::LocalVar],
+    body: vir::Expr,
+) -> vir::Expr {
+    let recursive_functions: HashMap<String, ProcedureDefId> =
+        get_recursive_functions(encoder, proc_def_id)
+            .into_iter()
+            .map(|def_id| (encoder.encode_item_name(def_id), def_id))
+            .collect();
+    if recursive_functions.is_empty() {
+        return body;
+    }
+
+    let measure = get_measure(encoder, proc_def_id, true);
+    let structural_arg = if measure.is_some() {
+        None
+    } else if recursive_functions.len() == 1 {
+        find_structural_arg(&body, &recursive_functions, formal_args)
+    } else {
+        Some(0)
+    };
+    let mut checker = TerminationChecker {
+        encoder,
+        proc_def_id,
+        formal_args,
+        recursive_functions,
+        measure,
+        structural_arg,
+    };
+    checker.fold(body)
+}
+
+/// The pure functions that are called by `proc_def_id` and that call it, possibly through other
+/// pure functions. This includes `proc_def_id` itself if it is recursive.
+fn get_recursive_functions<'v, 'r, 'a, 'tcx>(
+    encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+    proc_def_id: ProcedureDefId,
+) -> Vec<ProcedureDefId> {
+    get_reachable_pure_functions(encoder, proc_def_id)
+        .into_iter()
+        .filter(|&def_id| {
+            def_id == proc_def_id
+                || get_reachable_pure_functions(encoder, def_id).contains(&proc_def_id)
+        })
+        .collect()
+}
+
+/// The pure functions with a body that are reachable through calls from the body of `def_id`.
+fn get_reachable_pure_functions<'v, 'r, 'a, 'tcx>(
+    encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+    def_id: ProcedureDefId,
+) -> HashSet<ProcedureDefId> {
+    let mut reachable = HashSet::new();
+    let mut to_visit = vec![def_id];
+    while let Some(caller) = to_visit.pop() {
+        for callee in encoder.env().get_called_procedures(caller) {
+            if callee.is_local()
+                && encoder.is_pure(callee)
+                && !encoder.is_trusted(callee)
+                && reachable.insert(callee)
+            {
+                to_visit.push(callee);
+            }
+        }
+    }
+    reachable
+}
+
+/// The indices of the arguments given by the `#[decreases="a, b"]` attribute of a pure function,
+/// if any. An invalid attribute is reported if `report_errors` is set.
+fn get_measure<'v, 'r, 'a, 'tcx>(
+    encoder: &Encoder<'v, 'r, 'a, 'tcx>,
+    def_id: ProcedureDefId,
+    report_errors: bool,
+) -> Option<Vec<usize>> {
+    let attr = encoder.env().get_attr(def_id, "decreases")?;
+    let procedure = encoder.env().get_procedure(def_id);
+    let mir = procedure.get_mir();
+    let mut measure = vec![];
+    for name in attr.split(',').map(|name| name.trim()) {
+        let arg_index = mir.args_iter().position(|arg| {
+            mir.local_decls[arg].name.map_or(false, |arg_name| arg_name.as_str() == name)
+        });
+        let is_valid = arg_index.map_or(false, |index| {
+            let local_decl = &mir.local_decls[mir.args_iter().nth(index).unwrap()];
+            let is_integer = match local_decl.ty.sty {
+                ty::TypeVariants::TyInt(_) | ty::TypeVariants::TyUint(_) => true,
+                _ => false,
+            };
+            is_integer && local_decl.mutability == mir::Mutability::Not
+        });
+        if !is_valid {
+            if report_errors {
+                encoder.env().span_err(
+                    encoder.env().get_item_span(def_id),
+                    &format!(
+                        "[Prusti: invalid specification] the measure of `#[decreases]` has to \
+                         be a list of immutable integer arguments, but `{}` is not one of them",
+                        name,
+                    ),
+                );
+            }
+            return None;
+        }
+        measure.push(arg_index.unwrap());
+    }
+    Some(measure)
+}
+
+/// Find an argument on which all the recursive calls in `body` descend structurally.
+fn find_structural_arg(
+    body: &vir::Expr,
+    recursive_functions: &HashMap<String, ProcedureDefId>,
+    formal_args: &[vir::LocalVar],
+) -> Option<usize> {
+    struct CallCollector<'c> {
+        recursive_functions: &'c HashMap<String, ProcedureDefId>,
+        calls_args: Vec<Vec<vir::Expr>>,
+    }
+    impl<'c> ExprWalker for CallCollector<'c> {
+        fn walk_func_app(
+            &mut self,
+            name: &str,
+            args: &Vec<vir::Expr>,
+            _formal_args: &Vec<vir::LocalVar>,
+            _return_type: &vir::Type,
+            _pos: &vir::Position,
+        ) {
+            if self.recursive_functions.contains_key(name) {
+                self.calls_args.push(args.clone());
+            }
+            for arg in args {
+                self.walk(arg);
+            }
+        }
+    }
+    let mut collector = CallCollector {
+        recursive_functions,
+        calls_args: vec![],
+    };
+    collector.walk(body);
+    (0..formal_args.len()).find(|&index| {
+        collector
+            .calls_args
+            .iter()
+            .all(|args| is_strict_part(&args[index], &formal_args[index]))
+    })
+}
+
+/// Returns true if `arg` is a strict part of the value referenced by `param`, e.g.
+/// `param.f$next.val_ref`. Dereferencing a reference or a box does not descend.
+fn is_strict_part(arg: &vir::Expr, param: &vir::LocalVar) -> bool {
+    let mut expr = arg;
+    let mut is_strict = false;
+    loop {
+        expr = match expr {
+            vir::Expr::Local(var, _) => return is_strict && var.name == param.name,
+            vir::Expr::Field(base, field, _) => {
+                is_strict |= field.name != "val_ref";
+                base
+            }
+            vir::Expr::Variant(base, _, _) | vir::Expr::AddrOf(base, _, _) => base,
+            _ => return false,
+        };
+    }
+}
+
+struct TerminationChecker<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> {
+    encoder: &'p Encoder<'v, 'r, 'a, 'tcx>,
+    proc_def_id: ProcedureDefId,
+    formal_args: &'p [vir::LocalVar],
+    /// The functions that are mutually recursive with `proc_def_id`, by encoded name.
+    recursive_functions: HashMap<String, ProcedureDefId>,
+    /// The measure given by the `#[decreases]` attribute of `proc_def_id`.
+    measure: Option<Vec<usize>>,
+    /// The argument on which the recursive calls descend structurally, if there is no measure.
+    structural_arg: Option<usize>,
+}
+
+impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> TerminationChecker<'p, 'v, 'r, 'a, 'tcx> {
+    /// Encode the condition under which a call of `callee` with the given arguments decreases the
+    /// measure.
+    fn encode_decreases(&self, callee: ProcedureDefId, args: &[vir::Expr]) -> vir::Expr {
+        let callee_measure = get_measure(self.encoder, callee, false);
+        match (&self.measure, callee_measure) {
+            (Some(measure), Some(callee_measure)) => {
+                if measure.len() != callee_measure.len() {
+                    return false.into();
+                }
+                let values: Vec<_> = callee_measure
+                    .into_iter()
+                    .map(|index| args[index].clone())
+                    .collect();
+                let bounds: Vec<_> = measure
+                    .iter()
+                    .map(|&index| vir::Expr::local(self.formal_args[index].clone()))
+                    .collect();
+                encode_lexicographic_decrease(&values, &bounds)
+            }
+            (None, None) => match self.structural_arg {
+                Some(index) => is_strict_part(&args[index], &self.formal_args[index]).into(),
+                None => false.into(),
+            },
+            _ => false.into(),
+        }
+    }
+}
+
+/// `(0 <= v0 && v0 < b0) || (v0 == b0 && ((0 <= v1 && v1 < b1) || ...))`
+fn encode_lexicographic_decrease(values: &[vir::Expr], bounds: &[vir::Expr]) -> vir::Expr {
+    match (values.split_first(), bounds.split_first()) {
+        (Some((value, values)), Some((bound, bounds))) => {
+            let decreases = vir::Expr::and(
+                vir::Expr::le_cmp(0.into(), value.clone()),
+                vir::Expr::lt_cmp(value.clone(), bound.clone()),
+            );
+            if values.is_empty() {
+                decreases
+            } else {
+                vir::Expr::or(
+                    decreases,
+                    vir::Expr::and(
+                        vir::Expr::eq_cmp(value.clone(), bound.clone()),
+                        encode_lexicographic_decrease(values, bounds),
+                    ),
+                )
+            }
+        }
+        _ => false.into(),
+    }
+}
+
+impl<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> ExprFolder for TerminationChecker<'p, 'v, 'r, 'a, 'tcx> {
+    fn fold_func_app(
+        &mut self,
+        name: String,
+        args: Vec<vir::Expr>,
+        formal_args: Vec<vir::LocalVar>,
+        return_type: vir::Type,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        let args: Vec<_> = args.into_iter().map(|arg| self.fold(arg)).collect();
+        let callee = match self.recursive_functions.get(&name) {
+            Some(&callee) => callee,
+            None => return vir::Expr::FuncApp(name, args, formal_args, return_type, pos),
+        };
+
+        let decreases = self.encode_decreases(callee, &args);
+        let check_pos = if pos.is_default() {
+            self.encoder.error_manager().register(
+                self.encoder.env().get_item_span(self.proc_def_id),
+                ErrorCtxt::PureFunctionTermination,
+            )
+        } else {
+            self.encoder
+                .error_manager()
+                .register_same_span(&pos, ErrorCtxt::PureFunctionTermination)
+        };
+        let check = vir::Expr::func_app(
+            self.encoder
+                .encode_builtin_function_use(BuiltinFunctionKind::TerminationCheck),
+            vec![decreases],
+            vec![vir::LocalVar::new("decreases", vir::Type::Bool)],
+            vir::Type::Bool,
+            check_pos,
+        );
+        let call = vir::Expr::FuncApp(name, args, formal_args, return_type, pos);
+        vir::Expr::ite(check, call.clone(), call)
+    }
+}
@@ -4,8 +4,12 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use encoder::errors::{SpannedEncodingError, UnsupportedFeature};
 use encoder::foldunfold;
+use encoder::ghost_encoder::is_ghost_ty;
+use encoder::rc_encoder::{get_rc_content_ty, is_rc_ty};
 use encoder::spec_encoder::SpecEncoder;
+use encoder::string_encoder::is_string_ty;
 use encoder::utils::range_extract;
 use encoder::utils::PlusOne;
 use encoder::vir;
@@ -14,6 +18,7 @@ use encoder::vir::ExprIterator;
 use encoder::Encoder;
 use prusti_interface::config;
 use prusti_interface::specifications::*;
+use rustc::hir::Mutability;
 use rustc::middle::const_val::ConstVal;
 use rustc::ty;
 use rustc::ty::layout;
@@ -25,6 +30,7 @@ use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use syntax::ast;
 use syntax::attr::SignedInt;
+use syntax_pos::DUMMY_SP;
 
 pub struct TypeEncoder<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> {
     encoder: &'p Encoder<'v, 'r, 'a, 'tcx>,
@@ -33,7 +39,10 @@ pub struct TypeEncoder<'p, 'v: 'p, 'r: 'v, 'a: 'r, 'tcx: 'a> {
 
 impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
     pub fn new(encoder: &'p Encoder<'v, 'r, 'a, 'tcx>, ty: ty::Ty<'tcx>) -> Self {
-        TypeEncoder { encoder, ty }
+        TypeEncoder {
+            encoder,
+            ty: encoder.normalize_projections(ty),
+        }
     }
 
     pub fn encode_type(self) -> vir::Type {
@@ -56,9 +65,26 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 vir::Type::TypedRef(type_name)
             }
 
-            ty::TypeVariants::TyAdt(_, _) | ty::TypeVariants::TyTuple(_) => unimplemented!(),
-
-            ref x => unimplemented!("{:?}", x),
+            // The value of an `Ordering` is its discriminant
+            _ if is_ordering_ty(self.encoder.env().tcx(), self.ty) => vir::Type::Int,
+
+            // The value of a ghost type is a value of its model. The types of the local variables
+            // are checked with `check_ghost_types`, so that there is a span to report.
+            _ if is_ghost_ty(self.encoder.env().tcx(), self.ty) => self
+                .encoder
+                .encode_ghost_type_or_register(self.ty, DUMMY_SP)
+                .encode_domain_type(),
+
+            // The arguments and results of pure functions are checked with
+            // `check_pure_function_signature`, so that there is a span to report.
+            ref x => {
+                self.encoder.register_encoding_error(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::ValueType,
+                    DUMMY_SP,
+                    format!("value type of {:?}", x),
+                ));
+                vir::Type::Int
+            }
         }
     }
 
@@ -77,13 +103,29 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 vir::Field::new("val_ref", vir::Type::TypedRef(type_name))
             }
 
+            _ if is_ordering_ty(self.encoder.env().tcx(), self.ty) => {
+                self.encoder.encode_discriminant_field()
+            }
+
+            _ if is_ghost_ty(self.encoder.env().tcx(), self.ty) => self
+                .encoder
+                .encode_ghost_type_or_register(self.ty, DUMMY_SP)
+                .encode_value_field(),
+
             ty::TypeVariants::TyAdt(_, _) | ty::TypeVariants::TyTuple(_) => unreachable!(),
 
-            ref x => unimplemented!("{:?}", x),
+            ref x => {
+                self.encoder.register_encoding_error(SpannedEncodingError::unsupported(
+                    UnsupportedFeature::ValueType,
+                    DUMMY_SP,
+                    format!("value field of {:?}", x),
+                ));
+                vir::Field::new("val_int", vir::Type::Int)
+            }
         }
     }
 
-    fn get_integer_bounds(&self) -> Option<(vir::Expr, vir::Expr)> {
+    pub fn get_integer_bounds(&self) -> Option<(vir::Expr, vir::Expr)> {
         match self.ty.sty {
             ty::TypeVariants::TyInt(int_ty) => {
                 let bounds = match int_ty {
@@ -108,10 +150,11 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 Some(bounds)
             }
             ty::TypeVariants::TyChar => {
-                // char is always four bytes in size
-                Some((0.into(), 0xFFFFFFFFu32.into()))
+                // A char is a Unicode scalar value
+                Some((0.into(), (std::char::MAX as u32).into()))
             }
             ty::TypeVariants::TyBool | ty::TypeVariants::TyRef(_, _, _) => None,
+            _ if is_ghost_ty(self.encoder.env().tcx(), self.ty) => None,
             ref x => unreachable!("{:?}", x),
         }
     }
@@ -133,6 +176,35 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
         let typ = vir::Type::TypedRef(predicate_name.clone());
 
         match self.ty.sty {
+            _ if is_string_ty(self.encoder.env().tcx(), self.ty) => {
+                // The content of a string is a value of the model of strings
+                vec![vir::Predicate::new_primitive_value(
+                    typ,
+                    self.encoder.encode_string_field(),
+                    None,
+                    false,
+                )]
+            }
+
+            _ if is_ghost_ty(self.encoder.env().tcx(), self.ty) => {
+                // The value of a ghost type is a value of its model
+                vec![vir::Predicate::new_primitive_value(
+                    typ,
+                    self.encoder.encode_value_field(self.ty),
+                    None,
+                    false,
+                )]
+            }
+
+            _ if is_rc_ty(self.encoder.env().tcx(), self.ty) => {
+                // An `Rc<T>` shares the ownership of its content, like a shared reference
+                let content_ty = get_rc_content_ty(self.ty);
+                vec![vir::Predicate::new_shared_reference(
+                    typ,
+                    self.encoder.encode_dereference_field(content_ty),
+                )]
+            }
+
             ty::TypeVariants::TyBool => vec![vir::Predicate::new_primitive_value(
                 typ,
                 self.encoder.encode_value_field(self.ty),
@@ -141,7 +213,8 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
             )],
 
             ty::TypeVariants::TyInt(_) | ty::TypeVariants::TyUint(_) | ty::TypeVariants::TyChar => {
-                let bounds = if config::check_binary_operations() {
+                // There is no arithmetic on characters, so their bounds always hold.
+                let bounds = if config::check_binary_operations() || self.ty.is_char() {
                     self.get_integer_bounds()
                 } else {
                     None
@@ -159,6 +232,13 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 )]
             }
 
+            ty::TypeVariants::TyRef(_, ref ty, Mutability::MutImmutable) => {
+                vec![vir::Predicate::new_shared_reference(
+                    typ,
+                    self.encoder.encode_dereference_field(ty),
+                )]
+            }
+
             ty::TypeVariants::TyRawPtr(ty::TypeAndMut { ref ty, .. })
             | ty::TypeVariants::TyRef(_, ref ty, _) => vec![vir::Predicate::new_struct(
                 typ,
@@ -269,6 +349,17 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 vec![vir::Predicate::new_abstract(typ)]
             }
 
+            ty::TypeVariants::TyProjection(_) => {
+                // Associated types that cannot be normalized are unknown like type parameters
+                vec![vir::Predicate::new_abstract(typ)]
+            }
+
+            ty::TypeVariants::TyDynamic(..) | ty::TypeVariants::TyAnon(..) => {
+                // Trait objects and `impl Trait` types hide the concrete type, so we can only
+                // use the contracts of their traits.
+                vec![vir::Predicate::new_abstract(typ)]
+            }
+
             ref ty_variant => {
                 debug!("Encoding of type '{}' is incomplete", ty_variant);
                 vec![vir::Predicate::new_abstract(typ)]
@@ -374,6 +465,44 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 format!("__TYPARAM__${}$__", param_ty.name.as_str())
             }
 
+            ty::TypeVariants::TyProjection(projection_ty) => {
+                let mut composed_name = vec![
+                    "__ASSOC__".to_string(),
+                    self.encoder.encode_item_name(projection_ty.item_def_id),
+                ];
+                for ty in projection_ty.substs.types() {
+                    composed_name.push(self.encoder.encode_type_predicate_use(ty));
+                }
+                composed_name.push("__".to_string());
+                composed_name.join("$")
+            }
+
+            ty::TypeVariants::TyDynamic(predicates, _) => {
+                let predicates_hash = {
+                    let mut s = DefaultHasher::new();
+                    predicates.hash(&mut s);
+                    s.finish()
+                };
+                format!("dyn${}", predicates_hash)
+            }
+
+            ty::TypeVariants::TyAnon(def_id, substs) => {
+                let subst_hash = {
+                    let mut s = DefaultHasher::new();
+                    substs.hash(&mut s);
+                    s.finish()
+                };
+
+                format!(
+                    "anon${:?}_{}_{}${}${}",
+                    def_id.krate.index(),
+                    def_id.index.address_space().index(),
+                    def_id.index.as_array_index(),
+                    substs.len(),
+                    subst_hash
+                )
+            }
+
             ref x => unimplemented!("{:?}", x),
         }
     }
@@ -388,6 +517,17 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
         let invariant_name = self.encoder.encode_type_invariant_use(self.ty);
 
         let field_invariants = match self.ty.sty {
+            _ if is_string_ty(self.encoder.env().tcx(), self.ty) => vec![],
+
+            _ if is_ghost_ty(self.encoder.env().tcx(), self.ty) => vec![],
+
+            _ if is_rc_ty(self.encoder.env().tcx(), self.ty) => {
+                let content_ty = get_rc_content_ty(self.ty);
+                let elem_field = self.encoder.encode_dereference_field(content_ty);
+                let elem_loc = vir::Expr::from(self_local_var.clone()).field(elem_field);
+                vec![self.encoder.encode_invariant_func_app(content_ty, elem_loc)]
+            }
+
             ty::TypeVariants::TyRawPtr(ty::TypeAndMut { ref ty, .. })
             | ty::TypeVariants::TyRef(_, ref ty, _) => {
                 let elem_field = self.encoder.encode_dereference_field(ty);
@@ -419,9 +559,9 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
                 let mut exprs: Vec<vir::Expr> = vec![];
                 let num_variants = adt_def.variants.len();
                 let tcx = self.encoder.env().tcx();
-                let opt_spec = self.encoder.get_spec_by_def_id(adt_def.did);
+                let type_invariants = self.encoder.get_type_invariants(adt_def.did);
 
-                if let Some(spec) = opt_spec {
+                if !type_invariants.is_empty() {
                     //let encoded_args = vec![vir::Expr::from(self_local_var.clone())];
                     let encoded_args = vec![];
                     let spec_encoder = SpecEncoder::new_simple(self.encoder, &encoded_args);
@@ -430,16 +570,15 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
                         saelf: self_local_var.clone(),
                     };
 
-                    match spec {
-                        SpecificationSet::Struct(items) => {
-                            for item in items {
-                                let enc = spec_encoder.encode_assertion(&item.assertion);
-                                // OPEN TODO: hacky fix here to convert the closure var to "self"...
-                                let enc = hacky_folder.fold(enc);
-                                exprs.push(enc);
-                            }
-                        }
-                        _ => unreachable!(),
+                    // History invariants are encoded in the postconditions of methods
+                    let invariants = type_invariants
+                        .iter()
+                        .filter(|item| item.typ == SpecType::Invariant);
+                    for item in invariants {
+                        let enc = spec_encoder.encode_assertion(&item.assertion);
+                        // OPEN TODO: hacky fix here to convert the closure var to "self"...
+                        let enc = hacky_folder.fold(enc);
+                        exprs.push(enc);
                     }
                 }
 
@@ -529,8 +668,8 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
         let tag_name = self.encoder.encode_type_tag_use(self.ty);
 
         let body = match self.ty.sty {
-            ty::TypeVariants::TyParam(_param_ty) => None,
-            _ => Some((vir::Const::Int((self.ty as *const ty::TyS<'tcx>) as i64)).into()),
+            ty::TypeVariants::TyParam(_param_ty) | ty::TypeVariants::TyProjection(_) => None,
+            _ => Some((vir::Const::Int(self.encoder.get_next_type_tag_value())).into()),
         };
 
         //let precondition = vir::Expr::PredicateAccessPredicate(
@@ -562,6 +701,31 @@ impl<'p, 'v, 'r: 'v, 'a: 'r, 'tcx: 'a> TypeEncoder<'p, 'v, 'r, 'a, 'tcx> {
     }
 }
 
+/// Returns true if the type is `std::cmp::Ordering`, whose values are encoded as the integers
+/// `-1`, `0` and `1` of its discriminant, so that they can be returned by pure functions.
+pub fn is_ordering_ty(tcx: ty::TyCtxt, ty: ty::Ty) -> bool {
+    match ty.sty {
+        ty::TypeVariants::TyAdt(adt_def, _) => {
+            let path = tcx.item_path_str(adt_def.did);
+            path == "std::cmp::Ordering" || path == "core::cmp::Ordering"
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if the values of the type are Viper values (see `encode_value_type`).
+pub fn has_value_type(tcx: ty::TyCtxt, ty: ty::Ty) -> bool {
+    match ty.sty {
+        ty::TypeVariants::TyBool
+        | ty::TypeVariants::TyInt(_)
+        | ty::TypeVariants::TyUint(_)
+        | ty::TypeVariants::TyChar
+        | ty::TypeVariants::TyRawPtr(_)
+        | ty::TypeVariants::TyRef(..) => true,
+        _ => is_ordering_ty(tcx, ty) || is_ghost_ty(tcx, ty),
+    }
+}
+
 /// Compute the values that a discriminant can take.
 pub fn compute_discriminant_values(
     adt_def: &ty::AdtDef,
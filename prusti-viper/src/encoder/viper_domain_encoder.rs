@@ -0,0 +1,120 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! User-supplied Viper domains, to model mathematical theories that cannot be expressed in Rust.
+//!
+//! A trusted pure function marked with `#[viper_domain="D"]` is declared as a function of the
+//! Viper domain `D`, and its Viper function is specified to be equal to the domain function. The
+//! postconditions of the (trusted) functions marked with `#[viper_axiom="D"]` are the axioms of
+//! `D`:
+//!
+//! ```ignore
+//! #[pure]
+//! #[trusted]
+//! #[viper_domain="Factorial"]
+//! fn fact(n: u32) -> u32 { unimplemented!() }
+//!
+//! #[trusted]
+//! #[viper_axiom="Factorial"]
+//! #[ensures="fact(0) == 1"]
+//! #[ensures="forall n: u32 :: {fact(n)} n > 0 ==> fact(n) == n * fact(n - 1)"]
+//! fn fact_definition() {}
+//! ```
+//!
+//! Domain axioms cannot call Viper functions, so in the axioms the calls of the pure functions of
+//! the domains are replaced by the corresponding domain functions. The axioms can only use pure
+//! functions declared in a domain.
+
+use encoder::vir;
+use encoder::vir::ExprFolder;
+use std::collections::HashMap;
+
+/// Encode the domain function that corresponds to the Viper function `function_name`.
+pub fn encode_domain_function(
+    domain_name: &str,
+    function_name: &str,
+    formal_args: Vec<vir::LocalVar>,
+    return_type: vir::Type,
+) -> vir::DomainFunc {
+    vir::DomainFunc {
+        name: format!("{}${}", domain_name, function_name),
+        formal_args,
+        return_type,
+        unique: false,
+        domain_name: domain_name.to_string(),
+    }
+}
+
+/// Replace the calls of Viper functions with the calls of the corresponding domain functions,
+/// which are indexed by the name of the Viper function.
+pub fn replace_function_calls(
+    expr: vir::Expr,
+    domain_functions: &HashMap<String, vir::DomainFunc>,
+) -> vir::Expr {
+    FunctionCallReplacer { domain_functions }.fold(expr)
+}
+
+struct FunctionCallReplacer<'a> {
+    domain_functions: &'a HashMap<String, vir::DomainFunc>,
+}
+
+impl<'a> FunctionCallReplacer<'a> {
+    /// The triggers have to mention the domain functions, like the body
+    fn fold_triggers(&mut self, triggers: Vec<vir::Trigger>) -> Vec<vir::Trigger> {
+        triggers
+            .into_iter()
+            .map(|trigger| {
+                vir::Trigger::new(
+                    trigger
+                        .elements()
+                        .iter()
+                        .cloned()
+                        .map(|term| self.fold(term))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+}
+
+impl<'a> ExprFolder for FunctionCallReplacer<'a> {
+    fn fold_func_app(
+        &mut self,
+        name: String,
+        args: Vec<vir::Expr>,
+        formal_args: Vec<vir::LocalVar>,
+        return_type: vir::Type,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        let args = args.into_iter().map(|arg| self.fold(arg)).collect();
+        match self.domain_functions.get(&name) {
+            Some(domain_function) => vir::Expr::DomainFuncApp(domain_function.clone(), args, pos),
+            None => vir::Expr::FuncApp(name, args, formal_args, return_type, pos),
+        }
+    }
+
+    fn fold_forall(
+        &mut self,
+        vars: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        body: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        let triggers = self.fold_triggers(triggers);
+        vir::Expr::ForAll(vars, triggers, self.fold_boxed(body), pos)
+    }
+
+    fn fold_exists(
+        &mut self,
+        vars: Vec<vir::LocalVar>,
+        triggers: Vec<vir::Trigger>,
+        body: Box<vir::Expr>,
+        pos: vir::Position,
+    ) -> vir::Expr {
+        let triggers = self.fold_triggers(triggers);
+        vir::Expr::Exists(vars, triggers, self.fold_boxed(body), pos)
+    }
+}
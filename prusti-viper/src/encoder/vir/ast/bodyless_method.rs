@@ -7,7 +7,7 @@
 use encoder::vir::ast::*;
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BodylessMethod {
     pub name: String,
     pub formal_args: Vec<LocalVar>,
@@ -4,8 +4,8 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use super::typaram;
 use std::cmp::Ordering;
-use std::collections::HashMap;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::mem::discriminant;
@@ -16,7 +16,7 @@ pub trait WithIdentifier {
 }
 
 /// The identifier of a statement. Used in error reporting.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Position {
     line: i32,
     column: i32,
@@ -63,22 +63,37 @@ mod tests {
 }
 
 /// The permission amount.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PermAmount {
     Read,
     Write,
     /// The permission remaining after ``Read`` was subtracted from ``Write``.
     Remaining,
+    /// An unknown positive amount, which can be required several times for the same place.
+    /// Used by functions, whose shared reference arguments may alias.
+    Wildcard,
 }
 
 impl PermAmount {
     /// Can this permission amount be used in specifications?
     pub fn is_valid_for_specs(&self) -> bool {
         match self {
-            PermAmount::Read | PermAmount::Write => true,
+            PermAmount::Read | PermAmount::Write | PermAmount::Wildcard => true,
             PermAmount::Remaining => false,
         }
     }
+
+    /// The amount of a permission with this amount in the body of a predicate, after unfolding
+    /// the predicate with amount `pred_perm`. The product of two read permissions is
+    /// approximated with a read permission.
+    pub fn scale(self, pred_perm: PermAmount) -> PermAmount {
+        match (self, pred_perm) {
+            (PermAmount::Write, _) => pred_perm,
+            (_, PermAmount::Write) => self,
+            (PermAmount::Read, PermAmount::Read) => PermAmount::Read,
+            _ => PermAmount::Wildcard,
+        }
+    }
 }
 
 impl fmt::Display for PermAmount {
@@ -87,6 +102,7 @@ impl fmt::Display for PermAmount {
             PermAmount::Read => write!(f, "read"),
             PermAmount::Write => write!(f, "write"),
             PermAmount::Remaining => write!(f, "write-read"),
+            PermAmount::Wildcard => write!(f, "wildcard"),
         }
     }
 }
@@ -134,13 +150,15 @@ impl PartialOrd for PermAmount {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Type {
     Int,
     Bool,
     //Ref, // At the moment we don't need this
     /// TypedRef: the first parameter is the name of the predicate that encodes the type
     TypedRef(String),
+    /// Domain: the first parameter is the name of the Viper domain
+    Domain(String),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -148,6 +166,7 @@ pub enum TypeId {
     Int,
     Bool,
     Ref,
+    Domain,
 }
 
 impl fmt::Display for Type {
@@ -157,6 +176,7 @@ impl fmt::Display for Type {
             &Type::Bool => write!(f, "Bool"),
             //&Type::Ref => write!(f, "Ref"),
             &Type::TypedRef(ref name) => write!(f, "Ref({})", name),
+            &Type::Domain(ref name) => write!(f, "Domain({})", name),
         }
     }
 }
@@ -175,6 +195,7 @@ impl Type {
             &Type::Bool => "bool".to_string(),
             &Type::Int => "int".to_string(),
             &Type::TypedRef(ref pred_name) => format!("{}", pred_name),
+            &Type::Domain(ref domain_name) => format!("{}", domain_name),
         }
     }
 
@@ -189,18 +210,13 @@ impl Type {
         }
     }
 
-    /// FIXME: A hack. Replaces all generic types with their instantiations by using string
-    /// substitution.
-    pub fn patch(self, substs: &HashMap<String, String>) -> Self {
+    /// Instantiates the type parameters that occur in the type.
+    pub fn patch(self, substs: &typaram::Substs) -> Self {
         match self {
             Type::Bool => Type::Bool,
             Type::Int => Type::Int,
-            Type::TypedRef(mut predicate_name) => {
-                for (typ, subst) in substs {
-                    predicate_name = predicate_name.replace(typ, subst);
-                }
-                Type::TypedRef(predicate_name)
-            }
+            Type::TypedRef(predicate_name) => Type::TypedRef(substs.apply(&predicate_name)),
+            Type::Domain(domain_name) => Type::Domain(substs.apply(&domain_name)),
         }
     }
 
@@ -209,6 +225,7 @@ impl Type {
             Type::Bool => TypeId::Bool,
             Type::Int => TypeId::Int,
             Type::TypedRef(_) => TypeId::Ref,
+            Type::Domain(_) => TypeId::Domain,
         }
     }
 }
@@ -227,7 +244,7 @@ impl Hash for Type {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct LocalVar {
     pub name: String,
     pub typ: Type,
@@ -254,7 +271,7 @@ impl LocalVar {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Field {
     pub name: String,
     pub typ: Type,
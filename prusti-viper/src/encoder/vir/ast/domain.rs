@@ -0,0 +1,80 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use encoder::vir::ast::*;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Domain {
+    pub name: String,
+    pub functions: Vec<DomainFunc>,
+    pub axioms: Vec<DomainAxiom>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DomainFunc {
+    pub name: String,
+    pub formal_args: Vec<LocalVar>,
+    pub return_type: Type,
+    pub unique: bool,
+    pub domain_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DomainAxiom {
+    pub name: String,
+    pub expr: Expr,
+    pub domain_name: String,
+}
+
+impl WithIdentifier for Domain {
+    fn get_identifier(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl fmt::Display for Domain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "domain {} {{", self.name)?;
+        for function in &self.functions {
+            writeln!(f, "\t{}", function)?;
+        }
+        for axiom in &self.axioms {
+            writeln!(f, "\t{}", axiom)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for DomainFunc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.unique {
+            write!(f, "unique ")?;
+        }
+        write!(f, "function {}(", self.name)?;
+        let mut first = true;
+        for arg in &self.formal_args {
+            if !first {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:?}", arg)?;
+            first = false
+        }
+        write!(f, "): {}", self.return_type)
+    }
+}
+
+impl fmt::Display for DomainAxiom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "axiom {} {{ {} }}", self.name, self.expr)
+    }
+}
+
+impl DomainFunc {
+    pub fn apply(&self, args: Vec<Expr>) -> Expr {
+        Expr::DomainFuncApp(self.clone(), args, Position::default())
+    }
+}
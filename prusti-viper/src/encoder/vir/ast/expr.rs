@@ -4,15 +4,15 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use super::super::borrows::Borrow;
+use super::super::borrows::{serde_maybe_borrow, Borrow};
 use encoder::vir::ast::*;
-use std::collections::HashMap;
+use prusti_interface::config;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::mem;
 use std::mem::discriminant;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Expr {
     /// A local var
     Local(LocalVar, Position),
@@ -25,7 +25,12 @@ pub enum Expr {
     LabelledOld(String, Box<Expr>, Position),
     Const(Const, Position),
     /// lhs, rhs, borrow, position
-    MagicWand(Box<Expr>, Box<Expr>, Option<Borrow>, Position),
+    MagicWand(
+        Box<Expr>,
+        Box<Expr>,
+        #[serde(with = "serde_maybe_borrow")] Option<Borrow>,
+        Position,
+    ),
     /// PredicateAccessPredicate: predicate_name, arg, permission amount
     PredicateAccessPredicate(String, Box<Expr>, PermAmount, Position),
     FieldAccessPredicate(Box<Expr>, PermAmount, Position),
@@ -37,26 +42,30 @@ pub enum Expr {
     Cond(Box<Expr>, Box<Expr>, Box<Expr>, Position),
     /// ForAll: variables, triggers, body
     ForAll(Vec<LocalVar>, Vec<Trigger>, Box<Expr>, Position),
+    /// Exists: variables, triggers, body
+    Exists(Vec<LocalVar>, Vec<Trigger>, Box<Expr>, Position),
     /// let variable == (expr) in body
     LetExpr(LocalVar, Box<Expr>, Box<Expr>, Position),
     /// FuncApp: function_name, args, formal_args, return_type, Viper position
     FuncApp(String, Vec<Expr>, Vec<LocalVar>, Type, Position),
+    /// DomainFuncApp: domain function, args, Viper position
+    DomainFuncApp(DomainFunc, Vec<Expr>, Position),
 }
 
 /// A component that can be used to represent a place as a vector.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PlaceComponent {
     Field(Field, Position),
     Variant(Field, Position),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum UnaryOpKind {
     Not,
     Minus,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BinOpKind {
     EqCmp,
     GtCmp,
@@ -73,7 +82,7 @@ pub enum BinOpKind {
     Implies,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Const {
     Bool(bool),
     Int(i64),
@@ -137,6 +146,20 @@ impl fmt::Display for Expr {
                     .join(", "),
                 body.to_string()
             ),
+            Expr::Exists(ref vars, ref triggers, ref body, ref _pos) => write!(
+                f,
+                "exists {} {} :: {}",
+                vars.iter()
+                    .map(|x| format!("{:?}", x))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                triggers
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                body.to_string()
+            ),
             Expr::LetExpr(ref var, ref expr, ref body, ref _pos) => write!(
                 f,
                 "(let {:?} == ({}) in {})",
@@ -159,6 +182,15 @@ impl fmt::Display for Expr {
                     .collect::<Vec<String>>()
                     .join(", "),
             ),
+            Expr::DomainFuncApp(ref function, ref args, ref _pos) => write!(
+                f,
+                "{}({})",
+                function.name,
+                args.iter()
+                    .map(|f| f.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ),
         }
     }
 }
@@ -219,8 +251,10 @@ impl Expr {
             Expr::Unfolding(_, _, _, _, _, ref p) => p,
             Expr::Cond(_, _, _, ref p) => p,
             Expr::ForAll(_, _, _, ref p) => p,
+            Expr::Exists(_, _, _, ref p) => p,
             Expr::LetExpr(_, _, _, ref p) => p,
             Expr::FuncApp(_, _, _, _, ref p) => p,
+            Expr::DomainFuncApp(_, _, ref p) => p,
         }
     }
 
@@ -244,8 +278,10 @@ impl Expr {
             },
             Expr::Cond(x, y, z, _) => Expr::Cond(x, y, z, pos),
             Expr::ForAll(x, y, z, _) => Expr::ForAll(x, y, z, pos),
+            Expr::Exists(x, y, z, _) => Expr::Exists(x, y, z, pos),
             Expr::LetExpr(x, y, z, _) => Expr::LetExpr(x, y, z, pos),
             Expr::FuncApp(x, y, z, k, _) => Expr::FuncApp(x, y, z, k, pos),
+            Expr::DomainFuncApp(x, y, _) => Expr::DomainFuncApp(x, y, pos),
         }
     }
 
@@ -287,6 +323,15 @@ impl Expr {
     }
 
     pub fn not(expr: Expr) -> Self {
+        Expr::build_not(expr, config::simplify_encoded_expressions())
+    }
+
+    fn build_not(expr: Expr, simplify: bool) -> Self {
+        if simplify {
+            if let Expr::Const(Const::Bool(value), pos) = expr {
+                return Expr::Const(Const::Bool(!value), pos);
+            }
+        }
         Expr::UnaryOp(UnaryOpKind::Not, box expr, Position::default())
     }
 
@@ -358,10 +403,36 @@ impl Expr {
     }
 
     pub fn and(left: Expr, right: Expr) -> Self {
+        Expr::build_and(left, right, config::simplify_encoded_expressions())
+    }
+
+    fn build_and(left: Expr, right: Expr, simplify: bool) -> Self {
+        if simplify {
+            // `true && e` and `e && true` are `e`
+            if left.is_bool_const(true) {
+                return right;
+            }
+            if right.is_bool_const(true) {
+                return left;
+            }
+        }
         Expr::BinOp(BinOpKind::And, box left, box right, Position::default())
     }
 
     pub fn or(left: Expr, right: Expr) -> Self {
+        Expr::build_or(left, right, config::simplify_encoded_expressions())
+    }
+
+    fn build_or(left: Expr, right: Expr, simplify: bool) -> Self {
+        if simplify {
+            // `false || e` and `e || false` are `e`
+            if left.is_bool_const(false) {
+                return right;
+            }
+            if right.is_bool_const(false) {
+                return left;
+            }
+        }
         Expr::BinOp(BinOpKind::Or, box left, box right, Position::default())
     }
 
@@ -370,13 +441,80 @@ impl Expr {
     }
 
     pub fn implies(left: Expr, right: Expr) -> Self {
+        Expr::build_implies(left, right, config::simplify_encoded_expressions())
+    }
+
+    fn build_implies(left: Expr, right: Expr, simplify: bool) -> Self {
+        if simplify {
+            // `true ==> e` is `e`
+            if left.is_bool_const(true) {
+                return right;
+            }
+            // `false ==> e` and `e ==> true` are `true`, unless the dropped expression has to
+            // be checked to be well-defined (e.g. the precondition of a function application,
+            // or the permission of a field access)
+            if (left.is_bool_const(false) && !right.has_func_app())
+                || (right.is_bool_const(true) && left.is_always_well_defined())
+            {
+                return true.into();
+            }
+        }
         Expr::BinOp(BinOpKind::Implies, box left, box right, Position::default())
     }
 
+    /// Is the expression the boolean constant `value`?
+    fn is_bool_const(&self, value: bool) -> bool {
+        match self {
+            &Expr::Const(Const::Bool(b), _) => b == value,
+            _ => false,
+        }
+    }
+
+    /// Is the expression well-defined in every state, i.e. does it need no permissions and
+    /// cannot fail? This holds for local variables, constants and the boolean and comparison
+    /// operations on them.
+    fn is_always_well_defined(&self) -> bool {
+        match self {
+            &Expr::Local(..) | &Expr::Const(..) => true,
+            &Expr::UnaryOp(_, ref expr, _) => expr.is_always_well_defined(),
+            &Expr::BinOp(kind, ref left, ref right, _) => match kind {
+                BinOpKind::Div | BinOpKind::Mod => false,
+                _ => left.is_always_well_defined() && right.is_always_well_defined(),
+            },
+            _ => false,
+        }
+    }
+
+    /// Does the expression contain the application of a function?
+    fn has_func_app(&self) -> bool {
+        struct FuncAppFinder {
+            found: bool,
+        }
+        impl ExprWalker for FuncAppFinder {
+            fn walk_func_app(
+                &mut self,
+                _name: &str,
+                _args: &Vec<Expr>,
+                _formal_args: &Vec<LocalVar>,
+                _return_type: &Type,
+                _pos: &Position
+            ) {
+                self.found = true;
+            }
+        }
+        let mut walker = FuncAppFinder { found: false };
+        walker.walk(self);
+        walker.found
+    }
+
     pub fn forall(vars: Vec<LocalVar>, triggers: Vec<Trigger>, body: Expr) -> Self {
         Expr::ForAll(vars, triggers, box body, Position::default())
     }
 
+    pub fn exists(vars: Vec<LocalVar>, triggers: Vec<Trigger>, body: Expr) -> Self {
+        Expr::Exists(vars, triggers, box body, Position::default())
+    }
+
     pub fn ite(guard: Expr, left: Expr, right: Expr) -> Self {
         Expr::Cond(box guard, box left, box right, Position::default())
     }
@@ -703,6 +841,34 @@ impl Expr {
         !walker.non_pure
     }
 
+    /// Returns `true` if the value of the expression does not depend on the heap, i.e. if it
+    /// does not read fields or apply functions outside of an old expression. Such an
+    /// expression has the same value in every state, e.g. a bound variable or a constant.
+    pub fn is_heap_independent(&self) -> bool {
+        struct HeapAccessFinder {
+            heap_access: bool,
+        }
+        impl ExprWalker for HeapAccessFinder {
+            fn walk_field(&mut self, _receiver: &Expr, _field: &Field, _pos: &Position) {
+                self.heap_access = true;
+            }
+            fn walk_labelled_old(&mut self, _label: &str, _body: &Expr, _pos: &Position) {}
+            fn walk_func_app(
+                &mut self,
+                _name: &str,
+                _args: &Vec<Expr>,
+                _formal_args: &Vec<LocalVar>,
+                _return_type: &Type,
+                _pos: &Position
+            ) {
+                self.heap_access = true;
+            }
+        }
+        let mut walker = HeapAccessFinder { heap_access: false };
+        walker.walk(self);
+        !walker.heap_access
+    }
+
     /// Only defined for places
     pub fn get_base(&self) -> LocalVar {
         debug_assert!(self.is_place());
@@ -848,39 +1014,13 @@ impl Expr {
         struct PlaceReplacer<'a> {
             target: &'a Expr,
             replacement: &'a Expr,
-            // FIXME: the following fields serve a grotesque hack.
-            //  Purpose:  Generics. When a less-generic function-under-test desugars specs from
-            //            a more-generic function, the vir::Expr contains Local's with __TYPARAM__s,
-            //            but Field's with the function-under-test's concrete types. The purpose is
-            //            the to "fix" the (Viper) predicates of the fields, i.e. replace those
-            //            typarams with local (more) concrete types.
-            //            THIS IS FRAGILE!
-            typaram_substs: Option<typaram::Substs>,
-            subst: bool,
         };
         impl<'a> ExprFolder for PlaceReplacer<'a> {
             fn fold(&mut self, e: Expr) -> Expr {
                 if e.is_place() && &e == self.target {
-                    self.subst = true;
                     self.replacement.clone()
                 } else {
-                    match default_fold_expr(self, e) {
-                        Expr::Field(expr, mut field, pos) => {
-                            if let Some(ts) = &self.typaram_substs {
-                                if self.subst && field.typ.is_ref() {
-                                    let inner1 = field.typ.name();
-                                    let inner2 = ts.apply(&inner1);
-                                    debug!("replacing:\n{}\n{}\n========", &inner1, &inner2);
-                                    field = Field::new(field.name, Type::TypedRef(inner2));
-                                }
-                            }
-                            Expr::Field(expr, field, pos)
-                        }
-                        x => {
-                            self.subst = false;
-                            x
-                        }
-                    }
+                    default_fold_expr(self, e)
                 }
             }
 
@@ -906,59 +1046,220 @@ impl Expr {
                     )
                 }
             }
-        }
-        let typaram_substs = match (&target, &replacement) {
-            (Expr::Local(tv, _), Expr::Local(rv, _)) => {
-                if tv.typ.is_ref() && rv.typ.is_ref() {
-                    debug!(
-                        "learning:\n{}\n{}\n=======",
-                        &target.local_type(),
-                        replacement.local_type()
-                    );
-                    Some(typaram::Substs::learn(
-                        &target.local_type(),
-                        &replacement.local_type(),
-                    ))
+
+            fn fold_exists(
+                &mut self,
+                vars: Vec<LocalVar>,
+                triggers: Vec<Trigger>,
+                body: Box<Expr>,
+                pos: Position,
+            ) -> Expr {
+                if vars.contains(&self.target.get_base()) {
+                    // Do nothing
+                    Expr::Exists(vars, triggers, body, pos)
                 } else {
-                    None
+                    Expr::Exists(
+                        vars,
+                        triggers
+                            .into_iter()
+                            .map(|x| x.replace_place(self.target, self.replacement))
+                            .collect(),
+                        self.fold_boxed(body),
+                        pos,
+                    )
                 }
             }
-            _ => None,
-        };
+        }
         PlaceReplacer {
             target,
             replacement,
-            typaram_substs,
-            subst: false,
         }
         .fold(self)
     }
 
-    /// Replaces expressions like `old[l5](old[l5](_9.val_ref).foo.bar)`
-    /// into `old[l5](_9.val_ref.foo.bar)`
+    /// Normalizes the old expressions:
+    ///
+    /// * nested old expressions are merged, e.g. `old[l5](old[l5](_9.val_ref).foo.bar)`
+    ///   becomes `old[l5](_9.val_ref.foo.bar)` and `old[l1](old[l2](x.f))` becomes
+    ///   `old[l2](x.f)`;
+    /// * old expressions are hoisted over pure operators whose operands are all in the
+    ///   same state, e.g. `old[l](x.f) + old[l](x.g) + 1` becomes `old[l](x.f + x.g + 1)`;
+    /// * inside the body of quantifiers old expressions are instead pushed down to the
+    ///   places, because that is the form expected by `optimiser::rewrite`. An old
+    ///   expression around a quantifier without triggers is moved into its body.
     pub fn remove_redundant_old(self) -> Self {
-        struct RedundantOldRemover {
+        struct OldNormalizer {
             current_label: Option<String>,
+            in_quantifier: bool,
         };
-        impl ExprFolder for RedundantOldRemover {
+        impl OldNormalizer {
+            /// If all the expressions are pure and either constants or old expressions with
+            /// the same label, returns the label.
+            fn common_label(&self, exprs: &[&Expr]) -> Option<String> {
+                if self.in_quantifier {
+                    return None;
+                }
+                let mut common_label: Option<&String> = None;
+                for expr in exprs {
+                    match **expr {
+                        Expr::Const(..) => {}
+                        Expr::LabelledOld(ref label, _, _) if expr.is_pure() => {
+                            if common_label.map_or(false, |l| l != label) {
+                                return None;
+                            }
+                            common_label = Some(label);
+                        }
+                        _ => return None,
+                    }
+                }
+                common_label.cloned()
+            }
+        }
+        fn strip_old(expr: Expr) -> Box<Expr> {
+            match expr {
+                Expr::LabelledOld(_, base, _) => base,
+                expr => box expr,
+            }
+        }
+        impl ExprFolder for OldNormalizer {
             fn fold_labelled_old(&mut self, label: String, base: Box<Expr>, pos: Position) -> Expr {
+                if Some(&label) == self.current_label.as_ref() {
+                    return self.fold(*base);
+                }
                 let old_current_label = mem::replace(&mut self.current_label, Some(label.clone()));
-                let new_base = default_fold_expr(self, *base);
-                let new_expr = if Some(label.clone()) == old_current_label {
-                    new_base
-                } else {
-                    new_base.old(label).set_pos(pos)
-                };
+                let new_base = self.fold(*base);
                 self.current_label = old_current_label;
-                new_expr
+                match new_base {
+                    Expr::Const(..) => new_base,
+                    Expr::ForAll(vars, triggers, box body, forall_pos) => {
+                        if triggers.is_empty() {
+                            let new_body = body.push_old_to_places(&label);
+                            Expr::ForAll(vars, triggers, box new_body, forall_pos)
+                        } else {
+                            Expr::ForAll(vars, triggers, box body, forall_pos)
+                                .old(label)
+                                .set_pos(pos)
+                        }
+                    }
+                    Expr::Exists(vars, triggers, box body, exists_pos) => {
+                        if triggers.is_empty() {
+                            let new_body = body.push_old_to_places(&label);
+                            Expr::Exists(vars, triggers, box new_body, exists_pos)
+                        } else {
+                            Expr::Exists(vars, triggers, box body, exists_pos)
+                                .old(label)
+                                .set_pos(pos)
+                        }
+                    }
+                    _ if self.in_quantifier => new_base.push_old_to_places(&label),
+                    _ => new_base.old(label).set_pos(pos),
+                }
+            }
+            fn fold_unary_op(&mut self, kind: UnaryOpKind, arg: Box<Expr>, pos: Position) -> Expr {
+                let arg = self.fold(*arg);
+                match self.common_label(&[&arg]) {
+                    Some(label) => {
+                        Expr::UnaryOp(kind, strip_old(arg), pos.clone()).old(label).set_pos(pos)
+                    }
+                    None => Expr::UnaryOp(kind, box arg, pos),
+                }
+            }
+            fn fold_bin_op(
+                &mut self,
+                kind: BinOpKind,
+                left: Box<Expr>,
+                right: Box<Expr>,
+                pos: Position,
+            ) -> Expr {
+                let left = self.fold(*left);
+                let right = self.fold(*right);
+                match self.common_label(&[&left, &right]) {
+                    Some(label) => {
+                        Expr::BinOp(kind, strip_old(left), strip_old(right), pos.clone())
+                            .old(label)
+                            .set_pos(pos)
+                    }
+                    None => Expr::BinOp(kind, box left, box right, pos),
+                }
+            }
+            fn fold_cond(
+                &mut self,
+                guard: Box<Expr>,
+                then_expr: Box<Expr>,
+                else_expr: Box<Expr>,
+                pos: Position,
+            ) -> Expr {
+                let guard = self.fold(*guard);
+                let then_expr = self.fold(*then_expr);
+                let else_expr = self.fold(*else_expr);
+                match self.common_label(&[&guard, &then_expr, &else_expr]) {
+                    Some(label) => {
+                        Expr::Cond(
+                            strip_old(guard),
+                            strip_old(then_expr),
+                            strip_old(else_expr),
+                            pos.clone(),
+                        ).old(label).set_pos(pos)
+                    }
+                    None => Expr::Cond(box guard, box then_expr, box else_expr, pos),
+                }
+            }
+            fn fold_forall(
+                &mut self,
+                vars: Vec<LocalVar>,
+                triggers: Vec<Trigger>,
+                body: Box<Expr>,
+                pos: Position,
+            ) -> Expr {
+                let old_in_quantifier = mem::replace(&mut self.in_quantifier, true);
+                let new_body = self.fold_boxed(body);
+                self.in_quantifier = old_in_quantifier;
+                Expr::ForAll(vars, triggers, new_body, pos)
+            }
+            fn fold_exists(
+                &mut self,
+                vars: Vec<LocalVar>,
+                triggers: Vec<Trigger>,
+                body: Box<Expr>,
+                pos: Position,
+            ) -> Expr {
+                let old_in_quantifier = mem::replace(&mut self.in_quantifier, true);
+                let new_body = self.fold_boxed(body);
+                self.in_quantifier = old_in_quantifier;
+                Expr::Exists(vars, triggers, new_body, pos)
             }
         }
-        RedundantOldRemover {
+        OldNormalizer {
             current_label: None,
+            in_quantifier: false,
         }
         .fold(self)
     }
 
+    /// Pushes `old[label](..)` down over the pure operators of the expression, so that
+    /// `old[l](x.f + x.g)` becomes `old[l](x.f) + old[l](x.g)`.
+    pub fn push_old_to_places(self, label: &str) -> Self {
+        match self {
+            Expr::UnaryOp(kind, box arg, pos) => {
+                Expr::UnaryOp(kind, box arg.push_old_to_places(label), pos)
+            }
+            Expr::BinOp(kind, box left, box right, pos) => Expr::BinOp(
+                kind,
+                box left.push_old_to_places(label),
+                box right.push_old_to_places(label),
+                pos,
+            ),
+            Expr::Cond(box guard, box then_expr, box else_expr, pos) => Expr::Cond(
+                box guard.push_old_to_places(label),
+                box then_expr.push_old_to_places(label),
+                box else_expr.push_old_to_places(label),
+                pos,
+            ),
+            Expr::Const(..) => self,
+            _ => self.old(label),
+        }
+    }
+
     /// Leaves a conjunction of `acc(..)` expressions
     pub fn filter_perm_conjunction(self) -> Self {
         struct PermConjunctionFilter();
@@ -983,8 +1284,10 @@ impl Expr {
                     | Expr::AddrOf(..)
                     | Expr::LabelledOld(..)
                     | Expr::ForAll(..)
+                    | Expr::Exists(..)
                     | Expr::LetExpr(..)
-                    | Expr::FuncApp(..) => true.into(),
+                    | Expr::FuncApp(..)
+                    | Expr::DomainFuncApp(..) => true.into(),
                 }
             }
         }
@@ -1041,16 +1344,6 @@ impl Expr {
         ExprFolderImpl { f }.fold(self)
     }
 
-    pub fn local_type(&self) -> String {
-        match &self {
-            Expr::Local(localvar, _) => match &localvar.typ {
-                Type::TypedRef(str) => str.clone(),
-                _ => panic!("expected Type::TypedRef"),
-            },
-            _ => panic!("expected Expr::Local"),
-        }
-    }
-
     /// Compute the permissions that are needed for this expression to
     /// be successfully evaluated. This is method is used for `fold` and
     /// `exhale` statements inside `package` statements because Silicon
@@ -1088,23 +1381,22 @@ impl Expr {
 
     /// FIXME: A hack. Replaces all generic types with their instantiations by using string
     /// substitution.
-    pub fn patch_types(self, substs: &HashMap<String, String>) -> Self {
+    /// Instantiates the type parameters that occur in the types of the local variables, of the
+    /// fields and of the predicates of the expression.
+    pub fn patch_types(self, substs: &typaram::Substs) -> Self {
         struct TypePatcher<'a> {
-            substs: &'a HashMap<String, String>,
+            substs: &'a typaram::Substs,
         }
         impl<'a> ExprFolder for TypePatcher<'a> {
             fn fold_predicate_access_predicate(
                 &mut self,
-                mut predicate_name: String,
+                predicate_name: String,
                 arg: Box<Expr>,
                 perm_amount: PermAmount,
                 pos: Position,
             ) -> Expr {
-                for (typ, subst) in self.substs {
-                    predicate_name = predicate_name.replace(typ, subst);
-                }
                 Expr::PredicateAccessPredicate(
-                    predicate_name,
+                    self.substs.apply(&predicate_name),
                     self.fold_boxed(arg),
                     perm_amount,
                     pos,
@@ -1114,6 +1406,10 @@ impl Expr {
                 var.typ = var.typ.patch(self.substs);
                 Expr::Local(var, pos)
             }
+            fn fold_field(&mut self, receiver: Box<Expr>, mut field: Field, pos: Position) -> Expr {
+                field.typ = field.typ.patch(self.substs);
+                Expr::Field(self.fold_boxed(receiver), field, pos)
+            }
             fn fold_func_app(
                 &mut self,
                 name: String,
@@ -1197,6 +1493,10 @@ impl PartialEq for Expr {
                 Expr::ForAll(ref self_vars, ref self_triggers, box ref self_expr, _),
                 Expr::ForAll(ref other_vars, ref other_triggers, box ref other_expr, _),
             ) => (self_vars, self_triggers, self_expr) == (other_vars, other_triggers, other_expr),
+            (
+                Expr::Exists(ref self_vars, ref self_triggers, box ref self_expr, _),
+                Expr::Exists(ref other_vars, ref other_triggers, box ref other_expr, _),
+            ) => (self_vars, self_triggers, self_expr) == (other_vars, other_triggers, other_expr),
             (
                 Expr::LetExpr(ref self_var, box ref self_def, box ref self_expr, _),
                 Expr::LetExpr(ref other_var, box ref other_def, box ref other_expr, _),
@@ -1205,6 +1505,10 @@ impl PartialEq for Expr {
                 Expr::FuncApp(ref self_name, ref self_args, _, _, _),
                 Expr::FuncApp(ref other_name, ref other_args, _, _, _),
             ) => (self_name, self_args) == (other_name, other_args),
+            (
+                Expr::DomainFuncApp(ref self_function, ref self_args, _),
+                Expr::DomainFuncApp(ref other_function, ref other_args, _),
+            ) => (self_function, self_args) == (other_function, other_args),
             (
                 Expr::Unfolding(ref self_name, ref self_args, box ref self_base, self_perm, ref self_variant, _),
                 Expr::Unfolding(ref other_name, ref other_args, box ref other_base, other_perm, ref other_variant, _),
@@ -1246,8 +1550,12 @@ impl Hash for Expr {
             Expr::ForAll(ref vars, ref triggers, box ref expr, _) => {
                 (vars, triggers, expr).hash(state)
             }
+            Expr::Exists(ref vars, ref triggers, box ref expr, _) => {
+                (vars, triggers, expr).hash(state)
+            }
             Expr::LetExpr(ref var, box ref def, box ref expr, _) => (var, def, expr).hash(state),
             Expr::FuncApp(ref name, ref args, _, _, _) => (name, args).hash(state),
+            Expr::DomainFuncApp(ref function, ref args, _) => (function, args).hash(state),
             Expr::Unfolding(ref name, ref args, box ref base, perm, ref variant, _) => {
                 (name, args, base, perm, variant).hash(state)
             }
@@ -1366,6 +1674,15 @@ pub trait ExprFolder: Sized {
     ) -> Expr {
         Expr::ForAll(x, y, self.fold_boxed(z), p)
     }
+    fn fold_exists(
+        &mut self,
+        x: Vec<LocalVar>,
+        y: Vec<Trigger>,
+        z: Box<Expr>,
+        p: Position,
+    ) -> Expr {
+        Expr::Exists(x, y, self.fold_boxed(z), p)
+    }
     fn fold_let_expr(
         &mut self,
         var: LocalVar,
@@ -1391,6 +1708,18 @@ pub trait ExprFolder: Sized {
             pos
         )
     }
+    fn fold_domain_func_app(
+        &mut self,
+        function: DomainFunc,
+        args: Vec<Expr>,
+        pos: Position,
+    ) -> Expr {
+        Expr::DomainFuncApp(
+            function,
+            args.into_iter().map(|e| self.fold(e)).collect(),
+            pos
+        )
+    }
 }
 
 pub fn default_fold_expr<T: ExprFolder>(this: &mut T, e: Expr) -> Expr {
@@ -1413,8 +1742,10 @@ pub fn default_fold_expr<T: ExprFolder>(this: &mut T, e: Expr) -> Expr {
         },
         Expr::Cond(x, y, z, p) => this.fold_cond(x, y, z, p),
         Expr::ForAll(x, y, z, p) => this.fold_forall(x, y, z, p),
+        Expr::Exists(x, y, z, p) => this.fold_exists(x, y, z, p),
         Expr::LetExpr(x, y, z, p) => this.fold_let_expr(x, y, z, p),
         Expr::FuncApp(x, y, z, k, p) => this.fold_func_app(x, y, z, k, p),
+        Expr::DomainFuncApp(x, y, p) => this.fold_domain_func_app(x, y, p),
     }
 }
 
@@ -1506,6 +1837,18 @@ pub trait ExprWalker: Sized {
         }
         self.walk(body);
     }
+    fn walk_exists(
+        &mut self,
+        vars: &Vec<LocalVar>,
+        _triggers: &Vec<Trigger>,
+        body: &Expr,
+        _pos: &Position
+    ) {
+        for var in vars {
+            self.walk_local_var(var);
+        }
+        self.walk(body);
+    }
     fn walk_let_expr(&mut self, bound_var: &LocalVar, expr: &Expr, body: &Expr, _pos: &Position) {
         self.walk_local_var(bound_var);
         self.walk(expr);
@@ -1526,6 +1869,19 @@ pub trait ExprWalker: Sized {
             self.walk_local_var(arg);
         }
     }
+    fn walk_domain_func_app(
+        &mut self,
+        function: &DomainFunc,
+        args: &Vec<Expr>,
+        _pos: &Position
+    ) {
+        for arg in args {
+            self.walk(arg)
+        }
+        for arg in &function.formal_args {
+            self.walk_local_var(arg);
+        }
+    }
 }
 
 pub fn default_walk_expr<T: ExprWalker>(this: &mut T, e: &Expr) {
@@ -1548,8 +1904,10 @@ pub fn default_walk_expr<T: ExprWalker>(this: &mut T, e: &Expr) {
         },
         Expr::Cond(ref x, ref y, ref z, ref p) => this.walk_cond(x, y, z, p),
         Expr::ForAll(ref x, ref y, ref z, ref p) => this.walk_forall(x, y, z, p),
+        Expr::Exists(ref x, ref y, ref z, ref p) => this.walk_exists(x, y, z, p),
         Expr::LetExpr(ref x, ref y, ref z, ref p) => this.walk_let_expr(x, y, z, p),
         Expr::FuncApp(ref x, ref y, ref z, ref k, ref p) => this.walk_func_app(x, y, z, k, p),
+        Expr::DomainFuncApp(ref x, ref y, ref p) => this.walk_domain_func_app(x, y, p),
     }
 }
 
@@ -1591,6 +1949,58 @@ impl Expr {
         let mut remover = ReadPermRemover {};
         remover.fold(self)
     }
+
+    /// Replace read permissions with wildcard permissions. For example, if the expression is
+    /// `unfolding acc(P(x), read) in x.f`, then after the transformation it will be:
+    /// `unfolding acc(P(x), wildcard) in x.f`.
+    pub fn make_reads_duplicable(self) -> Self {
+        fn duplicable(perm_amount: PermAmount) -> PermAmount {
+            match perm_amount {
+                PermAmount::Read => PermAmount::Wildcard,
+                _ => perm_amount,
+            }
+        }
+        struct ReadPermReplacer {};
+        impl ExprFolder for ReadPermReplacer {
+            fn fold_predicate_access_predicate(
+                &mut self,
+                name: String,
+                arg: Box<Expr>,
+                perm_amount: PermAmount,
+                p: Position,
+            ) -> Expr {
+                Expr::PredicateAccessPredicate(name, self.fold_boxed(arg), duplicable(perm_amount), p)
+            }
+            fn fold_field_access_predicate(
+                &mut self,
+                reference: Box<Expr>,
+                perm_amount: PermAmount,
+                p: Position,
+            ) -> Expr {
+                Expr::FieldAccessPredicate(self.fold_boxed(reference), duplicable(perm_amount), p)
+            }
+            fn fold_unfolding(
+                &mut self,
+                name: String,
+                args: Vec<Expr>,
+                expr: Box<Expr>,
+                perm: PermAmount,
+                variant: MaybeEnumVariantIndex,
+                p: Position,
+            ) -> Expr {
+                Expr::Unfolding(
+                    name,
+                    args.into_iter().map(|e| self.fold(e)).collect(),
+                    self.fold_boxed(expr),
+                    duplicable(perm),
+                    variant,
+                    p,
+                )
+            }
+        }
+        let mut replacer = ReadPermReplacer {};
+        replacer.fold(self)
+    }
 }
 
 pub trait ExprIterator {
@@ -1635,3 +2045,86 @@ where
         rfold(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag() -> Expr {
+        Expr::local(LocalVar::new("flag", Type::Bool))
+    }
+
+    fn field_access() -> Expr {
+        Expr::local(LocalVar::new("x", Type::TypedRef("Cell".to_string())))
+            .field(Field::new("f", Type::Bool))
+    }
+
+    fn implies(left: Expr, right: Expr) -> Expr {
+        Expr::BinOp(BinOpKind::Implies, box left, box right, Position::default())
+    }
+
+    #[test]
+    fn test_implies_with_true_rhs_and_local_lhs() {
+        assert_eq!(Expr::build_implies(flag(), true.into(), true), Expr::from(true));
+        assert_eq!(
+            Expr::build_implies(flag(), true.into(), false),
+            implies(flag(), true.into())
+        );
+    }
+
+    #[test]
+    fn test_implies_with_true_rhs_keeps_lhs_that_needs_permissions() {
+        let old_field_access = Expr::labelled_old("pre", field_access());
+        for lhs in vec![field_access(), old_field_access] {
+            for &simplify in &[true, false] {
+                assert_eq!(
+                    Expr::build_implies(lhs.clone(), true.into(), simplify),
+                    implies(lhs.clone(), true.into())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_implies_with_constant_lhs() {
+        assert_eq!(Expr::build_implies(true.into(), flag(), true), flag());
+        assert_eq!(Expr::build_implies(false.into(), field_access(), true), Expr::from(true));
+        assert_eq!(
+            Expr::build_implies(true.into(), flag(), false),
+            implies(true.into(), flag())
+        );
+        assert_eq!(
+            Expr::build_implies(false.into(), field_access(), false),
+            implies(false.into(), field_access())
+        );
+    }
+
+    #[test]
+    fn test_and_or_not() {
+        assert_eq!(Expr::build_and(true.into(), field_access(), true), field_access());
+        assert_eq!(Expr::build_or(field_access(), false.into(), true), field_access());
+        assert_eq!(Expr::build_not(true.into(), true), Expr::from(false));
+        assert_eq!(
+            Expr::build_and(true.into(), field_access(), false),
+            Expr::BinOp(
+                BinOpKind::And,
+                box Expr::from(true),
+                box field_access(),
+                Position::default()
+            )
+        );
+        assert_eq!(
+            Expr::build_or(field_access(), false.into(), false),
+            Expr::BinOp(
+                BinOpKind::Or,
+                box field_access(),
+                box Expr::from(false),
+                Position::default()
+            )
+        );
+        assert_eq!(
+            Expr::build_not(true.into(), false),
+            Expr::UnaryOp(UnaryOpKind::Not, box Expr::from(true), Position::default())
+        );
+    }
+}
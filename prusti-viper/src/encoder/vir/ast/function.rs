@@ -8,7 +8,7 @@ use encoder::vir::ast::*;
 use std::collections::HashMap;
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Function {
     pub name: String,
     pub formal_args: Vec<LocalVar>,
@@ -46,6 +46,18 @@ impl fmt::Display for Function {
 }
 
 impl Function {
+    /// Require only wildcard read permissions, so that the function can be applied to aliased
+    /// shared references (e.g. `f(&x, &x)`). Used for the functions marked with
+    /// `#[duplicable_reads]`.
+    pub fn make_reads_duplicable(self) -> Self {
+        Function {
+            pres: self.pres.into_iter().map(|e| e.make_reads_duplicable()).collect(),
+            posts: self.posts.into_iter().map(|e| e.make_reads_duplicable()).collect(),
+            body: self.body.map(|e| e.make_reads_duplicable()),
+            ..self
+        }
+    }
+
     pub fn inline_body(&self, args: Vec<Expr>) -> Expr {
         let subst: HashMap<LocalVar, Expr> = self
             .formal_args
@@ -72,6 +84,7 @@ pub fn compute_identifier(name: &str, formal_args: &[LocalVar], return_type: &Ty
             Type::Int => "$int$",
             Type::Bool => "$bool$",
             Type::TypedRef(ref name) => name,
+            Type::Domain(ref name) => name,
         }
     }
     for arg in formal_args {
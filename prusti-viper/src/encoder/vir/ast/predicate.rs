@@ -7,7 +7,7 @@
 use encoder::vir::ast::*;
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Predicate {
     Struct(StructPredicate),
     Enum(EnumPredicate),
@@ -74,6 +74,15 @@ impl Predicate {
     pub fn new_struct(typ: Type, fields: Vec<Field>) -> Predicate {
         Predicate::Struct(StructPredicate::new(typ, fields))
     }
+    /// Construct a predicate that corresponds to a shared reference. It grants only read
+    /// permission to the target of the reference, so that several shared references to the same
+    /// place can coexist, e.g. in the fields of different structs.
+    pub fn new_shared_reference(typ: Type, field: Field) -> Predicate {
+        Predicate::Struct(StructPredicate::new_with_perm_amounts(
+            typ,
+            vec![(field, PermAmount::Read)],
+        ))
+    }
     /// Construct a predicate that corresponds to a composite type that has zero or more than one
     /// variants.
     pub fn new_enum(
@@ -117,7 +126,7 @@ impl WithIdentifier for Predicate {
 }
 
 /// The predicate for types that have exactly one variant.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct StructPredicate {
     /// The predicate name in Viper.
     pub name: String,
@@ -143,16 +152,25 @@ impl fmt::Display for StructPredicate {
 
 impl StructPredicate {
     pub fn new(typ: Type, fields: Vec<Field>) -> Self {
+        let fields = fields
+            .into_iter()
+            .map(|field| (field, PermAmount::Write))
+            .collect();
+        Self::new_with_perm_amounts(typ, fields)
+    }
+    /// Construct a predicate with write permission to each field, and with the given permission
+    /// amount to the predicate of the value of each field.
+    pub fn new_with_perm_amounts(typ: Type, fields: Vec<(Field, PermAmount)>) -> Self {
         let predicate_name = typ.name();
         let this = Predicate::construct_this(typ);
         let body = fields
             .into_iter()
-            .flat_map(|field| {
+            .flat_map(|(field, perm_amount)| {
                 let predicate_name = field.typed_ref_name().unwrap();
                 let location: Expr = Expr::from(this.clone()).field(field).into();
                 let field_perm = Expr::acc_permission(location.clone(), PermAmount::Write);
                 let pred_perm =
-                    Expr::predicate_access_predicate(predicate_name, location, PermAmount::Write);
+                    Expr::predicate_access_predicate(predicate_name, location, perm_amount);
                 vec![field_perm, pred_perm]
             })
             .conjoin();
@@ -187,7 +205,7 @@ impl WithIdentifier for StructPredicate {
 }
 
 /// The predicate for types that have 0 or more than one variants.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct EnumPredicate {
     /// The predicate name in Viper.
     pub name: String,
@@ -202,7 +220,7 @@ pub struct EnumPredicate {
     pub variants: Vec<(Expr, String, StructPredicate)>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct EnumVariantIndex(String);
 pub type MaybeEnumVariantIndex = Option<EnumVariantIndex>;
 
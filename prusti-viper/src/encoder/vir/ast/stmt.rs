@@ -9,7 +9,7 @@ use super::super::cfg::CfgBlockIndex;
 use encoder::vir::ast::*;
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Stmt {
     Comment(String),
     Label(String),
@@ -48,13 +48,18 @@ pub enum Stmt {
     /// Arguments: the magic wand.
     ApplyMagicWand(Expr, Position),
     /// Expire borrows given in the reborrowing DAG.
+    /// Not serializable: `Program::erase_expire_borrows` replaces it with a comment.
+    #[serde(skip)]
     ExpireBorrows(ReborrowingDAG),
+    /// Exhale the permissions of a local variable whose storage is dead.
+    /// The fold/unfold algorithm replaces it with the exhale of the permissions that are in its state.
+    ExhaleDeadPermissions(LocalVar),
     /// An `if` statement: the guard and the 'then' branch.
     If(Expr, Vec<Stmt>),
 }
 
 /// What folding behaviour should be used?
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FoldingBehaviour {
     /// Use `fold` and `unfold` statements.
     Stmt,
@@ -64,7 +69,7 @@ pub enum FoldingBehaviour {
     None,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AssignKind {
     /// Encodes a Rust copy.
     /// This assignment can be used iff the Viper type of the `lhs` and `rhs` is *not* Ref.
@@ -181,6 +186,8 @@ impl fmt::Display for Stmt {
 
             Stmt::ExpireBorrows(dag) => writeln!(f, "expire_borrows {:?}", dag),
 
+            Stmt::ExhaleDeadPermissions(ref local) => write!(f, "exhale dead permissions {}", local),
+
             Stmt::If(ref guard, ref then_stmts) => {
                 write!(f, "if {} {{", guard)?;
                 if !then_stmts.is_empty() {
@@ -232,26 +239,39 @@ impl Stmt {
 
     pub fn pos(&self) -> Option<&Position> {
         match self {
-            Stmt::PackageMagicWand(_, _, _, _, ref p) => Some(p),
+            Stmt::Exhale(_, ref p)
+            | Stmt::Assert(_, _, ref p)
+            | Stmt::Fold(_, _, _, _, ref p)
+            | Stmt::Obtain(_, ref p)
+            | Stmt::PackageMagicWand(_, _, _, _, ref p)
+            | Stmt::ApplyMagicWand(_, ref p) => Some(p),
             _ => None,
         }
     }
 
     pub fn set_pos(self, pos: Position) -> Self {
         match self {
+            Stmt::Exhale(expr, _) => Stmt::Exhale(expr, pos),
+            Stmt::Assert(expr, folding, _) => Stmt::Assert(expr, folding, pos),
+            Stmt::Fold(pred_name, args, perm, variant, _) => {
+                Stmt::Fold(pred_name, args, perm, variant, pos)
+            },
+            Stmt::Obtain(expr, _) => Stmt::Obtain(expr, pos),
             Stmt::PackageMagicWand(wand, package_body, label, vars, _) => {
                 Stmt::PackageMagicWand(wand, package_body, label, vars, pos)
             },
+            Stmt::ApplyMagicWand(wand, _) => Stmt::ApplyMagicWand(wand, pos),
             x => x,
         }
     }
 
-    // Replace a Position::default() position with `pos`
+    // Replace a Position::default() position of a package statement with `pos`. The positions
+    // of the other statements are set where they are encoded, because they determine the span
+    // and the message of the reported errors.
     pub fn set_default_pos(self, pos: Position) -> Self {
-        if self.pos().iter().any(|x| x.is_default()) {
-            self.set_pos(pos)
-        } else {
-            self
+        match self {
+            Stmt::PackageMagicWand(_, _, _, _, ref p) if p.is_default() => self.set_pos(pos),
+            x => x,
         }
     }
 
@@ -280,6 +300,7 @@ pub trait StmtFolder {
             Stmt::PackageMagicWand(w, s, l, v, p) => self.fold_package_magic_wand(w, s, l, v, p),
             Stmt::ApplyMagicWand(w, p) => self.fold_apply_magic_wand(w, p),
             Stmt::ExpireBorrows(d) => self.fold_expire_borrows(d),
+            Stmt::ExhaleDeadPermissions(v) => self.fold_exhale_dead_permissions(v),
             Stmt::If(g, t) => self.fold_if(g, t),
         }
     }
@@ -384,6 +405,10 @@ pub trait StmtFolder {
         Stmt::ExpireBorrows(dag)
     }
 
+    fn fold_exhale_dead_permissions(&mut self, v: LocalVar) -> Stmt {
+        Stmt::ExhaleDeadPermissions(v)
+    }
+
     fn fold_if(&mut self, g: Expr, t: Vec<Stmt>) -> Stmt {
         Stmt::If(
             self.fold_expr(g),
@@ -411,6 +436,7 @@ pub trait StmtWalker {
             Stmt::PackageMagicWand(w, s, l, v, p) => self.walk_package_magic_wand(w, s, l, v, p),
             Stmt::ApplyMagicWand(w, p) => self.walk_apply_magic_wand(w, p),
             Stmt::ExpireBorrows(d) => self.walk_expire_borrows(d),
+            Stmt::ExhaleDeadPermissions(v) => self.walk_exhale_dead_permissions(v),
             Stmt::If(g, t) => self.walk_if(g, t),
         }
     }
@@ -516,6 +542,10 @@ pub trait StmtWalker {
 
     fn walk_expire_borrows(&mut self, _dag: &ReborrowingDAG) {}
 
+    fn walk_exhale_dead_permissions(&mut self, local_var: &LocalVar) {
+        self.walk_local_var(local_var);
+    }
+
     fn walk_nested_cfg(&mut self, _entry: &CfgBlockIndex, _exit: &CfgBlockIndex) {}
 
     fn walk_if(&mut self, g: &Expr, t: &Vec<Stmt>) {
@@ -7,7 +7,7 @@
 use encoder::vir::ast::*;
 use std::fmt;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Trigger(Vec<Expr>);
 
 impl fmt::Display for Trigger {
@@ -42,3 +42,145 @@ impl Trigger {
         )
     }
 }
+
+impl Trigger {
+    /// Infer a trigger for a quantifier over `vars` with the given body. A trigger is a set of
+    /// function applications that together mention all the quantified variables, in which the
+    /// quantified variables do not appear under arithmetic or logical operations. Returns `None`
+    /// if there is no such trigger, in which case the verifier is likely to never instantiate the
+    /// quantifier.
+    pub fn infer(vars: &[LocalVar], body: &Expr) -> Option<Self> {
+        let mut collector = TriggerTermCollector {
+            vars,
+            nested_vars: vec![],
+            terms: vec![],
+        };
+        collector.walk(body);
+        let terms = collector.terms;
+
+        // Prefer a single term that mentions all the variables.
+        if let Some(term) = terms
+            .iter()
+            .find(|term| vars.iter().all(|var| mentions(term, var)))
+        {
+            return Some(Trigger(vec![term.clone()]));
+        }
+
+        // Otherwise, build a multi-trigger.
+        let mut uncovered_vars: Vec<&LocalVar> = vars.iter().collect();
+        let mut trigger_terms = vec![];
+        for term in terms {
+            if uncovered_vars.iter().any(|var| mentions(&term, var)) {
+                uncovered_vars.retain(|var| !mentions(&term, var));
+                trigger_terms.push(term);
+            }
+        }
+        if uncovered_vars.is_empty() && !trigger_terms.is_empty() {
+            Some(Trigger(trigger_terms))
+        } else {
+            None
+        }
+    }
+}
+
+impl Expr {
+    /// Does the expression contain a quantifier?
+    pub fn contains_quantifier(&self) -> bool {
+        struct QuantifierFinder {
+            found: bool,
+        }
+        impl ExprWalker for QuantifierFinder {
+            fn walk_forall(
+                &mut self,
+                _vars: &Vec<LocalVar>,
+                _triggers: &Vec<Trigger>,
+                _body: &Expr,
+                _pos: &Position,
+            ) {
+                self.found = true;
+            }
+            fn walk_exists(
+                &mut self,
+                _vars: &Vec<LocalVar>,
+                _triggers: &Vec<Trigger>,
+                _body: &Expr,
+                _pos: &Position,
+            ) {
+                self.found = true;
+            }
+        }
+        let mut finder = QuantifierFinder { found: false };
+        finder.walk(self);
+        finder.found
+    }
+}
+
+fn mentions(term: &Expr, var: &LocalVar) -> bool {
+    term.find(&Expr::local(var.clone()))
+}
+
+/// Can the expression be part of a trigger?
+fn is_trigger_safe(expr: &Expr) -> bool {
+    match expr {
+        Expr::Local(..) | Expr::Const(..) => true,
+        Expr::Field(box base, ..)
+        | Expr::Variant(box base, ..)
+        | Expr::AddrOf(box base, ..)
+        | Expr::LabelledOld(_, box base, _) => is_trigger_safe(base),
+        Expr::FuncApp(_, args, ..) | Expr::DomainFuncApp(_, args, _) => {
+            args.iter().all(is_trigger_safe)
+        }
+        _ => false,
+    }
+}
+
+/// Collects the function applications that can be part of a trigger for the given variables.
+struct TriggerTermCollector<'a> {
+    vars: &'a [LocalVar],
+    /// The variables of the nested quantifiers, which cannot appear in a trigger.
+    nested_vars: Vec<LocalVar>,
+    terms: Vec<Expr>,
+}
+
+impl<'a> ExprWalker for TriggerTermCollector<'a> {
+    fn walk(&mut self, expr: &Expr) {
+        match expr {
+            Expr::FuncApp(..) | Expr::DomainFuncApp(..) => {
+                if is_trigger_safe(expr)
+                    && self.vars.iter().any(|var| mentions(expr, var))
+                    && !self.nested_vars.iter().any(|var| mentions(expr, var))
+                {
+                    self.terms.push(expr.clone());
+                }
+            }
+            _ => {}
+        }
+        default_walk_expr(self, expr);
+    }
+
+    fn walk_forall(
+        &mut self,
+        vars: &Vec<LocalVar>,
+        _triggers: &Vec<Trigger>,
+        body: &Expr,
+        _pos: &Position,
+    ) {
+        let depth = self.nested_vars.len();
+        self.nested_vars.extend(vars.iter().cloned());
+        self.walk(body);
+        self.nested_vars.truncate(depth);
+    }
+
+    fn walk_exists(
+        &mut self,
+        vars: &Vec<LocalVar>,
+        _triggers: &Vec<Trigger>,
+        body: &Expr,
+        _pos: &Position,
+    ) {
+        let depth = self.nested_vars.len();
+        self.nested_vars.extend(vars.iter().cloned());
+        self.walk(body);
+        self.nested_vars.truncate(depth);
+    }
+}
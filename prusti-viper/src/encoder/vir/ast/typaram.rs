@@ -7,164 +7,130 @@
 use regex::Regex;
 use std::collections::HashMap;
 
-pub struct Substs {
-    regex: Regex,
-    repls: HashMap<String, String>,
+lazy_static! {
+    static ref TYPARAM_RE: Regex = Regex::new("__TYPARAM__\\$.*?\\$__").unwrap();
 }
 
-fn escape_dollars(s: &str) -> String {
-    s.replace('$', "\\$")
+/// A substitution of type parameters in the names of VIR types (and predicates), e.g. from
+/// `__TYPARAM__$T$__` to `i32`. It is computed from the type substitution of the Rust types
+/// (e.g. the generics of a callee and the types with which the caller instantiates them), so
+/// that the encoding of a generic function can be instantiated at the types of its caller.
+#[derive(Debug, Clone, Default)]
+pub struct Substs {
+    repls: HashMap<String, String>,
 }
 
 impl Substs {
-    /// Takes the string representation of two types: `from` is the generic one; `to` is the more
-    /// concrete one.
-    /// This function will compute what is the type substitution needed to go from `from` to `to`.
-    pub fn learn(from: &str, to: &str) -> Self {
-        lazy_static! {
-            static ref TYPARAM_RE: Regex = Regex::new("(__TYPARAM__\\$(.*?)\\$__)").unwrap();
+    /// Takes pairs of encoded types: the first one is a type parameter, e.g. `__TYPARAM__$T$__`,
+    /// and the second one is the type that replaces it. Pairs whose first type is not a type
+    /// parameter are ignored.
+    pub fn new<I: IntoIterator<Item = (String, String)>>(repls: I) -> Self {
+        Substs {
+            repls: repls
+                .into_iter()
+                .filter(|(typaram, _)| is_typaram(typaram))
+                .collect(),
         }
+    }
 
-        // Start with an empty `repls_regex`
-        let mut repls_regex_str = String::new();
-        repls_regex_str.push('^');
+    pub fn is_empty(&self) -> bool {
+        self.repls.iter().all(|(typaram, subst)| typaram == subst)
+    }
 
-        // Extract the name of type parameters from the `from` string
-        let mut found_typarams = Vec::new();
+    /// Replaces all the type parameters of the substitution that occur in `name`. The type
+    /// parameters are replaced simultaneously, so the replacement of a type parameter is never
+    /// substituted again. Type parameters that are not in the substitution are kept.
+    pub fn apply(&self, name: &str) -> String {
+        let mut new_name = String::new();
         let mut last = 0;
-        for matched_item in TYPARAM_RE.find_iter(from) {
-            repls_regex_str.push_str(&escape_dollars(&from[last..matched_item.start()]));
-            repls_regex_str.push_str("(.*?)");
-            found_typarams.push(matched_item.as_str().to_string());
-            last = matched_item.end();
-        }
-
-        repls_regex_str.push_str(&escape_dollars(&from[last..]));
-        repls_regex_str.push('$');
-        let repls_regex = Regex::new(&repls_regex_str).unwrap();
-
-        // Early return for simple case
-        if from == to {
-            return Substs {
-                regex: TYPARAM_RE.clone(),
-                repls: found_typarams.into_iter().map(|x| (x.clone(), x)).collect(),
-            };
-        }
-
-        // Use `repls_regex` to find typaram replacements
-        let mut repls = HashMap::new();
-        let captures = repls_regex.captures(to).unwrap();
-        for i in 1..captures.len() {
-            let from_typaram = found_typarams[i - 1].to_string();
-            let to_typaram = captures.get(i).unwrap().as_str();
-            let old_entry = repls.insert(from_typaram.clone(), to_typaram.to_string());
-            // What if there was something in `repls`? Check that we didn't change it.
-            if let Some(x) = old_entry {
-                assert!(
-                    to == x,
-                    "Error in learn({:?}, {:?}). from_typaram: {:?}, to_typaram: {:?}, old_entry: {:?}, repls_regex_str: {:?}",
-                    from,
-                    to,
-                    from_typaram,
-                    to_typaram,
-                    Some(x),
-                    repls_regex_str
-                );
+        for matched in TYPARAM_RE.find_iter(name) {
+            new_name.push_str(&name[last..matched.start()]);
+            match self.repls.get(matched.as_str()) {
+                Some(subst) => new_name.push_str(subst),
+                None => new_name.push_str(matched.as_str()),
             }
+            last = matched.end();
         }
-        Substs {
-            regex: TYPARAM_RE.clone(),
-            repls,
-        }
+        new_name.push_str(&name[last..]);
+        new_name
     }
+}
 
-    pub fn apply(&self, inner1: &str) -> String {
-        let mut newstr = String::new();
-        let mut last = 0;
-        for matsh in self.regex.find_iter(inner1) {
-            newstr.push_str(&inner1[last..matsh.start()]);
-            newstr.push_str(&self.repls[matsh.as_str()]);
-            last = matsh.end();
-        }
-        newstr.push_str(&inner1[last..]);
-        newstr
-    }
+/// Is the string the encoding of a type parameter, e.g. `__TYPARAM__$T$__`?
+fn is_typaram(name: &str) -> bool {
+    TYPARAM_RE
+        .find(name)
+        .map_or(false, |matched| matched.start() == 0 && matched.end() == name.len())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn test(outer1: &str, outer2: &str, inner1: &str, inner2: &str) {
-        let substs = Substs::learn(outer1, outer2);
-        let inner2_gen = substs.apply(inner1);
-        assert_eq!(inner2_gen, inner2);
+    fn test(repls: &[(&str, &str)], from: &str, to: &str) {
+        let substs = Substs::new(
+            repls
+                .iter()
+                .map(|(typaram, subst)| (typaram.to_string(), subst.to_string())),
+        );
+        assert_eq!(substs.apply(from), to);
     }
 
     #[test]
     pub fn test1() {
-        let outer1 =
-            "ref$m_generics_basic_3$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$Y$__$_end_";
-        let outer2 =
-            "ref$m_generics_basic_3$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$Z$__$_end_";
-        let inner1 = "m_generics_basic_3$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$Y$__$_end_";
-        let inner2 = "m_generics_basic_3$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$Z$__$_end_";
-        test(outer1, outer2, inner1, inner2);
+        let repls = [("__TYPARAM__$Y$__", "__TYPARAM__$Z$__")];
+        let from = "m_generics_basic_3$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$Y$__$_end_";
+        let to = "m_generics_basic_3$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$Z$__$_end_";
+        test(&repls, from, to);
     }
 
     #[test]
     fn test2() {
-        let outer1 = "ref$m_generics_basic_7$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$__TYPARAM__$B$__$_end_";
-        let outer2 = "ref$m_generics_basic_7$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$B$__$_sep_$__TYPARAM__$A$__$_end_";
-        let inner1 = "m_generics_basic_7$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$__TYPARAM__$B$__$_end_";
-        let inner2 = "m_generics_basic_7$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$B$__$_sep_$__TYPARAM__$A$__$_end_";
-        test(outer1, outer2, inner1, inner2);
+        // The substitution is simultaneous
+        let repls = [
+            ("__TYPARAM__$A$__", "__TYPARAM__$B$__"),
+            ("__TYPARAM__$B$__", "__TYPARAM__$A$__"),
+        ];
+        let from = "m_generics_basic_7$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$__TYPARAM__$B$__$_end_";
+        let to = "m_generics_basic_7$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$B$__$_sep_$__TYPARAM__$A$__$_end_";
+        test(&repls, from, to);
     }
 
     #[test]
     fn test3() {
-        let outer1 = "m_generics_basic_6$$Foo$opensqu$0$closesqu$$_beg_$__TYPARAM__$C$__$_end_";
-        let outer2 = "m_generics_basic_6$$Foo$opensqu$0$closesqu$$_beg_$u128$_end_";
-        let inner1 = "m_generics_basic_6$$BarBaz$opensqu$0$closesqu$$_beg_$__TYPARAM__$C$__$_end_";
-        let inner2 = "m_generics_basic_6$$BarBaz$opensqu$0$closesqu$$_beg_$u128$_end_";
-        test(outer1, outer2, inner1, inner2);
+        let repls = [("__TYPARAM__$C$__", "u128")];
+        let from = "m_generics_basic_6$$BarBaz$opensqu$0$closesqu$$_beg_$__TYPARAM__$C$__$_end_";
+        let to = "m_generics_basic_6$$BarBaz$opensqu$0$closesqu$$_beg_$u128$_end_";
+        test(&repls, from, to);
     }
 
     #[test]
     fn test4() {
-        let outer1 = "ref$m_generics_basic_4$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$__TYPARAM__$B$__$_end_";
-        let outer2 = "ref$m_generics_basic_4$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$C$__$_sep_$i16$_end_";
-        let inner1 = "m_generics_basic_4$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$__TYPARAM__$B$__$_end_";
-        let inner2 =
-            "m_generics_basic_4$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$C$__$_sep_$i16$_end_";
-        test(outer1, outer2, inner1, inner2);
+        // Type parameters that are not substituted are kept
+        let repls = [("__TYPARAM__$B$__", "i16")];
+        let from = "m_generics_basic_4$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$__TYPARAM__$B$__$_end_";
+        let to = "m_generics_basic_4$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$i16$_end_";
+        test(&repls, from, to);
     }
 
     #[test]
     fn test5() {
-        let outer1 = "ref$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$B$__$_sep_$i32$_sep_$__TYPARAM__$C$__$_end_$_sep_$__TYPARAM__$D$__$_end_";
-        let outer2 = "ref$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$i8$_sep_$i32$_sep_$u8$_end_$_sep_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$i16$_sep_$i32$_sep_$i64$_end_$_sep_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$isize$_sep_$i32$_sep_$usize$_end_$_end_";
-        let inner1 = "m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$B$__$_sep_$i32$_sep_$__TYPARAM__$C$__$_end_";
-        let inner2 =
-            "m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$i16$_sep_$i32$_sep_$i64$_end_";
-        test(outer1, outer2, inner1, inner2);
-    }
-
-    #[test]
-    fn test6() {
-        let outer1 = "ref$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$B$__$_sep_$i32$_sep_$__TYPARAM__$C$__$_end_$_sep_$__TYPARAM__$D$__$_end_";
-        let outer2 = "ref$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$i8$_sep_$i32$_sep_$u8$_end_$_sep_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$i16$_sep_$i32$_sep_$i64$_end_$_sep_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$isize$_sep_$i32$_sep_$usize$_end_$_end_";
-        let inner1 = "m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$A$__$_sep_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$B$__$_sep_$i32$_sep_$__TYPARAM__$C$__$_end_$_sep_$__TYPARAM__$D$__$_end_";
-        let inner2 = "m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$i8$_sep_$i32$_sep_$u8$_end_$_sep_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$i16$_sep_$i32$_sep_$i64$_end_$_sep_$m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$isize$_sep_$i32$_sep_$usize$_end_$_end_";
-        test(outer1, outer2, inner1, inner2);
+        let repls = [
+            ("__TYPARAM__$B$__", "i16"),
+            ("__TYPARAM__$C$__", "i64"),
+            ("u8", "i8"),
+        ];
+        let from = "m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$__TYPARAM__$B$__$_sep_$u8$_sep_$__TYPARAM__$C$__$_end_";
+        let to = "m_generics_basic_5$$Number$opensqu$0$closesqu$$_beg_$i16$_sep_$u8$_sep_$i64$_end_";
+        test(&repls, from, to);
     }
 
     #[test]
-    pub fn test7() {
-        let outer1 = "tuple2$__TYPARAM__$T$__$__TYPARAM__$T$__";
-        let outer2 = "tuple2$__TYPARAM__$T$__$__TYPARAM__$T$__";
-        let inner1 = "tuple2$__TYPARAM__$T$__$__TYPARAM__$T$__";
-        let inner2 = "tuple2$__TYPARAM__$T$__$__TYPARAM__$T$__";
-        test(outer1, outer2, inner1, inner2);
+    pub fn test6() {
+        let repls = [("__TYPARAM__$T$__", "__TYPARAM__$T$__")];
+        let from = "tuple2$__TYPARAM__$T$__$__TYPARAM__$T$__";
+        test(&repls, from, from);
+        let identity = vec![("__TYPARAM__$T$__".to_string(), "__TYPARAM__$T$__".to_string())];
+        assert!(Substs::new(identity).is_empty());
     }
 }
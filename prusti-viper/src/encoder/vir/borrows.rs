@@ -182,3 +182,22 @@ impl fmt::Display for DAG {
         write!(f, "ReborrowingDAG")
     }
 }
+
+/// (De)serialization of the borrow of a magic wand as its identifier.
+pub mod serde_maybe_borrow {
+    use super::{borrow_id, Borrow};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        borrow: &Option<Borrow>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        borrow.map(borrow_id).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Borrow>, D::Error> {
+        Ok(Option::<usize>::deserialize(deserializer)?.map(Borrow::from))
+    }
+}
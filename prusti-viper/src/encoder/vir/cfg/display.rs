@@ -9,6 +9,22 @@ use std::fmt;
 
 impl fmt::Display for CfgMethod {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.write_method(f, None)
+    }
+}
+
+impl CfgMethod {
+    /// Pretty-print the method, preceding each statement that has a source position with a
+    /// comment that contains the corresponding line of the Rust program.
+    ///
+    /// `source_lines` contains the lines of the Rust source file, starting from line 1.
+    pub fn to_string_with_source(&self, source_lines: &[&str]) -> String {
+        let mut res = String::new();
+        self.write_method(&mut res, Some(source_lines)).unwrap();
+        res
+    }
+
+    fn write_method<W: fmt::Write>(&self, f: &mut W, source_lines: Option<&[&str]>) -> fmt::Result {
         writeln!(
             f,
             "method {}({})",
@@ -33,6 +49,7 @@ impl fmt::Display for CfgMethod {
             writeln!(f, "    {:?}", local_var)?;
         }
 
+        let mut last_source_line = 0;
         for (index, block) in self.basic_blocks.iter().enumerate() {
             writeln!(
                 f,
@@ -43,6 +60,15 @@ impl fmt::Display for CfgMethod {
                 writeln!(f, "    inv {}", inv)?;
             }
             for stmt in &block.stmts {
+                if let (Some(lines), Some(pos)) = (source_lines, stmt.pos()) {
+                    let line = pos.line();
+                    if line > 0 && line != last_source_line {
+                        if let Some(source_line) = lines.get(line as usize - 1) {
+                            writeln!(f, "    // [line {}] {}", line, source_line.trim())?;
+                        }
+                        last_source_line = line;
+                    }
+                }
                 writeln!(f, "    {}", stmt)?;
             }
             writeln!(f, "    {:?}", block.successor)?;
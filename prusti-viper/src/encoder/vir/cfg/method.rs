@@ -12,7 +12,7 @@ use uuid::Uuid;
 
 pub(super) const RETURN_LABEL: &str = "end_of_method";
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CfgMethod {
     pub(super) uuid: Uuid,
     pub(super) method_name: String,
@@ -27,7 +27,7 @@ pub struct CfgMethod {
     fresh_label_index: i32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CfgBlock {
     // FIXME: Hack, should be pub(super).
     pub(super) invs: Vec<Expr>,
@@ -35,7 +35,7 @@ pub struct CfgBlock {
     pub(in super::super) successor: Successor,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Successor {
     Undefined,
     Return,
@@ -45,7 +45,7 @@ pub enum Successor {
     GotoSwitch(Vec<(Expr, CfgBlockIndex)>, CfgBlockIndex),
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub struct CfgBlockIndex {
     pub(super) method_uuid: Uuid,
     pub(in super::super) block_index: usize,
@@ -154,6 +154,11 @@ impl CfgMethod {
         self.method_name.clone()
     }
 
+    /// The label of the `index`-th basic block.
+    pub fn get_block_label(&self, index: usize) -> &str {
+        &self.basic_blocks_labels[index]
+    }
+
     pub(super) fn block_index(&self, index: usize) -> CfgBlockIndex {
         CfgBlockIndex {
             method_uuid: self.uuid,
@@ -271,6 +276,38 @@ impl CfgMethod {
         self.basic_blocks[index.block_index].successor = successor;
     }
 
+    pub fn get_block_stmts(&self, index: CfgBlockIndex) -> &[Stmt] {
+        assert_eq!(
+            self.uuid, index.method_uuid,
+            "The provided CfgBlockIndex doesn't belong to this CfgMethod"
+        );
+        &self.basic_blocks[index.block_index].stmts
+    }
+
+    /// Find the blocks that are reachable from the `start_block` (included) without going
+    /// through any of the `stop_blocks`.
+    pub fn get_reachable_blocks(
+        &self,
+        start_block: CfgBlockIndex,
+        stop_blocks: &HashSet<CfgBlockIndex>,
+    ) -> Vec<CfgBlockIndex> {
+        let mut visited = vec![false; self.basic_blocks.len()];
+        let mut reachable = vec![];
+        let mut to_visit = vec![start_block];
+        visited[start_block.block_index] = true;
+        while let Some(curr_block_index) = to_visit.pop() {
+            reachable.push(curr_block_index);
+            let curr_block = &self.basic_blocks[curr_block_index.block_index];
+            for successor_block in curr_block.successor.get_following() {
+                if !visited[successor_block.block_index] && !stop_blocks.contains(&successor_block) {
+                    visited[successor_block.block_index] = true;
+                    to_visit.push(successor_block);
+                }
+            }
+        }
+        reachable
+    }
+
     pub fn get_preceding(&self, target_index: CfgBlockIndex) -> Vec<CfgBlockIndex> {
         assert_eq!(
             self.uuid, target_index.method_uuid,
@@ -30,6 +30,12 @@ pub trait CfgReplacer<BranchCtxt: Debug + Clone, Action: CheckNoOpAction + Debug
     /// Callback method called each time the CFG is modified. Useful for debugging purposes.
     fn current_cfg(&self, _cfg: &CfgMethod) {}
 
+    /// Should the visit stop before the next block or statement? The blocks that are not visited
+    /// keep an undefined successor, so the resulting CFG should be discarded.
+    fn is_aborted(&self) -> bool {
+        false
+    }
+
     /// Are two branch context compatible for a back edge?
     fn check_compatible_back_edge(left: &BranchCtxt, right: &BranchCtxt);
 
@@ -110,6 +116,10 @@ pub trait CfgReplacer<BranchCtxt: Debug + Clone, Action: CheckNoOpAction + Debug
         reachable[0] = true;
 
         for curr_index in to_visit {
+            if self.is_aborted() {
+                debug!("Abort the visit of the CFG");
+                break;
+            }
             assert!(!visited[curr_index]);
             visited[curr_index] = true;
 
@@ -202,6 +212,9 @@ pub trait CfgReplacer<BranchCtxt: Debug + Clone, Action: CheckNoOpAction + Debug
 
             // REPLACE statement
             for (stmt_index, stmt) in curr_block.stmts.iter().enumerate() {
+                if self.is_aborted() {
+                    break;
+                }
                 self.current_cfg(&new_cfg);
                 let last_stmt_before_return =
                     stmt_index == curr_block.stmts.len() - 1 && curr_block.successor.is_return();
@@ -224,6 +237,11 @@ pub trait CfgReplacer<BranchCtxt: Debug + Clone, Action: CheckNoOpAction + Debug
                 }
             }
 
+            if self.is_aborted() {
+                debug!("Abort the visit of the CFG in {:?}", curr_block_index);
+                break;
+            }
+
             // REPLACE successor
             self.current_cfg(&new_cfg);
             let (new_stmts, new_successor) =
@@ -7,6 +7,7 @@
 pub use self::ast::*;
 pub use self::cfg::*;
 pub use self::conversions::*;
+pub use self::program::*;
 pub use self::to_viper::*;
 
 mod ast;
@@ -15,5 +16,6 @@ mod cfg;
 mod conversions;
 pub mod fixes;
 pub mod optimisations;
+mod program;
 mod to_viper;
 pub mod utils;
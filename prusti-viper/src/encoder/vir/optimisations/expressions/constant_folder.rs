@@ -0,0 +1,128 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimisation that evaluates operations on constants.
+
+use super::super::super::ast;
+
+/// Evaluate unary and binary operations whose operands are constants, and
+/// remove the boolean operations and conditionals that are decided by a
+/// constant operand.
+///
+/// Division and modulo are not evaluated, because their semantics on negative
+/// integers differs between Rust and Viper. Operations that would overflow
+/// an `i64` are also left unchanged.
+pub fn fold_constants(expr: ast::Expr) -> ast::Expr {
+    ast::ExprFolder::fold(&mut ConstantFolder, expr)
+}
+
+struct ConstantFolder;
+
+impl ast::ExprFolder for ConstantFolder {
+    fn fold_unary_op(
+        &mut self,
+        kind: ast::UnaryOpKind,
+        arg: Box<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        let arg = self.fold_boxed(arg);
+        match (kind, *arg) {
+            (ast::UnaryOpKind::Not, ast::Expr::Const(ast::Const::Bool(value), _)) => {
+                ast::Expr::Const(ast::Const::Bool(!value), pos)
+            }
+            (ast::UnaryOpKind::Minus, ast::Expr::Const(ast::Const::Int(value), arg_pos)) => {
+                match value.checked_neg() {
+                    Some(result) => ast::Expr::Const(ast::Const::Int(result), pos),
+                    None => ast::Expr::UnaryOp(
+                        kind,
+                        box ast::Expr::Const(ast::Const::Int(value), arg_pos),
+                        pos,
+                    ),
+                }
+            }
+            (kind, arg) => ast::Expr::UnaryOp(kind, box arg, pos),
+        }
+    }
+
+    fn fold_bin_op(
+        &mut self,
+        kind: ast::BinOpKind,
+        left: Box<ast::Expr>,
+        right: Box<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        let left = self.fold(*left);
+        let right = self.fold(*right);
+        match (kind, left, right) {
+            (kind, ast::Expr::Const(ast::Const::Int(a), a_pos),
+                ast::Expr::Const(ast::Const::Int(b), b_pos)) => {
+                match evaluate_int_op(kind, a, b) {
+                    Some(result) => ast::Expr::Const(result, pos),
+                    None => ast::Expr::BinOp(
+                        kind,
+                        box ast::Expr::Const(ast::Const::Int(a), a_pos),
+                        box ast::Expr::Const(ast::Const::Int(b), b_pos),
+                        pos,
+                    ),
+                }
+            }
+            (ast::BinOpKind::EqCmp, ast::Expr::Const(ast::Const::Bool(a), _),
+                ast::Expr::Const(ast::Const::Bool(b), _)) => {
+                ast::Expr::Const(ast::Const::Bool(a == b), pos)
+            }
+            (ast::BinOpKind::And, ast::Expr::Const(ast::Const::Bool(true), _), other) |
+            (ast::BinOpKind::And, other, ast::Expr::Const(ast::Const::Bool(true), _)) |
+            (ast::BinOpKind::Or, ast::Expr::Const(ast::Const::Bool(false), _), other) |
+            (ast::BinOpKind::Or, other, ast::Expr::Const(ast::Const::Bool(false), _)) |
+            (ast::BinOpKind::Implies, ast::Expr::Const(ast::Const::Bool(true), _), other) => {
+                other
+            }
+            (ast::BinOpKind::And, ast::Expr::Const(ast::Const::Bool(false), _), _) => {
+                ast::Expr::Const(ast::Const::Bool(false), pos)
+            }
+            (ast::BinOpKind::Or, ast::Expr::Const(ast::Const::Bool(true), _), _) |
+            (ast::BinOpKind::Implies, ast::Expr::Const(ast::Const::Bool(false), _), _) |
+            (ast::BinOpKind::Implies, _, ast::Expr::Const(ast::Const::Bool(true), _)) => {
+                ast::Expr::Const(ast::Const::Bool(true), pos)
+            }
+            (kind, left, right) => ast::Expr::BinOp(kind, box left, box right, pos),
+        }
+    }
+
+    fn fold_cond(
+        &mut self,
+        guard: Box<ast::Expr>,
+        then_expr: Box<ast::Expr>,
+        else_expr: Box<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        match self.fold(*guard) {
+            ast::Expr::Const(ast::Const::Bool(true), _) => self.fold(*then_expr),
+            ast::Expr::Const(ast::Const::Bool(false), _) => self.fold(*else_expr),
+            guard => ast::Expr::Cond(
+                box guard,
+                self.fold_boxed(then_expr),
+                self.fold_boxed(else_expr),
+                pos,
+            ),
+        }
+    }
+}
+
+/// Evaluate an operation on two integers, if this can be done without overflows.
+fn evaluate_int_op(kind: ast::BinOpKind, a: i64, b: i64) -> Option<ast::Const> {
+    match kind {
+        ast::BinOpKind::EqCmp => Some(ast::Const::Bool(a == b)),
+        ast::BinOpKind::GtCmp => Some(ast::Const::Bool(a > b)),
+        ast::BinOpKind::GeCmp => Some(ast::Const::Bool(a >= b)),
+        ast::BinOpKind::LtCmp => Some(ast::Const::Bool(a < b)),
+        ast::BinOpKind::LeCmp => Some(ast::Const::Bool(a <= b)),
+        ast::BinOpKind::Add => a.checked_add(b).map(ast::Const::Int),
+        ast::BinOpKind::Sub => a.checked_sub(b).map(ast::Const::Int),
+        ast::BinOpKind::Mul => a.checked_mul(b).map(ast::Const::Int),
+        _ => None,
+    }
+}
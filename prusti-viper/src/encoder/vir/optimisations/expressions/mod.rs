@@ -0,0 +1,13 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A module that contains optimisations for expressions.
+
+mod constant_folder;
+mod unfolding_remover;
+
+pub use self::constant_folder::fold_constants;
+pub use self::unfolding_remover::remove_redundant_unfoldings;
@@ -0,0 +1,194 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Optimisation that removes unfoldings whose body does not need them.
+
+use super::super::super::ast;
+use super::super::super::borrows::Borrow;
+
+/// Replace `unfolding P(x) in e` by `e` when `e` does not access any place
+/// that is reachable from `x`. Such an unfolding does not provide any
+/// permission that is needed to evaluate `e`. Besides field accesses, the
+/// arguments of function applications, of predicate accesses and of nested
+/// unfoldings are considered accesses, and a magic wand in `e` keeps the
+/// unfolding, because its footprint is not known.
+///
+/// Accesses inside old expressions are also considered, so the optimisation
+/// is conservative.
+pub fn remove_redundant_unfoldings(expr: ast::Expr) -> ast::Expr {
+    ast::ExprFolder::fold(&mut UnfoldingRemover, expr)
+}
+
+struct UnfoldingRemover;
+
+impl ast::ExprFolder for UnfoldingRemover {
+    fn fold_unfolding(
+        &mut self,
+        name: String,
+        args: Vec<ast::Expr>,
+        expr: Box<ast::Expr>,
+        perm: ast::PermAmount,
+        variant: ast::MaybeEnumVariantIndex,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        let body = self.fold_boxed(expr);
+        let is_redundant = args.len() == 1 && args[0].is_simple_place() && {
+            let mut collector = AccessedPlaceCollector {
+                places: Vec::new(),
+                has_magic_wand: false,
+            };
+            ast::ExprWalker::walk(&mut collector, &body);
+            !collector.has_magic_wand
+                && collector.places.iter().all(|place| !place.has_prefix(&args[0]))
+        };
+        if is_redundant {
+            *body
+        } else {
+            ast::Expr::Unfolding(
+                name,
+                args.into_iter().map(|e| self.fold(e)).collect(),
+                body,
+                perm,
+                variant,
+                pos,
+            )
+        }
+    }
+}
+
+/// Collects the places whose fields are accessed or that are passed to functions and predicates.
+struct AccessedPlaceCollector {
+    places: Vec<ast::Expr>,
+    has_magic_wand: bool,
+}
+
+impl AccessedPlaceCollector {
+    fn collect_argument(&mut self, arg: &ast::Expr) {
+        if arg.is_simple_place() {
+            self.places.push(arg.clone());
+        }
+        ast::ExprWalker::walk(self, arg);
+    }
+}
+
+impl ast::ExprWalker for AccessedPlaceCollector {
+    fn walk_field(&mut self, receiver: &ast::Expr, _field: &ast::Field, _pos: &ast::Position) {
+        if receiver.is_simple_place() {
+            self.places.push(receiver.clone());
+        }
+        ast::ExprWalker::walk(self, receiver);
+    }
+
+    fn walk_magic_wand(
+        &mut self,
+        _lhs: &ast::Expr,
+        _rhs: &ast::Expr,
+        _borrow: &Option<Borrow>,
+        _pos: &ast::Position,
+    ) {
+        self.has_magic_wand = true;
+    }
+
+    fn walk_predicate_access_predicate(
+        &mut self,
+        _name: &str,
+        arg: &ast::Expr,
+        _perm_amount: ast::PermAmount,
+        _pos: &ast::Position,
+    ) {
+        self.collect_argument(arg);
+    }
+
+    fn walk_unfolding(
+        &mut self,
+        _name: &str,
+        args: &Vec<ast::Expr>,
+        body: &ast::Expr,
+        _perm: ast::PermAmount,
+        _variant: &ast::MaybeEnumVariantIndex,
+        _pos: &ast::Position,
+    ) {
+        for arg in args {
+            self.collect_argument(arg);
+        }
+        ast::ExprWalker::walk(self, body);
+    }
+
+    fn walk_func_app(
+        &mut self,
+        _name: &str,
+        args: &Vec<ast::Expr>,
+        _formal_args: &Vec<ast::LocalVar>,
+        _return_type: &ast::Type,
+        _pos: &ast::Position,
+    ) {
+        for arg in args {
+            self.collect_argument(arg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list() -> ast::Expr {
+        ast::Expr::local(ast::LocalVar::new("list", ast::Type::TypedRef("List".to_string())))
+    }
+
+    fn next() -> ast::Expr {
+        list().field(ast::Field::new("next", ast::Type::TypedRef("List".to_string())))
+    }
+
+    fn unfold_list(body: ast::Expr) -> ast::Expr {
+        ast::Expr::unfolding(
+            "List".to_string(),
+            vec![list()],
+            body,
+            ast::PermAmount::Read,
+            None,
+        )
+    }
+
+    fn len(arg: ast::Expr) -> ast::Expr {
+        ast::Expr::func_app(
+            "len".to_string(),
+            vec![arg],
+            vec![ast::LocalVar::new("self", ast::Type::TypedRef("List".to_string()))],
+            ast::Type::Int,
+            ast::Position::default(),
+        )
+    }
+
+    #[test]
+    fn test_unused_unfolding_is_removed() {
+        let other = ast::Expr::local(ast::LocalVar::new("other", ast::Type::Int));
+        let expr = unfold_list(other.clone());
+        assert_eq!(remove_redundant_unfoldings(expr), other);
+    }
+
+    #[test]
+    fn test_unfolding_used_by_func_app_is_kept() {
+        let expr = unfold_list(len(next()));
+        assert_eq!(remove_redundant_unfoldings(expr.clone()), expr);
+    }
+
+    #[test]
+    fn test_unfolding_used_by_predicate_access_is_kept() {
+        let expr = unfold_list(ast::Expr::predicate_access_predicate(
+            "List",
+            next(),
+            ast::PermAmount::Read,
+        ));
+        assert_eq!(remove_redundant_unfoldings(expr.clone()), expr);
+    }
+
+    #[test]
+    fn test_unfolding_with_magic_wand_is_kept() {
+        let expr = unfold_list(ast::Expr::magic_wand(true.into(), true.into(), None));
+        assert_eq!(remove_redundant_unfoldings(expr.clone()), expr);
+    }
+}
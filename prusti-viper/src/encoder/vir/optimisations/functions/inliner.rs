@@ -194,3 +194,216 @@ fn inline_into_methods(
         })
         .collect()
 }
+
+/// Inline the calls of small pure functions, binding the arguments with
+/// let expressions:
+///
+/// ```viper
+/// function first(this: Ref): Int
+///     requires acc(Pair(this), wildcard)
+/// {
+///     unfolding acc(Pair(this), wildcard) in this.first.val_int
+/// }
+///
+/// first(x)  ~~>  let this$inlined$1 == (x) in
+///                unfolding acc(Pair(this$inlined$1), wildcard) in
+///                this$inlined$1.first.val_int
+/// ```
+///
+/// Only functions whose precondition consists only of permissions and that
+/// have no postcondition are inlined, so that no check or assumption of the
+/// call is lost: the permissions are still required by the unfoldings of the
+/// inlined body. A function is never inlined in its own body, neither directly
+/// nor through the inlining of other functions.
+pub fn inline_small_functions(
+    methods: Vec<cfg::CfgMethod>,
+    functions: Vec<ast::Function>,
+    max_size: usize,
+) -> (Vec<cfg::CfgMethod>, Vec<ast::Function>) {
+    trace!("[enter] inline_small_functions(max_size={})", max_size);
+    let inlinable_functions: HashMap<String, ast::Function> = functions
+        .iter()
+        .filter(|function| function.is_inlinable(max_size))
+        .map(|function| (function.name.clone(), function.clone()))
+        .collect();
+    let mut inliner = SmallFunctionInliner {
+        functions: &inlinable_functions,
+        inlining: Vec::new(),
+        counter: 0,
+    };
+    let functions = functions
+        .into_iter()
+        .map(|mut function| {
+            inliner.inlining.push(function.name.clone());
+            function.body = function
+                .body
+                .map(|body| ast::ExprFolder::fold(&mut inliner, body));
+            inliner.inlining.pop();
+            function
+        })
+        .collect();
+    let methods = methods
+        .into_iter()
+        .map(|mut method| {
+            for block in &mut method.basic_blocks {
+                let stmts = mem::replace(&mut block.stmts, vec![]);
+                block.stmts = stmts
+                    .into_iter()
+                    .map(|stmt| ast::StmtFolder::fold(&mut inliner, stmt))
+                    .collect();
+                if let cfg::Successor::GotoSwitch(ref mut targets, _) = block.successor {
+                    for &mut (ref mut guard, _) in targets.iter_mut() {
+                        let old_guard = mem::replace(guard, true.into());
+                        *guard = ast::ExprFolder::fold(&mut inliner, old_guard);
+                    }
+                }
+            }
+            method
+        })
+        .collect();
+    (methods, functions)
+}
+
+impl ast::Function {
+    /// Can the calls of the function be replaced by its body?
+    fn is_inlinable(&self, max_size: usize) -> bool {
+        match self.body {
+            Some(ref body) => {
+                self.pres.iter().all(|cond| cond.is_only_permissions())
+                    && self.posts.is_empty()
+                    && body.size() <= max_size
+                    && !body.calls_function(&self.name)
+            }
+            None => false,
+        }
+    }
+}
+
+impl ast::Expr {
+    /// The number of nodes of the expression.
+    fn size(&self) -> usize {
+        struct NodeCounter {
+            count: usize,
+        }
+        impl ast::ExprWalker for NodeCounter {
+            fn walk(&mut self, expr: &ast::Expr) {
+                self.count += 1;
+                ast::default_walk_expr(self, expr);
+            }
+        }
+        let mut counter = NodeCounter { count: 0 };
+        ast::ExprWalker::walk(&mut counter, self);
+        counter.count
+    }
+
+    /// Does the expression contain a call of the given function?
+    fn calls_function(&self, function_name: &str) -> bool {
+        struct CallFinder<'a> {
+            function_name: &'a str,
+            found: bool,
+        }
+        impl<'a> ast::ExprWalker for CallFinder<'a> {
+            fn walk_func_app(
+                &mut self,
+                name: &str,
+                args: &Vec<ast::Expr>,
+                _formal_args: &Vec<ast::LocalVar>,
+                _return_type: &ast::Type,
+                _pos: &ast::Position,
+            ) {
+                if name == self.function_name {
+                    self.found = true;
+                }
+                for arg in args {
+                    self.walk(arg);
+                }
+            }
+        }
+        let mut finder = CallFinder {
+            function_name,
+            found: false,
+        };
+        ast::ExprWalker::walk(&mut finder, self);
+        finder.found
+    }
+}
+
+/// Inline all calls to small functions.
+struct SmallFunctionInliner<'a> {
+    functions: &'a HashMap<String, ast::Function>,
+    /// The functions whose body is being inlined, used to stop at recursive calls.
+    inlining: Vec<String>,
+    /// Used to generate fresh names for the let-bound arguments.
+    counter: usize,
+}
+
+impl<'a> SmallFunctionInliner<'a> {
+    fn inline_call(
+        &mut self,
+        function: &ast::Function,
+        args: Vec<ast::Expr>,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        self.counter += 1;
+        let fresh_vars: Vec<ast::LocalVar> = function
+            .formal_args
+            .iter()
+            .map(|arg| {
+                ast::LocalVar::new(
+                    format!("{}$inlined${}", arg.name, self.counter),
+                    arg.typ.clone(),
+                )
+            })
+            .collect();
+        let renaming: HashMap<ast::LocalVar, ast::LocalVar> = function
+            .formal_args
+            .iter()
+            .cloned()
+            .zip(fresh_vars.iter().cloned())
+            .collect();
+        let body = function.body.clone().unwrap().fold_expr(|expr| {
+            if let ast::Expr::Local(ref var, ref var_pos) = expr {
+                if let Some(fresh_var) = renaming.get(var) {
+                    return ast::Expr::Local(fresh_var.clone(), var_pos.clone());
+                }
+            }
+            expr
+        });
+        self.inlining.push(function.name.clone());
+        let body = ast::ExprFolder::fold(self, body);
+        self.inlining.pop();
+        fresh_vars
+            .into_iter()
+            .zip(args.into_iter())
+            .rev()
+            .fold(body, |body, (var, arg)| {
+                ast::Expr::LetExpr(var, box arg, box body, pos.clone())
+            })
+    }
+}
+
+impl<'a> ast::StmtFolder for SmallFunctionInliner<'a> {
+    fn fold_expr(&mut self, expr: ast::Expr) -> ast::Expr {
+        ast::ExprFolder::fold(self, expr)
+    }
+}
+
+impl<'a> ast::ExprFolder for SmallFunctionInliner<'a> {
+    fn fold_func_app(
+        &mut self,
+        name: String,
+        args: Vec<ast::Expr>,
+        formal_args: Vec<ast::LocalVar>,
+        return_type: ast::Type,
+        pos: ast::Position,
+    ) -> ast::Expr {
+        let args = args.into_iter().map(|e| self.fold(e)).collect();
+        let functions = self.functions;
+        if !self.inlining.contains(&name) {
+            if let Some(function) = functions.get(&name) {
+                return self.inline_call(function, args, pos);
+            }
+        }
+        ast::Expr::FuncApp(name, args, formal_args, return_type, pos)
+    }
+}
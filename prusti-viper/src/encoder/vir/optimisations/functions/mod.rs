@@ -9,5 +9,5 @@
 mod inliner;
 mod simplifier;
 
-pub use self::inliner::inline_constant_functions;
+pub use self::inliner::{inline_constant_functions, inline_small_functions};
 pub use self::simplifier::simplify;
@@ -6,6 +6,11 @@
 
 //! A module that contains various VIR optimisations.
 
+pub mod expressions;
 pub mod folding;
 pub mod functions;
 pub mod methods;
+mod pipeline;
+
+pub use self::pipeline::optimise_program;
+pub(crate) use self::pipeline::{map_expressions, map_methods};
@@ -0,0 +1,92 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The configurable pipeline of optimisations that is run on a VIR program
+//! before it is sent to the verifier.
+
+use super::super::ast;
+use super::super::cfg;
+use super::super::program::Program;
+use super::expressions::{fold_constants, remove_redundant_unfoldings};
+use super::methods::{remove_trivial_assertions, remove_unused_vars};
+use std::mem;
+
+/// Run the optimisations named in `passes` on the methods and functions of
+/// the program, in the given order. The supported optimisations are:
+///
+/// * `fold_constants`: evaluate operations on constants;
+/// * `remove_redundant_unfoldings`: remove unfoldings whose body does not
+///   access the content of the unfolded predicate (not run by default);
+/// * `remove_unused_vars`: remove unused temporary variables of methods;
+/// * `remove_trivial_assertions`: remove `assert true` and `exhale true`
+///   statements of methods.
+pub fn optimise_program(program: &mut Program, passes: &[String]) {
+    for pass in passes {
+        trace!("Running optimisation {}", pass);
+        match pass.as_str() {
+            "fold_constants" => map_expressions(program, fold_constants),
+            "remove_redundant_unfoldings" => {
+                map_expressions(program, remove_redundant_unfoldings)
+            }
+            "remove_unused_vars" => map_methods(program, remove_unused_vars),
+            "remove_trivial_assertions" => map_methods(program, remove_trivial_assertions),
+            _ => warn!("Ignoring unknown optimisation '{}'", pass),
+        }
+    }
+}
+
+/// Apply `f` to all the methods of the program.
+pub(crate) fn map_methods<F>(program: &mut Program, f: F)
+where
+    F: Fn(cfg::CfgMethod) -> cfg::CfgMethod,
+{
+    let methods = mem::replace(&mut program.methods, vec![]);
+    program.methods = methods.into_iter().map(f).collect();
+}
+
+/// Apply `f` to all the expressions of the methods and functions of the program.
+pub(crate) fn map_expressions<F>(program: &mut Program, f: F)
+where
+    F: Fn(ast::Expr) -> ast::Expr,
+{
+    let mut mapper = ExprMapper { f: &f };
+    for method in &mut program.methods {
+        for block in &mut method.basic_blocks {
+            let stmts = mem::replace(&mut block.stmts, vec![]);
+            block.stmts = stmts
+                .into_iter()
+                .map(|stmt| ast::StmtFolder::fold(&mut mapper, stmt))
+                .collect();
+            if let cfg::Successor::GotoSwitch(ref mut targets, _) = block.successor {
+                let old_targets = mem::replace(targets, vec![]);
+                *targets = old_targets
+                    .into_iter()
+                    .map(|(guard, target)| (f(guard), target))
+                    .collect();
+            }
+        }
+    }
+    for function in &mut program.functions {
+        let pres = mem::replace(&mut function.pres, vec![]);
+        function.pres = pres.into_iter().map(&f).collect();
+        let posts = mem::replace(&mut function.posts, vec![]);
+        function.posts = posts.into_iter().map(&f).collect();
+        function.body = function.body.take().map(&f);
+    }
+}
+
+struct ExprMapper<'a, F: 'a> {
+    f: &'a F,
+}
+
+impl<'a, F> ast::StmtFolder for ExprMapper<'a, F>
+where
+    F: Fn(ast::Expr) -> ast::Expr,
+{
+    fn fold_expr(&mut self, expr: ast::Expr) -> ast::Expr {
+        (self.f)(expr)
+    }
+}
@@ -0,0 +1,130 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use encoder::vir::ast::*;
+use encoder::vir::borrows::DAG as ReborrowingDAG;
+use encoder::vir::cfg::CfgMethod;
+use serde_json;
+use std::mem;
+
+/// A Viper program that does not depend on the JVM, so that it can be sent to a verifier
+/// daemon. The function `read$` and the predicate `DeadBorrowToken$` are added when the
+/// program is converted to Viper.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Program {
+    pub domains: Vec<Domain>,
+    pub fields: Vec<Field>,
+    pub builtin_methods: Vec<BodylessMethod>,
+    pub methods: Vec<CfgMethod>,
+    pub functions: Vec<Function>,
+    pub predicates: Vec<Predicate>,
+}
+
+impl Program {
+    /// A textual representation of the program, which can be read back by `Program::from_text`.
+    /// Unlike the Viper program, it keeps the positions of the expressions and statements (so that
+    /// the verification errors can still be mapped back to the Rust program) and it does not need
+    /// the JVM. The `ExpireBorrows` statements are replaced by comments.
+    pub fn to_text(&self) -> String {
+        let mut program = self.clone();
+        program.erase_expire_borrows();
+        serde_json::to_string_pretty(&program).unwrap()
+    }
+
+    /// Read a program from the representation produced by `Program::to_text`.
+    pub fn from_text(text: &str) -> Result<Program, String> {
+        serde_json::from_str(text).map_err(|error| format!("invalid VIR program: {}", error))
+    }
+
+    /// Replace the `ExpireBorrows` statements, which are only used by the fold/unfold algorithm,
+    /// with the comments that they would be converted to.
+    pub fn erase_expire_borrows(&mut self) {
+        for method in &mut self.methods {
+            for block in &mut method.basic_blocks {
+                let stmts = mem::replace(&mut block.stmts, vec![]);
+                block.stmts = stmts.into_iter().map(|stmt| ExpireBorrowsEraser.fold(stmt)).collect();
+            }
+        }
+    }
+}
+
+struct ExpireBorrowsEraser;
+
+impl StmtFolder for ExpireBorrowsEraser {
+    fn fold_expire_borrows(&mut self, dag: ReborrowingDAG) -> Stmt {
+        Stmt::comment(Stmt::ExpireBorrows(dag).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoder::vir::borrows::{Borrow, DAGBuilder};
+    use encoder::vir::cfg::Successor;
+    use serde_json;
+
+    #[test]
+    fn test_program_serialization() {
+        let var = LocalVar::new("x", Type::Bool);
+        let wand = Expr::magic_wand(var.clone().into(), true.into(), Some(Borrow::from(3)));
+        let mut method = CfgMethod::new("m".to_string(), vec![var], vec![], vec![], vec![]);
+        let stmts = vec![Stmt::ApplyMagicWand(wand, Position::default())];
+        let block = method.add_block("start", vec![], stmts);
+        method.set_successor(block, Successor::Return);
+        let program = Program {
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![method],
+            functions: vec![],
+            predicates: vec![],
+        };
+        let json = serde_json::to_string(&program).unwrap();
+        let deserialized: Program = serde_json::from_str(&json).unwrap();
+        assert_eq!(program.methods[0].to_string(), deserialized.methods[0].to_string());
+    }
+
+    #[test]
+    fn test_program_text_round_trip() {
+        let var = LocalVar::new("x", Type::Int);
+        let pos = Position::new(12, 5, "42".to_string());
+        let assertion = Expr::gt_cmp(Expr::local(var.clone()), 0.into()).set_pos(pos.clone());
+        let mut method = CfgMethod::new("m".to_string(), vec![var.clone()], vec![], vec![], vec![]);
+        let stmts = vec![
+            Stmt::Assert(assertion, FoldingBehaviour::Stmt, pos.clone()),
+            Stmt::ExpireBorrows(DAGBuilder::new().finish()),
+        ];
+        let block = method.add_block("start", vec![], stmts);
+        method.set_successor(block, Successor::Return);
+        let function = Function {
+            name: "f".to_string(),
+            formal_args: vec![var.clone()],
+            return_type: Type::Int,
+            pres: vec![],
+            posts: vec![],
+            body: Some(Expr::local(var).set_pos(pos.clone())),
+        };
+        let program = Program {
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![method],
+            functions: vec![function],
+            predicates: vec![],
+        };
+        let parsed = Program::from_text(&program.to_text()).unwrap();
+        match parsed.methods[0].basic_blocks[0].stmts[..] {
+            [Stmt::Assert(ref expr, _, ref stmt_pos), Stmt::Comment(_)] => {
+                assert_eq!(expr.pos(), &pos);
+                assert_eq!(stmt_pos, &pos);
+            }
+            ref stmts => panic!("unexpected statements: {:?}", stmts),
+        }
+        assert_eq!(parsed.functions[0].body.as_ref().unwrap().pos(), &pos);
+        assert_eq!(parsed.functions[0].to_string(), program.functions[0].to_string());
+        assert!(Program::from_text("{}").is_err());
+    }
+}
@@ -6,6 +6,7 @@
 
 use encoder::vir::ast::*;
 use encoder::vir::borrows::borrow_id;
+use encoder::vir::Program;
 use prusti_interface::config;
 use viper;
 use viper::AstFactory;
@@ -31,6 +32,7 @@ impl<'v> ToViper<'v, viper::Type<'v>> for Type {
             &Type::Bool => ast.bool_type(),
             //&Type::Ref |
             &Type::TypedRef(_) => ast.ref_type(),
+            &Type::Domain(ref name) => ast.domain_type(name, &[], &[]),
         }
     }
 }
@@ -213,7 +215,7 @@ impl<'v> ToViper<'v, viper::Stmt<'v>> for Stmt {
                 let apply = ast.apply(wand.to_viper(ast), position);
                 ast.seqn(&[inhale, apply], &[])
             }
-            &Stmt::ExpireBorrows(_) => {
+            &Stmt::ExpireBorrows(_) | &Stmt::ExhaleDeadPermissions(_) => {
                 // Skip
                 ast.comment(&self.to_string())
             }
@@ -235,6 +237,7 @@ impl<'v> ToViper<'v, viper::Expr<'v>> for PermAmount {
                 PermAmount::Write.to_viper(ast),
                 PermAmount::Read.to_viper(ast),
             ),
+            PermAmount::Wildcard => ast.wildcard_perm(),
         }
     }
 }
@@ -361,6 +364,19 @@ impl<'v> ToViper<'v, viper::Expr<'v>> for Expr {
                 body.to_viper(ast),
                 pos.to_viper(ast),
             ),
+            &Expr::Exists(ref vars, ref triggers, ref body, ref pos) => {
+                // Encoded as `!(forall vars :: {triggers} !body)`, because the Viper
+                // constructor of existential quantifiers does not take triggers.
+                ast.not_with_pos(
+                    ast.forall_with_pos(
+                        &vars.to_viper_decl(ast)[..],
+                        &(triggers, pos).to_viper(ast),
+                        ast.not_with_pos(body.to_viper(ast), pos.to_viper(ast)),
+                        pos.to_viper(ast),
+                    ),
+                    pos.to_viper(ast),
+                )
+            }
             &Expr::LetExpr(ref var, ref expr, ref body, ref pos) => ast.let_expr_with_pos(
                 var.to_viper_decl(ast),
                 expr.to_viper(ast),
@@ -382,6 +398,9 @@ impl<'v> ToViper<'v, viper::Expr<'v>> for Expr {
                     pos.to_viper(ast),
                 )
             }
+            &Expr::DomainFuncApp(ref function, ref args, ref _pos) => {
+                ast.domain_func_app(function.to_viper(ast), &args.to_viper(ast), &[])
+            }
         };
         if config::simplify_expressions() {
             ast.simplified_expression(expr)
@@ -476,6 +495,77 @@ impl<'a, 'v> ToViper<'v, viper::Function<'v>> for &'a Function {
     }
 }
 
+impl<'v> ToViper<'v, viper::Domain<'v>> for Domain {
+    fn to_viper(&self, ast: &AstFactory<'v>) -> viper::Domain<'v> {
+        (&self).to_viper(ast)
+    }
+}
+
+impl<'a, 'v> ToViper<'v, viper::Domain<'v>> for &'a Domain {
+    fn to_viper(&self, ast: &AstFactory<'v>) -> viper::Domain<'v> {
+        let functions: Vec<_> = self.functions.iter().map(|f| f.to_viper(ast)).collect();
+        let axioms: Vec<_> = self.axioms.iter().map(|a| a.to_viper(ast)).collect();
+        ast.domain(&self.name, &functions, &axioms, &[])
+    }
+}
+
+impl<'v> ToViper<'v, viper::DomainFunc<'v>> for DomainFunc {
+    fn to_viper(&self, ast: &AstFactory<'v>) -> viper::DomainFunc<'v> {
+        ast.domain_func(
+            &self.name,
+            &self.formal_args.to_viper_decl(ast),
+            self.return_type.to_viper(ast),
+            self.unique,
+            &self.domain_name,
+        )
+    }
+}
+
+impl<'v> ToViper<'v, viper::DomainAxiom<'v>> for DomainAxiom {
+    fn to_viper(&self, ast: &AstFactory<'v>) -> viper::DomainAxiom<'v> {
+        ast.domain_axiom(&self.name, self.expr.to_viper(ast), &self.domain_name)
+    }
+}
+
+impl<'v> ToViper<'v, viper::Program<'v>> for Program {
+    fn to_viper(&self, ast: &AstFactory<'v>) -> viper::Program<'v> {
+        let domains: Vec<_> = self.domains.iter().map(|d| d.to_viper(ast)).collect();
+        let fields = self.fields.to_viper(ast);
+        let mut functions: Vec<_> = self.functions.iter().map(|f| f.to_viper(ast)).collect();
+        functions.push(read_perm_function(ast));
+        let mut predicates = self.predicates.to_viper(ast);
+        predicates.push(dead_borrow_token_predicate(ast));
+        let mut methods: Vec<_> = self.builtin_methods.iter().map(|m| m.to_viper(ast)).collect();
+        methods.extend(self.methods.iter().map(|m| m.to_viper(ast)));
+        ast.program(&domains, &fields, &functions, &predicates, &methods)
+    }
+}
+
+/// The function that represents the symbolic read permission amount.
+pub fn read_perm_function<'v>(ast: &AstFactory<'v>) -> viper::Function<'v> {
+    ast.function(
+        "read$",
+        &[],
+        ast.perm_type(),
+        &[],
+        &[
+            ast.lt_cmp(ast.no_perm(), ast.result(ast.perm_type())),
+            ast.lt_cmp(ast.result(ast.perm_type()), ast.full_perm()),
+        ],
+        ast.no_position(),
+        None,
+    )
+}
+
+/// The predicate that represents the dead loan token.
+pub fn dead_borrow_token_predicate<'v>(ast: &AstFactory<'v>) -> viper::Predicate<'v> {
+    ast.predicate(
+        "DeadBorrowToken$",
+        &[LocalVar::new("borrow", Type::Int).to_viper_decl(ast)],
+        None,
+    )
+}
+
 // Vectors
 
 impl<'v> ToViper<'v, Vec<viper::Field<'v>>> for Vec<Field> {
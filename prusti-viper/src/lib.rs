@@ -25,7 +25,13 @@ extern crate prusti_interface;
 extern crate regex;
 extern crate rustc;
 extern crate rustc_data_structures;
+extern crate rustc_metadata;
 extern crate rustc_mir;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate fnv;
 extern crate syntax;
 extern crate syntax_pos;
 extern crate uuid;
@@ -37,6 +43,10 @@ extern crate lazy_static;
 #[macro_use]
 extern crate pretty_assertions;
 
+pub mod daemon;
 mod encoder;
+pub mod plugins;
 mod utils;
 pub mod verifier;
+
+pub use encoder::vir;
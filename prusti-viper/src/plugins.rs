@@ -0,0 +1,179 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! VIR transformations provided by external code, which are run on the encoded program after the
+//! optimisations and before the verification. They allow to experiment with extensions of the
+//! encoding (e.g. a custom purification of the methods) without changing the encoder.
+//!
+//! The transformations are registered on a `Registry`, either by the code that embeds the
+//! verifier (with `register_plugin`) or by the plugin libraries listed in the `VIR_PLUGINS`
+//! configuration flag. A plugin library has to be a `dylib` compiled with the same compiler as
+//! Prusti, and it has to export a registrar:
+//!
+//! ```ignore
+//! #[no_mangle]
+//! pub fn __prusti_vir_plugin_registrar(registry: &mut prusti_viper::plugins::Registry) {
+//!     registry.register_expr_transformation("my_transformation", Box::new(|expr| expr));
+//! }
+//! ```
+
+use encoder::vir::{self, optimisations};
+use prusti_interface::config;
+use rustc_metadata::dynamic_lib::DynamicLibrary;
+use std::mem;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// The name of the registrar exported by a plugin library.
+const REGISTRAR_SYMBOL: &str = "__prusti_vir_plugin_registrar";
+
+/// The function that registers the transformations of a plugin.
+pub type PluginRegistrar = fn(&mut Registry);
+
+enum Transformation {
+    Expr(Box<Fn(vir::Expr) -> vir::Expr + Send>),
+    Method(Box<Fn(vir::CfgMethod) -> vir::CfgMethod + Send>),
+    Program(Box<Fn(&mut vir::Program) + Send>),
+}
+
+/// The registered transformations, which are run in the order of registration.
+#[derive(Default)]
+pub struct Registry {
+    transformations: Vec<(String, Transformation)>,
+}
+
+impl Registry {
+    /// Register a transformation of the expressions of the methods and functions, e.g. the
+    /// `fold` of an `ExprFolder`.
+    pub fn register_expr_transformation(
+        &mut self,
+        name: &str,
+        transformation: Box<Fn(vir::Expr) -> vir::Expr + Send>,
+    ) {
+        self.register(name, Transformation::Expr(transformation));
+    }
+
+    /// Register a transformation of the CFG of each method.
+    pub fn register_method_transformation(
+        &mut self,
+        name: &str,
+        transformation: Box<Fn(vir::CfgMethod) -> vir::CfgMethod + Send>,
+    ) {
+        self.register(name, Transformation::Method(transformation));
+    }
+
+    /// Register a transformation of the whole program, e.g. to add new functions or predicates.
+    pub fn register_program_transformation(
+        &mut self,
+        name: &str,
+        transformation: Box<Fn(&mut vir::Program) + Send>,
+    ) {
+        self.register(name, Transformation::Program(transformation));
+    }
+
+    fn register(&mut self, name: &str, transformation: Transformation) {
+        debug!("Register VIR transformation {}", name);
+        self.transformations.push((name.to_string(), transformation));
+    }
+
+    fn apply(&self, program: &mut vir::Program) {
+        for (name, transformation) in &self.transformations {
+            trace!("Running VIR transformation {}", name);
+            match transformation {
+                Transformation::Expr(f) => optimisations::map_expressions(program, |e| f(e)),
+                Transformation::Method(f) => optimisations::map_methods(program, |m| f(m)),
+                Transformation::Program(f) => f(program),
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(load_plugin_libraries());
+}
+
+/// Load the libraries listed in `VIR_PLUGINS`, and register their transformations.
+fn load_plugin_libraries() -> Registry {
+    let mut registry = Registry::default();
+    for path in config::vir_plugins() {
+        info!("Loading VIR plugin {}", path);
+        let library = DynamicLibrary::open(Some(Path::new(&path)))
+            .unwrap_or_else(|error| panic!("Failed to load the VIR plugin '{}': {}", path, error));
+        let registrar = unsafe {
+            let symbol = library.symbol::<u8>(REGISTRAR_SYMBOL).unwrap_or_else(|error| {
+                panic!("Failed to find the registrar of the VIR plugin '{}': {}", path, error)
+            });
+            mem::transmute::<*mut u8, PluginRegistrar>(symbol)
+        };
+        // The registered closures are defined in the library, which therefore must stay loaded.
+        mem::forget(library);
+        registrar(&mut registry);
+    }
+    registry
+}
+
+/// Register the transformations of a plugin that is linked with the verifier.
+pub fn register_plugin(registrar: PluginRegistrar) {
+    registrar(&mut REGISTRY.lock().unwrap());
+}
+
+/// Run the registered transformations on the program.
+pub(crate) fn run_transformations(program: &mut vir::Program) {
+    REGISTRY.lock().unwrap().apply(program);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoder::vir::ExprFolder;
+
+    struct TrueToFalse;
+
+    impl ExprFolder for TrueToFalse {
+        fn fold_const(&mut self, constant: vir::Const, pos: vir::Position) -> vir::Expr {
+            match constant {
+                vir::Const::Bool(true) => vir::Expr::Const(vir::Const::Bool(false), pos),
+                _ => vir::Expr::Const(constant, pos),
+            }
+        }
+    }
+
+    #[test]
+    fn test_registered_transformations() {
+        let function = vir::Function {
+            name: "f".to_string(),
+            formal_args: vec![],
+            return_type: vir::Type::Bool,
+            pres: vec![],
+            posts: vec![],
+            body: Some(true.into()),
+        };
+        let mut program = vir::Program {
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![],
+            functions: vec![function],
+            predicates: vec![],
+        };
+        let mut registry = Registry::default();
+        registry.register_expr_transformation(
+            "true_to_false",
+            Box::new(|expr| TrueToFalse.fold(expr)),
+        );
+        registry.register_program_transformation(
+            "rename_functions",
+            Box::new(|program| {
+                for function in &mut program.functions {
+                    function.name = format!("{}$renamed", function.name);
+                }
+            }),
+        );
+        registry.apply(&mut program);
+        assert_eq!(program.functions[0].name, "f$renamed");
+        assert_eq!(program.functions[0].body, Some(false.into()));
+    }
+}
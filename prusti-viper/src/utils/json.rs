@@ -0,0 +1,86 @@
+// © 2019, ETH Zurich
+//
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! The machine-readable verification report and the streamed verification events, which are
+//! serialized with `serde_json`.
+
+use std::collections::BTreeMap;
+
+/// A position in the Rust source code, which is unknown if the error has no span.
+#[derive(Serialize)]
+pub struct JsonLocation {
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+/// A verification error.
+#[derive(Serialize)]
+pub struct JsonError {
+    pub message: String,
+    #[serde(flatten)]
+    pub location: JsonLocation,
+}
+
+/// A use of an unsupported feature that prevented the encoding of a procedure.
+#[derive(Serialize)]
+pub struct JsonUnsupportedFeature {
+    pub feature: &'static str,
+    pub message: String,
+    #[serde(flatten)]
+    pub location: JsonLocation,
+}
+
+/// The outcome of the verification of a single encoded method.
+#[derive(Serialize)]
+pub struct JsonOutcome<'a> {
+    pub name: &'a str,
+    pub status: &'static str,
+    pub duration_ms: Option<u64>,
+    pub vir_statements: usize,
+    pub statistics: &'a BTreeMap<String, u64>,
+    pub errors: Vec<JsonError>,
+}
+
+/// The number of items of each kind in the Viper program.
+#[derive(Serialize)]
+pub struct JsonProgramSize {
+    pub domains: usize,
+    pub fields: usize,
+    pub functions: usize,
+    pub predicates: usize,
+    pub methods: usize,
+}
+
+/// The report written to the file given by `REPORT_JSON`.
+#[derive(Serialize)]
+pub struct JsonReport<'a> {
+    #[serde(rename = "crate")]
+    pub crate_name: &'a str,
+    pub viper_program: JsonProgramSize,
+    pub functions: JsonOutcome<'a>,
+    pub procedures: Vec<JsonOutcome<'a>>,
+    pub unsupported_features: Vec<JsonUnsupportedFeature>,
+}
+
+/// An event printed on stdout if `STREAM_JSON` is enabled.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JsonEvent<'a> {
+    Started {
+        procedure: &'a str,
+    },
+    Error {
+        procedure: &'a str,
+        error: JsonError,
+    },
+    Finished {
+        procedure: &'a str,
+        status: &'static str,
+        duration_ms: Option<u64>,
+        statistics: &'a BTreeMap<String, u64>,
+    },
+}
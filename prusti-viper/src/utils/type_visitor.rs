@@ -4,6 +4,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use rustc::hir::def_id::DefId;
 use rustc::hir::Mutability;
 use rustc::ty::subst::Substs;
 use rustc::ty::TypeVariants::*;
@@ -58,6 +59,12 @@ pub trait TypeVisitor<'a, 'tcx>: Sized {
             TyProjection(data) => {
                 self.visit_projection(data);
             }
+            TyDynamic(..) => {
+                self.visit_dynamic();
+            }
+            TyAnon(def_id, substs) => {
+                self.visit_anon(def_id, substs);
+            }
             ref x => {
                 unimplemented!("{:?}", x);
             }
@@ -80,6 +87,10 @@ pub trait TypeVisitor<'a, 'tcx>: Sized {
 
     fn visit_projection(&mut self, _data: ProjectionTy<'tcx>) {}
 
+    fn visit_dynamic(&mut self) {}
+
+    fn visit_anon(&mut self, _def_id: DefId, _substs: &'tcx Substs<'tcx>) {}
+
     fn visit_adt(&mut self, adt_def: &'tcx AdtDef, substs: &'tcx Substs<'tcx>) {
         trace!("visit_adt({:?})", adt_def);
         walk_adt(self, adt_def, substs);
@@ -4,10 +4,14 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use encoder::vir::{self, optimisations, ToViper, ToViperDecl};
-use encoder::Encoder;
+use daemon::{DaemonClient, VerificationRequest};
+use encoder::errors::SpannedEncodingError;
+use encoder::vir::{self, optimisations, ToViper};
+use encoder::{Encoder, SpecSanityCheckKind};
+use plugins;
 use prusti_filter::validators::Validator;
-use prusti_interface::config;
+use prusti_interface::config::{self, ExplainAssertions};
+use prusti_interface::data::ProcedureDefId;
 use prusti_interface::data::VerificationResult;
 use prusti_interface::data::VerificationTask;
 use prusti_interface::environment::Environment;
@@ -16,23 +20,40 @@ use prusti_interface::specifications::TypedSpecificationMap;
 use prusti_interface::verifier::VerificationContext as VerificationContextSpec;
 use prusti_interface::verifier::Verifier as VerifierSpec;
 use prusti_interface::verifier::VerifierBuilder as VerifierBuilderSpec;
-use std::time::Instant;
+use prusti_interface::report::user;
+use serde_json;
+use utils::json::{
+    JsonError, JsonEvent, JsonLocation, JsonOutcome, JsonProgramSize, JsonReport,
+    JsonUnsupportedFeature,
+};
+use std::cell::Cell;
+use fnv::FnvHasher;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::mem;
+use std::time::{Duration, Instant};
+use syntax_pos::MultiSpan;
 use viper::{self, VerificationBackend, Viper};
 use std::path::PathBuf;
-use std::fs::create_dir_all;
+use std::fs::{self, create_dir_all};
 
 pub struct VerifierBuilder {
-    viper: Viper,
+    /// The JVM, which is not started if the programs are verified by a verifier daemon.
+    viper: Option<Viper>,
 }
 
 impl VerifierBuilder {
     pub fn new() -> Self {
-        VerifierBuilder {
-            viper: Viper::new_with_args(
+        let viper = if config::verifier_daemon().is_empty() {
+            Some(Viper::new_with_args(
                 config::extra_jvm_args(),
                 VerificationBackend::from_str(&config::viper_backend())
-            ),
-        }
+            ))
+        } else {
+            None
+        };
+        VerifierBuilder { viper }
     }
 }
 
@@ -51,17 +72,17 @@ where
     type VerificationContextImpl = VerificationContext<'v>;
 
     fn new_verification_context(&'v self) -> VerificationContext<'v> {
-        let verification_ctx = self.viper.new_verification_context();
+        let verification_ctx = self.viper.as_ref().map(|viper| viper.new_verification_context());
         VerificationContext::new(verification_ctx)
     }
 }
 
 pub struct VerificationContext<'v> {
-    verification_ctx: viper::VerificationContext<'v>,
+    verification_ctx: Option<viper::VerificationContext<'v>>,
 }
 
 impl<'v> VerificationContext<'v> {
-    pub fn new(verification_ctx: viper::VerificationContext<'v>) -> Self {
+    pub fn new(verification_ctx: Option<viper::VerificationContext<'v>>) -> Self {
         VerificationContext { verification_ctx }
     }
 }
@@ -97,6 +118,18 @@ where
                 log_dir_str.to_string(),
                 //"--logLevel".to_string(), "WARN".to_string(),
             ]);
+            let timeout_ms = config::verification_timeout_ms();
+            if timeout_ms > 0 {
+                // Silicon counts the timeout in seconds.
+                let timeout_secs = (timeout_ms + 999) / 1000;
+                verifier_args.extend(vec!["--timeout".to_string(), timeout_secs.to_string()]);
+            }
+            if config::report_verifier_statistics() {
+                verifier_args.extend(vec![
+                    "--showStatistics".to_string(),
+                    format!("file={}", statistics_path().to_str().unwrap()),
+                ]);
+            }
         } else {
             verifier_args.extend(vec![
                 "--disableAllocEncoding".to_string(),
@@ -113,54 +146,539 @@ where
                     //"--printTranslatedProgram".to_string(),
                 ]);
             } else {
-                verifier_args.extend::<Vec<_>>(vec![
-                    //"--print".to_string(), "./log/boogie_program/program.bpl".to_string(),
+                let boogie_path = PathBuf::from(config::log_dir()).join("boogie_program");
+                create_dir_all(&boogie_path).unwrap();
+                verifier_args.extend(vec![
+                    "--print".to_string(),
+                    boogie_path.join("program.bpl").to_str().unwrap().to_string(),
                 ]);
             }
         }
         verifier_args.extend(config::extra_verifier_args());
-        Verifier::new(
-            self.verification_ctx.new_ast_utils(),
-            self.verification_ctx.new_ast_factory(),
-            self.verification_ctx
-                .new_verifier_with_args(backend, verifier_args, Some(report_path)),
-            env,
-            spec,
-        )
+        let verifier_backend = match self.verification_ctx {
+            Some(ref verification_ctx) => VerifierBackend::Local {
+                ast_utils: verification_ctx.new_ast_utils(),
+                ast_factory: verification_ctx.new_ast_factory(),
+                verifier: verification_ctx
+                    .new_verifier_with_args(backend, verifier_args, Some(report_path)),
+            },
+            None => VerifierBackend::Daemon {
+                client: DaemonClient::new(config::verifier_daemon()),
+                verifier_args,
+            },
+        };
+        Verifier::new(verifier_backend, env, spec)
     }
 }
 
+/// Where the encoded programs are verified.
+pub enum VerifierBackend<'v> {
+    /// By a Viper verifier running in the JVM of this process.
+    Local {
+        ast_utils: viper::AstUtils<'v>,
+        ast_factory: viper::AstFactory<'v>,
+        verifier: viper::Verifier<'v, viper::state::Started>,
+    },
+    /// By the verifier daemon given by `VERIFIER_DAEMON`.
+    Daemon {
+        client: DaemonClient,
+        verifier_args: Vec<String>,
+    },
+}
+
 pub struct Verifier<'v, 'r, 'a, 'tcx>
 where
     'r: 'v,
     'a: 'r,
     'tcx: 'a,
 {
-    ast_utils: viper::AstUtils<'v>,
-    ast_factory: viper::AstFactory<'v>,
-    verifier: viper::Verifier<'v, viper::state::Started>,
+    backend: VerifierBackend<'v>,
     env: &'v Environment<'r, 'a, 'tcx>,
     encoder: Encoder<'v, 'r, 'a, 'tcx>,
+    /// Whether a request to the verifier daemon failed, in which case the verification is not
+    /// successful even if no verification error has been reported.
+    daemon_failed: Cell<bool>,
 }
 
 impl<'v, 'r, 'a, 'tcx> Verifier<'v, 'r, 'a, 'tcx> {
     pub fn new(
-        ast_utils: viper::AstUtils<'v>,
-        ast_factory: viper::AstFactory<'v>,
-        verifier: viper::Verifier<'v, viper::state::Started>,
+        backend: VerifierBackend<'v>,
         env: &'v Environment<'r, 'a, 'tcx>,
         spec: &'v TypedSpecificationMap,
     ) -> Self {
         Verifier {
-            ast_utils,
-            ast_factory,
-            verifier,
+            backend,
             env,
             encoder: Encoder::new(env, spec),
+            daemon_failed: Cell::new(false),
         }
     }
 }
 
+impl<'v, 'r, 'a, 'tcx> Verifier<'v, 'r, 'a, 'tcx> {
+    /// Dump each encoded method in a separate file, annotating the statements with the line
+    /// of the Rust program from which they have been generated.
+    fn dump_annotated_methods(&self, methods: &[vir::CfgMethod]) {
+        let source_path = self.env.source_path();
+        let source_filename = source_path.file_name().unwrap().to_str().unwrap();
+        let source = fs::read_to_string(&source_path).unwrap_or_default();
+        let source_lines: Vec<&str> = source.lines().collect();
+        for method in methods {
+            log::report(
+                "viper_program",
+                format!("{}.{}.vpr", source_filename, method.name()),
+                method.to_string_with_source(&source_lines),
+            );
+        }
+    }
+
+    /// The file that stores the hashes of the methods verified by the last successful run.
+    fn cache_path(&self) -> PathBuf {
+        PathBuf::from(config::cache_dir()).join(format!("{}.cache", self.env.crate_name()))
+    }
+
+    /// Load the hashes of the methods that have been verified by the last run.
+    fn load_cache(&self) -> HashMap<String, u64> {
+        parse_cache(&fs::read_to_string(self.cache_path()).unwrap_or_default())
+    }
+
+    /// Store the hashes of the methods that have been verified successfully.
+    fn store_cache(&self, cache: &HashMap<String, u64>) {
+        create_dir_all(config::cache_dir()).unwrap();
+        if let Err(error) = fs::write(self.cache_path(), format_cache(cache)) {
+            warn!("Failed to store the verification cache: {}", error);
+        }
+    }
+
+    /// Verify a program that contains all the functions and predicates, but only the given
+    /// methods (and the built-in ones).
+    fn verify_methods(
+        &self,
+        context: &ProgramContext<'v>,
+        methods: &[vir::CfgMethod],
+    ) -> (viper::VerificationResult, Duration) {
+        match self.backend {
+            VerifierBackend::Local {
+                ref ast_factory,
+                ref verifier,
+                ..
+            } => {
+                let viper_context = context.viper.as_ref().unwrap();
+                let mut program_methods = viper_context.builtin_methods.clone();
+                program_methods.extend(methods.iter().map(|m| m.to_viper(ast_factory)));
+                let program = ast_factory.program(
+                    &viper_context.domains,
+                    &viper_context.fields,
+                    &viper_context.functions,
+                    &viper_context.predicates,
+                    &program_methods,
+                );
+                let start = Instant::now();
+                let result = verifier.verify(program);
+                (result, start.elapsed())
+            }
+            VerifierBackend::Daemon {
+                ref client,
+                ref verifier_args,
+            } => {
+                let mut program = context.program.clone();
+                program.methods = methods.to_vec();
+                program.erase_expire_borrows();
+                let request = VerificationRequest {
+                    program,
+                    backend: config::viper_backend(),
+                    verifier_args: verifier_args.clone(),
+                };
+                let start = Instant::now();
+                let result = client.verify(&request).unwrap_or_else(|error| {
+                    // Report the failure only once, since the following requests are likely
+                    // to fail for the same reason.
+                    if !self.daemon_failed.replace(true) {
+                        self.env.err(&format!(
+                            "[Prusti] the verification by the verifier daemon failed: {}",
+                            error
+                        ));
+                    }
+                    viper::VerificationResult::Failure(vec![])
+                });
+                (result, start.elapsed())
+            }
+        }
+    }
+
+    /// Warn about the type invariants and the preconditions that are unsatisfiable, whose sanity
+    /// check verifies. The invariants are checked first, so that the precondition of a procedure
+    /// is not reported just because it assumes an unsatisfiable invariant.
+    fn check_spec_sanity(&self, context: &ProgramContext<'v>) {
+        let (invariant_checks, precondition_checks): (Vec<_>, Vec<_>) = self
+            .encoder
+            .get_spec_sanity_checks()
+            .into_iter()
+            .partition(|check| match check.kind {
+                SpecSanityCheckKind::TypeInvariant(_) => true,
+                SpecSanityCheckKind::Precondition(..) => false,
+            });
+        let mut unsatisfiable_invariants = HashSet::new();
+        for check in invariant_checks.into_iter().chain(precondition_checks) {
+            let (result, _) = self.verify_methods(context, &[check.method]);
+            let is_unsatisfiable = result == viper::VerificationResult::Success();
+            match check.kind {
+                SpecSanityCheckKind::TypeInvariant(adt_did) => {
+                    if is_unsatisfiable {
+                        unsatisfiable_invariants.insert(adt_did);
+                        self.env.span_warn(
+                            check.span,
+                            "[Prusti] the invariant of this type is unsatisfiable, so the \
+                             procedures that take it are verified vacuously",
+                        );
+                    }
+                }
+                SpecSanityCheckKind::Precondition(_, ref invariant_adts) => {
+                    let assumes_unsatisfiable_invariant = invariant_adts
+                        .iter()
+                        .any(|adt_did| unsatisfiable_invariants.contains(adt_did));
+                    if is_unsatisfiable && !assumes_unsatisfiable_invariant {
+                        self.env.span_warn(
+                            check.span,
+                            "[Prusti] the precondition of this procedure is unsatisfiable, so \
+                             its calls cannot be verified and its body is verified vacuously",
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Print the explanations of the verification errors on stderr, or write them to
+    /// `log/assertion_explanations/`, depending on `EXPLAIN_ASSERTIONS`.
+    fn report_explanations(&self, explanations: &[String]) {
+        let text = explanations.join("\n");
+        match config::explain_assertions() {
+            ExplainAssertions::Off => {}
+            ExplainAssertions::Print => user::message(text),
+            ExplainAssertions::Log => {
+                let source_path = self.env.source_path();
+                let source_filename = source_path.file_name().unwrap().to_str().unwrap();
+                log::report(
+                    "assertion_explanations",
+                    format!("{}.txt", source_filename),
+                    text,
+                );
+            }
+        }
+    }
+
+    /// Read and remove the statistics written by Silicon for the last verified program.
+    fn take_statistics(&self) -> BTreeMap<String, u64> {
+        if !config::report_verifier_statistics() {
+            return BTreeMap::new();
+        }
+        let path = statistics_path();
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        if let Err(error) = fs::remove_file(&path) {
+            debug!("Failed to remove the verifier statistics: {}", error);
+        }
+        parse_statistics(&content)
+    }
+
+    /// Print a table with the outcome and the verification time of each method.
+    fn report_summary(&self, outcomes: &[MethodOutcome]) {
+        let name_width = outcomes.iter().map(|outcome| outcome.name.len()).max().unwrap_or(0);
+        user::message("Verification summary:");
+        for outcome in outcomes {
+            let time = match outcome.duration {
+                Some(duration) => format!(
+                    "{}.{:02} seconds",
+                    duration.as_secs(),
+                    duration.subsec_millis() / 10
+                ),
+                None => "-".to_string(),
+            };
+            user::message(format!(
+                "  {:width$}  {:8}  {}",
+                outcome.name,
+                outcome.status,
+                time,
+                width = name_width
+            ));
+        }
+    }
+
+    /// Print the time spent in each phase of the encoding and of the verification.
+    fn report_profile(&self, phase_durations: &[(&str, Duration)]) {
+        user::message("Encoder profile:");
+        for &(phase, duration) in phase_durations {
+            user::message(format!(
+                "  {:28}  {}.{:02} seconds",
+                phase,
+                duration.as_secs(),
+                duration.subsec_millis() / 10
+            ));
+        }
+    }
+
+    /// The position in the Rust source code of the primary span of an error.
+    fn location_to_json(&self, span: &MultiSpan) -> JsonLocation {
+        let location = span.primary_span().map(|span| {
+            self.env.codemap().lookup_char_pos(span.source_callsite().lo())
+        });
+        JsonLocation {
+            file: location.as_ref().map(|loc| loc.file.name.to_string()),
+            line: location.as_ref().map(|loc| loc.line),
+            column: location.as_ref().map(|loc| loc.col.0 + 1),
+        }
+    }
+
+    /// Encode a verification error, together with the Rust span it refers to.
+    fn error_to_json(&self, error: &viper::VerificationError) -> JsonError {
+        let compilation_error = self.encoder.error_manager().translate(error);
+        JsonError {
+            location: self.location_to_json(&compilation_error.span),
+            message: compilation_error.message,
+        }
+    }
+
+    /// Encode an unsupported feature, together with the Rust span where it is used.
+    fn encoding_error_to_json(&self, error: &SpannedEncodingError) -> JsonUnsupportedFeature {
+        JsonUnsupportedFeature {
+            feature: error.feature.name(),
+            message: error.message(),
+            location: self.location_to_json(&error.span),
+        }
+    }
+
+    /// Report each unsupported feature that prevented the encoding of a procedure, followed by
+    /// a summary of the unsupported features used in the crate.
+    fn report_encoding_errors(&self, errors: &[SpannedEncodingError]) {
+        if errors.is_empty() {
+            return;
+        }
+        let mut occurrences: BTreeMap<&str, usize> = BTreeMap::new();
+        for error in errors {
+            debug!("Encoding error: {:?}", error);
+            self.env.span_err_with_help(error.span.clone(), &error.message(), error.feature.hint());
+            *occurrences.entry(error.feature.name()).or_insert(0) += 1;
+        }
+        user::message(format!("Unsupported features in crate '{}':", self.env.crate_name()));
+        for (feature, count) in occurrences {
+            user::message(format!("  {:24}  {} occurrence(s)", feature, count));
+        }
+    }
+
+    fn outcome_to_json<'o>(&self, outcome: &'o MethodOutcome) -> JsonOutcome<'o> {
+        JsonOutcome {
+            name: &outcome.name,
+            status: outcome.status,
+            duration_ms: outcome.duration.map(duration_to_millis),
+            vir_statements: outcome.vir_statements,
+            statistics: &outcome.statistics,
+            errors: outcome.errors.iter().map(|e| self.error_to_json(e)).collect(),
+        }
+    }
+
+    /// Print a verification event on stdout, if `STREAM_JSON` is enabled.
+    fn emit_event(&self, event: JsonEvent) {
+        if !config::stream_json() {
+            return;
+        }
+        match serde_json::to_string(&event) {
+            Ok(json) => println!("{}", json),
+            Err(error) => warn!("Failed to serialize a verification event: {}", error),
+        }
+        if let Err(error) = io::stdout().flush() {
+            warn!("Failed to flush the verification events: {}", error);
+        }
+    }
+
+    fn emit_outcome_events(&self, outcome: &MethodOutcome) {
+        for error in &outcome.errors {
+            self.emit_event(JsonEvent::Error {
+                procedure: &outcome.name,
+                error: self.error_to_json(error),
+            });
+        }
+        self.emit_event(JsonEvent::Finished {
+            procedure: &outcome.name,
+            status: outcome.status,
+            duration_ms: outcome.duration.map(duration_to_millis),
+            statistics: &outcome.statistics,
+        });
+    }
+
+    /// Write the per-procedure verification results to the file given by `REPORT_JSON`.
+    fn write_json_report(
+        &self,
+        context: &ProgramContext<'v>,
+        function_outcome: &MethodOutcome,
+        outcomes: &[MethodOutcome],
+        encoding_errors: &[SpannedEncodingError],
+    ) {
+        let report = JsonReport {
+            crate_name: self.env.crate_name(),
+            viper_program: JsonProgramSize {
+                domains: context.program.domains.len(),
+                fields: context.program.fields.len(),
+                functions: context.program.functions.len(),
+                predicates: context.program.predicates.len(),
+                methods: outcomes.len() + context.program.builtin_methods.len(),
+            },
+            functions: self.outcome_to_json(function_outcome),
+            procedures: outcomes.iter().map(|o| self.outcome_to_json(o)).collect(),
+            unsupported_features: encoding_errors
+                .iter()
+                .map(|e| self.encoding_error_to_json(e))
+                .collect(),
+        };
+        let result = serde_json::to_string(&report)
+            .map_err(|error| error.to_string())
+            .and_then(|json| {
+                fs::write(config::report_json(), json).map_err(|error| error.to_string())
+            });
+        if let Err(error) = result {
+            warn!("Failed to write the JSON report: {}", error);
+        }
+    }
+}
+
+/// The key under which the functions are stored in the verification cache.
+const FUNCTIONS_CACHE_KEY: &str = "$functions";
+
+/// The version of Prusti, which is part of the hashes stored in the verification cache.
+const PRUSTI_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The parts of the Viper program that are shared by all the encoded methods.
+struct ProgramContext<'v> {
+    /// The shared parts, without any method other than the built-in ones.
+    program: vir::Program,
+    /// The shared parts as JVM objects, unless the programs are verified by a daemon.
+    viper: Option<ViperProgramContext<'v>>,
+}
+
+struct ViperProgramContext<'v> {
+    domains: Vec<viper::Domain<'v>>,
+    fields: Vec<viper::Field<'v>>,
+    functions: Vec<viper::Function<'v>>,
+    predicates: Vec<viper::Predicate<'v>>,
+    builtin_methods: Vec<viper::Method<'v>>,
+}
+
+/// The outcome of the verification of a single encoded method.
+struct MethodOutcome {
+    name: String,
+    status: &'static str,
+    duration: Option<Duration>,
+    vir_statements: usize,
+    errors: Vec<viper::VerificationError>,
+    /// The statistics reported by Silicon, if `REPORT_VERIFIER_STATISTICS` is enabled.
+    statistics: BTreeMap<String, u64>,
+}
+
+/// The verification errors of a result, which are none if the verification timed out.
+fn result_errors(result: viper::VerificationResult) -> Vec<viper::VerificationError> {
+    match result {
+        viper::VerificationResult::Failure(errors) => errors,
+        _ => vec![],
+    }
+}
+
+/// The hash of everything that a method may depend on, other than its own body.
+/// The encoding of a method already contains the contracts of the called procedures, so
+/// a change in the contract of a callee is detected by a change of the caller's hash.
+/// The hash function is stable across runs, unlike the default one of the standard
+/// library, and the version of Prusti is hashed because it may change the encoding.
+fn hash_context(version: &str, program: &vir::Program) -> u64 {
+    let mut hasher = FnvHasher::default();
+    version.hash(&mut hasher);
+    for domain in &program.domains {
+        domain.to_string().hash(&mut hasher);
+    }
+    for field in &program.fields {
+        field.to_string().hash(&mut hasher);
+    }
+    for function in &program.functions {
+        function.to_string().hash(&mut hasher);
+    }
+    for predicate in &program.predicates {
+        predicate.to_string().hash(&mut hasher);
+    }
+    let mut builtin_method_strings: Vec<_> = program.builtin_methods.iter()
+        .map(|m| m.to_string())
+        .collect();
+    builtin_method_strings.sort();
+    builtin_method_strings.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The key of a method in the verification cache.
+fn hash_method(context_hash: u64, method: &vir::CfgMethod) -> u64 {
+    let mut hasher = FnvHasher::default();
+    context_hash.hash(&mut hasher);
+    method.to_string().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Parse the content of a cache file, in which each line has the form `<hash> <method name>`.
+fn parse_cache(content: &str) -> HashMap<String, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ' ');
+            let hash = parts.next()?.parse::<u64>().ok()?;
+            let name = parts.next()?;
+            Some((name.to_string(), hash))
+        })
+        .collect()
+}
+
+/// The content of a cache file, which is read back by `parse_cache`.
+fn format_cache(cache: &HashMap<String, u64>) -> String {
+    let mut lines: Vec<_> = cache
+        .iter()
+        .map(|(name, hash)| format!("{} {}", hash, name))
+        .collect();
+    lines.sort();
+    lines.join("\n")
+}
+
+fn outcome_status(timed_out: bool, errors: &[viper::VerificationError]) -> &'static str {
+    if timed_out {
+        "timeout"
+    } else if errors.is_empty() {
+        "verified"
+    } else {
+        "failed"
+    }
+}
+
+/// The file in which Silicon writes its statistics after each verified program.
+fn statistics_path() -> PathBuf {
+    PathBuf::from(config::log_dir()).join("viper_tmp").join("statistics.txt")
+}
+
+/// Collect the numeric entries of the statistics written by Silicon, which are either lines of
+/// the form `key: value` (or `key = value`), or the `:key value` pairs reported by Z3.
+fn parse_statistics(content: &str) -> BTreeMap<String, u64> {
+    let tokens: Vec<&str> = content
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')' || c == ',')
+        .map(|token| token.trim_matches(|c| c == ':' || c == '='))
+        .filter(|token| !token.is_empty())
+        .collect();
+    let mut statistics = BTreeMap::new();
+    for pair in tokens.windows(2) {
+        if pair[0].parse::<f64>().is_ok() {
+            continue;
+        }
+        if let Ok(value) = pair[1].parse::<u64>() {
+            statistics.insert(pair[0].to_string(), value);
+        }
+    }
+    statistics
+}
+
+fn duration_to_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
 impl<'v, 'r, 'a, 'tcx> VerifierSpec for Verifier<'v, 'r, 'a, 'tcx> {
     fn verify(&mut self, task: &VerificationTask) -> VerificationResult {
         let start = Instant::now();
@@ -195,133 +713,330 @@ impl<'v, 'r, 'a, 'tcx> VerifierSpec for Verifier<'v, 'r, 'a, 'tcx> {
             }
         }
 
+        for &proc_id in &task.procedures {
+            self.encoder.check_model_fields(proc_id);
+            self.encoder.check_contract_visibility(proc_id);
+        }
+
         for &proc_id in task.procedures.iter().rev() {
             self.encoder.queue_procedure_encoding(proc_id);
         }
         self.encoder.process_encoding_queue();
 
+        // The procedures that use unsupported features are not verified
+        let encoding_errors = self.encoder.get_encoding_errors();
+        self.report_encoding_errors(&encoding_errors);
+
         let duration = start.elapsed();
         info!(
             "Encoding to Viper successful ({}.{} seconds)",
             duration.as_secs(),
             duration.subsec_millis() / 10
         );
+        let fold_unfold_duration = self.encoder.get_fold_unfold_duration();
+        let mut phase_durations = vec![
+            ("encoding", duration - fold_unfold_duration),
+            ("fold-unfold", fold_unfold_duration),
+        ];
         let start = Instant::now();
 
-        let program = {
-            let ast = &self.ast_factory;
-
-            let domains = self.encoder.get_used_viper_domains();
-            let fields = self.encoder.get_used_viper_fields().to_viper(ast);
-            let builtin_methods = self.encoder.get_used_builtin_methods();
-            let mut methods = self.encoder.get_used_viper_methods();
-            let mut functions = self.encoder.get_used_viper_functions();
-            if config::simplify_functions() {
-                let (new_methods, new_functions) = optimisations::functions::inline_constant_functions(
-                    methods, functions);
-                methods = new_methods;
-                functions = new_functions
-                    .into_iter()
-                    .map(|mut f| {
-                        optimisations::functions::simplify(&mut f);
-                        optimisations::folding::FoldingOptimiser::optimise(f)
-                    })
-                    .collect();
-            }
-            let mut viper_functions: Vec<_> = functions.into_iter().map(|f| f.to_viper(ast)).collect();
-            let mut viper_methods: Vec<_> = methods.into_iter().map(|m| m.to_viper(ast)).collect();
-            viper_methods.extend(builtin_methods.into_iter().map(|m| m.to_viper(ast)));
-            let mut predicates = self.encoder.get_used_viper_predicates().to_viper(ast);
+        let vir_domains = self.encoder.get_used_viper_domains();
+        let vir_fields = self.encoder.get_used_viper_fields();
+        let builtin_methods = self.encoder.get_used_builtin_methods();
+        let vir_predicates = self.encoder.get_used_viper_predicates();
+        let mut methods = self.encoder.get_used_viper_methods();
+        let mut functions = self.encoder.get_used_viper_functions();
+        if config::simplify_functions() {
+            let (new_methods, new_functions) = optimisations::functions::inline_constant_functions(
+                methods, functions);
+            methods = new_methods;
+            functions = new_functions
+                .into_iter()
+                .map(|mut f| {
+                    optimisations::functions::simplify(&mut f);
+                    optimisations::folding::FoldingOptimiser::optimise(f)
+                })
+                .collect();
+        }
+        if config::inline_pure_functions() {
+            let (new_methods, new_functions) = optimisations::functions::inline_small_functions(
+                methods, functions, config::inline_pure_functions_max_size());
+            methods = new_methods;
+            functions = new_functions;
+        }
+        let mut program = vir::Program {
+            domains: vir_domains,
+            fields: vir_fields,
+            builtin_methods,
+            methods,
+            functions,
+            predicates: vir_predicates,
+        };
+        optimisations::optimise_program(&mut program, &config::optimizations());
+        plugins::run_transformations(&mut program);
+        let optimisation_duration = start.elapsed();
+        phase_durations.push(("optimisation", optimisation_duration));
+        if config::dump_vir_program() {
+            let source_path = self.env.source_path();
+            let source_filename = source_path.file_name().unwrap().to_str().unwrap();
+            log::report("vir_program", format!("{}.vir", source_filename), program.to_text());
+        }
+        let methods = mem::replace(&mut program.methods, vec![]);
+        if config::dump_viper_program() {
+            self.dump_annotated_methods(&methods);
+        }
+        // The verified methods, in which the failing assertions are looked up to explain them.
+        let explained_methods = if config::explain_assertions() != ExplainAssertions::Off {
+            methods.clone()
+        } else {
+            vec![]
+        };
 
-            info!(
-                "Viper encoding uses {} domains, {} fields, {} functions, {} predicates, {} methods",
-                domains.len(), fields.len(), viper_functions.len(), predicates.len(),
-                viper_methods.len()
-            );
+        let context_hash = hash_context(PRUSTI_VERSION, &program);
+        let method_infos: Vec<(String, u64, usize)> = methods
+            .iter()
+            .map(|method| {
+                let vir_statements: usize = method.basic_blocks.iter().map(|b| b.stmts.len()).sum();
+                (method.name(), hash_method(context_hash, method), vir_statements)
+            })
+            .collect();
 
-            // Add a function that represents the symbolic read permission amount.
-            viper_functions.push(ast.function(
-                "read$",
-                &[],
-                ast.perm_type(),
-                &[],
-                &[
-                    ast.lt_cmp(ast.no_perm(), ast.result(ast.perm_type())),
-                    ast.lt_cmp(ast.result(ast.perm_type()), ast.full_perm()),
-                ],
-                ast.no_position(),
-                None,
-            ));
+        info!(
+            "Viper encoding uses {} domains, {} fields, {} functions, {} predicates, {} methods",
+            program.domains.len(), program.fields.len(), program.functions.len(),
+            program.predicates.len(), methods.len() + program.builtin_methods.len()
+        );
 
-            // Add a predicate that represents the dead loan token.
-            predicates.push(
-                ast.predicate(
-                    "DeadBorrowToken$",
-                    &[vir::LocalVar {
-                        name: "borrow".to_string(),
-                        typ: vir::Type::Int,
-                    }
-                    .to_viper_decl(ast)],
-                    None,
-                ),
+        let viper_context = if let VerifierBackend::Local { ast_factory: ref ast, .. } = self.backend {
+            let mut functions: Vec<_> = program.functions.iter().map(|f| f.to_viper(ast)).collect();
+            functions.push(vir::read_perm_function(ast));
+            let mut predicates = program.predicates.to_viper(ast);
+            predicates.push(vir::dead_borrow_token_predicate(ast));
+            let viper_context = ViperProgramContext {
+                domains: program.domains.iter().map(|d| d.to_viper(ast)).collect(),
+                fields: program.fields.to_viper(ast),
+                functions,
+                predicates,
+                builtin_methods: program.builtin_methods.iter().map(|m| m.to_viper(ast)).collect(),
+            };
+
+            let duration = start.elapsed();
+            info!(
+                "Construction of JVM objects successful ({}.{} seconds)",
+                duration.as_secs(),
+                duration.subsec_millis() / 10
             );
+            phase_durations.push(("construction of JVM objects", duration - optimisation_duration));
+            Some(viper_context)
+        } else {
+            None
+        };
 
-            ast.program(&domains, &fields, &viper_functions, &predicates, &viper_methods)
+        let context = ProgramContext {
+            program,
+            viper: viper_context,
         };
 
-        if config::dump_viper_program() {
-            // Dump Viper program
-            let source_path = self.env.source_path();
-            let source_filename = source_path.file_name().unwrap().to_str().unwrap();
-            log::report(
-                "viper_program",
-                format!("{}.vpr", source_filename),
-                self.ast_utils.pretty_print(program),
-            );
-        }
+        let verify_separately = config::incremental()
+            || !config::report_json().is_empty()
+            || config::stream_json()
+            || config::verification_timeout_ms() > 0
+            || config::report_verifier_statistics();
+        let verification_start = Instant::now();
+        // The procedures whose verification timed out, or `None` if the verification of the
+        // functions or of the whole program timed out.
+        let mut timeouts: Vec<Option<ProcedureDefId>> = vec![];
+        let verification_errors = if verify_separately {
+            let old_cache = if config::incremental() {
+                self.load_cache()
+            } else {
+                HashMap::new()
+            };
+            let mut new_cache = HashMap::new();
+            let procedure_ids: HashMap<String, ProcedureDefId> = task.procedures
+                .iter()
+                .map(|&proc_id| (self.encoder.encode_item_name(proc_id), proc_id))
+                .collect();
 
-        let duration = start.elapsed();
-        info!(
-            "Construction of JVM objects successful ({}.{} seconds)",
-            duration.as_secs(),
-            duration.subsec_millis() / 10
-        );
-        let start = Instant::now();
+            // The functions are verified on their own, so that their errors are not reported
+            // again for each method.
+            let mut functions_verified = old_cache.get(FUNCTIONS_CACHE_KEY) == Some(&context_hash);
+            let function_outcome = if functions_verified {
+                MethodOutcome {
+                    name: FUNCTIONS_CACHE_KEY.to_string(),
+                    status: "cached",
+                    duration: None,
+                    vir_statements: 0,
+                    errors: vec![],
+                    statistics: BTreeMap::new(),
+                }
+            } else {
+                self.emit_event(JsonEvent::Started {
+                    procedure: FUNCTIONS_CACHE_KEY,
+                });
+                let (result, duration) = self.verify_methods(&context, &[]);
+                let statistics = self.take_statistics();
+                functions_verified = result == viper::VerificationResult::Success();
+                let timed_out = result == viper::VerificationResult::Timeout();
+                if timed_out {
+                    timeouts.push(None);
+                }
+                let errors = result_errors(result);
+                MethodOutcome {
+                    name: FUNCTIONS_CACHE_KEY.to_string(),
+                    status: outcome_status(timed_out, &errors),
+                    duration: Some(duration),
+                    vir_statements: 0,
+                    errors,
+                    statistics,
+                }
+            };
+            self.emit_outcome_events(&function_outcome);
+            // Only the successful verifications are cached, not the failed, timed out or
+            // aborted ones
+            if functions_verified {
+                new_cache.insert(FUNCTIONS_CACHE_KEY.to_string(), context_hash);
+            }
 
-        let verification_result: viper::VerificationResult = self.verifier.verify(program);
+            let mut outcomes = vec![];
+            for ((name, hash, vir_statements), method) in method_infos.into_iter().zip(methods.into_iter()) {
+                let proc_id = procedure_ids.get(&name).cloned();
+                let display_name = proc_id
+                    .map(|proc_id| self.env.get_item_name(proc_id))
+                    .unwrap_or_else(|| name.clone());
+                let outcome = if old_cache.get(&name) == Some(&hash) {
+                    new_cache.insert(name, hash);
+                    MethodOutcome {
+                        name: display_name,
+                        status: "cached",
+                        duration: None,
+                        vir_statements,
+                        errors: vec![],
+                        statistics: BTreeMap::new(),
+                    }
+                } else {
+                    self.emit_event(JsonEvent::Started {
+                        procedure: &display_name,
+                    });
+                    let (result, duration) = self.verify_methods(&context, &[method]);
+                    let statistics = self.take_statistics();
+                    for (key, value) in &statistics {
+                        info!("Statistics of {}: {} = {}", display_name, key, value);
+                    }
+                    if result == viper::VerificationResult::Success() {
+                        new_cache.insert(name, hash);
+                    }
+                    let timed_out = result == viper::VerificationResult::Timeout();
+                    if timed_out {
+                        timeouts.push(proc_id);
+                    }
+                    let mut errors = result_errors(result);
+                    errors.retain(|error| !function_outcome.errors.contains(error));
+                    MethodOutcome {
+                        name: display_name,
+                        status: outcome_status(timed_out, &errors),
+                        duration: Some(duration),
+                        vir_statements,
+                        errors,
+                        statistics,
+                    }
+                };
+                self.emit_outcome_events(&outcome);
+                outcomes.push(outcome);
+            }
 
-        let duration = start.elapsed();
-        info!(
-            "Verification complete ({}.{} seconds)",
-            duration.as_secs(),
-            duration.subsec_millis() / 10
-        );
+            if config::incremental() {
+                self.store_cache(&new_cache);
+                self.report_summary(&outcomes);
+            }
+            if !config::report_json().is_empty() {
+                self.write_json_report(&context, &function_outcome, &outcomes, &encoding_errors);
+            }
+
+            let mut verification_errors = function_outcome.errors;
+            for outcome in outcomes {
+                verification_errors.extend(outcome.errors);
+            }
+            verification_errors
+        } else {
+            if let VerifierBackend::Local {
+                ref ast_utils,
+                ref ast_factory,
+                ..
+            } = self.backend
+            {
+                if config::dump_viper_program() {
+                    // Dump Viper program
+                    let mut program = context.program.clone();
+                    program.methods = methods.clone();
+                    let source_path = self.env.source_path();
+                    let source_filename = source_path.file_name().unwrap().to_str().unwrap();
+                    log::report(
+                        "viper_program",
+                        format!("{}.vpr", source_filename),
+                        ast_utils.pretty_print(program.to_viper(ast_factory)),
+                    );
+                }
+            }
 
-        let verification_errors = match verification_result {
-            viper::VerificationResult::Failure(errors) => errors,
-            _ => vec![],
+            let (result, duration) = self.verify_methods(&context, &methods);
+            if result == viper::VerificationResult::Timeout() {
+                timeouts.push(None);
+            }
+            info!(
+                "Verification complete ({}.{} seconds)",
+                duration.as_secs(),
+                duration.subsec_millis() / 10
+            );
+            result_errors(result)
         };
+        phase_durations.push(("verification", verification_start.elapsed()));
+        if config::check_spec_sanity() {
+            let sanity_start = Instant::now();
+            self.check_spec_sanity(&context);
+            phase_durations.push(("spec sanity checks", sanity_start.elapsed()));
+        }
+        if config::profile_encoder() {
+            self.report_profile(&phase_durations);
+        }
 
-        if verification_errors.is_empty() {
+        if verification_errors.is_empty()
+            && timeouts.is_empty()
+            && encoding_errors.is_empty()
+            && !self.daemon_failed.get()
+        {
             VerificationResult::Success
         } else {
             let error_manager = self.encoder.error_manager();
 
+            let mut explanations = vec![];
             for verification_error in verification_errors {
                 debug!("Verification error: {:?}", verification_error);
+                if !explained_methods.is_empty() {
+                    explanations.push(
+                        error_manager.explain(&verification_error, &explained_methods),
+                    );
+                }
                 let compilation_error = error_manager.translate(&verification_error);
                 debug!("Compilation error: {:?}", compilation_error);
-                if let Some(reason_span) = compilation_error.reason_span {
-                    self.env.span_err_with_reason(
-                        compilation_error.span,
-                        &format!("[Prusti] {}", compilation_error.message),
-                        reason_span,
-                    )
-                } else {
-                    self.env.span_err(
-                        compilation_error.span,
-                        &format!("[Prusti] {}", compilation_error.message),
-                    )
+                self.env.span_err_with_notes(
+                    compilation_error.span,
+                    &format!("[Prusti] {}", compilation_error.message),
+                    compilation_error.reason_span,
+                    compilation_error.note.as_ref().map(|note| note.as_str()),
+                    compilation_error.help.as_ref().map(|help| help.as_str()),
+                );
+            }
+            if !explanations.is_empty() {
+                self.report_explanations(&explanations);
+            }
+            for timeout in timeouts {
+                match timeout {
+                    Some(proc_id) => self.env.span_err(
+                        self.env.get_item_span(proc_id),
+                        "[Prusti] verification timed out",
+                    ),
+                    None => self.env.err("[Prusti] verification timed out"),
                 }
             }
             VerificationResult::Failure
@@ -332,3 +1047,64 @@ impl<'v, 'r, 'a, 'tcx> VerifierSpec for Verifier<'v, 'r, 'a, 'tcx> {
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoder::vir::{Expr, Function, LocalVar, Type};
+
+    fn program(body: i32) -> vir::Program {
+        let function = Function {
+            name: "f".to_string(),
+            formal_args: vec![],
+            return_type: Type::Int,
+            pres: vec![],
+            posts: vec![],
+            body: Some(Expr::from(body)),
+        };
+        vir::Program {
+            domains: vec![],
+            fields: vec![],
+            builtin_methods: vec![],
+            methods: vec![],
+            functions: vec![function],
+            predicates: vec![],
+        }
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let mut cache = HashMap::new();
+        cache.insert(FUNCTIONS_CACHE_KEY.to_string(), 42);
+        cache.insert("m_foo$$bar".to_string(), u64::max_value());
+        assert_eq!(parse_cache(&format_cache(&cache)), cache);
+        assert!(parse_cache("invalid line").is_empty());
+    }
+
+    #[test]
+    fn test_cache_hit_needs_the_same_context_and_method() {
+        let method = vir::CfgMethod::new(
+            "m".to_string(),
+            vec![LocalVar::new("x", Type::Int)],
+            vec![],
+            vec![],
+            vec![],
+        );
+        let context_hash = hash_context("1.0.0", &program(0));
+        assert_eq!(context_hash, hash_context("1.0.0", &program(0)));
+        assert_eq!(hash_method(context_hash, &method), hash_method(context_hash, &method));
+
+        // A different version of Prusti or a different function invalidates the cached methods
+        let other_contexts = vec![
+            hash_context("1.0.1", &program(0)),
+            hash_context("1.0.0", &program(1)),
+        ];
+        for other_context_hash in other_contexts {
+            assert_ne!(context_hash, other_context_hash);
+            assert_ne!(
+                hash_method(context_hash, &method),
+                hash_method(other_context_hash, &method)
+            );
+        }
+    }
+}
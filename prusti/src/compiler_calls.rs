@@ -6,6 +6,8 @@
 
 use getopts;
 use prusti_interface;
+use prusti_interface::config;
+use prusti_interface::report::user;
 use rustc;
 use rustc::session;
 use rustc_codegen_utils::codegen_backend::CodegenBackend;
@@ -94,8 +96,9 @@ impl<'a> CompilerCalls<'a> for PrustiCompilerCalls {
             let start = Instant::now();
 
             prusti_interface::parser::register_attributes(state);
-            let untyped_specifications = prusti_interface::parser::rewrite_crate(state);
-            put_specifications.set(Some(untyped_specifications));
+            let (untyped_specifications, specification_warnings) =
+                prusti_interface::parser::rewrite_crate(state);
+            put_specifications.set(Some((untyped_specifications, specification_warnings)));
 
             let duration = start.elapsed();
             info!(
@@ -113,10 +116,19 @@ impl<'a> CompilerCalls<'a> for PrustiCompilerCalls {
             trace!("[after_analysis.callback] enter");
             let start = Instant::now();
 
-            let untyped_specifications = get_specifications.replace(None).unwrap();
+            let (untyped_specifications, specification_warnings) =
+                get_specifications.replace(None).unwrap();
             let typed_specifications = typeck::type_specifications(state, untyped_specifications);
             debug!("typed_specifications = {:?}", typed_specifications);
 
+            // Report useless specifications only if the specifications type-check
+            if !state.session.has_errors() {
+                prusti_interface::parser::report_specification_warnings(
+                    state.session,
+                    specification_warnings,
+                );
+            }
+
             let duration = start.elapsed();
             info!(
                 "Type-checking of annotations successful ({}.{} seconds)",
@@ -125,8 +137,28 @@ impl<'a> CompilerCalls<'a> for PrustiCompilerCalls {
             );
 
             // Call the verifier
-            if Ok(String::from("true")) != var("PRUSTI_NO_VERIFY") {
+            if config::check_specs_only() {
+                if !state.session.has_errors() {
+                    user::message("Type-checking of specifications successful");
+                }
+                warn!("Verification skipped due to the CHECK_SPECS_ONLY flag");
+            } else if !config::verify_contracts() {
+                warn!("Verification skipped due to the CONTRACT_CHECKS flag");
+            } else if Ok(String::from("true")) != var("PRUSTI_NO_VERIFY") {
                 verifier::verify(state, typed_specifications);
+                let is_library = state
+                    .session
+                    .crate_types
+                    .borrow()
+                    .contains(&session::config::CrateType::CrateTypeRlib);
+                if config::contract_metadata() && is_library && !state.session.has_errors() {
+                    if let Some(out_dir) = state.out_dir {
+                        prusti_interface::contract_metadata::export_contracts(
+                            state.tcx.unwrap(),
+                            out_dir,
+                        );
+                    }
+                }
             } else {
                 warn!("Verification skipped due to PRUSTI_NO_VERIFY env variable");
             }
@@ -139,7 +171,10 @@ impl<'a> CompilerCalls<'a> for PrustiCompilerCalls {
             old_after_analysis_callback(state);
         };
 
-        if Ok(String::from("true")) != var("PRUSTI_FULL_COMPILATION") {
+        // The runtime checks are useful only if the program is compiled
+        if Ok(String::from("true")) != var("PRUSTI_FULL_COMPILATION")
+            && config::verify_contracts()
+        {
             debug!("The program will not be compiled.");
             control.after_analysis.stop = Compilation::Stop;
         }
@@ -49,6 +49,18 @@ pub fn main() {
             args.remove(1);
         }
 
+        // `--prusti-check-specs-only` is not a rustc flag: record it and remove it
+        if let Some(pos) = args.iter().position(|arg| arg == "--prusti-check-specs-only") {
+            args.remove(pos);
+            env::set_var("PRUSTI_CHECK_SPECS_ONLY", "true");
+        }
+
+        // The same for `--profile-encoder`
+        if let Some(pos) = args.iter().position(|arg| arg == "--profile-encoder") {
+            args.remove(pos);
+            env::set_var("PRUSTI_PROFILE_ENCODER", "true");
+        }
+
         // this conditional check for the --sysroot flag is there so users can call
         // `prusti-filter` directly without having to pass --sysroot or anything
         if !args.iter().any(|s| s == "--sysroot") {
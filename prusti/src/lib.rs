@@ -13,10 +13,12 @@
 //! # Prusti Design
 //!
 //! Prusti registers attributes for specifying programs:
-//! `#[requires="<precondition>"]`, `#[ensures="<postcondition>"]`, and
-//! `#[invariant="<loop invariant>"]`. The registration and control of
-//! the entire workflow is done by the rustc driver implemented in the
-//! `lib/driver.rs`.
+//! `#[requires="<precondition>"]`, `#[ensures="<postcondition>"]`,
+//! `#[after_expiry="<pledge>"]`, `#[assert_on_expiry="<obligation>, <pledge>"]`,
+//! `#[invariant="<loop or type invariant>"]`, `#[open_invariant]`, `#[prusti::skip]`, and
+//! `#[extern_spec]` (on modules that specify functions and methods of other crates). The
+//! registration and control of the entire workflow is done by the rustc driver implemented in
+//! the `lib/driver.rs`.
 //!
 //! The Rust compiler exposes procedures for parsing strings into Rust
 //! AST, but it does not expose procedures for type-checking AST.
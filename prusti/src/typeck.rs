@@ -9,8 +9,9 @@
 //! Please see the `parser.rs` file for more information about
 //! specifications.
 
+use prusti_interface::constants::PRUSTI_SPEC_ATTR;
 use prusti_interface::specifications::{
-    Assertion, AssertionKind, Expression, ExpressionId, ForAllVars, Specification,
+    Assertion, AssertionKind, Expression, ExpressionId, ForAllVars, SpecID, Specification,
     SpecificationSet, Trigger, TypedAssertion, TypedSpecification, TypedSpecificationMap,
     TypedTriggerSet, UntypedAssertion, UntypedSpecification, UntypedSpecificationMap,
     UntypedTriggerSet,
@@ -20,7 +21,7 @@ use rustc;
 use rustc::hir::{self, intravisit};
 use rustc::ty::TyCtxt;
 use rustc_driver::driver;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use syntax::ast;
 use syntax_pos::Span;
 
@@ -35,6 +36,7 @@ pub fn type_specifications(
     intravisit::walk_crate(&mut collector, tcx.hir.krate());
     let typed_specifications = convert_to_typed(
         untyped_specifications,
+        &collector.spec_ids,
         &collector.typed_expressions,
         &collector.typed_forallargs,
     );
@@ -107,6 +109,14 @@ fn type_assertion(
                     type_trigger_set(trigger_set, typed_expressions),
                     type_assertion(assertion, typed_expressions, typed_forallargs),
                 ),
+                AssertionKind::Exists(vars, trigger_set, assertion) => AssertionKind::Exists(
+                    ForAllVars {
+                        id: vars.id,
+                        vars: typed_forallargs[&vars.id].clone(),
+                    },
+                    type_trigger_set(trigger_set, typed_expressions),
+                    type_assertion(assertion, typed_expressions, typed_forallargs),
+                ),
                 AssertionKind::Pledge(Some(reference), lhs, rhs) => AssertionKind::Pledge(
                     Some(Expression {
                         id: reference.id,
@@ -133,11 +143,16 @@ fn type_specification(
     Specification {
         typ: specification.typ,
         assertion: type_assertion(specification.assertion, typed_expressions, typed_forallargs),
+        reason: specification.reason,
     }
 }
 
+/// Convert the specifications of the items that are in the crate. The specifications of the
+/// items removed by a disabled `#[cfg(..)]` attribute (e.g. a `#[cfg(test)]` module compiled
+/// without `--test`) have been parsed, but they have no type and they are not needed.
 fn convert_to_typed(
     untyped_specifications: UntypedSpecificationMap,
+    spec_ids: &HashSet<SpecID>,
     typed_expressions: &HashMap<ExpressionId, rustc::hir::Expr>,
     typed_forallargs: &HashMap<ExpressionId, Vec<rustc::hir::Arg>>,
 ) -> TypedSpecificationMap {
@@ -149,6 +164,13 @@ fn convert_to_typed(
     };
     untyped_specifications
         .into_iter()
+        .filter(|(id, _)| {
+            let is_in_crate = spec_ids.contains(id);
+            if !is_in_crate {
+                debug!("The item of the specification {:?} has been removed by `#[cfg(..)]`", id);
+            }
+            is_in_crate
+        })
         .map(|(id, untyped_specification)| match untyped_specification {
             SpecificationSet::Procedure(precondition, postcondition) => (
                 id,
@@ -166,6 +188,8 @@ fn convert_to_typed(
 struct TypeCollector<'a, 'tcx: 'a> {
     pub typed_expressions: HashMap<ExpressionId, rustc::hir::Expr>,
     pub typed_forallargs: HashMap<ExpressionId, Vec<rustc::hir::Arg>>,
+    /// The ids of the specifications attached to the items and loops of the crate.
+    pub spec_ids: HashSet<SpecID>,
     tcx: TyCtxt<'a, 'tcx, 'tcx>,
 }
 
@@ -174,6 +198,7 @@ impl<'a, 'tcx: 'a> TypeCollector<'a, 'tcx> {
         Self {
             typed_expressions: HashMap::new(),
             typed_forallargs: HashMap::new(),
+            spec_ids: HashSet::new(),
             tcx: tcx,
         }
     }
@@ -200,6 +225,13 @@ impl<'a, 'tcx: 'a, 'hir> intravisit::Visitor<'tcx> for TypeCollector<'a, 'tcx> {
         intravisit::NestedVisitorMap::All(map)
     }
 
+    fn visit_attribute(&mut self, attr: &'tcx ast::Attribute) {
+        if attr.path.to_string() == PRUSTI_SPEC_ATTR {
+            let spec_id: u64 = get_attr_value(attr).parse().unwrap();
+            self.spec_ids.insert(SpecID::from(spec_id));
+        }
+    }
+
     fn visit_fn(
         &mut self,
         fk: intravisit::FnKind<'tcx>,
@@ -6,6 +6,8 @@
 
 //! A module that invokes the verifier `prusti-viper`
 
+use prusti_interface::config;
+use prusti_interface::data::ProcedureDefId;
 use prusti_interface::data::VerificationResult;
 use prusti_interface::data::VerificationTask;
 use prusti_interface::environment::Environment;
@@ -34,9 +36,8 @@ pub fn verify<'r, 'a: 'r, 'tcx: 'a>(
 
         debug!("Prepare verification task...");
         let annotated_procedures = env.get_annotated_procedures();
-        let verification_task = VerificationTask {
-            procedures: annotated_procedures,
-        };
+        let procedures = select_entry_point_procedures(&env, annotated_procedures);
+        let verification_task = VerificationTask { procedures };
         debug!("Verification task: {:?}", &verification_task);
 
         user::message(format!(
@@ -93,3 +94,51 @@ pub fn verify<'r, 'a: 'r, 'tcx: 'a>(
 
     trace!("[verify] exit");
 }
+
+/// Restrict the verification to the procedures that are reachable from the entry point (see
+/// `ENTRY_POINT`), if there is one. When the crate is compiled with the test harness, the
+/// `#[test]` functions are entry points as well. The callees are verified before their callers,
+/// and each call checks the precondition of the callee, so that no assertion fails on an
+/// execution that starts at an entry point.
+fn select_entry_point_procedures(
+    env: &Environment,
+    procedures: Vec<ProcedureDefId>,
+) -> Vec<ProcedureDefId> {
+    let entry_point = config::entry_point();
+    if entry_point.is_empty() {
+        return procedures;
+    }
+    let mut entries: Vec<_> = procedures
+        .iter()
+        .cloned()
+        .find(|&def_id| env.get_item_name(def_id) == entry_point)
+        .into_iter()
+        .collect();
+    if env.is_test_harness() {
+        entries.extend(
+            procedures
+                .iter()
+                .cloned()
+                .filter(|&def_id| env.has_attribute_name(def_id, "test")),
+        );
+    }
+    if entries.is_empty() {
+        env.err(&format!(
+            "[Prusti] the entry point '{}' is not a procedure of the crate",
+            entry_point
+        ));
+        return vec![];
+    }
+    let (verified, unverified): (Vec<_>, Vec<_>) = env
+        .get_reachable_procedures(&entries, &procedures)
+        .into_iter()
+        .partition(|def_id| procedures.contains(def_id));
+    for def_id in unverified {
+        user::message(format!(
+            "The procedure '{}' is reachable from the entry point, but it is not \
+             verified (e.g. because it is trusted)",
+            env.get_item_name(def_id)
+        ));
+    }
+    verified
+}
@@ -0,0 +1,9 @@
+#[macro_use]
+extern crate prusti_contracts;
+
+fn test(x: u32) -> u32 {
+    body_invariant!(x > 0); //~ ERROR `body_invariant!` can only be used in the body of a loop
+    x
+}
+
+fn main() {}
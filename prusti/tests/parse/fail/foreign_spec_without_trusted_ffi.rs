@@ -0,0 +1,8 @@
+extern crate prusti_contracts;
+
+extern "C" {
+    #[ensures="result >= 0"]
+    fn abs(x: i32) -> i32; //~ ERROR the specification of a foreign function requires `#[trusted_ffi]`
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+extern crate prusti_contracts;
+
+#[ensures(forall(i: u32, i < 100))] //~ ERROR malformed specification
+fn without_closure() {}
+
+fn main() {}
@@ -0,0 +1,8 @@
+extern crate prusti_contracts;
+
+#[prusti::overflow_checks(maybe)] //~ ERROR the argument of `prusti::overflow_checks` must be `on` or `off`
+fn add(x: u32, y: u32) -> u32 {
+    x + y
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+extern crate prusti_contracts;
+
+#[ensures_on_panic="result == 0"] //~ ERROR a panic postcondition cannot refer to `result`
+fn zero() -> u32 {
+    0
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+extern crate prusti_contracts;
+
+fn count(n: u32) {
+    let mut i = 0;
+    #[invariant(reason = "the counter is bounded", "i <= n")] //~ ERROR only preconditions and postconditions can have a reason
+    while i < n {
+        i += 1;
+    }
+}
+
+#[requires(reason = "n must be small")] //~ ERROR malformed specification
+fn small(n: u32) {}
+
+fn main() {}
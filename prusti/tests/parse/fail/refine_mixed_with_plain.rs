@@ -0,0 +1,17 @@
+extern crate prusti_contracts;
+
+trait Double {
+    fn double(&self, x: u32) -> u32;
+}
+
+struct S;
+
+impl Double for S {
+    #[requires="x < 100"]
+    #[refine_requires="x < 1000"] //~ ERROR `refine_requires` cannot be combined with `requires`
+    fn double(&self, x: u32) -> u32 {
+        2 * x
+    }
+}
+
+fn main() {}
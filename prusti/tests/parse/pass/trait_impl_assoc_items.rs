@@ -0,0 +1,27 @@
+extern crate prusti_contracts;
+
+trait Bounded {
+    const MAX: u32;
+
+    type Value;
+
+    fn get(&self) -> Self::Value;
+}
+
+struct Counter {
+    value: u32,
+}
+
+impl Bounded for Counter {
+    const MAX: u32 = 10;
+
+    type Value = u32;
+
+    #[requires="self.value <= Self::MAX"]
+    #[ensures="result <= Self::MAX"]
+    fn get(&self) -> Self::Value {
+        self.value
+    }
+}
+
+fn main() {}
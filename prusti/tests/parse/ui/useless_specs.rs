@@ -0,0 +1,26 @@
+// Tests the warnings about specifications that have no effect.
+
+#![allow(warnings)]
+
+extern crate prusti_contracts;
+
+#[requires="true"]
+pub fn test1(x: i32) -> i32 {
+    x
+}
+
+#[requires="x > 0"]
+pub fn test2(x: i32, n: i32) -> i32 {
+    let mut i = 0;
+    #[invariant="n > 0"]
+    while i < x {
+        i += 1;
+    }
+    #[invariant="i >= 0"]
+    while i < 2 * x {
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
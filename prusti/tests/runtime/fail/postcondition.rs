@@ -0,0 +1,16 @@
+// error-pattern:the postcondition does not hold
+
+extern crate prusti_contracts;
+
+#[ensures="result > x"]
+fn wrong_incr(x: i32) -> i32 {
+    if x > 5 {
+        return x;
+    }
+    x + 1
+}
+
+fn main() {
+    wrong_incr(1);
+    wrong_incr(10);
+}
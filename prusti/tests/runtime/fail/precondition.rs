@@ -0,0 +1,12 @@
+// error-pattern:the precondition does not hold
+
+extern crate prusti_contracts;
+
+#[requires="x > 0"]
+fn positive(x: i32) -> i32 {
+    x
+}
+
+fn main() {
+    positive(-1);
+}
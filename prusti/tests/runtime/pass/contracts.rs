@@ -0,0 +1,43 @@
+extern crate prusti_contracts;
+
+struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[pure]
+    fn get(&self) -> u32 {
+        self.value
+    }
+
+    #[requires="self.get() < 100"]
+    #[ensures="self.get() == old(self.get()) + 1"]
+    #[ensures="self.get() > 0"]
+    fn incr(&mut self) {
+        self.value += 1;
+    }
+}
+
+#[requires="x >= 0"]
+#[ensures="result >= x"]
+#[ensures="x > 10 ==> result == x"]
+fn at_least_ten(x: i32) -> i32 {
+    if x > 10 {
+        return x;
+    }
+    10
+}
+
+#[ensures="match result { Some(v) => v < 10, None => true }"]
+fn find_digit(v: u32) -> Option<u32> {
+    if v < 10 { Some(v) } else { None }
+}
+
+fn main() {
+    assert!(at_least_ten(3) == 10);
+    assert!(at_least_ten(42) == 42);
+    assert!(find_digit(7) == Some(7));
+    let mut counter = Counter { value: 0 };
+    counter.incr();
+    assert!(counter.get() == 1);
+}
@@ -1,8 +1,12 @@
 extern crate compiletest_rs;
+extern crate serde_json;
 
 use compiletest_rs::{common, run_tests, Config};
-use std::env::{remove_var, set_var, var};
+use serde_json::Value;
+use std::env::{self, remove_var, set_var, var};
+use std::fs;
 use std::path::PathBuf;
+use std::process::{Command, Output};
 
 static LOCAL_DRIVER_PATH: &'static str = "target/debug/prusti-driver";
 static WORKSPACE_DRIVER_PATH: &'static str = "../target/debug/prusti-driver";
@@ -72,6 +76,7 @@ fn run_verification(group_name: &str) {
     set_var("PRUSTI_TEST", "true");
 
     set_var("PRUSTI_CHECK_BINARY_OPERATIONS", "false");
+    set_var("PRUSTI_CHECK_INTEGER_CASTS", "false");
     set_var("PRUSTI_DUMP_DEBUG_INFO", "false");
     set_var("PRUSTI_DUMP_BORROWCK_INFO", "false");
     set_var("PRUSTI_ENCODE_UNSIGNED_NUM_CONSTRAINT", "true");
@@ -111,8 +116,28 @@ fn run_verification(group_name: &str) {
         config.mode = common::Mode::RunPass;
         config.src_base = path;
         set_var("PRUSTI_CHECK_BINARY_OPERATIONS", "true");
+        set_var("PRUSTI_CHECK_INTEGER_CASTS", "true");
         run_tests(&config);
         set_var("PRUSTI_CHECK_BINARY_OPERATIONS", "false");
+        set_var("PRUSTI_CHECK_INTEGER_CASTS", "false");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/pass-dead-permissions", group_name));
+    if path.exists() {
+        config.mode = common::Mode::RunPass;
+        config.src_base = path;
+        set_var("PRUSTI_EXHALE_DEAD_PERMISSIONS", "true");
+        run_tests(&config);
+        set_var("PRUSTI_EXHALE_DEAD_PERMISSIONS", "false");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/pass-inlining", group_name));
+    if path.exists() {
+        config.mode = common::Mode::RunPass;
+        config.src_base = path;
+        set_var("PRUSTI_INLINE_PURE_FUNCTIONS", "true");
+        run_tests(&config);
+        set_var("PRUSTI_INLINE_PURE_FUNCTIONS", "false");
     }
 
     let path = PathBuf::from(format!("tests/{}/fail", group_name));
@@ -122,19 +147,250 @@ fn run_verification(group_name: &str) {
         run_tests(&config);
     }
 
+    let path = PathBuf::from(format!("tests/{}/pass-termination", group_name));
+    if path.exists() {
+        config.mode = common::Mode::RunPass;
+        config.src_base = path;
+        set_var("PRUSTI_CHECK_PURE_FUNCTION_TERMINATION", "true");
+        run_tests(&config);
+        set_var("PRUSTI_CHECK_PURE_FUNCTION_TERMINATION", "false");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/fail-termination", group_name));
+    if path.exists() {
+        config.mode = common::Mode::CompileFail;
+        config.src_base = path;
+        set_var("PRUSTI_CHECK_PURE_FUNCTION_TERMINATION", "true");
+        run_tests(&config);
+        set_var("PRUSTI_CHECK_PURE_FUNCTION_TERMINATION", "false");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/fail-provided-methods-once", group_name));
+    if path.exists() {
+        config.mode = common::Mode::CompileFail;
+        config.src_base = path;
+        set_var("PRUSTI_VERIFY_PROVIDED_METHODS_ONCE", "true");
+        run_tests(&config);
+        set_var("PRUSTI_VERIFY_PROVIDED_METHODS_ONCE", "false");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/fail-triggers", group_name));
+    if path.exists() {
+        config.mode = common::Mode::CompileFail;
+        config.src_base = path;
+        set_var("PRUSTI_STRICT_TRIGGERS", "true");
+        run_tests(&config);
+        set_var("PRUSTI_STRICT_TRIGGERS", "false");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/fail-entry-point", group_name));
+    if path.exists() {
+        config.mode = common::Mode::CompileFail;
+        config.src_base = path;
+        set_var("PRUSTI_ENTRY_POINT", "main");
+        run_tests(&config);
+        remove_var("PRUSTI_ENTRY_POINT");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/fail-spec-sanity", group_name));
+    if path.exists() {
+        config.mode = common::Mode::CompileFail;
+        config.src_base = path;
+        // The unsatisfiable specifications are reported as warnings
+        config.target_rustcflags = None;
+        set_var("PRUSTI_CHECK_SPEC_SANITY", "true");
+        run_tests(&config);
+        set_var("PRUSTI_CHECK_SPEC_SANITY", "false");
+        config.target_rustcflags = Some("-A warnings".to_string());
+    }
+
+    let path = PathBuf::from(format!("tests/{}/fail-unfold-depth", group_name));
+    if path.exists() {
+        config.mode = common::Mode::CompileFail;
+        config.src_base = path;
+        set_var("PRUSTI_MAX_UNFOLD_DEPTH", "4");
+        run_tests(&config);
+        remove_var("PRUSTI_MAX_UNFOLD_DEPTH");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/fail-timeout", group_name));
+    if path.exists() {
+        config.mode = common::Mode::CompileFail;
+        config.src_base = path;
+        // The smallest timeout, which is rounded up to one second
+        set_var("PRUSTI_VERIFICATION_TIMEOUT_MS", "1");
+        run_tests(&config);
+        remove_var("PRUSTI_VERIFICATION_TIMEOUT_MS");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/pass-check-only", group_name));
+    if path.exists() {
+        config.mode = common::Mode::RunPass;
+        config.src_base = path;
+        set_var("PRUSTI_CONFIG", format!("tests/{}/check-only.toml", group_name));
+        run_tests(&config);
+        remove_var("PRUSTI_CONFIG");
+    }
+
+    let path = PathBuf::from(format!("tests/{}/fail-check-only", group_name));
+    if path.exists() {
+        config.mode = common::Mode::CompileFail;
+        config.src_base = path;
+        set_var("PRUSTI_CONFIG", format!("tests/{}/check-only.toml", group_name));
+        run_tests(&config);
+        remove_var("PRUSTI_CONFIG");
+    }
+
     let path = PathBuf::from(format!("tests/{}/fail-overflow", group_name));
     if path.exists() {
         config.mode = common::Mode::CompileFail;
         config.src_base = path;
         set_var("PRUSTI_CHECK_BINARY_OPERATIONS", "true");
+        set_var("PRUSTI_CHECK_INTEGER_CASTS", "true");
         run_tests(&config);
         set_var("PRUSTI_CHECK_BINARY_OPERATIONS", "false");
+        set_var("PRUSTI_CHECK_INTEGER_CASTS", "false");
     }
 }
 
+/// The failing verification case used to check the streamed events and the cache.
+static STREAM_JSON_TEST: &'static str = "tests/verify/fail/with-spec/stream-json.rs";
+
+/// Run the driver on a test case, outside of compiletest.
+fn run_driver(path: &str) -> Output {
+    set_var("PRUSTI_CONTRACTS_LIB", PRUSTI_CONTRACTS_LIB);
+    set_var("PRUSTI_FULL_COMPILATION", "true");
+    Command::new(get_driver_path())
+        .arg(path)
+        .args(&["-L", "../target/debug/deps", "--out-dir"])
+        .arg(env::temp_dir())
+        .output()
+        .unwrap()
+}
+
+/// Verify a test case with `STREAM_JSON` enabled, and return the streamed events of the
+/// procedures whose name ends with `procedure`.
+fn verify_with_json_events(path: &str, procedure: &str) -> Vec<Value> {
+    set_var("PRUSTI_STREAM_JSON", "true");
+    let output = run_driver(path);
+    remove_var("PRUSTI_STREAM_JSON");
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    stdout
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .filter(|event| event["procedure"].as_str().unwrap().ends_with(procedure))
+        .collect()
+}
+
+/// Run a failing verification case with `STREAM_JSON` enabled, and check the streamed events.
+fn check_stream_json_events() {
+    let procedure_events = verify_with_json_events(STREAM_JSON_TEST, "increment");
+    assert_eq!(procedure_events.len(), 3, "unexpected events: {:?}", procedure_events);
+
+    assert_eq!(procedure_events[0]["event"], "started");
+
+    let error = &procedure_events[1];
+    assert_eq!(error["event"], "error");
+    let message = error["error"]["message"].as_str().unwrap();
+    assert!(message.contains("postcondition might not hold"), "{}", message);
+    let file = error["error"]["file"].as_str().unwrap();
+    assert!(file.ends_with("stream-json.rs"), "{}", file);
+    assert_eq!(error["error"]["line"], 9);
+    assert!(error["error"]["column"].is_u64());
+
+    let finished = &procedure_events[2];
+    assert_eq!(finished["event"], "finished");
+    assert_eq!(finished["status"], "failed");
+    assert!(finished["duration_ms"].is_u64());
+    assert!(finished["statistics"].is_object());
+}
+
+/// Verify a test case twice in incremental mode, and check that only the procedures verified
+/// successfully by the first run are cached.
+fn check_incremental_cache() {
+    let cache_dir = env::temp_dir().join("prusti-test-cache");
+    let _ = fs::remove_dir_all(&cache_dir);
+    set_var("PRUSTI_INCREMENTAL", "true");
+    set_var("PRUSTI_CACHE_DIR", &cache_dir);
+
+    let finished_status = |procedure: &str| -> Vec<String> {
+        verify_with_json_events(STREAM_JSON_TEST, procedure)
+            .iter()
+            .filter(|event| event["event"] == "finished")
+            .map(|event| event["status"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(finished_status("identity"), vec!["verified"]);
+    assert_eq!(finished_status("identity"), vec!["cached"]);
+    assert_eq!(finished_status("increment"), vec!["failed"]);
+    assert_eq!(finished_status("increment"), vec!["failed"]);
+
+    remove_var("PRUSTI_INCREMENTAL");
+    remove_var("PRUSTI_CACHE_DIR");
+    let _ = fs::remove_dir_all(&cache_dir);
+}
+
+/// Check that an unknown flag in the file of `PRUSTI_CONFIG` is reported as an error.
+fn check_unknown_config_flag() {
+    set_var("PRUSTI_CONFIG", "tests/verify/unknown-flag.toml");
+    let output = run_driver(STREAM_JSON_TEST);
+    remove_var("PRUSTI_CONFIG");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains(
+            "error: unknown configuration flag 'chek_overflows' in \
+             'tests/verify/unknown-flag.toml'"
+        ),
+        "{}",
+        stderr
+    );
+}
+
+fn run_runtime_checks(group_name: &str) {
+    set_var("PRUSTI_CONTRACTS_LIB", PRUSTI_CONTRACTS_LIB);
+    set_var("PRUSTI_FULL_COMPILATION", "true");
+    set_var("PRUSTI_TEST", "true");
+    set_var("PRUSTI_CONTRACT_CHECKS", "runtime");
+    set_var("PRUSTI_QUIET", "true");
+
+    let mut config = Config::default();
+    config.rustc_path = get_driver_path();
+    config.link_deps();
+
+    // Filter the tests to run
+    if let Ok(name) = var::<&str>("TESTNAME") {
+        let s: String = name.to_owned();
+        config.filter = Some(s)
+    }
+
+    let path = PathBuf::from(format!("tests/{}/pass", group_name));
+    if path.exists() {
+        config.mode = common::Mode::RunPass;
+        config.src_base = path;
+        run_tests(&config);
+    }
+
+    let path = PathBuf::from(format!("tests/{}/fail", group_name));
+    if path.exists() {
+        config.mode = common::Mode::RunFail;
+        config.src_base = path;
+        run_tests(&config);
+    }
+
+    remove_var("PRUSTI_CONTRACT_CHECKS");
+    remove_var("PRUSTI_QUIET");
+}
+
 #[test]
 fn test_runner() {
     run_no_verification("parse");
     run_no_verification("typecheck");
     run_verification("verify");
+    check_stream_json_events();
+    check_incremental_cache();
+    check_unknown_config_flag();
+    run_runtime_checks("runtime");
 }
@@ -0,0 +1,18 @@
+extern crate prusti_contracts;
+
+fn check_positive(x: i32) {
+    assert!(x > 0); //~ ERROR assert!(..) statement might not hold
+}
+
+fn forward(x: i32) {
+    check_positive(x);
+}
+
+/// Not reachable from `main`, so it is not verified
+fn unreachable_failure() {
+    assert!(false);
+}
+
+fn main() {
+    forward(-1);
+}
@@ -0,0 +1,21 @@
+extern crate prusti_contracts;
+
+fn to_u8(a: i32) -> u8 {
+    a as u8 //~ ERROR the value might not fit into the target type of the cast
+}
+
+#[requires="a >= 0"]
+fn to_i8(a: i64) -> i8 {
+    a as i8 //~ ERROR the value might not fit into the target type of the cast
+}
+
+#[requires="a <= 255"]
+fn to_u8_from_negative(a: i32) -> u8 {
+    a as u8 //~ ERROR the value might not fit into the target type of the cast
+}
+
+fn to_unsigned(a: i32) -> u32 {
+    a as u32 //~ ERROR the value might not fit into the target type of the cast
+}
+
+fn main() {}
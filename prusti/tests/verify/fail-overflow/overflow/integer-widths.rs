@@ -0,0 +1,19 @@
+extern crate prusti_contracts;
+
+fn add_i16(x: i16) -> i16 {
+    x + 1 //~ ERROR
+}
+
+fn add_u64(x: u64) -> u64 {
+    x + 1 //~ ERROR
+}
+
+fn sub_i128(x: i128) -> i128 {
+    x - 1 //~ ERROR
+}
+
+fn mul_u128(x: u128) -> u128 {
+    x * 2 //~ ERROR
+}
+
+fn main() {}
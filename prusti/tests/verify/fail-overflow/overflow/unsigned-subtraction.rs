@@ -0,0 +1,28 @@
+extern crate prusti_contracts;
+
+fn countdown(mut c: u32) {
+    while c != 10 {
+        c -= 1; //~ ERROR the unsigned subtraction `c - 1` might underflow below zero
+    }
+}
+
+#[requires="c >= 1"]
+fn decrement(c: u32) -> u32 {
+    c - 1
+}
+
+struct Counter {
+    count: usize,
+}
+
+impl Counter {
+    fn decrement(&mut self, step: usize) {
+        self.count -= step; //~ ERROR the unsigned subtraction `self.count - step` might underflow below zero
+    }
+}
+
+fn difference(a: i32, b: i32) -> i32 {
+    a - b //~ ERROR assertion might fail with "attempt to subtract with overflow"
+}
+
+fn main() {}
@@ -122,7 +122,7 @@ impl Ant {
 
         //move direction
         match self.dir {
-            North => self.y -= 1,   //~ ERROR: assertion might fail with "attempt to subtract with overflow"
+            North => self.y -= 1,   //~ ERROR: the unsigned subtraction `self.y - 1` might underflow below zero
             South => self.y += 1,
             East => self.x += 1,
             West => self.x -= 1,
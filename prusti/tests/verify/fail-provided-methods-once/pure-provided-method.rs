@@ -0,0 +1,22 @@
+extern crate prusti_contracts;
+
+trait Percentage {
+    #[pure]
+    #[ensures="result <= 100"]
+    fn value(&self) -> u8 {
+        50
+    }
+}
+
+struct First {}
+
+impl Percentage for First {}
+
+fn test(first: &First) {
+    // The contract of the trait holds for each implementation
+    assert!(first.value() <= 100);
+    // The body is verified once for all the implementations, so it is not known here
+    assert!(first.value() == 50); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
@@ -0,0 +1,35 @@
+#![allow(dead_code)]
+
+extern crate prusti_contracts;
+
+#[requires="x > 0 && x < 0"]
+fn impossible(x: i32) -> i32 { //~ WARNING the precondition of this procedure is unsatisfiable
+    x
+}
+
+#[requires="x > 0"]
+fn possible(x: i32) -> i32 {
+    x
+}
+
+#[invariant="self.value > 100 && self.value < 10"]
+struct Broken { //~ WARNING the invariant of this type is unsatisfiable
+    value: u32,
+}
+
+impl Broken {
+    // Not reported again: the precondition is unsatisfiable because of the invariant
+    fn get(&self) -> u32 {
+        self.value
+    }
+}
+
+#[requires="b.value == 0"]
+fn reset(b: &mut Broken) {
+    b.value = 0;
+}
+
+fn main() {
+    let y = possible(1);
+    impossible(y); //~ ERROR precondition might not hold
+}
@@ -0,0 +1,25 @@
+extern crate prusti_contracts;
+
+struct List {
+    value: u32,
+    next: Option<Box<List>>,
+}
+
+#[pure]
+fn loop_forever(head: &List) -> u32 {
+    loop_forever(head) //~ ERROR termination of the recursive call in pure function might not hold
+}
+
+#[pure]
+#[decreases="n"]
+fn increase(n: u32) -> u32 {
+    if n == 0 { 0 } else { increase(n + 1) } //~ ERROR termination of the recursive call in pure function might not hold
+}
+
+#[pure]
+#[decreases="n"]
+fn below_zero(n: i32) -> i32 {
+    if n == 0 { 0 } else { below_zero(n - 1) } //~ ERROR termination of the recursive call in pure function might not hold
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+extern crate prusti_contracts;
+
+#[pure]
+#[trusted]
+fn f(x: i64) -> i64 {
+    x
+}
+
+// Each instantiation of the quantifier creates a new term `f(i + 1)` that matches the trigger,
+// so the verifier keeps instantiating it until the timeout
+#[requires="forall i: i64 :: {f(i)} f(i) < f(i + 1)"]
+fn increasing() { //~ ERROR verification timed out
+    assert!(f(0) == 5);
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+extern crate prusti_contracts;
+
+struct VecWrapperI32 {
+    v: Vec<i32>,
+}
+
+impl VecWrapperI32 {
+    #[trusted]
+    #[pure]
+    #[requires="0 <= index && index < self.len()"]
+    pub fn lookup(&self, index: usize) -> i32 {
+        self.v[index]
+    }
+
+    #[trusted]
+    #[pure]
+    pub fn len(&self) -> usize {
+        self.v.len()
+    }
+}
+
+#[requires="forall i: usize :: (0 <= i && i < v.len()) ==> v.lookup(i) >= 0"]
+fn with_trigger(v: &VecWrapperI32) {}
+
+#[requires="forall i: usize :: (0 < i && i < v.len()) ==> v.lookup(i - 1) >= 0"] //~ ERROR no valid trigger
+fn with_arithmetic_in_trigger(v: &VecWrapperI32) {}
+
+#[requires="forall i: usize :: (0 <= i && i < v.len()) ==> (
+    forall j: usize :: (i <= j && j < v.len()) ==> v.lookup(i) <= v.lookup(j)
+)"] //~^^ ERROR nested quantifiers
+fn nested(v: &VecWrapperI32) {}
+
+fn main() {}
@@ -0,0 +1,28 @@
+extern crate prusti_contracts;
+
+struct Outer {
+    middle: Middle,
+}
+
+struct Middle {
+    inner: Inner,
+}
+
+struct Inner {
+    leaf: Leaf,
+}
+
+struct Leaf {
+    value: u32,
+}
+
+fn deep(outer: Outer) -> u32 { //~ ERROR accessing fields nested deeper than the unfolding bound
+    outer.middle.inner.leaf.value
+}
+
+#[ensures="result == leaf.value"]
+fn shallow(leaf: &Leaf) -> u32 {
+    leaf.value
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+// compile-flags: --prusti-check-specs-only
+
+// The type errors in the specifications are reported, but the wrong postcondition is not
+// reported because the program is not verified.
+
+extern crate prusti_contracts;
+
+#[ensures="x != true"] //~ ERROR mismatched types
+fn mismatched(x: i32) -> i32 {
+    x
+}
+
+#[ensures="result == x + 1"]
+fn wrong_increment(x: i32) -> i32 {
+    x
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+// no-prefer-dynamic
+
+#![crate_type = "lib"]
+
+extern crate prusti_contracts;
+
+pub struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[ensures="result.value() == 0"]
+    pub fn new() -> Counter {
+        Counter { value: 0 }
+    }
+
+    #[pure]
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    #[requires="self.value() < 100"]
+    #[ensures="self.value() == old(self.value()) + 1"]
+    pub fn increment(&mut self) {
+        self.value += 1;
+    }
+}
+
+#[ensures="result >= a && result >= b"]
+pub fn max(a: u32, b: u32) -> u32 {
+    if a > b { a } else { b }
+}
@@ -0,0 +1,19 @@
+// aux-build:verified_counter.rs
+
+extern crate prusti_contracts;
+extern crate verified_counter;
+
+use verified_counter::Counter;
+
+fn test_counter() {
+    let mut counter = Counter::new();
+    counter.increment();
+    assert!(counter.value() == 2); //~ ERROR assert!(..) statement might not hold
+}
+
+#[requires="counter.value() == 100"]
+fn test_precondition(counter: &mut Counter) {
+    counter.increment(); //~ ERROR precondition might not hold
+}
+
+fn main() {}
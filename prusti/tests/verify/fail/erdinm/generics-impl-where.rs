@@ -0,0 +1,34 @@
+extern crate prusti_contracts;
+
+trait Bound {}
+
+impl Bound for i8 {}
+
+struct Number<A, B> {
+    a: A,
+    b: B,
+    c: i32,
+}
+
+impl<A, B> Number<A, B> where A: Bound {
+    #[requires="self.c > 0"]
+    #[ensures="self.c == old(self.c) - 1"]
+    fn decr(&mut self) {
+        self.c -= 1;
+    }
+}
+
+impl<T: Bound> Number<T, Number<T, u32>> {
+    #[ensures="self.b.c == old(self.b.c) - 1"]
+    fn decr_inner(&mut self) {
+        self.b.decr(); //~ ERROR precondition might not hold
+    }
+}
+
+#[requires="arg.b.c == 1"]
+fn test(arg: &mut Number<i8, Number<i8, u32>>) {
+    arg.decr_inner();
+    assert!(arg.b.c == 1); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
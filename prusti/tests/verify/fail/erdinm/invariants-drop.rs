@@ -0,0 +1,19 @@
+extern crate prusti_contracts;
+
+#[invariant="self.value <= 100"]
+struct Percentage {
+    value: u8,
+}
+
+impl Drop for Percentage {
+    fn drop(&mut self) {
+        assert!(self.value <= 100);
+    }
+}
+
+fn test_scope_exit() {
+    let mut p = Percentage { value: 10 };
+    p.value = 101;
+} //~ ERROR type invariant might not hold when the value is dropped
+
+fn main() {}
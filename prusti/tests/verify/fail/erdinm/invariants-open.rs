@@ -0,0 +1,27 @@
+extern crate prusti_contracts;
+
+// the type invariant is checked at the end of methods that are not marked as open
+
+#[invariant="self.low <= self.high"]
+struct Range {
+    low: u32,
+    high: u32,
+}
+
+impl Range {
+    fn new(low: u32, high: u32) -> Self { //~ ERROR type invariants
+        Range { low, high }
+    }
+
+    #[open_invariant]
+    #[ensures="self.low == value && self.high == old(self.high)"]
+    fn set_low_unchecked(&mut self, value: u32) {
+        self.low = value;
+    }
+
+    fn set_low(&mut self, value: u32) { //~ ERROR type invariants might not hold at the end of the method.
+        self.set_low_unchecked(value);
+    }
+}
+
+fn main() {}
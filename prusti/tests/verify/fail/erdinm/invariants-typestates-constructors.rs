@@ -0,0 +1,35 @@
+extern crate prusti_contracts;
+
+use std::marker::PhantomData;
+
+struct Even;
+struct Odd;
+
+#[invariant="S == Even ~~> self.i % 2 == 0"]
+#[invariant="S == Odd  ~~> self.i % 2 != 0"]
+struct Int<S> {
+    i: i32,
+    s: PhantomData<S>,
+}
+
+impl<A> Int<A> {
+    #[requires="A == Even ~~> i % 2 == 0"]
+    #[requires="A == Odd  ~~> i % 2 != 0"]
+    fn new(i: i32) -> Int<A> {
+        Int {
+            i,
+            s: PhantomData,
+        }
+    }
+}
+
+fn test_even() {
+    let int: Int<Even> = Int::new(3); //~ ERROR precondition might not hold
+}
+
+fn test_odd() {
+    let int = Int::<Odd>::new(3);
+    assert!(int.i % 2 == 0); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
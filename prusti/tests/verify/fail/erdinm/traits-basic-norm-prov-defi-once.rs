@@ -0,0 +1,24 @@
+extern crate prusti_contracts;
+
+trait Percentage {
+    #[requires="arg <= 100"]
+    fn set(&mut self, arg: u8) {
+        assert!(arg <= 99); //~ ERROR assert!(..) statement might not hold
+    }
+}
+
+struct First {}
+
+impl Percentage for First {}
+
+struct Second {}
+
+impl Percentage for Second {}
+
+fn test(first: &mut First, second: &mut Second) {
+    first.set(100);
+    second.set(50);
+    second.set(123); //~ ERROR precondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,45 @@
+extern crate prusti_contracts;
+
+trait Percentage {
+    #[ensures="result <= 100"]
+    fn get(&self) -> u8;
+}
+
+trait Scaled: Percentage {
+    #[ensures="result >= 1"]
+    fn scale(&self) -> u8;
+}
+
+struct Wrapper<T> {
+    t: T,
+}
+
+impl<T> Wrapper<T> where T: Percentage {
+    fn test_get_fail(&self) {
+        let p = self.t.get();
+        assert!(p <= 99); //~ ERROR assert!(..) statement might not hold
+    }
+}
+
+impl<T> Wrapper<T> where T: Scaled + Clone {
+    fn test_scale_fail(&self) {
+        let p = self.t.get();
+        let s = self.t.scale();
+        assert!(p <= 99 || s >= 2); //~ ERROR assert!(..) statement might not hold
+    }
+}
+
+struct Fixed {}
+
+impl Percentage for Fixed {
+    fn get(&self) -> u8 {
+        100
+    }
+}
+
+fn test_concrete_fail(f: &Fixed) {
+    let p = f.get();
+    assert!(p <= 99); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
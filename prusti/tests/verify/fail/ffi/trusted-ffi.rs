@@ -0,0 +1,28 @@
+extern crate prusti_contracts;
+
+#[repr(C)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+extern "C" {
+    #[trusted_ffi]
+    #[requires="p.x < 1000"]
+    #[ensures="result.x == old(p.x) + 1"]
+    fn move_right(p: Point) -> Point;
+}
+
+fn test_pre() {
+    let p = Point { x: 1000, y: 0 };
+    unsafe { move_right(p); } //~ ERROR precondition might not hold
+}
+
+fn test_post() {
+    let p = Point { x: 1, y: 2 };
+    let q = unsafe { move_right(p) };
+    assert!(q.x == 2);
+    assert!(q.y == 2); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
@@ -0,0 +1,29 @@
+extern crate prusti_contracts;
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+impl Point {
+    fn x_mut(&mut self) -> &mut u32 {
+        &mut self.x
+    }
+}
+
+fn test_value() {
+    let mut point = Point { x: 1, y: 2 };
+    let x = point.x_mut();
+    *x = 5;
+    assert!(point.x == 1); //~ ERROR assert!(..) statement might not hold
+}
+
+fn test_other_field() {
+    let mut point = Point { x: 1, y: 2 };
+    let x = point.x_mut();
+    *x = 5;
+    // The inferred pledge says nothing about the other fields
+    assert!(point.y == 2); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+extern crate prusti_contracts;
+
+struct T {
+    val: i32,
+}
+
+#[after_expiry="x.val == 5"]
+fn identity(x: &mut T) -> &mut T { //~ ERROR pledge
+    x
+}
+
+#[assert_on_expiry="result.val > 0, x.val > 0"]
+fn identity_checked(x: &mut T) -> &mut T {
+    x
+}
+
+fn use_checked() {
+    let mut t = T { val: 1 };
+    let y = identity_checked(&mut t); //~ ERROR obligation might not hold on borrow expiry
+    y.val = 0;
+}
+
+fn main() {}
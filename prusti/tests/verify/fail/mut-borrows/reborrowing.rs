@@ -0,0 +1,61 @@
+#![feature(box_patterns)]
+
+extern crate prusti_contracts;
+
+pub struct List {
+    value: u32,
+    next: Option<Box<List>>,
+}
+
+impl List {
+    #[pure]
+    pub fn len(&self) -> usize {
+        match self.next {
+            Some(box ref tail) => 1 + tail.len(),
+            None => 1,
+        }
+    }
+
+    #[pure]
+    pub fn last(&self) -> u32 {
+        match self.next {
+            Some(box ref tail) => tail.last(),
+            None => self.value,
+        }
+    }
+
+    #[ensures="*result == old(self.last())"]
+    #[ensures="after_expiry(
+        self.len() == old(self.len()) &&
+        self.last() == before_expiry(*result)
+    )"]
+    pub fn last_mut(&mut self) -> &mut u32 {
+        match self.next {
+            Some(box ref mut tail) => tail.last_mut(),
+            None => &mut self.value,
+        }
+    }
+
+    #[ensures="after_expiry(self.last() == old(self.last()))"]
+    pub fn wrong_last_mut(&mut self) -> &mut u32 { //~ ERROR pledge
+        match self.next {
+            Some(box ref mut tail) => tail.wrong_last_mut(),
+            None => &mut self.value,
+        }
+    }
+}
+
+#[ensures="list.last() == 42"]
+pub fn set_last(list: &mut List) { //~ ERROR postcondition might not hold
+    let last = list.last_mut();
+    *last = 41;
+}
+
+pub fn read_last(list: &mut List) {
+    let old_last = list.last();
+    let last = list.last_mut();
+    *last += 1;
+    assert!(list.last() == old_last); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+extern crate prusti_contracts;
+
+fn test_eq(x: u32, y: u32) {
+    assert_eq!(x, y); //~ ERROR assert_eq!(..) statement might not hold
+}
+
+fn test_ne(x: u32) {
+    assert_ne!(x, 0); //~ ERROR assert_ne!(..) statement might not hold
+}
+
+fn test_message(x: u32) {
+    assert!(x > 10, "x = {} is too small", x); //~ ERROR assert!(..) statement might not hold
+}
+
+fn test_eq_message(x: u32) {
+    assert_eq!(x, 42, "x should be {}", 42); //~ ERROR assert_eq!(..) statement might not hold
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+extern crate prusti_contracts;
+
+fn abs(x: i32) -> i32 {
+    if x < 0 {
+        panic!()  //~ ERROR panic!(..) statement might panic
+        //~^ NOTE this error happens when `x < 0` is true
+    } else {
+        x
+    }
+}
+
+fn get(opt: Option<u32>) -> u32 {
+    match opt {
+        Some(value) => value,
+        None => unreachable!(),  //~ ERROR unreachable!(..) statement might be reachable
+        //~^ NOTE this error happens when `opt` is `None`
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+extern crate prusti_contracts;
+
+fn test(x: u32) {
+    debug_assert!(x > 10); //~ ERROR debug_assert!(..) statement might not hold
+}
+
+fn test_eq(x: u32, y: u32) {
+    debug_assert_eq!(x, y); //~ ERROR debug_assert_eq!(..) statement might not hold
+}
+
+fn test_ne(x: u32, y: u32) {
+    debug_assert_ne!(x, y, "{} and {} should differ", x, y); //~ ERROR debug_assert_ne!(..) statement might not hold
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+extern crate prusti_contracts;
+
+fn unchecked(x: u32, y: u32) -> u32 {
+    x + y
+}
+
+#[prusti::overflow_checks(on)]
+fn checked(x: u32, y: u32) -> u32 {
+    x + y //~ ERROR
+}
+
+#[prusti::overflow_checks(on)]
+#[requires="x < 100 && y < 100"]
+fn checked_with_precondition(x: u32, y: u32) -> u32 {
+    x * y
+}
+
+#[prusti::overflow_checks(on)]
+mod checked_module {
+    fn checked(x: u8) -> u8 {
+        x - 1 //~ ERROR
+    }
+
+    #[prusti::overflow_checks(off)]
+    fn unchecked(x: u8) -> u8 {
+        x - 1
+    }
+}
+
+fn main() {}
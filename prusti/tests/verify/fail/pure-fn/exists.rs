@@ -0,0 +1,13 @@
+extern crate prusti_contracts;
+
+#[pure]
+fn double(x: usize) -> usize {
+    x + x
+}
+
+#[ensures="exists i: usize :: {double(i)} double(i) == result"]
+fn not_double(x: usize) -> usize { //~ ERROR postcondition might not hold
+    x + 1
+}
+
+fn main() {}
@@ -0,0 +1,47 @@
+#![feature(box_patterns)]
+
+extern crate prusti_contracts;
+
+struct List {
+    value: u32,
+    next: Option<Box<List>>,
+}
+
+#[pure]
+#[ensures="result > 0"]
+fn len(head: &List) -> usize {
+    match head.next {
+        None => 1,
+        Some(box ref tail) => 1 + len(tail)
+    }
+}
+
+#[pure]
+#[requires="0 <= index && index < len(head)"]
+fn lookup(head: &List, index: usize) -> u32 {
+    if index == 0 {
+        head.value
+    } else {
+        match head.next {
+            Some(box ref tail) => lookup(tail, index - 1),
+            None => unreachable!()
+        }
+    }
+}
+
+#[ensures="len(list) == old(len(list))"]
+fn push_front(list: &mut List, value: u32) { //~ ERROR postcondition might not hold
+    let head = std::mem::replace(list, List { value, next: None });
+    list.next = Some(Box::new(head));
+}
+
+// The lookup in the old list is evaluated in the old state, not on the old reference in the
+// current state.
+#[ensures="forall i: usize :: (0 <= i && i < len(old(&*list))) ==>
+            lookup(list, i) == lookup(old(&*list), i)"]
+fn push_front_again(list: &mut List, value: u32) { //~ ERROR postcondition might not hold
+    let head = std::mem::replace(list, List { value, next: None });
+    list.next = Some(Box::new(head));
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+extern crate prusti_contracts;
+
+#[pure]
+fn get(x: &Option<u32>) -> u32 {
+    x.unwrap() //~ ERROR might panic
+}
+
+#[pure]
+#[requires="r.is_ok()"]
+fn get_err(r: &Result<u32, i32>) -> i32 {
+    r.unwrap_err() //~ ERROR might panic
+}
+
+fn main() {}
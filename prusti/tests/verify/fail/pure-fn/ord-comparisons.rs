@@ -0,0 +1,33 @@
+extern crate prusti_contracts;
+
+use std::cmp::Ordering;
+
+#[derive(PartialEq, Eq)]
+struct Version {
+    major: u32,
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    #[pure]
+    fn cmp(&self, other: &Version) -> Ordering {
+        if self.major < other.major {
+            Ordering::Less
+        } else if self.major > other.major {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+#[requires="a.major <= b.major"]
+#[ensures="a < b"]
+fn not_strictly_older(a: &Version, b: &Version) {} //~ ERROR postcondition might not hold
+
+fn main() {}
@@ -0,0 +1,14 @@
+extern crate prusti_contracts;
+
+#[pure]
+fn double(x: usize) -> usize {
+    x + x
+}
+
+#[ensures="forall i: usize :: {i + 1} (i < 10) ==> double(i) == i + i"] //~ ERROR arithmetic operations are not allowed in quantifier triggers
+fn arithmetic_trigger() {}
+
+#[ensures="forall i: usize :: {double(i + 1)} (i < 10) ==> double(i) == i + i"] //~ ERROR arithmetic operations are not allowed in quantifier triggers
+fn nested_arithmetic_trigger() {}
+
+fn main() {}
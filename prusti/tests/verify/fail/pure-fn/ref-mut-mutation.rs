@@ -0,0 +1,13 @@
+extern crate prusti_contracts;
+
+struct Counter {
+    value: u32,
+}
+
+#[pure]
+fn reset(counter: &mut Counter) -> u32 {
+    counter.value = 0; //~ ERROR assigns to memory behind a reference
+    counter.value
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+extern crate prusti_contracts;
+
+#[pure]
+#[trusted]
+#[viper_domain="Factorial"]
+fn fact(n: u32) -> u32 {
+    unimplemented!()
+}
+
+#[trusted]
+#[viper_axiom="Factorial"]
+#[ensures="fact(0) == 1"]
+#[ensures="forall n: u32 :: {fact(n)} n > 0 ==> fact(n) == n * fact(n - 1)"]
+fn fact_definition() {}
+
+fn test() {
+    assert!(fact(2) == 2);
+    assert!(fact(3) == 3); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+extern crate prusti_contracts;
+
+pub struct Cell {
+    value: u32,
+    other: u32,
+}
+
+impl Cell {
+    #[ensures="*result == self.value"]
+    pub fn get(&self) -> &u32 { //~ ERROR postcondition might not hold
+        &self.other
+    }
+
+    #[ensures="*result == self.other"]
+    pub fn get_other(&self) -> &u32 {
+        &self.other
+    }
+}
+
+pub fn client() {
+    let cell = Cell { value: 5, other: 6 };
+    let other = cell.get_other();
+    assert!(*other == 5); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+extern crate prusti_contracts;
+
+struct Config {
+    limit: u32,
+}
+
+struct Counter<'a> {
+    config: &'a Config,
+    count: u32,
+}
+
+impl<'a> Counter<'a> {
+    #[pure]
+    fn limit(&self) -> u32 {
+        self.config.limit
+    }
+
+    #[pure]
+    fn is_within_limit(&self) -> bool {
+        self.count <= self.config.limit
+    }
+
+    #[ensures="self.is_within_limit()"]
+    fn increment(&mut self) { //~ ERROR postcondition might not hold
+        self.count += 1;
+    }
+}
+
+fn test(config: &Config) {
+    let counter = Counter { config, count: 0 };
+    assert!(counter.limit() == 0); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+// compile-flags: --test
+
+extern crate prusti_contracts;
+
+#[requires="x < 1000"]
+#[ensures="result == x * 2"]
+pub fn double(x: u32) -> u32 {
+    x + x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double() {
+        assert!(double(3) == 7); //~ ERROR assert!(..) statement might not hold
+    }
+
+    #[test]
+    fn test_double_precondition() {
+        double(1000); //~ ERROR precondition might not hold
+    }
+}
@@ -0,0 +1,7 @@
+extern crate prusti_contracts;
+
+fn to_int(b: bool) -> u8 {
+    b as u8 //~ ERROR casts between non-numeric types
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+extern crate prusti_contracts;
+
+extern "C" {
+    fn abs(x: i32) -> i32;
+}
+
+fn test(x: i32) -> i32 {
+    unsafe { abs(x) } //~ ERROR calling foreign functions without `#[trusted_ffi]`
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+extern crate prusti_contracts;
+
+use prusti_contracts::Seq;
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+fn first_point(points: Seq<Point>) { //~ ERROR ghost types with elements that are not integers, characters or booleans
+}
+
+fn main() {}
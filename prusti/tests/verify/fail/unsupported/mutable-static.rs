@@ -0,0 +1,9 @@
+extern crate prusti_contracts;
+
+static mut COUNTER: u32 = 0;
+
+fn read_counter() -> u32 {
+    unsafe { COUNTER } //~ ERROR `static mut` items
+}
+
+fn main() {}
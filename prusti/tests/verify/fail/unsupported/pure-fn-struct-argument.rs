@@ -0,0 +1,13 @@
+extern crate prusti_contracts;
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+#[pure]
+fn get_x(p: Point) -> u32 { //~ ERROR values of this type
+    p.x
+}
+
+fn main() {}
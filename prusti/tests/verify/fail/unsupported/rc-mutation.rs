@@ -0,0 +1,11 @@
+extern crate prusti_contracts;
+
+use std::rc::Rc;
+
+fn reset(rc: &mut Rc<u32>) {
+    if let Some(value) = Rc::get_mut(rc) { //~ ERROR mutation of the content of an `Rc` or `Arc`
+        *value = 0;
+    }
+}
+
+fn main() {}
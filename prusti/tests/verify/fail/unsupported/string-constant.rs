@@ -0,0 +1,9 @@
+extern crate prusti_contracts;
+
+const GREETING: &str = "Hello";
+
+fn greeting() -> String {
+    String::from(GREETING) //~ ERROR string constants other than literals
+}
+
+fn main() {}
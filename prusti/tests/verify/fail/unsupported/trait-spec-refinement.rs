@@ -0,0 +1,18 @@
+extern crate prusti_contracts;
+
+trait Counter {
+    fn increment(&mut self, amount: u32);
+}
+
+struct Wrapper {
+    value: u32,
+}
+
+impl Counter for Wrapper {
+    #[requires="amount < 100"]
+    fn increment(&mut self, amount: u32) { //~ ERROR refinement of trait specifications
+        self.value = amount;
+    }
+}
+
+fn main() {}
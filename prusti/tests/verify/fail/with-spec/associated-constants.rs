@@ -0,0 +1,23 @@
+extern crate prusti_contracts;
+
+trait Bounded {
+    const MAX: u32;
+}
+
+struct Small;
+
+impl Bounded for Small {
+    const MAX: u32 = 10;
+}
+
+#[ensures="result < Small::MAX"]
+fn small_max() -> u32 { //~ ERROR postcondition might not hold
+    10
+}
+
+#[ensures="result < T::MAX"]
+fn generic_max<T: Bounded>() -> u32 { //~ ERROR postcondition might not hold
+    T::MAX
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+extern crate prusti_contracts;
+
+trait Percentage {
+    #[ensures="result <= 100"]
+    fn get(&self) -> u8;
+
+    #[requires="arg <= 100"]
+    fn set(&mut self, arg: u8);
+}
+
+struct Gauge {
+    label: u32,
+    level: Box<dyn Percentage>,
+}
+
+fn test_get(gauge: &Gauge) {
+    let level = gauge.level.get();
+    assert!(level <= 99); //~ ERROR assert!(..) statement might not hold
+}
+
+fn test_set(gauge: &mut Gauge) {
+    gauge.level.set(101); //~ ERROR precondition might not hold
+}
+
+#[ensures="gauge.label == old(gauge.label) + 1"]
+fn test_label(gauge: &mut Gauge) { //~ ERROR postcondition might not hold
+    gauge.level.set(0);
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+extern crate prusti_contracts;
+
+#[ensures="result < s.len()"]
+fn count_positive(s: &[i32]) -> usize { //~ ERROR postcondition might not hold
+    let mut count = 0;
+    let mut it = s.iter();
+    #[invariant="count + it.len() <= s.len()"]
+    while let Some(x) = it.next() {
+        if *x > 0 {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn first(v: &Vec<u32>) -> u32 {
+    let mut it = v.iter();
+    *it.next().unwrap() //~ ERROR precondition might not hold
+}
+
+#[requires="s.len() == 1"]
+fn second(s: &[u32]) -> u32 {
+    let mut it = s.iter();
+    it.next();
+    let second = it.next();
+    assert!(second.is_some()); //~ ERROR assert!(..) statement might not hold
+    0
+}
+
+fn main() {}
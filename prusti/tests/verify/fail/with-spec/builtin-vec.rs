@@ -0,0 +1,22 @@
+extern crate prusti_contracts;
+
+fn out_of_bounds() -> u32 {
+    let mut v = Vec::new();
+    v.push(1);
+    v[1] //~ ERROR precondition might not hold
+}
+
+fn pop_empty() {
+    let mut v: Vec<u32> = Vec::new();
+    let last = v.pop();
+    assert!(last.is_some()); //~ ERROR assert!(..) statement might not hold
+}
+
+#[ensures="result.len() == 2"]
+fn wrong_len() -> Vec<u32> { //~ ERROR postcondition might not hold
+    let mut v = Vec::new();
+    v.push(1);
+    v
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+extern crate prusti_contracts;
+
+#[ensures="result == (c >= 'a' && c <= 'z')"]
+fn check_lowercase(c: char) -> bool { //~ ERROR postcondition might not hold
+    match c {
+        'a'..='y' => true,
+        _ => false,
+    }
+}
+
+fn digit(c: char) -> u32 {
+    match c {
+        '0' => 0,
+        '1' => 1,
+        _ => unreachable!(), //~ ERROR unreachable!(..) statement might be reachable
+    }
+}
+
+fn main() {}
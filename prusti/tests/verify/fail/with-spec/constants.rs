@@ -0,0 +1,24 @@
+#![feature(const_fn)]
+
+extern crate prusti_contracts;
+
+const MAX_LEN: usize = 100;
+
+const fn limit() -> usize {
+    MAX_LEN - 1
+}
+
+#[requires="x <= MAX_LEN"]
+#[ensures="result <= limit()"]
+fn identity(x: usize) -> usize { //~ ERROR postcondition might not hold
+    x
+}
+
+const HUGE: u128 = 1 << 100;
+
+#[ensures="result < HUGE"]
+fn almost_huge(x: u128) -> u128 { //~ ERROR postcondition might not hold
+    x
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+extern crate prusti_contracts;
+
+#[ensures_on_panic="true"]
+fn may_fail(x: u32) -> u32 {
+    if x == 0 {
+        panic!("zero");
+    }
+    x
+}
+
+fn call_may_fail() -> u32 {
+    may_fail(1) //~ ERROR the called function might panic
+}
+
+#[ensures_on_panic="*x == old(*x)"]
+fn reset(x: &mut u32) {
+    *x = 0;
+    panic!("reset"); //~ ERROR panic postcondition might not hold
+}
+
+#[ensures_on_panic="a == 0"]
+fn divide(a: u32, b: u32) -> u32 {
+    a / b //~ ERROR panic postcondition might not hold
+}
+
+fn unwrap_none() -> u32 {
+    let x: Option<u32> = None;
+    x.unwrap() //~ ERROR precondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,24 @@
+extern crate prusti_contracts;
+
+#[extern_spec]
+mod specs {
+    mod std {
+        mod vec {
+            impl<T> Vec<T> {
+                #[trusted]
+                #[requires="self.len() < 100"]
+                #[ensures="self.len() == old(self.len()) + 1"]
+                fn push(&mut self, value: T) {
+                    self.push(value)
+                }
+            }
+        }
+    }
+}
+
+#[ensures="v.len() == old(v.len()) + 1"]
+fn push_one(v: &mut Vec<u32>) {
+    v.push(1); //~ ERROR precondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+extern crate prusti_contracts;
+
+#[extern_spec]
+mod specs {
+    mod std {
+        mod cmp {
+            #[ensures="result >= a && result >= b"]
+            fn max(a: i32, b: i32) -> i32 {
+                ::std::cmp::max(a, b)
+            }
+        }
+    }
+}
+
+fn test(a: i32, b: i32) {
+    let x = std::cmp::max(a, b);
+    assert!(x >= a);
+    assert!(x == a); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
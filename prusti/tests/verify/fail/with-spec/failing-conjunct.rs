@@ -0,0 +1,15 @@
+extern crate prusti_contracts;
+
+#[requires="0 <= x && (x < 10 && x != 5)"]
+fn callee(x: i32) {}
+
+fn caller() {
+    callee(5); //~ ERROR precondition might not hold
+}
+
+#[ensures="result >= 0 && (result <= 10 && result != 5)"]
+fn five() -> i32 { //~ ERROR postcondition might not hold
+    5
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+extern crate prusti_contracts;
+
+fn all_below_last(n: usize) {
+    for i in 0..n {
+        assert!(i < n - 1); //~ ERROR assert!(..) statement might not hold
+    }
+}
+
+#[requires="a <= b"]
+fn all_after_start(a: u32, b: u32) {
+    for i in a..b {
+        assert!(a < i); //~ ERROR assert!(..) statement might not hold
+    }
+}
+
+fn main() {}
@@ -0,0 +1,26 @@
+#[macro_use]
+extern crate prusti_contracts;
+
+#[pure]
+fn max(a: i32, b: i32) -> i32 {
+    if a > b { a } else { b }
+}
+
+#[trusted]
+#[ensures="result >= 0"]
+fn unknown() -> i32 {
+    42
+}
+
+fn test_assert(a: i32, b: i32) {
+    let m = max(a, b);
+    prusti_assert!(m > a); //~ ERROR prusti_assert!(..) statement might not hold
+}
+
+fn test_assume() {
+    let x = unknown();
+    prusti_assume!(x < 100);
+    assert!(x < 99); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
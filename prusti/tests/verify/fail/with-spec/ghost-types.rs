@@ -0,0 +1,19 @@
+extern crate prusti_contracts;
+
+use prusti_contracts::{Map, Seq, Set};
+
+#[pure]
+fn pair(a: u32, b: u32) -> Seq<u32> {
+    Seq::single(a).concat(Seq::single(b))
+}
+
+#[ensures="pair(a, b).lookup(1) == a"]
+fn wrong_lookup(a: u32, b: u32) {} //~ ERROR postcondition might not hold
+
+#[ensures="Set::single(a).contains(b)"]
+fn wrong_set(a: u32, b: u32) {} //~ ERROR postcondition might not hold
+
+#[ensures="Map::empty().update(k, v).contains_key(j)"]
+fn wrong_map(k: u32, v: u32, j: u32) {} //~ ERROR postcondition might not hold
+
+fn main() {}
@@ -0,0 +1,14 @@
+extern crate prusti_contracts;
+
+#[history_invariant="self.count >= old(self.count)"]
+struct Counter {
+    count: u32,
+}
+
+impl Counter {
+    fn reset(&mut self) { //~ ERROR postcondition might not hold
+        self.count = 0;
+    }
+}
+
+fn main() {}
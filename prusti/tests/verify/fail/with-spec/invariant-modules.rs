@@ -0,0 +1,33 @@
+extern crate prusti_contracts;
+
+mod percentage {
+    #[invariant="self.value <= 100"]
+    pub struct Percentage {
+        pub value: u8,
+    }
+
+    #[history_invariant="self.count >= old(self.count)"]
+    pub struct Counter {
+        pub count: u32,
+    }
+}
+
+mod ops {
+    use percentage::{Counter, Percentage};
+
+    impl Percentage {
+        pub fn incr(&mut self) { //~ ERROR type invariants
+            if self.value <= 100 { // mistake
+                self.value += 1;
+            }
+        }
+    }
+
+    impl Counter {
+        pub fn reset(&mut self) { //~ ERROR postcondition might not hold
+            self.count = 0;
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+extern crate prusti_contracts;
+
+#[ensures="match result { Some(v) => v > 0 && v < 10, None => true }"]
+fn wrong(x: i32) -> Option<i32> { //~ ERROR postcondition
+    Some(x)
+}
+
+fn main() {}
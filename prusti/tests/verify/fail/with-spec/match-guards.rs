@@ -0,0 +1,20 @@
+extern crate prusti_contracts;
+
+#[ensures="result > 0"]
+fn sign(x: Option<i32>) -> i32 { //~ ERROR postcondition
+    match x {
+        Some(v) if v > 0 => v,
+        Some(v) if v < 0 => -v,
+        _ => 0,
+    }
+}
+
+#[ensures="result == 1"]
+fn first_or_second(x: u32) -> u32 { //~ ERROR postcondition
+    match x {
+        1 | 2 => x,
+        _ => 1,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+extern crate prusti_contracts;
+
+use std::mem;
+
+fn main() {
+    let mut a = 1;
+    let mut b = 2;
+    mem::swap(&mut a, &mut b);
+    assert!(a == 1); //~ ERROR assert!(..) statement might not hold
+
+    let old = mem::replace(&mut b, 3);
+    assert!(old == 3); //~ ERROR assert!(..) statement might not hold
+}
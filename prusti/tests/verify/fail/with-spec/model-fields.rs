@@ -0,0 +1,25 @@
+extern crate prusti_contracts;
+
+pub struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[model]
+    pub fn count(&self) -> u32 {
+        self.value
+    }
+
+    #[ensures="result.value == 0"] //~ ERROR private field
+    pub fn new() -> Self {
+        Counter { value: 0 }
+    }
+
+    // Private procedures can mention the representation
+    #[ensures="self.value == 0"]
+    fn reset(&mut self) {
+        self.value = 0;
+    }
+}
+
+fn main() {}
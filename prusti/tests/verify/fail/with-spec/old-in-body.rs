@@ -0,0 +1,23 @@
+#[macro_use]
+extern crate prusti_contracts;
+
+use prusti_contracts::old;
+
+struct Counter {
+    value: u32,
+}
+
+#[requires="counter.value < 1000"]
+fn increment_twice(counter: &mut Counter) {
+    counter.value += 1;
+    counter.value += 1;
+    assert!(counter.value == old(counter.value) + 1); //~ ERROR assert!(..) statement might not hold
+}
+
+#[requires="x < 1000"]
+fn increment_arg(mut x: u32) {
+    x += 1;
+    prusti_assert!(x == old(x)); //~ ERROR prusti_assert!(..) statement might not hold
+}
+
+fn main() {}
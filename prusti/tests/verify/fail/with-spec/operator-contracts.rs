@@ -0,0 +1,46 @@
+extern crate prusti_contracts;
+
+use std::ops::{Add, Index};
+
+struct Money {
+    cents: u32,
+}
+
+impl<'a> Add<&'a Money> for &'a Money {
+    type Output = u32;
+
+    #[pure]
+    #[requires="self.cents + other.cents <= 1000000"]
+    fn add(self, other: &'a Money) -> u32 {
+        self.cents + other.cents
+    }
+}
+
+struct Pair {
+    first: u32,
+    second: u32,
+}
+
+impl Index<usize> for Pair {
+    type Output = u32;
+
+    #[requires="index < 2"]
+    #[ensures="*result == self.first"]
+    fn index(&self, index: usize) -> &u32 { //~ ERROR postcondition might not hold
+        if index == 0 {
+            &self.first
+        } else {
+            &self.second
+        }
+    }
+}
+
+fn total(a: &Money, b: &Money) -> u32 {
+    a + b //~ ERROR precondition might not hold
+}
+
+fn third(pair: &Pair) -> u32 {
+    pair[2] //~ ERROR precondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+extern crate prusti_contracts;
+
+#[requires="0 <= x"]
+#[requires="x < 10"] //~ NOTE this precondition might not hold
+fn callee(x: i32) {}
+
+fn caller() {
+    callee(10); //~ ERROR precondition might not hold
+}
+
+fn main() {}
@@ -0,0 +1,33 @@
+#![crate_type = "lib"]
+
+extern crate prusti_contracts;
+
+pub struct Account {
+    balance: u32,
+    pub owner: u32,
+}
+
+impl Account {
+    #[pure]
+    pub fn balance(&self) -> u32 {
+        self.balance
+    }
+
+    #[ensures="result.balance == 0"] //~ ERROR not visible to its callers
+    pub fn new(owner: u32) -> Self {
+        Account { balance: 0, owner }
+    }
+
+    // Public fields and pure getters are visible to the callers
+    #[ensures="self.owner == old(self.owner)"]
+    #[ensures="self.balance() == old(self.balance()) + amount"]
+    pub fn deposit(&mut self, amount: u32) {
+        self.balance += amount;
+    }
+
+    // Private procedures can mention the representation
+    #[ensures="self.balance == 0"]
+    fn clear(&mut self) {
+        self.balance = 0;
+    }
+}
@@ -0,0 +1,26 @@
+extern crate prusti_contracts;
+
+use std::rc::Rc;
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+fn test_clone() {
+    let p = Rc::new(Point { x: 1, y: 2 });
+    let q = p.clone();
+    assert!(q.x == 2); //~ ERROR assert!(..) statement might not hold
+}
+
+#[requires="p.x == 1"]
+fn read(p: Rc<Point>) -> u32 {
+    p.y
+}
+
+fn test_call() {
+    let p = Rc::new(Point { x: 2, y: 1 });
+    read(p.clone()); //~ ERROR precondition might not hold
+}
+
+fn main() {}
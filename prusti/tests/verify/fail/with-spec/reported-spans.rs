@@ -0,0 +1,27 @@
+extern crate prusti_contracts;
+
+#[requires="x > 0"]
+#[ensures="result == x - 1"]
+fn decrement(x: u32) -> u32 {
+    x - 1
+}
+
+fn call_precondition(a: u32) -> u32 {
+    let b = a + 1;
+    let _c = decrement(b);
+    decrement(a) //~ ERROR precondition might not hold
+}
+
+fn assertion(a: u32) {
+    let b = decrement(a + 1);
+    assert!(b == a);
+    assert!(b > 0); //~ ERROR assert!(..) statement might not hold
+}
+
+#[ensures="result > x"]
+fn postcondition(x: u32) -> u32 { //~ ERROR postcondition might not hold
+    let y = x;
+    y
+}
+
+fn main() {}
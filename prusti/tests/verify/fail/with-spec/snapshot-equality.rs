@@ -0,0 +1,19 @@
+extern crate prusti_contracts;
+
+#[derive(Clone, Copy, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures="result == old(*p)"]
+fn shift(p: &Point) -> Point { //~ ERROR postcondition might not hold
+    Point { x: p.x + 1, y: p.y }
+}
+
+#[ensures="*p == old(*p)"]
+fn move_up(p: &mut Point) { //~ ERROR postcondition might not hold
+    p.y = p.y - 1;
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+extern crate prusti_contracts;
+
+struct Buffer {
+    len: usize,
+}
+
+impl Buffer {
+    #[requires="i <= 1000"]
+    #[requires(reason = "index must be within bounds", "i < self.len")]
+    fn check(&self, i: usize) -> usize {
+        i
+    }
+}
+
+#[ensures(reason = "the result must be positive", "result > 0")]
+fn positive(x: i32) -> i32 { //~ ERROR postcondition might not hold: the result must be positive.
+    x
+}
+
+fn test(b: &Buffer) {
+    b.check(0); //~ ERROR precondition might not hold: index must be within bounds.
+}
+
+fn test_without_reason(b: &Buffer) {
+    if 0 < b.len {
+        b.check(2000); //~ ERROR precondition might not hold.
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+extern crate prusti_contracts;
+
+#[ensures="result == x"]
+fn identity(x: u32) -> u32 {
+    x
+}
+
+#[ensures="result > x"]
+fn increment(x: u32) -> u32 { //~ ERROR postcondition might not hold
+    x
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+extern crate prusti_contracts;
+
+#[ensures="result.len() == s.len()"]
+fn first_byte(s: &str) -> &str { //~ ERROR postcondition might not hold
+    &s[0..1] //~ ERROR assertion might fail
+}
+
+fn test_literals() {
+    let a = "hello";
+    let b = "world";
+    assert!(a == b); //~ ERROR assert!(..) statement might not hold
+}
+
+fn test_string() {
+    let mut s = String::new();
+    s.push_str("ab");
+    assert!(s.len() == 3); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
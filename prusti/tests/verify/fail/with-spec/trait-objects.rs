@@ -0,0 +1,20 @@
+extern crate prusti_contracts;
+
+trait Percentage {
+    #[ensures="result <= 100"]
+    fn get(&self) -> u8;
+
+    #[requires="arg <= 100"]
+    fn set(&mut self, arg: u8);
+}
+
+fn test_get(t: &Percentage) {
+    let p = t.get();
+    assert!(p <= 99); //~ ERROR assert!(..) statement might not hold
+}
+
+fn test_set(t: &mut Percentage) {
+    t.set(101); //~ ERROR precondition might not hold
+}
+
+fn main() {}
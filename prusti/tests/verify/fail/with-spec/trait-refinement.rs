@@ -0,0 +1,31 @@
+extern crate prusti_contracts;
+
+trait Percentage {
+    #[requires="value <= 100"]
+    #[ensures="result <= 100"]
+    fn clamp(&self, value: u32) -> u32;
+}
+
+struct Stronger;
+
+impl Percentage for Stronger {
+    #[refine_requires="value <= 10"]
+    fn clamp(&self, value: u32) -> u32 { //~ ERROR the precondition of the trait might not imply the refined precondition
+        value
+    }
+}
+
+struct Weaker;
+
+impl Percentage for Weaker {
+    #[refine_ensures="result <= 200"]
+    fn clamp(&self, value: u32) -> u32 { //~ ERROR the refined postcondition might not imply the postcondition of the trait
+        value + 100
+    }
+}
+
+fn test_stronger(s: &Stronger) {
+    s.clamp(50); //~ ERROR precondition might not hold
+}
+
+fn main() {}
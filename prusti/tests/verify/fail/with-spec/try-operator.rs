@@ -0,0 +1,37 @@
+extern crate prusti_contracts;
+
+struct ParseError {
+    position: u32,
+}
+
+struct InputError {
+    code: u32,
+}
+
+impl From<ParseError> for InputError {
+    #[ensures="result.code == error.position"]
+    fn from(error: ParseError) -> Self { //~ ERROR postcondition might not hold
+        InputError { code: 0 }
+    }
+}
+
+fn parse_digit(x: u32) -> Result<u32, ParseError> {
+    if x < 10 {
+        Ok(x)
+    } else {
+        Err(ParseError { position: x })
+    }
+}
+
+fn read_input(x: u32) -> Result<u32, InputError> {
+    let digit = parse_digit(x)?;
+    assert!(digit < 5); //~ ERROR assert!(..) statement might not hold
+    Ok(digit)
+}
+
+fn test_conversion() {
+    let error = InputError::from(ParseError { position: 3 });
+    assert!(error.code == 4); //~ ERROR assert!(..) statement might not hold
+}
+
+fn main() {}
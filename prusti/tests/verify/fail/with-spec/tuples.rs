@@ -0,0 +1,13 @@
+extern crate prusti_contracts;
+
+#[ensures="result == (a, b)"]
+fn swap(a: u32, b: u32) -> (u32, u32) { //~ ERROR postcondition might not hold
+    (b, a)
+}
+
+#[ensures="result == old(x.0.0)"]
+fn nested_projection(x: ((u32, u32), u32)) -> u32 { //~ ERROR postcondition might not hold
+    (x.0).1
+}
+
+fn main() {}
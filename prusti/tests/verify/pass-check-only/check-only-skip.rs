@@ -0,0 +1,28 @@
+extern crate prusti_contracts;
+
+mod checked {
+    #[ensures="result == x + 1"]
+    pub fn increment(x: i32) -> i32 {
+        x + 1
+    }
+
+    pub mod skipped {
+        /// Not verified, because the module is listed in `SKIP`
+        #[ensures="result == x + 1"]
+        pub fn wrong_increment(x: i32) -> i32 {
+            x
+        }
+    }
+}
+
+/// Not verified, because it is not in a module listed in `CHECK_ONLY`
+#[ensures="result == x - 1"]
+fn wrong_decrement(x: i32) -> i32 {
+    x
+}
+
+fn main() {
+    assert!(checked::increment(1) == 2);
+    checked::skipped::wrong_increment(1);
+    wrong_decrement(1);
+}
@@ -0,0 +1,71 @@
+extern crate prusti_contracts;
+
+#[derive(Clone, Copy)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[pure]
+fn norm1(p: &Point) -> i32 {
+    p.x + p.y
+}
+
+struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[ensures="self.value == old(self.value) + 1"]
+    fn increment(&mut self) {
+        self.value += 1;
+    }
+}
+
+fn test_temporaries(a: i32, b: i32) -> i32 {
+    let p = Point { x: a, y: b };
+    let q = Point { x: p.y, y: p.x };
+    let r = {
+        let tmp = Point { x: q.x + 1, y: q.y - 1 };
+        norm1(&tmp)
+    };
+    assert!(r == norm1(&p));
+    let s = {
+        let tmp = Box::new(Point { x: 1, y: 2 });
+        tmp.x + tmp.y
+    };
+    assert!(s == 3);
+    r + s
+}
+
+fn test_borrows() {
+    let mut counter = Counter { value: 0 };
+    {
+        let other = Counter { value: 5 };
+        let c = &mut counter;
+        c.increment();
+        assert!(other.value == 5);
+    }
+    {
+        let c = &counter;
+        assert!(c.value == 1);
+    }
+    counter.increment();
+    assert!(counter.value == 2);
+}
+
+fn test_loop() {
+    let mut i = 0;
+    let mut sum = 0;
+    #[invariant="0 <= i && i <= 10"]
+    #[invariant="sum == i"]
+    while i < 10 {
+        let p = Point { x: i, y: 1 };
+        let q = p;
+        sum += norm1(&q) - i;
+        i += 1;
+    }
+    assert!(sum == 10);
+}
+
+fn main() {}
@@ -0,0 +1,51 @@
+extern crate prusti_contracts;
+
+struct Pair {
+    first: i32,
+    second: i32,
+}
+
+impl Pair {
+    #[pure]
+    fn first(&self) -> i32 {
+        self.first
+    }
+
+    #[pure]
+    fn second(&self) -> i32 {
+        self.second
+    }
+
+    #[pure]
+    fn sum(&self) -> i32 {
+        self.first() + self.second()
+    }
+
+    #[pure]
+    fn is_ordered(&self) -> bool {
+        self.first() <= self.second()
+    }
+}
+
+#[pure]
+#[requires="n >= 0"]
+fn fib(n: i32) -> i32 {
+    if n <= 1 { n } else { fib(n - 1) + fib(n - 2) }
+}
+
+#[requires="pair.is_ordered()"]
+#[ensures="result.is_ordered()"]
+#[ensures="result.sum() == old(pair.sum()) + 1"]
+fn increment_second(pair: Pair) -> Pair {
+    Pair {
+        first: pair.first,
+        second: pair.second + 1,
+    }
+}
+
+#[ensures="result == fib(2)"]
+fn fib_two() -> i32 {
+    fib(2)
+}
+
+fn main() {}
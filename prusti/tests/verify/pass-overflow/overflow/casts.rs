@@ -0,0 +1,17 @@
+extern crate prusti_contracts;
+
+#[requires="a >= 0 && a <= 255"]
+fn to_u8(a: i32) -> u8 {
+    a as u8
+}
+
+#[requires="a >= -128 && a <= 127"]
+fn to_i8(a: i64) -> i8 {
+    a as i8
+}
+
+fn widen(a: u32, b: i16) -> i64 {
+    a as i64 + b as i64
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+extern crate prusti_contracts;
+
+#[requires="x < 100"]
+fn add_i16(x: i16) -> i16 {
+    x + 1
+}
+
+#[requires="x < 18446744073709551615"]
+fn add_u64(x: u64) -> u64 {
+    x + 1
+}
+
+#[requires="x > 0"]
+fn sub_i128(x: i128) -> i128 {
+    x - 1
+}
+
+#[requires="x < 340282366920938463463374607431768211455"]
+fn add_u128(x: u128) -> u128 {
+    x + 1
+}
+
+#[requires="x >= 1"]
+fn sub_usize(x: usize) -> usize {
+    x - 1
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+extern crate prusti_contracts;
+
+#[prusti::overflow_checks(off)]
+fn unchecked(x: u32, y: u32) -> u32 {
+    x + y
+}
+
+#[requires="x < 100 && y < 100"]
+fn checked(x: u32, y: u32) -> u32 {
+    x * y
+}
+
+#[prusti::overflow_checks(off)]
+mod unchecked_module {
+    fn decrement(x: u8) -> u8 {
+        x - 1
+    }
+
+    fn use_decrement(x: u8) -> u8 {
+        let y = decrement(x);
+        y * 2
+    }
+}
+
+fn main() {}
@@ -0,0 +1,56 @@
+#![feature(box_patterns)]
+
+extern crate prusti_contracts;
+
+struct List {
+    value: u32,
+    next: Option<Box<List>>,
+}
+
+#[pure]
+fn sum(head: &List) -> u32 {
+    match head.next {
+        None => head.value,
+        Some(box ref tail) => head.value + sum(tail)
+    }
+}
+
+#[pure]
+#[decreases="n"]
+#[requires="n >= 0"]
+fn triangle(n: i32) -> i32 {
+    if n == 0 { 0 } else { n + triangle(n - 1) }
+}
+
+#[pure]
+#[decreases="x, y"]
+#[requires="x >= 0 && y >= 0"]
+fn count(x: u32, y: u32) -> u32 {
+    if x == 0 {
+        y
+    } else if y == 0 {
+        count(x - 1, 10)
+    } else {
+        count(x, y - 1)
+    }
+}
+
+#[pure]
+#[decreases="n"]
+#[requires="n >= 0"]
+fn is_even(n: i32) -> bool {
+    if n == 0 { true } else { is_odd(n - 1) }
+}
+
+#[pure]
+#[decreases="n"]
+#[requires="n >= 0"]
+fn is_odd(n: i32) -> bool {
+    if n == 0 { false } else { is_even(n - 1) }
+}
+
+#[ensures="triangle(2) == 3"]
+#[ensures="is_even(2)"]
+fn test() {}
+
+fn main() {}
@@ -0,0 +1,16 @@
+// compile-flags: --prusti-check-specs-only
+
+// The specifications are type-checked, but the wrong postcondition is not reported because the
+// program is not verified.
+
+extern crate prusti_contracts;
+
+#[requires="x > 0"]
+#[ensures="result == x + 1"]
+fn wrong_increment(x: i32) -> i32 {
+    x
+}
+
+fn main() {
+    wrong_increment(1);
+}
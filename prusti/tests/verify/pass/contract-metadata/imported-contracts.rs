@@ -0,0 +1,20 @@
+// aux-build:verified_counter.rs
+
+extern crate prusti_contracts;
+extern crate verified_counter;
+
+use verified_counter::Counter;
+
+fn test_counter() {
+    let mut counter = Counter::new();
+    counter.increment();
+    counter.increment();
+    assert!(counter.value() == 2);
+}
+
+#[ensures="result >= 10"]
+fn test_max(x: u32) -> u32 {
+    verified_counter::max(x, 10)
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+extern crate prusti_contracts;
+
+struct Tree {
+    value: i32,
+    children: Forest,
+}
+
+enum Forest {
+    Nil,
+    Cons(Box<Tree>, Box<Forest>),
+}
+
+struct Node<'a> {
+    parent: Option<&'a Node<'a>>,
+    siblings: &'a mut Siblings<'a>,
+}
+
+enum Siblings<'a> {
+    None,
+    Some(&'a mut Node<'a>),
+}
+
+#[pure]
+fn forest_size(forest: &Forest) -> u32 {
+    match forest {
+        Forest::Nil => 0,
+        Forest::Cons(tree, rest) => tree_size(tree) + forest_size(rest),
+    }
+}
+
+#[pure]
+fn tree_size(tree: &Tree) -> u32 {
+    1 + forest_size(&tree.children)
+}
+
+#[ensures="tree_size(&result) == 1"]
+fn leaf(value: i32) -> Tree {
+    Tree {
+        value,
+        children: Forest::Nil,
+    }
+}
+
+fn set_value(tree: &mut Tree, value: i32) {
+    tree.value = value;
+}
+
+fn is_root(node: &Node) -> bool {
+    node.parent.is_none()
+}
+
+fn main() {}
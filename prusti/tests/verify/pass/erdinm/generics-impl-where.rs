@@ -0,0 +1,58 @@
+extern crate prusti_contracts;
+
+trait Bound {}
+
+impl Bound for i8 {}
+impl Bound for u16 {}
+
+struct Number<A, B> {
+    a: A,
+    b: B,
+    c: i32,
+}
+
+impl<A, B> Number<A, B> where A: Bound {
+    #[ensures="self.c == old(self.c) + 1"]
+    fn incr(&mut self) {
+        self.c += 1;
+    }
+
+    #[requires="self.c > 0"]
+    #[ensures="self.c == old(self.c) - 1"]
+    fn decr(&mut self) {
+        self.c -= 1;
+    }
+}
+
+impl<T: Bound> Number<T, Number<T, u32>> {
+    #[ensures="self.b.c == old(self.b.c) + 1"]
+    #[ensures="self.c == old(self.c)"]
+    fn incr_inner(&mut self) {
+        self.b.incr();
+    }
+}
+
+#[requires="arg.c == 3"]
+#[requires="arg.b.c == 5"]
+fn test1(arg: &mut Number<i8, Number<i8, u32>>) {
+    arg.incr();
+    arg.incr_inner();
+    assert!(arg.c == 4);
+    assert!(arg.b.c == 6);
+    arg.decr();
+    assert!(arg.c == 3);
+}
+
+#[requires="arg.c == 1"]
+fn test2<X: Bound, Y>(arg: &mut Number<X, Y>) {
+    arg.decr();
+    assert!(arg.c == 0);
+}
+
+#[requires="arg.c == 1"]
+fn test3(arg: &mut Number<u16, Number<u16, u32>>) {
+    test2(arg);
+    assert!(arg.c == 0);
+}
+
+fn main() {}
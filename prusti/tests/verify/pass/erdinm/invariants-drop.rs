@@ -0,0 +1,26 @@
+extern crate prusti_contracts;
+
+#[invariant="self.value <= 100"]
+struct Percentage {
+    value: u8,
+}
+
+impl Drop for Percentage {
+    fn drop(&mut self) {
+        assert!(self.value <= 100);
+    }
+}
+
+fn consume(_p: Percentage) {}
+
+fn test_scope_exit() {
+    let mut p = Percentage { value: 10 };
+    p.value = 100;
+}
+
+fn test_moved() {
+    let p = Percentage { value: 10 };
+    consume(p);
+}
+
+fn main() {}
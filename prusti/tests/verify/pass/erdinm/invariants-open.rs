@@ -0,0 +1,44 @@
+extern crate prusti_contracts;
+
+// `#[open_invariant]` methods may temporarily violate the type invariant
+
+#[invariant="self.low <= self.high"]
+struct Range {
+    low: u32,
+    high: u32,
+}
+
+impl Range {
+    #[requires="low <= high"]
+    fn new(low: u32, high: u32) -> Self {
+        Range { low, high }
+    }
+
+    #[open_invariant]
+    #[ensures="self.low == value && self.high == old(self.high)"]
+    fn set_low_unchecked(&mut self, value: u32) {
+        self.low = value;
+    }
+
+    #[open_invariant]
+    #[ensures="self.low == old(self.low) && self.high == value"]
+    fn set_high_unchecked(&mut self, value: u32) {
+        self.high = value;
+    }
+
+    #[requires="low <= high"]
+    fn set(&mut self, low: u32, high: u32) {
+        self.set_low_unchecked(high);
+        self.set_high_unchecked(high);
+        self.set_low_unchecked(low);
+    }
+}
+
+#[requires="x <= y"]
+fn test(x: u32, y: u32) {
+    let mut range = Range::new(0, 0);
+    range.set(x, y);
+    assert!(range.low <= range.high);
+}
+
+fn main() {}
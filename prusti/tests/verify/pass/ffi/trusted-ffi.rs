@@ -0,0 +1,41 @@
+extern crate prusti_contracts;
+
+#[repr(C)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+extern "C" {
+    #[trusted_ffi]
+    #[requires="p.x < 1000"]
+    #[ensures="result.x == old(p.x) + 1"]
+    #[ensures="result.y == old(p.y)"]
+    fn move_right(p: Point) -> Point;
+
+    #[trusted_ffi]
+    #[ensures="result >= 0"]
+    fn count_points() -> i32;
+}
+
+#[ensures="result.x == x && result.y == y"]
+fn new_point(x: i32, y: i32) -> Point {
+    Point { x, y }
+}
+
+#[requires="p.x < 999"]
+#[ensures="result.x == old(p.x) + 2"]
+#[ensures="result.y == old(p.y)"]
+fn move_twice(p: Point) -> Point {
+    unsafe { move_right(move_right(p)) }
+}
+
+fn test() {
+    let count = unsafe { count_points() };
+    assert!(count >= 0);
+    let p = move_twice(new_point(1, 2));
+    assert!(p.x == 3);
+    assert!(p.y == 2);
+}
+
+fn main() {}
@@ -0,0 +1,52 @@
+extern crate prusti_contracts;
+
+struct Inner {
+    value: u32,
+}
+
+struct Outer {
+    inner: Inner,
+    count: u32,
+}
+
+#[requires="p.inner.value < 100"]
+#[ensures="p.inner.value == old(p.inner.value) + 1"]
+#[ensures="p.count == old(p.count)"]
+fn increment(p: &mut Outer) {
+    p.inner.value += 1;
+}
+
+#[requires="p.inner.value < 10"]
+#[ensures="p.inner.value == old(p.inner.value) + 2"]
+#[ensures="p.count == old(p.count)"]
+fn increment_twice(p: &mut Outer) {
+    increment(p);
+    increment(p);
+    // The same nested places are looked up again in the old states
+    assert!(p.inner.value == old(p.inner.value) + 2);
+    assert!(p.count == old(p.count) && p.inner.value > old(p.inner.value));
+}
+
+#[requires="n < 50 && p.inner.value == 0"]
+#[ensures="p.inner.value == n"]
+#[ensures="p.count == old(p.count)"]
+fn increment_n(p: &mut Outer, n: u32) {
+    let mut i = 0;
+    // The state before each call is labelled again in each iteration
+    #[invariant="n < 50 && i <= n && p.inner.value == i"]
+    #[invariant="p.count == old(p.count)"]
+    while i < n {
+        increment(p);
+        i += 1;
+    }
+}
+
+fn main() {
+    let mut p = Outer {
+        inner: Inner { value: 0 },
+        count: 3,
+    };
+    increment_n(&mut p, 4);
+    increment_twice(&mut p);
+    assert!(p.inner.value == 6 && p.count == 3);
+}
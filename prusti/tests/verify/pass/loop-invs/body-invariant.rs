@@ -0,0 +1,36 @@
+#[macro_use]
+extern crate prusti_contracts;
+
+#[requires="n >= 0"]
+#[ensures="result == 2 * n"]
+fn double(n: i32) -> i32 {
+    let mut i = 0;
+    let mut res = 0;
+    while i < n {
+        body_invariant!(i <= n && res == 2 * i);
+        res += 2;
+        i += 1;
+    }
+    res
+}
+
+#[requires="n >= 0"]
+#[ensures="result >= 0"]
+fn nested(n: i32) -> i32 {
+    let mut res = 0;
+    let mut i = 0;
+    #[invariant="i <= n"]
+    while i < n {
+        let mut j = 0;
+        while j < n {
+            body_invariant!(j <= n && res >= 0);
+            res += j;
+            j += 1;
+        }
+        body_invariant!(res >= 0);
+        i += 1;
+    }
+    res
+}
+
+fn main() {}
@@ -0,0 +1,56 @@
+extern crate prusti_contracts;
+
+struct Pair {
+    first: u32,
+    second: u32,
+}
+
+#[ensures="result <= n"]
+fn return_from_loop(n: u32) -> u32 {
+    let mut i = 0;
+    #[invariant="i <= n"]
+    while i < n {
+        if i == 5 {
+            return i;
+        }
+        i += 1;
+    }
+    i
+}
+
+#[ensures="result <= 10"]
+fn return_from_nested_loops() -> u32 {
+    let mut i = 0;
+    #[invariant="i <= 10"]
+    while i < 10 {
+        let mut j = 0;
+        #[invariant="j <= 10"]
+        #[invariant="i < 10"]
+        while j < 10 {
+            if i + j == 15 {
+                return i;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    i
+}
+
+#[ensures="pair.second == old(pair.second)"]
+fn return_with_live_borrow(pair: &mut Pair, n: u32) {
+    let mut i = 0;
+    #[invariant="i <= n"]
+    #[invariant="pair.second == old(pair.second)"]
+    while i < n {
+        let first = &mut pair.first;
+        if *first > 100 {
+            *first = 0;
+            return;
+        }
+        *first += 1;
+        i += 1;
+    }
+}
+
+fn main() {}
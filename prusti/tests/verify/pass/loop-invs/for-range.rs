@@ -0,0 +1,29 @@
+extern crate prusti_contracts;
+
+fn all_below(n: usize) {
+    for i in 0..n {
+        assert!(i < n);
+    }
+}
+
+#[requires="a <= b"]
+fn all_within(a: u32, b: u32) {
+    for i in a..b {
+        assert!(a <= i);
+        assert!(i < b);
+    }
+}
+
+#[ensures="result <= 10"]
+fn count_up_to_ten(n: u32) -> u32 {
+    let mut count = 0;
+    #[invariant="count <= 10"]
+    for _ in 0..n {
+        if count < 10 {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+extern crate prusti_contracts;
+
+#[requires="n >= 0"]
+#[ensures="result == n"]
+fn count(n: i32) -> i32 {
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    i
+}
+
+#[requires="start <= end"]
+#[ensures="result == end"]
+fn count_from(start: u32, end: u32) -> u32 {
+    let mut i = start;
+    let mut steps = 0;
+    while i < end {
+        steps = 2 * i;
+        i += 1;
+    }
+    i
+}
+
+fn main() {
+    let mut i = 0;
+    let n = 10;
+    while i < n {
+        i += 1;
+    }
+    assert!(i == 10);
+}
@@ -0,0 +1,28 @@
+extern crate prusti_contracts;
+
+#[ensures="result >= 10"]
+fn break_with_value() -> u32 {
+    let mut i = 0;
+    let x = loop {
+        if i >= 10 {
+            break i;
+        }
+        i += 1;
+    };
+    x
+}
+
+#[ensures="result == 10"]
+fn break_with_invariant() -> u32 {
+    let mut i = 0;
+    #[invariant="i <= 10"]
+    loop {
+        if i == 10 {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+fn main() {}
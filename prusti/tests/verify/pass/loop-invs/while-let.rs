@@ -0,0 +1,17 @@
+extern crate prusti_contracts;
+
+#[ensures="result <= n"]
+fn min_of_countdown(n: u32) -> u32 {
+    let mut current = Some(n);
+    let mut min = n;
+    #[invariant="min <= n"]
+    while let Some(i) = current {
+        if i < min {
+            min = i;
+        }
+        current = if i == 0 { None } else { Some(i - 1) };
+    }
+    min
+}
+
+fn main() {}
@@ -0,0 +1,42 @@
+extern crate prusti_contracts;
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+struct Segment {
+    start: Point,
+    end: Point,
+}
+
+impl Point {
+    fn x_mut(&mut self) -> &mut u32 {
+        &mut self.x
+    }
+}
+
+impl Segment {
+    fn end_y_mut(&mut self) -> &mut u32 {
+        &mut self.end.y
+    }
+}
+
+fn test_point() {
+    let mut point = Point { x: 1, y: 2 };
+    let x = point.x_mut();
+    *x = 5;
+    assert!(point.x == 5);
+}
+
+fn test_segment() {
+    let mut segment = Segment {
+        start: Point { x: 0, y: 0 },
+        end: Point { x: 3, y: 4 },
+    };
+    let y = segment.end_y_mut();
+    *y += 1;
+    assert!(segment.end.y == 5);
+}
+
+fn main() {}
@@ -0,0 +1,43 @@
+extern crate prusti_contracts;
+
+struct Counter {
+    value: u32,
+    limit: u32,
+}
+
+impl Counter {
+    #[pure]
+    fn valid(&self) -> bool {
+        self.value <= self.limit
+    }
+
+    #[requires="self.valid()"]
+    #[ensures="*result == old(self.value)"]
+    #[assert_on_expiry="*result == 0, self.valid()"]
+    fn value_mut(&mut self) -> &mut u32 {
+        &mut self.value
+    }
+
+    #[ensures="*result == old(self.limit)"]
+    #[after_expiry="self.value == old(self.value) && self.limit == before_expiry(*result)"]
+    fn limit_mut(&mut self) -> &mut u32 {
+        &mut self.limit
+    }
+}
+
+#[requires="counter.valid()"]
+#[ensures="counter.valid()"]
+fn reset(counter: &mut Counter) {
+    let value = counter.value_mut();
+    *value = 0;
+}
+
+fn raise_limit(counter: &mut Counter) {
+    let old_value = counter.value;
+    let limit = counter.limit_mut();
+    *limit = 100;
+    assert!(counter.limit == 100);
+    assert!(counter.value == old_value);
+}
+
+fn main() {}
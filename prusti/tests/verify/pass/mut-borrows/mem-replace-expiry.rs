@@ -0,0 +1,31 @@
+extern crate prusti_contracts;
+
+use std::mem;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn replace_through_field(p: &mut Point) -> i32 {
+    let x = &mut p.x;
+    let old = mem::replace(x, 5);
+    // The borrow `x` has expired
+    assert!(p.x == 5);
+    old
+}
+
+#[requires="p.x == 1 && p.y == 2"]
+fn replace_through_reborrow(p: &mut Point) {
+    let r = &mut *p;
+    let old = mem::replace(r, Point { x: 3, y: 4 });
+    // The reborrow `r` has expired
+    assert!(p.x == 3 && p.y == 4);
+    assert!(old.x == 1 && old.y == 2);
+}
+
+fn main() {
+    let mut p = Point { x: 1, y: 2 };
+    let old = replace_through_field(&mut p);
+    assert!(old == 1 && p.x == 5 && p.y == 2);
+}
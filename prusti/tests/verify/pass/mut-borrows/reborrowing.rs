@@ -0,0 +1,80 @@
+#![feature(box_patterns)]
+
+extern crate prusti_contracts;
+
+pub struct List {
+    value: u32,
+    next: Option<Box<List>>,
+}
+
+impl List {
+    #[pure]
+    pub fn len(&self) -> usize {
+        match self.next {
+            Some(box ref tail) => 1 + tail.len(),
+            None => 1,
+        }
+    }
+
+    #[pure]
+    pub fn last(&self) -> u32 {
+        match self.next {
+            Some(box ref tail) => tail.last(),
+            None => self.value,
+        }
+    }
+
+    #[pure]
+    pub fn first(&self) -> u32 {
+        self.value
+    }
+
+    #[ensures="*result == old(self.last())"]
+    #[ensures="after_expiry(
+        self.len() == old(self.len()) &&
+        self.last() == before_expiry(*result)
+    )"]
+    pub fn last_mut(&mut self) -> &mut u32 {
+        match self.next {
+            Some(box ref mut tail) => tail.last_mut(),
+            None => &mut self.value,
+        }
+    }
+
+    #[ensures="*result == old(self.first())"]
+    #[ensures="after_expiry(
+        self.len() == old(self.len()) &&
+        self.first() == before_expiry(*result)
+    )"]
+    pub fn first_mut(&mut self) -> &mut u32 {
+        &mut self.value
+    }
+}
+
+#[ensures="*result == old(*x)"]
+#[ensures="after_expiry(before_expiry(*result) == *x)"]
+fn reborrow(x: &mut u32) -> &mut u32 {
+    x
+}
+
+#[ensures="list.last() == 42"]
+#[ensures="list.len() == old(list.len())"]
+pub fn set_last(list: &mut List) {
+    let last = list.last_mut();
+    *last = 42;
+}
+
+#[ensures="list.last() == old(list.last())"]
+pub fn read_last(list: &mut List) -> u32 {
+    let last = list.last_mut();
+    *last
+}
+
+#[ensures="list.first() == 7"]
+pub fn set_first_through_reborrow(list: &mut List) {
+    let first = list.first_mut();
+    let first2 = reborrow(first);
+    *first2 = 7;
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+extern crate prusti_contracts;
+
+fn test(x: u32) {
+    let y = x;
+    assert_eq!(x, y);
+    assert_eq!(x, y, "x = {} and y = {} should be equal", x, y);
+    if x > 10 {
+        assert_ne!(x, 0);
+        assert!(x > 5, "x = {} is too small", x);
+        debug_assert_ne!(x, 3);
+    } else {
+        debug_assert_eq!(y, x);
+    }
+}
+
+fn main() {
+    test(42);
+}
@@ -0,0 +1,13 @@
+extern crate prusti_contracts;
+
+fn casts() {
+    let x: u32 = 300;
+    assert!(x as u8 == 44);
+    let y: i32 = -1;
+    assert!(y as u32 == 4294967295);
+    assert!(y as i64 == -1);
+    let z: u64 = 128;
+    assert!(z as i8 == -128);
+}
+
+fn main() {}
@@ -0,0 +1,35 @@
+extern crate prusti_contracts;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[pure]
+#[duplicable_reads]
+fn same_x(a: &Point, b: &Point) -> bool {
+    a.x == b.x
+}
+
+#[pure]
+#[duplicable_reads]
+fn self_same_x(p: &Point) -> bool {
+    same_x(p, p)
+}
+
+#[requires="same_x(a, b)"]
+#[ensures="same_x(a, b)"]
+fn check(a: &Point, b: &Point) {}
+
+#[ensures="same_x(p, p)"]
+#[ensures="self_same_x(p)"]
+fn test(p: &Point) {
+    check(p, p);
+    assert!(same_x(p, p));
+}
+
+fn main() {
+    let p = Point { x: 1, y: 2 };
+    let q = &p;
+    assert!(same_x(&p, q));
+}
@@ -0,0 +1,24 @@
+extern crate prusti_contracts;
+
+#[pure]
+fn double(x: usize) -> usize {
+    x + x
+}
+
+#[ensures="exists i: usize :: {double(i)} double(i) == result"]
+fn make_double(x: usize) -> usize {
+    double(x)
+}
+
+#[requires="exists i: usize :: {double(i)} double(i) == n"]
+#[ensures="exists i: usize :: {double(i)} double(i) == result"]
+fn keep_double(n: usize) -> usize {
+    n
+}
+
+#[ensures(exists(|i: usize| double(i) == result))]
+fn make_double_tokens(x: usize) -> usize {
+    double(x)
+}
+
+fn main() {}
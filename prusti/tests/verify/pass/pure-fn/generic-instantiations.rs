@@ -0,0 +1,49 @@
+extern crate prusti_contracts;
+
+pub struct Wrapper<T> {
+    value: T,
+    valid: bool,
+}
+
+#[pure]
+pub fn is_valid<T>(w: &Wrapper<T>) -> bool {
+    w.valid
+}
+
+#[pure]
+#[requires="is_valid(w)"]
+pub fn checked_valid<T>(w: &Wrapper<T>) -> bool {
+    is_valid(w)
+}
+
+#[requires="is_valid(a) && is_valid(b)"]
+#[ensures="checked_valid(a) && checked_valid(b)"]
+pub fn both<T, U>(a: &Wrapper<T>, b: &Wrapper<U>) {}
+
+pub fn test_u32(w: &Wrapper<u32>) {
+    if is_valid(w) {
+        assert!(checked_valid(w));
+        both(w, w);
+    }
+}
+
+pub fn test_many() {
+    let a = Wrapper { value: 1u32, valid: true };
+    let b = Wrapper { value: -1i64, valid: true };
+    let c = Wrapper { value: true, valid: false };
+    assert!(is_valid(&a) && is_valid(&b) && !is_valid(&c));
+    assert!(checked_valid(&a) && checked_valid(&b));
+    both(&a, &b);
+    both(&b, &a);
+    both(&a, &a);
+}
+
+pub fn test_generic<T>(w: Wrapper<T>) -> Wrapper<T> {
+    if is_valid(&w) {
+        assert!(checked_valid(&w));
+        both(&w, &w);
+    }
+    w
+}
+
+fn main() {}
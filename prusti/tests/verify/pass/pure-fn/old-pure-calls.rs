@@ -0,0 +1,48 @@
+#![feature(box_patterns)]
+
+extern crate prusti_contracts;
+
+struct List {
+    value: u32,
+    next: Option<Box<List>>,
+}
+
+#[pure]
+#[ensures="result > 0"]
+fn len(head: &List) -> usize {
+    match head.next {
+        None => 1,
+        Some(box ref tail) => 1 + len(tail)
+    }
+}
+
+#[pure]
+#[requires="0 <= index && index < len(head)"]
+fn lookup(head: &List, index: usize) -> u32 {
+    if index == 0 {
+        head.value
+    } else {
+        match head.next {
+            Some(box ref tail) => lookup(tail, index - 1),
+            None => unreachable!()
+        }
+    }
+}
+
+#[ensures="len(list) == old(len(list)) + 1"]
+#[ensures="lookup(list, 0) == value"]
+#[ensures="forall i: usize :: (0 <= i && i < old(len(list))) ==>
+            lookup(list, i + 1) == old(lookup(list, i))"]
+fn push_front(list: &mut List, value: u32) {
+    let head = std::mem::replace(list, List { value, next: None });
+    list.next = Some(Box::new(head));
+}
+
+#[ensures="len(list) == len(old(&*list)) + 1"]
+#[ensures="forall i: usize :: (0 <= i && i < len(old(&*list))) ==>
+            lookup(list, i + 1) == lookup(old(&*list), i)"]
+fn push_front_again(list: &mut List, value: u32) {
+    push_front(list, value);
+}
+
+fn main() {}
@@ -0,0 +1,55 @@
+extern crate prusti_contracts;
+
+#[pure]
+#[requires="x.is_some()"]
+fn get_or_fail(x: &Option<u32>) -> u32 {
+    x.unwrap()
+}
+
+#[pure]
+fn is_empty(x: &Option<u32>) -> bool {
+    x.is_none()
+}
+
+#[pure]
+#[requires="r.is_ok()"]
+fn get_ok(r: &Result<u32, i32>) -> u32 {
+    r.unwrap()
+}
+
+#[pure]
+#[requires="r.is_err()"]
+fn get_err(r: &Result<u32, i32>) -> i32 {
+    r.unwrap_err()
+}
+
+#[ensures="result.is_some() && get_or_fail(&result) == x"]
+fn wrap(x: u32) -> Option<u32> {
+    Some(x)
+}
+
+#[ensures="is_empty(&result)"]
+fn nothing() -> Option<u32> {
+    None
+}
+
+#[ensures="result.is_err() && get_err(&result) == e"]
+fn fail(e: i32) -> Result<u32, i32> {
+    Err(e)
+}
+
+#[ensures="result.is_ok() && get_ok(&result) == v"]
+fn succeed(v: u32) -> Result<u32, i32> {
+    Ok(v)
+}
+
+fn main() {
+    let a = wrap(3);
+    assert!(get_or_fail(&a) == 3);
+    let b = nothing();
+    assert!(is_empty(&b));
+    let c = succeed(4);
+    assert!(get_ok(&c) == 4);
+    let d = fail(-1);
+    assert!(get_err(&d) == -1);
+}
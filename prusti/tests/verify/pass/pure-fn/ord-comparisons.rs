@@ -0,0 +1,54 @@
+extern crate prusti_contracts;
+
+use std::cmp::Ordering;
+
+#[derive(PartialEq, Eq)]
+struct Version {
+    major: u32,
+    minor: u32,
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Version) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    #[pure]
+    fn cmp(&self, other: &Version) -> Ordering {
+        if self.major < other.major {
+            Ordering::Less
+        } else if self.major > other.major {
+            Ordering::Greater
+        } else if self.minor < other.minor {
+            Ordering::Less
+        } else if self.minor > other.minor {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+#[pure]
+fn is_newer(a: &Version, b: &Version) -> bool {
+    match a.cmp(b) {
+        Ordering::Greater => true,
+        _ => false,
+    }
+}
+
+#[requires="a.major < b.major"]
+#[ensures="a < b && a <= b && b > a && b >= a"]
+fn major_update(a: &Version, b: &Version) {}
+
+#[requires="a.major == b.major && a.minor < b.minor"]
+#[ensures="is_newer(b, a)"]
+fn minor_update(a: &Version, b: &Version) {}
+
+#[requires="first <= second && second <= third"]
+#[ensures="first.major <= third.major"]
+fn sorted_triple(first: &Version, second: &Version, third: &Version) {}
+
+fn main() {}
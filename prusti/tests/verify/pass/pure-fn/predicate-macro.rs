@@ -0,0 +1,36 @@
+#[macro_use]
+extern crate prusti_contracts;
+
+predicate! {
+    fn in_range(x: i32, lo: i32, hi: i32) -> bool {
+        lo <= x && x <= hi
+    }
+}
+
+predicate! {
+    fn is_percentage(x: i32) -> bool {
+        in_range(x, 0, 100)
+    }
+}
+
+#[requires="is_percentage(x)"]
+#[ensures="is_percentage(result)"]
+fn half(x: i32) -> i32 {
+    x / 2
+}
+
+#[requires="in_range(x, 0, 10)"]
+#[ensures="in_range(result, 0, 20)"]
+fn double(x: i32) -> i32 {
+    x + x
+}
+
+fn main() {
+    let mut i = 0;
+    #[invariant="in_range(i, 0, 10)"]
+    while i < 10 {
+        i += 1;
+    }
+    let h = half(50);
+    assert!(h <= 100);
+}
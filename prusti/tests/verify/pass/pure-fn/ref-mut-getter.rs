@@ -0,0 +1,29 @@
+extern crate prusti_contracts;
+
+struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[pure]
+    fn get(&mut self) -> u32 {
+        self.value
+    }
+
+    #[ensures="self.value == old(self.value) + 1"]
+    fn incr(&mut self) {
+        self.value += 1;
+    }
+}
+
+#[requires="counter.value < 100"]
+fn use_getter(counter: &mut Counter) {
+    let a = counter.get();
+    let b = counter.get();
+    assert!(a == b);
+    counter.incr();
+    let c = counter.get();
+    assert!(c == a + 1);
+}
+
+fn main() {}
@@ -0,0 +1,37 @@
+extern crate prusti_contracts;
+
+#[pure]
+#[trusted]
+#[viper_domain="Factorial"]
+fn fact(n: u32) -> u32 {
+    unimplemented!()
+}
+
+#[trusted]
+#[viper_axiom="Factorial"]
+#[ensures="fact(0) == 1"]
+#[ensures="forall n: u32 :: {fact(n)} n > 0 ==> fact(n) == n * fact(n - 1)"]
+#[ensures="forall n: u32 :: {fact(n)} fact(n) >= 1"]
+fn fact_definition() {}
+
+#[requires="n <= 10"]
+#[ensures="result == fact(n)"]
+fn compute_fact(n: u32) -> u32 {
+    let mut i = 0;
+    let mut result = 1;
+    #[invariant="i <= n"]
+    #[invariant="result == fact(i)"]
+    while i < n {
+        i += 1;
+        result *= i;
+    }
+    result
+}
+
+fn test() {
+    assert!(fact(0) == 1);
+    assert!(fact(1) == 1);
+    assert!(fact(3) == 6);
+}
+
+fn main() {}
@@ -0,0 +1,37 @@
+extern crate prusti_contracts;
+
+pub struct Cell {
+    value: u32,
+    other: u32,
+}
+
+impl Cell {
+    #[ensures="*result == self.value"]
+    pub fn get(&self) -> &u32 {
+        &self.value
+    }
+
+    #[ensures="*result == self.other"]
+    pub fn get_other(&self) -> &u32 {
+        let other = &self.other;
+        other
+    }
+}
+
+#[ensures="*result == *x"]
+pub fn identity(x: &u32) -> &u32 {
+    x
+}
+
+pub fn client() {
+    let cell = Cell { value: 5, other: 6 };
+    let value = cell.get();
+    assert!(*value == 5);
+    let other = cell.get_other();
+    assert!(*other == 6);
+    let x = 7;
+    let y = identity(&x);
+    assert!(*y == 7);
+}
+
+fn main() {}
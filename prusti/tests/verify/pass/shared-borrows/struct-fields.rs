@@ -0,0 +1,56 @@
+extern crate prusti_contracts;
+
+struct Config {
+    limit: u32,
+}
+
+struct Counter<'a> {
+    config: &'a Config,
+    count: u32,
+}
+
+impl<'a> Counter<'a> {
+    #[pure]
+    fn limit(&self) -> u32 {
+        self.config.limit
+    }
+
+    #[pure]
+    fn is_within_limit(&self) -> bool {
+        self.count <= self.config.limit
+    }
+
+    #[requires="self.count < self.limit()"]
+    #[ensures="self.is_within_limit()"]
+    #[ensures="self.limit() == old(self.limit())"]
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+}
+
+#[requires="config.limit > 1"]
+fn test(config: &Config) {
+    let mut counter = Counter { config, count: 0 };
+    assert!(counter.limit() == config.limit);
+    counter.increment();
+    counter.increment();
+    assert!(counter.is_within_limit());
+}
+
+struct Pair<'a> {
+    first: &'a u32,
+    second: &'a u32,
+}
+
+#[pure]
+fn sum(pair: &Pair) -> u32 {
+    *pair.first + *pair.second
+}
+
+fn test_aliasing() {
+    let value = 3;
+    let pair = Pair { first: &value, second: &value };
+    assert!(sum(&pair) == 6);
+}
+
+fn main() {}
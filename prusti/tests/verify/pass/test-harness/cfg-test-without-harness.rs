@@ -0,0 +1,20 @@
+extern crate prusti_contracts;
+
+#[ensures="result == x * 2"]
+fn double(x: u32) -> u32 {
+    x + x
+}
+
+// Without `--test` this module is removed, together with its specifications
+#[cfg(test)]
+mod tests {
+    #[requires="x > 0"]
+    #[ensures="result < x"]
+    fn predecessor(x: u32) -> u32 {
+        x - 1
+    }
+}
+
+fn main() {
+    assert!(double(2) == 4);
+}
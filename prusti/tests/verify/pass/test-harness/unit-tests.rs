@@ -0,0 +1,31 @@
+// compile-flags: --test
+
+extern crate prusti_contracts;
+
+#[requires="x < 1000"]
+#[ensures="result == x * 2"]
+pub fn double(x: u32) -> u32 {
+    x + x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[ensures="result == x + 1"]
+    fn successor(x: u32) -> u32 {
+        x + 1
+    }
+
+    #[test]
+    fn test_double() {
+        assert!(double(3) == 6);
+        assert!(double(successor(4)) == 10);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_double_is_not_odd() {
+        assert!(double(1) == 3);
+    }
+}
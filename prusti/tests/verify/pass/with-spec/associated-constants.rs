@@ -0,0 +1,55 @@
+extern crate prusti_contracts;
+
+trait Bounded {
+    const MAX: u32;
+
+    #[ensures="result <= Self::MAX"]
+    fn clamp(&self, x: u32) -> u32 {
+        if x > Self::MAX {
+            Self::MAX
+        } else {
+            x
+        }
+    }
+}
+
+struct Small;
+
+impl Bounded for Small {
+    const MAX: u32 = 10;
+}
+
+struct Percentage {
+    value: u32,
+}
+
+impl Percentage {
+    const FULL: u32 = 100;
+
+    #[ensures="result.value == Self::FULL"]
+    fn full() -> Self {
+        Percentage { value: Self::FULL }
+    }
+}
+
+#[ensures="result <= 10"]
+fn clamp_small(s: &Small, x: u32) -> u32 {
+    s.clamp(x)
+}
+
+#[ensures="result == Small::MAX"]
+fn small_max() -> u32 {
+    10
+}
+
+#[ensures="result <= T::MAX"]
+fn clamp_generic<T: Bounded>(t: &T, x: u32) -> u32 {
+    t.clamp(x)
+}
+
+fn test() {
+    let p = Percentage::full();
+    assert!(p.value == 100);
+}
+
+fn main() {}
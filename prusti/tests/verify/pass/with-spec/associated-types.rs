@@ -0,0 +1,48 @@
+extern crate prusti_contracts;
+
+trait Container {
+    type Item;
+
+    fn first(&self) -> Option<Self::Item>;
+}
+
+struct Pair {
+    left: u32,
+    right: u32,
+}
+
+impl Container for Pair {
+    type Item = u32;
+
+    #[trusted]
+    fn first(&self) -> Option<u32> {
+        Some(self.left)
+    }
+}
+
+#[trusted]
+#[ensures="result <= 100"]
+fn count_up_to_hundred<I: Iterator<Item = u32>>(iter: I) -> usize {
+    iter.take(100).count()
+}
+
+#[trusted]
+fn first_of<C: Container>(container: &C) -> Option<C::Item> {
+    container.first()
+}
+
+/// The associated type is unknown, so the item is encoded as an abstract value
+fn forward_first<C: Container>(container: &C) -> Option<C::Item> {
+    first_of(container)
+}
+
+fn client(pair: &Pair) -> Option<u32> {
+    forward_first(pair)
+}
+
+fn count_range(n: u32) {
+    let count = count_up_to_hundred(0..n);
+    assert!(count <= 100);
+}
+
+fn main() {}
@@ -0,0 +1,38 @@
+//! Example: test specifications that dereference boxes stored in fields
+
+extern crate prusti_contracts;
+
+pub struct Leaf {
+    value: i32,
+}
+
+pub struct Node {
+    value: i32,
+    next: Box<Leaf>,
+}
+
+#[ensures="(*result.next).value == 5"]
+#[ensures="result.value == 3"]
+fn build() -> Node {
+    Node {
+        value: 3,
+        next: Box::new(Leaf { value: 5 }),
+    }
+}
+
+#[ensures="(*node.next).value == old((*node.next).value) + 1"]
+#[ensures="node.value == old(node.value)"]
+fn increment_next(node: &mut Node) {
+    (*node.next).value += 1;
+}
+
+#[requires="(*node.next).value == 5"]
+#[ensures="result == 5"]
+fn read_next(node: Node) -> i32 {
+    let leaf = node.next;
+    leaf.value
+}
+
+fn main() {
+
+}
@@ -0,0 +1,65 @@
+extern crate prusti_contracts;
+
+trait Percentage {
+    #[ensures="result <= 100"]
+    fn get(&self) -> u8;
+
+    #[requires="arg <= 100"]
+    fn set(&mut self, arg: u8);
+}
+
+struct Fixed {
+    value: u8,
+}
+
+impl Percentage for Fixed {
+    fn get(&self) -> u8 {
+        if self.value <= 100 {
+            self.value
+        } else {
+            100
+        }
+    }
+
+    fn set(&mut self, arg: u8) {
+        self.value = arg;
+    }
+}
+
+struct Gauge {
+    label: u32,
+    level: Box<dyn Percentage>,
+}
+
+impl Gauge {
+    #[ensures="result.label == label"]
+    fn new(label: u32) -> Gauge {
+        Gauge {
+            label,
+            level: Box::new(Fixed { value: 0 }),
+        }
+    }
+
+    #[ensures="result <= 100"]
+    fn level(&self) -> u8 {
+        self.level.get()
+    }
+
+    #[ensures="self.label == old(self.label)"]
+    fn fill(&mut self) {
+        self.level.set(100);
+    }
+}
+
+fn test(gauge: &mut Gauge) {
+    let label = gauge.label;
+    gauge.fill();
+    assert!(gauge.label == label);
+    assert!(gauge.level() <= 100);
+}
+
+fn main() {
+    let mut gauge = Gauge::new(42);
+    test(&mut gauge);
+    assert!(gauge.label == 42);
+}
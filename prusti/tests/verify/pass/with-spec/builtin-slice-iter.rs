@@ -0,0 +1,47 @@
+extern crate prusti_contracts;
+
+#[ensures="result <= s.len()"]
+fn count_positive(s: &[i32]) -> usize {
+    let mut count = 0;
+    let mut it = s.iter();
+    #[invariant="count + it.len() <= s.len()"]
+    while let Some(x) = it.next() {
+        if *x > 0 {
+            count += 1;
+        }
+    }
+    count
+}
+
+#[ensures="result == v.len()"]
+fn count_all(v: &Vec<u32>) -> usize {
+    let mut count = 0;
+    let mut it = v.iter();
+    #[invariant="count + it.len() == v.len()"]
+    while let Some(_) = it.next() {
+        count += 1;
+    }
+    count
+}
+
+#[requires="s.len() > 0"]
+fn first(s: &[u32]) -> u32 {
+    let mut it = s.iter();
+    *it.next().unwrap()
+}
+
+fn visit_all(v: &Vec<u32>) -> u32 {
+    let mut last = 0;
+    for x in v.iter() {
+        last = *x;
+    }
+    last
+}
+
+fn main() {
+    let mut v = Vec::new();
+    v.push(1);
+    assert!(count_all(&v) == 1);
+    first(&v);
+    visit_all(&v);
+}
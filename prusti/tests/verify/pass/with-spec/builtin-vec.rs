@@ -0,0 +1,36 @@
+extern crate prusti_contracts;
+
+#[ensures="result.len() == n"]
+fn zeros(n: usize) -> Vec<u32> {
+    let mut v = Vec::new();
+    let mut i = 0;
+    #[invariant="i <= n"]
+    #[invariant="v.len() == i"]
+    while i < n {
+        v.push(0);
+        i += 1;
+    }
+    v
+}
+
+#[requires="v.len() > 0"]
+#[ensures="v.len() == old(v.len()) - 1"]
+fn drop_last(v: &mut Vec<u32>) {
+    let last = v.pop();
+    assert!(last.is_some());
+}
+
+#[requires="v.len() == 3"]
+fn read_and_write(v: &mut Vec<u32>) -> u32 {
+    let first = v[0];
+    v[2] = first;
+    assert!(v.len() == 3);
+    v[1]
+}
+
+fn main() {
+    let mut v = zeros(3);
+    read_and_write(&mut v);
+    drop_last(&mut v);
+    assert!(v.len() == 2);
+}
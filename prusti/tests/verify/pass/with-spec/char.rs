@@ -0,0 +1,50 @@
+extern crate prusti_contracts;
+
+#[pure]
+fn is_lowercase(c: char) -> bool {
+    c >= 'a' && c <= 'z'
+}
+
+#[pure]
+fn axis_index(c: char) -> u32 {
+    match c {
+        'x' => 0,
+        'y' => 1,
+        'z' => 2,
+        _ => 3,
+    }
+}
+
+#[ensures="result == (c >= 'a' && c <= 'z')"]
+fn check_lowercase(c: char) -> bool {
+    match c {
+        'a'..='z' => true,
+        _ => false,
+    }
+}
+
+#[ensures="result == is_lowercase(c)"]
+fn check_lowercase_pure(c: char) -> bool {
+    is_lowercase(c)
+}
+
+#[ensures="c == 'x' ==> result == 0"]
+#[ensures="result == axis_index(c)"]
+fn axis(c: char) -> u32 {
+    axis_index(c)
+}
+
+#[ensures="result <= '\u{10FFFF}'"]
+fn identity(c: char) -> char {
+    c
+}
+
+#[ensures="result == !b"]
+fn negate(b: bool) -> bool {
+    match b {
+        true => false,
+        false => true,
+    }
+}
+
+fn main() {}
@@ -0,0 +1,44 @@
+extern crate prusti_contracts;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[pure]
+fn seconds_per_day() -> i32 {
+    24 * 60 * 60
+}
+
+#[pure]
+fn origin_x(_p: &Point) -> i32 {
+    3 - 3
+}
+
+#[requires="true && n >= -(1 + 1)"]
+#[ensures="result == n + 2 * 3"]
+fn add_six(n: i32) -> i32 {
+    if n > 1000 {
+        n + 6
+    } else {
+        n + (1 + 2 + 3)
+    }
+}
+
+#[ensures="result == 86400"]
+fn day() -> i32 {
+    seconds_per_day()
+}
+
+#[ensures="result == 0"]
+fn origin(p: &Point) -> i32 {
+    origin_x(p)
+}
+
+#[ensures="false ==> result == 1"]
+#[ensures="(1 < 2) ==> result == p.y"]
+fn get_y(p: &Point) -> i32 {
+    p.y
+}
+
+fn main() {}
@@ -0,0 +1,50 @@
+#![feature(const_fn)]
+
+extern crate prusti_contracts;
+
+const MAX_LEN: usize = 100;
+
+const BIG: u64 = 1 << 40;
+
+const HUGE: u128 = 1 << 100;
+
+const MIN_HUGE: i128 = -(1 << 100);
+
+#[derive(Clone, Copy)]
+enum Level {
+    Low = 1,
+    High = 10,
+}
+
+const fn max_level() -> i32 {
+    Level::High as i32
+}
+
+#[requires="x < MAX_LEN"]
+#[ensures="result <= MAX_LEN"]
+fn increment(x: usize) -> usize {
+    x + 1
+}
+
+#[ensures="result == BIG"]
+fn big() -> u64 {
+    BIG
+}
+
+#[ensures="result == HUGE && result > BIG"]
+fn huge() -> u128 {
+    HUGE
+}
+
+#[ensures="result == MIN_HUGE && result < 0"]
+fn min_huge() -> i128 {
+    MIN_HUGE
+}
+
+#[requires="Level::Low as i32 <= level && level <= max_level()"]
+#[ensures="result <= 10"]
+fn clamp(level: i32) -> i32 {
+    level
+}
+
+fn main() {}
@@ -0,0 +1,41 @@
+extern crate prusti_contracts;
+
+#[ensures_on_panic="true"]
+fn may_fail(x: u32) -> u32 {
+    if x == 0 {
+        panic!("zero");
+    }
+    x
+}
+
+#[ensures_on_panic="true"]
+fn call_may_fail(x: u32) -> u32 {
+    may_fail(x)
+}
+
+#[ensures_on_panic="b == 0"]
+#[ensures="b > 0"]
+fn divide(a: u32, b: u32) -> u32 {
+    a / b
+}
+
+#[ensures_on_panic="*x == old(*x) && old(*x) == 0"]
+#[ensures="*x == old(*x) - 1"]
+fn decrement(x: &mut u32) {
+    if *x == 0 {
+        panic!("cannot decrement zero");
+    }
+    *x -= 1;
+}
+
+#[requires="x.is_some()"]
+fn get(x: Option<u32>) -> u32 {
+    x.unwrap()
+}
+
+#[requires="x.is_ok()"]
+fn get_ok(x: Result<u32, u32>) -> u32 {
+    x.expect("not ok")
+}
+
+fn main() {}
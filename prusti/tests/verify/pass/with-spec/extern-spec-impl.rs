@@ -0,0 +1,30 @@
+extern crate prusti_contracts;
+
+#[extern_spec]
+mod specs {
+    mod std {
+        mod vec {
+            impl<T> Vec<T> {
+                #[trusted]
+                #[requires="self.len() < 100"]
+                #[ensures="self.len() == old(self.len()) + 1"]
+                fn push(&mut self, value: T) {
+                    self.push(value)
+                }
+            }
+        }
+    }
+}
+
+#[requires="v.len() < 10"]
+#[ensures="v.len() == old(v.len()) + 2"]
+fn push_twice(v: &mut Vec<u32>) {
+    v.push(1);
+    v.push(2);
+}
+
+fn main() {
+    let mut v = Vec::new();
+    push_twice(&mut v);
+    assert!(v.len() == 2);
+}
@@ -0,0 +1,33 @@
+extern crate prusti_contracts;
+
+#[extern_spec]
+mod specs {
+    mod std {
+        mod cmp {
+            #[ensures="result >= a && result >= b"]
+            #[ensures="result == a || result == b"]
+            fn max(a: i32, b: i32) -> i32 {
+                ::std::cmp::max(a, b)
+            }
+
+            #[ensures="result <= a && result <= b"]
+            #[ensures="result == a || result == b"]
+            fn min(a: i32, b: i32) -> i32 {
+                ::std::cmp::min(a, b)
+            }
+        }
+    }
+}
+
+#[requires="lower <= upper"]
+#[ensures="lower <= result && result <= upper"]
+fn clamp(value: i32, lower: i32, upper: i32) -> i32 {
+    std::cmp::min(std::cmp::max(value, lower), upper)
+}
+
+fn main() {
+    let x = std::cmp::max(3, 5);
+    assert!(x == 5);
+    let y = clamp(10, 0, 7);
+    assert!(y <= 7);
+}
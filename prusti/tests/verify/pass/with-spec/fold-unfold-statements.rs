@@ -0,0 +1,30 @@
+#[macro_use]
+extern crate prusti_contracts;
+
+struct Inner {
+    value: u32,
+}
+
+struct Outer {
+    inner: Inner,
+    count: u32,
+}
+
+#[ensures="result == outer.inner.value"]
+fn get_value(outer: &Outer) -> u32 {
+    unfold!(*outer);
+    unfold!(outer.inner);
+    let value = outer.inner.value;
+    fold!(outer.inner);
+    fold!(*outer);
+    value
+}
+
+fn reset(mut outer: Outer) -> Outer {
+    unfold!(outer);
+    outer.count = 0;
+    fold!(outer);
+    outer
+}
+
+fn main() {}
@@ -0,0 +1,40 @@
+extern crate prusti_contracts;
+
+use prusti_contracts::{Map, Seq, Set};
+
+#[pure]
+fn pair(a: u32, b: u32) -> Seq<u32> {
+    Seq::single(a).concat(Seq::single(b))
+}
+
+#[pure]
+fn first(s: Seq<u32>) -> u32 {
+    s.lookup(0)
+}
+
+#[pure]
+fn digits() -> Set<u32> {
+    Set::single(0).insert(1).insert(2)
+}
+
+#[ensures="pair(a, b).len() == 2"]
+#[ensures="pair(a, b).lookup(1) == b && pair(a, b).contains(b)"]
+#[ensures="first(pair(a, b)) == a"]
+#[ensures="pair(a, b) == pair(a, b)"]
+fn check_seq(a: u32, b: u32) {}
+
+#[ensures="pair(a, b).update(1, c).lookup(1) == c"]
+#[ensures="pair(a, b).update(1, c).lookup(0) == a"]
+#[ensures="pair(a, b).update(2, c).len() == 2"]
+fn check_seq_update(a: u32, b: u32, c: u32) {}
+
+#[ensures="digits().contains(2)"]
+#[ensures="!digits().contains(3)"]
+#[ensures="digits().union(Set::single(x)).contains(x)"]
+fn check_set(x: u32) {}
+
+#[ensures="Map::empty().update(k, v).contains_key(k)"]
+#[ensures="Map::empty().update(k, v).lookup(k) == v"]
+fn check_map(k: u32, v: bool) {}
+
+fn main() {}
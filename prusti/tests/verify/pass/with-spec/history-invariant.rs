@@ -0,0 +1,31 @@
+extern crate prusti_contracts;
+
+#[history_invariant="self.count >= old(self.count)"]
+struct Counter {
+    count: u32,
+}
+
+impl Counter {
+    #[requires="self.count < 1000"]
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+
+    fn reset_if_zero(&mut self) {
+        if self.count == 0 {
+            self.count = 0;
+        }
+    }
+
+    fn get(&self) -> u32 {
+        self.count
+    }
+}
+
+#[requires="counter.count < 1000"]
+#[ensures="counter.count >= old(counter.count)"]
+fn use_counter(counter: &mut Counter) {
+    counter.increment();
+}
+
+fn main() {}
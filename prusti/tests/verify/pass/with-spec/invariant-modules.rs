@@ -0,0 +1,63 @@
+extern crate prusti_contracts;
+
+mod percentage {
+    #[invariant="self.value <= 100"]
+    pub struct Percentage {
+        pub value: u8,
+    }
+
+    #[history_invariant="self.count >= old(self.count)"]
+    pub struct Counter {
+        pub count: u32,
+    }
+}
+
+mod ops {
+    use percentage::{Counter, Percentage};
+
+    impl Percentage {
+        pub fn new() -> Self {
+            Percentage { value: 0 }
+        }
+
+        #[ensures="self.value <= 100"]
+        pub fn incr(&mut self) {
+            if self.value < 100 {
+                self.value += 1;
+            }
+        }
+    }
+
+    impl Counter {
+        #[requires="self.count < 1000"]
+        pub fn incr(&mut self) {
+            self.count += 1;
+        }
+    }
+}
+
+mod client {
+    use percentage::Percentage;
+
+    pub trait Reset {
+        fn reset(&mut self);
+    }
+
+    impl Reset for Percentage {
+        fn reset(&mut self) {
+            self.value = 0;
+        }
+    }
+}
+
+use client::Reset;
+use percentage::Percentage;
+
+#[ensures="p.value <= 100"]
+fn test(p: &mut Percentage) {
+    p.incr();
+    p.reset();
+    p.incr();
+}
+
+fn main() {}
@@ -0,0 +1,36 @@
+//! Example: test match expressions that destructure the enum variants of `result`
+
+extern crate prusti_contracts;
+
+pub enum Shape {
+    Point,
+    Segment(i32),
+    Rectangle { width: i32, height: i32 },
+}
+
+#[requires="x > 0"]
+#[ensures="match result { Some(v) => v > 0 && v == x, None => false }"]
+fn positive(x: i32) -> Option<i32> {
+    Some(x)
+}
+
+#[requires="w > 0 && h > 0"]
+#[ensures="match result { Shape::Rectangle { width, height } => width == w && height == h, _ => false }"]
+fn rectangle(w: i32, h: i32) -> Shape {
+    Shape::Rectangle { width: w, height: h }
+}
+
+#[ensures="match result { Shape::Segment(len) => len >= 0, Shape::Point => x == 0, _ => false }"]
+fn segment_or_point(x: i32) -> Shape {
+    if x == 0 {
+        Shape::Point
+    } else if x > 0 {
+        Shape::Segment(x)
+    } else {
+        Shape::Segment(-x)
+    }
+}
+
+fn main() {
+
+}
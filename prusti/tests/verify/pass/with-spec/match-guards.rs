@@ -0,0 +1,57 @@
+extern crate prusti_contracts;
+
+enum Shape {
+    Circle(u32),
+    Square(u32),
+    Triangle(u32),
+    Empty,
+}
+
+#[ensures="result >= 0"]
+fn sign(x: Option<i32>) -> i32 {
+    match x {
+        Some(v) if v > 0 => v,
+        Some(v) if v < 0 => -v,
+        _ => 0,
+    }
+}
+
+#[ensures="x > 100 ==> result == 2"]
+#[ensures="0 < x && x <= 100 ==> result == 1"]
+#[ensures="x == 0 ==> result == 0"]
+fn classify(x: u32) -> u32 {
+    match Some(x) {
+        Some(v) if v > 100 => 2,
+        Some(0) | None => 0,
+        Some(_) => 1,
+    }
+}
+
+#[ensures="result <= 1"]
+fn corners(shape: &Shape) -> u32 {
+    match shape {
+        Shape::Circle(_) | Shape::Empty => 0,
+        Shape::Square(s) | Shape::Triangle(s) if *s == 0 => 0,
+        Shape::Square(_) | Shape::Triangle(_) => 1,
+    }
+}
+
+#[requires="n >= 0"]
+fn count_down(n: i32) {
+    let mut i = n;
+    let mut cont = true;
+    #[invariant="i >= 0"]
+    while cont {
+        match i {
+            0 | 1 => cont = false,
+            x if x % 2 == 0 => i -= 2,
+            _ => i -= 1,
+        }
+    }
+}
+
+fn main() {
+    assert!(sign(Some(-3)) >= 0);
+    assert!(classify(0) == 0);
+    assert!(classify(101) == 2);
+}
@@ -0,0 +1,38 @@
+extern crate prusti_contracts;
+
+use std::mem;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[ensures="a.x == old(b.x) && a.y == old(b.y)"]
+#[ensures="b.x == old(a.x) && b.y == old(a.y)"]
+fn swap_points(a: &mut Point, b: &mut Point) {
+    mem::swap(a, b);
+}
+
+#[ensures="result.x == old(p.x) && result.y == old(p.y)"]
+#[ensures="p.x == 0 && p.y == 0"]
+fn take_point(p: &mut Point) -> Point {
+    mem::replace(p, Point { x: 0, y: 0 })
+}
+
+fn main() {
+    let mut a = 1;
+    let mut b = 2;
+    mem::swap(&mut a, &mut b);
+    assert!(a == 2 && b == 1);
+
+    let old = mem::replace(&mut a, 3);
+    assert!(old == 2 && a == 3);
+
+    let mut p = Point { x: 1, y: 2 };
+    let mut q = Point { x: 3, y: 4 };
+    swap_points(&mut p, &mut q);
+    assert!(p.x == 3 && p.y == 4 && q.x == 1 && q.y == 2);
+
+    let r = take_point(&mut p);
+    assert!(r.x == 3 && r.y == 4 && p.x == 0 && p.y == 0);
+}
@@ -0,0 +1,47 @@
+extern crate prusti_contracts;
+
+pub struct Counter {
+    value: u32,
+    limit: u32,
+}
+
+impl Counter {
+    #[model]
+    pub fn count(&self) -> u32 {
+        self.value
+    }
+
+    #[model]
+    pub fn capacity(&self) -> u32 {
+        self.limit
+    }
+
+    #[requires="limit > 0"]
+    #[ensures="result.count() == 0"]
+    #[ensures="result.capacity() == limit"]
+    pub fn new(limit: u32) -> Self {
+        Counter { value: 0, limit }
+    }
+
+    #[requires="self.count() < self.capacity()"]
+    #[ensures="self.count() == old(self.count()) + 1"]
+    #[ensures="self.capacity() == old(self.capacity())"]
+    pub fn increment(&mut self) {
+        self.value += 1;
+    }
+
+    // Private procedures can mention the representation
+    #[ensures="self.value == 0"]
+    fn reset(&mut self) {
+        self.value = 0;
+    }
+}
+
+fn client() {
+    let mut counter = Counter::new(2);
+    counter.increment();
+    counter.increment();
+    assert!(counter.count() == 2);
+}
+
+fn main() {}
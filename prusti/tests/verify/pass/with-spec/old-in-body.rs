@@ -0,0 +1,25 @@
+#[macro_use]
+extern crate prusti_contracts;
+
+use prusti_contracts::old;
+
+struct Counter {
+    value: u32,
+}
+
+#[requires="x < 1000"]
+fn increment_arg(mut x: u32) {
+    x += 1;
+    prusti_assert!(x == old(x) + 1);
+}
+
+#[requires="counter.value < 1000"]
+#[ensures="counter.value == old(counter.value) + 2"]
+fn increment_twice(counter: &mut Counter) {
+    counter.value += 1;
+    assert!(counter.value == old(counter.value) + 1);
+    counter.value += 1;
+    assert!(counter.value == old(counter.value) + 2);
+}
+
+fn main() {}
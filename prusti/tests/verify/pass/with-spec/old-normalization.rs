@@ -0,0 +1,24 @@
+extern crate prusti_contracts;
+
+struct Pair {
+    a: i32,
+    b: i32,
+}
+
+#[pure]
+fn sum(p: &Pair) -> i32 {
+    p.a + p.b
+}
+
+#[requires="p.a < 1000 && p.b < 1000"]
+#[ensures="p.a + p.b == old(p.a + p.b) + 2"]
+#[ensures="sum(p) == old(sum(p) + 1) + 1"]
+#[ensures="old(p.a) + old(p.b) + 1 == old(p.a + p.b + 1)"]
+#[ensures="old(if p.a > p.b { p.a } else { p.b }) + 1 == if p.a > p.b { p.a } else { p.b }"]
+#[ensures="forall i: i32 :: (0 <= i && i < old(p.a)) ==> i < p.a"]
+fn increment(p: &mut Pair) {
+    p.a += 1;
+    p.b += 1;
+}
+
+fn main() {}
@@ -0,0 +1,51 @@
+extern crate prusti_contracts;
+
+use std::ops::{Add, Index};
+
+struct Money {
+    cents: u32,
+}
+
+impl<'a> Add<&'a Money> for &'a Money {
+    type Output = u32;
+
+    #[pure]
+    #[requires="self.cents + other.cents <= 1000000"]
+    fn add(self, other: &'a Money) -> u32 {
+        self.cents + other.cents
+    }
+}
+
+struct Pair {
+    first: u32,
+    second: u32,
+}
+
+impl Index<usize> for Pair {
+    type Output = u32;
+
+    #[requires="index < 2"]
+    #[ensures="index == 0 ==> *result == self.first"]
+    #[ensures="index == 1 ==> *result == self.second"]
+    fn index(&self, index: usize) -> &u32 {
+        if index == 0 {
+            &self.first
+        } else {
+            &self.second
+        }
+    }
+}
+
+#[requires="a.cents <= 1000 && b.cents <= 1000"]
+#[ensures="a + b == a.cents + b.cents"]
+#[ensures="result == a + b"]
+fn total(a: &Money, b: &Money) -> u32 {
+    a + b
+}
+
+#[ensures="result == pair.first + pair.second"]
+fn sum(pair: &Pair) -> u32 {
+    pair[0] + pair[1]
+}
+
+fn main() {}
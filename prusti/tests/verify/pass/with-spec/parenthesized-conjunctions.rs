@@ -0,0 +1,21 @@
+extern crate prusti_contracts;
+
+#[requires="(x) < (y)"]
+#[ensures="(result) == (y)"]
+fn max(x: i32, y: i32) -> i32 {
+    if x < y { y } else { x }
+}
+
+#[requires="(0 <= x && x < 10) ==> (y == x)"]
+#[ensures="(0 <= x && x < 10) ==> (result == x + 1)"]
+fn succ(x: i32, y: i32) -> i32 {
+    if 0 <= x && x < 10 { y + 1 } else { 0 }
+}
+
+#[requires="(0 <= x && (x < 10))"]
+#[ensures="(result >= 1 && (result <= 10 && result == x + 1))"]
+fn inc(x: i32) -> i32 {
+    x + 1
+}
+
+fn main() {}
@@ -0,0 +1,49 @@
+extern crate prusti_contracts;
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+struct Point {
+    x: u32,
+    y: u32,
+}
+
+#[pure]
+fn sum(p: &Point) -> u32 {
+    p.x + p.y
+}
+
+#[pure]
+fn rc_sum(p: &Rc<Point>) -> u32 {
+    p.x + p.y
+}
+
+#[requires="rc_sum(p) == 3"]
+#[ensures="rc_sum(p) == 3"]
+fn read(p: Rc<Point>) -> Rc<Point> {
+    let q = p.clone();
+    assert!(q.x + q.y == 3);
+    assert!(sum(&*q) == 3);
+    q
+}
+
+fn test_rc() {
+    let p = Rc::new(Point { x: 1, y: 2 });
+    let q = p.clone();
+    assert!(p.x == 1);
+    assert!(q.y == 2);
+    let r = read(q);
+    assert!(rc_sum(&r) == 3);
+    assert!(p.x == 1);
+}
+
+fn test_arc() {
+    let a = Arc::new(Point { x: 3, y: 4 });
+    let b = a.clone();
+    let c = a.clone();
+    assert!(b.x == 3);
+    assert!(c.y == 4);
+    assert!(a.x + a.y == 7);
+}
+
+fn main() {}
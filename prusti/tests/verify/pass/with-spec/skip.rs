@@ -0,0 +1,28 @@
+extern crate prusti_contracts;
+
+/// The body of this procedure is not verified, but its contract is used by the callers
+#[prusti::skip]
+#[ensures="result == old(x) + 1"]
+fn increment(x: i32) -> i32 {
+    x
+}
+
+struct Counter {
+    value: i32,
+}
+
+impl Counter {
+    #[prusti::skip]
+    #[ensures="self.value == 0"]
+    fn reset(&mut self) {
+        assert!(false);
+    }
+}
+
+fn main() {
+    let x = increment(1);
+    assert!(x == 2);
+    let mut c = Counter { value: 5 };
+    c.reset();
+    assert!(c.value == 0);
+}
@@ -0,0 +1,51 @@
+extern crate prusti_contracts;
+
+#[derive(Clone, Copy, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Shape {
+    Dot(Point),
+    Segment(Point, Point),
+    Empty,
+}
+
+#[ensures="result == old(*p)"]
+fn copy_point(p: &Point) -> Point {
+    *p
+}
+
+#[ensures="*p == old(*p)"]
+fn keep_point(p: &mut Point) {
+    let x = p.x;
+    p.x = x;
+}
+
+#[ensures="*p != old(*p)"]
+#[ensures="p.y == old(p.y)"]
+fn move_right(p: &mut Point) {
+    p.x = p.x + 1;
+}
+
+#[requires="a == b"]
+#[ensures="a.x == b.x && a.y == b.y"]
+fn same_coordinates(a: Point, b: Point) {}
+
+#[ensures="result == old(s)"]
+fn identity(s: Shape) -> Shape {
+    s
+}
+
+#[ensures="result == old(*s)"]
+fn copy_shape(s: &Shape) -> Shape {
+    match s {
+        Shape::Dot(p) => Shape::Dot(*p),
+        Shape::Segment(a, b) => Shape::Segment(*a, *b),
+        Shape::Empty => Shape::Empty,
+    }
+}
+
+fn main() {}
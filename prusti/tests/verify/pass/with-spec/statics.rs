@@ -0,0 +1,48 @@
+extern crate prusti_contracts;
+
+static LIMIT: u32 = 100;
+
+static ENABLED: bool = true;
+
+struct Range {
+    low: i32,
+    high: i32,
+}
+
+static BOUNDS: Range = Range { low: -10, high: 10 };
+
+static PAIR: (u8, u8) = (3, 4);
+
+#[ensures="result <= LIMIT"]
+fn clamp(x: u32) -> u32 {
+    if x > LIMIT {
+        LIMIT
+    } else {
+        x
+    }
+}
+
+#[ensures="result == 100"]
+fn limit() -> u32 {
+    LIMIT
+}
+
+#[pure]
+#[ensures="result == (BOUNDS.low <= x && x <= BOUNDS.high)"]
+fn in_bounds(x: i32) -> bool {
+    BOUNDS.low <= x && x <= BOUNDS.high
+}
+
+#[requires="in_bounds(x)"]
+#[ensures="result <= 10"]
+fn use_bounds(x: i32) -> i32 {
+    assert!(BOUNDS.low == -10);
+    x
+}
+
+#[ensures="ENABLED ==> result == 7"]
+fn pair_sum() -> u8 {
+    PAIR.0 + PAIR.1
+}
+
+fn main() {}
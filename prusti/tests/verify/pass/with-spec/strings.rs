@@ -0,0 +1,46 @@
+extern crate prusti_contracts;
+
+#[requires="s.len() >= 2"]
+#[ensures="result.len() == 1"]
+fn first_byte(s: &str) -> &str {
+    &s[0..1]
+}
+
+#[ensures="result == (s.len() == 0)"]
+fn is_blank(s: &str) -> bool {
+    s.is_empty()
+}
+
+#[requires="s == \"yes\""]
+fn expect_yes(s: &str) {
+    assert!(s.len() == 3);
+}
+
+fn test_literals() {
+    let a = "hello";
+    let b = "hello";
+    assert!(a == b);
+    assert!(a.len() == 5);
+    assert!(!a.is_empty());
+    expect_yes("yes");
+}
+
+fn test_string() {
+    let mut s = String::new();
+    assert!(s.len() == 0);
+    s.push_str("ab");
+    s.push_str("cde");
+    assert!(s.len() == 5);
+    let t = String::from("abcde");
+    assert!(t.len() == 5);
+}
+
+fn test_substring() {
+    let s = "key=value";
+    let key = &s[0..3];
+    assert!(key.len() == 3);
+    let first = first_byte(key);
+    assert!(first.len() == 1);
+}
+
+fn main() {}
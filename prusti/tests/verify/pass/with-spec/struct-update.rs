@@ -0,0 +1,40 @@
+extern crate prusti_contracts;
+
+struct Point {
+    x: i32,
+    y: i32,
+    z: i32,
+}
+
+struct Labeled {
+    label: Box<u32>,
+    value: i32,
+}
+
+#[ensures="result.x == 1 && result.y == old(p.y) && result.z == old(p.z)"]
+fn with_x(p: Point) -> Point {
+    Point { x: 1, ..p }
+}
+
+#[ensures="result.x == old(p.x) && result.y == 2 && result.z == 3"]
+fn with_yz(p: &Point) -> Point {
+    Point { y: 2, z: 3, ..*p }
+}
+
+#[ensures="*result.label == old(*l.label) && result.value == v"]
+fn with_value(l: Labeled, v: i32) -> Labeled {
+    Labeled { value: v, ..l }
+}
+
+fn main() {
+    let p = Point { x: 5, y: 6, z: 7 };
+    let q = with_x(p);
+    assert!(q.x == 1 && q.y == 6 && q.z == 7);
+    let r = with_yz(&q);
+    assert!(r.x == 1 && r.y == 2 && r.z == 3);
+    let s = Point { z: 0, ..r };
+    assert!(s.x == 1 && s.y == 2 && s.z == 0);
+    let mut t = s;
+    t = Point { x: 10, ..t };
+    assert!(t.x == 10 && t.y == 2 && t.z == 0);
+}
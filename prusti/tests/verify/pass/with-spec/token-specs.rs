@@ -0,0 +1,50 @@
+extern crate prusti_contracts;
+
+struct Counter {
+    value: u32,
+}
+
+#[pure]
+fn is_small(x: u32) -> bool {
+    x < 100
+}
+
+#[requires(x < 100 && y < 100)]
+#[ensures(result == x + y)]
+fn add(x: u32, y: u32) -> u32 {
+    x + y
+}
+
+#[requires(implies(flag, is_small(x)))]
+#[ensures(implies!(flag, result < 200))]
+fn double_if(flag: bool, x: u32) -> u32 {
+    if flag {
+        x + x
+    } else {
+        0
+    }
+}
+
+#[requires(flag ==> x < 100)]
+#[ensures(!flag ==> result == 0)]
+fn double_if_small(flag: bool, x: u32) -> u32 {
+    if flag {
+        x + x
+    } else {
+        0
+    }
+}
+
+#[requires(counter.value < 1000)]
+#[ensures(counter.value == old(counter.value) + 1)]
+fn increment(counter: &mut Counter) {
+    counter.value += 1;
+}
+
+#[ensures(forall(|i: u32| i < 100 ==> is_small(i)))]
+fn small_numbers() {}
+
+#[ensures(forall!(|i: u32, j: u32| i < 10 && j < 10 ==> i + j < 20))]
+fn small_sums() {}
+
+fn main() {}
@@ -0,0 +1,25 @@
+extern crate prusti_contracts;
+
+trait Percentage {
+    #[ensures="result <= 100"]
+    fn get(&self) -> u8;
+
+    #[requires="arg <= 100"]
+    fn set(&mut self, arg: u8);
+}
+
+fn test_get(t: &Percentage) {
+    let p = t.get();
+    assert!(p <= 100);
+}
+
+fn test_set(t: &mut Percentage) {
+    t.set(100);
+}
+
+fn test_impl_trait_arg(t: &impl Percentage) {
+    let p = t.get();
+    assert!(p <= 100);
+}
+
+fn main() {}
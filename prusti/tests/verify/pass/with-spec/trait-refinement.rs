@@ -0,0 +1,45 @@
+extern crate prusti_contracts;
+
+trait Percentage {
+    #[requires="value <= 100"]
+    #[ensures="result <= 100"]
+    fn clamp(&self, value: u32) -> u32;
+}
+
+struct Saturating;
+
+impl Percentage for Saturating {
+    #[refine_requires="value <= 1000"]
+    #[refine_ensures="result <= 100"]
+    #[refine_ensures="value <= 100 ==> result == value"]
+    fn clamp(&self, value: u32) -> u32 {
+        if value > 100 {
+            100
+        } else {
+            value
+        }
+    }
+}
+
+struct Identity;
+
+impl Percentage for Identity {
+    #[refine_ensures="result == value"]
+    fn clamp(&self, value: u32) -> u32 {
+        value
+    }
+}
+
+fn test_saturating(s: &Saturating) {
+    let x = s.clamp(500);
+    let y = s.clamp(42);
+    assert!(x <= 100);
+    assert!(y == 42);
+}
+
+fn test_identity(i: &Identity) {
+    let x = i.clamp(7);
+    assert!(x == 7);
+}
+
+fn main() {}
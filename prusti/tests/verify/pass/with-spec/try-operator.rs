@@ -0,0 +1,49 @@
+extern crate prusti_contracts;
+
+struct ParseError {
+    position: u32,
+}
+
+struct InputError {
+    code: u32,
+}
+
+impl From<ParseError> for InputError {
+    #[ensures="result.code == error.position"]
+    fn from(error: ParseError) -> Self {
+        InputError { code: error.position }
+    }
+}
+
+fn parse_digit(x: u32) -> Result<u32, ParseError> {
+    if x < 10 {
+        Ok(x)
+    } else {
+        Err(ParseError { position: x })
+    }
+}
+
+fn parse_twice(x: u32) -> Result<u32, ParseError> {
+    let first = parse_digit(x)?;
+    let second = parse_digit(first)?;
+    assert!(second < 10);
+    Ok(second)
+}
+
+fn read_input(x: u32) -> Result<u32, InputError> {
+    let digit = parse_digit(x)?;
+    assert!(digit < 10);
+    Ok(digit)
+}
+
+fn propagate(result: Result<u32, ParseError>) -> Result<u32, InputError> {
+    let value = result?;
+    Ok(value)
+}
+
+fn test_conversion() {
+    let error = InputError::from(ParseError { position: 3 });
+    assert!(error.code == 3);
+}
+
+fn main() {}
@@ -0,0 +1,45 @@
+extern crate prusti_contracts;
+
+struct Wrapper(i32);
+
+struct Pair(i32, i32);
+
+enum Shape {
+    Circle(u32),
+    Square(u32, u32),
+}
+
+#[ensures="result.0 == x"]
+fn wrap(x: i32) -> Wrapper {
+    Wrapper(x)
+}
+
+#[ensures="result.0 == old(p.1) && result.1 == old(p.0)"]
+fn swap(p: Pair) -> Pair {
+    Pair(p.1, p.0)
+}
+
+#[requires="w.0 < 1000"]
+#[ensures="result.0 == old(w.0) + 1"]
+fn increment(w: Wrapper) -> Wrapper {
+    let Wrapper(value) = w;
+    Wrapper(value + 1)
+}
+
+fn area(shape: Shape) -> u32 {
+    match shape {
+        Shape::Circle(_) => 0,
+        Shape::Square(w, h) => if w < 100 && h < 100 { w * h } else { 0 },
+    }
+}
+
+fn main() {
+    let w = wrap(5);
+    assert!(w.0 == 5);
+    let v = increment(w);
+    assert!(v.0 == 6);
+    let p = swap(Pair(3, 4));
+    assert!(p.0 == 4 && p.1 == 3);
+    let _ = area(Shape::Circle(3));
+    let _ = area(Shape::Square(2, 3));
+}
@@ -0,0 +1,41 @@
+extern crate prusti_contracts;
+
+#[requires="a <= 1000 && b <= 1000"]
+#[ensures="result.0 + result.1 == old(a + b)"]
+fn split(a: u32, b: u32) -> (u32, u32) {
+    (b, a)
+}
+
+#[ensures="result == (b, a)"]
+fn swap(a: u32, b: u32) -> (u32, u32) {
+    (b, a)
+}
+
+#[ensures="result.0 == old(x.1) && result.1 == old(x.0)"]
+fn swap_pair(x: (u32, u32)) -> (u32, u32) {
+    (x.1, x.0)
+}
+
+#[ensures="result == old(x.0.1)"]
+fn nested_projection(x: ((u32, u32), u32)) -> u32 {
+    (x.0).1
+}
+
+#[ensures="result.0.0 == old(x.1)"]
+#[ensures="result.0.1 == old(x.0.1)"]
+#[ensures="result.1 == old(x.0.0)"]
+fn rotate(x: ((u32, u32), u32)) -> ((u32, u32), u32) {
+    ((x.1, (x.0).1), (x.0).0)
+}
+
+fn client() {
+    let (x, y) = swap(1, 2);
+    assert!(x == 2 && y == 1);
+    let pair = swap_pair((x, y));
+    assert!(pair.0 == 1 && pair.1 == 2);
+    let rotated = rotate(((1, 2), 3));
+    assert!((rotated.0).0 == 3 && (rotated.0).1 == 2 && rotated.1 == 1);
+    assert!(nested_projection(rotated) == 2);
+}
+
+fn main() {}
@@ -14,6 +14,9 @@ extern crate error_chain;
 extern crate jni;
 #[macro_use]
 extern crate log;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
 extern crate uuid;
 extern crate viper_sys;
 
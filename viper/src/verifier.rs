@@ -178,6 +178,14 @@ impl<'a> Verifier<'a, state::Started> {
                     .is_instance_of(viper_error, "viper/silver/verifier/VerificationError");
 
                 if !is_verification_error {
+                    let is_timeout_occurred = self
+                        .jni
+                        .is_instance_of(viper_error, "viper/silver/verifier/TimeoutOccurred");
+
+                    if is_timeout_occurred {
+                        return VerificationResult::Timeout();
+                    }
+
                     let is_aborted_exceptionally = self
                         .jni
                         .is_instance_of(viper_error, "viper/silver/verifier/AbortedExceptionally");